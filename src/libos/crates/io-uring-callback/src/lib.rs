@@ -110,7 +110,7 @@ extern crate sgx_libc as libc;
 #[cfg(feature = "sgx")]
 extern crate sgx_tstd as std;
 
-use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicU64, AtomicUsize};
 use std::sync::Arc;
 use std::{collections::HashMap, io};
 cfg_if::cfg_if! {
@@ -149,6 +149,17 @@ pub struct IoUring {
     token_table: Mutex<Slab<Arc<IoToken>>>,
     sq_lock: Mutex<()>, // For submission queue synchronization
     fd_map: RwLock<HashMap<usize, AtomicUsize>>, // (key: fd, value: op num)
+    nr_submitted: AtomicU64,
+    nr_completed: AtomicU64,
+}
+
+/// The statistics of an io_uring instance.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IoUringStats {
+    /// The number of submitted I/O requests, including cancel requests.
+    pub nr_submitted: u64,
+    /// The number of completed I/O requests, excluding cancel requests.
+    pub nr_completed: u64,
 }
 
 impl Drop for IoUring {
@@ -180,6 +191,8 @@ impl IoUring {
             token_table,
             sq_lock,
             fd_map,
+            nr_submitted: AtomicU64::new(0),
+            nr_completed: AtomicU64::new(0),
         }
     }
 
@@ -285,6 +298,54 @@ impl IoUring {
         self.push_entry(entry, callback)
     }
 
+    /// Push a read request, whose buffer is a registered one, into the submission queue
+    /// of the io_uring.
+    ///
+    /// The buffer must be within the registered buffer of the index `buf_index`.
+    ///
+    /// # Safety
+    ///
+    /// See the safety section of the `IoUring`.
+    pub unsafe fn read_fixed(
+        &self,
+        fd: Fd,
+        buf: *mut u8,
+        len: u32,
+        offset: libc::off_t,
+        buf_index: u16,
+        callback: impl FnOnce(i32) + Send + 'static,
+    ) -> IoHandle {
+        let entry = opcode::ReadFixed::new(fd, buf, len, buf_index)
+            .offset(offset)
+            .build();
+        self.op_fetch_add(fd.0 as usize, 1);
+        self.push_entry(entry, callback)
+    }
+
+    /// Push a write request, whose buffer is a registered one, into the submission queue
+    /// of the io_uring.
+    ///
+    /// The buffer must be within the registered buffer of the index `buf_index`.
+    ///
+    /// # Safety
+    ///
+    /// See the safety section of the `IoUring`.
+    pub unsafe fn write_fixed(
+        &self,
+        fd: Fd,
+        buf: *const u8,
+        len: u32,
+        offset: libc::off_t,
+        buf_index: u16,
+        callback: impl FnOnce(i32) + Send + 'static,
+    ) -> IoHandle {
+        let entry = opcode::WriteFixed::new(fd, buf, len, buf_index)
+            .offset(offset)
+            .build();
+        self.op_fetch_add(fd.0 as usize, 1);
+        self.push_entry(entry, callback)
+    }
+
     /// Push a readv request into the submission queue of the io_uring.
     ///
     /// # Safety
@@ -451,6 +512,8 @@ impl IoUring {
             }
 
             if nr_complete >= min_complete {
+                self.nr_completed
+                    .fetch_add(nr_complete as u64, Ordering::Relaxed);
                 return nr_complete;
             }
 
@@ -479,6 +542,7 @@ impl IoUring {
             }
         }
         drop(sq_guard);
+        self.nr_submitted.fetch_add(1, Ordering::Relaxed);
 
         // Make sure Linux is aware of the new submission
         if let Err(e) = self.ring.submit() {
@@ -548,6 +612,33 @@ impl IoUring {
         fd_map.len()
     }
 
+    /// Get the statistics of the io_uring instance.
+    pub fn stats(&self) -> IoUringStats {
+        IoUringStats {
+            nr_submitted: self.nr_submitted.load(Ordering::Relaxed),
+            nr_completed: self.nr_completed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Register an eventfd, which is signaled whenever new completions are
+    /// posted to the completion queue.
+    ///
+    /// This allows a polling thread to sleep on the eventfd, instead of
+    /// busy polling, when the io_uring is idle.
+    pub fn register_eventfd(&self, fd: RawFd) -> io::Result<()> {
+        self.ring.submitter().register_eventfd(fd)
+    }
+
+    /// Register buffers, which can then be used by `read_fixed` and `write_fixed`
+    /// with their indexes in `bufs`.
+    ///
+    /// # Safety
+    ///
+    /// The buffers must be valid as long as the io_uring instance is alive.
+    pub unsafe fn register_buffers(&self, bufs: &[libc::iovec]) -> io::Result<()> {
+        self.ring.submitter().register_buffers(bufs)
+    }
+
     /// Cancel an ongoing I/O request.
     ///
     /// # safety
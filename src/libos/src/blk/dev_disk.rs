@@ -6,15 +6,21 @@ use crate::util::sgx::get_autokey;
 
 use core::any::Any;
 use rcore_fs::vfs::{self, FileType, INode, Metadata, Timespec};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::untrusted::path::PathEx;
 use sworndisk_v2::{AeadKey, SwornDisk, BLOCK_SIZE};
 
 lazy_static! {
-    pub static ref SWORNDISK: RwLock<Option<Arc<SwornDisk<RawDisk>>>> = { RwLock::new(None) };
-    pub static ref SWORNDISK_METADATA: RwLock<SwornDiskMeta> =
-        { RwLock::new(SwornDiskMeta::default()) };
+    /// All opened SwornDisks, indexed by the disk name
+    pub static ref SWORNDISKS: RwLock<HashMap<String, Arc<SwornDisk<RawDisk>>>> =
+        { RwLock::new(HashMap::new()) };
+    /// Metadata of all set-up SwornDisks, indexed by the disk name
+    pub static ref SWORNDISK_METADATAS: RwLock<HashMap<String, SwornDiskMeta>> =
+        { RwLock::new(HashMap::new()) };
 }
 
+/// The name of the default SwornDisk
 pub const DEV_SWORNDISK: &str = "sworndisk";
 
 /// Block device wrapper.
@@ -24,37 +30,44 @@ pub struct DevDisk {
 
 impl DevDisk {
     pub fn open_or_create(name: &str) -> Result<Self> {
-        let disk: Arc<dyn BlockDevice> = match name {
-            // Currently only support SwornDisk
-            DEV_SWORNDISK => {
-                let mut sworndisk_opt = SWORNDISK.write().unwrap();
-                if let Some(sworndisk) = sworndisk_opt.as_ref() {
-                    sworndisk.clone()
-                } else {
-                    let metadata = SWORNDISK_METADATA.read().unwrap();
-                    if !metadata.is_setup {
-                        return_errno!(EINVAL, "SwornDisk not set up");
-                    }
-                    let total_blocks = metadata.size / BLOCK_SIZE;
-                    let image_path = {
-                        let mut path = metadata.image_dir.clone();
-                        path.push("sworndisk.image");
-                        path
-                    };
-                    let raw_disk =
-                        RawDisk::open_or_create(total_blocks, image_path.to_str().unwrap())?;
-                    let root_key = metadata.root_key;
-
-                    let sworndisk = Arc::new(
-                        SwornDisk::open(raw_disk.clone(), root_key, None).unwrap_or_else(|_e| {
-                            SwornDisk::create(raw_disk, root_key, None).unwrap()
-                        }),
-                    );
-                    sworndisk_opt.insert(sworndisk.clone());
-                    sworndisk
-                }
-            }
-            _ => return_errno!(EINVAL, "Unrecognized block device"),
+        // Currently only support SwornDisk
+        let mut sworndisks = SWORNDISKS.write().unwrap();
+        let disk: Arc<dyn BlockDevice> = if let Some(sworndisk) = sworndisks.get(name) {
+            sworndisk.clone()
+        } else {
+            let metadatas = SWORNDISK_METADATAS.read().unwrap();
+            let metadata = metadatas
+                .get(name)
+                .ok_or_else(|| errno!(EINVAL, "SwornDisk not set up"))?;
+            let total_blocks = metadata.size / BLOCK_SIZE;
+            let image_path = {
+                let mut path = metadata.image_dir.clone();
+                path.push(name.to_owned() + ".image");
+                path
+            };
+            // Only create a new disk if the image does not exist, otherwise a
+            // corrupted or tampered disk would be silently replaced
+            let image_exists = image_path.exists();
+            let image_path = image_path
+                .to_str()
+                .ok_or_else(|| errno!(EINVAL, "invalid path of disk image"))?;
+            let raw_disk = RawDisk::open_or_create(total_blocks, image_path)?;
+            let root_key = AeadKey::from(metadata.root_key);
+
+            let sworndisk = if image_exists {
+                SwornDisk::open(raw_disk, root_key, None).map_err(|e| {
+                    error!("failed to open SwornDisk {}: {:?}", name, e);
+                    errno!(EIO, "failed to open SwornDisk")
+                })?
+            } else {
+                SwornDisk::create(raw_disk, root_key, None).map_err(|e| {
+                    error!("failed to create SwornDisk {}: {:?}", name, e);
+                    errno!(EIO, "failed to create SwornDisk")
+                })?
+            };
+            let sworndisk = Arc::new(sworndisk);
+            sworndisks.insert(name.to_owned(), sworndisk.clone());
+            sworndisk
         };
         Ok(Self { disk })
     }
@@ -133,50 +146,90 @@ fn rw_args_block_aligned(offset: usize, buf_len: usize) -> bool {
 #[derive(Debug)]
 pub struct SwornDiskMeta {
     size: usize,
-    root_key: AeadKey,
+    root_key: sgx_key_128bit_t,
     image_dir: PathBuf,
-    is_setup: bool,
-}
-
-impl Default for SwornDiskMeta {
-    fn default() -> Self {
-        Self {
-            size: 0,
-            root_key: AeadKey::default(),
-            image_dir: PathBuf::from("run"),
-            is_setup: false,
-        }
-    }
 }
 
 impl SwornDiskMeta {
+    /// Set up the metadata of the SwornDisk named `name`, whose image is
+    /// `<source_path>/<name>.image` on host (`run/<name>.image` by default).
     pub fn setup(
+        name: &str,
         disk_size: u64,
         user_key: &Option<sgx_key_128bit_t>,
         source_path: Option<&PathBuf>,
     ) -> Result<()> {
-        let mut metadata = SWORNDISK_METADATA.write().unwrap();
-        if metadata.is_setup {
+        let mut metadatas = SWORNDISK_METADATAS.write().unwrap();
+        if metadatas.contains_key(name) {
             return_errno!(EEXIST, "SwornDisk already set up");
         };
         if disk_size < (5 * GB) as _ {
             return_errno!(EINVAL, "Disk size too small for SwornDisk");
         };
-        metadata.size = disk_size as _;
-        if let Some(source_path) = source_path {
-            metadata.image_dir = source_path.clone();
-        }
-        let root_key = if let Some(user_key) = user_key {
-            *user_key
-        } else {
-            get_autokey(&metadata.image_dir)?
+        let image_dir = Self::image_dir(source_path);
+        let root_key = Self::root_key(user_key, &image_dir)?;
+        let metadata = Self {
+            size: disk_size as _,
+            root_key,
+            image_dir,
         };
-        metadata.root_key = AeadKey::from(root_key);
-        metadata.is_setup = true;
+        metadatas.insert(name.to_owned(), metadata);
         Ok(())
     }
 
-    pub fn is_setup() -> bool {
-        SWORNDISK_METADATA.read().unwrap().is_setup
+    /// Check that the options to set up the SwornDisk named `name` again, e.g.,
+    /// when it is mounted again after umounted, agree with its metadata.
+    pub fn check(
+        name: &str,
+        disk_size: Option<u64>,
+        user_key: &Option<sgx_key_128bit_t>,
+        source_path: Option<&PathBuf>,
+    ) -> Result<()> {
+        let metadatas = SWORNDISK_METADATAS.read().unwrap();
+        let metadata = metadatas
+            .get(name)
+            .ok_or_else(|| errno!(ENOENT, "SwornDisk not set up"))?;
+        if let Some(disk_size) = disk_size {
+            if disk_size as usize != metadata.size {
+                return_errno!(EINVAL, "the disk size does not match the SwornDisk");
+            }
+        }
+        let image_dir = Self::image_dir(source_path);
+        if image_dir != metadata.image_dir {
+            return_errno!(EINVAL, "the source does not match the SwornDisk");
+        }
+        if Self::root_key(user_key, &image_dir)? != metadata.root_key {
+            return_errno!(EINVAL, "the key does not match the SwornDisk");
+        }
+        Ok(())
+    }
+
+    fn image_dir(source_path: Option<&PathBuf>) -> PathBuf {
+        source_path.cloned().unwrap_or_else(|| PathBuf::from("run"))
+    }
+
+    fn root_key(
+        user_key: &Option<sgx_key_128bit_t>,
+        image_dir: &PathBuf,
+    ) -> Result<sgx_key_128bit_t> {
+        if let Some(user_key) = user_key {
+            Ok(*user_key)
+        } else {
+            get_autokey(image_dir)
+        }
+    }
+
+    pub fn is_setup(name: &str) -> bool {
+        SWORNDISK_METADATAS.read().unwrap().contains_key(name)
+    }
+
+    /// The names of all set-up SwornDisks
+    pub fn names() -> Vec<String> {
+        SWORNDISK_METADATAS
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
     }
 }
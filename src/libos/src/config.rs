@@ -193,6 +193,8 @@ pub struct ConfigMountOptions {
     pub cache_size: Option<u64>,
     pub disk_size: Option<u64>,
     pub index: u32,
+    pub protected: bool,
+    pub disk: Option<String>,
 }
 
 impl Config {
@@ -378,11 +380,13 @@ impl ConfigMountOptions {
             cache_size,
             disk_size,
             index: input.index,
+            protected: input.protected,
+            disk: input.disk.clone(),
         })
     }
 }
 
-fn parse_memory_size(mem_str: &str) -> Result<usize> {
+pub fn parse_memory_size(mem_str: &str) -> Result<usize> {
     const UNIT2FACTOR: [(&str, usize); 5] = [
         ("KB", 1024),
         ("MB", 1024 * 1024),
@@ -530,6 +534,10 @@ struct InputConfigMountOptions {
     pub disk_size: Option<String>,
     #[serde(default)]
     pub index: u32,
+    #[serde(default)]
+    pub protected: bool,
+    #[serde(default)]
+    pub disk: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -103,7 +103,7 @@ pub extern "C" fn occlum_ecall_init(
         vm::init_user_space();
 
         if ENABLE_URING.load(Ordering::Relaxed) {
-            crate::io_uring::MULTITON.poll_completions();
+            crate::io_uring::MULTITON.init();
         }
 
         // Register exception handlers (support cpuid & rdtsc for now)
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use crate::prelude::*;
+use crate::process::account_sleep;
 use crate::time::{timespec_t, TIMERSLACK};
 
 pub struct HostEventFd {
@@ -26,7 +27,7 @@ impl HostEventFd {
             &mut val as *mut _ as *mut c_void,
             std::mem::size_of::<u64>(),
         )) as usize;
-        debug_assert!(ret != std::mem::size_of::<u64>());
+        debug_assert!(ret == std::mem::size_of::<u64>());
         Ok(val)
     }
 
@@ -98,7 +99,8 @@ impl Drop for HostEventFd {
 fn ocall_eventfd_poll(host_fd: FileDesc, timeout: *mut timespec_t) -> Result<()> {
     try_libc!({
         let mut ret = 0;
-        let status = unsafe { occlum_ocall_eventfd_poll(&mut ret, host_fd, timeout) };
+        let status =
+            account_sleep(|| unsafe { occlum_ocall_eventfd_poll(&mut ret, host_fd, timeout) });
         assert!(status == sgx_status_t::SGX_SUCCESS);
         ret
     });
@@ -15,6 +15,7 @@ struct Subscriber<E: Event, F: EventFilter<E>> {
     observer: Weak<dyn Observer<E>>,
     filter: Option<F>,
     metadata: Option<Weak<dyn Any + Send + Sync>>,
+    exclusive: bool,
 }
 
 impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
@@ -30,12 +31,38 @@ impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
         observer: Weak<dyn Observer<E>>,
         filter: Option<F>,
         metadata: Option<Weak<dyn Any + Send + Sync>>,
+    ) {
+        self.do_register(observer, filter, metadata, false);
+    }
+
+    /// Register an observer as an exclusive one.
+    ///
+    /// An event is delivered to the exclusive observers one by one in their
+    /// registration order, until one of them has woken up some waiting threads.
+    /// This avoids the thundering herd problem when many observers (e.g., the
+    /// epoll files of worker threads) are interested in the same events.
+    pub fn register_exclusive(
+        &self,
+        observer: Weak<dyn Observer<E>>,
+        filter: Option<F>,
+        metadata: Option<Weak<dyn Any + Send + Sync>>,
+    ) {
+        self.do_register(observer, filter, metadata, true);
+    }
+
+    fn do_register(
+        &self,
+        observer: Weak<dyn Observer<E>>,
+        filter: Option<F>,
+        metadata: Option<Weak<dyn Any + Send + Sync>>,
+        exclusive: bool,
     ) {
         let mut subscribers = self.subscribers.lock();
         subscribers.push_back(Subscriber {
             observer,
             filter,
             metadata,
+            exclusive,
         });
     }
 
@@ -46,9 +73,16 @@ impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
     }
 
     /// Broadcast an event to all registered observers.
+    ///
+    /// Among the exclusive observers, only the ones up to the first that has
+    /// woken up some waiting threads get the event.
     pub fn broadcast(&self, event: &E) {
         let subscribers = self.subscribers.lock();
+        let mut has_woken_exclusive = false;
         for subscriber in subscribers.iter() {
+            if subscriber.exclusive && has_woken_exclusive {
+                continue;
+            }
             if let Some(filter) = subscriber.filter.as_ref() {
                 if !filter.filter(event) {
                     continue;
@@ -60,7 +94,11 @@ impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
                 Some(observer) => observer,
             };
 
-            observer.on_event(event, &subscriber.metadata);
+            if subscriber.exclusive {
+                has_woken_exclusive = observer.on_exclusive_event(event, &subscriber.metadata);
+            } else {
+                observer.on_event(event, &subscriber.metadata);
+            }
         }
     }
 }
@@ -17,4 +17,20 @@ pub trait Observer<E: Event>: Send + Sync {
     /// the odds of deadlocks, the `on_event` method should be written short
     /// and sweet.
     fn on_event(&self, event: &E, metadata: &Option<Weak<dyn Any + Send + Sync>>) -> ();
+
+    /// The callback that will be executed when some interesting events are
+    /// delivered to this observer, which is registered as an exclusive one.
+    ///
+    /// The return value tells whether the observer has woken up any waiting
+    /// threads. If so, the notifier will not deliver the events to the
+    /// remaining exclusive observers. By default, an observer always claims
+    /// the events.
+    fn on_exclusive_event(
+        &self,
+        event: &E,
+        metadata: &Option<Weak<dyn Any + Send + Sync>>,
+    ) -> bool {
+        self.on_event(event, metadata);
+        true
+    }
 }
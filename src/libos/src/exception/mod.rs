@@ -75,6 +75,7 @@ fn try_handle_kernel_exception(info: &sgx_exception_info_t) -> i32 {
             let kernel_triggers = true;
             enclave_page_fault_handler(info.cpu_context.rip as usize, info.exinfo, kernel_triggers)
                 .expect("handle PF failure");
+            current!().usage().page_fault();
             SGX_MM_EXCEPTION_CONTINUE_EXECUTION
         }
     } else {
@@ -146,6 +147,7 @@ pub fn do_handle_exception(
         info!("Userspace #PF caught, try handle");
         if enclave_page_fault_handler(info.cpu_context.rip as usize, info.exinfo, false).is_ok() {
             info!("#PF handling is done successfully");
+            current!().usage().page_fault();
             return Ok(0);
         }
 
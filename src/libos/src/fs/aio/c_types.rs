@@ -0,0 +1,50 @@
+use crate::prelude::*;
+
+pub const IOCB_CMD_PREAD: u16 = 0;
+pub const IOCB_CMD_PWRITE: u16 = 1;
+pub const IOCB_CMD_FSYNC: u16 = 2;
+pub const IOCB_CMD_FDSYNC: u16 = 3;
+pub const IOCB_CMD_POLL: u16 = 5;
+pub const IOCB_CMD_PREADV: u16 = 7;
+pub const IOCB_CMD_PWRITEV: u16 = 8;
+
+bitflags! {
+    pub struct IocbFlags: u32 {
+        /// Signal the eventfd in `aio_resfd` on completion
+        const IOCB_FLAG_RESFD   = 1 << 0;
+        /// Use the I/O priority in `aio_reqprio`
+        const IOCB_FLAG_IOPRIO  = 1 << 1;
+    }
+}
+
+/// The control block of an AIO request.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct iocb {
+    pub aio_data: u64,
+    pub aio_key: u32,
+    pub aio_rw_flags: i32,
+    pub aio_lio_opcode: u16,
+    pub aio_reqprio: i16,
+    pub aio_fildes: u32,
+    pub aio_buf: u64,
+    pub aio_nbytes: u64,
+    pub aio_offset: i64,
+    pub aio_reserved2: u64,
+    pub aio_flags: u32,
+    pub aio_resfd: u32,
+}
+
+/// The completion event of an AIO request.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_event {
+    /// The `aio_data` of the request
+    pub data: u64,
+    /// The user address of the control block of the request
+    pub obj: u64,
+    pub res: i64,
+    pub res2: i64,
+}
@@ -0,0 +1,338 @@
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+use std::time::Duration;
+
+use super::c_types::io_event;
+use crate::events::{Observer, Pollee, Poller};
+use crate::fs::IoEvents;
+use crate::net::HOST_POLLER;
+use crate::prelude::*;
+use crate::util::mem_util::from_user;
+
+/// An AIO context, which collects the completion events of the requests
+/// submitted to it.
+pub struct AioContext {
+    id: u64,
+    max_events: usize,
+    inner: Mutex<Inner>,
+    // IN is set when there are completion events
+    pollee: Pollee,
+    weak_self: Weak<Self>,
+}
+
+struct Inner {
+    completed: VecDeque<(io_event, Option<ReadBack>)>,
+    // The number of the requests that are either pending or completed but not
+    // yet reaped by `get_events`
+    nr_inflight: usize,
+    pending_polls: Vec<Arc<PollRequest>>,
+    is_destroyed: bool,
+}
+
+impl AioContext {
+    pub fn new(id: u64, max_events: usize) -> Arc<Self> {
+        let inner = Mutex::new(Inner {
+            completed: VecDeque::new(),
+            nr_inflight: 0,
+            pending_polls: Vec::new(),
+            is_destroyed: false,
+        });
+        Arc::new_cyclic(|weak_self| Self {
+            id,
+            max_events,
+            inner,
+            pollee: Pollee::new(IoEvents::empty()),
+            weak_self: weak_self.clone(),
+        })
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Reserve a slot for a new request.
+    ///
+    /// The number of the inflight requests cannot exceed the capacity of the
+    /// context, so that their completion events are never dropped.
+    pub fn reserve(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.is_destroyed {
+            return_errno!(EINVAL, "the AIO context is destroyed");
+        }
+        if inner.nr_inflight >= self.max_events {
+            return_errno!(EAGAIN, "too many inflight requests");
+        }
+        inner.nr_inflight += 1;
+        Ok(())
+    }
+
+    /// Give back the slot of a request that fails to be submitted.
+    pub fn unreserve(&self) {
+        let mut inner = self.inner.lock();
+        debug_assert!(inner.nr_inflight > 0);
+        inner.nr_inflight -= 1;
+    }
+
+    /// Complete a request with the event, and signal the eventfd if any.
+    pub fn complete(&self, event: io_event, resfd: Option<&FileRef>) {
+        self.complete_with(event, None, resfd);
+    }
+
+    /// Complete a request with the event and the data read by it, which is
+    /// copied to the user buffers when the event is reaped.
+    pub fn complete_with(
+        &self,
+        event: io_event,
+        read_back: Option<ReadBack>,
+        resfd: Option<&FileRef>,
+    ) {
+        {
+            let mut inner = self.inner.lock();
+            if inner.is_destroyed {
+                return;
+            }
+            inner.completed.push_back((event, read_back));
+        }
+        self.pollee.add_events(IoEvents::IN);
+
+        if let Some(resfd) = resfd {
+            if let Err(e) = resfd.write(&1_u64.to_ne_bytes()) {
+                warn!("failed to signal the eventfd of AIO: {:?}", e);
+            }
+        }
+    }
+
+    /// Submit a poll request, which completes once any of the events in the
+    /// mask happens on the file.
+    pub fn submit_poll(
+        &self,
+        file: FileRef,
+        mask: IoEvents,
+        event: io_event,
+        resfd: Option<FileRef>,
+    ) -> Result<()> {
+        let notifier = file
+            .notifier()
+            .ok_or_else(|| errno!(EINVAL, "the file cannot be polled asynchronously"))?;
+
+        let req = Arc::new(PollRequest {
+            ctx: self.weak_self.clone(),
+            file: file.clone(),
+            mask: mask | IoEvents::ALWAYS_POLL,
+            event,
+            resfd,
+            is_done: AtomicBool::new(false),
+        });
+        let weak_observer = Arc::downgrade(&req) as Weak<dyn Observer<_>>;
+        notifier.register(weak_observer, Some(req.mask), None);
+        // The events of the host files are only updated when the host is polled
+        if file.host_fd().is_some() {
+            if let Err(e) = HOST_POLLER.watch(&file, req.mask) {
+                let weak_observer = Arc::downgrade(&req) as Weak<dyn Observer<_>>;
+                notifier.unregister(&weak_observer);
+                return Err(e);
+            }
+        }
+        self.inner.lock().pending_polls.push(req.clone());
+
+        // The file may be ready before the registration
+        req.try_complete(file.poll_new());
+        Ok(())
+    }
+
+    /// Cancel the pending poll request of the control block.
+    pub fn cancel(&self, obj: u64) -> Result<()> {
+        let req = {
+            let inner = self.inner.lock();
+            inner
+                .pending_polls
+                .iter()
+                .find(|req| req.event.obj == obj)
+                .cloned()
+                .ok_or_else(|| errno!(EINVAL, "the request is not found or cannot be cancelled"))?
+        };
+        // Like Linux, the cancelled request is completed with no event
+        if !req.cancel() {
+            return_errno!(EINVAL, "the request has been completed");
+        }
+        self.reap_polls();
+        Ok(())
+    }
+
+    /// Get at least `min_nr` and at most `events.len()` completion events,
+    /// returning the number of the events got.
+    ///
+    /// Fewer events are returned if timeout or the context is destroyed.
+    pub fn get_events(
+        &self,
+        min_nr: usize,
+        events: &mut [io_event],
+        mut timeout: Option<&mut Duration>,
+    ) -> Result<usize> {
+        let min_nr = min_nr.min(events.len());
+        let mut nr_got = 0;
+
+        let mask = IoEvents::IN;
+        let poller = Poller::new();
+        self.pollee.connect_poller(mask, &poller);
+        loop {
+            self.reap_polls();
+            {
+                let mut inner = self.inner.lock();
+                while nr_got < events.len() {
+                    match inner.completed.pop_front() {
+                        Some((mut event, read_back)) => {
+                            if let Some(read_back) = read_back {
+                                if let Err(e) = read_back.copy_to_user() {
+                                    event.res = -(e.errno() as i64);
+                                }
+                            }
+                            events[nr_got] = event;
+                            nr_got += 1;
+                            inner.nr_inflight -= 1;
+                        }
+                        None => break,
+                    }
+                }
+                if inner.completed.is_empty() {
+                    self.pollee.del_events(IoEvents::IN);
+                }
+                if nr_got >= min_nr || inner.is_destroyed {
+                    return Ok(nr_got);
+                }
+            }
+
+            if let Err(e) = poller.wait_timeout(timeout.as_deref_mut()) {
+                if e.errno() == ETIMEDOUT || nr_got > 0 {
+                    return Ok(nr_got);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    /// Destroy the context. The pending requests are cancelled and the
+    /// threads waiting for completion events are woken up.
+    pub fn destroy(&self) {
+        let pending_polls = {
+            let mut inner = self.inner.lock();
+            inner.is_destroyed = true;
+            inner.completed.clear();
+            core::mem::take(&mut inner.pending_polls)
+        };
+        for req in pending_polls {
+            req.is_done.store(true, Ordering::Relaxed);
+            req.unregister();
+        }
+        self.pollee.add_events(IoEvents::IN);
+    }
+
+    /// Unregister the poll requests that are done.
+    ///
+    /// The unregistration cannot be done when the requests complete, since the
+    /// notifiers are locked during the event delivery.
+    fn reap_polls(&self) {
+        let done_polls: Vec<Arc<PollRequest>> = {
+            let mut inner = self.inner.lock();
+            let (done_polls, pending_polls) = core::mem::take(&mut inner.pending_polls)
+                .into_iter()
+                .partition(|req| req.is_done.load(Ordering::Relaxed));
+            inner.pending_polls = pending_polls;
+            done_polls
+        };
+        for req in done_polls {
+            req.unregister();
+        }
+    }
+}
+
+impl Drop for AioContext {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+        for req in inner.pending_polls.drain(..) {
+            req.unregister();
+        }
+    }
+}
+
+/// A pending poll request.
+struct PollRequest {
+    ctx: Weak<AioContext>,
+    file: FileRef,
+    mask: IoEvents,
+    event: io_event,
+    resfd: Option<FileRef>,
+    is_done: AtomicBool,
+}
+
+impl PollRequest {
+    fn try_complete(&self, events: IoEvents) {
+        let events = events & self.mask;
+        if events.is_empty() {
+            return;
+        }
+        self.do_complete(events.to_raw() as i64);
+    }
+
+    fn cancel(&self) -> bool {
+        self.do_complete(0)
+    }
+
+    fn do_complete(&self, res: i64) -> bool {
+        if self.is_done.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+        if let Some(ctx) = self.ctx.upgrade() {
+            let event = io_event { res, ..self.event };
+            ctx.complete(event, self.resfd.as_ref());
+        }
+        true
+    }
+
+    fn unregister(self: &Arc<Self>) {
+        if let Some(notifier) = self.file.notifier() {
+            let weak_observer = Arc::downgrade(self) as Weak<dyn Observer<_>>;
+            notifier.unregister(&weak_observer);
+        }
+        if self.file.host_fd().is_some() {
+            HOST_POLLER.unwatch(&self.file);
+        }
+    }
+}
+
+/// The data read by a request, which is copied to the user buffers when the
+/// completion event is reaped, i.e., in the context of the process.
+pub struct ReadBack {
+    data: Vec<u8>,
+    // The addresses and the lengths of the user buffers
+    bufs: Vec<(usize, usize)>,
+}
+
+impl ReadBack {
+    pub fn new(data: Vec<u8>, bufs: Vec<(usize, usize)>) -> Self {
+        Self { data, bufs }
+    }
+
+    fn copy_to_user(&self) -> Result<()> {
+        let mut data = &self.data[..];
+        for &(addr, len) in &self.bufs {
+            if data.is_empty() {
+                break;
+            }
+            let len = len.min(data.len());
+            from_user::check_mut_array(addr as *mut u8, len)?;
+            unsafe {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), addr as *mut u8, len);
+            }
+            data = &data[len..];
+        }
+        Ok(())
+    }
+}
+
+impl Observer<IoEvents> for PollRequest {
+    fn on_event(&self, events: &IoEvents, _metadata: &Option<Weak<dyn Any + Send + Sync>>) {
+        self.try_complete(*events);
+    }
+}
@@ -0,0 +1,377 @@
+//! Linux native asynchronous I/O (AIO).
+//!
+//! The read, write and sync requests on regular files are executed by worker
+//! threads, so `io_submit` returns without waiting for the I/O. The workers
+//! never access the user memory: the data to write is copied at submission and
+//! the data read is copied to the user buffers when the completion events are
+//! reaped. The requests on other files are executed at submission and complete
+//! immediately. The poll requests complete once the files become ready, where
+//! the host files are watched by the host poller. The completions can also be
+//! signaled to an eventfd by setting `IOCB_FLAG_RESFD`.
+
+use std::time::Duration;
+
+use self::c_types::*;
+use self::context::{AioContext, ReadBack};
+use super::file_ops;
+use super::{AsEvent, AsINodeFile};
+use crate::fs::IoEvents;
+use crate::prelude::*;
+use crate::util::mem_util::from_user;
+use crate::vm::{MMapFlags, VMPerms, PAGE_SIZE};
+
+pub use self::c_types::{io_event, iocb};
+
+mod c_types;
+mod context;
+mod worker;
+
+/// The maximal number of events of all AIO contexts of a process.
+const AIO_MAX_NR: usize = 65536;
+
+lazy_static! {
+    /// The AIO contexts of all processes, indexed by (pid, context id).
+    static ref AIO_CONTEXTS: Mutex<HashMap<(pid_t, u64), (Arc<AioContext>, usize)>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn do_io_setup(nr_events: u32) -> Result<u64> {
+    debug!("io_setup: nr_events: {}", nr_events);
+    let nr_events = nr_events as usize;
+    if nr_events == 0 || nr_events > AIO_MAX_NR {
+        return_errno!(EINVAL, "invalid nr_events");
+    }
+
+    let current = current!();
+    let pid = current.process().pid();
+    let mut contexts = AIO_CONTEXTS.lock();
+    let nr_total_events: usize = contexts
+        .iter()
+        .filter(|((ctx_pid, _), _)| *ctx_pid == pid)
+        .map(|(_, (_, nr_events))| nr_events)
+        .sum();
+    if nr_total_events + nr_events > AIO_MAX_NR {
+        return_errno!(EAGAIN, "too many AIO events");
+    }
+
+    // Like Linux, the context id is the address of a ring in the user space.
+    // The ring is left empty, with which libaio always gets the completion
+    // events by the syscall.
+    let ring_addr = current.vm().mmap(
+        0,
+        PAGE_SIZE,
+        VMPerms::READ,
+        MMapFlags::MAP_PRIVATE | MMapFlags::MAP_ANONYMOUS,
+        0,
+        0,
+    )?;
+    let ctx_id = ring_addr as u64;
+    let ctx = AioContext::new(ctx_id, nr_events);
+    contexts.insert((pid, ctx_id), (ctx, nr_events));
+    Ok(ctx_id)
+}
+
+pub fn do_io_destroy(ctx_id: u64) -> Result<()> {
+    debug!("io_destroy: ctx_id: {:#x}", ctx_id);
+    let current = current!();
+    let ctx = {
+        let mut contexts = AIO_CONTEXTS.lock();
+        let (ctx, _) = contexts
+            .remove(&(current.process().pid(), ctx_id))
+            .ok_or_else(|| errno!(EINVAL, "invalid AIO context"))?;
+        ctx
+    };
+    ctx.destroy();
+    current.vm().munmap(ctx_id as usize, PAGE_SIZE)?;
+    Ok(())
+}
+
+/// Submit the requests, returning the number of the requests submitted.
+///
+/// An error is returned only if the first request fails to be submitted.
+pub fn do_io_submit(ctx_id: u64, iocbs: &[(u64, iocb)]) -> Result<usize> {
+    debug!("io_submit: ctx_id: {:#x}, nr: {}", ctx_id, iocbs.len());
+    let ctx = get_context(ctx_id)?;
+    for (idx, (iocb_addr, iocb)) in iocbs.iter().enumerate() {
+        if let Err(e) = submit_one(&ctx, *iocb_addr, iocb) {
+            if idx == 0 {
+                return Err(e);
+            }
+            return Ok(idx);
+        }
+    }
+    Ok(iocbs.len())
+}
+
+pub fn do_io_getevents(
+    ctx_id: u64,
+    min_nr: usize,
+    events: &mut [io_event],
+    timeout: Option<&mut Duration>,
+) -> Result<usize> {
+    debug!(
+        "io_getevents: ctx_id: {:#x}, min_nr: {}, nr: {}, timeout: {:?}",
+        ctx_id,
+        min_nr,
+        events.len(),
+        timeout
+    );
+    let ctx = get_context(ctx_id)?;
+    ctx.get_events(min_nr, events, timeout)
+}
+
+pub fn do_io_cancel(ctx_id: u64, iocb_addr: u64, iocb: &iocb) -> Result<()> {
+    debug!("io_cancel: ctx_id: {:#x}, iocb: {:#x}", ctx_id, iocb_addr);
+    if iocb.aio_key != 0 {
+        return_errno!(EINVAL, "invalid aio_key");
+    }
+    let ctx = get_context(ctx_id)?;
+    ctx.cancel(iocb_addr)
+}
+
+/// Destroy the AIO contexts of a process when it exits.
+///
+/// The memory of the rings is freed along with the VM of the process.
+pub fn release_aio_contexts_when_process_exit(pid: pid_t) {
+    let ctxs: Vec<Arc<AioContext>> = {
+        let mut contexts = AIO_CONTEXTS.lock();
+        let ctx_ids: Vec<(pid_t, u64)> = contexts
+            .keys()
+            .filter(|(ctx_pid, _)| *ctx_pid == pid)
+            .cloned()
+            .collect();
+        ctx_ids
+            .iter()
+            .filter_map(|key| contexts.remove(key))
+            .map(|(ctx, _)| ctx)
+            .collect()
+    };
+    for ctx in ctxs {
+        ctx.destroy();
+    }
+}
+
+fn get_context(ctx_id: u64) -> Result<Arc<AioContext>> {
+    let pid = current!().process().pid();
+    let contexts = AIO_CONTEXTS.lock();
+    contexts
+        .get(&(pid, ctx_id))
+        .map(|(ctx, _)| ctx.clone())
+        .ok_or_else(|| errno!(EINVAL, "invalid AIO context"))
+}
+
+fn submit_one(ctx: &Arc<AioContext>, iocb_addr: u64, iocb: &iocb) -> Result<()> {
+    if iocb.aio_reserved2 != 0 {
+        return_errno!(EINVAL, "reserved fields must be zero");
+    }
+    let flags =
+        IocbFlags::from_bits(iocb.aio_flags).ok_or_else(|| errno!(EINVAL, "invalid aio_flags"))?;
+    if iocb.aio_rw_flags != 0 {
+        return_errno!(EOPNOTSUPP, "aio_rw_flags is not supported");
+    }
+
+    let current = current!();
+    let resfd = if flags.contains(IocbFlags::IOCB_FLAG_RESFD) {
+        let file = current.file(iocb.aio_resfd as FileDesc)?;
+        file.as_event()
+            .map_err(|_| errno!(EINVAL, "aio_resfd is not an eventfd"))?;
+        Some(file)
+    } else {
+        None
+    };
+    let fd = iocb.aio_fildes as FileDesc;
+    let file = current.file(fd)?;
+
+    let event = io_event {
+        data: iocb.aio_data,
+        obj: iocb_addr,
+        res: 0,
+        res2: 0,
+    };
+
+    if iocb.aio_lio_opcode == IOCB_CMD_POLL {
+        if iocb.aio_offset != 0 || iocb.aio_nbytes != 0 {
+            return_errno!(EINVAL, "invalid poll request");
+        }
+        let mask = IoEvents::from_raw(iocb.aio_buf as u16 as u32);
+        ctx.reserve()?;
+        if let Err(e) = ctx.submit_poll(file, mask, event, resfd) {
+            ctx.unreserve();
+            return Err(e);
+        }
+        return Ok(());
+    }
+
+    let offset = iocb.aio_offset;
+    if offset < 0 {
+        return_errno!(EINVAL, "the offset is negative");
+    }
+    if file.as_inode_file().is_ok() {
+        return submit_to_worker(ctx, file, iocb, event, resfd);
+    }
+
+    let buf_ptr = iocb.aio_buf as usize as *mut u8;
+    let nbytes = iocb.aio_nbytes as usize;
+    let res = match iocb.aio_lio_opcode {
+        IOCB_CMD_PREAD => {
+            let buf = user_buf_mut(buf_ptr, nbytes)?;
+            ctx.reserve()?;
+            file_ops::do_pread(fd, buf, offset).map(|len| len as i64)
+        }
+        IOCB_CMD_PWRITE => {
+            let buf = user_buf(buf_ptr, nbytes)?;
+            ctx.reserve()?;
+            file_ops::do_pwrite(fd, buf, offset).map(|len| len as i64)
+        }
+        IOCB_CMD_PREADV => {
+            let mut bufs = user_iovecs(buf_ptr as *const libc::iovec, nbytes)?;
+            ctx.reserve()?;
+            file_ops::do_preadv(fd, &mut bufs, offset).map(|len| len as i64)
+        }
+        IOCB_CMD_PWRITEV => {
+            let bufs = user_iovecs(buf_ptr as *const libc::iovec, nbytes)?;
+            let bufs: Vec<&[u8]> = bufs.into_iter().map(|buf| &*buf).collect();
+            ctx.reserve()?;
+            file_ops::do_pwritev(fd, &bufs, offset).map(|len| len as i64)
+        }
+        IOCB_CMD_FSYNC => {
+            ctx.reserve()?;
+            file_ops::do_fsync(fd).map(|_| 0)
+        }
+        IOCB_CMD_FDSYNC => {
+            ctx.reserve()?;
+            file_ops::do_fdatasync(fd).map(|_| 0)
+        }
+        _ => return_errno!(EINVAL, "invalid aio_lio_opcode"),
+    };
+    // The I/O errors are reported by the completion events
+    let res = res.unwrap_or_else(|e| -(e.errno() as i64));
+    ctx.complete(io_event { res, ..event }, resfd.as_ref());
+    Ok(())
+}
+
+/// Submit a request on a regular file, which is executed by a worker.
+fn submit_to_worker(
+    ctx: &Arc<AioContext>,
+    file: FileRef,
+    iocb: &iocb,
+    event: io_event,
+    resfd: Option<FileRef>,
+) -> Result<()> {
+    let offset = iocb.aio_offset as usize;
+    let buf_ptr = iocb.aio_buf as usize as *mut u8;
+    let nbytes = iocb.aio_nbytes as usize;
+    let op = match iocb.aio_lio_opcode {
+        IOCB_CMD_PREAD => {
+            let buf = user_buf_mut(buf_ptr, nbytes)?;
+            WorkerOp::Read(
+                alloc_buf(nbytes)?,
+                vec![(buf.as_mut_ptr() as usize, nbytes)],
+            )
+        }
+        IOCB_CMD_PREADV => {
+            let bufs = user_iovecs(buf_ptr as *const libc::iovec, nbytes)?;
+            let len = bufs.iter().map(|buf| buf.len()).sum();
+            let bufs = bufs
+                .into_iter()
+                .map(|buf| (buf.as_mut_ptr() as usize, buf.len()))
+                .collect();
+            WorkerOp::Read(alloc_buf(len)?, bufs)
+        }
+        IOCB_CMD_PWRITE => {
+            let buf = user_buf(buf_ptr, nbytes)?;
+            let mut data = alloc_buf(nbytes)?;
+            data.copy_from_slice(buf);
+            WorkerOp::Write(data)
+        }
+        IOCB_CMD_PWRITEV => {
+            let bufs = user_iovecs(buf_ptr as *const libc::iovec, nbytes)?;
+            let mut data = alloc_buf(bufs.iter().map(|buf| buf.len()).sum())?;
+            let mut pos = 0;
+            for buf in bufs {
+                data[pos..pos + buf.len()].copy_from_slice(buf);
+                pos += buf.len();
+            }
+            WorkerOp::Write(data)
+        }
+        IOCB_CMD_FSYNC | IOCB_CMD_FDSYNC => {
+            // The memory mappings are flushed in the context of the process
+            current!().vm().msync_by_file(&file);
+            WorkerOp::Sync(iocb.aio_lio_opcode == IOCB_CMD_FDSYNC)
+        }
+        _ => return_errno!(EINVAL, "invalid aio_lio_opcode"),
+    };
+    ctx.reserve()?;
+
+    let ctx = ctx.clone();
+    worker::execute(move || {
+        let mut read_back = None;
+        let res = match op {
+            WorkerOp::Read(mut data, bufs) => file.read_at(offset, &mut data).map(|len| {
+                data.truncate(len);
+                read_back = Some(ReadBack::new(data, bufs));
+                len as i64
+            }),
+            WorkerOp::Write(data) => file.write_at(offset, &data).map(|len| len as i64),
+            WorkerOp::Sync(false) => file.sync_all().map(|_| 0),
+            WorkerOp::Sync(true) => file.sync_data().map(|_| 0),
+        };
+        // The I/O errors are reported by the completion events
+        let res = res.unwrap_or_else(|e| -(e.errno() as i64));
+        ctx.complete_with(io_event { res, ..event }, read_back, resfd.as_ref());
+    });
+    Ok(())
+}
+
+/// The operation of a request executed by a worker.
+enum WorkerOp {
+    /// Read into the buffer, which is copied to the user buffers on reaping.
+    Read(Vec<u8>, Vec<(usize, usize)>),
+    /// Write the data copied from the user buffers.
+    Write(Vec<u8>),
+    /// Sync the data, and the metadata unless it is a data sync.
+    Sync(bool),
+}
+
+/// Allocate a buffer of the length, which is filled with zeros.
+fn alloc_buf(len: usize) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    buf.try_reserve_exact(len)
+        .map_err(|_| errno!(ENOMEM, "no memory for the AIO buffer"))?;
+    buf.resize(len, 0);
+    Ok(buf)
+}
+
+fn user_buf_mut<'a>(buf_ptr: *mut u8, nbytes: usize) -> Result<&'a mut [u8]> {
+    if nbytes == 0 {
+        return Ok(&mut []);
+    }
+    from_user::check_mut_array(buf_ptr, nbytes)?;
+    Ok(unsafe { std::slice::from_raw_parts_mut(buf_ptr, nbytes) })
+}
+
+fn user_buf<'a>(buf_ptr: *const u8, nbytes: usize) -> Result<&'a [u8]> {
+    if nbytes == 0 {
+        return Ok(&[]);
+    }
+    from_user::check_array(buf_ptr, nbytes)?;
+    Ok(unsafe { std::slice::from_raw_parts(buf_ptr, nbytes) })
+}
+
+fn user_iovecs<'a>(iov: *const libc::iovec, count: usize) -> Result<Vec<&'a mut [u8]>> {
+    if count > IOV_MAX {
+        return_errno!(EINVAL, "too many iovecs");
+    }
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    from_user::check_array(iov, count)?;
+    let iovs = unsafe { std::slice::from_raw_parts(iov, count) };
+    iovs.iter()
+        .filter(|iov| iov.iov_len != 0)
+        .map(|iov| user_buf_mut(iov.iov_base as *mut u8, iov.iov_len))
+        .collect()
+}
+
+/// The maximal number of iovecs of a request.
+const IOV_MAX: usize = 1024;
@@ -0,0 +1,80 @@
+//! The worker threads that execute the AIO requests in the background.
+//!
+//! The workers are started on demand, up to `MAX_WORKERS`. An idle worker is
+//! parked on its own host eventfd until a new request is queued.
+
+use crate::events::HostEventFd;
+use crate::prelude::*;
+
+/// The maximal number of the worker threads.
+const MAX_WORKERS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+lazy_static! {
+    static ref QUEUE: Mutex<Queue> = Mutex::new(Queue {
+        jobs: VecDeque::new(),
+        idle_workers: Vec::new(),
+        nr_workers: 0,
+    });
+}
+
+struct Queue {
+    jobs: VecDeque<Job>,
+    idle_workers: Vec<Arc<HostEventFd>>,
+    nr_workers: usize,
+}
+
+/// Execute the job by a worker thread.
+pub fn execute(job: impl FnOnce() + Send + 'static) {
+    let mut queue = QUEUE.lock();
+    queue.jobs.push_back(Box::new(job));
+    if let Some(eventfd) = queue.idle_workers.pop() {
+        drop(queue);
+        eventfd.write_u64(1);
+        return;
+    }
+    if queue.nr_workers >= MAX_WORKERS {
+        return;
+    }
+
+    match HostEventFd::new() {
+        Ok(eventfd) => {
+            queue.nr_workers += 1;
+            drop(queue);
+            std::thread::spawn(move || run_worker(Arc::new(eventfd)));
+        }
+        Err(e) => {
+            warn!("failed to start an AIO worker: {:?}", e);
+            // Without any worker, execute the job in place
+            if queue.nr_workers == 0 {
+                let job = queue.jobs.pop_back().unwrap();
+                drop(queue);
+                job();
+            }
+        }
+    }
+}
+
+fn run_worker(eventfd: Arc<HostEventFd>) {
+    loop {
+        let job = {
+            let mut queue = QUEUE.lock();
+            match queue.jobs.pop_front() {
+                Some(job) => Some(job),
+                None => {
+                    // Register as idle with the lock held, so that no wakeup is missed
+                    queue.idle_workers.push(eventfd.clone());
+                    None
+                }
+            }
+        };
+        match job {
+            Some(job) => job(),
+            None => {
+                let _ = eventfd.poll(None);
+                let _ = eventfd.read_u64();
+            }
+        }
+    }
+}
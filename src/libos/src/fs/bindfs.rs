@@ -0,0 +1,35 @@
+use alloc::sync::Arc;
+use rcore_fs::vfs::*;
+
+/// A file system that presents an existing directory as its root.
+///
+/// It is used to implement bind mounts: the `BindFS` is mounted on the target
+/// just like any other file system, and all the operations are forwarded to
+/// the inodes under the source directory.
+pub struct BindFS {
+    root: Arc<dyn INode>,
+}
+
+impl BindFS {
+    /// Create a new `BindFS` whose root is the directory `root`
+    pub fn new(root: Arc<dyn INode>) -> Result<Arc<BindFS>> {
+        if root.metadata()?.type_ != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        Ok(Arc::new(BindFS { root }))
+    }
+}
+
+impl FileSystem for BindFS {
+    fn sync(&self) -> Result<()> {
+        self.root.fs().sync()
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.root.clone()
+    }
+
+    fn info(&self) -> FsInfo {
+        self.root.fs().info()
+    }
+}
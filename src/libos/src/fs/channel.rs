@@ -1,5 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Weak;
+use std::time::Duration;
 
 use ringbuf::{Consumer as RbConsumer, Producer as RbProducer, RingBuffer};
 
@@ -71,6 +72,11 @@ impl<I> Channel<I> {
         self.consumer.pop()
     }
 
+    /// Pop an item out of the channel without blocking.
+    pub fn try_pop(&self) -> Result<Option<I>> {
+        self.consumer.try_pop()
+    }
+
     /// Turn the channel into a pair of producer and consumer.
     pub fn split(self) -> (Producer<I>, Consumer<I>) {
         let Channel { producer, consumer } = self;
@@ -130,6 +136,7 @@ macro_rules! impl_end_point_type {
             notifier: Arc<IoNotifier>,
             peer_notifier: Weak<IoNotifier>,
             is_nonblocking: AtomicBool,
+            timeout: SgxMutex<Option<Duration>>,
         }
 
         impl<$i> $end_point<$i> {
@@ -139,6 +146,7 @@ macro_rules! impl_end_point_type {
                 let notifier = Arc::new(IoNotifier::new());
                 let peer_notifier = Default::default();
                 let is_nonblocking = AtomicBool::new(false);
+                let timeout = SgxMutex::new(None);
                 Self {
                     inner,
                     state,
@@ -146,6 +154,7 @@ macro_rules! impl_end_point_type {
                     notifier,
                     peer_notifier,
                     is_nonblocking,
+                    timeout,
                 }
             }
 
@@ -174,6 +183,19 @@ macro_rules! impl_end_point_type {
                 }
             }
 
+            /// Returns the timeout of a blocking push/pop.
+            ///
+            /// By default, a blocking push/pop never times out.
+            pub fn timeout(&self) -> Option<Duration> {
+                *self.timeout.lock().unwrap()
+            }
+
+            /// Set the timeout of a blocking push/pop, after which `EAGAIN` is
+            /// returned.
+            pub fn set_timeout(&self, timeout: Option<Duration>) {
+                *self.timeout.lock().unwrap() = timeout;
+            }
+
             fn trigger_peer_events(&self, events: &IoEvents) {
                 if let Some(peer_notifier) = self.peer_notifier.upgrade() {
                     peer_notifier.broadcast(events);
@@ -185,9 +207,9 @@ macro_rules! impl_end_point_type {
 
 // Just like a normal loop, except that a waiter queue (as well as a waiter)
 // is used to avoid busy loop. This macro is used in the push/pop implementation
-// below.
+// below. Like the socket timeouts of Linux, `EAGAIN` is returned on timeout.
 macro_rules! waiter_loop {
-    ($loop_body: block, $waiter_queue: expr) => {
+    ($loop_body: block, $waiter_queue: expr, $timeout: expr) => {
         // Try without creating a waiter. This saves some CPU cycles if the
         // first attempt succeeds.
         {
@@ -197,6 +219,7 @@ macro_rules! waiter_loop {
         // The main loop
         let waiter = Waiter::new();
         let waiter_queue = $waiter_queue;
+        let mut timeout = $timeout;
         loop {
             waiter_queue.reset_and_enqueue(&waiter);
 
@@ -204,7 +227,12 @@ macro_rules! waiter_loop {
                 $loop_body
             }
 
-            waiter.wait(None)?;
+            waiter
+                .wait_mut(timeout.as_mut())
+                .map_err(|e| match e.errno() {
+                    ETIMEDOUT => errno!(EAGAIN, "timeout reached"),
+                    _ => e,
+                })?;
         }
     };
 }
@@ -238,7 +266,8 @@ impl<I> Producer<I> {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
 
@@ -283,6 +312,37 @@ impl<I> Producer<I> {
     pub fn is_peer_shutdown(&self) -> bool {
         self.state.is_consumer_shutdown()
     }
+
+    /// Resize the buffer shared with the consumer of the same channel.
+    ///
+    /// The items in the buffer are kept, so the new capacity is at least the
+    /// number of them.
+    pub fn resize(&self, consumer: &Consumer<I>, capacity: usize) -> Result<()> {
+        if !Arc::ptr_eq(&self.state, &consumer.state) {
+            return_errno!(EINVAL, "the endpoints are not of the same channel");
+        }
+
+        {
+            let mut rb_producer = self.inner.lock().unwrap();
+            let mut rb_consumer = consumer.inner.lock().unwrap();
+            let capacity = capacity.max(rb_consumer.len()).max(1);
+            if capacity == rb_consumer.capacity() {
+                return Ok(());
+            }
+
+            let (mut new_producer, new_consumer) = RingBuffer::new(capacity).split();
+            while let Some(item) = rb_consumer.pop() {
+                // Never fails since the capacity is enough
+                let _ = new_producer.push(item);
+            }
+            *rb_producer = new_producer;
+            *rb_consumer = new_consumer;
+        }
+
+        // The producer may become writable
+        consumer.trigger_peer_events(&IoEvents::OUT);
+        Ok(())
+    }
 }
 
 impl<I: Copy> Producer<I> {
@@ -291,6 +351,16 @@ impl<I: Copy> Producer<I> {
     }
 
     pub fn push_slices(&self, item_slices: &[&[I]]) -> Result<usize> {
+        self.do_push_slices(item_slices, false)
+    }
+
+    /// Push the slices like `push_slices`, but never block even if the
+    /// producer is blocking.
+    pub fn try_push_slices(&self, item_slices: &[&[I]]) -> Result<usize> {
+        self.do_push_slices(item_slices, true)
+    }
+
+    fn do_push_slices(&self, item_slices: &[&[I]], nonblocking: bool) -> Result<usize> {
         let len: usize = item_slices.iter().map(|slice| slice.len()).sum();
         if len == 0 {
             return Ok(0);
@@ -320,11 +390,12 @@ impl<I: Copy> Producer<I> {
                     return Ok(total_count);
                 }
 
-                if self.is_nonblocking() {
+                if nonblocking || self.is_nonblocking() {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
 }
@@ -344,6 +415,16 @@ impl_end_point_type! {
 
 impl<I> Consumer<I> {
     pub fn pop(&self) -> Result<Option<I>> {
+        self.do_pop(false)
+    }
+
+    /// Pop an item like `pop`, but never block even if the consumer is
+    /// blocking.
+    pub fn try_pop(&self) -> Result<Option<I>> {
+        self.do_pop(true)
+    }
+
+    fn do_pop(&self, nonblocking: bool) -> Result<Option<I>> {
         waiter_loop!(
             {
                 let mut rb_consumer = self.inner.lock().unwrap();
@@ -360,11 +441,12 @@ impl<I> Consumer<I> {
                 if self.is_peer_shutdown() {
                     return Ok(None);
                 }
-                if self.is_nonblocking() {
+                if nonblocking || self.is_nonblocking() {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
 
@@ -436,6 +518,16 @@ impl<I: Copy> Consumer<I> {
     }
 
     pub fn pop_slices(&self, item_slices: &mut [&mut [I]]) -> Result<usize> {
+        self.do_pop_slices(item_slices, false)
+    }
+
+    /// Pop the slices like `pop_slices`, but never block even if the
+    /// consumer is blocking.
+    pub fn try_pop_slices(&self, item_slices: &mut [&mut [I]]) -> Result<usize> {
+        self.do_pop_slices(item_slices, true)
+    }
+
+    fn do_pop_slices(&self, item_slices: &mut [&mut [I]], nonblocking: bool) -> Result<usize> {
         let len: usize = item_slices.iter().map(|slice| slice.len()).sum();
         if len == 0 {
             return Ok(0);
@@ -468,13 +560,33 @@ impl<I: Copy> Consumer<I> {
                 if self.is_peer_shutdown() {
                     return Ok(0);
                 }
-                if self.is_nonblocking() {
+                if nonblocking || self.is_nonblocking() {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
+
+    /// Copy the items to the slices without consuming them, e.g., to
+    /// implement MSG_PEEK. This method never blocks.
+    pub fn peek_slices(&self, item_slices: &mut [&mut [I]]) -> Result<usize> {
+        let rb_consumer = self.inner.lock().unwrap();
+        if self.is_self_shutdown() {
+            return_errno!(EPIPE, "this endpoint has been shutdown");
+        }
+
+        let mut dst_items = item_slices.iter_mut().flat_map(|slice| slice.iter_mut());
+        let mut count = 0;
+        rb_consumer.for_each(|item| {
+            if let Some(dst_item) = dst_items.next() {
+                *dst_item = *item;
+                count += 1;
+            }
+        });
+        Ok(count)
+    }
 }
 
 impl<I> Drop for Consumer<I> {
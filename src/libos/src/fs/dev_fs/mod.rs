@@ -5,6 +5,7 @@ use rcore_fs::vfs;
 use rcore_fs_devfs::DevFS;
 use rcore_fs_mountfs::MountFS;
 use rcore_fs_ramfs::RamFS;
+use std::sync::Weak;
 
 use self::dev_fd::DevFd;
 use self::dev_null::DevNull;
@@ -21,9 +22,29 @@ mod dev_sgx;
 mod dev_shm;
 mod dev_zero;
 
+lazy_static! {
+    /// All the created DevFSes, used to register block devices at runtime
+    static ref DEVFS_INSTANCES: SgxMutex<Vec<Weak<DevFS>>> = SgxMutex::new(Vec::new());
+}
+
+/// The names of the built-in devices, which can not be used by block devices
+const BUILTIN_DEVS: [&str; 8] = [
+    "null", "zero", "random", "urandom", "arandom", "sgx", "shm", "fd",
+];
+
+/// Whether `name` is used by a built-in device of the DevFS
+pub fn is_builtin_dev(name: &str) -> bool {
+    BUILTIN_DEVS.contains(&name)
+}
+
 /// API to initialize the DevFS
 pub fn init_devfs(disk_options: &[DevDiskOption]) -> Result<Arc<MountFS>> {
     let devfs = DevFS::new();
+    {
+        let mut instances = DEVFS_INSTANCES.lock().unwrap();
+        instances.retain(|devfs| devfs.strong_count() > 0);
+        instances.push(Arc::downgrade(&devfs));
+    }
     let dev_null = Arc::new(DevNull) as _;
     devfs.add("null", dev_null)?;
     let dev_zero = Arc::new(DevZero) as _;
@@ -56,13 +77,40 @@ pub fn init_devfs(disk_options: &[DevDiskOption]) -> Result<Arc<MountFS>> {
     Ok(mountable_devfs)
 }
 
+/// Add the block device named `name` into all the existing DevFSes.
+///
+/// It is used when a new disk is set up after the DevFSes are mounted.
+pub fn register_disk(name: &str) -> Result<()> {
+    let dev_disk = Arc::new(DevDisk::open_or_create(name)?);
+    let instances = DEVFS_INSTANCES.lock().unwrap();
+    for devfs in instances.iter().filter_map(|devfs| devfs.upgrade()) {
+        match devfs.add(name, dev_disk.clone()) {
+            Ok(_) => {}
+            // The DevFS may be created after the disk is set up
+            Err(vfs::FsError::EntryExist)
+                if devfs
+                    .root_inode()
+                    .find(name)?
+                    .downcast_ref::<DevDisk>()
+                    .is_some() => {}
+            Err(vfs::FsError::EntryExist) => {
+                return_errno!(EEXIST, "the disk name is used by another device");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
 /// Options of block device under the DevFS.
 pub struct DevDiskOption {
-    name: &'static str,
+    name: String,
 }
 
 impl DevDiskOption {
-    pub fn new(name: &'static str) -> Self {
-        Self { name }
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+        }
     }
 }
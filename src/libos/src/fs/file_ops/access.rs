@@ -51,13 +51,12 @@ pub fn do_faccessat(fs_path: &FsPath, mode: AccessibilityCheckMode) -> Result<()
     if mode.test_for_exist() {
         return Ok(());
     }
-    // Check the permissions of file owner
-    let owner_file_mode = {
-        let metadata = inode.metadata()?;
-        AccessibilityCheckMode::from_u32((metadata.mode >> 6) as u32 & 0b111)?
-    };
-    if !owner_file_mode.contains(mode) {
+    // TODO: Check with the real user and group IDs instead of the file system ones
+    if !inode.allow_access(mode)? {
         return_errno!(EACCES, "the requested access is denied");
     }
+    if mode.contains(AccessibilityCheckMode::W_OK) {
+        inode.check_writable_mount()?;
+    }
     Ok(())
 }
@@ -75,7 +75,9 @@ pub fn do_fchmodat(fs_path: &FsPath, mode: FileMode) -> Result<()> {
         let fs = current.fs().read().unwrap();
         fs.lookup_inode(&path)?
     };
+    inode.check_writable_mount()?;
     let mut info = inode.metadata()?;
+    check_chmod_permission(&info)?;
     info.mode = mode.bits();
     inode.set_metadata(&info)?;
     Ok(())
@@ -86,7 +88,18 @@ pub fn do_fchmod(fd: FileDesc, mode: FileMode) -> Result<()> {
 
     let file_ref = current!().file(fd)?;
     let mut info = file_ref.metadata()?;
+    check_chmod_permission(&info)?;
     info.mode = mode.bits();
     file_ref.set_metadata(&info)?;
     Ok(())
 }
+
+/// Only the owner or a privileged process can change the mode of a file
+fn check_chmod_permission(info: &Metadata) -> Result<()> {
+    let current = current!();
+    let credentials = current.process().credentials();
+    if credentials.fsuid() != 0 && info.uid as uid_t != credentials.fsuid() {
+        return_errno!(EPERM, "not the owner of the file");
+    }
+    Ok(())
+}
@@ -30,7 +30,9 @@ pub fn do_fchownat(fs_path: &FsPath, uid: i32, gid: i32, flags: ChownFlags) -> R
             fs.lookup_inode(&path)?
         }
     };
+    inode.check_writable_mount()?;
     let mut info = inode.metadata()?;
+    check_chown_permission(&info, uid, gid)?;
     if let Some(uid) = uid {
         info.uid = uid as usize;
     }
@@ -53,6 +55,7 @@ pub fn do_fchown(fd: FileDesc, uid: i32, gid: i32) -> Result<()> {
 
     let file_ref = current!().file(fd)?;
     let mut info = file_ref.metadata()?;
+    check_chown_permission(&info, uid, gid)?;
     if let Some(uid) = uid {
         info.uid = uid as usize;
     }
@@ -63,6 +66,26 @@ pub fn do_fchown(fd: FileDesc, uid: i32, gid: i32) -> Result<()> {
     Ok(())
 }
 
+/// A privileged process can change the owner and group of a file arbitrarily,
+/// while the owner can only change the group to one of its groups.
+fn check_chown_permission(info: &Metadata, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+    let current = current!();
+    let credentials = current.process().credentials();
+    if credentials.fsuid() == 0 {
+        return Ok(());
+    }
+    if info.uid as uid_t != credentials.fsuid() {
+        return_errno!(EPERM, "not the owner of the file");
+    }
+    if uid.map_or(false, |uid| uid != info.uid as uid_t) {
+        return_errno!(EPERM, "not allowed to change the owner");
+    }
+    if gid.map_or(false, |gid| !credentials.in_group(gid)) {
+        return_errno!(EPERM, "not allowed to change the group");
+    }
+    Ok(())
+}
+
 fn to_opt(id: i32) -> Result<Option<u32>> {
     let id = if id >= 0 {
         Some(id as u32)
@@ -24,8 +24,19 @@ pub enum FcntlCmd<'a> {
     SetLk(&'a c_flock),
     /// The blocking version of SetLK
     SetLkWait(&'a c_flock),
+    /// Test an open file description lock
+    OfdGetLk(&'a mut c_flock),
+    /// Acquire or release an open file description lock
+    OfdSetLk(&'a c_flock),
+    /// The blocking version of OfdSetLk
+    OfdSetLkWait(&'a c_flock),
 }
 
+// Commands for open file description locks, which may be missing in libc
+const F_OFD_GETLK: c_int = 36;
+const F_OFD_SETLK: c_int = 37;
+const F_OFD_SETLKW: c_int = 38;
+
 impl<'a> FcntlCmd<'a> {
     #[deny(unreachable_patterns)]
     pub fn from_raw(cmd: u32, arg: u64) -> Result<FcntlCmd<'a>> {
@@ -54,6 +65,24 @@ impl<'a> FcntlCmd<'a> {
                 let lock_c = unsafe { &*lock_ptr };
                 FcntlCmd::SetLkWait(lock_c)
             }
+            F_OFD_GETLK => {
+                let lock_mut_ptr = arg as *mut c_flock;
+                from_user::check_mut_ptr(lock_mut_ptr)?;
+                let lock_mut_c = unsafe { &mut *lock_mut_ptr };
+                FcntlCmd::OfdGetLk(lock_mut_c)
+            }
+            F_OFD_SETLK => {
+                let lock_ptr = arg as *const c_flock;
+                from_user::check_ptr(lock_ptr)?;
+                let lock_c = unsafe { &*lock_ptr };
+                FcntlCmd::OfdSetLk(lock_c)
+            }
+            F_OFD_SETLKW => {
+                let lock_ptr = arg as *const c_flock;
+                from_user::check_ptr(lock_ptr)?;
+                let lock_c = unsafe { &*lock_ptr };
+                FcntlCmd::OfdSetLkWait(lock_c)
+            }
             _ => return_errno!(EINVAL, "unsupported command"),
         })
     }
@@ -135,6 +164,56 @@ pub fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd) -> Result<isize> {
             file.set_advisory_lock(&lock, is_nonblocking)?;
             0
         }
+        FcntlCmd::OfdGetLk(lock_mut_c) => {
+            let file = file_table.get(fd)?;
+            let lock_type = RangeLockType::from_u16(lock_mut_c.l_type)?;
+            if RangeLockType::F_UNLCK == lock_type {
+                return_errno!(EINVAL, "invalid flock type for ofd getlk");
+            }
+            check_ofd_c_flock(lock_mut_c)?;
+            let mut lock = RangeLockBuilder::new()
+                .owner(RangeLockOwner::new_open_file(&file))
+                .type_(lock_type)
+                .range(FileRange::from_c_flock_and_file(&lock_mut_c, &file)?)
+                .build()?;
+            file.test_advisory_lock(&mut lock)?;
+            trace!("ofd getlk returns: {:?}", lock);
+            (*lock_mut_c).copy_from_range_lock(&lock);
+            0
+        }
+        FcntlCmd::OfdSetLk(lock_c) => {
+            let file = file_table.get(fd)?;
+            let lock = build_ofd_lock(lock_c, &file)?;
+            let is_nonblocking = true;
+            file.set_advisory_lock(&lock, is_nonblocking)?;
+            0
+        }
+        FcntlCmd::OfdSetLkWait(lock_c) => {
+            let file = file_table.get(fd)?;
+            let lock = build_ofd_lock(lock_c, &file)?;
+            // Other threads sharing the file table may close the file to wake us up
+            drop(file_table);
+            let is_nonblocking = false;
+            file.set_advisory_lock(&lock, is_nonblocking)?;
+            0
+        }
     };
     Ok(ret)
 }
+
+fn check_ofd_c_flock(lock_c: &c_flock) -> Result<()> {
+    // The l_pid field must be set to 0 for OFD locks
+    if lock_c.l_pid != 0 {
+        return_errno!(EINVAL, "l_pid must be 0 for ofd locks");
+    }
+    Ok(())
+}
+
+fn build_ofd_lock(lock_c: &c_flock, file: &FileRef) -> Result<RangeLock> {
+    check_ofd_c_flock(lock_c)?;
+    RangeLockBuilder::new()
+        .owner(RangeLockOwner::new_open_file(file))
+        .type_(RangeLockType::from_u16(lock_c.l_type)?)
+        .range(FileRange::from_c_flock_and_file(lock_c, file)?)
+        .build()
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crate::process::{
+    tty_get_foreground_pgrp, tty_get_sid, tty_release_ctty, tty_set_ctty, tty_set_foreground_pgrp,
+};
+
+impl_ioctl_cmd! {
+    pub struct GetForegroundPgrp<Input=(), Output=i32> {}
+}
+
+impl GetForegroundPgrp {
+    pub fn execute(&mut self) -> Result<()> {
+        let pgid = tty_get_foreground_pgrp()?;
+        self.set_output(pgid as i32);
+        Ok(())
+    }
+}
+
+impl_ioctl_cmd! {
+    pub struct SetForegroundPgrp<Input=i32, Output=()> {}
+}
+
+impl SetForegroundPgrp {
+    pub fn execute(&self) -> Result<()> {
+        tty_set_foreground_pgrp(*self.input())
+    }
+}
+
+impl_ioctl_cmd! {
+    pub struct GetTtySid<Input=(), Output=i32> {}
+}
+
+impl GetTtySid {
+    pub fn execute(&mut self) -> Result<()> {
+        let sid = tty_get_sid()?;
+        self.set_output(sid as i32);
+        Ok(())
+    }
+}
+
+impl_ioctl_cmd! {
+    pub struct SetControllingTty<Input=(), Output=()> {}
+}
+
+impl SetControllingTty {
+    pub fn execute(&self) -> Result<()> {
+        tty_set_ctty()
+    }
+}
+
+impl_ioctl_cmd! {
+    pub struct ReleaseControllingTty<Input=(), Output=()> {}
+}
+
+impl ReleaseControllingTty {
+    pub fn execute(&self) -> Result<()> {
+        tty_release_ctty()
+    }
+}
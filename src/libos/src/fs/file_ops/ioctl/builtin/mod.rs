@@ -210,6 +210,7 @@ use super::*;
 pub use self::get_ifconf::{GetIfConf, IfConf};
 pub use self::get_ifreq::{GetIfReq, GetIfReqWithRawCmd, IfReq};
 pub use self::get_readbuflen::GetReadBufLen;
+pub use self::job_control::*;
 pub use self::set_close_on_exec::*;
 pub use self::set_nonblocking::SetNonBlocking;
 pub use self::termios::*;
@@ -218,6 +219,7 @@ pub use self::winsize::*;
 mod get_ifconf;
 mod get_ifreq;
 mod get_readbuflen;
+mod job_control;
 mod set_close_on_exec;
 mod set_nonblocking;
 mod termios;
@@ -301,6 +303,14 @@ macro_rules! match_ioctl_cmd_auto_error {
         else if __cmd.is::<SetWinSize>() {
             return_errno!(Errno::ENOTTY, "not tty device");
         }
+        else if __cmd.is::<GetForegroundPgrp>()
+            || __cmd.is::<SetForegroundPgrp>()
+            || __cmd.is::<GetTtySid>()
+            || __cmd.is::<SetControllingTty>()
+            || __cmd.is::<ReleaseControllingTty>()
+        {
+            return_errno!(Errno::ENOTTY, "not tty device");
+        }
         else {
             // Default branch
             return_errno!(EINVAL, "unsupported ioctl cmd");
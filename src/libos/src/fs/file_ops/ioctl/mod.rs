@@ -8,7 +8,8 @@ use super::*;
 
 use self::builtin::*;
 pub use self::builtin::{
-    GetIfConf, GetIfReqWithRawCmd, GetReadBufLen, GetWinSize, IfConf, IoctlCmd, SetNonBlocking,
+    GetForegroundPgrp, GetIfConf, GetIfReqWithRawCmd, GetReadBufLen, GetTtySid, GetWinSize, IfConf,
+    IoctlCmd, ReleaseControllingTty, SetControllingTty, SetForegroundPgrp, SetNonBlocking,
     SetWinSize, TcGets, TcSets,
 };
 pub use self::non_builtin::{NonBuiltinIoctlCmd, StructuredIoctlArgType, StructuredIoctlNum};
@@ -38,6 +39,15 @@ impl_ioctl_nums_and_cmds! {
     TIOCGWINSZ => (0x5413, mut WinSize),
     // Set window size
     TIOCSWINSZ => (0x5414, WinSize),
+    // Make the given terminal the controlling terminal of the calling process. The argument is
+    // passed by value and only matters for stealing the terminal, which is not supported.
+    TIOCSCTTY => (0x540E, ()),
+    // Get the process group ID of the foreground process group on this terminal
+    TIOCGPGRP => (0x540F, mut i32),
+    // Set the foreground process group ID of this terminal
+    TIOCSPGRP => (0x5410, i32),
+    // Get the session ID of the session which has this terminal as the controlling terminal
+    TIOCGSID => (0x5429, mut i32),
     // Set the nonblocking mode for socket
     FIONBIO => (0x5421, i32),
     // If the given terminal was the controlling terminal of the calling process, give up this
@@ -86,6 +96,11 @@ impl<'a> IoctlRawCmd<'a> {
                 let winsize = **winsize_ref;
                 Box::new(SetWinSize::new(winsize))
             }
+            IoctlRawCmd::TIOCSCTTY(_) => Box::new(SetControllingTty::new(())),
+            IoctlRawCmd::TIOCNOTTY(_) => Box::new(ReleaseControllingTty::new(())),
+            IoctlRawCmd::TIOCGPGRP(_) => Box::new(GetForegroundPgrp::new(())),
+            IoctlRawCmd::TIOCSPGRP(pgid) => Box::new(SetForegroundPgrp::new(**pgid)),
+            IoctlRawCmd::TIOCGSID(_) => Box::new(GetTtySid::new(())),
             IoctlRawCmd::NonBuiltin(inner) => {
                 let nonbuiltin_cmd =
                     unsafe { NonBuiltinIoctlCmd::new(*inner.cmd_num(), inner.arg_ptr() as _)? };
@@ -134,6 +149,14 @@ impl<'a> IoctlRawCmd<'a> {
                 let cmd = cmd.downcast_ref::<GetWinSize>().unwrap();
                 **winsize_mut = *cmd.output().unwrap();
             }
+            IoctlRawCmd::TIOCGPGRP(pgid_mut) => {
+                let cmd = cmd.downcast_ref::<GetForegroundPgrp>().unwrap();
+                **pgid_mut = *cmd.output().unwrap();
+            }
+            IoctlRawCmd::TIOCGSID(sid_mut) => {
+                let cmd = cmd.downcast_ref::<GetTtySid>().unwrap();
+                **sid_mut = *cmd.output().unwrap();
+            }
             IoctlRawCmd::FIONREAD(len_mut) => {
                 let cmd = cmd.downcast_ref::<GetReadBufLen>().unwrap();
                 **len_mut = *cmd.output().unwrap();
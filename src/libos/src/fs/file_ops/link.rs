@@ -30,6 +30,10 @@ pub fn do_linkat(old_fs_path: &FsPath, new_fs_path: &FsPath, flags: LinkFlags) -
         let new_dir_inode = fs.lookup_inode(new_dir_path)?;
         (inode, new_dir_inode)
     };
+    if !new_dir_inode.allow_modify_entries()? {
+        return_errno!(EACCES, "link cannot be created");
+    }
+    new_dir_inode.check_writable_mount()?;
     new_dir_inode.link(new_file_name, &inode)?;
     Ok(())
 }
@@ -13,10 +13,12 @@ pub fn do_mkdirat(fs_path: &FsPath, mode: FileMode) -> Result<()> {
     if inode.find(file_name).is_ok() {
         return_errno!(EEXIST, "");
     }
-    if !inode.allow_write()? {
-        return_errno!(EPERM, "dir cannot be written");
+    if !inode.allow_modify_entries()? {
+        return_errno!(EACCES, "dir cannot be written");
     }
+    inode.check_writable_mount()?;
     let masked_mode = mode & !current.process().umask();
-    inode.create(file_name, FileType::Dir, masked_mode.bits())?;
+    let new_inode = inode.create(file_name, FileType::Dir, masked_mode.bits())?;
+    new_inode.set_owner_to_current()?;
     Ok(())
 }
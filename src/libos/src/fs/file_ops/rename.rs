@@ -36,6 +36,11 @@ pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath) -> Result<()> {
     if old_file_mode.has_sticky_bit() {
         warn!("ignoring the sticky bit");
     }
+    if !old_dir_inode.allow_modify_entries()? || !new_dir_inode.allow_modify_entries()? {
+        return_errno!(EACCES, "file cannot be renamed");
+    }
+    old_dir_inode.check_writable_mount()?;
+    new_dir_inode.check_writable_mount()?;
     old_dir_inode.move_(old_file_name, &new_dir_inode, new_file_name)?;
     Ok(())
 }
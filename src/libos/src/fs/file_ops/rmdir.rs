@@ -13,6 +13,10 @@ pub fn do_rmdir(path: &str) -> Result<()> {
     if file_inode.metadata()?.type_ != FileType::Dir {
         return_errno!(ENOTDIR, "rmdir on not directory");
     }
+    if !dir_inode.allow_modify_entries()? {
+        return_errno!(EACCES, "dir cannot be removed");
+    }
+    dir_inode.check_writable_mount()?;
     dir_inode.unlink(file_name)?;
     Ok(())
 }
@@ -44,10 +44,12 @@ pub fn do_symlinkat(target: &str, link_path: &FsPath) -> Result<usize> {
         let fs = current.fs().read().unwrap();
         fs.lookup_inode(dir_path)?
     };
-    if !dir_inode.allow_write()? {
-        return_errno!(EPERM, "symlink cannot be created");
+    if !dir_inode.allow_modify_entries()? {
+        return_errno!(EACCES, "symlink cannot be created");
     }
+    dir_inode.check_writable_mount()?;
     let link_inode = dir_inode.create(link_name, FileType::SymLink, 0o0777)?;
+    link_inode.set_owner_to_current()?;
     let data = target.as_bytes();
     link_inode.resize(data.len())?;
     link_inode.write_at(0, data)?;
@@ -7,6 +7,7 @@ pub fn do_truncate(path: &str, len: usize) -> Result<()> {
         let fs = current.fs().read().unwrap();
         fs.lookup_inode(&path)?
     };
+    inode.check_writable_mount()?;
     inode.resize(len)?;
     Ok(())
 }
@@ -25,6 +25,10 @@ fn do_unlink(path: &str) -> Result<()> {
     if file_mode.has_sticky_bit() {
         warn!("ignoring the sticky bit");
     }
+    if !dir_inode.allow_modify_entries()? {
+        return_errno!(EACCES, "file cannot be unlinked");
+    }
+    dir_inode.check_writable_mount()?;
     dir_inode.unlink(file_name)?;
     Ok(())
 }
@@ -126,6 +126,7 @@ pub fn do_utimes_path(
             fs.lookup_inode(&path)?
         }
     };
+    inode.check_writable_mount()?;
     let mut info = inode.metadata()?;
     if let Utime::UTIME(atime) = atime {
         info.atime = atime;
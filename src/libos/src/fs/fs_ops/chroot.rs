@@ -0,0 +1,14 @@
+use super::*;
+
+pub fn do_chroot(path: &str) -> Result<()> {
+    debug!("chroot: path: {:?}", path);
+
+    let current = current!();
+    // Like CAP_SYS_CHROOT on Linux, only root is allowed to chroot
+    if current.process().credentials().euid() != 0 {
+        return_errno!(EPERM, "only root can change the root directory");
+    }
+    let mut fs = current.fs().write().unwrap();
+    fs.set_root(path)?;
+    Ok(())
+}
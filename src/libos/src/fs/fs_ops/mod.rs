@@ -1,15 +1,20 @@
 use super::*;
 
 pub use self::chdir::do_chdir;
+pub use self::chroot::do_chroot;
 pub use self::getcwd::do_getcwd;
 pub use self::mount::{
-    do_mount, do_mount_rootfs, do_umount, MountFlags, MountOptions, UmountFlags,
+    do_bind_mount, do_mount, do_mount_rootfs, do_move_mount, do_remount, do_umount, MountFlags,
+    MountOptions, UmountFlags,
 };
+pub use self::pivot_root::do_pivot_root;
 pub use self::statfs::{do_fstatfs, do_statfs, fetch_host_statfs, Statfs};
 pub use self::sync::do_sync;
 
 mod chdir;
+mod chroot;
 mod getcwd;
 mod mount;
+mod pivot_root;
 mod statfs;
 mod sync;
@@ -1,4 +1,6 @@
-use config::{parse_key, parse_mac, ConfigMount, ConfigMountFsType, ConfigMountOptions};
+use config::{
+    parse_key, parse_mac, parse_memory_size, ConfigMount, ConfigMountFsType, ConfigMountOptions,
+};
 use rcore_fs_mountfs::MNode;
 use rootfs::SEFS_MANAGER;
 use std::path::PathBuf;
@@ -6,7 +8,10 @@ use std::sync::Once;
 use util::host_file_util::{write_host_file, HostFile};
 use util::mem_util::from_user;
 
-use super::rootfs::{mount_nonroot_fs_according_to, open_root_fs_according_to, umount_nonroot_fs};
+use super::rootfs::{
+    bind_mount_at, mount_nonroot_fs_according_to, move_mount, open_root_fs_according_to,
+    remount_fs_at, umount_nonroot_fs,
+};
 use super::*;
 
 lazy_static! {
@@ -64,24 +69,13 @@ pub fn do_mount(
 
     let target = if target == "/" {
         return_errno!(EPERM, "can not mount on root");
-    } else if target.len() > 0 && target.as_bytes()[0] == b'/' {
-        PathBuf::from(target)
     } else {
-        let thread = current!();
-        let fs = thread.fs().read().unwrap();
-        PathBuf::from(fs.convert_to_abs_path(target))
+        PathBuf::from(to_abs_path(target))
     };
-
-    if flags.contains(MountFlags::MS_REMOUNT)
-        || flags.contains(MountFlags::MS_BIND)
-        || flags.contains(MountFlags::MS_SHARED)
-        || flags.contains(MountFlags::MS_PRIVATE)
-        || flags.contains(MountFlags::MS_SLAVE)
-        || flags.contains(MountFlags::MS_UNBINDABLE)
-        || flags.contains(MountFlags::MS_MOVE)
-    {
-        return_errno!(EINVAL, "Only support to create a new mount");
-    }
+    let target_path = target
+        .to_str()
+        .ok_or_else(|| errno!(EINVAL, "invalid target"))?
+        .to_owned();
 
     let (mount_configs, user_key) = match options {
         MountOptions::UnionFS(unionfs_options) => {
@@ -123,14 +117,18 @@ pub fn do_mount(
             };
             (vec![mc], sefs_options.key)
         }
-        MountOptions::HostFS(dir) => {
+        MountOptions::HostFS(hostfs_options) => {
             let mc = ConfigMount {
                 type_: ConfigMountFsType::TYPE_HOSTFS,
                 target,
-                source: Some(dir.clone()),
-                options: Default::default(),
+                source: Some(hostfs_options.dir.clone()),
+                options: ConfigMountOptions {
+                    mac: hostfs_options.mac,
+                    protected: hostfs_options.protected,
+                    ..Default::default()
+                },
             };
-            (vec![mc], None)
+            (vec![mc], hostfs_options.key)
         }
         MountOptions::RamFS => {
             let mc = ConfigMount {
@@ -141,10 +139,40 @@ pub fn do_mount(
             };
             (vec![mc], None)
         }
-        MountOptions::Ext2 => {
+        MountOptions::Ext2(ext2_options) => {
+            let disk = match ext2_options.disk {
+                Some(disk) => disk,
+                // Use the source as the disk name, e.g., "/dev/sworndisk"
+                None => source.trim_start_matches("/dev/").to_owned(),
+            };
+            if disk.is_empty() || disk.contains('/') {
+                return_errno!(EINVAL, "invalid disk name for Ext2");
+            }
             let mc = ConfigMount {
                 type_: ConfigMountFsType::TYPE_EXT2,
                 target,
+                source: ext2_options.dir,
+                options: ConfigMountOptions {
+                    disk_size: ext2_options.disk_size,
+                    disk: Some(disk),
+                    ..Default::default()
+                },
+            };
+            (vec![mc], ext2_options.key)
+        }
+        MountOptions::ProcFS => {
+            let mc = ConfigMount {
+                type_: ConfigMountFsType::TYPE_PROCFS,
+                target,
+                source: None,
+                options: Default::default(),
+            };
+            (vec![mc], None)
+        }
+        MountOptions::DevFS => {
+            let mc = ConfigMount {
+                type_: ConfigMountFsType::TYPE_DEVFS,
+                target,
                 source: None,
                 options: Default::default(),
             };
@@ -159,6 +187,72 @@ pub fn do_mount(
 
     let follow_symlink = !flags.contains(MountFlags::MS_NOSYMFOLLOW);
     mount_nonroot_fs_according_to(&root_inode, &mount_configs, &user_key, follow_symlink)?;
+    if flags.contains(MountFlags::MS_RDONLY) {
+        remount_fs_at(&root_inode, &target_path, true, follow_symlink)?;
+    }
+    Ok(())
+}
+
+pub fn do_bind_mount(source: &str, target: &str, flags: MountFlags) -> Result<()> {
+    debug!(
+        "bind mount: source: {}, target: {}, flags: {:?}",
+        source, target, flags
+    );
+
+    if target == "/" {
+        return_errno!(EPERM, "can not mount on root");
+    }
+    let target = to_abs_path(target);
+    let source_inode = {
+        let thread = current!();
+        let fs = thread.fs().read().unwrap();
+        fs.lookup_inode(source)?
+    };
+    if source_inode.metadata()?.type_ != FileType::Dir {
+        return_errno!(ENOTDIR, "only directories can be bind mounted");
+    }
+
+    let root_inode = ROOT_FS.read().unwrap().root_inode();
+    let follow_symlink = !flags.contains(MountFlags::MS_NOSYMFOLLOW);
+    bind_mount_at(source_inode, &root_inode, &target, follow_symlink)?;
+    if flags.contains(MountFlags::MS_RDONLY) {
+        remount_fs_at(&root_inode, &target, true, follow_symlink)?;
+    }
+    Ok(())
+}
+
+pub fn do_remount(target: &str, flags: MountFlags) -> Result<()> {
+    debug!("remount: target: {}, flags: {:?}", target, flags);
+
+    if target == "/" {
+        return_errno!(EPERM, "can not remount rootfs");
+    }
+    let target = to_abs_path(target);
+
+    let root_inode = ROOT_FS.read().unwrap().root_inode();
+    let follow_symlink = !flags.contains(MountFlags::MS_NOSYMFOLLOW);
+    let read_only = flags.contains(MountFlags::MS_RDONLY);
+    remount_fs_at(&root_inode, &target, read_only, follow_symlink)?;
+    Ok(())
+}
+
+pub fn do_move_mount(source: &str, target: &str, flags: MountFlags) -> Result<()> {
+    debug!(
+        "move mount: source: {}, target: {}, flags: {:?}",
+        source, target, flags
+    );
+
+    if source == "/" || target == "/" {
+        return_errno!(EINVAL, "can not move the rootfs or move onto root");
+    }
+    let source = to_abs_path(source);
+    let target = to_abs_path(target);
+
+    let rootfs = ROOT_FS.write().unwrap();
+    SEFS_MANAGER.sync_all()?;
+    let root_inode = rootfs.root_inode();
+    let follow_symlink = !flags.contains(MountFlags::MS_NOSYMFOLLOW);
+    move_mount(&root_inode, &source, &target, follow_symlink)?;
     Ok(())
 }
 
@@ -167,12 +261,8 @@ pub fn do_umount(target: &str, flags: UmountFlags) -> Result<()> {
 
     let target = if target == "/" {
         return_errno!(EPERM, "cannot umount rootfs");
-    } else if target.len() > 0 && target.as_bytes()[0] == b'/' {
-        target.to_owned()
     } else {
-        let thread = current!();
-        let fs = thread.fs().read().unwrap();
-        fs.convert_to_abs_path(target)
+        to_abs_path(target)
     };
 
     let mut rootfs = ROOT_FS.write().unwrap();
@@ -185,6 +275,18 @@ pub fn do_umount(target: &str, flags: UmountFlags) -> Result<()> {
     Ok(())
 }
 
+/// Convert the path to be absolute from the root of the rootfs
+fn to_abs_path(path: &str) -> String {
+    let thread = current!();
+    let fs = thread.fs().read().unwrap();
+    let abs_path = if path.len() > 0 && path.as_bytes()[0] == b'/' {
+        path.to_owned()
+    } else {
+        fs.convert_to_abs_path(path)
+    };
+    fs.convert_to_global_path(&abs_path)
+}
+
 bitflags! {
     pub struct MountFlags: u32 {
         const MS_RDONLY = 1;
@@ -221,13 +323,22 @@ bitflags! {
     }
 }
 
+impl MountFlags {
+    /// Whether the flags only change the propagation type of a mount
+    pub fn is_propagation_change(&self) -> bool {
+        self.intersects(Self::MS_SHARED | Self::MS_PRIVATE | Self::MS_SLAVE | Self::MS_UNBINDABLE)
+    }
+}
+
 #[derive(Debug)]
 pub enum MountOptions {
     UnionFS(UnionFSMountOptions),
     SEFS(SEFSMountOptions),
-    HostFS(PathBuf),
+    HostFS(HostFSMountOptions),
     RamFS,
-    Ext2,
+    Ext2(Ext2MountOptions),
+    ProcFS,
+    DevFS,
 }
 
 impl MountOptions {
@@ -252,21 +363,28 @@ impl MountOptions {
                 Self::UnionFS(unionfs_mount_options)
             }
             ConfigMountFsType::TYPE_HOSTFS => {
-                let options = from_user::clone_cstring_safely(options)?
-                    .to_string_lossy()
-                    .into_owned();
-                let dir = {
-                    let options: Vec<&str> = options.split(",").collect();
-                    let dir = options
-                        .iter()
-                        .find_map(|s| s.strip_prefix("dir="))
-                        .ok_or_else(|| errno!(EINVAL, "no dir options"))?;
-                    PathBuf::from(dir)
+                let hostfs_mount_options = {
+                    let options = from_user::clone_cstring_safely(options)?
+                        .to_string_lossy()
+                        .into_owned();
+                    HostFSMountOptions::from_input(options.as_str())?
                 };
-                Self::HostFS(dir)
+                Self::HostFS(hostfs_mount_options)
             }
             ConfigMountFsType::TYPE_RAMFS => Self::RamFS,
-            ConfigMountFsType::TYPE_EXT2 => Self::Ext2,
+            ConfigMountFsType::TYPE_EXT2 => {
+                let ext2_mount_options = if options.is_null() {
+                    Ext2MountOptions::from_input("")?
+                } else {
+                    let options = from_user::clone_cstring_safely(options)?
+                        .to_string_lossy()
+                        .into_owned();
+                    Ext2MountOptions::from_input(options.as_str())?
+                };
+                Self::Ext2(ext2_mount_options)
+            }
+            ConfigMountFsType::TYPE_PROCFS => Self::ProcFS,
+            ConfigMountFsType::TYPE_DEVFS => Self::DevFS,
             _ => {
                 return_errno!(EINVAL, "unsupported fs type");
             }
@@ -338,6 +456,82 @@ impl SEFSMountOptions {
     }
 }
 
+#[derive(Debug)]
+pub struct HostFSMountOptions {
+    dir: PathBuf,
+    protected: bool,
+    key: Option<sgx_key_128bit_t>,
+    mac: Option<sgx_aes_gcm_128bit_tag_t>,
+}
+
+impl HostFSMountOptions {
+    pub fn from_input(input: &str) -> Result<Self> {
+        let options: Vec<&str> = input.split(",").collect();
+
+        let dir = options
+            .iter()
+            .find_map(|s| s.strip_prefix("dir="))
+            .ok_or_else(|| errno!(EINVAL, "no dir options"))?;
+        let protected = options.iter().any(|s| *s == "protected");
+        let key = match options.iter().find_map(|s| s.strip_prefix("key=")) {
+            Some(key_str) => Some(parse_key(key_str)?),
+            None => None,
+        };
+        let mac = match options.iter().find_map(|s| s.strip_prefix("mac=")) {
+            Some(mac_str) => Some(parse_mac(mac_str)?),
+            None => None,
+        };
+        if !protected && (key.is_some() || mac.is_some()) {
+            return_errno!(EINVAL, "key and mac options are only for protected HostFS");
+        }
+
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            protected,
+            key,
+            mac,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Ext2MountOptions {
+    disk: Option<String>,
+    dir: Option<PathBuf>,
+    disk_size: Option<u64>,
+    key: Option<sgx_key_128bit_t>,
+}
+
+impl Ext2MountOptions {
+    pub fn from_input(input: &str) -> Result<Self> {
+        let options: Vec<&str> = input.split(",").collect();
+
+        let disk = options
+            .iter()
+            .find_map(|s| s.strip_prefix("disk="))
+            .map(|s| s.to_owned());
+        let dir = options
+            .iter()
+            .find_map(|s| s.strip_prefix("dir="))
+            .map(PathBuf::from);
+        let disk_size = match options.iter().find_map(|s| s.strip_prefix("disk_size=")) {
+            Some(size_str) => Some(parse_memory_size(size_str)? as u64),
+            None => None,
+        };
+        let key = match options.iter().find_map(|s| s.strip_prefix("key=")) {
+            Some(key_str) => Some(parse_key(key_str)?),
+            None => None,
+        };
+
+        Ok(Self {
+            disk,
+            dir,
+            disk_size,
+            key,
+        })
+    }
+}
+
 bitflags! {
     pub struct UmountFlags: u32 {
         const MNT_FORCE = 1;
@@ -0,0 +1,54 @@
+use super::rootfs::{bind_mount_at, is_mount_point};
+use super::*;
+
+/// Change the root directory of the current process to `new_root`, and make
+/// the old root directory accessible at `put_old`.
+///
+/// Since there is only one mount namespace, the root of the other processes
+/// is not changed. The old root is bind mounted at `put_old` instead of being
+/// moved, so it can be detached by `umount2(put_old, MNT_DETACH)` as usual.
+pub fn do_pivot_root(new_root: &str, put_old: &str) -> Result<()> {
+    debug!(
+        "pivot_root: new_root: {:?}, put_old: {:?}",
+        new_root, put_old
+    );
+
+    let current = current!();
+    // Like CAP_SYS_ADMIN on Linux, only root is allowed to pivot_root
+    if current.process().credentials().euid() != 0 {
+        return_errno!(EPERM, "only root can change the root directory");
+    }
+    let mut fs = current.fs().write().unwrap();
+
+    let new_root_inode = fs.lookup_inode(new_root)?;
+    let put_old_inode = fs.lookup_inode(put_old)?;
+    if new_root_inode.metadata()?.type_ != FileType::Dir
+        || put_old_inode.metadata()?.type_ != FileType::Dir
+    {
+        return_errno!(ENOTDIR, "new_root or put_old is not a directory");
+    }
+    if !is_mount_point(&new_root_inode)? {
+        return_errno!(EINVAL, "new_root is not a mount point");
+    }
+
+    let global_new_root = fs.convert_to_global_path(&fs.convert_to_abs_path(new_root));
+    let global_put_old = fs.convert_to_global_path(&fs.convert_to_abs_path(put_old));
+    let global_new_root = global_new_root.trim_end_matches('/');
+    let global_put_old = global_put_old.trim_end_matches('/');
+    if global_new_root.is_empty() || global_new_root == fs.root().trim_end_matches('/') {
+        return_errno!(EBUSY, "new_root is the current root");
+    }
+    let is_underneath = match global_put_old.strip_prefix(global_new_root) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    };
+    if !is_underneath {
+        return_errno!(EINVAL, "put_old is not underneath new_root");
+    }
+
+    let old_root_inode = fs.lookup_inode("/")?;
+    let root_inode = ROOT_FS.read().unwrap().root_inode();
+    bind_mount_at(old_root_inode, &root_inode, global_put_old, true)?;
+    fs.set_root(new_root)?;
+    Ok(())
+}
@@ -1,17 +1,23 @@
 /// Present a per-process view of FS.
 use super::*;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct FsView {
     root: String,
     cwd: String,
+    /// The inode of the root directory if it is not the root of the rootfs
+    root_inode: Option<Arc<dyn INode>>,
 }
 
 impl FsView {
     pub fn new() -> FsView {
         let root = String::from("/");
         let cwd = root.clone();
-        Self { root, cwd }
+        Self {
+            root,
+            cwd,
+            root_inode: None,
+        }
     }
 
     /// Get the root directory
@@ -24,6 +30,66 @@ impl FsView {
         &self.cwd
     }
 
+    /// Change the root directory to the directory of `path`.
+    ///
+    /// The current working directory is kept if it is under the new root,
+    /// otherwise it is moved to the new root.
+    pub fn set_root(&mut self, path: &str) -> Result<()> {
+        let inode = self.lookup_inode(path)?;
+        if inode.metadata()?.type_ != FileType::Dir {
+            return_errno!(ENOTDIR, "root must be directory");
+        }
+        let abs_path = self.convert_to_abs_path(path);
+        let global_root = self.convert_to_global_path(&abs_path);
+        if global_root == "/" {
+            // The root of the rootfs, which may be replaced by pivot_root
+            self.root_inode = None;
+        } else {
+            self.root_inode = Some(inode);
+        }
+
+        let global_cwd = self.convert_to_global_path(&self.cwd);
+        self.cwd = match global_cwd.strip_prefix(global_root.trim_end_matches('/')) {
+            Some(cwd) if cwd.is_empty() => String::from("/"),
+            Some(cwd) if cwd.starts_with('/') => cwd.to_owned(),
+            _ => String::from("/"),
+        };
+        self.root = global_root;
+        Ok(())
+    }
+
+    /// Convert the absolute path seen by the process to the path from the
+    /// root of the rootfs, which is used to operate on the mounts.
+    ///
+    /// The ".." components can not escape the root directory.
+    pub fn convert_to_global_path(&self, abs_path: &str) -> String {
+        if self.root_inode.is_none() {
+            return abs_path.to_owned();
+        }
+        let mut components: Vec<&str> = Vec::new();
+        for component in self.root.split('/').chain(abs_path.split('/')) {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    // Only pop the components under the root directory
+                    if components.len() > self.root_depth() {
+                        components.pop();
+                    }
+                }
+                _ => components.push(component),
+            }
+        }
+        let mut global_path = String::from("/") + &components.join("/");
+        if abs_path.ends_with('/') && !global_path.ends_with('/') {
+            global_path += "/";
+        }
+        global_path
+    }
+
+    fn root_depth(&self) -> usize {
+        self.root.split('/').filter(|c| !c.is_empty()).count()
+    }
+
     /// Set the current working directory.
     pub fn set_cwd(&mut self, path: &str) -> Result<()> {
         if path.len() == 0 {
@@ -46,7 +112,7 @@ impl FsView {
     /// Open a file on the process. But DO NOT add it to file table.
     pub fn open_file(&self, path: &str, flags: u32, mode: FileMode) -> Result<Arc<dyn File>> {
         let creation_flags = CreationFlags::from_bits_truncate(flags);
-        let inode = if creation_flags.no_follow_symlink() {
+        let (inode, is_created) = if creation_flags.no_follow_symlink() {
             match self.lookup_inode_no_follow(path) {
                 Ok(inode) => {
                     let status_flags = StatusFlags::from_bits_truncate(flags);
@@ -65,7 +131,7 @@ impl FsView {
                             "O_DIRECTORY is specified but file is not a directory"
                         );
                     }
-                    inode
+                    (inode, false)
                 }
                 Err(e) if e.errno() == ENOENT && creation_flags.can_create() => {
                     if creation_flags.must_be_directory() {
@@ -76,10 +142,13 @@ impl FsView {
                     }
                     let (dir_path, file_name) = split_path(&path);
                     let dir_inode = self.lookup_inode(dir_path)?;
-                    if !dir_inode.allow_write()? {
-                        return_errno!(EPERM, "file cannot be created");
+                    if !dir_inode.allow_modify_entries()? {
+                        return_errno!(EACCES, "file cannot be created");
                     }
-                    dir_inode.create(file_name, FileType::File, mode.bits())?
+                    dir_inode.check_writable_mount()?;
+                    let inode = dir_inode.create(file_name, FileType::File, mode.bits())?;
+                    inode.set_owner_to_current()?;
+                    (inode, true)
                 }
                 Err(e) => return Err(e),
             }
@@ -97,7 +166,7 @@ impl FsView {
                             "O_DIRECTORY is specified but file is not a directory"
                         );
                     }
-                    inode
+                    (inode, false)
                 }
                 Err(e) if e.errno() == ENOENT && creation_flags.can_create() => {
                     if creation_flags.must_be_directory() {
@@ -112,16 +181,24 @@ impl FsView {
                         return_errno!(EISDIR, "path refers to a directory");
                     }
                     let dir_inode = self.lookup_inode(dir_path)?;
-                    if !dir_inode.allow_write()? {
-                        return_errno!(EPERM, "file cannot be created");
+                    if !dir_inode.allow_modify_entries()? {
+                        return_errno!(EACCES, "file cannot be created");
                     }
-                    dir_inode.create(file_name, FileType::File, mode.bits())?
+                    dir_inode.check_writable_mount()?;
+                    let inode = dir_inode.create(file_name, FileType::File, mode.bits())?;
+                    inode.set_owner_to_current()?;
+                    (inode, true)
                 }
                 Err(e) => return Err(e),
             }
         };
         let abs_path = self.convert_to_abs_path(&path);
-        Ok(Arc::new(INodeFile::open(inode, &abs_path, flags)?))
+        let file = if is_created {
+            INodeFile::open_created(inode, &abs_path, flags)?
+        } else {
+            INodeFile::open(inode, &abs_path, flags)?
+        };
+        Ok(Arc::new(file))
     }
 
     /// Recursively lookup the real path of giving path, dereference symlinks
@@ -168,41 +245,50 @@ impl FsView {
             self.cwd(),
             path
         );
-        let (dir_path, file_name) = split_path(&path);
-        let inode = if file_name.ends_with("/") {
-            self.lookup_inode(path)?
-        } else {
-            let dir_inode = self.lookup_inode(dir_path)?;
-            dir_inode.lookup(file_name)?
-        };
-        Ok(inode)
+        self.lookup_inode_in_root(&self.root_dir_inode(), path, false)
     }
 
     /// Lookup INode from the cwd of the process, dereference symlink
     pub fn lookup_inode(&self, path: &str) -> Result<Arc<dyn INode>> {
         debug!("lookup_inode: cwd: {:?}, path: {:?}", self.cwd(), path);
-        if path.len() > 0 && path.as_bytes()[0] == b'/' {
-            // absolute path
-            let abs_path = path.trim_start_matches('/');
-            let inode = ROOT_FS
-                .read()
-                .unwrap()
-                .root_inode()
-                .lookup_follow(abs_path, MAX_SYMLINKS)?;
-            Ok(inode)
-        } else {
-            // relative path
-            let cwd = self.cwd().trim_start_matches('/');
-            let inode = ROOT_FS
-                .read()
-                .unwrap()
-                .root_inode()
-                .lookup_follow(cwd, MAX_SYMLINKS)?
-                .lookup_follow(path, MAX_SYMLINKS)?;
-            Ok(inode)
+        self.lookup_inode_in_root(&self.root_dir_inode(), path, true)
+    }
+
+    /// The root directory of the process, which is the root of the rootfs
+    /// unless it is changed by chroot.
+    fn root_dir_inode(&self) -> Arc<dyn INode> {
+        match &self.root_inode {
+            Some(root_inode) => root_inode.clone(),
+            None => ROOT_FS.read().unwrap().root_inode(),
         }
     }
 
+    /// Lookup INode from the cwd of the process without escaping the root
+    /// directory by ".." or symlinks.
+    fn lookup_inode_in_root(
+        &self,
+        root_inode: &Arc<dyn INode>,
+        path: &str,
+        follow_last: bool,
+    ) -> Result<Arc<dyn INode>> {
+        let start_inode = if path.len() > 0 && path.as_bytes()[0] == b'/' {
+            root_inode.clone()
+        } else {
+            self.lookup_inode_in_root(root_inode, self.cwd(), true)?
+        };
+        let mut follow_times = 0;
+        // Like Linux, root can search any directory
+        let check_search = current!().process().credentials().fsuid() != 0;
+        walk_in_root(
+            root_inode,
+            start_inode,
+            path,
+            follow_last,
+            check_search,
+            &mut follow_times,
+        )
+    }
+
     /// Convert the path to be absolute
     pub fn convert_to_abs_path(&self, path: &str) -> String {
         debug!(
@@ -227,8 +313,83 @@ impl FsView {
 
 impl Default for FsView {
     fn default() -> Self {
-        let root = String::from("/");
-        let cwd = root.clone();
-        Self { root, cwd }
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FsView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FsView")
+            .field("root", &self.root)
+            .field("cwd", &self.cwd)
+            .finish()
     }
 }
+
+/// Walk through `path` from `dir_inode`, the ".." of `root_inode` is itself and
+/// the absolute symlinks are resolved from `root_inode`.
+fn walk_in_root(
+    root_inode: &Arc<dyn INode>,
+    dir_inode: Arc<dyn INode>,
+    path: &str,
+    follow_last: bool,
+    check_search: bool,
+    follow_times: &mut usize,
+) -> Result<Arc<dyn INode>> {
+    let must_be_dir = path.ends_with('/');
+    let components: Vec<&str> = path
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect();
+    let mut inode = dir_inode;
+    for (idx, name) in components.iter().enumerate() {
+        if *name == ".." && is_same_inode(&inode, root_inode)? {
+            continue;
+        }
+        if check_search && !inode.allow_search()? {
+            return_errno!(EACCES, "search permission is denied");
+        }
+        let next_inode = inode.find(name)?;
+        let is_last = idx == components.len() - 1;
+        if next_inode.metadata()?.type_ == FileType::SymLink
+            && (!is_last || follow_last || must_be_dir)
+        {
+            *follow_times += 1;
+            if *follow_times > MAX_SYMLINKS {
+                return_errno!(ELOOP, "too many symlinks");
+            }
+            let link_path = {
+                let mut content = vec![0u8; PATH_MAX];
+                let len = next_inode.read_at(0, &mut content)?;
+                std::str::from_utf8(&content[..len])
+                    .map_err(|_| errno!(ENOENT, "invalid symlink content"))?
+                    .to_owned()
+            };
+            let base_inode = if link_path.starts_with('/') {
+                root_inode.clone()
+            } else {
+                inode.clone()
+            };
+            inode = walk_in_root(
+                root_inode,
+                base_inode,
+                &link_path,
+                true,
+                check_search,
+                follow_times,
+            )?;
+        } else {
+            inode = next_inode;
+        }
+    }
+    if must_be_dir && inode.metadata()?.type_ != FileType::Dir {
+        return_errno!(ENOTDIR, "not a directory");
+    }
+    Ok(inode)
+}
+
+fn is_same_inode(lhs: &Arc<dyn INode>, rhs: &Arc<dyn INode>) -> Result<bool> {
+    let lhs_fs = Arc::as_ptr(&lhs.fs()) as *const u8;
+    let rhs_fs = Arc::as_ptr(&rhs.fs()) as *const u8;
+    Ok(lhs_fs == rhs_fs && lhs.metadata()?.inode == rhs.metadata()?.inode)
+}
@@ -1,10 +1,12 @@
 use crate::fs::fs_ops::fetch_host_statfs;
+use crate::io_uring::FILE_IO_URING;
 use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use core::any::Any;
 use rcore_fs::vfs::*;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::os::unix::fs::{DirEntryExt, FileExt, FileTypeExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::sync::{SgxMutex as Mutex, SgxMutexGuard as MutexGuard};
 use std::untrusted::fs;
@@ -70,6 +72,11 @@ impl HostFS {
     }
 }
 
+/// The minimal length of the I/O that goes through io_uring.
+///
+/// Smaller I/O is done by synchronous ocalls, which have lower latency.
+const URING_IO_MIN_LEN: usize = 16 * 1024;
+
 // workaround for unable to `impl From<std::io::Error> for FsError`
 macro_rules! try_std {
     ($ret: expr) => {
@@ -84,6 +91,14 @@ impl INode for HNode {
         }
         let mut guard = self.open_file()?;
         let file = guard.as_mut().unwrap();
+        if buf.len() >= URING_IO_MIN_LEN {
+            if let Some(file_io_uring) = FILE_IO_URING.as_ref() {
+                let host_fd = file.as_raw_fd() as FileDesc;
+                if let Some(ret) = file_io_uring.read_at(host_fd, buf, offset) {
+                    return ret.map_err(|e| e.into_fs_error());
+                }
+            }
+        }
         let len = try_std!(file.read_at(buf, offset as u64));
         Ok(len)
     }
@@ -94,6 +109,14 @@ impl INode for HNode {
         }
         let mut guard = self.open_file()?;
         let file = guard.as_mut().unwrap();
+        if buf.len() >= URING_IO_MIN_LEN {
+            if let Some(file_io_uring) = FILE_IO_URING.as_ref() {
+                let host_fd = file.as_raw_fd() as FileDesc;
+                if let Some(ret) = file_io_uring.write_at(host_fd, buf, offset) {
+                    return ret.map_err(|e| e.into_fs_error());
+                }
+            }
+        }
         let len = try_std!(file.write_at(buf, offset as u64));
         Ok(len)
     }
@@ -123,6 +146,12 @@ impl INode for HNode {
         if self.is_file() {
             let guard = self.open_file()?;
             let file = guard.as_ref().unwrap();
+            if let Some(file_io_uring) = FILE_IO_URING.as_ref() {
+                let host_fd = file.as_raw_fd() as FileDesc;
+                return file_io_uring
+                    .fsync(host_fd, false)
+                    .map_err(|e| e.into_fs_error());
+            }
             try_std!(file.sync_all());
         } else {
             warn!("no sync_all method about dir, do nothing");
@@ -134,6 +163,12 @@ impl INode for HNode {
         if self.is_file() {
             let guard = self.open_file()?;
             let file = guard.as_ref().unwrap();
+            if let Some(file_io_uring) = FILE_IO_URING.as_ref() {
+                let host_fd = file.as_raw_fd() as FileDesc;
+                return file_io_uring
+                    .fsync(host_fd, true)
+                    .map_err(|e| e.into_fs_error());
+            }
             try_std!(file.sync_data());
         } else {
             warn!("no sync_data method about dir, do nothing");
@@ -346,6 +381,20 @@ impl IntoFsError for std::io::Error {
     }
 }
 
+impl IntoFsError for crate::error::Error {
+    fn into_fs_error(self) -> FsError {
+        use crate::error::Errno::*;
+        match self.errno() {
+            ENOENT => FsError::EntryNotFound,
+            EEXIST => FsError::EntryExist,
+            EAGAIN => FsError::Again,
+            EINVAL => FsError::InvalidParam,
+            EPERM | EACCES => FsError::PermError,
+            errno => FsError::DeviceError(errno as i32),
+        }
+    }
+}
+
 trait IntoFsFileType {
     fn into_fs_filetype(self) -> FileType;
 }
@@ -1,7 +1,9 @@
+use super::file_ops::AccessibilityCheckMode;
+use super::rootfs::{mount_flags_of, MountFlags};
 use super::*;
 use crate::fs::IoctlCmd;
 use crate::net::PollEventFlags;
-use crate::process::do_getuid;
+use crate::process::gid_t;
 use rcore_fs::vfs::FallocateMode;
 use rcore_fs_sefs::dev::SefsMac;
 
@@ -11,6 +13,8 @@ pub struct INodeFile {
     offset: SgxMutex<usize>,
     access_mode: AccessMode,
     status_flags: RwLock<StatusFlags>,
+    /// The flags of the mount which the file is opened on, `None` for the root FS
+    mount_flags: Option<Arc<MountFlags>>,
 }
 
 impl File for INodeFile {
@@ -28,6 +32,7 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        self.check_writable_mount()?;
         let mut offset = self.offset.lock().unwrap();
         if self.status_flags.read().unwrap().always_append() {
             let info = self.inode.metadata()?;
@@ -50,6 +55,7 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        self.check_writable_mount()?;
         let len = self.inode.write_at(offset, buf)?;
         Ok(len)
     }
@@ -77,6 +83,7 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        self.check_writable_mount()?;
         let mut offset = self.offset.lock().unwrap();
         if self.status_flags.read().unwrap().always_append() {
             let info = self.inode.metadata()?;
@@ -137,6 +144,7 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        self.check_writable_mount()?;
         let mut offset = offset;
         let mut total_len = 0;
         for buf in bufs {
@@ -163,6 +171,7 @@ impl File for INodeFile {
     }
 
     fn set_metadata(&self, metadata: &Metadata) -> Result<()> {
+        self.check_writable_mount()?;
         self.inode.set_metadata(metadata)?;
         Ok(())
     }
@@ -171,6 +180,7 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File is not opened for writing");
         }
+        self.check_writable_mount()?;
         let mode = FallocateMode::from(flags);
         self.inode.fallocate(&mode, offset, len)?;
         Ok(())
@@ -180,6 +190,7 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable. Can't set len.");
         }
+        self.check_writable_mount()?;
         self.inode.resize(len as usize)?;
         Ok(())
     }
@@ -314,31 +325,64 @@ impl INodeFile {
         if (access_mode.writable() && !inode.allow_write()?) {
             return_errno!(EACCES, "File not writable");
         }
+        Self::do_open(inode, abs_path, flags)
+    }
+
+    /// Open a file just created by the current process.
+    ///
+    /// Like Linux, the mode of the new file is not checked for the creator, so
+    /// that creating a file with a restrictive mode, e.g., 0o444, can succeed.
+    pub fn open_created(inode: Arc<dyn INode>, abs_path: &str, flags: u32) -> Result<Self> {
+        Self::do_open(inode, abs_path, flags)
+    }
+
+    fn do_open(inode: Arc<dyn INode>, abs_path: &str, flags: u32) -> Result<Self> {
+        let access_mode = AccessMode::from_u32(flags)?;
         if access_mode.writable() && inode.metadata()?.type_ == FileType::Dir {
             return_errno!(EISDIR, "Directory cannot be open to write");
         }
-        let creation_flags = CreationFlags::from_bits_truncate(flags);
-        if creation_flags.should_truncate()
-            && inode.metadata()?.type_ == FileType::File
-            && access_mode.writable()
-        {
-            // truncate the length to 0
-            inode.resize(0)?;
+        let mount_flags = mount_flags_of(&inode.fs());
+        if access_mode.writable() {
+            // The mount cannot be remounted read-only until the file is closed
+            if let Some(mount_flags) = &mount_flags {
+                mount_flags.get_write()?;
+            }
         }
         let status_flags = StatusFlags::from_bits_truncate(flags);
-        Ok(INodeFile {
+        let file = INodeFile {
             inode,
             abs_path: abs_path.to_owned(),
             offset: SgxMutex::new(0),
             access_mode,
             status_flags: RwLock::new(status_flags),
-        })
+            mount_flags,
+        };
+        let creation_flags = CreationFlags::from_bits_truncate(flags);
+        if creation_flags.should_truncate()
+            && file.inode.metadata()?.type_ == FileType::File
+            && access_mode.writable()
+        {
+            // truncate the length to 0
+            file.inode.resize(0)?;
+        }
+        Ok(file)
     }
 
     pub fn abs_path(&self) -> &str {
         &self.abs_path
     }
 
+    /// Check the mount is writable, which may be remounted read-only after
+    /// the file is opened.
+    pub fn check_writable_mount(&self) -> Result<()> {
+        if let Some(mount_flags) = &self.mount_flags {
+            if mount_flags.is_read_only() {
+                return_errno!(EROFS, "the file system is mounted read-only");
+            }
+        }
+        Ok(())
+    }
+
     fn check_advisory_lock_with_access_mode(&self, lock: &RangeLock) -> Result<()> {
         match lock.type_() {
             RangeLockType::F_RDLCK => {
@@ -373,6 +417,25 @@ impl INodeFile {
         range_lock_list.unlock(lock)
     }
 
+    fn release_ofd_locks(&self) {
+        let ext = match self.inode.ext() {
+            Some(ext) => ext,
+            None => {
+                return;
+            }
+        };
+        let range_lock_list = match ext.get::<RangeLockList>() {
+            Some(list) => list,
+            None => {
+                return;
+            }
+        };
+
+        // The OFD locks owned by this file are released once the last reference
+        // to this open file description is dropped.
+        range_lock_list.release_dead_ofd_locks()
+    }
+
     pub fn set_flock(&self, lock: Flock, is_nonblocking: bool) -> Result<()> {
         let ext = match self.inode.ext() {
             Some(ext) => ext,
@@ -410,7 +473,13 @@ impl INodeFile {
 
 impl Drop for INodeFile {
     fn drop(&mut self) {
-        self.unlock_flock()
+        self.unlock_flock();
+        self.release_ofd_locks();
+        if self.access_mode.writable() {
+            if let Some(mount_flags) = &self.mount_flags {
+                mount_flags.put_write();
+            }
+        }
     }
 }
 
@@ -431,6 +500,16 @@ pub trait INodeExt {
     fn read_as_vec(&self) -> Result<Vec<u8>>;
     fn allow_write(&self) -> Result<bool>;
     fn allow_read(&self) -> Result<bool>;
+    fn allow_access(&self, mode: AccessibilityCheckMode) -> Result<bool>;
+    /// Whether entries can be added to or removed from the directory, which
+    /// needs both the write and search permissions
+    fn allow_modify_entries(&self) -> Result<bool>;
+    /// Whether the path walk can pass through the inode. It is always true for
+    /// non-directories, so that the walk fails with ENOTDIR later.
+    fn allow_search(&self) -> Result<bool>;
+    /// Set the owner of a newly-created inode to the current fsuid and fsgid
+    fn set_owner_to_current(&self) -> Result<()>;
+    fn check_writable_mount(&self) -> Result<()>;
 }
 
 impl INodeExt for dyn INode {
@@ -445,26 +524,67 @@ impl INodeExt for dyn INode {
     }
 
     fn allow_write(&self) -> Result<bool> {
-        // TODO: Since Occlum does not support the capability,
-        //       just skip the permission check if uid is root.
-        if do_getuid().unwrap() == 0 {
-            return Ok(true);
-        }
-
-        let info = self.metadata()?;
-        let file_mode = FileMode::from_bits_truncate(info.mode);
-        Ok(file_mode.is_writable())
+        self.allow_access(AccessibilityCheckMode::W_OK)
     }
 
     fn allow_read(&self) -> Result<bool> {
-        // TODO: See the comments in allow_write
-        if do_getuid().unwrap() == 0 {
+        self.allow_access(AccessibilityCheckMode::R_OK)
+    }
+
+    fn allow_modify_entries(&self) -> Result<bool> {
+        self.allow_access(AccessibilityCheckMode::W_OK | AccessibilityCheckMode::X_OK)
+    }
+
+    fn allow_search(&self) -> Result<bool> {
+        if self.metadata()?.type_ != FileType::Dir {
             return Ok(true);
         }
+        self.allow_access(AccessibilityCheckMode::X_OK)
+    }
 
+    fn allow_access(&self, mode: AccessibilityCheckMode) -> Result<bool> {
+        let current = current!();
+        let credentials = current.process().credentials();
         let info = self.metadata()?;
-        let file_mode = FileMode::from_bits_truncate(info.mode);
-        Ok(file_mode.is_readable())
+        // TODO: Since Occlum does not support the capability, just skip the
+        //       permission check if the fsuid is root. Like Linux, the execute
+        //       permission is granted only if any execute bit is set.
+        if credentials.fsuid() == 0 {
+            return Ok(!mode.contains(AccessibilityCheckMode::X_OK)
+                || info.type_ == FileType::Dir
+                || info.mode & 0o111 != 0);
+        }
+
+        let granted_bits = if info.uid as uid_t == credentials.fsuid() {
+            info.mode >> 6
+        } else if credentials.in_group(info.gid as gid_t) {
+            info.mode >> 3
+        } else {
+            info.mode
+        } & 0o7;
+        Ok(AccessibilityCheckMode::from_bits_truncate(granted_bits as u32).contains(mode))
+    }
+
+    fn set_owner_to_current(&self) -> Result<()> {
+        let current = current!();
+        let credentials = current.process().credentials();
+        let mut info = self.metadata()?;
+        if info.uid as uid_t == credentials.fsuid() && info.gid as gid_t == credentials.fsgid() {
+            return Ok(());
+        }
+        info.uid = credentials.fsuid() as usize;
+        info.gid = credentials.fsgid() as usize;
+        self.set_metadata(&info)?;
+        Ok(())
+    }
+
+    fn check_writable_mount(&self) -> Result<()> {
+        if let Some(mount_flags) = mount_flags_of(&self.fs()) {
+            if mount_flags.is_read_only() {
+                return_errno!(EROFS, "the file system is mounted read-only");
+            }
+        }
+        Ok(())
     }
 }
 
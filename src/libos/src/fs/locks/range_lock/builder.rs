@@ -5,7 +5,7 @@ pub struct RangeLockBuilder {
     type_: Option<RangeLockType>,
     range: Option<FileRange>,
     // Optional fields
-    owner: Option<pid_t>,
+    owner: Option<RangeLockOwner>,
     waiters: Option<WaiterQueue>,
 }
 
@@ -19,7 +19,7 @@ impl RangeLockBuilder {
         }
     }
 
-    pub fn owner(mut self, owner: pid_t) -> Self {
+    pub fn owner(mut self, owner: RangeLockOwner) -> Self {
         self.owner = Some(owner);
         self
     }
@@ -40,7 +40,9 @@ impl RangeLockBuilder {
     }
 
     pub fn build(self) -> Result<RangeLock> {
-        let owner = self.owner.unwrap_or_else(|| current!().process().pid());
+        let owner = self
+            .owner
+            .unwrap_or_else(|| RangeLockOwner::Process(current!().process().pid()));
         let type_ = self
             .type_
             .ok_or_else(|| errno!(EINVAL, "type_ is mandatory"))?;
@@ -1,9 +1,10 @@
-/// File POSIX advisory range locks
+/// File POSIX advisory range locks and open file description (OFD) locks
 use super::*;
 use crate::events::{Waiter, WaiterQueue};
 use crate::util::sync::rw_lock::RwLockWriteGuard;
 use process::pid_t;
 use rcore_fs::vfs::AnyExt;
+use std::sync::Weak;
 
 pub use self::builder::RangeLockBuilder;
 pub use self::range::{FileRange, OverlapWith, OFFSET_MAX};
@@ -39,15 +40,66 @@ impl c_flock {
             } else {
                 lock.range.len() as off_t
             };
-            self.l_pid = lock.owner;
+            // OFD locks are not owned by any process
+            self.l_pid = match lock.owner {
+                RangeLockOwner::Process(pid) => pid,
+                RangeLockOwner::OpenFile(_) => -1i32 as pid_t,
+            };
+        }
+    }
+}
+
+/// Owner of a file range lock
+#[derive(Clone)]
+pub enum RangeLockOwner {
+    /// Classic POSIX record lock, owned by the process holding the lock
+    Process(pid_t),
+    /// OFD lock, owned by the open file description holding the lock
+    OpenFile(Weak<dyn File>),
+}
+
+impl RangeLockOwner {
+    pub fn new_open_file(file: &FileRef) -> Self {
+        Self::OpenFile(Arc::downgrade(file))
+    }
+
+    pub fn is_ofd(&self) -> bool {
+        matches!(self, Self::OpenFile(_))
+    }
+
+    /// Whether the owner is an open file description that has been closed
+    pub fn is_dead(&self) -> bool {
+        match self {
+            Self::Process(_) => false,
+            Self::OpenFile(file) => file.strong_count() == 0,
+        }
+    }
+}
+
+impl PartialEq for RangeLockOwner {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Process(pid), Self::Process(other_pid)) => pid == other_pid,
+            (Self::OpenFile(file), Self::OpenFile(other_file)) => file.ptr_eq(other_file),
+            // OFD locks and classic POSIX locks always conflict with each other
+            _ => false,
+        }
+    }
+}
+
+impl Debug for RangeLockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Process(pid) => f.debug_tuple("Process").field(pid).finish(),
+            Self::OpenFile(file) => f.debug_tuple("OpenFile").field(&file.as_ptr()).finish(),
         }
     }
 }
 
 /// Kernel representation of file range lock
 pub struct RangeLock {
-    /// Owner of lock, process holding the lock
-    owner: pid_t,
+    /// Owner of lock, process or open file description holding the lock
+    owner: RangeLockOwner,
     /// Type of lock, F_RDLCK, F_WRLCK, or F_UNLCK
     type_: RangeLockType,
     /// Range of lock
@@ -65,12 +117,12 @@ impl RangeLock {
         self.type_ = type_;
     }
 
-    pub fn owner(&self) -> pid_t {
-        self.owner
+    pub fn owner(&self) -> &RangeLockOwner {
+        &self.owner
     }
 
     pub fn conflict_with(&self, other: &Self) -> bool {
-        // locks owned by the same process do not conflict
+        // locks owned by the same process or open file description do not conflict
         if self.owner == other.owner {
             return false;
         }
@@ -160,7 +212,7 @@ impl Clone for RangeLock {
 /// List of File POSIX advisory range locks.
 ///
 /// Rule of ordering:
-/// Locks are sorted by owner process, then by the starting offset.
+/// Locks are sorted by owner (process or open file description), then by the starting offset.
 ///
 /// Rule of mergeing:
 /// Adjacent and overlapping locks with same owner and type will be merged.
@@ -186,7 +238,7 @@ impl RangeLockList {
         for existing_lock in list.iter() {
             if lock.conflict_with(existing_lock) {
                 // Return the information about the conflict lock
-                lock.owner = existing_lock.owner;
+                lock.owner = existing_lock.owner.clone();
                 lock.type_ = existing_lock.type_;
                 lock.range = existing_lock.range;
                 return;
@@ -210,7 +262,10 @@ impl RangeLockList {
                 // Start to wait
                 let waiter = Waiter::new();
                 // TODO: Add deadlock detection, and returns EDEADLK
-                warn!("Do not support deadlock detection, maybe wait infinitely");
+                // Note that deadlock detection is never performed for OFD locks.
+                if !lock.owner().is_ofd() {
+                    warn!("Do not support deadlock detection, maybe wait infinitely");
+                }
                 conflict_lock.enqueue_waiter(&waiter);
                 // Ensure that we drop any locks before wait
                 drop(list);
@@ -357,6 +412,14 @@ impl RangeLockList {
             }
         }
     }
+
+    /// Release the OFD locks whose open file descriptions have been closed.
+    ///
+    /// The waiters blocking by the released locks will be woken up.
+    pub fn release_dead_ofd_locks(&self) {
+        let mut list = self.inner.write().unwrap();
+        list.retain(|lock| !lock.owner().is_dead());
+    }
 }
 
 impl Default for RangeLockList {
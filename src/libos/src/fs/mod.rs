@@ -14,13 +14,15 @@ use untrusted::{SliceAsMutPtrAndLen, SliceAsPtrAndLen};
 
 use crate::config::ConfigMount;
 
+pub use self::aio::{io_event, iocb, release_aio_contexts_when_process_exit};
 pub use self::event_file::{AsEvent, EventCreationFlags, EventFile};
 pub use self::events::{AtomicIoEvents, IoEvents, IoNotifier};
 pub use self::file::{File, FileRef};
 pub use self::file_ops::{
     occlum_ocall_ioctl, utimbuf_t, AccessMode, BuiltinIoctlNum, CreationFlags, FallocateFlags,
-    FileMode, GetIfConf, GetIfReqWithRawCmd, GetReadBufLen, GetWinSize, IfConf, IoctlCmd,
-    IoctlRawCmd, NonBuiltinIoctlCmd, SetNonBlocking, SetWinSize, Stat, StatusFlags,
+    FileMode, GetForegroundPgrp, GetIfConf, GetIfReqWithRawCmd, GetReadBufLen, GetTtySid,
+    GetWinSize, IfConf, IoctlCmd, IoctlRawCmd, NonBuiltinIoctlCmd, ReleaseControllingTty,
+    SetControllingTty, SetForegroundPgrp, SetNonBlocking, SetWinSize, Stat, StatusFlags,
     StructuredIoctlArgType, StructuredIoctlNum, TcGets, TcSets, STATUS_FLAGS_MASK,
 };
 pub use self::file_table::{FileDesc, FileTable, FileTableEvent, FileTableNotifier};
@@ -30,14 +32,21 @@ pub use self::host_fd::HostFd;
 pub use self::inode_file::{AsINodeFile, INodeExt, INodeFile};
 pub use self::locks::flock::{Flock, FlockList, FlockOps, FlockType};
 pub use self::locks::range_lock::{
-    FileRange, RangeLock, RangeLockBuilder, RangeLockList, RangeLockType, OFFSET_MAX,
+    FileRange, RangeLock, RangeLockBuilder, RangeLockList, RangeLockOwner, RangeLockType,
+    OFFSET_MAX,
 };
 pub use self::pipe::PipeType;
 pub use self::rootfs::{ROOT_FS, SEFS_MANAGER};
 pub use self::stdio::{HostStdioFds, StdinFile, StdoutFile};
 pub use self::syscalls::*;
 pub use self::timer_file::{AsTimer, TimerCreationFlags, TimerFile};
+pub use self::uring::{
+    check_io_urings_mprotect, detach_io_urings_in_range, io_uring_params,
+    release_io_urings_when_process_exit, AsIoUring,
+};
 
+mod aio;
+mod bindfs;
 pub mod channel;
 mod dev_fs;
 mod event_file;
@@ -53,11 +62,13 @@ mod inode_file;
 mod locks;
 mod pipe;
 mod procfs;
+mod protected_hostfs;
 mod rootfs;
 mod sefs;
 mod stdio;
 mod syscalls;
 mod timer_file;
+mod uring;
 
 /// Split a `path` to (`dir_path`, `file_name`).
 ///
@@ -124,6 +124,11 @@ impl PipeReader {
     fn get_ready_len(&self) -> usize {
         self.consumer.ready_len()
     }
+
+    /// Read like `readv`, but never block even if the pipe is blocking.
+    pub fn try_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        self.consumer.try_pop_slices(bufs)
+    }
 }
 
 pub struct PipeWriter {
@@ -221,6 +226,13 @@ impl fmt::Debug for PipeReader {
     }
 }
 
+impl PipeWriter {
+    /// Write like `writev`, but never block even if the pipe is blocking.
+    pub fn try_writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        self.producer.try_push_slices(bufs)
+    }
+}
+
 impl fmt::Debug for PipeWriter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("PipeWriter")
@@ -0,0 +1,45 @@
+use super::*;
+use crate::io_uring::{ENABLE_URING, MULTITON};
+use atomic::Ordering;
+use std::fmt::Write;
+
+/// It returns the statistics of the io_uring instances used by the sockets.
+pub struct IoUringStatINode;
+
+impl IoUringStatINode {
+    pub fn new() -> Arc<dyn INode> {
+        Arc::new(File::new(Self))
+    }
+}
+
+impl ProcINode for IoUringStatINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let mut stat = String::new();
+        if !ENABLE_URING.load(Ordering::Relaxed) {
+            return Ok(stat.into_bytes());
+        }
+
+        writeln!(
+            stat,
+            "{:<4} {:<6} {:<6} {:>8} {:>8} {:>12} {:>12} {:>8}",
+            "id", "active", "parked", "sockets", "load", "submitted", "completed", "parks"
+        )
+        .unwrap();
+        for stats in MULTITON.stats() {
+            writeln!(
+                stat,
+                "{:<4} {:<6} {:<6} {:>8} {:>8} {:>12} {:>12} {:>8}",
+                stats.id,
+                stats.is_active as u8,
+                stats.is_parked as u8,
+                stats.nr_sockets,
+                stats.task_load,
+                stats.nr_submitted,
+                stats.nr_completed,
+                stats.nr_parks
+            )
+            .unwrap();
+        }
+        Ok(stat.into_bytes())
+    }
+}
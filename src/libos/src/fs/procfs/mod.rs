@@ -2,10 +2,11 @@ use super::*;
 use alloc::sync::{Arc, Weak};
 use rcore_fs::vfs;
 
-use crate::process::pid_t;
 use crate::process::table::get_all_processes;
+use crate::process::{gid_t, pid_t};
 
 use self::cpuinfo::CpuInfoINode;
+use self::io_uring_stat::IoUringStatINode;
 use self::meminfo::MemInfoINode;
 use self::pid::LockedPidDirINode;
 use self::proc_inode::{Dir, DirProcINode, File, ProcINode, SymLink};
@@ -13,6 +14,7 @@ use self::self_::SelfSymINode;
 use self::stat::StatINode;
 
 mod cpuinfo;
+mod io_uring_stat;
 mod meminfo;
 mod pid;
 mod proc_inode;
@@ -113,6 +115,9 @@ impl LockedProcRootINode {
         let stat_inode = StatINode::new();
         file.non_volatile_entries
             .insert(String::from("stat"), stat_inode);
+        let io_uring_inode = IoUringStatINode::new();
+        file.non_volatile_entries
+            .insert(String::from("io_uring"), io_uring_inode);
     }
 }
 
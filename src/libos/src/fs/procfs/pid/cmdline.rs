@@ -20,4 +20,8 @@ impl ProcINode for ProcCmdlineINode {
         };
         Ok(cmdline)
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
@@ -16,4 +16,8 @@ impl ProcINode for ProcCommINode {
         comm.push(b'\n');
         Ok(comm)
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
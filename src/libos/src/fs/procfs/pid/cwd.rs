@@ -10,8 +10,13 @@ impl ProcCwdSymINode {
 
 impl ProcINode for ProcCwdSymINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        check_process_access(&self.0)?;
         let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
         let fs = main_thread.fs().read().unwrap();
         Ok(fs.cwd().to_owned().into_bytes())
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
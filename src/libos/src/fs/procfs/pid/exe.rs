@@ -10,6 +10,11 @@ impl ProcExeSymINode {
 
 impl ProcINode for ProcExeSymINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        check_process_access(&self.0)?;
         Ok(self.0.exec_path().to_owned().into_bytes())
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
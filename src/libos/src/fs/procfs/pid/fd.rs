@@ -29,6 +29,7 @@ impl DirProcINode for LockedProcFdDirINode {
         if name == ".." {
             return Ok(Arc::clone(&file.parent));
         }
+        check_process_access(&file.process_ref)?;
         let fd = name
             .parse::<FileDesc>()
             .map_err(|_| FsError::EntryNotFound)?;
@@ -42,6 +43,7 @@ impl DirProcINode for LockedProcFdDirINode {
             1 => Ok(String::from("..")),
             i => {
                 let file = self.0.read().unwrap();
+                check_process_access(&file.process_ref)?;
                 let main_thread = file
                     .process_ref
                     .main_thread()
@@ -66,6 +68,7 @@ impl DirProcINode for LockedProcFdDirINode {
                 visit_first_two_entries!(&mut visitor, &file, &mut offset);
 
                 // The fd entries
+                check_process_access(&file.process_ref)?;
                 let main_thread = match file.process_ref.main_thread() {
                     Some(main_thread) => main_thread,
                     None => {
@@ -93,21 +96,29 @@ impl DirProcINode for LockedProcFdDirINode {
             _ => Ok(iterate_offset - offset),
         }
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0.read().unwrap().process_ref)
+    }
 }
 
-struct FdSymINode(FileRef);
+struct FdSymINode(ProcessRef, FileRef);
 
 impl FdSymINode {
     pub fn new(process_ref: &ProcessRef, fd: FileDesc) -> vfs::Result<Arc<dyn INode>> {
         let main_thread = process_ref.main_thread().ok_or(FsError::EntryNotFound)?;
         let file_ref = main_thread.file(fd).map_err(|_| FsError::EntryNotFound)?;
-        Ok(Arc::new(SymLink::new(Self(Arc::clone(&file_ref)))))
+        Ok(Arc::new(SymLink::new(Self(
+            Arc::clone(process_ref),
+            Arc::clone(&file_ref),
+        ))))
     }
 }
 
 impl ProcINode for FdSymINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
-        let path = if let Ok(inode_file) = self.0.as_inode_file() {
+        check_process_access(&self.0)?;
+        let path = if let Ok(inode_file) = self.1.as_inode_file() {
             inode_file.abs_path().to_owned()
         } else {
             // TODO: Support other file types
@@ -119,4 +130,8 @@ impl ProcINode for FdSymINode {
         };
         Ok(path.into_bytes())
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
@@ -48,6 +48,7 @@ impl ProcMapsINode {
 
 impl ProcINode for ProcMapsINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        check_process_access(&self.0)?;
         let result_string = {
             let main_thread = self.0.main_thread().unwrap();
             let process_vm = main_thread.vm();
@@ -84,6 +85,10 @@ impl ProcINode for ProcMapsINode {
 
         Ok(result_string.into_bytes())
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
 
 fn get_output_for_vma(vma: &VMArea, heap_or_stack: Option<&str>) -> String {
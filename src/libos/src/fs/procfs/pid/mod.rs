@@ -1,6 +1,6 @@
 use super::*;
 use crate::process::table::get_process;
-use crate::process::{ProcessRef, ProcessStatus};
+use crate::process::{check_ptrace_access, ProcessRef, ProcessStatus};
 
 use self::cmdline::ProcCmdlineINode;
 use self::comm::ProcCommINode;
@@ -9,6 +9,7 @@ use self::exe::ProcExeSymINode;
 use self::fd::LockedProcFdDirINode;
 use self::maps::ProcMapsINode;
 use self::root::ProcRootSymINode;
+use self::sched::ProcSchedINode;
 use self::stat::ProcStatINode;
 
 mod cmdline;
@@ -18,8 +19,25 @@ mod exe;
 mod fd;
 mod maps;
 mod root;
+mod sched;
 mod stat;
 
+// Like Linux, the files of a process are owned by its effective user and group,
+// unless the process is not dumpable.
+fn process_owner(process: &ProcessRef) -> (uid_t, gid_t) {
+    if !process.prctl_attrs().dumpable {
+        return (0, 0);
+    }
+    let credentials = process.credentials();
+    (credentials.euid(), credentials.egid())
+}
+
+// The sensitive files of a process, e.g., fd, cwd and maps, can only be accessed
+// by the processes which are allowed to trace it.
+fn check_process_access(process: &ProcessRef) -> vfs::Result<()> {
+    check_ptrace_access(current!().process(), process).map_err(|_| FsError::PermError)
+}
+
 pub struct LockedPidDirINode(RwLock<PidDirINode>);
 
 struct PidDirINode {
@@ -65,6 +83,9 @@ impl LockedPidDirINode {
         // maps
         let maps_inode = ProcMapsINode::new(&file.process_ref);
         file.entries.insert(String::from("maps"), maps_inode);
+        // sched
+        let sched_inode = ProcSchedINode::new(&file.process_ref);
+        file.entries.insert(String::from("sched"), sched_inode);
 
         Ok(())
     }
@@ -96,6 +117,10 @@ impl DirProcINode for LockedPidDirINode {
         }
     }
 
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0.read().unwrap().process_ref)
+    }
+
     fn get_entry(&self, id: usize) -> vfs::Result<String> {
         match id {
             0 => Ok(String::from(".")),
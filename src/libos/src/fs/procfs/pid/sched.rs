@@ -0,0 +1,57 @@
+use super::*;
+use crate::process::thread_usage;
+
+pub struct ProcSchedINode(ProcessRef);
+
+impl ProcSchedINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcSchedINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        let comm = String::from_utf8(main_thread.name().as_c_str().to_bytes().to_vec()).unwrap();
+        let param = *main_thread.sched().lock().unwrap().param();
+        let nice = main_thread.nice().read().unwrap().to_raw_val();
+        let usage = thread_usage(&main_thread);
+        let sum_exec_runtime = usage.utime() + usage.stime();
+
+        // Follow the format of Linux, where the kernel priority of a realtime
+        // thread is in [0, 99) and that of a normal thread is 120 + nice.
+        let prio = if param.policy().is_realtime() {
+            99 - param.priority() as i64
+        } else {
+            120 + nice as i64
+        };
+        let mut result = format!(
+            "{} ({}, #threads: {})\n{}\n",
+            comm,
+            self.0.pid(),
+            self.0.threads().len(),
+            "-".repeat(67)
+        );
+        result += &format!(
+            "{:<45}:{:>14}.{:06}\n",
+            "se.sum_exec_runtime",
+            sum_exec_runtime.as_millis(),
+            sum_exec_runtime.subsec_nanos() % 1_000_000
+        );
+        let fields = [
+            ("nr_switches", usage.nvcsw() + usage.nivcsw()),
+            ("nr_voluntary_switches", usage.nvcsw()),
+            ("nr_involuntary_switches", usage.nivcsw()),
+        ];
+        for (name, value) in fields.iter() {
+            result += &format!("{:<45}:{:>21}\n", name, value);
+        }
+        result += &format!("{:<45}:{:>21}\n", "policy", param.policy() as u32);
+        result += &format!("{:<45}:{:>21}\n", "prio", prio);
+        Ok(result.into_bytes())
+    }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
+}
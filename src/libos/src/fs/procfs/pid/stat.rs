@@ -1,4 +1,6 @@
 use super::*;
+use crate::process::{children_usage, duration_to_ticks, process_usage};
+use crate::vm::PAGE_SIZE;
 
 pub struct ProcStatINode(ProcessRef);
 
@@ -28,22 +30,30 @@ impl ProcINode for ProcStatINode {
         let tty_nr = 0;
         let tpgid = pgrp.clone();
         let flags = 0;
-        let minflt = 0;
-        let cminflt = 0;
-        let majflt = 0;
-        let cmajflt = 0;
-        let utime = 0;
-        let stime = 0;
-        let cutime = 0;
-        let cstime = 0;
-        // Convert [19,-20] to [39,0].
-        let priority = main_thread.nice().read().unwrap().to_raw_val() + 20;
+        let usage = process_usage(&self.0);
+        let children_usage = children_usage(&self.0);
+        let minflt = usage.minflt();
+        let cminflt = children_usage.minflt();
+        let majflt = usage.majflt();
+        let cmajflt = children_usage.majflt();
+        let utime = duration_to_ticks(usage.utime());
+        let stime = duration_to_ticks(usage.stime());
+        let cutime = duration_to_ticks(children_usage.utime());
+        let cstime = duration_to_ticks(children_usage.stime());
+        let sched_param = *main_thread.sched().lock().unwrap().param();
         let nice = main_thread.nice().read().unwrap().to_raw_val();
+        // Convert the realtime priority [1,99] to [-2,-100], and the nice
+        // value [19,-20] to [39,0].
+        let priority = if sched_param.policy().is_realtime() {
+            -1 - sched_param.priority() as i64
+        } else {
+            nice as i64 + 20
+        };
         let num_threads = self.0.threads().len();
         let itrealvalue = 0;
         let starttime = self.0.start_time();
         let vsize = main_thread.vm().get_process_range().size();
-        let rss = 0;
+        let rss = main_thread.vm().resident_size(pid) / PAGE_SIZE;
         let rsslim = 0;
         let startcode = 0;
         let endcode = 0;
@@ -59,8 +69,8 @@ impl ProcINode for ProcStatINode {
         let cnswap = 0;
         let exit_signal = 0;
         let processor = 0;
-        let rt_priority = 0;
-        let policy = 0;
+        let rt_priority = sched_param.priority();
+        let policy = sched_param.policy() as u32;
         let delayacct_blkio_ticks = 0;
         let guest_time = 0;
         let cguest_time = 0;
@@ -183,4 +193,8 @@ impl ProcINode for ProcStatINode {
         .into_bytes();
         Ok(result)
     }
+
+    fn owner(&self) -> (uid_t, gid_t) {
+        process_owner(&self.0)
+    }
 }
@@ -27,6 +27,7 @@ where
     }
 
     fn metadata(&self) -> vfs::Result<Metadata> {
+        let (uid, gid) = self.inner().owner();
         Ok(Metadata {
             dev: 0,
             inode: PROC_INO,
@@ -39,8 +40,8 @@ where
             type_: vfs::FileType::Dir,
             mode: 0o555,
             nlinks: 1,
-            uid: 0,
-            gid: 0,
+            uid: uid as usize,
+            gid: gid as usize,
             rdev: 0,
         })
     }
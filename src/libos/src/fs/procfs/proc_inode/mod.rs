@@ -10,6 +10,11 @@ mod symlink;
 
 pub trait ProcINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>>;
+
+    /// The owner of the inode, which is root by default
+    fn owner(&self) -> (uid_t, gid_t) {
+        (0, 0)
+    }
 }
 
 pub trait DirProcINode {
@@ -17,6 +22,11 @@ pub trait DirProcINode {
     fn get_entry(&self, id: usize) -> vfs::Result<String>;
     fn iterate_entries(&self, offset: usize, visitor: &mut dyn DirentVisitor)
         -> vfs::Result<usize>;
+
+    /// The owner of the inode, which is root by default
+    fn owner(&self) -> (uid_t, gid_t) {
+        (0, 0)
+    }
 }
 
 #[macro_export]
@@ -19,6 +19,7 @@ where
     T: ProcINode + Sync + Send + 'static,
 {
     fn metadata(&self) -> vfs::Result<Metadata> {
+        let (uid, gid) = self.inner().owner();
         Ok(Metadata {
             dev: 0,
             inode: PROC_INO,
@@ -31,8 +32,8 @@ where
             type_: vfs::FileType::SymLink,
             mode: 0o777,
             nlinks: 1,
-            uid: 0,
-            gid: 0,
+            uid: uid as usize,
+            gid: gid as usize,
             rdev: 0,
         })
     }
@@ -0,0 +1,1215 @@
+//! Integrity-protected HostFS.
+//!
+//! The host-visible layout is the same as the plain `HostFS`: one host file per
+//! file inside the LibOS, with the same name. But the content of each file is
+//! encrypted block by block with AES-GCM, and each directory keeps a hidden
+//! metadata file which records the type, size and MAC of its children.
+//!
+//! The MAC of a directory is recorded in its parent directory, so the MAC of the
+//! root directory is the root of a Merkle tree covering the whole file system.
+//! The root MAC is kept inside the enclave (and can be given as the `MAC` mount
+//! option), thus tampering or rollback of the host directory can be detected.
+//! The current root MAC can be read from the virtual file `.occlum_hostfs_mac`
+//! at the root directory.
+//!
+//! The nodes are bound to the random ids of their entries instead of paths, so
+//! an opened file keeps its own metadata after being renamed or unlinked.
+use super::hostfs::HostFS;
+use crate::misc::get_random;
+use crate::util::sgx::get_autokey;
+
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use core::any::Any;
+use rcore_fs::vfs::*;
+use sgx_tcrypto::{
+    rsgx_rijndael128GCM_decrypt, rsgx_rijndael128GCM_encrypt, rsgx_rijndael128_cmac_slice,
+};
+use sgx_trts::libc;
+use sgx_types::{sgx_aes_gcm_128bit_tag_t, sgx_key_128bit_t};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{SgxMutex as Mutex, SgxMutexGuard as MutexGuard};
+use std::untrusted::fs as untrusted_fs;
+use std::untrusted::path::PathEx;
+
+/// Names with this prefix are reserved for the bookkeeping of protection
+const RESERVED_PREFIX: &str = ".occlum_hostfs";
+/// The hidden metadata file in each host directory
+const DIR_META_NAME: &str = ".occlum_hostfs_meta";
+/// The host directory to keep the metadata of the automatic key
+const AUTOKEY_DIR_NAME: &str = ".occlum_hostfs_key";
+/// The read-only virtual file at the root directory to get the root MAC
+const ROOT_MAC_NAME: &str = ".occlum_hostfs_mac";
+
+/// The id of the root directory
+const ROOT_DIR_ID: u64 = 0;
+/// The size of plaintext in a data block
+const BLOCK_SIZE: usize = 0x1000;
+const IV_SIZE: usize = 12;
+const MAC_SIZE: usize = 16;
+/// The size of an encrypted data block on host: IV, MAC, and ciphertext
+const RECORD_SIZE: usize = IV_SIZE + MAC_SIZE + BLOCK_SIZE;
+
+type Mac = sgx_aes_gcm_128bit_tag_t;
+
+/// Integrity-protected file system at host
+pub struct ProtectedHostFS {
+    host_fs: Arc<HostFS>,
+    path: PathBuf,
+    data_key: sgx_key_128bit_t,
+    meta_key: sgx_key_128bit_t,
+    /// All updates of the Merkle tree are serialized by this lock
+    state: Mutex<MerkleState>,
+    self_ref: Weak<ProtectedHostFS>,
+}
+
+/// INode for `ProtectedHostFS`
+///
+/// A node is bound to the id of its entry rather than its path, so it keeps
+/// referring to the same file after being renamed or unlinked.
+pub struct PNode {
+    /// The opened host file of a regular file, which stays valid after the
+    /// file is renamed or unlinked. A directory is looked up on host by its
+    /// current path instead.
+    host_file: Option<Arc<dyn INode>>,
+    id: u64,
+    type_: FileType,
+    fs: Arc<ProtectedHostFS>,
+}
+
+/// The read-only file at the root directory to get the current root MAC
+pub struct RootMacINode {
+    fs: Arc<ProtectedHostFS>,
+}
+
+struct MerkleState {
+    root_mac: Mac,
+    /// The verified metadata of directories, indexed by the directory id
+    dir_metas: BTreeMap<u64, DirMeta>,
+    /// The locations of the entries in use, indexed by the entry id
+    locations: BTreeMap<u64, Location>,
+    /// The number of nodes of each entry in use, indexed by the entry id
+    node_counts: BTreeMap<u64, usize>,
+    /// The verified MACs of data blocks, indexed by the file id
+    block_macs: BTreeMap<u64, Vec<Mac>>,
+}
+
+enum Location {
+    /// The entry is named `name` in the directory of id `parent`
+    Linked { parent: u64, name: String },
+    /// The entry has been unlinked or replaced while still in use, so its
+    /// metadata is kept in memory until the last node is dropped
+    Unlinked(EntryMeta),
+}
+
+/// The trusted metadata of a directory
+#[derive(Clone, Debug, Default)]
+struct DirMeta {
+    id: u64,
+    entries: BTreeMap<String, EntryMeta>,
+}
+
+/// The trusted metadata of a directory entry
+#[derive(Clone, Copy, Debug)]
+struct EntryMeta {
+    type_: FileType,
+    /// A random id binding the content to the entry, so the content
+    /// cannot be swapped between files or directories by the host
+    id: u64,
+    /// The plaintext size of a file, always zero for a directory
+    size: u64,
+    mac: Mac,
+}
+
+impl FileSystem for ProtectedHostFS {
+    fn sync(&self) -> Result<()> {
+        // All updates are written through to the host
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        let mut state = self.lock_state();
+        self.new_node(&mut state, None, ROOT_DIR_ID, FileType::Dir)
+    }
+
+    fn info(&self) -> FsInfo {
+        self.host_fs.info()
+    }
+}
+
+impl ProtectedHostFS {
+    /// Open an integrity-protected `HostFS` at host `path`, or create a new one
+    /// if the host directory is empty.
+    ///
+    /// If `root_mac` is given, the root directory must match it, which prevents
+    /// the whole host directory from being rolled back across mounts.
+    pub fn open(
+        path: impl AsRef<Path>,
+        key: &Option<sgx_key_128bit_t>,
+        root_mac: &Option<Mac>,
+    ) -> Result<Arc<Self>> {
+        let path = path.as_ref().to_path_buf();
+        let key = match key {
+            Some(key) => *key,
+            None => {
+                let autokey_dir = path.join(AUTOKEY_DIR_NAME);
+                if !autokey_dir.exists() {
+                    untrusted_fs::create_dir(&autokey_dir).map_err(|_| FsError::PermError)?;
+                }
+                get_autokey(&autokey_dir).map_err(|_| FsError::PermError)?
+            }
+        };
+        let data_key = derive_key(&key, b"OCCLUM_HOSTFS_DATA")?;
+        let meta_key = derive_key(&key, b"OCCLUM_HOSTFS_META")?;
+
+        let mut fs = Self {
+            host_fs: HostFS::new(&path),
+            path,
+            data_key,
+            meta_key,
+            state: Mutex::new(MerkleState {
+                root_mac: Mac::default(),
+                dir_metas: BTreeMap::new(),
+                locations: BTreeMap::new(),
+                node_counts: BTreeMap::new(),
+                block_macs: BTreeMap::new(),
+            }),
+            self_ref: Weak::default(),
+        };
+
+        let root_meta_path = fs.path.join(DIR_META_NAME);
+        let (root_meta, root_mac_on_host) = if root_meta_path.exists() {
+            fs.load_dir_meta(Path::new(""), ROOT_DIR_ID)?
+        } else {
+            // Only an empty host directory can be turned into a new one,
+            // otherwise the root metadata has been removed by the host
+            if root_mac.is_some() || !fs.is_host_dir_empty()? {
+                error!("ProtectedHostFS: the metadata of the root directory is missing");
+                return Err(FsError::NoIntegrity);
+            }
+            let root_meta = DirMeta {
+                id: ROOT_DIR_ID,
+                entries: BTreeMap::new(),
+            };
+            let mac = fs.persist_dir_meta(Path::new(""), &root_meta)?;
+            (root_meta, mac)
+        };
+        match root_mac {
+            Some(root_mac) if *root_mac != root_mac_on_host => {
+                error!("ProtectedHostFS: the root MAC mismatches, the host directory may be rolled back");
+                return Err(FsError::NoIntegrity);
+            }
+            Some(_) => {}
+            None => warn!(
+                "ProtectedHostFS: no root MAC is given for {:?}, rollback across mounts cannot be detected",
+                fs.path
+            ),
+        }
+        {
+            let state = fs.state.get_mut().unwrap();
+            state.root_mac = root_mac_on_host;
+            state.dir_metas.insert(ROOT_DIR_ID, root_meta);
+        }
+        Ok(fs.wrap())
+    }
+
+    /// Get the current root MAC of the Merkle tree
+    pub fn root_mac(&self) -> Mac {
+        self.lock_state().root_mac
+    }
+
+    /// Wrap pure `ProtectedHostFS` with Arc
+    /// Used in constructors
+    fn wrap(self) -> Arc<Self> {
+        let fs = Arc::new(self);
+        let weak = Arc::downgrade(&fs);
+        let ptr = Arc::into_raw(fs) as *mut Self;
+        unsafe {
+            (*ptr).self_ref = weak;
+        }
+        unsafe { Arc::from_raw(ptr) }
+    }
+
+    fn lock_state(&self) -> MutexGuard<MerkleState> {
+        self.state.lock().unwrap()
+    }
+
+    fn is_host_dir_empty(&self) -> Result<bool> {
+        let entries = self.path.read_dir().map_err(|_| FsError::EntryNotFound)?;
+        for entry in entries {
+            let entry = entry.map_err(|_| device_error())?;
+            if entry.file_name() != AUTOKEY_DIR_NAME {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn new_node(
+        &self,
+        state: &mut MerkleState,
+        host_file: Option<Arc<dyn INode>>,
+        id: u64,
+        type_: FileType,
+    ) -> Arc<dyn INode> {
+        *state.node_counts.entry(id).or_insert(0) += 1;
+        Arc::new(PNode {
+            host_file,
+            id,
+            type_,
+            fs: self.self_ref.upgrade().unwrap(),
+        })
+    }
+
+    /// Get the path of the entry relative to the root, empty for the root
+    fn path_of(&self, state: &MerkleState, id: u64) -> Result<PathBuf> {
+        let mut names = Vec::new();
+        let mut id = id;
+        while id != ROOT_DIR_ID {
+            match state.locations.get(&id) {
+                Some(Location::Linked { parent, name }) => {
+                    names.push(name.as_str());
+                    id = *parent;
+                }
+                _ => return Err(FsError::EntryNotFound),
+            }
+        }
+        Ok(names.iter().rev().collect())
+    }
+
+    /// Get the host inode at the current path of the entry
+    fn host_inode_of(&self, state: &MerkleState, id: u64) -> Result<Arc<dyn INode>> {
+        let rel_path = self.path_of(state, id)?;
+        if rel_path.as_os_str().is_empty() {
+            return Ok(self.host_fs.root_inode());
+        }
+        self.host_fs
+            .root_inode()
+            .lookup(rel_path.to_str().ok_or(FsError::InvalidParam)?)
+    }
+
+    /// Get the verified metadata of the directory, loading the metadata of it
+    /// and its ancestors from host if necessary.
+    fn dir_meta<'a>(&self, state: &'a mut MerkleState, dir_id: u64) -> Result<&'a mut DirMeta> {
+        if !state.dir_metas.contains_key(&dir_id) {
+            let (parent, name) = match state.locations.get(&dir_id) {
+                Some(Location::Linked { parent, name }) => (*parent, name.clone()),
+                _ => return Err(FsError::EntryNotFound),
+            };
+            let entry = self
+                .dir_meta(state, parent)?
+                .entries
+                .get(&name)
+                .copied()
+                .filter(|entry| entry.id == dir_id)
+                .ok_or(FsError::EntryNotFound)?;
+            if entry.type_ != FileType::Dir {
+                return Err(FsError::NotDir);
+            }
+            let rel_dir = self.path_of(state, dir_id)?;
+            let (meta, mac) = self.load_dir_meta(&rel_dir, dir_id)?;
+            if mac != entry.mac {
+                error!(
+                    "ProtectedHostFS: the MAC of directory {:?} mismatches",
+                    rel_dir
+                );
+                return Err(FsError::NoIntegrity);
+            }
+            state.dir_metas.insert(dir_id, meta);
+        }
+        Ok(state.dir_metas.get_mut(&dir_id).unwrap())
+    }
+
+    /// Get the verified metadata of the directory named `name` in the
+    /// directory `parent`, even if the directory is not in use.
+    fn child_dir_meta<'a>(
+        &self,
+        state: &'a mut MerkleState,
+        parent: u64,
+        name: &str,
+        dir_id: u64,
+    ) -> Result<&'a mut DirMeta> {
+        state
+            .locations
+            .entry(dir_id)
+            .or_insert_with(|| Location::Linked {
+                parent,
+                name: name.to_owned(),
+            });
+        self.dir_meta(state, dir_id)
+    }
+
+    /// Get the verified metadata of the entry
+    fn entry_meta(&self, state: &mut MerkleState, id: u64) -> Result<EntryMeta> {
+        match state.locations.get(&id) {
+            Some(Location::Linked { parent, name }) => {
+                let (parent, name) = (*parent, name.clone());
+                self.dir_meta(state, parent)?
+                    .entries
+                    .get(&name)
+                    .copied()
+                    .filter(|entry| entry.id == id)
+                    .ok_or(FsError::EntryNotFound)
+            }
+            Some(Location::Unlinked(entry)) => Ok(*entry),
+            None => Err(FsError::EntryNotFound),
+        }
+    }
+
+    /// Record the new metadata of the entry, in the Merkle tree if the entry
+    /// is linked, or in memory if it is unlinked.
+    fn set_entry_meta(&self, state: &mut MerkleState, entry: EntryMeta) -> Result<()> {
+        match state.locations.get_mut(&entry.id) {
+            Some(Location::Linked { parent, name }) => {
+                let (parent, name) = (*parent, name.clone());
+                self.update_dir_meta(state, parent, |meta| {
+                    meta.entries.insert(name, entry);
+                })
+            }
+            Some(Location::Unlinked(unlinked)) => {
+                *unlinked = entry;
+                Ok(())
+            }
+            None => Err(FsError::EntryNotFound),
+        }
+    }
+
+    /// Forget the entry which has been unlinked or replaced. The metadata is
+    /// kept in memory if the entry is still in use.
+    fn detach_entry(&self, state: &mut MerkleState, entry: EntryMeta) {
+        if entry.type_ == FileType::Dir {
+            state.dir_metas.remove(&entry.id);
+        }
+        if state.node_counts.contains_key(&entry.id) {
+            state.locations.insert(entry.id, Location::Unlinked(entry));
+        } else {
+            state.locations.remove(&entry.id);
+            state.block_macs.remove(&entry.id);
+        }
+    }
+
+    /// Get the verified MACs of the data blocks of a file
+    fn block_macs<'a>(
+        &self,
+        state: &'a mut MerkleState,
+        host_file: &Arc<dyn INode>,
+        entry: &EntryMeta,
+    ) -> Result<&'a mut Vec<Mac>> {
+        if !state.block_macs.contains_key(&entry.id) {
+            let block_num = blocks_of(entry.size as usize);
+            let mut macs = Vec::with_capacity(block_num);
+            let mut header = [0u8; IV_SIZE + MAC_SIZE];
+            for idx in 0..block_num {
+                let len = host_file.read_at(idx * RECORD_SIZE, &mut header)?;
+                if len != header.len() {
+                    return Err(FsError::NoIntegrity);
+                }
+                let mut mac = Mac::default();
+                mac.copy_from_slice(&header[IV_SIZE..]);
+                macs.push(mac);
+            }
+            if self.file_mac(entry.id, entry.size, &macs)? != entry.mac {
+                error!("ProtectedHostFS: the MAC of file {} mismatches", entry.id);
+                return Err(FsError::NoIntegrity);
+            }
+            state.block_macs.insert(entry.id, macs);
+        }
+        Ok(state.block_macs.get_mut(&entry.id).unwrap())
+    }
+
+    /// Update the metadata of the directory, then update the MACs along the
+    /// path to the root.
+    fn update_dir_meta(
+        &self,
+        state: &mut MerkleState,
+        dir_id: u64,
+        update_fn: impl FnOnce(&mut DirMeta),
+    ) -> Result<()> {
+        let meta = self.dir_meta(state, dir_id)?;
+        update_fn(meta);
+        let meta = meta.clone();
+        let mut mac = self.persist_dir_meta(&self.path_of(state, dir_id)?, &meta)?;
+
+        let mut child = dir_id;
+        while child != ROOT_DIR_ID {
+            let (parent, name) = match state.locations.get(&child) {
+                Some(Location::Linked { parent, name }) => (*parent, name.clone()),
+                _ => return Err(FsError::EntryNotFound),
+            };
+            let parent_meta = self.dir_meta(state, parent)?;
+            parent_meta
+                .entries
+                .get_mut(&name)
+                .ok_or(FsError::EntryNotFound)?
+                .mac = mac;
+            let parent_meta = parent_meta.clone();
+            mac = self.persist_dir_meta(&self.path_of(state, parent)?, &parent_meta)?;
+            child = parent;
+        }
+        state.root_mac = mac;
+        Ok(())
+    }
+
+    fn load_dir_meta(&self, rel_dir: &Path, id: u64) -> Result<(DirMeta, Mac)> {
+        let mut buf = Vec::new();
+        untrusted_fs::File::open(self.path.join(rel_dir).join(DIR_META_NAME))
+            .and_then(|mut file| file.read_to_end(&mut buf))
+            .map_err(|_| FsError::NoIntegrity)?;
+        if buf.len() < IV_SIZE + MAC_SIZE {
+            return Err(FsError::NoIntegrity);
+        }
+        let (iv, rest) = buf.split_at(IV_SIZE);
+        let (mac_bytes, ciphertext) = rest.split_at(MAC_SIZE);
+        let mut mac = Mac::default();
+        mac.copy_from_slice(mac_bytes);
+        let mut plaintext = vec![0u8; ciphertext.len()];
+        rsgx_rijndael128GCM_decrypt(
+            &self.meta_key,
+            ciphertext,
+            iv,
+            &id.to_le_bytes(),
+            &mac,
+            &mut plaintext,
+        )
+        .map_err(|_| FsError::NoIntegrity)?;
+        let meta = DirMeta::decode(id, &plaintext)?;
+        Ok((meta, mac))
+    }
+
+    fn persist_dir_meta(&self, rel_dir: &Path, meta: &DirMeta) -> Result<Mac> {
+        let plaintext = meta.encode();
+        let mut buf = vec![0u8; IV_SIZE + MAC_SIZE + plaintext.len()];
+        let (iv, rest) = buf.split_at_mut(IV_SIZE);
+        let (mac_bytes, ciphertext) = rest.split_at_mut(MAC_SIZE);
+        get_random(iv).map_err(|_| device_error())?;
+        let mut mac = Mac::default();
+        rsgx_rijndael128GCM_encrypt(
+            &self.meta_key,
+            &plaintext,
+            iv,
+            &meta.id.to_le_bytes(),
+            ciphertext,
+            &mut mac,
+        )
+        .map_err(|_| device_error())?;
+        mac_bytes.copy_from_slice(&mac);
+        untrusted_fs::File::create(self.path.join(rel_dir).join(DIR_META_NAME))
+            .and_then(|mut file| file.write_all(&buf))
+            .map_err(|_| device_error())?;
+        Ok(mac)
+    }
+
+    /// The MAC of a file, which covers its id, size and all the data blocks
+    fn file_mac(&self, id: u64, size: u64, block_macs: &[Mac]) -> Result<Mac> {
+        let mut buf = Vec::with_capacity(16 + block_macs.len() * MAC_SIZE);
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&size.to_le_bytes());
+        for mac in block_macs {
+            buf.extend_from_slice(mac);
+        }
+        rsgx_rijndael128_cmac_slice(&self.meta_key, &buf).map_err(|_| device_error())
+    }
+
+    fn read_block(
+        &self,
+        host_file: &Arc<dyn INode>,
+        file_id: u64,
+        idx: usize,
+        expected_mac: &Mac,
+        buf: &mut [u8; BLOCK_SIZE],
+    ) -> Result<()> {
+        let mut record = vec![0u8; RECORD_SIZE];
+        let len = host_file.read_at(idx * RECORD_SIZE, &mut record)?;
+        if len != RECORD_SIZE {
+            return Err(FsError::NoIntegrity);
+        }
+        let (iv, rest) = record.split_at(IV_SIZE);
+        let (mac_bytes, ciphertext) = rest.split_at(MAC_SIZE);
+        if mac_bytes != expected_mac {
+            return Err(FsError::NoIntegrity);
+        }
+        rsgx_rijndael128GCM_decrypt(
+            &self.data_key,
+            ciphertext,
+            iv,
+            &block_aad(file_id, idx),
+            expected_mac,
+            buf,
+        )
+        .map_err(|_| FsError::NoIntegrity)
+    }
+
+    fn write_block(
+        &self,
+        host_file: &Arc<dyn INode>,
+        file_id: u64,
+        idx: usize,
+        buf: &[u8; BLOCK_SIZE],
+    ) -> Result<Mac> {
+        let mut record = vec![0u8; RECORD_SIZE];
+        let (iv, rest) = record.split_at_mut(IV_SIZE);
+        let (mac_bytes, ciphertext) = rest.split_at_mut(MAC_SIZE);
+        get_random(iv).map_err(|_| device_error())?;
+        let mut mac = Mac::default();
+        rsgx_rijndael128GCM_encrypt(
+            &self.data_key,
+            buf,
+            iv,
+            &block_aad(file_id, idx),
+            ciphertext,
+            &mut mac,
+        )
+        .map_err(|_| device_error())?;
+        mac_bytes.copy_from_slice(&mac);
+        let len = host_file.write_at(idx * RECORD_SIZE, &record)?;
+        if len != RECORD_SIZE {
+            return Err(device_error());
+        }
+        Ok(mac)
+    }
+}
+
+impl INode for PNode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let host_file = self.host_file()?;
+        let fs = &self.fs;
+        let mut state = fs.lock_state();
+        let entry = fs.entry_meta(&mut state, self.id)?;
+        let size = entry.size as usize;
+        if offset >= size {
+            return Ok(0);
+        }
+        let end = size.min(offset + buf.len());
+        let block_macs = fs.block_macs(&mut state, host_file, &entry)?;
+
+        let mut block_buf = [0u8; BLOCK_SIZE];
+        let mut pos = offset;
+        while pos < end {
+            let idx = pos / BLOCK_SIZE;
+            let block_offset = pos % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - block_offset).min(end - pos);
+            fs.read_block(host_file, entry.id, idx, &block_macs[idx], &mut block_buf)?;
+            buf[pos - offset..pos - offset + len]
+                .copy_from_slice(&block_buf[block_offset..block_offset + len]);
+            pos += len;
+        }
+        Ok(end - offset)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        let host_file = self.host_file()?;
+        let fs = &self.fs;
+        let mut state = fs.lock_state();
+        let entry = fs.entry_meta(&mut state, self.id)?;
+        let old_size = entry.size as usize;
+        let end = offset + buf.len();
+        let new_size = old_size.max(end);
+        let mut block_macs = fs.block_macs(&mut state, host_file, &entry)?.clone();
+
+        // Fill the hole between the old end and the start of the write with zeros
+        let zero_block = [0u8; BLOCK_SIZE];
+        while block_macs.len() < offset / BLOCK_SIZE {
+            let idx = block_macs.len();
+            block_macs.push(fs.write_block(host_file, entry.id, idx, &zero_block)?);
+        }
+
+        let mut block_buf = [0u8; BLOCK_SIZE];
+        let mut pos = offset;
+        while pos < end {
+            let idx = pos / BLOCK_SIZE;
+            let block_offset = pos % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - block_offset).min(end - pos);
+            if len < BLOCK_SIZE && idx < block_macs.len() {
+                fs.read_block(host_file, entry.id, idx, &block_macs[idx], &mut block_buf)?;
+            } else {
+                block_buf = zero_block;
+            }
+            block_buf[block_offset..block_offset + len]
+                .copy_from_slice(&buf[pos - offset..pos - offset + len]);
+            let mac = fs.write_block(host_file, entry.id, idx, &block_buf)?;
+            if idx < block_macs.len() {
+                block_macs[idx] = mac;
+            } else {
+                block_macs.push(mac);
+            }
+            pos += len;
+        }
+
+        self.commit_file(&mut state, entry, new_size, block_macs)?;
+        Ok(buf.len())
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let fs = &self.fs;
+        if let Some(host_file) = &self.host_file {
+            let mut metadata = host_file.metadata()?;
+            let mut state = fs.lock_state();
+            let entry = fs.entry_meta(&mut state, self.id)?;
+            metadata.size = entry.size as usize;
+            metadata.blocks = (metadata.size + 511) / 512;
+            return Ok(metadata);
+        }
+        let host_dir = fs.host_inode_of(&fs.lock_state(), self.id)?;
+        host_dir.metadata()
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<()> {
+        let fs = &self.fs;
+        let host_inode = {
+            let state = fs.lock_state();
+            match state.locations.get(&self.id) {
+                Some(Location::Unlinked(_)) => self.host_file()?.clone(),
+                _ => fs.host_inode_of(&state, self.id)?,
+            }
+        };
+        host_inode.set_metadata(metadata)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        match &self.host_file {
+            Some(host_file) => host_file.sync_all(),
+            None => Ok(()),
+        }
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        match &self.host_file {
+            Some(host_file) => host_file.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    fn resize(&self, len: usize) -> Result<()> {
+        let host_file = self.host_file()?;
+        let fs = &self.fs;
+        let mut state = fs.lock_state();
+        let entry = fs.entry_meta(&mut state, self.id)?;
+        let mut block_macs = fs.block_macs(&mut state, host_file, &entry)?.clone();
+        let new_block_num = blocks_of(len);
+
+        if new_block_num < block_macs.len() {
+            block_macs.truncate(new_block_num);
+            host_file.resize(new_block_num * RECORD_SIZE)?;
+        }
+        // The tail of the last block must be zeros, which may be read after extending
+        if len < entry.size as usize && len % BLOCK_SIZE != 0 {
+            let idx = len / BLOCK_SIZE;
+            let mut block_buf = [0u8; BLOCK_SIZE];
+            fs.read_block(host_file, entry.id, idx, &block_macs[idx], &mut block_buf)?;
+            block_buf[len % BLOCK_SIZE..].fill(0);
+            block_macs[idx] = fs.write_block(host_file, entry.id, idx, &block_buf)?;
+        }
+        let zero_block = [0u8; BLOCK_SIZE];
+        while block_macs.len() < new_block_num {
+            let idx = block_macs.len();
+            block_macs.push(fs.write_block(host_file, entry.id, idx, &zero_block)?);
+        }
+
+        self.commit_file(&mut state, entry, len, block_macs)
+    }
+
+    fn create(&self, name: &str, type_: FileType, mode: u16) -> Result<Arc<dyn INode>> {
+        check_name(name)?;
+        let fs = &self.fs;
+        let mut state = fs.lock_state();
+        if fs.dir_meta(&mut state, self.id)?.entries.contains_key(name) {
+            return Err(FsError::EntryExist);
+        }
+
+        if type_ != FileType::File && type_ != FileType::Dir {
+            return Err(FsError::PermError);
+        }
+
+        let host_inode = fs
+            .host_inode_of(&state, self.id)?
+            .create(name, type_, mode)?;
+        let id = new_id()?;
+        state.locations.insert(
+            id,
+            Location::Linked {
+                parent: self.id,
+                name: name.to_owned(),
+            },
+        );
+        let mac = match type_ {
+            FileType::File => fs.file_mac(id, 0, &[])?,
+            FileType::Dir => {
+                let meta = DirMeta {
+                    id,
+                    entries: BTreeMap::new(),
+                };
+                let mac = fs.persist_dir_meta(&fs.path_of(&state, id)?, &meta)?;
+                state.dir_metas.insert(id, meta);
+                mac
+            }
+            _ => unreachable!(),
+        };
+        let entry = EntryMeta {
+            type_,
+            id,
+            size: 0,
+            mac,
+        };
+        fs.update_dir_meta(&mut state, self.id, |meta| {
+            meta.entries.insert(name.to_owned(), entry);
+        })?;
+
+        let host_file = (type_ == FileType::File).then(|| host_inode);
+        Ok(fs.new_node(&mut state, host_file, id, type_))
+    }
+
+    fn link(&self, _name: &str, _other: &Arc<dyn INode>) -> Result<()> {
+        warn!("hard link is not supported in the integrity-protected HostFS");
+        Err(FsError::NotSupported)
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        check_name(name)?;
+        let fs = &self.fs;
+        let mut state = fs.lock_state();
+        let entry = fs
+            .dir_meta(&mut state, self.id)?
+            .entries
+            .get(name)
+            .copied()
+            .ok_or(FsError::EntryNotFound)?;
+        let host_dir = fs.host_inode_of(&state, self.id)?;
+        if entry.type_ == FileType::Dir {
+            if !fs
+                .child_dir_meta(&mut state, self.id, name, entry.id)?
+                .entries
+                .is_empty()
+            {
+                return Err(FsError::DirNotEmpty);
+            }
+            let rel_dir = fs.path_of(&state, entry.id)?;
+            let _ = untrusted_fs::remove_file(fs.path.join(rel_dir).join(DIR_META_NAME));
+        }
+        host_dir.unlink(name)?;
+        fs.detach_entry(&mut state, entry);
+        fs.update_dir_meta(&mut state, self.id, |meta| {
+            meta.entries.remove(name);
+        })
+    }
+
+    fn move_(&self, old_name: &str, target: &Arc<dyn INode>, new_name: &str) -> Result<()> {
+        check_name(old_name)?;
+        check_name(new_name)?;
+        let target = target.downcast_ref::<Self>().ok_or(FsError::NotSameFs)?;
+        if !Arc::ptr_eq(&self.fs, &target.fs) {
+            return Err(FsError::NotSameFs);
+        }
+        let fs = &self.fs;
+        let mut state = fs.lock_state();
+        let entry = fs
+            .dir_meta(&mut state, self.id)?
+            .entries
+            .get(old_name)
+            .copied()
+            .ok_or(FsError::EntryNotFound)?;
+        let replaced = fs
+            .dir_meta(&mut state, target.id)?
+            .entries
+            .get(new_name)
+            .copied();
+        if entry.type_ == FileType::Dir && fs.is_ancestor(&state, entry.id, target.id) {
+            return Err(FsError::InvalidParam);
+        }
+        if let Some(replaced) = replaced {
+            if replaced.id == entry.id {
+                return Ok(());
+            }
+            match (entry.type_, replaced.type_) {
+                (FileType::Dir, FileType::Dir) => {
+                    if !fs
+                        .child_dir_meta(&mut state, target.id, new_name, replaced.id)?
+                        .entries
+                        .is_empty()
+                    {
+                        return Err(FsError::DirNotEmpty);
+                    }
+                    let rel_dir = fs.path_of(&state, replaced.id)?;
+                    let _ = untrusted_fs::remove_file(fs.path.join(rel_dir).join(DIR_META_NAME));
+                }
+                (FileType::Dir, _) => return Err(FsError::NotDir),
+                (_, FileType::Dir) => return Err(FsError::IsDir),
+                _ => {}
+            }
+        }
+
+        let host_dir = fs.host_inode_of(&state, self.id)?;
+        let target_host_dir = fs.host_inode_of(&state, target.id)?;
+        host_dir.move_(old_name, &target_host_dir, new_name)?;
+
+        if let Some(replaced) = replaced {
+            fs.detach_entry(&mut state, replaced);
+        }
+        if let Some(location) = state.locations.get_mut(&entry.id) {
+            *location = Location::Linked {
+                parent: target.id,
+                name: new_name.to_owned(),
+            };
+        }
+        fs.update_dir_meta(&mut state, self.id, |meta| {
+            meta.entries.remove(old_name);
+        })?;
+        fs.update_dir_meta(&mut state, target.id, |meta| {
+            meta.entries.insert(new_name.to_owned(), entry);
+        })
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn INode>> {
+        if !self.is_dir() {
+            return Err(FsError::NotDir);
+        }
+        let fs = &self.fs;
+        if self.id == ROOT_DIR_ID && name == ROOT_MAC_NAME {
+            return Ok(Arc::new(RootMacINode { fs: fs.clone() }));
+        }
+
+        let mut state = fs.lock_state();
+        match name {
+            "." => return Ok(fs.new_node(&mut state, None, self.id, FileType::Dir)),
+            ".." => {
+                let parent = match state.locations.get(&self.id) {
+                    _ if self.id == ROOT_DIR_ID => ROOT_DIR_ID,
+                    Some(Location::Linked { parent, .. }) => *parent,
+                    _ => return Err(FsError::EntryNotFound),
+                };
+                return Ok(fs.new_node(&mut state, None, parent, FileType::Dir));
+            }
+            _ if name.starts_with(RESERVED_PREFIX) => return Err(FsError::EntryNotFound),
+            _ => {}
+        }
+
+        let entry = fs
+            .dir_meta(&mut state, self.id)?
+            .entries
+            .get(name)
+            .copied()
+            .ok_or(FsError::EntryNotFound)?;
+        let host_inode = fs
+            .host_inode_of(&state, self.id)?
+            .find(name)
+            .map_err(|e| match e {
+                // The entry is recorded, but the host file has been removed
+                FsError::EntryNotFound => FsError::NoIntegrity,
+                e => e,
+            })?;
+        if host_inode.metadata()?.type_ != entry.type_ {
+            return Err(FsError::NoIntegrity);
+        }
+        state.locations.insert(
+            entry.id,
+            Location::Linked {
+                parent: self.id,
+                name: name.to_owned(),
+            },
+        );
+
+        let host_file = (entry.type_ == FileType::File).then(|| host_inode);
+        Ok(fs.new_node(&mut state, host_file, entry.id, entry.type_))
+    }
+
+    fn get_entry(&self, id: usize) -> Result<String> {
+        if !self.is_dir() {
+            return Err(FsError::NotDir);
+        }
+        let mut state = self.fs.lock_state();
+        self.fs
+            .dir_meta(&mut state, self.id)?
+            .entries
+            .keys()
+            .nth(id)
+            .cloned()
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    fn iterate_entries(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        if !self.is_dir() {
+            return Err(FsError::NotDir);
+        }
+        // Only the entries recorded in the trusted metadata are visible
+        let entries: Vec<(String, EntryMeta)> = {
+            let mut state = self.fs.lock_state();
+            self.fs
+                .dir_meta(&mut state, self.id)?
+                .entries
+                .iter()
+                .skip(offset)
+                .map(|(name, entry)| (name.clone(), *entry))
+                .collect()
+        };
+
+        let mut visited = 0;
+        for (idx, (name, entry)) in entries.iter().enumerate() {
+            if let Err(e) = visitor.visit_entry(name, entry.id, entry.type_, offset + idx) {
+                if visited == 0 {
+                    return Err(e);
+                }
+                break;
+            }
+            visited += 1;
+        }
+        Ok(visited)
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> Result<()> {
+        match &self.host_file {
+            Some(host_file) => host_file.io_control(cmd, data),
+            None => Ok(()),
+        }
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Drop for PNode {
+    fn drop(&mut self) {
+        let mut state = self.fs.lock_state();
+        let count = state.node_counts.get_mut(&self.id).unwrap();
+        *count -= 1;
+        if *count > 0 {
+            return;
+        }
+        state.node_counts.remove(&self.id);
+        // The locations of the loaded directories are kept to find their paths
+        let is_unlinked = matches!(state.locations.get(&self.id), Some(Location::Unlinked(_)));
+        if is_unlinked || !state.dir_metas.contains_key(&self.id) {
+            state.locations.remove(&self.id);
+        }
+        if is_unlinked {
+            state.block_macs.remove(&self.id);
+        }
+    }
+}
+
+impl PNode {
+    /// Record the new size and block MACs of this file in the Merkle tree
+    fn commit_file(
+        &self,
+        state: &mut MerkleState,
+        mut entry: EntryMeta,
+        new_size: usize,
+        block_macs: Vec<Mac>,
+    ) -> Result<()> {
+        let fs = &self.fs;
+        entry.size = new_size as u64;
+        entry.mac = fs.file_mac(entry.id, entry.size, &block_macs)?;
+        state.block_macs.insert(entry.id, block_macs);
+        fs.set_entry_meta(state, entry)
+    }
+
+    /// Get the opened host file if this PNode is for a regular file.
+    fn host_file(&self) -> Result<&Arc<dyn INode>> {
+        self.host_file.as_ref().ok_or(FsError::NotFile)
+    }
+
+    /// Returns `true` if this PNode is for a directory.
+    fn is_dir(&self) -> bool {
+        self.type_ == FileType::Dir
+    }
+}
+
+impl ProtectedHostFS {
+    /// Whether the directory `ancestor` is `id` or an ancestor of it
+    fn is_ancestor(&self, state: &MerkleState, ancestor: u64, id: u64) -> bool {
+        let mut id = id;
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match state.locations.get(&id) {
+                Some(Location::Linked { parent, .. }) => id = *parent,
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl RootMacINode {
+    /// The root MAC in the same format as the `mac` mount option
+    fn mac_string(&self) -> String {
+        let mac = self.fs.root_mac();
+        let bytes: Vec<String> = mac.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{}\n", bytes.join("-"))
+    }
+}
+
+impl INode for RootMacINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let data = self.mac_string().into_bytes();
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + buf.len());
+        buf[..end - start].copy_from_slice(&data[start..end]);
+        Ok(end - start)
+    }
+
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let mut metadata = self.fs.host_fs.root_inode().metadata()?;
+        metadata.type_ = FileType::File;
+        metadata.mode = 0o444;
+        metadata.nlinks = 1;
+        metadata.size = self.mac_string().len();
+        metadata.blocks = 0;
+        Ok(metadata)
+    }
+
+    fn set_metadata(&self, _metadata: &Metadata) -> Result<()> {
+        Err(FsError::PermError)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn find(&self, _name: &str) -> Result<Arc<dyn INode>> {
+        Err(FsError::NotDir)
+    }
+
+    fn get_entry(&self, _id: usize) -> Result<String> {
+        Err(FsError::NotDir)
+    }
+
+    fn iterate_entries(&self, _offset: usize, _visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        Err(FsError::NotDir)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl DirMeta {
+    /// Encode the entries as: count(u32), then for each entry:
+    /// type(u8), id(u64), size(u64), mac([u8; 16]), name_len(u16), name
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for (name, entry) in &self.entries {
+            let type_ = match entry.type_ {
+                FileType::Dir => 1u8,
+                _ => 0u8,
+            };
+            buf.push(type_);
+            buf.extend_from_slice(&entry.id.to_le_bytes());
+            buf.extend_from_slice(&entry.size.to_le_bytes());
+            buf.extend_from_slice(&entry.mac);
+            buf.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            buf.extend_from_slice(name.as_bytes());
+        }
+        buf
+    }
+
+    fn decode(id: u64, buf: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader { buf, pos: 0 };
+        let count = u32::from_le_bytes(reader.read_array()?);
+        let mut entries = BTreeMap::new();
+        for _ in 0..count {
+            let type_ = match reader.read_array::<1>()?[0] {
+                0 => FileType::File,
+                1 => FileType::Dir,
+                _ => return Err(FsError::NoIntegrity),
+            };
+            let id = u64::from_le_bytes(reader.read_array()?);
+            let size = u64::from_le_bytes(reader.read_array()?);
+            let mac = reader.read_array::<MAC_SIZE>()?;
+            let name_len = u16::from_le_bytes(reader.read_array()?) as usize;
+            let name = core::str::from_utf8(reader.read_slice(name_len)?)
+                .map_err(|_| FsError::NoIntegrity)?;
+            entries.insert(
+                name.to_owned(),
+                EntryMeta {
+                    type_,
+                    id,
+                    size,
+                    mac,
+                },
+            );
+        }
+        Ok(Self { id, entries })
+    }
+}
+
+struct ByteReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        let slice = self
+            .buf
+            .get(self.pos..self.pos + len)
+            .ok_or(FsError::NoIntegrity)?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_slice(N)?);
+        Ok(array)
+    }
+}
+
+fn derive_key(key: &sgx_key_128bit_t, label: &[u8]) -> Result<sgx_key_128bit_t> {
+    rsgx_rijndael128_cmac_slice(key, label).map_err(|_| device_error())
+}
+
+fn device_error() -> FsError {
+    FsError::DeviceError(libc::EIO)
+}
+
+fn new_id() -> Result<u64> {
+    let mut id = [0u8; 8];
+    loop {
+        get_random(&mut id).map_err(|_| device_error())?;
+        // Zero is reserved for the root directory
+        if u64::from_le_bytes(id) != ROOT_DIR_ID {
+            return Ok(u64::from_le_bytes(id));
+        }
+    }
+}
+
+fn block_aad(file_id: u64, idx: usize) -> [u8; 16] {
+    let mut aad = [0u8; 16];
+    aad[..8].copy_from_slice(&file_id.to_le_bytes());
+    aad[8..].copy_from_slice(&(idx as u64).to_le_bytes());
+    aad
+}
+
+fn blocks_of(size: usize) -> usize {
+    (size + BLOCK_SIZE - 1) / BLOCK_SIZE
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.starts_with(RESERVED_PREFIX) {
+        warn!("the names prefixed with {} are reserved", RESERVED_PREFIX);
+        return Err(FsError::PermError);
+    }
+    if name == "." || name == ".." || name.contains('/') {
+        return Err(FsError::InvalidParam);
+    }
+    Ok(())
+}
@@ -1,6 +1,8 @@
+use super::bindfs::BindFS;
 use super::dev_fs;
 use super::hostfs::HostFS;
 use super::procfs::ProcFS;
+use super::protected_hostfs::ProtectedHostFS;
 use super::sefs::{SgxStorage, SgxUuidProvider};
 use super::*;
 use crate::blk::{DevDisk, SwornDiskMeta, DEV_SWORNDISK};
@@ -11,12 +13,14 @@ use crate::time::OcclumTimeProvider;
 
 use alloc::ffi::CString;
 use alloc::sync::Weak;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use ext2_rs::{BlockDevice, Ext2};
 use rcore_fs_mountfs::{MNode, MountFS};
 use rcore_fs_ramfs::RamFS;
 use rcore_fs_sefs::dev::*;
 use rcore_fs_sefs::SEFS;
 use rcore_fs_unionfs::UnionFS;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::untrusted::path::PathEx;
 
@@ -38,6 +42,69 @@ lazy_static! {
     };
     /// All mounted SEFSes
     pub static ref SEFS_MANAGER: GlobalSefsManager = GlobalSefsManager::new();
+    /// All mounted Ext2s, indexed by the name of the underlying disk
+    static ref EXT2_MOUNTS: SgxMutex<HashMap<String, Option<Weak<Ext2>>>> =
+        SgxMutex::new(HashMap::new());
+    /// All non-root mounts, indexed by the address of the mountable FS of the mount
+    static ref MOUNT_TABLE: RwLock<HashMap<usize, MountEntry>> = RwLock::new(HashMap::new());
+}
+
+/// The record of a non-root mount.
+struct MountEntry {
+    /// The mountable FS which wraps the mounted FS
+    mount_fs: Weak<dyn FileSystem>,
+    /// The mounted FS, kept to move the mount
+    fs: Arc<dyn FileSystem>,
+    flags: Arc<MountFlags>,
+}
+
+/// The flags of a non-root mount.
+///
+/// It is shared with the files opened on the mount, so that checking the flags
+/// on reading or writing does not need to look up the mount table.
+#[derive(Debug, Default)]
+pub struct MountFlags {
+    read_only: AtomicBool,
+    // The number of the files opened for writing on the mount
+    writers: AtomicUsize,
+}
+
+impl MountFlags {
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    /// Register a file opened for writing, which fails if the mount is read-only.
+    pub fn get_write(&self) -> Result<()> {
+        self.writers.fetch_add(1, Ordering::SeqCst);
+        if self.is_read_only() {
+            self.put_write();
+            return_errno!(EROFS, "the file system is mounted read-only");
+        }
+        Ok(())
+    }
+
+    /// Unregister a file opened for writing.
+    pub fn put_write(&self) {
+        self.writers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Set the read-only flag, which fails if any file is opened for writing.
+    ///
+    /// Both the flag and the writers are updated before checked by the other,
+    /// so a file being opened either sees the flag or is seen here.
+    fn set_read_only(&self, read_only: bool) -> Result<()> {
+        let was_read_only = self.read_only.swap(read_only, Ordering::SeqCst);
+        if read_only && !was_read_only && self.writers.load(Ordering::SeqCst) > 0 {
+            self.read_only.store(false, Ordering::SeqCst);
+            return_errno!(EBUSY, "some files are opened for writing");
+        }
+        Ok(())
+    }
+}
+
+fn mount_key(mount_fs: &Arc<dyn FileSystem>) -> usize {
+    Arc::as_ptr(mount_fs) as *const u8 as usize
 }
 
 pub fn open_root_fs_according_to(
@@ -86,32 +153,119 @@ pub fn umount_nonroot_fs(
     abs_path: &str,
     follow_symlink: bool,
 ) -> Result<()> {
-    let mount_dir = if follow_symlink {
-        root.lookup_follow(abs_path, MAX_SYMLINKS)?
-    } else {
-        let (dir_path, file_name) = split_path(abs_path);
-        if file_name.ends_with("/") {
-            root.lookup_follow(abs_path, MAX_SYMLINKS)?
-        } else {
-            root.lookup_follow(dir_path, MAX_SYMLINKS)?
-                .lookup(file_name)?
-        }
+    let mount_dir = lookup_mount_dir(root, abs_path, follow_symlink)?;
+    let mount_fs = mount_dir.fs();
+    mount_dir.downcast_ref::<MNode>().unwrap().umount()?;
+    MOUNT_TABLE.write().unwrap().remove(&mount_key(&mount_fs));
+    Ok(())
+}
+
+/// Bind the directory `source` to `abs_path`, so that the same directory tree
+/// is visible at both places.
+pub fn bind_mount_at(
+    source: Arc<dyn INode>,
+    root: &Arc<dyn INode>,
+    abs_path: &str,
+    follow_symlink: bool,
+) -> Result<()> {
+    let bindfs = BindFS::new(source)?;
+    mount_fs_at(bindfs, root, Path::new(abs_path), follow_symlink)
+}
+
+/// Change the read-only flag of the mount at `abs_path`.
+pub fn remount_fs_at(
+    root: &Arc<dyn INode>,
+    abs_path: &str,
+    read_only: bool,
+    follow_symlink: bool,
+) -> Result<()> {
+    let mount_dir = lookup_mount_dir(root, abs_path, follow_symlink)?;
+    if !is_mount_point(&mount_dir)? {
+        return_errno!(EINVAL, "the target is not a mount point");
+    }
+    let mount_fs = mount_dir.fs();
+    if read_only {
+        mount_fs.sync()?;
+    }
+    let mount_table = MOUNT_TABLE.read().unwrap();
+    let entry = mount_table
+        .get(&mount_key(&mount_fs))
+        .ok_or_else(|| errno!(EINVAL, "the target is not a mount point"))?;
+    entry.flags.set_read_only(read_only)
+}
+
+/// Move the mount at `old_path` to `new_path`.
+///
+/// The mounts under the moved mount are not carried along.
+pub fn move_mount(
+    root: &Arc<dyn INode>,
+    old_path: &str,
+    new_path: &str,
+    follow_symlink: bool,
+) -> Result<()> {
+    let old_mount_dir = lookup_mount_dir(root, old_path, follow_symlink)?;
+    if !is_mount_point(&old_mount_dir)? {
+        return_errno!(EINVAL, "the source is not a mount point");
+    }
+    let new_mount_dir = lookup_mount_dir(root, new_path, follow_symlink)?;
+    if is_mount_point(&new_mount_dir)? {
+        return_errno!(EBUSY, "the target is already a mount point");
+    }
+    if Arc::ptr_eq(&new_mount_dir.fs(), &old_mount_dir.fs()) {
+        return_errno!(ELOOP, "the target is under the source mount");
+    }
+
+    let old_mount_fs = old_mount_dir.fs();
+    let (fs, flags) = {
+        let mount_table = MOUNT_TABLE.read().unwrap();
+        let entry = mount_table
+            .get(&mount_key(&old_mount_fs))
+            .ok_or_else(|| errno!(EINVAL, "the source is not a mount point"))?;
+        (entry.fs.clone(), entry.flags.clone())
     };
+    old_mount_dir.downcast_ref::<MNode>().unwrap().umount()?;
+    MOUNT_TABLE
+        .write()
+        .unwrap()
+        .remove(&mount_key(&old_mount_fs));
 
-    mount_dir.downcast_ref::<MNode>().unwrap().umount()?;
+    // The flags are kept for the files opened on the moved mount
+    if let Err(e) = mount_fs_with_flags(
+        fs.clone(),
+        root,
+        Path::new(new_path),
+        follow_symlink,
+        flags.clone(),
+    ) {
+        // Try to restore the mount at the old place
+        mount_fs_with_flags(fs, root, Path::new(old_path), follow_symlink, flags)?;
+        return Err(e);
+    }
     Ok(())
 }
 
+/// Get the flags of the mount which the FS of an inode or a file belongs to.
+///
+/// Returns `None` for the root FS, which is never read-only.
+pub fn mount_flags_of(mount_fs: &Arc<dyn FileSystem>) -> Option<Arc<MountFlags>> {
+    MOUNT_TABLE
+        .read()
+        .unwrap()
+        .get(&mount_key(mount_fs))
+        .map(|entry| entry.flags.clone())
+}
+
 pub fn mount_nonroot_fs_according_to(
     root: &Arc<dyn INode>,
     mount_configs: &Vec<ConfigMount>,
     user_key: &Option<sgx_key_128bit_t>,
     follow_symlink: bool,
 ) -> Result<()> {
-    let mc_ext2_opt = mount_configs
+    let ext2_mcs: Vec<&ConfigMount> = mount_configs
         .iter()
-        .find(|mc| mc.type_ == ConfigMountFsType::TYPE_EXT2);
-    if let Some(mc) = mc_ext2_opt {
+        .filter(|mc| mc.type_ == ConfigMountFsType::TYPE_EXT2)
+        .collect();
+    for mc in &ext2_mcs {
         // Setup disk metadata first if enabled Ext2
         setup_disk_meta_for_ext2(mc, user_key)?;
     }
@@ -146,19 +300,24 @@ pub fn mount_nonroot_fs_according_to(
                     return_errno!(EINVAL, "Source is expected for HostFS");
                 }
 
-                let hostfs = HostFS::new(source_path.unwrap());
-                mount_fs_at(hostfs, root, &mc.target, follow_symlink)?;
+                if mc.options.protected {
+                    let hostfs =
+                        ProtectedHostFS::open(source_path.unwrap(), user_key, &mc.options.mac)?;
+                    mount_fs_at(hostfs, root, &mc.target, follow_symlink)?;
+                } else {
+                    let hostfs = HostFS::new(source_path.unwrap());
+                    mount_fs_at(hostfs, root, &mc.target, follow_symlink)?;
+                }
             }
             TYPE_RAMFS => {
                 let ramfs = RamFS::new();
                 mount_fs_at(ramfs, root, &mc.target, follow_symlink)?;
             }
             TYPE_DEVFS => {
-                let disk_options = if mc_ext2_opt.is_some() {
-                    vec![DevDiskOption::new(DEV_SWORNDISK)]
-                } else {
-                    vec![]
-                };
+                let disk_options: Vec<DevDiskOption> = SwornDiskMeta::names()
+                    .iter()
+                    .map(|name| DevDiskOption::new(name))
+                    .collect();
                 let devfs = dev_fs::init_devfs(&disk_options)?;
                 mount_fs_at(devfs, root, &mc.target, follow_symlink)?;
             }
@@ -198,9 +357,35 @@ pub fn mount_nonroot_fs_according_to(
         }
     }
 
-    if let Some(mc) = mc_ext2_opt {
-        let ext2 = open_ext2()?;
-        mount_fs_at(ext2, root, &mc.target, follow_symlink)?;
+    for mc in ext2_mcs {
+        let disk_name = ext2_disk_name(mc);
+        {
+            let mut ext2_mounts = EXT2_MOUNTS.lock().unwrap();
+            // `None` means the Ext2 is being mounted
+            let is_busy = ext2_mounts.get(disk_name).map_or(false, |ext2| {
+                ext2.as_ref().map_or(true, |ext2| ext2.strong_count() > 0)
+            });
+            if is_busy {
+                return_errno!(EBUSY, "the disk of Ext2 is already mounted");
+            }
+            ext2_mounts.insert(disk_name.to_owned(), None);
+        }
+        // The lock is not held while opening the Ext2, since formatting
+        // the disk with mke2fs may take a long time
+        let res = open_ext2(disk_name).and_then(|ext2| {
+            mount_fs_at(ext2.clone(), root, &mc.target, follow_symlink)?;
+            Ok(ext2)
+        });
+        let mut ext2_mounts = EXT2_MOUNTS.lock().unwrap();
+        match res {
+            Ok(ext2) => {
+                ext2_mounts.insert(disk_name.to_owned(), Some(Arc::downgrade(&ext2)));
+            }
+            Err(e) => {
+                ext2_mounts.remove(disk_name);
+                return Err(e);
+            }
+        }
     }
     Ok(())
 }
@@ -210,10 +395,49 @@ pub fn mount_fs_at(
     parent_inode: &Arc<dyn INode>,
     path: &Path,
     follow_symlink: bool,
+) -> Result<()> {
+    mount_fs_with_flags(fs, parent_inode, path, follow_symlink, Default::default())
+}
+
+fn mount_fs_with_flags(
+    fs: Arc<dyn FileSystem>,
+    parent_inode: &Arc<dyn INode>,
+    path: &Path,
+    follow_symlink: bool,
+    flags: Arc<MountFlags>,
 ) -> Result<()> {
     let path = path
         .to_str()
         .ok_or_else(|| errno!(EINVAL, "invalid path"))?;
+    let mount_dir = lookup_mount_dir(parent_inode, path, follow_symlink)?;
+    if is_mount_point(&mount_dir)? {
+        return_errno!(EBUSY, "the target is already a mount point");
+    }
+    mount_dir
+        .downcast_ref::<MNode>()
+        .unwrap()
+        .mount(fs.clone())?;
+
+    // The lookup crosses the new mount point now
+    let mount_fs = lookup_mount_dir(parent_inode, path, follow_symlink)?.fs();
+    let mut mount_table = MOUNT_TABLE.write().unwrap();
+    mount_table.retain(|_, entry| entry.mount_fs.strong_count() > 0);
+    mount_table.insert(
+        mount_key(&mount_fs),
+        MountEntry {
+            mount_fs: Arc::downgrade(&mount_fs),
+            fs,
+            flags,
+        },
+    );
+    Ok(())
+}
+
+fn lookup_mount_dir(
+    parent_inode: &Arc<dyn INode>,
+    path: &str,
+    follow_symlink: bool,
+) -> Result<Arc<dyn INode>> {
     let mount_dir = if follow_symlink {
         parent_inode.lookup_follow(path, MAX_SYMLINKS)?
     } else {
@@ -226,8 +450,13 @@ pub fn mount_fs_at(
                 .lookup(file_name)?
         }
     };
-    mount_dir.downcast_ref::<MNode>().unwrap().mount(fs)?;
-    Ok(())
+    Ok(mount_dir)
+}
+
+pub fn is_mount_point(inode: &Arc<dyn INode>) -> Result<bool> {
+    // Only the root inode of a mounted FS can be the root of its FS, since
+    // the root directory of the rootfs can not be a mount target.
+    Ok(inode.metadata()?.inode == inode.fs().root_inode().metadata()?.inode)
 }
 
 fn open_or_create_sefs_according_to(
@@ -297,13 +526,13 @@ fn open_or_create_sefs_according_to(
     Ok(sefs)
 }
 
-fn open_ext2() -> Result<Arc<Ext2>> {
-    debug_assert!(SwornDiskMeta::is_setup());
+fn open_ext2(disk_name: &str) -> Result<Arc<Ext2>> {
+    debug_assert!(SwornDiskMeta::is_setup(disk_name));
 
-    let sworndisk = DevDisk::open_or_create(DEV_SWORNDISK)?.disk();
+    let sworndisk = DevDisk::open_or_create(disk_name)?.disk();
     let ext2 = match Ext2::open(sworndisk, Arc::new(OcclumTimeProvider)) {
         Err(e) if e == ext2_rs::FsError::WrongFs => {
-            let sworndisk = format_disk_for_ext2()?;
+            let sworndisk = format_disk_for_ext2(disk_name)?;
             Ext2::open(sworndisk, Arc::new(OcclumTimeProvider))?
         }
         res => res?,
@@ -311,7 +540,7 @@ fn open_ext2() -> Result<Arc<Ext2>> {
     Ok(ext2)
 }
 
-fn format_disk_for_ext2() -> Result<Arc<dyn BlockDevice>> {
+fn format_disk_for_ext2(disk_name: &str) -> Result<Arc<dyn BlockDevice>> {
     // Format the SwornDisk using 'mke2fs' tool for Ext2
     let path = PathBuf::from("/sbin/mke2fs");
     let argv = vec![
@@ -319,23 +548,37 @@ fn format_disk_for_ext2() -> Result<Arc<dyn BlockDevice>> {
         CString::new("-q").unwrap(),
         CString::new("-t").unwrap(),
         CString::new("ext2").unwrap(),
-        CString::new("/dev/".to_owned() + DEV_SWORNDISK).unwrap(),
+        CString::new("/dev/".to_owned() + disk_name).unwrap(),
     ];
     let pid = process::do_spawn(&path.to_str().unwrap(), &argv, &[], &[], None, &current!())?;
     let _ = process::do_wait4(pid as _, core::ptr::null_mut(), 0)?;
 
-    let sworndisk = DevDisk::open_or_create(DEV_SWORNDISK)?.disk();
+    let sworndisk = DevDisk::open_or_create(disk_name)?.disk();
     Ok(sworndisk)
 }
 
 fn setup_disk_meta_for_ext2(mc: &ConfigMount, user_key: &Option<sgx_key_128bit_t>) -> Result<()> {
     debug_assert_eq!(mc.type_, ConfigMountFsType::TYPE_EXT2);
+    let disk_name = ext2_disk_name(mc);
+    if dev_fs::is_builtin_dev(disk_name) {
+        return_errno!(EINVAL, "the disk name conflicts with a device in DevFS");
+    }
     let disk_size = mc.options.disk_size;
+    let source_path = mc.source.as_ref();
+    if SwornDiskMeta::is_setup(disk_name) {
+        // The disk has been set up before, e.g., mounted and then umounted
+        return SwornDiskMeta::check(disk_name, disk_size, user_key, source_path);
+    }
     if disk_size.is_none() {
         return_errno!(EINVAL, "Disk size is expected for Ext2");
     }
-    let source_path = mc.source.as_ref();
-    SwornDiskMeta::setup(disk_size.unwrap(), user_key, source_path)
+    SwornDiskMeta::setup(disk_name, disk_size.unwrap(), user_key, source_path)?;
+    // The disk under the mounted DevFSes is used to format the Ext2
+    dev_fs::register_disk(disk_name)
+}
+
+fn ext2_disk_name(mc: &ConfigMount) -> &str {
+    mc.options.disk.as_deref().unwrap_or(DEV_SWORNDISK)
 }
 
 /// Manage all mounted SEFSes globally.
@@ -0,0 +1,170 @@
+//! The node I/O of the SGX protected files, which back SEFS.
+//!
+//! The protected FS library of the SGX SDK reads and writes a protected file
+//! node by node, each through an ocall on the host `FILE*`. These ocalls are
+//! not imported from the SDK (see Enclave.edl). Instead, they are implemented
+//! here on the host fd of the file: through io_uring if it is enabled, so that
+//! reading or writing a node does not exit the enclave, or through the
+//! `pread64`/`pwrite64` ocalls otherwise.
+//!
+//! The host `FILE*` is only used to open, flush and close the file. Its stdio
+//! buffer is never filled, so the I/O on the host fd is always consistent.
+use crate::io_uring::FILE_IO_URING;
+use crate::prelude::*;
+use sgx_trts::libc::ocall::{pread64, pwrite64};
+
+lazy_static! {
+    // The host fds of the opened protected files, keyed by their host `FILE*`
+    static ref HOST_FDS: Mutex<HashMap<usize, FileDesc>> = Mutex::new(HashMap::new());
+}
+
+/// Read the node at `node_number` of the protected file.
+///
+/// Return 0 on success, the errno on error, or -1 if the node is incomplete,
+/// as the ocall of the SGX SDK does.
+#[no_mangle]
+pub unsafe extern "C" fn u_sgxprotectedfs_fread_node(
+    retval: *mut i32,
+    f: *mut c_void,
+    node_number: u64,
+    buffer: *mut u8,
+    node_size: u32,
+) -> sgx_status_t {
+    let buf = std::slice::from_raw_parts_mut(buffer, node_size as usize);
+    let offset = node_number as usize * node_size as usize;
+    let ret = host_fd_of(f).and_then(|host_fd| read_node(host_fd, buf, offset));
+    *retval = node_io_retval(ret, node_size);
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Write the node at `node_number` of the protected file.
+///
+/// Return 0 on success, the errno on error, or -1 if the node is incomplete,
+/// as the ocall of the SGX SDK does.
+#[no_mangle]
+pub unsafe extern "C" fn u_sgxprotectedfs_fwrite_node(
+    retval: *mut i32,
+    f: *mut c_void,
+    node_number: u64,
+    buffer: *const u8,
+    node_size: u32,
+) -> sgx_status_t {
+    let buf = std::slice::from_raw_parts(buffer, node_size as usize);
+    let offset = node_number as usize * node_size as usize;
+    let ret = host_fd_of(f).and_then(|host_fd| write_node(host_fd, buf, offset));
+    *retval = node_io_retval(ret, node_size);
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Close the protected file.
+#[no_mangle]
+pub unsafe extern "C" fn u_sgxprotectedfs_fclose(retval: *mut i32, f: *mut c_void) -> sgx_status_t {
+    // Forget the host fd before the `FILE*` and the fd can be reused
+    HOST_FDS.lock().remove(&(f as usize));
+    occlum_ocall_sgxfs_fclose(retval, f)
+}
+
+fn node_io_retval(ret: Result<usize>, node_size: u32) -> i32 {
+    match ret {
+        Ok(len) if len == node_size as usize => 0,
+        Ok(_) => -1,
+        Err(e) => e.errno() as i32,
+    }
+}
+
+fn host_fd_of(f: *mut c_void) -> Result<FileDesc> {
+    if f.is_null() {
+        return_errno!(EINVAL, "the protected file is not opened");
+    }
+    let mut host_fds = HOST_FDS.lock();
+    if let Some(host_fd) = host_fds.get(&(f as usize)) {
+        return Ok(*host_fd);
+    }
+
+    let mut host_fd = -1;
+    let sgx_status = unsafe { occlum_ocall_sgxfs_fileno(&mut host_fd, f) };
+    assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
+    if host_fd < 0 {
+        return_errno!(EBADF, "invalid host fd of the protected file");
+    }
+    host_fds.insert(f as usize, host_fd as FileDesc);
+    Ok(host_fd as FileDesc)
+}
+
+/// Read the node until it is full or EOF, returning the length read.
+fn read_node(host_fd: FileDesc, buf: &mut [u8], offset: usize) -> Result<usize> {
+    let mut nread = 0;
+    while nread < buf.len() {
+        let remaining = &mut buf[nread..];
+        let pos = offset + nread;
+        let len = match FILE_IO_URING
+            .as_ref()
+            .and_then(|file_io_uring| file_io_uring.read_at(host_fd, remaining, pos))
+        {
+            Some(ret) => ret?,
+            None => {
+                let ret = unsafe {
+                    pread64(
+                        host_fd as _,
+                        remaining.as_mut_ptr() as _,
+                        remaining.len(),
+                        pos as _,
+                    )
+                };
+                check_host_len(ret, remaining.len(), "pread64 failed")?
+            }
+        };
+        if len == 0 {
+            break;
+        }
+        nread += len;
+    }
+    Ok(nread)
+}
+
+/// Write the whole node, returning the length written.
+fn write_node(host_fd: FileDesc, buf: &[u8], offset: usize) -> Result<usize> {
+    let mut nwritten = 0;
+    while nwritten < buf.len() {
+        let remaining = &buf[nwritten..];
+        let pos = offset + nwritten;
+        let len = match FILE_IO_URING
+            .as_ref()
+            .and_then(|file_io_uring| file_io_uring.write_at(host_fd, remaining, pos))
+        {
+            Some(ret) => ret?,
+            None => {
+                let ret = unsafe {
+                    pwrite64(
+                        host_fd as _,
+                        remaining.as_ptr() as _,
+                        remaining.len(),
+                        pos as _,
+                    )
+                };
+                check_host_len(ret, remaining.len(), "pwrite64 failed")?
+            }
+        };
+        if len == 0 {
+            break;
+        }
+        nwritten += len;
+    }
+    Ok(nwritten)
+}
+
+fn check_host_len(ret: isize, max_len: usize, msg: &'static str) -> Result<usize> {
+    if ret < 0 {
+        return_errno!(Errno::from(libc::errno() as u32), msg);
+    }
+    // Guard against Iago attack
+    if ret as usize > max_len {
+        return_errno!(EIO, "the host returns an invalid length");
+    }
+    Ok(ret as usize)
+}
+
+extern "C" {
+    fn occlum_ocall_sgxfs_fileno(retval: *mut i32, f: *mut c_void) -> sgx_status_t;
+    fn occlum_ocall_sgxfs_fclose(retval: *mut i32, f: *mut c_void) -> sgx_status_t;
+}
@@ -26,6 +26,10 @@ macro_rules! convert_result {
     }};
 }
 
+/// The storage of SEFS, which is backed by the SGX protected files on host.
+///
+/// The nodes of the protected files are read and written through io_uring if
+/// it is enabled (see `sgx_file_ocalls`).
 pub struct SgxStorage {
     path: PathBuf,
     encrypt_mode: EncryptMode,
@@ -4,7 +4,10 @@ use core::cmp;
 use std::io::{BufReader, LineWriter};
 use std::sync::SgxMutex;
 
-use crate::fs::file_ops::{GetWinSize, SetWinSize, TcGets, TcSets};
+use crate::fs::file_ops::{
+    GetForegroundPgrp, GetTtySid, GetWinSize, ReleaseControllingTty, SetControllingTty,
+    SetForegroundPgrp, SetWinSize, TcGets, TcSets,
+};
 use crate::fs::IoctlCmd;
 
 macro_rules! try_libc_stdio {
@@ -386,6 +389,21 @@ fn stdio_ioctl(cmd: &mut dyn IoctlCmd, host_fd: FileDesc) -> Result<()> {
         cmd : GetWinSize => {
             cmd.execute(host_fd)?
         },
+        cmd : GetForegroundPgrp => {
+            cmd.execute()?
+        },
+        cmd : SetForegroundPgrp => {
+            cmd.execute()?
+        },
+        cmd : GetTtySid => {
+            cmd.execute()?
+        },
+        cmd : SetControllingTty => {
+            cmd.execute()?
+        },
+        cmd : ReleaseControllingTty => {
+            cmd.execute()?
+        },
     });
     Ok(())
 }
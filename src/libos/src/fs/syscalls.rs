@@ -1,3 +1,4 @@
+use super::aio;
 use super::event_file::EventCreationFlags;
 use super::file_ops;
 use super::file_ops::{
@@ -8,6 +9,7 @@ use super::fs_ops;
 use super::fs_ops::{MountFlags, MountOptions, UmountFlags};
 use super::time::{clockid_t, itimerspec_t, timespec_t, timeval_t, ClockId};
 use super::timer_file::{TimerCreationFlags, TimerSetFlags};
+use super::uring;
 use super::*;
 use crate::config::{user_rootfs_config, ConfigApp, ConfigMountFsType};
 use util::mem_util::from_user;
@@ -99,6 +101,134 @@ pub fn do_timerfd_gettime(fd: FileDesc, curr_value_ptr: *mut itimerspec_t) -> Re
     Ok(0)
 }
 
+pub fn do_io_setup(nr_events: u32, ctx_ptr: *mut u64) -> Result<isize> {
+    from_user::check_mut_ptr(ctx_ptr)?;
+    if unsafe { ctx_ptr.read() } != 0 {
+        return_errno!(EINVAL, "the context must be initialized to zero");
+    }
+    let ctx_id = aio::do_io_setup(nr_events)?;
+    unsafe {
+        ctx_ptr.write(ctx_id);
+    }
+    Ok(0)
+}
+
+pub fn do_io_destroy(ctx_id: u64) -> Result<isize> {
+    aio::do_io_destroy(ctx_id)?;
+    Ok(0)
+}
+
+pub fn do_io_submit(ctx_id: u64, nr: i64, iocbs_ptr: *const *const iocb) -> Result<isize> {
+    if nr < 0 {
+        return_errno!(EINVAL, "nr is negative");
+    }
+    let nr = nr as usize;
+    if nr == 0 {
+        return Ok(0);
+    }
+    from_user::check_array(iocbs_ptr, nr)?;
+    let iocb_ptrs = unsafe { std::slice::from_raw_parts(iocbs_ptr, nr) };
+    // Stop at the first invalid control block, like a failed submission
+    let mut iocbs = Vec::with_capacity(nr);
+    for iocb_ptr in iocb_ptrs {
+        if let Err(e) = from_user::check_ptr(*iocb_ptr) {
+            if iocbs.is_empty() {
+                return Err(e);
+            }
+            break;
+        }
+        iocbs.push((*iocb_ptr as u64, unsafe { iocb_ptr.read() }));
+    }
+    let nr_submitted = aio::do_io_submit(ctx_id, &iocbs)?;
+    Ok(nr_submitted as isize)
+}
+
+pub fn do_io_getevents(
+    ctx_id: u64,
+    min_nr: i64,
+    nr: i64,
+    events_ptr: *mut io_event,
+    timeout_ptr: *const timespec_t,
+) -> Result<isize> {
+    if min_nr < 0 || nr < 0 || min_nr > nr {
+        return_errno!(EINVAL, "invalid min_nr or nr");
+    }
+    let events: &mut [io_event] = {
+        let nr = nr as usize;
+        if nr == 0 {
+            &mut []
+        } else {
+            from_user::check_mut_array(events_ptr, nr)?;
+            unsafe { std::slice::from_raw_parts_mut(events_ptr, nr) }
+        }
+    };
+    let mut timeout = if timeout_ptr.is_null() {
+        None
+    } else {
+        from_user::check_ptr(timeout_ptr)?;
+        Some(timespec_t::from_raw_ptr(timeout_ptr)?.as_duration())
+    };
+    let nr_got = aio::do_io_getevents(ctx_id, min_nr as usize, events, timeout.as_mut())?;
+    Ok(nr_got as isize)
+}
+
+pub fn do_io_pgetevents(
+    ctx_id: u64,
+    min_nr: i64,
+    nr: i64,
+    events_ptr: *mut io_event,
+    timeout_ptr: *const timespec_t,
+    sigmask: *const c_void,
+) -> Result<isize> {
+    if !sigmask.is_null() {
+        warn!("io_pgetevents sigmask is not supported!");
+    }
+    do_io_getevents(ctx_id, min_nr, nr, events_ptr, timeout_ptr)
+}
+
+pub fn do_io_cancel(ctx_id: u64, iocb_ptr: *const iocb, _result: *mut io_event) -> Result<isize> {
+    from_user::check_ptr(iocb_ptr)?;
+    let iocb = unsafe { iocb_ptr.read() };
+    aio::do_io_cancel(ctx_id, iocb_ptr as u64, &iocb)?;
+    // Like Linux, the result is always delivered as a completion event
+    return_errno!(EINPROGRESS, "the request is being cancelled");
+}
+
+pub fn do_io_uring_setup(entries: u32, params_ptr: *mut io_uring_params) -> Result<isize> {
+    from_user::check_mut_ptr(params_ptr)?;
+    let mut params = unsafe { params_ptr.read() };
+    let fd = uring::do_io_uring_setup(entries, &mut params)?;
+    unsafe {
+        params_ptr.write(params);
+    }
+    Ok(fd as isize)
+}
+
+pub fn do_io_uring_enter(
+    fd: FileDesc,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+    sigmask: *const c_void,
+    _sigsz: size_t,
+) -> Result<isize> {
+    if !sigmask.is_null() {
+        warn!("io_uring_enter sigmask is not supported!");
+    }
+    let nr_submitted = uring::do_io_uring_enter(fd, to_submit, min_complete, flags)?;
+    Ok(nr_submitted as isize)
+}
+
+pub fn do_io_uring_register(
+    fd: FileDesc,
+    opcode: u32,
+    arg: *const c_void,
+    nr_args: u32,
+) -> Result<isize> {
+    uring::do_io_uring_register(fd, opcode, arg as *const u8, nr_args)?;
+    Ok(0)
+}
+
 pub fn do_creat(path: *const i8, mode: u16) -> Result<isize> {
     let flags =
         AccessMode::O_WRONLY as u32 | (CreationFlags::O_CREAT | CreationFlags::O_TRUNC).bits();
@@ -437,6 +567,35 @@ pub fn do_chdir(path: *const i8) -> Result<isize> {
     Ok(0)
 }
 
+pub fn do_chroot(path: *const i8) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    if path.is_empty() {
+        return_errno!(ENOENT, "path is an empty string");
+    } else if path.len() > PATH_MAX {
+        return_errno!(ENAMETOOLONG, "path name too long");
+    }
+    fs_ops::do_chroot(&path)?;
+    Ok(0)
+}
+
+pub fn do_pivot_root(new_root: *const i8, put_old: *const i8) -> Result<isize> {
+    let new_root = from_user::clone_cstring_safely(new_root)?
+        .to_string_lossy()
+        .into_owned();
+    let put_old = from_user::clone_cstring_safely(put_old)?
+        .to_string_lossy()
+        .into_owned();
+    if new_root.is_empty() || put_old.is_empty() {
+        return_errno!(ENOENT, "path is an empty string");
+    } else if new_root.len() > PATH_MAX || put_old.len() > PATH_MAX {
+        return_errno!(ENAMETOOLONG, "path name too long");
+    }
+    fs_ops::do_pivot_root(&new_root, &put_old)?;
+    Ok(0)
+}
+
 pub fn do_fchdir(fd: FileDesc) -> Result<isize> {
     let path = get_abs_path_by_fd(fd)?;
     fs_ops::do_chdir(&path)?;
@@ -761,6 +920,22 @@ pub fn do_mount(
         return_errno!(ENAMETOOLONG, "target name too long");
     }
     let flags = MountFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    // The fs type and options are ignored when changing an existing mount
+    if flags.contains(MountFlags::MS_REMOUNT) {
+        fs_ops::do_remount(&target, flags)?;
+        return Ok(0);
+    } else if flags.contains(MountFlags::MS_BIND) {
+        fs_ops::do_bind_mount(&source, &target, flags)?;
+        return Ok(0);
+    } else if flags.contains(MountFlags::MS_MOVE) {
+        fs_ops::do_move_mount(&source, &target, flags)?;
+        return Ok(0);
+    } else if flags.is_propagation_change() {
+        // There is only one mount namespace, so the propagation type has no effect
+        warn!("the propagation type of mounts is ignored");
+        return Ok(0);
+    }
+
     let mount_options = {
         let fs_type = {
             let fs_type = from_user::clone_cstring_safely(fs_type)?
@@ -0,0 +1,221 @@
+use crate::prelude::*;
+
+pub const IORING_OP_NOP: u8 = 0;
+pub const IORING_OP_READV: u8 = 1;
+pub const IORING_OP_WRITEV: u8 = 2;
+pub const IORING_OP_FSYNC: u8 = 3;
+pub const IORING_OP_READ_FIXED: u8 = 4;
+pub const IORING_OP_WRITE_FIXED: u8 = 5;
+pub const IORING_OP_POLL_ADD: u8 = 6;
+pub const IORING_OP_POLL_REMOVE: u8 = 7;
+pub const IORING_OP_SENDMSG: u8 = 9;
+pub const IORING_OP_RECVMSG: u8 = 10;
+pub const IORING_OP_TIMEOUT: u8 = 11;
+pub const IORING_OP_TIMEOUT_REMOVE: u8 = 12;
+pub const IORING_OP_ACCEPT: u8 = 13;
+pub const IORING_OP_ASYNC_CANCEL: u8 = 14;
+pub const IORING_OP_CONNECT: u8 = 16;
+pub const IORING_OP_READ: u8 = 22;
+pub const IORING_OP_WRITE: u8 = 23;
+pub const IORING_OP_SEND: u8 = 26;
+pub const IORING_OP_RECV: u8 = 27;
+/// The largest opcode known by the probe
+pub const IORING_OP_LAST: u8 = IORING_OP_RECV;
+
+/// The opcodes that are supported
+pub const SUPPORTED_OPS: &[u8] = &[
+    IORING_OP_NOP,
+    IORING_OP_READV,
+    IORING_OP_WRITEV,
+    IORING_OP_FSYNC,
+    IORING_OP_READ_FIXED,
+    IORING_OP_WRITE_FIXED,
+    IORING_OP_POLL_ADD,
+    IORING_OP_POLL_REMOVE,
+    IORING_OP_SENDMSG,
+    IORING_OP_RECVMSG,
+    IORING_OP_TIMEOUT,
+    IORING_OP_TIMEOUT_REMOVE,
+    IORING_OP_ACCEPT,
+    IORING_OP_ASYNC_CANCEL,
+    IORING_OP_CONNECT,
+    IORING_OP_READ,
+    IORING_OP_WRITE,
+    IORING_OP_SEND,
+    IORING_OP_RECV,
+];
+
+/// The offsets of the rings to be passed to mmap
+pub const IORING_OFF_SQ_RING: usize = 0;
+pub const IORING_OFF_CQ_RING: usize = 0x8000000;
+pub const IORING_OFF_SQES: usize = 0x10000000;
+
+pub const IORING_MAX_ENTRIES: u32 = 32768;
+pub const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+
+pub const IORING_FSYNC_DATASYNC: u32 = 1 << 0;
+pub const IORING_TIMEOUT_ABS: u32 = 1 << 0;
+
+pub const IORING_REGISTER_BUFFERS: u32 = 0;
+pub const IORING_UNREGISTER_BUFFERS: u32 = 1;
+pub const IORING_REGISTER_FILES: u32 = 2;
+pub const IORING_UNREGISTER_FILES: u32 = 3;
+pub const IORING_REGISTER_EVENTFD: u32 = 4;
+pub const IORING_UNREGISTER_EVENTFD: u32 = 5;
+pub const IORING_REGISTER_EVENTFD_ASYNC: u32 = 7;
+pub const IORING_REGISTER_PROBE: u32 = 8;
+
+pub const IO_URING_OP_SUPPORTED: u16 = 1 << 0;
+
+bitflags! {
+    pub struct SetupFlags: u32 {
+        const IORING_SETUP_IOPOLL       = 1 << 0;
+        const IORING_SETUP_SQPOLL       = 1 << 1;
+        const IORING_SETUP_SQ_AFF       = 1 << 2;
+        const IORING_SETUP_CQSIZE       = 1 << 3;
+        const IORING_SETUP_CLAMP        = 1 << 4;
+        const IORING_SETUP_ATTACH_WQ    = 1 << 5;
+        const IORING_SETUP_R_DISABLED   = 1 << 6;
+    }
+}
+
+bitflags! {
+    pub struct Features: u32 {
+        const IORING_FEAT_SINGLE_MMAP       = 1 << 0;
+        const IORING_FEAT_NODROP            = 1 << 1;
+        const IORING_FEAT_SUBMIT_STABLE     = 1 << 2;
+        const IORING_FEAT_RW_CUR_POS        = 1 << 3;
+        const IORING_FEAT_FAST_POLL         = 1 << 5;
+    }
+}
+
+bitflags! {
+    pub struct EnterFlags: u32 {
+        const IORING_ENTER_GETEVENTS    = 1 << 0;
+        const IORING_ENTER_SQ_WAKEUP    = 1 << 1;
+        const IORING_ENTER_SQ_WAIT      = 1 << 2;
+        const IORING_ENTER_EXT_ARG      = 1 << 3;
+    }
+}
+
+bitflags! {
+    pub struct SqeFlags: u8 {
+        const IOSQE_FIXED_FILE      = 1 << 0;
+        const IOSQE_IO_DRAIN        = 1 << 1;
+        const IOSQE_IO_LINK         = 1 << 2;
+        const IOSQE_IO_HARDLINK     = 1 << 3;
+        const IOSQE_ASYNC           = 1 << 4;
+        const IOSQE_BUFFER_SELECT   = 1 << 5;
+    }
+}
+
+/// The flag in `sq_flags` telling that the CQ ring has overflowed
+pub const IORING_SQ_CQ_OVERFLOW: u32 = 1 << 1;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_sqring_offsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub flags: u32,
+    pub dropped: u32,
+    pub array: u32,
+    pub resv1: u32,
+    pub resv2: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_cqring_offsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub overflow: u32,
+    pub cqes: u32,
+    pub flags: u32,
+    pub resv1: u32,
+    pub resv2: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_params {
+    pub sq_entries: u32,
+    pub cq_entries: u32,
+    pub flags: u32,
+    pub sq_thread_cpu: u32,
+    pub sq_thread_idle: u32,
+    pub features: u32,
+    pub wq_fd: u32,
+    pub resv: [u32; 3],
+    pub sq_off: io_sqring_offsets,
+    pub cq_off: io_cqring_offsets,
+}
+
+/// The submission queue entry.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_sqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    /// The offset, or `addr2` for some opcodes
+    pub off: u64,
+    pub addr: u64,
+    pub len: u32,
+    /// The union of the flags specific to the opcodes, e.g., `rw_flags`,
+    /// `poll32_events` and `msg_flags`
+    pub op_flags: u32,
+    pub user_data: u64,
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pub pad: [u64; 2],
+}
+
+/// The completion queue entry.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_cqe {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_probe {
+    pub last_op: u8,
+    pub ops_len: u8,
+    pub resv: u16,
+    pub resv2: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_probe_op {
+    pub op: u8,
+    pub resv: u8,
+    pub flags: u16,
+    pub resv2: u32,
+}
+
+/// The `__kernel_timespec` used by the timeout requests.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct kernel_timespec {
+    pub tv_sec: i64,
+    pub tv_nsec: i64,
+}
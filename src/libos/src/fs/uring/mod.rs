@@ -0,0 +1,255 @@
+//! The io_uring interface for applications.
+//!
+//! The io_uring of an application is emulated in the LibOS, rather than being
+//! backed by an io_uring of the host, since the buffers of the requests are in
+//! the enclave. The requests are executed through the `File` trait, so that
+//! the I/O of the files and sockets that are built upon the io_uring of the
+//! host, e.g., the files of HostFS and the sockets, is still done by the host
+//! io_uring, only with the extra copying.
+//!
+//! The rings are allocated in the user space of the process at setup. As a
+//! result, they can be accessed by the LibOS directly, and mapping the fd of
+//! the io_uring just returns the address of the rings. After the rings are
+//! unmapped or the process exits, the io_uring is detached from the rings.
+//! And the rings cannot be protected so that the LibOS cannot access them.
+//!
+//! Since there is no SQ polling thread, the requests that would block are
+//! executed by the threads calling `io_uring_enter` once the files are ready.
+//! For the same reason, the timeout requests only expire when some thread is
+//! waiting for CQEs.
+
+use std::sync::Weak;
+
+use self::c_types::*;
+use crate::fs::AsEvent;
+use crate::prelude::*;
+use crate::util::mem_util::from_user;
+use crate::vm::{VMPerms, VMRange};
+
+pub use self::c_types::io_uring_params;
+pub use self::uring_file::{AsIoUring, IoUringFile};
+
+mod c_types;
+mod request;
+mod ring;
+mod uring_file;
+
+/// The maximal number of the registered buffers.
+const IORING_MAX_FIXED_BUFS: usize = 1024;
+
+lazy_static! {
+    /// The io_urings of all processes, whose rings are in the user space of the
+    /// processes.
+    static ref IO_URINGS: Mutex<Vec<(pid_t, Weak<IoUringFile>)>> = Mutex::new(Vec::new());
+}
+
+pub fn do_io_uring_setup(entries: u32, params: &mut io_uring_params) -> Result<FileDesc> {
+    debug!("io_uring_setup: entries: {}, params: {:?}", entries, params);
+    let flags =
+        SetupFlags::from_bits(params.flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let unsupported_flags = SetupFlags::IORING_SETUP_IOPOLL
+        | SetupFlags::IORING_SETUP_SQPOLL
+        | SetupFlags::IORING_SETUP_SQ_AFF
+        | SetupFlags::IORING_SETUP_ATTACH_WQ
+        | SetupFlags::IORING_SETUP_R_DISABLED;
+    if flags.intersects(unsupported_flags) {
+        return_errno!(EINVAL, "the flags are not supported");
+    }
+    if params.resv.iter().any(|resv| *resv != 0) {
+        return_errno!(EINVAL, "reserved fields must be zero");
+    }
+
+    let is_clamp = flags.contains(SetupFlags::IORING_SETUP_CLAMP);
+    let sq_entries = round_entries(entries, IORING_MAX_ENTRIES, is_clamp)?;
+    let cq_entries = if flags.contains(SetupFlags::IORING_SETUP_CQSIZE) {
+        let cq_entries = round_entries(params.cq_entries, IORING_MAX_CQ_ENTRIES, is_clamp)?;
+        if cq_entries < sq_entries {
+            return_errno!(EINVAL, "the CQ ring is smaller than the SQ ring");
+        }
+        cq_entries
+    } else {
+        2 * sq_entries
+    };
+
+    let uring = IoUringFile::new(sq_entries, cq_entries)?;
+    uring.fill_params(params);
+    params.features = (Features::IORING_FEAT_SINGLE_MMAP
+        | Features::IORING_FEAT_NODROP
+        | Features::IORING_FEAT_SUBMIT_STABLE
+        | Features::IORING_FEAT_RW_CUR_POS
+        | Features::IORING_FEAT_FAST_POLL)
+        .bits();
+
+    let current = current!();
+    {
+        let mut io_urings = IO_URINGS.lock();
+        io_urings.retain(|(_, uring)| uring.strong_count() > 0);
+        io_urings.push((current.process().pid(), Arc::downgrade(&uring)));
+    }
+    // Like Linux, the fd of an io_uring is close-on-exec
+    let fd = current.add_file(uring, true);
+    Ok(fd)
+}
+
+pub fn do_io_uring_enter(
+    fd: FileDesc,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+) -> Result<usize> {
+    debug!(
+        "io_uring_enter: fd: {}, to_submit: {}, min_complete: {}, flags: {:#x}",
+        fd, to_submit, min_complete, flags
+    );
+    let flags = EnterFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    if flags.contains(EnterFlags::IORING_ENTER_EXT_ARG) {
+        return_errno!(EINVAL, "the extended arguments are not supported");
+    }
+    let file = current!().file(fd)?;
+    let uring = file.as_io_uring()?;
+    uring.enter(to_submit, min_complete, flags)
+}
+
+pub fn do_io_uring_register(fd: FileDesc, opcode: u32, arg: *const u8, nr_args: u32) -> Result<()> {
+    debug!(
+        "io_uring_register: fd: {}, opcode: {}, arg: {:?}, nr_args: {}",
+        fd, opcode, arg, nr_args
+    );
+    let current = current!();
+    let file = current.file(fd)?;
+    let uring = file.as_io_uring()?;
+    let nr_args = nr_args as usize;
+    match opcode {
+        IORING_REGISTER_BUFFERS => {
+            if nr_args == 0 || nr_args > IORING_MAX_FIXED_BUFS {
+                return_errno!(EINVAL, "invalid number of buffers");
+            }
+            let iovs = arg as *const libc::iovec;
+            from_user::check_array(iovs, nr_args)?;
+            let buffers = unsafe { std::slice::from_raw_parts(iovs, nr_args) }
+                .iter()
+                .map(|iov| {
+                    let base = iov.iov_base as usize;
+                    from_user::check_array(base as *const u8, iov.iov_len)?;
+                    Ok(base..base + iov.iov_len)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            uring.register_buffers(buffers)
+        }
+        IORING_UNREGISTER_BUFFERS => {
+            if !arg.is_null() || nr_args != 0 {
+                return_errno!(EINVAL, "invalid arguments");
+            }
+            uring.unregister_buffers()
+        }
+        IORING_REGISTER_EVENTFD | IORING_REGISTER_EVENTFD_ASYNC => {
+            if nr_args != 1 {
+                return_errno!(EINVAL, "invalid number of eventfds");
+            }
+            let fd_ptr = arg as *const i32;
+            from_user::check_ptr(fd_ptr)?;
+            let eventfd = current.file(unsafe { fd_ptr.read() } as FileDesc)?;
+            eventfd
+                .as_event()
+                .map_err(|_| errno!(EINVAL, "not an eventfd"))?;
+            uring.register_eventfd(eventfd, opcode == IORING_REGISTER_EVENTFD_ASYNC)
+        }
+        IORING_UNREGISTER_EVENTFD => {
+            if !arg.is_null() || nr_args != 0 {
+                return_errno!(EINVAL, "invalid arguments");
+            }
+            uring.unregister_eventfd()
+        }
+        IORING_REGISTER_PROBE => {
+            if nr_args > 256 {
+                return_errno!(EINVAL, "too many probe ops");
+            }
+            let probe_ptr = arg as *mut io_uring_probe;
+            from_user::check_mut_ptr(probe_ptr)?;
+            let ops_ptr = unsafe { probe_ptr.add(1) } as *mut io_uring_probe_op;
+            let ops_len = nr_args.min(IORING_OP_LAST as usize + 1);
+            if ops_len > 0 {
+                from_user::check_mut_array(ops_ptr, ops_len)?;
+            }
+            let probe = io_uring_probe {
+                last_op: IORING_OP_LAST,
+                ops_len: ops_len as u8,
+                ..Default::default()
+            };
+            unsafe { probe_ptr.write(probe) };
+            for op in 0..ops_len {
+                let flags = if SUPPORTED_OPS.contains(&(op as u8)) {
+                    IO_URING_OP_SUPPORTED
+                } else {
+                    0
+                };
+                let probe_op = io_uring_probe_op {
+                    op: op as u8,
+                    flags,
+                    ..Default::default()
+                };
+                unsafe { ops_ptr.add(op).write(probe_op) };
+            }
+            Ok(())
+        }
+        _ => return_errno!(EINVAL, "the opcode is not supported"),
+    }
+}
+
+/// Check whether the rings of the io_urings of a process in the range can be
+/// protected with the permissions, which must still allow the LibOS to access
+/// the rings.
+pub fn check_io_urings_mprotect(pid: pid_t, range: &VMRange, perms: VMPerms) -> Result<()> {
+    let io_urings = IO_URINGS.lock();
+    let is_accessible = io_urings.iter().all(|(uring_pid, uring)| {
+        *uring_pid != pid
+            || uring
+                .upgrade()
+                .map_or(true, |uring| uring.is_accessible_with(range, perms))
+    });
+    if !is_accessible {
+        return_errno!(EACCES, "the rings of io_uring must be accessible");
+    }
+    Ok(())
+}
+
+/// Detach the io_urings of a process whose rings are in the range to be
+/// unmapped.
+pub fn detach_io_urings_in_range(pid: pid_t, range: &VMRange) {
+    for uring in take_io_urings(|uring_pid, uring| uring_pid == pid && uring.overlap_with(range)) {
+        uring.detach();
+    }
+}
+
+/// Detach the io_urings of a process when it exits, before the user space of
+/// the process is freed.
+pub fn release_io_urings_when_process_exit(pid: pid_t) {
+    for uring in take_io_urings(|uring_pid, _| uring_pid == pid) {
+        uring.detach();
+    }
+}
+
+fn take_io_urings(mut filter: impl FnMut(pid_t, &IoUringFile) -> bool) -> Vec<Arc<IoUringFile>> {
+    let mut taken = Vec::new();
+    let mut io_urings = IO_URINGS.lock();
+    io_urings.retain(|(pid, uring)| match uring.upgrade() {
+        Some(uring) if filter(*pid, &uring) => {
+            taken.push(uring);
+            false
+        }
+        Some(_) => true,
+        None => false,
+    });
+    taken
+}
+
+/// Round up the number of entries to a power of two.
+fn round_entries(entries: u32, max_entries: u32, is_clamp: bool) -> Result<u32> {
+    if entries == 0 {
+        return_errno!(EINVAL, "the number of entries is zero");
+    }
+    if entries > max_entries && !is_clamp {
+        return_errno!(EINVAL, "too many entries");
+    }
+    Ok(entries.min(max_entries).next_power_of_two())
+}
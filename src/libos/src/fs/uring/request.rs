@@ -0,0 +1,301 @@
+use std::ptr;
+use std::time::Duration;
+
+use super::c_types::*;
+use super::uring_file::IoUringFile;
+use crate::fs::{IoEvents, PipeType};
+use crate::net::{
+    do_accept4_nonblocking, do_connect_nonblocking, do_recvfrom, do_recvmsg, do_sendmsg, do_sendto,
+    is_socket, socket_recv_nonblocking, socket_send_nonblocking, HOST_POLLER,
+};
+use crate::prelude::*;
+use crate::util::mem_util::from_user;
+use crate::vm::PAGE_SIZE;
+
+/// The maximal number of iovecs of a request.
+const IOV_MAX: usize = 1024;
+/// The maximal number of bytes transferred by a request, which is the same as
+/// Linux, so that the result always fits in the `res` of the CQE.
+const MAX_RW_COUNT: usize = i32::MAX as usize & !(PAGE_SIZE - 1);
+
+/// Issue a request popped from the SQ ring.
+///
+/// The request is executed without blocking, and completes at once unless it
+/// has to wait for the file to be ready. The errors are reported by the CQE of
+/// the request.
+pub fn issue(ring: &IoUringFile, sqe: io_uring_sqe) {
+    if let Err(e) = do_issue(ring, &sqe) {
+        ring.complete(sqe.user_data, to_res(Err(e)), false);
+    }
+}
+
+fn do_issue(ring: &IoUringFile, sqe: &io_uring_sqe) -> Result<()> {
+    let flags = SqeFlags::from_bits(sqe.flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    if !(flags - SqeFlags::IOSQE_ASYNC).is_empty() {
+        return_errno!(EINVAL, "the flags are not supported");
+    }
+
+    match sqe.opcode {
+        IORING_OP_NOP => ring.complete(sqe.user_data, 0, false),
+        IORING_OP_TIMEOUT => {
+            if sqe.len != 1 || sqe.op_flags & !IORING_TIMEOUT_ABS != 0 {
+                return_errno!(EINVAL, "invalid timeout request");
+            }
+            let ts_ptr = sqe.addr as *const kernel_timespec;
+            from_user::check_ptr(ts_ptr)?;
+            let ts = unsafe { ts_ptr.read() };
+            if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
+                return_errno!(EINVAL, "invalid timespec");
+            }
+            let duration = Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32);
+            let is_abs = sqe.op_flags & IORING_TIMEOUT_ABS != 0;
+            ring.add_timeout(sqe.user_data, duration, is_abs, sqe.off)?;
+        }
+        IORING_OP_TIMEOUT_REMOVE => {
+            ring.remove_timeout(sqe.addr)?;
+            ring.complete(sqe.user_data, 0, false);
+        }
+        IORING_OP_POLL_REMOVE => {
+            ring.cancel(sqe.addr, true)?;
+            ring.complete(sqe.user_data, 0, false);
+        }
+        IORING_OP_ASYNC_CANCEL => {
+            ring.cancel(sqe.addr, false)?;
+            ring.complete(sqe.user_data, 0, false);
+        }
+        IORING_OP_POLL_ADD => {
+            let file = current!().file(sqe.fd as FileDesc)?;
+            let mask = IoEvents::from_raw(sqe.op_flags as u16 as u32);
+            if file.notifier().is_none() {
+                // Like Linux, the files without poll support are always ready
+                let events = (IoEvents::IN | IoEvents::OUT) & mask;
+                ring.complete(sqe.user_data, events.to_raw() as i32, false);
+                return Ok(());
+            }
+            ring.add_pending(file, *sqe, mask)?;
+        }
+        _ => {
+            let file = current!().file(sqe.fd as FileDesc)?;
+            let wait_mask = wait_events(sqe.opcode).filter(|_| file.notifier().is_some());
+            match execute(ring, &file, sqe) {
+                Err(e) if e.errno() == EAGAIN && wait_mask.is_some() => {
+                    ring.add_pending(file, *sqe, wait_mask.unwrap())?;
+                }
+                res => ring.complete(sqe.user_data, to_res(res), false),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Execute a request on the file, which is given by the fd of the request.
+///
+/// The request never blocks. `EAGAIN` is returned if it would block, or if a
+/// connect is in progress.
+///
+/// The socket requests are executed through the syscalls, which look up the
+/// file again.
+pub fn execute(ring: &IoUringFile, file: &FileRef, sqe: &io_uring_sqe) -> Result<usize> {
+    let fd = sqe.fd;
+    let addr = sqe.addr as usize;
+    let len = sqe.len as usize;
+    let flags = sqe.op_flags as i32;
+    let nonblocking_flags = flags | libc::MSG_DONTWAIT;
+    let res = match sqe.opcode {
+        IORING_OP_READ | IORING_OP_READ_FIXED => {
+            if sqe.opcode == IORING_OP_READ_FIXED {
+                ring.check_fixed_buffer(sqe.buf_index, &(addr..addr.saturating_add(len)))?;
+            }
+            let buf = user_buf_mut(addr as *mut u8, len.min(MAX_RW_COUNT))?;
+            if is_cur_pos(file, sqe.off) {
+                read_nonblocking(file, &mut [buf])?
+            } else {
+                file.read_at(sqe.off as usize, buf)?
+            }
+        }
+        IORING_OP_WRITE | IORING_OP_WRITE_FIXED => {
+            if sqe.opcode == IORING_OP_WRITE_FIXED {
+                ring.check_fixed_buffer(sqe.buf_index, &(addr..addr.saturating_add(len)))?;
+            }
+            let buf = user_buf(addr as *const u8, len.min(MAX_RW_COUNT))?;
+            if is_cur_pos(file, sqe.off) {
+                write_nonblocking(file, &[buf])?
+            } else {
+                file.write_at(sqe.off as usize, buf)?
+            }
+        }
+        IORING_OP_READV => {
+            let mut bufs = user_iovecs(addr as *const libc::iovec, len)?;
+            if is_cur_pos(file, sqe.off) {
+                read_nonblocking(file, &mut bufs)?
+            } else {
+                file.preadv(&mut bufs, sqe.off as usize)?
+            }
+        }
+        IORING_OP_WRITEV => {
+            let bufs = user_iovecs(addr as *const libc::iovec, len)?;
+            let bufs: Vec<&[u8]> = bufs.into_iter().map(|buf| &*buf).collect();
+            if is_cur_pos(file, sqe.off) {
+                write_nonblocking(file, &bufs)?
+            } else {
+                file.pwritev(&bufs, sqe.off as usize)?
+            }
+        }
+        IORING_OP_FSYNC => {
+            if sqe.op_flags & !IORING_FSYNC_DATASYNC != 0 {
+                return_errno!(EINVAL, "invalid fsync flags");
+            }
+            if sqe.op_flags & IORING_FSYNC_DATASYNC != 0 {
+                file.sync_data()?;
+            } else {
+                file.sync_all()?;
+            }
+            0
+        }
+        IORING_OP_SEND => do_sendto(
+            fd,
+            addr as *const libc::c_void,
+            len.min(MAX_RW_COUNT),
+            nonblocking_flags,
+            ptr::null(),
+            0,
+        )? as usize,
+        IORING_OP_RECV => do_recvfrom(
+            fd,
+            addr as *mut libc::c_void,
+            len.min(MAX_RW_COUNT),
+            nonblocking_flags,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )? as usize,
+        IORING_OP_SENDMSG => {
+            do_sendmsg(fd, addr as *const libc::msghdr, nonblocking_flags)? as usize
+        }
+        IORING_OP_RECVMSG => do_recvmsg(fd, addr as *mut libc::msghdr, nonblocking_flags)? as usize,
+        IORING_OP_ACCEPT => do_accept4_nonblocking(
+            fd,
+            addr as *mut libc::sockaddr,
+            sqe.off as usize as *mut libc::socklen_t,
+            flags,
+        )? as usize,
+        IORING_OP_CONNECT => do_connect_nonblocking(
+            fd,
+            addr as *const libc::sockaddr,
+            sqe.off as libc::socklen_t,
+        )
+        .map_err(|e| match e.errno() {
+            EINPROGRESS | EALREADY => errno!(EAGAIN, "the connect is in progress"),
+            _ => e,
+        })? as usize,
+        _ => return_errno!(EINVAL, "the opcode is not supported"),
+    };
+    Ok(res)
+}
+
+/// Convert the result of a request to the `res` of its CQE.
+pub fn to_res(res: Result<usize>) -> i32 {
+    match res {
+        Ok(len) => len.min(i32::MAX as usize) as i32,
+        Err(e) => -(e.errno() as i32),
+    }
+}
+
+/// The events that a request waits for if it would block.
+fn wait_events(opcode: u8) -> Option<IoEvents> {
+    match opcode {
+        IORING_OP_READ | IORING_OP_READ_FIXED | IORING_OP_READV | IORING_OP_RECV
+        | IORING_OP_RECVMSG | IORING_OP_ACCEPT => Some(IoEvents::IN),
+        IORING_OP_WRITE
+        | IORING_OP_WRITE_FIXED
+        | IORING_OP_WRITEV
+        | IORING_OP_SEND
+        | IORING_OP_SENDMSG
+        | IORING_OP_CONNECT => Some(IoEvents::OUT),
+        _ => None,
+    }
+}
+
+/// Read from the file at its current position without blocking.
+///
+/// The host files other than the sockets have no way to be read without
+/// blocking unless they are non-blocking, so they are read only if they are
+/// ready. It may still block in the rare case that the host file is drained by
+/// another thread in between.
+fn read_nonblocking(file: &FileRef, bufs: &mut [&mut [u8]]) -> Result<usize> {
+    if is_socket(file) {
+        return socket_recv_nonblocking(file, bufs);
+    }
+    if let Ok(pipe_reader) = file.as_pipe_reader() {
+        return pipe_reader.try_readv(bufs);
+    }
+    if file.host_fd().is_some() && HOST_POLLER.poll_now(file, IoEvents::IN)?.is_empty() {
+        return_errno!(EAGAIN, "the host file is not readable");
+    }
+    match bufs {
+        [buf] => file.read(buf),
+        _ => file.readv(bufs),
+    }
+}
+
+/// Write to the file at its current position without blocking.
+///
+/// See `read_nonblocking` for the host files.
+fn write_nonblocking(file: &FileRef, bufs: &[&[u8]]) -> Result<usize> {
+    if is_socket(file) {
+        return socket_send_nonblocking(file, bufs);
+    }
+    if let Ok(pipe_writer) = file.as_pipe_writer() {
+        return pipe_writer.try_writev(bufs);
+    }
+    if file.host_fd().is_some() && HOST_POLLER.poll_now(file, IoEvents::OUT)?.is_empty() {
+        return_errno!(EAGAIN, "the host file is not writable");
+    }
+    match bufs {
+        [buf] => file.write(buf),
+        _ => file.writev(bufs),
+    }
+}
+
+/// Whether to do I/O at the current position of the file rather than at the
+/// offset. Like Linux, the offset is -1 for the current position, and is
+/// ignored for the stream-like files, e.g., pipes and sockets.
+fn is_cur_pos(file: &FileRef, offset: u64) -> bool {
+    offset == u64::MAX || file.position().is_err()
+}
+
+fn user_buf_mut<'a>(buf_ptr: *mut u8, nbytes: usize) -> Result<&'a mut [u8]> {
+    if nbytes == 0 {
+        return Ok(&mut []);
+    }
+    from_user::check_mut_array(buf_ptr, nbytes)?;
+    Ok(unsafe { std::slice::from_raw_parts_mut(buf_ptr, nbytes) })
+}
+
+fn user_buf<'a>(buf_ptr: *const u8, nbytes: usize) -> Result<&'a [u8]> {
+    if nbytes == 0 {
+        return Ok(&[]);
+    }
+    from_user::check_array(buf_ptr, nbytes)?;
+    Ok(unsafe { std::slice::from_raw_parts(buf_ptr, nbytes) })
+}
+
+fn user_iovecs<'a>(iov: *const libc::iovec, count: usize) -> Result<Vec<&'a mut [u8]>> {
+    if count > IOV_MAX {
+        return_errno!(EINVAL, "too many iovecs");
+    }
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    from_user::check_array(iov, count)?;
+    let iovs = unsafe { std::slice::from_raw_parts(iov, count) };
+    // Like Linux, the total length is truncated to MAX_RW_COUNT
+    let mut remaining = MAX_RW_COUNT;
+    iovs.iter()
+        .filter(|iov| iov.iov_len != 0)
+        .map(|iov| {
+            let len = iov.iov_len.min(remaining);
+            remaining -= len;
+            user_buf_mut(iov.iov_base as *mut u8, len)
+        })
+        .collect()
+}
@@ -0,0 +1,205 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::c_types::*;
+use crate::prelude::*;
+use crate::vm::{MMapFlags, VMPerms, VMRange};
+
+// The layout of the mapping shared by the SQ ring and CQ ring
+const SQ_HEAD: usize = 0;
+const SQ_TAIL: usize = 4;
+const SQ_RING_MASK: usize = 8;
+const SQ_RING_ENTRIES: usize = 12;
+const SQ_FLAGS: usize = 16;
+const SQ_DROPPED: usize = 20;
+const CQ_HEAD: usize = 64;
+const CQ_TAIL: usize = 68;
+const CQ_RING_MASK: usize = 72;
+const CQ_RING_ENTRIES: usize = 76;
+const CQ_OVERFLOW: usize = 80;
+const CQ_FLAGS: usize = 84;
+const CQES: usize = 128;
+
+/// The SQ ring, CQ ring and SQE array shared with the application.
+///
+/// The memory is allocated in the user space of the process, so it can be
+/// mapped by the application through the fd of the io_uring. The heads and
+/// tails are updated by both sides, thus they are always accessed atomically.
+/// Since the application may write the memory at any time, any index read from
+/// the rings is checked before use.
+#[derive(Debug)]
+pub struct Rings {
+    ring_range: VMRange,
+    sqes_range: VMRange,
+    sq_entries: u32,
+    cq_entries: u32,
+}
+
+impl Rings {
+    pub fn new(sq_entries: u32, cq_entries: u32) -> Result<Self> {
+        debug_assert!(sq_entries.is_power_of_two() && cq_entries.is_power_of_two());
+        let ring_size = Self::sq_array_offset(cq_entries) + sq_entries as usize * 4;
+        let sqes_size = sq_entries as usize * core::mem::size_of::<io_uring_sqe>();
+
+        let current = current!();
+        let mmap = |size| {
+            current.vm().mmap(
+                0,
+                size,
+                VMPerms::READ | VMPerms::WRITE,
+                MMapFlags::MAP_PRIVATE | MMapFlags::MAP_ANONYMOUS,
+                0,
+                0,
+            )
+        };
+        let ring_addr = mmap(ring_size)?;
+        let sqes_addr = match mmap(sqes_size) {
+            Ok(addr) => addr,
+            Err(e) => {
+                current.vm().munmap(ring_addr, ring_size)?;
+                return Err(e);
+            }
+        };
+
+        let rings = Self {
+            ring_range: VMRange::new_with_size(ring_addr, ring_size)?,
+            sqes_range: VMRange::new_with_size(sqes_addr, sqes_size)?,
+            sq_entries,
+            cq_entries,
+        };
+        // The newly-mapped memory is filled with zeros
+        rings
+            .u32_at(SQ_RING_MASK)
+            .store(sq_entries - 1, Ordering::Relaxed);
+        rings
+            .u32_at(SQ_RING_ENTRIES)
+            .store(sq_entries, Ordering::Relaxed);
+        rings
+            .u32_at(CQ_RING_MASK)
+            .store(cq_entries - 1, Ordering::Relaxed);
+        rings
+            .u32_at(CQ_RING_ENTRIES)
+            .store(cq_entries, Ordering::Relaxed);
+        Ok(rings)
+    }
+
+    pub fn fill_params(&self, params: &mut io_uring_params) {
+        params.sq_entries = self.sq_entries;
+        params.cq_entries = self.cq_entries;
+        params.sq_off = io_sqring_offsets {
+            head: SQ_HEAD as u32,
+            tail: SQ_TAIL as u32,
+            ring_mask: SQ_RING_MASK as u32,
+            ring_entries: SQ_RING_ENTRIES as u32,
+            flags: SQ_FLAGS as u32,
+            dropped: SQ_DROPPED as u32,
+            array: Self::sq_array_offset(self.cq_entries) as u32,
+            ..Default::default()
+        };
+        params.cq_off = io_cqring_offsets {
+            head: CQ_HEAD as u32,
+            tail: CQ_TAIL as u32,
+            ring_mask: CQ_RING_MASK as u32,
+            ring_entries: CQ_RING_ENTRIES as u32,
+            overflow: CQ_OVERFLOW as u32,
+            cqes: CQES as u32,
+            flags: CQ_FLAGS as u32,
+            ..Default::default()
+        };
+    }
+
+    pub fn sq_entries(&self) -> u32 {
+        self.sq_entries
+    }
+
+    pub fn cq_entries(&self) -> u32 {
+        self.cq_entries
+    }
+
+    /// The range of the SQ and CQ rings, which share one mapping.
+    pub fn ring_range(&self) -> &VMRange {
+        &self.ring_range
+    }
+
+    pub fn sqes_range(&self) -> &VMRange {
+        &self.sqes_range
+    }
+
+    /// Pop an SQE submitted by the application.
+    ///
+    /// The SQEs with invalid indexes are dropped and counted in `dropped`.
+    pub fn pop_sqe(&self) -> Option<io_uring_sqe> {
+        let head = self.u32_at(SQ_HEAD);
+        let tail = self.u32_at(SQ_TAIL).load(Ordering::Acquire);
+        loop {
+            let pos = head.load(Ordering::Relaxed);
+            // The ring is empty, or is corrupted by the application
+            if pos == tail || tail.wrapping_sub(pos) > self.sq_entries {
+                return None;
+            }
+            let array_ptr =
+                (self.ring_range.start() + Self::sq_array_offset(self.cq_entries)) as *const u32;
+            let idx = unsafe {
+                array_ptr
+                    .add((pos & (self.sq_entries - 1)) as usize)
+                    .read_volatile()
+            };
+            head.store(pos.wrapping_add(1), Ordering::Release);
+
+            if idx >= self.sq_entries {
+                self.u32_at(SQ_DROPPED).fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let sqe_ptr = self.sqes_range.start() as *const io_uring_sqe;
+            return Some(unsafe { sqe_ptr.add(idx as usize).read_volatile() });
+        }
+    }
+
+    /// The number of the SQEs that are submitted but not yet popped.
+    pub fn nr_sqes(&self) -> u32 {
+        let head = self.u32_at(SQ_HEAD).load(Ordering::Relaxed);
+        let tail = self.u32_at(SQ_TAIL).load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(self.sq_entries)
+    }
+
+    /// Push a CQE, returning false if the CQ ring is full.
+    pub fn push_cqe(&self, cqe: io_uring_cqe) -> bool {
+        if self.nr_cqes() >= self.cq_entries {
+            return false;
+        }
+        let tail = self.u32_at(CQ_TAIL);
+        let pos = tail.load(Ordering::Relaxed);
+        let cqe_ptr = (self.ring_range.start() + CQES) as *mut io_uring_cqe;
+        unsafe {
+            cqe_ptr
+                .add((pos & (self.cq_entries - 1)) as usize)
+                .write_volatile(cqe);
+        }
+        tail.store(pos.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// The number of the CQEs that are not yet consumed by the application.
+    pub fn nr_cqes(&self) -> u32 {
+        let head = self.u32_at(CQ_HEAD).load(Ordering::Acquire);
+        let tail = self.u32_at(CQ_TAIL).load(Ordering::Relaxed);
+        tail.wrapping_sub(head).min(self.cq_entries)
+    }
+
+    /// Tell the application whether there are CQEs waiting for free slots.
+    pub fn set_cq_overflow(&self, is_overflow: bool) {
+        let flags = self.u32_at(SQ_FLAGS);
+        if is_overflow {
+            flags.fetch_or(IORING_SQ_CQ_OVERFLOW, Ordering::Release);
+        } else {
+            flags.fetch_and(!IORING_SQ_CQ_OVERFLOW, Ordering::Release);
+        }
+    }
+
+    fn u32_at(&self, offset: usize) -> &AtomicU32 {
+        unsafe { &*((self.ring_range.start() + offset) as *const AtomicU32) }
+    }
+
+    fn sq_array_offset(cq_entries: u32) -> usize {
+        CQES + cq_entries as usize * core::mem::size_of::<io_uring_cqe>()
+    }
+}
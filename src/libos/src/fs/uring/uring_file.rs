@@ -0,0 +1,634 @@
+use std::any::Any;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+use std::time::Duration;
+
+use super::c_types::*;
+use super::request;
+use super::ring::Rings;
+use crate::events::{Observer, Pollee, Poller};
+use crate::fs::{IoEvents, IoNotifier};
+use crate::net::HOST_POLLER;
+use crate::prelude::*;
+use crate::time::{do_clock_gettime, ClockId};
+use crate::vm::{MMapFlags, VMPerms, VMRange};
+
+/// The file of an io_uring instance, which is created by `io_uring_setup`.
+///
+/// There is no kernel thread in the LibOS to consume the SQ ring, so the
+/// requests are issued by the thread calling `io_uring_enter`. A request is
+/// executed at once without blocking. If it would block, it is pending until
+/// the file becomes ready, and then is executed by the next `io_uring_enter`
+/// call. The readiness of the host files is driven by the host poller.
+pub struct IoUringFile {
+    rings: Rings,
+    inner: Mutex<Inner>,
+    // IN is added when CQEs are posted or pending requests become ready
+    pollee: Pollee,
+    weak_self: Weak<Self>,
+}
+
+struct Inner {
+    // The CQEs waiting for free slots in the CQ ring
+    overflow: VecDeque<io_uring_cqe>,
+    // The number of the CQEs posted, including the overflowed ones
+    nr_posted: u64,
+    pending: Vec<Arc<PendingRequest>>,
+    timeouts: Vec<TimeoutRequest>,
+    // The eventfd to be signaled, and whether it is only for the requests
+    // that are not completed at submission
+    eventfd: Option<(FileRef, bool)>,
+    buffers: Vec<Range<usize>>,
+    // Once the rings are unmapped, they are never accessed again
+    is_detached: bool,
+}
+
+impl IoUringFile {
+    pub fn new(sq_entries: u32, cq_entries: u32) -> Result<Arc<Self>> {
+        let rings = Rings::new(sq_entries, cq_entries)?;
+        let inner = Mutex::new(Inner {
+            overflow: VecDeque::new(),
+            nr_posted: 0,
+            pending: Vec::new(),
+            timeouts: Vec::new(),
+            eventfd: None,
+            buffers: Vec::new(),
+            is_detached: false,
+        });
+        Ok(Arc::new_cyclic(|weak_self| Self {
+            rings,
+            inner,
+            pollee: Pollee::new(IoEvents::empty()),
+            weak_self: weak_self.clone(),
+        }))
+    }
+
+    pub fn fill_params(&self, params: &mut io_uring_params) {
+        self.rings.fill_params(params);
+    }
+
+    /// Map the rings into the process, returning the address.
+    ///
+    /// The rings are allocated in the user space at setup, thus they are
+    /// always at the same address no matter how many times they are mapped.
+    pub fn mmap(&self, size: usize, flags: MMapFlags, offset: usize) -> Result<usize> {
+        if self.inner.lock().is_detached {
+            return_errno!(EINVAL, "the rings have been unmapped");
+        }
+        if flags.contains(MMapFlags::MAP_FIXED) {
+            return_errno!(EINVAL, "the rings cannot be mapped at a fixed address");
+        }
+        let range = match offset {
+            IORING_OFF_SQ_RING | IORING_OFF_CQ_RING => self.rings.ring_range(),
+            IORING_OFF_SQES => self.rings.sqes_range(),
+            _ => return_errno!(EINVAL, "invalid offset of the rings"),
+        };
+        if size > range.size() {
+            return_errno!(EINVAL, "the size is larger than the rings");
+        }
+        Ok(range.start())
+    }
+
+    pub fn overlap_with(&self, range: &VMRange) -> bool {
+        self.rings.ring_range().overlap_with(range) || self.rings.sqes_range().overlap_with(range)
+    }
+
+    /// Whether the rings can still be accessed after the range is protected
+    /// with the permissions. The LibOS writes the ring of the heads and the
+    /// CQEs, and reads the SQEs.
+    pub fn is_accessible_with(&self, range: &VMRange, perms: VMPerms) -> bool {
+        (perms.can_write() || !self.rings.ring_range().overlap_with(range))
+            && (perms.can_read() || !self.rings.sqes_range().overlap_with(range))
+    }
+
+    /// Stop accessing the rings, which are going to be unmapped.
+    ///
+    /// The pending requests are dropped and the waiters are woken up.
+    pub fn detach(&self) {
+        let pending = {
+            let mut inner = self.inner.lock();
+            inner.is_detached = true;
+            inner.overflow.clear();
+            inner.timeouts.clear();
+            core::mem::take(&mut inner.pending)
+        };
+        for req in pending {
+            req.is_done.store(true, Ordering::Relaxed);
+            req.unregister();
+        }
+        self.pollee.add_events(IoEvents::IN);
+    }
+
+    /// Submit at most `to_submit` SQEs, and then wait for `min_complete` CQEs
+    /// if `IORING_ENTER_GETEVENTS` is given.
+    ///
+    /// Like Linux, the number of the SQEs submitted is returned even if the
+    /// waiting fails.
+    pub fn enter(&self, to_submit: u32, min_complete: u32, flags: EnterFlags) -> Result<usize> {
+        let nr_submitted = self.submit(to_submit)?;
+        if flags.contains(EnterFlags::IORING_ENTER_GETEVENTS) {
+            if let Err(e) = self.wait_cqes(min_complete) {
+                if nr_submitted == 0 {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(nr_submitted)
+    }
+
+    fn submit(&self, to_submit: u32) -> Result<usize> {
+        let mut nr_submitted = 0;
+        while nr_submitted < to_submit as usize {
+            let sqe = {
+                let inner = self.inner.lock();
+                if inner.is_detached {
+                    return_errno!(EFAULT, "the rings have been unmapped");
+                }
+                // Stop submitting until the overflowed CQEs are consumed
+                if !inner.overflow.is_empty() {
+                    if nr_submitted == 0 {
+                        return_errno!(EBUSY, "the CQ ring overflows");
+                    }
+                    break;
+                }
+                match self.rings.pop_sqe() {
+                    Some(sqe) => sqe,
+                    None => break,
+                }
+            };
+            request::issue(self, sqe);
+            nr_submitted += 1;
+        }
+        Ok(nr_submitted)
+    }
+
+    fn wait_cqes(&self, min_complete: u32) -> Result<()> {
+        let poller = Poller::new();
+        self.pollee.connect_poller(IoEvents::IN, &poller);
+        loop {
+            self.run_ready_requests();
+            let mut timeout = self.fire_timeouts()?;
+            {
+                let mut inner = self.inner.lock();
+                if inner.is_detached {
+                    return_errno!(EFAULT, "the rings have been unmapped");
+                }
+                inner.flush_overflow(&self.rings);
+                if self.rings.nr_cqes() >= min_complete.min(self.rings.cq_entries()) {
+                    return Ok(());
+                }
+            }
+
+            if let Err(e) = poller.wait_timeout(timeout.as_mut()) {
+                if e.errno() != ETIMEDOUT {
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Post a CQE, and signal the eventfd if any.
+    ///
+    /// `is_async` tells whether the request is not completed at submission.
+    pub fn complete(&self, user_data: u64, res: i32, is_async: bool) {
+        let eventfd = {
+            let mut inner = self.inner.lock();
+            if inner.is_detached {
+                return;
+            }
+            let cqe = io_uring_cqe {
+                user_data,
+                res,
+                flags: 0,
+            };
+            inner.post(&self.rings, cqe);
+            inner
+                .eventfd
+                .as_ref()
+                .filter(|(_, async_only)| is_async || !async_only)
+                .map(|(eventfd, _)| eventfd.clone())
+        };
+        self.pollee.add_events(IoEvents::IN);
+
+        if let Some(eventfd) = eventfd {
+            if let Err(e) = eventfd.write(&1_u64.to_ne_bytes()) {
+                warn!("failed to signal the eventfd of io_uring: {:?}", e);
+            }
+        }
+    }
+
+    /// Add a request that waits for any of the events in the mask to happen on
+    /// the file.
+    pub fn add_pending(&self, file: FileRef, sqe: io_uring_sqe, mask: IoEvents) -> Result<()> {
+        let notifier = file
+            .notifier()
+            .ok_or_else(|| errno!(EINVAL, "the file cannot be polled asynchronously"))?;
+
+        let req = Arc::new(PendingRequest {
+            ring: self.weak_self.clone(),
+            file: file.clone(),
+            sqe,
+            mask: mask | IoEvents::ALWAYS_POLL,
+            is_ready: AtomicBool::new(false),
+            is_done: AtomicBool::new(false),
+        });
+        let weak_observer = Arc::downgrade(&req) as Weak<dyn Observer<_>>;
+        notifier.register(weak_observer.clone(), Some(req.mask), None);
+        // The events of the host files are only updated when they are polled
+        if file.host_fd().is_some() {
+            if let Err(e) = HOST_POLLER.watch(&file, req.mask) {
+                notifier.unregister(&weak_observer);
+                return Err(e);
+            }
+        }
+        let is_detached = {
+            let mut inner = self.inner.lock();
+            if !inner.is_detached {
+                inner.pending.push(req.clone());
+            }
+            inner.is_detached
+        };
+        if is_detached {
+            req.unregister();
+            return_errno!(EFAULT, "the rings have been unmapped");
+        }
+
+        // The file may be ready before the registration
+        req.on_events(file.poll_new());
+        Ok(())
+    }
+
+    /// Add a timeout request, which completes with `ETIME` when it expires, or
+    /// completes normally after `count` CQEs are posted if `count` is not zero.
+    pub fn add_timeout(
+        &self,
+        user_data: u64,
+        duration: Duration,
+        is_abs: bool,
+        count: u64,
+    ) -> Result<()> {
+        let deadline = if is_abs {
+            duration
+        } else {
+            monotonic_now()? + duration
+        };
+        {
+            let mut inner = self.inner.lock();
+            let target_nr = if count == 0 {
+                None
+            } else {
+                Some(inner.nr_posted + count)
+            };
+            inner.timeouts.push(TimeoutRequest {
+                user_data,
+                deadline,
+                target_nr,
+            });
+        }
+        // Let the waiters know the new deadline
+        self.pollee.add_events(IoEvents::IN);
+        Ok(())
+    }
+
+    /// Cancel a timeout request, which completes with `ECANCELED`.
+    pub fn remove_timeout(&self, user_data: u64) -> Result<()> {
+        {
+            let mut inner = self.inner.lock();
+            let idx = inner
+                .timeouts
+                .iter()
+                .position(|timeout| timeout.user_data == user_data)
+                .ok_or_else(|| errno!(ENOENT, "the timeout is not found"))?;
+            inner.timeouts.remove(idx);
+        }
+        self.complete(user_data, -(ECANCELED as i32), true);
+        Ok(())
+    }
+
+    /// Cancel a pending request, which completes with `ECANCELED`.
+    ///
+    /// If `only_polls` is false, the timeout requests can also be cancelled.
+    pub fn cancel(&self, user_data: u64, only_polls: bool) -> Result<()> {
+        let req = {
+            let inner = self.inner.lock();
+            inner
+                .pending
+                .iter()
+                .find(|req| {
+                    req.sqe.user_data == user_data
+                        && (!only_polls || req.sqe.opcode == IORING_OP_POLL_ADD)
+                })
+                .cloned()
+        };
+        let req = match req {
+            Some(req) => req,
+            None if !only_polls => return self.remove_timeout(user_data),
+            None => return_errno!(ENOENT, "the request is not found"),
+        };
+        if req.is_done.swap(true, Ordering::Relaxed) {
+            return_errno!(EALREADY, "the request is being completed");
+        }
+        self.complete(user_data, -(ECANCELED as i32), true);
+        self.reap_pending();
+        Ok(())
+    }
+
+    pub fn register_buffers(&self, buffers: Vec<Range<usize>>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if !inner.buffers.is_empty() {
+            return_errno!(EBUSY, "the buffers have been registered");
+        }
+        inner.buffers = buffers;
+        Ok(())
+    }
+
+    pub fn unregister_buffers(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.buffers.is_empty() {
+            return_errno!(ENXIO, "no buffers are registered");
+        }
+        inner.buffers.clear();
+        Ok(())
+    }
+
+    /// Check that the buffer of a request is inside the registered buffer.
+    pub fn check_fixed_buffer(&self, buf_index: u16, buf: &Range<usize>) -> Result<()> {
+        let inner = self.inner.lock();
+        let fixed_buf = inner
+            .buffers
+            .get(buf_index as usize)
+            .ok_or_else(|| errno!(EFAULT, "invalid buffer index"))?;
+        if buf.start < fixed_buf.start || buf.end > fixed_buf.end {
+            return_errno!(EFAULT, "the buffer is out of the registered buffer");
+        }
+        Ok(())
+    }
+
+    pub fn register_eventfd(&self, eventfd: FileRef, async_only: bool) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.eventfd.is_some() {
+            return_errno!(EBUSY, "an eventfd has been registered");
+        }
+        inner.eventfd = Some((eventfd, async_only));
+        Ok(())
+    }
+
+    pub fn unregister_eventfd(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.eventfd.take().is_none() {
+            return_errno!(ENXIO, "no eventfd is registered");
+        }
+        Ok(())
+    }
+
+    /// Execute the pending requests whose files have become ready.
+    fn run_ready_requests(&self) {
+        let ready_reqs: Vec<Arc<PendingRequest>> = {
+            let inner = self.inner.lock();
+            inner
+                .pending
+                .iter()
+                .filter(|req| req.is_ready.load(Ordering::Relaxed))
+                .cloned()
+                .collect()
+        };
+        for req in ready_reqs {
+            req.try_execute(self);
+        }
+        self.reap_pending();
+    }
+
+    /// Complete the expired timeout requests, returning the time until the
+    /// next deadline.
+    fn fire_timeouts(&self) -> Result<Option<Duration>> {
+        let now = monotonic_now()?;
+        let (expired, next_deadline) = {
+            let mut inner = self.inner.lock();
+            let (expired, timeouts): (Vec<_>, Vec<_>) = core::mem::take(&mut inner.timeouts)
+                .into_iter()
+                .partition(|timeout| timeout.deadline <= now);
+            inner.timeouts = timeouts;
+            let next_deadline = inner.timeouts.iter().map(|timeout| timeout.deadline).min();
+            (expired, next_deadline)
+        };
+        for timeout in expired {
+            self.complete(timeout.user_data, -(ETIME as i32), true);
+        }
+        Ok(next_deadline.map(|deadline| deadline - now))
+    }
+
+    /// Unregister the pending requests that are done.
+    ///
+    /// The unregistration cannot be done when the requests complete, since the
+    /// notifiers are locked during the event delivery.
+    fn reap_pending(&self) {
+        let done_reqs: Vec<Arc<PendingRequest>> = {
+            let mut inner = self.inner.lock();
+            let (done_reqs, pending) = core::mem::take(&mut inner.pending)
+                .into_iter()
+                .partition(|req| req.is_done.load(Ordering::Relaxed));
+            inner.pending = pending;
+            done_reqs
+        };
+        for req in done_reqs {
+            req.unregister();
+        }
+    }
+}
+
+impl Inner {
+    fn post(&mut self, rings: &Rings, cqe: io_uring_cqe) {
+        let mut next_cqe = Some(cqe);
+        while let Some(cqe) = next_cqe.take() {
+            self.overflow.push_back(cqe);
+            self.flush_overflow(rings);
+            self.nr_posted += 1;
+
+            // Complete the timeout requests waiting for enough CQEs one by
+            // one, since each of them posts a CQE too
+            let nr_posted = self.nr_posted;
+            if let Some(idx) = self
+                .timeouts
+                .iter()
+                .position(|timeout| timeout.target_nr.map_or(false, |nr| nr <= nr_posted))
+            {
+                let timeout = self.timeouts.remove(idx);
+                next_cqe = Some(io_uring_cqe {
+                    user_data: timeout.user_data,
+                    res: 0,
+                    flags: 0,
+                });
+            }
+        }
+    }
+
+    fn flush_overflow(&mut self, rings: &Rings) {
+        while let Some(cqe) = self.overflow.front() {
+            if !rings.push_cqe(*cqe) {
+                break;
+            }
+            self.overflow.pop_front();
+        }
+        rings.set_cq_overflow(!self.overflow.is_empty());
+    }
+}
+
+impl Drop for IoUringFile {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+        for req in inner.pending.drain(..) {
+            req.unregister();
+        }
+    }
+}
+
+impl File for IoUringFile {
+    fn poll_new(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+        let inner = self.inner.lock();
+        if inner.is_detached {
+            return events;
+        }
+        if self.rings.nr_cqes() > 0
+            || inner
+                .pending
+                .iter()
+                .any(|req| req.is_ready.load(Ordering::Relaxed))
+        {
+            events |= IoEvents::IN;
+        }
+        if self.rings.nr_sqes() < self.rings.sq_entries() {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(self.pollee.notifier())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl std::fmt::Debug for IoUringFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoUringFile")
+            .field("rings", &self.rings)
+            .finish()
+    }
+}
+
+pub trait AsIoUring {
+    fn as_io_uring(&self) -> Result<&IoUringFile>;
+}
+
+impl AsIoUring for FileRef {
+    fn as_io_uring(&self) -> Result<&IoUringFile> {
+        self.as_any()
+            .downcast_ref::<IoUringFile>()
+            .ok_or_else(|| errno!(EOPNOTSUPP, "not an io_uring file"))
+    }
+}
+
+/// A request waiting for the file to be ready.
+///
+/// A poll request completes once the file is ready. Other requests are only
+/// marked as ready, and then are executed by the threads calling
+/// `io_uring_enter`, since the I/O cannot be done during the event delivery.
+struct PendingRequest {
+    ring: Weak<IoUringFile>,
+    file: FileRef,
+    sqe: io_uring_sqe,
+    mask: IoEvents,
+    is_ready: AtomicBool,
+    is_done: AtomicBool,
+}
+
+impl PendingRequest {
+    fn on_events(&self, events: IoEvents) {
+        let events = events & self.mask;
+        if events.is_empty() {
+            return;
+        }
+        if self.sqe.opcode == IORING_OP_POLL_ADD {
+            if self.is_done.swap(true, Ordering::Relaxed) {
+                return;
+            }
+            if let Some(ring) = self.ring.upgrade() {
+                ring.complete(self.sqe.user_data, events.to_raw() as i32, true);
+            }
+        } else {
+            self.is_ready.store(true, Ordering::Relaxed);
+            if let Some(ring) = self.ring.upgrade() {
+                ring.pollee.add_events(IoEvents::IN);
+            }
+        }
+    }
+
+    fn try_execute(&self, ring: &IoUringFile) {
+        // Clear the flag before polling, so that a new event is never missed
+        self.is_ready.store(false, Ordering::Relaxed);
+        if (self.file.poll_new() & self.mask).is_empty() {
+            self.rearm_host_file();
+            return;
+        }
+        if self.is_done.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let res = match request::execute(ring, &self.file, &self.sqe) {
+            // The connect in progress is established
+            Err(e) if e.errno() == EISCONN && self.sqe.opcode == IORING_OP_CONNECT => Ok(0),
+            res => res,
+        };
+        match res {
+            // The file is drained by others, so wait for the next event
+            Err(e) if e.errno() == EAGAIN => {
+                self.is_done.store(false, Ordering::Relaxed);
+                self.rearm_host_file();
+            }
+            res => ring.complete(self.sqe.user_data, request::to_res(res), true),
+        }
+    }
+
+    /// Watch the events of the host file again, since the host poller stops
+    /// watching the events once they happen.
+    fn rearm_host_file(&self) {
+        if self.file.host_fd().is_none() {
+            return;
+        }
+        if let Err(e) = HOST_POLLER.rearm(&self.file, self.mask) {
+            warn!("failed to rearm the host file: {:?}", e);
+        }
+    }
+
+    fn unregister(self: &Arc<Self>) {
+        if let Some(notifier) = self.file.notifier() {
+            let weak_observer = Arc::downgrade(self) as Weak<dyn Observer<_>>;
+            notifier.unregister(&weak_observer);
+        }
+        if self.file.host_fd().is_some() {
+            HOST_POLLER.unwatch(&self.file);
+        }
+    }
+}
+
+impl Observer<IoEvents> for PendingRequest {
+    fn on_event(&self, events: &IoEvents, _metadata: &Option<Weak<dyn Any + Send + Sync>>) {
+        self.on_events(*events);
+    }
+}
+
+struct TimeoutRequest {
+    user_data: u64,
+    // The deadline on the monotonic clock
+    deadline: Duration,
+    // The number of the CQEs posted, after which the request completes
+    target_nr: Option<u64>,
+}
+
+fn monotonic_now() -> Result<Duration> {
+    Ok(do_clock_gettime(ClockId::CLOCK_MONOTONIC)?.as_duration())
+}
@@ -32,6 +32,8 @@ pub fn do_handle_interrupt(
     context.extra_context = ExtraContext::XsaveOnStack;
     context.extra_context_ptr = info.xsave_area.as_mut_ptr();
     context.extra_context_size = info.xsave_size;
+    // The thread is preempted by the interrupt
+    current!().usage().interrupted();
     Ok(0)
 }
 
@@ -41,6 +43,9 @@ pub fn broadcast_interrupts() -> Result<usize> {
         if thread.process().is_forced_to_exit() || thread.is_forced_to_stop() {
             return true;
         }
+        if thread.process().stop_status().is_pending_for(thread) {
+            return true;
+        }
 
         let interested = !*thread.sig_mask().read().unwrap();
         // In the nightly-2022-10-22 Rust compiler, this expression holds two nested read locks.
@@ -1,16 +1,32 @@
-use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize};
-use std::{collections::HashMap, thread::current};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize};
+use core::time::Duration;
 
 use crate::util::sync::Mutex;
 use alloc::{sync::Arc, vec::Vec};
 use atomic::Ordering;
-use io_uring_callback::{Builder, IoUring};
-use keyable_arc::KeyableArc;
+use io_uring_callback::{Builder, Fd, IoHandle, IoUring};
+use sgx_untrusted_alloc::UntrustedBox;
 
 use crate::config::LIBOS_CONFIG;
+use crate::events::{HostEventFd, Waiter, Waker};
+use crate::prelude::*;
 
 // The number of sockets to reach the network bandwidth threshold of one io_uring instance
 const SOCKET_THRESHOLD_PER_URING: u32 = 1;
+// The number of completions per second to saturate one io_uring instance
+const THROUGHPUT_THRESHOLD_PER_URING: u64 = 200_000;
+// The interval to sample the throughput of io_uring instances
+const THROUGHPUT_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+// The number of retries to poll the completion queue in one polling round
+const POLLING_RETRIES: usize = 10000;
+// The number of consecutive polling rounds without any completions, after
+// which the polling thread is parked
+const IDLE_ROUNDS_BEFORE_PARK: u32 = 64;
+// The number and the size of the registered buffers for file I/O
+const FILE_IO_BUF_NUM: usize = 16;
+const FILE_IO_BUF_SIZE: usize = 64 * 1024;
+// The number of retries to check the completion of a file I/O request before sleeping
+const FILE_IO_SPIN_RETRIES: usize = 2000;
 
 lazy_static::lazy_static! {
     pub static ref MULTITON: UringSet = {
@@ -20,6 +36,16 @@ lazy_static::lazy_static! {
 
     pub static ref ENABLE_URING: AtomicBool = AtomicBool::new(LIBOS_CONFIG.feature.io_uring > 0);
 
+    // The io_uring instance dedicated to file I/O, which is `None` if unavailable.
+    pub static ref FILE_IO_URING: Option<FileIoUring> = {
+        if !ENABLE_URING.load(Ordering::Relaxed) {
+            return None;
+        }
+        FileIoUring::new()
+            .map_err(|e| warn!("io_uring is unavailable for file I/O: {:?}", e))
+            .ok()
+    };
+
     // Four uring instances are sufficient to reach the network bandwidth threshold of host kernel.
     pub static ref URING_LIMIT: AtomicUsize = {
         let uring_limit = LIBOS_CONFIG.feature.io_uring;
@@ -28,140 +54,459 @@ lazy_static::lazy_static! {
     };
 }
 
-#[derive(Clone, Copy, Default)]
+/// The states of an io_uring instance, which are shared with its CQE polling thread.
+#[derive(Default)]
 struct UringState {
-    registered_num: u32,
-    is_enable_poll: bool, // CQE polling thread
+    registered_num: AtomicU32,
+    // Whether the CQE polling thread is parked
+    is_parked: AtomicBool,
+    // The number of times that the CQE polling thread is parked
+    nr_parks: AtomicU64,
 }
 
 impl UringState {
-    fn register_one_socket(&mut self) {
-        self.registered_num += 1;
+    fn register_one_socket(&self) {
+        self.registered_num.fetch_add(1, Ordering::Relaxed);
     }
 
-    fn unregister_one_socket(&mut self) {
-        self.registered_num -= 1;
+    fn unregister_one_socket(&self) {
+        self.registered_num.fetch_sub(1, Ordering::Relaxed);
     }
 
-    fn enable_poll(&mut self, uring: Arc<IoUring>) {
-        if !self.is_enable_poll {
-            self.is_enable_poll = true;
-            std::thread::spawn(move || loop {
-                let min_complete = 1;
-                let polling_retries = 10000;
-                uring.poll_completions(min_complete, polling_retries);
-            });
-        }
+    fn registered_num(&self) -> u32 {
+        self.registered_num.load(Ordering::Relaxed)
     }
 }
 
+struct UringInstance {
+    uring: Arc<IoUring>,
+    state: Arc<UringState>,
+}
+
+impl UringInstance {
+    fn new() -> Result<Self> {
+        let uring = Builder::new()
+            .setup_sqpoll(500 /* ms */)
+            .build(256)
+            .map_err(|e| errno!(e.raw_os_error().unwrap_or(EINVAL), "io_uring setup failed"))?;
+        let uring = Arc::new(uring);
+        let state = Arc::new(UringState::default());
+        start_cqe_poller(uring.clone(), state.clone());
+        Ok(Self { uring, state })
+    }
+}
+
+/// Start the CQE polling thread of an io_uring instance.
+///
+/// The thread busy polls the completion queue while there are completions.
+/// After being idle for a while, the thread is parked on a host eventfd, which
+/// is registered to the io_uring and thus signaled once new completions arrive.
+/// If the eventfd is not available, the thread falls back to busy polling.
+fn start_cqe_poller(uring: Arc<IoUring>, state: Arc<UringState>) {
+    let eventfd = HostEventFd::new().and_then(|eventfd| {
+        uring
+            .register_eventfd(eventfd.host_fd() as _)
+            .map_err(|e| {
+                errno!(
+                    e.raw_os_error().unwrap_or(EINVAL),
+                    "register_eventfd failed"
+                )
+            })?;
+        Ok(eventfd)
+    });
+
+    std::thread::spawn(move || {
+        let eventfd = match eventfd {
+            Ok(eventfd) => eventfd,
+            Err(e) => {
+                warn!("cannot park the CQE poller: {:?}", e);
+                loop {
+                    uring.poll_completions(1, POLLING_RETRIES);
+                }
+            }
+        };
+
+        let mut idle_rounds = 0;
+        loop {
+            if uring.poll_completions(0, POLLING_RETRIES) > 0 {
+                idle_rounds = 0;
+                continue;
+            }
+
+            idle_rounds += 1;
+            if idle_rounds < IDLE_ROUNDS_BEFORE_PARK {
+                continue;
+            }
+            idle_rounds = 0;
+
+            // No completion will be missed. The eventfd is signaled for any
+            // completion posted since the last time it was drained, and the
+            // completion queue is always polled after the eventfd is drained.
+            state.is_parked.store(true, Ordering::Relaxed);
+            state.nr_parks.fetch_add(1, Ordering::Relaxed);
+            let _ = eventfd.poll(None);
+            let _ = eventfd.read_u64();
+            state.is_parked.store(false, Ordering::Relaxed);
+        }
+    });
+}
+
+/// The statistics of an io_uring instance.
+#[derive(Debug, Clone, Copy)]
+pub struct UringStats {
+    pub id: usize,
+    // Whether new sockets can be attached to the io_uring instance
+    pub is_active: bool,
+    pub is_parked: bool,
+    pub nr_sockets: u32,
+    pub task_load: usize,
+    pub nr_submitted: u64,
+    pub nr_completed: u64,
+    pub nr_parks: u64,
+}
+
+#[derive(Default)]
+struct ThroughputSample {
+    // The uptime when the sample is taken
+    time: Duration,
+    nr_completed: u64,
+    // The number of completions per second
+    throughput: u64,
+}
+
+/// The io_uring instances shared by the sockets.
+///
+/// The io_uring instances are created on demand, up to `URING_LIMIT`. The
+/// number of active instances, to which new sockets are attached, scales with
+/// the number of sockets and the throughput of the existing instances. The
+/// inactive instances keep serving their attached sockets and their polling
+/// threads get parked once idle.
 pub struct UringSet {
-    urings: Mutex<HashMap<KeyableArc<IoUring>, UringState>>,
-    running_uring_num: AtomicU32,
+    urings: Mutex<Vec<UringInstance>>,
+    sample: Mutex<ThroughputSample>,
 }
 
 impl UringSet {
     pub fn new() -> Self {
-        let urings = Mutex::new(HashMap::new());
-        let running_uring_num = AtomicU32::new(0);
-        Self {
-            urings,
-            running_uring_num,
-        }
+        let urings = Mutex::new(Vec::new());
+        let sample = Mutex::new(ThroughputSample::default());
+        Self { urings, sample }
     }
 
-    pub fn poll_completions(&self) {
-        let mut guard = self.urings.lock();
-        let uring_limit = URING_LIMIT.load(Ordering::Relaxed) as u32;
-
-        for _ in 0..uring_limit {
-            let uring: KeyableArc<IoUring> = Arc::new(
-                Builder::new()
-                    .setup_sqpoll(500 /* ms */)
-                    .build(256)
-                    .unwrap(),
-            )
-            .into();
-            let mut state = UringState::default();
-            state.enable_poll(uring.clone().into());
-
-            guard.insert(uring.clone(), state);
-            self.running_uring_num.fetch_add(1, Ordering::Relaxed);
+    /// Create the first io_uring instance.
+    pub fn init(&self) {
+        let mut urings = self.urings.lock();
+        if urings.is_empty() && URING_LIMIT.load(Ordering::Relaxed) > 0 {
+            let instance =
+                UringInstance::new().expect("failed to create the first io_uring instance");
+            urings.push(instance);
         }
     }
 
     pub fn get_uring(&self) -> Arc<IoUring> {
-        let mut map = self.urings.lock();
-        let running_uring_num = self.running_uring_num.load(Ordering::Relaxed);
-        let uring_limit = URING_LIMIT.load(Ordering::Relaxed) as u32;
-        assert!(running_uring_num <= uring_limit);
-
-        let init_stage = running_uring_num < uring_limit;
+        let mut urings = self.urings.lock();
+        let active_num = self.active_uring_num(&urings);
 
         // Construct an io_uring instance and initiate a polling thread
-        if init_stage {
-            let should_build_uring = {
-                // Sum registered socket
-                let total_socket_num = map
-                    .values()
-                    .fold(0, |acc, state| acc + state.registered_num);
-                // Determine the number of available io_uring
-                let uring_num = (total_socket_num / SOCKET_THRESHOLD_PER_URING) + 1;
-
-                running_uring_num < uring_num
-            };
-
-            if should_build_uring {
-                let uring: KeyableArc<IoUring> = Arc::new(
-                    Builder::new()
-                        .setup_sqpoll(500 /* ms */)
-                        .build(256)
-                        .unwrap(),
-                )
-                .into();
-                let mut state = UringState::default();
-                state.register_one_socket();
-                state.enable_poll(uring.clone().into());
-
-                map.insert(uring.clone(), state);
-                self.running_uring_num.fetch_add(1, Ordering::Relaxed);
-                return uring.into();
+        if urings.len() < active_num {
+            match UringInstance::new() {
+                Ok(instance) => {
+                    instance.state.register_one_socket();
+                    let uring = instance.uring.clone();
+                    urings.push(instance);
+                    return uring;
+                }
+                // Share the existing io_uring instances instead
+                Err(e) if !urings.is_empty() => {
+                    warn!("failed to create a new io_uring instance: {:?}", e);
+                }
+                Err(e) => panic!("failed to create the io_uring instance: {:?}", e),
             }
         }
 
-        // Link the file to the io_uring instance with the least load.
-        let (mut uring, mut state) = map
-            .iter_mut()
-            .min_by_key(|(_, state)| state.registered_num)
+        // Link the file to the active io_uring instance with the least sockets and task load.
+        let active_num = active_num.min(urings.len());
+        let instance = urings[..active_num]
+            .iter()
+            .min_by_key(|instance| (instance.state.registered_num(), instance.uring.task_load()))
             .unwrap();
+        instance.state.register_one_socket();
+        instance.uring.clone()
+    }
+
+    pub fn disattach_uring(&self, fd: usize, uring: Arc<IoUring>) {
+        let urings = self.urings.lock();
+        let instance = urings
+            .iter()
+            .find(|instance| Arc::ptr_eq(&instance.uring, &uring))
+            .unwrap();
+        instance.state.unregister_one_socket();
+        drop(urings);
+
+        uring.disattach_fd(fd);
+    }
+
+    pub fn stats(&self) -> Vec<UringStats> {
+        let urings = self.urings.lock();
+        let active_num = self.active_uring_num(&urings);
+        urings
+            .iter()
+            .enumerate()
+            .map(|(id, instance)| {
+                let uring_stats = instance.uring.stats();
+                UringStats {
+                    id,
+                    is_active: id < active_num,
+                    is_parked: instance.state.is_parked.load(Ordering::Relaxed),
+                    nr_sockets: instance.state.registered_num(),
+                    task_load: instance.uring.task_load(),
+                    nr_submitted: uring_stats.nr_submitted,
+                    nr_completed: uring_stats.nr_completed,
+                    nr_parks: instance.state.nr_parks.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+
+    /// The number of io_uring instances to which new sockets can be attached.
+    fn active_uring_num(&self, urings: &[UringInstance]) -> usize {
+        let uring_limit = URING_LIMIT.load(Ordering::Relaxed).max(1);
+
+        let total_socket_num: u32 = urings
+            .iter()
+            .map(|instance| instance.state.registered_num())
+            .sum();
+        let num_by_sockets = (total_socket_num / SOCKET_THRESHOLD_PER_URING) as usize + 1;
+
+        let throughput = self.sample_throughput(urings);
+        let num_by_throughput = (throughput / THROUGHPUT_THRESHOLD_PER_URING) as usize + 1;
 
-        // Re-select io_uring instance with least task load
-        if !init_stage {
-            let min_registered_num = state.registered_num;
-            (uring, state) = map
-                .iter_mut()
-                .filter(|(_, state)| state.registered_num == min_registered_num)
-                .min_by_key(|(uring, _)| uring.task_load())
-                .unwrap();
-        } else {
-            // At the initial stage, without constructing additional io_uring instances,
-            // there exists a singular io_uring which has the minimum number of registered sockets.
+        num_by_sockets.max(num_by_throughput).min(uring_limit)
+    }
+
+    /// Get the number of completions per second of all io_uring instances.
+    fn sample_throughput(&self, urings: &[UringInstance]) -> u64 {
+        let mut sample = self.sample.lock();
+        let now = match crate::time::up_time::get() {
+            Some(now) => now,
+            None => return sample.throughput,
+        };
+        let elapsed = now.saturating_sub(sample.time);
+        if elapsed < THROUGHPUT_SAMPLE_INTERVAL {
+            return sample.throughput;
         }
 
-        // Update io_uring instance states
-        state.register_one_socket();
-        assert!(state.is_enable_poll);
+        let nr_completed: u64 = urings
+            .iter()
+            .map(|instance| instance.uring.stats().nr_completed)
+            .sum();
+        let delta = nr_completed.saturating_sub(sample.nr_completed);
+        sample.throughput = (delta as u128 * 1000 / elapsed.as_millis().max(1)) as u64;
+        sample.time = now;
+        sample.nr_completed = nr_completed;
+        sample.throughput
+    }
+}
+
+/// The io_uring instance for the I/O on host files.
+///
+/// The data is transferred through a pool of buffers in untrusted memory,
+/// which are registered to the io_uring so that the kernel does not need to
+/// map them for every request. If all buffers are in use, the callers should
+/// fall back to the synchronous I/O.
+pub struct FileIoUring {
+    instance: UringInstance,
+    // Keep the registered buffers alive as long as the io_uring instance
+    bufs: UntrustedBox<[u8]>,
+    free_bufs: Mutex<Vec<u16>>,
+}
 
-        uring.clone().into()
+// The untrusted buffers are only accessed by the thread that holds their indexes.
+unsafe impl Send for FileIoUring {}
+unsafe impl Sync for FileIoUring {}
+
+impl FileIoUring {
+    fn new() -> Result<Self> {
+        let instance = UringInstance::new()?;
+        let bufs = UntrustedBox::<[u8]>::new_uninit_slice(FILE_IO_BUF_NUM * FILE_IO_BUF_SIZE);
+        let iovecs: Vec<libc::iovec> = (0..FILE_IO_BUF_NUM)
+            .map(|idx| libc::iovec {
+                iov_base: unsafe { (bufs.as_mut_ptr() as *mut u8).add(idx * FILE_IO_BUF_SIZE) }
+                    as *mut libc::c_void,
+                iov_len: FILE_IO_BUF_SIZE,
+            })
+            .collect();
+        unsafe { instance.uring.register_buffers(&iovecs) }.map_err(|e| {
+            errno!(
+                e.raw_os_error().unwrap_or(EINVAL),
+                "register_buffers failed"
+            )
+        })?;
+        let free_bufs = Mutex::new((0..FILE_IO_BUF_NUM as u16).collect());
+        Ok(Self {
+            instance,
+            bufs,
+            free_bufs,
+        })
     }
 
-    pub fn disattach_uring(&self, fd: usize, uring: Arc<IoUring>) {
-        let uring: KeyableArc<IoUring> = uring.into();
-        let mut map = self.urings.lock();
-        let mut state = map.get_mut(&uring).unwrap();
-        state.unregister_one_socket();
-        drop(map);
+    /// Read from the host file at the offset.
+    ///
+    /// Return `None` if no buffer is available now.
+    pub fn read_at(
+        &self,
+        host_fd: FileDesc,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Option<Result<usize>> {
+        let buf_idx = self.free_bufs.lock().pop()?;
+        let ret = self.do_read_at(host_fd, buf, offset, buf_idx);
+        self.free_bufs.lock().push(buf_idx);
+        Some(ret)
+    }
 
-        uring.disattach_fd(fd);
+    /// Write to the host file at the offset.
+    ///
+    /// Return `None` if no buffer is available now.
+    pub fn write_at(&self, host_fd: FileDesc, buf: &[u8], offset: usize) -> Option<Result<usize>> {
+        let buf_idx = self.free_bufs.lock().pop()?;
+        let ret = self.do_write_at(host_fd, buf, offset, buf_idx);
+        self.free_bufs.lock().push(buf_idx);
+        Some(ret)
+    }
+
+    /// Flush the data (and the metadata if `datasync` is false) of the host file.
+    pub fn fsync(&self, host_fd: FileDesc, datasync: bool) -> Result<()> {
+        let retval = self.submit_and_wait(|uring, callback| unsafe {
+            uring.fsync(Fd(host_fd as _), datasync, callback)
+        });
+        if retval < 0 {
+            return_errno!(Errno::from(-retval as u32), "fsync failed");
+        }
+        Ok(())
+    }
+
+    fn do_read_at(
+        &self,
+        host_fd: FileDesc,
+        buf: &mut [u8],
+        offset: usize,
+        buf_idx: u16,
+    ) -> Result<usize> {
+        let untrusted_buf = self.untrusted_buf(buf_idx);
+        let mut nread = 0;
+        // Read chunk by chunk until the buffer is full or EOF
+        for chunk in buf.chunks_mut(FILE_IO_BUF_SIZE) {
+            let len = chunk.len();
+            let chunk_offset = offset + nread;
+            let retval = self.submit_and_wait(|uring, callback| unsafe {
+                uring.read_fixed(
+                    Fd(host_fd as _),
+                    untrusted_buf,
+                    len as u32,
+                    chunk_offset as _,
+                    buf_idx,
+                    callback,
+                )
+            });
+            if retval < 0 {
+                if nread > 0 {
+                    break;
+                }
+                return_errno!(Errno::from(-retval as u32), "read failed");
+            }
+            // Guard against Iago attack
+            let chunk_nread = retval as usize;
+            if chunk_nread > len {
+                return_errno!(EIO, "the host returns an invalid length");
+            }
+            unsafe {
+                std::ptr::copy_nonoverlapping(untrusted_buf, chunk.as_mut_ptr(), chunk_nread);
+            }
+            nread += chunk_nread;
+            if chunk_nread < len {
+                break;
+            }
+        }
+        Ok(nread)
+    }
+
+    fn do_write_at(
+        &self,
+        host_fd: FileDesc,
+        buf: &[u8],
+        offset: usize,
+        buf_idx: u16,
+    ) -> Result<usize> {
+        let untrusted_buf = self.untrusted_buf(buf_idx);
+        let mut nwritten = 0;
+        for chunk in buf.chunks(FILE_IO_BUF_SIZE) {
+            let len = chunk.len();
+            let chunk_offset = offset + nwritten;
+            unsafe {
+                std::ptr::copy_nonoverlapping(chunk.as_ptr(), untrusted_buf, len);
+            }
+            let retval = self.submit_and_wait(|uring, callback| unsafe {
+                uring.write_fixed(
+                    Fd(host_fd as _),
+                    untrusted_buf,
+                    len as u32,
+                    chunk_offset as _,
+                    buf_idx,
+                    callback,
+                )
+            });
+            if retval < 0 {
+                if nwritten > 0 {
+                    break;
+                }
+                return_errno!(Errno::from(-retval as u32), "write failed");
+            }
+            // Guard against Iago attack
+            let chunk_nwritten = retval as usize;
+            if chunk_nwritten > len {
+                return_errno!(EIO, "the host returns an invalid length");
+            }
+            nwritten += chunk_nwritten;
+            if chunk_nwritten < len {
+                break;
+            }
+        }
+        Ok(nwritten)
+    }
+
+    fn untrusted_buf(&self, buf_idx: u16) -> *mut u8 {
+        debug_assert!((buf_idx as usize) < FILE_IO_BUF_NUM);
+        unsafe { (self.bufs.as_mut_ptr() as *mut u8).add(buf_idx as usize * FILE_IO_BUF_SIZE) }
+    }
+
+    /// Submit a request and wait for its completion, returning the result.
+    ///
+    /// The waiting cannot be interrupted, since the host kernel may still
+    /// access the buffer of the request.
+    fn submit_and_wait(
+        &self,
+        submit: impl FnOnce(&IoUring, Box<dyn FnOnce(i32) + Send>) -> IoHandle,
+    ) -> i32 {
+        let waiter = Waiter::new();
+        let waker: Waker = waiter.waker();
+        let callback = Box::new(move |_retval: i32| waker.wake());
+        let handle = submit(&self.instance.uring, callback);
+
+        // Spin for a while as the request may complete soon
+        for _ in 0..FILE_IO_SPIN_RETRIES {
+            if let Some(retval) = handle.retval() {
+                return retval;
+            }
+            std::hint::spin_loop();
+        }
+
+        loop {
+            if let Some(retval) = handle.retval() {
+                return retval;
+            }
+            let _ = waiter.wait(None);
+            waiter.reset();
+        }
     }
 }
@@ -242,7 +242,10 @@ impl EpollFile {
             return_errno!(EINVAL, "a epoll file cannot epoll itself");
         }
 
-        self.check_flags(&flags);
+        let flags = self.check_flags(flags, &event)?;
+        if flags.contains(EpollFlags::EXCLUSIVE) && file.as_epoll_file().is_ok() {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE cannot be used on an epoll file");
+        }
         self.prepare_event(&mut event);
 
         let ep_entry = Arc::new(EpollEntry::new(fd, file, event, flags));
@@ -263,7 +266,15 @@ impl EpollFile {
             // Start observing events on the target file.
             let weak_observer = self.weak_self.clone() as Weak<dyn Observer<_>>;
             let weak_ep_entry = Arc::downgrade(&ep_entry);
-            notifier.register(weak_observer, Some(IoEvents::all()), Some(weak_ep_entry));
+            if flags.contains(EpollFlags::EXCLUSIVE) {
+                notifier.register_exclusive(
+                    weak_observer,
+                    Some(IoEvents::all()),
+                    Some(weak_ep_entry),
+                );
+            } else {
+                notifier.register(weak_observer, Some(IoEvents::all()), Some(weak_ep_entry));
+            }
 
             // Handle host file
             if ep_entry.file.host_fd().is_some() {
@@ -301,7 +312,10 @@ impl EpollFile {
     }
 
     fn mod_interest(&self, fd: FileDesc, mut event: EpollEvent, flags: EpollFlags) -> Result<()> {
-        self.check_flags(&flags);
+        if flags.contains(EpollFlags::EXCLUSIVE) {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE cannot be modified");
+        }
+        let flags = self.check_flags(flags, &event)?;
         self.prepare_event(&mut event);
 
         // A critical section protected by the lock of self.interest
@@ -314,6 +328,9 @@ impl EpollFile {
 
             let new_ep_inner = EpollEntryInner { event, flags };
             let mut old_ep_inner = ep_entry.inner.lock().unwrap();
+            if old_ep_inner.flags.contains(EpollFlags::EXCLUSIVE) {
+                return_errno!(EINVAL, "an exclusive entry cannot be modified");
+            }
             *old_ep_inner = new_ep_inner;
             drop(old_ep_inner);
 
@@ -331,7 +348,10 @@ impl EpollFile {
         Ok(())
     }
 
-    fn push_ready(&self, ep_entry: Arc<EpollEntry>) {
+    /// Push an entry into the ready list and wake up the waiters.
+    ///
+    /// Returns whether any waiting threads are woken up.
+    fn push_ready(&self, ep_entry: Arc<EpollEntry>) -> bool {
         // Fast path to avoid locking
         if ep_entry.is_ready.load(Ordering::Relaxed) {
             // Concurrency note:
@@ -342,13 +362,13 @@ impl EpollFile {
             // `false`, then the `EpollEntry` must be popped out of the ready list and its
             // corresponding file must be polled in the `wait` method. This means that we have
             // taken into account any interesting events happened on the file so far.
-            return;
+            return false;
         }
 
-        self.push_ready_iter(std::iter::once(ep_entry));
+        self.push_ready_iter(std::iter::once(ep_entry))
     }
 
-    fn push_ready_iter<I: Iterator<Item = Arc<EpollEntry>>>(&self, ep_entries: I) {
+    fn push_ready_iter<I: Iterator<Item = Arc<EpollEntry>>>(&self, ep_entries: I) -> bool {
         let mut has_pushed_any = false;
 
         // A critical section protected by self.ready.lock()
@@ -367,7 +387,9 @@ impl EpollFile {
         }
 
         if has_pushed_any {
-            self.mark_ready();
+            self.mark_ready()
+        } else {
+            false
         }
     }
 
@@ -386,15 +408,25 @@ impl EpollFile {
         }
     }
 
-    fn mark_ready(&self) {
+    fn mark_ready(&self) -> bool {
         self.notifier.broadcast(&IoEvents::IN);
-        self.waiters.dequeue_and_wake_all();
+        self.waiters.dequeue_and_wake_all() > 0
     }
 
-    fn check_flags(&self, flags: &EpollFlags) {
-        if flags.intersects(EpollFlags::EXCLUSIVE | EpollFlags::WAKE_UP) {
-            warn!("{:?} contains unsupported flags", flags);
+    /// Check the flags and return the ones to take effect.
+    fn check_flags(&self, mut flags: EpollFlags, event: &EpollEvent) -> Result<EpollFlags> {
+        if flags.contains(EpollFlags::EXCLUSIVE) {
+            // Same as Linux, only the events that are meaningful to wake up
+            // one of many waiters are allowed
+            let allowed_events = IoEvents::IN | IoEvents::OUT | IoEvents::ERR | IoEvents::HUP;
+            if flags.contains(EpollFlags::ONE_SHOT) || !allowed_events.contains(event.mask()) {
+                return_errno!(EINVAL, "invalid events or flags with EPOLLEXCLUSIVE");
+            }
         }
+        // There is no system suspend in enclaves. So EPOLLWAKEUP is ignored,
+        // which is what Linux does without the CAP_BLOCK_SUSPEND capability.
+        flags.remove(EpollFlags::WAKE_UP);
+        Ok(flags)
     }
 
     fn prepare_event(&self, event: &mut EpollEvent) {
@@ -471,6 +503,25 @@ impl Observer<IoEvents> for EpollFile {
 
         self.push_ready(ep_entry);
     }
+
+    fn on_exclusive_event(
+        &self,
+        _events: &IoEvents,
+        metadata: &Option<Weak<dyn Any + Send + Sync>>,
+    ) -> bool {
+        let ep_entry_opt = metadata
+            .as_ref()
+            .and_then(|weak_any| weak_any.upgrade())
+            .and_then(|strong_any| strong_any.downcast().ok());
+        let ep_entry: Arc<EpollEntry> = match ep_entry_opt {
+            None => return false,
+            Some(ep_entry) => ep_entry,
+        };
+
+        // Let the next exclusive observer take over the events if no thread
+        // is waiting on this epoll file
+        self.push_ready(ep_entry)
+    }
 }
 
 impl Observer<FileTableEvent> for EpollFile {
@@ -0,0 +1,244 @@
+//! A poller that drives the readiness of host files in the background.
+//!
+//! The events of a host file, e.g., a host socket, are only updated when the
+//! host is polled. Epoll and poll do so while they wait, but the requests that
+//! complete asynchronously, e.g., the AIO poll requests and the pending
+//! io_uring requests, have no thread to wait for them. Such requests watch the host files with the poller instead, whose
+//! thread waits for the events on the host and broadcasts them through the
+//! notifiers of the files.
+
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::EpollFlags;
+use crate::fs::{HostFd, IoEvents};
+use crate::prelude::*;
+use crate::time::timespec_t;
+
+lazy_static! {
+    pub static ref HOST_POLLER: HostPoller = HostPoller::new();
+}
+
+/// The maximal number of the events got from the host at once.
+const MAX_EVENTS: usize = 64;
+
+pub struct HostPoller {
+    // The watched host files, indexed by their host fds
+    watches: SgxMutex<HashMap<FileDesc, Watch>>,
+    host_epoll_fd: HostFd,
+    is_started: AtomicBool,
+}
+
+struct Watch {
+    file: FileRef,
+    // The events to wait for. They are disarmed once any of them happens.
+    armed: IoEvents,
+    nr_watchers: usize,
+}
+
+impl HostPoller {
+    fn new() -> Self {
+        let raw_host_fd = (|| -> Result<u32> {
+            let raw_host_fd = try_libc!(libc::ocall::epoll_create1(0)) as u32;
+            Ok(raw_host_fd)
+        })()
+        .expect("epoll_create should never fail");
+        Self {
+            watches: SgxMutex::new(HashMap::new()),
+            host_epoll_fd: HostFd::new(raw_host_fd),
+            is_started: AtomicBool::new(false),
+        }
+    }
+
+    /// Watch the host file for the events in the mask, which are broadcast
+    /// through the notifier of the file once any of them happens.
+    ///
+    /// Each call must be paired with a call to `unwatch`.
+    pub fn watch(&self, file: &FileRef, mask: IoEvents) -> Result<()> {
+        let host_fd = file
+            .host_fd()
+            .ok_or_else(|| errno!(EINVAL, "not a host file"))?
+            .to_raw();
+        self.start();
+
+        let mut watches = self.watches.lock().unwrap();
+        let is_new = !watches.contains_key(&host_fd);
+        let watch = watches.entry(host_fd).or_insert_with(|| Watch {
+            file: file.clone(),
+            armed: IoEvents::empty(),
+            nr_watchers: 0,
+        });
+        watch.armed |= mask;
+        let op = if is_new {
+            libc::EPOLL_CTL_ADD
+        } else {
+            libc::EPOLL_CTL_MOD
+        };
+        if let Err(e) = self.do_epoll_ctl(op, host_fd, Some(watch.armed)) {
+            if is_new {
+                watches.remove(&host_fd);
+            }
+            return Err(e);
+        }
+        watch.nr_watchers += 1;
+        Ok(())
+    }
+
+    /// Arm the events in the mask again for a host file that is watched,
+    /// e.g., when the events have happened but are consumed by others.
+    pub fn rearm(&self, file: &FileRef, mask: IoEvents) -> Result<()> {
+        let host_fd = match file.host_fd() {
+            Some(host_fd) => host_fd.to_raw(),
+            None => return_errno!(EINVAL, "not a host file"),
+        };
+        let mut watches = self.watches.lock().unwrap();
+        let watch = watches
+            .get_mut(&host_fd)
+            .ok_or_else(|| errno!(ENOENT, "the host file is not watched"))?;
+        watch.armed |= mask;
+        self.do_epoll_ctl(libc::EPOLL_CTL_MOD, host_fd, Some(watch.armed))
+    }
+
+    /// Stop watching the host file.
+    pub fn unwatch(&self, file: &FileRef) {
+        let host_fd = match file.host_fd() {
+            Some(host_fd) => host_fd.to_raw(),
+            None => return,
+        };
+        let mut watches = self.watches.lock().unwrap();
+        let watch = match watches.get_mut(&host_fd) {
+            Some(watch) => watch,
+            None => return,
+        };
+        watch.nr_watchers -= 1;
+        if watch.nr_watchers == 0 {
+            watches.remove(&host_fd);
+            if let Err(e) = self.do_epoll_ctl(libc::EPOLL_CTL_DEL, host_fd, None) {
+                warn!("failed to unwatch the host file: {:?}", e);
+            }
+        }
+    }
+
+    /// Poll the host file at once, returning the ready events among the ones
+    /// in the mask. The events of the file are updated as well.
+    pub fn poll_now(&self, file: &FileRef, mask: IoEvents) -> Result<IoEvents> {
+        let host_fd = file
+            .host_fd()
+            .ok_or_else(|| errno!(EINVAL, "not a host file"))?
+            .to_raw();
+        let mask = mask | IoEvents::ALWAYS_POLL;
+        let mut pollfd = libc::pollfd {
+            fd: host_fd as i32,
+            events: mask.to_raw() as i16,
+            revents: 0,
+        };
+        let mut zero_timeout = timespec_t::from(Duration::from_secs(0));
+        try_libc!({
+            let mut retval = 0;
+            let status = unsafe {
+                occlum_ocall_poll_with_eventfd(&mut retval, &mut pollfd, 1, &mut zero_timeout, -1)
+            };
+            assert!(status == sgx_status_t::SGX_SUCCESS);
+            retval
+        });
+
+        let events = IoEvents::from_raw(pollfd.revents as u16 as u32) & mask;
+        file.update_host_events(&events, &mask, false);
+        Ok(events)
+    }
+
+    fn start(&self) {
+        if self.is_started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        std::thread::spawn(move || loop {
+            HOST_POLLER.poll_events();
+        });
+    }
+
+    /// Wait for the events of the watched host files, and broadcast them.
+    fn poll_events(&self) {
+        let mut raw_events = vec![MaybeUninit::<libc::epoll_event>::uninit(); MAX_EVENTS];
+        let count = match (|| -> Result<usize> {
+            let count = try_libc!(libc::ocall::epoll_wait(
+                self.host_epoll_fd.to_raw() as i32,
+                raw_events.as_mut_ptr() as *mut _,
+                raw_events.len() as c_int,
+                -1,
+            )) as usize;
+            assert!(count <= MAX_EVENTS);
+            Ok(count)
+        })() {
+            Ok(count) => count,
+            Err(e) => {
+                if e.errno() != EINTR {
+                    warn!("unexpected error from ocall::epoll_wait(): {:?}", e);
+                }
+                return;
+            }
+        };
+
+        let ready_files: Vec<(FileRef, IoEvents, IoEvents)> = {
+            let mut watches = self.watches.lock().unwrap();
+            raw_events[..count]
+                .iter()
+                .filter_map(|raw_event| {
+                    let raw_event = unsafe { raw_event.assume_init() };
+                    let host_fd = raw_event.u64 as FileDesc;
+                    let events = IoEvents::from_raw(raw_event.events as u32);
+                    // The host file may be unwatched
+                    let watch = watches.get_mut(&host_fd)?;
+                    let mask = watch.armed | IoEvents::ALWAYS_POLL;
+
+                    // The events that happened are disarmed. The errors and
+                    // hangups are permanent, so all events are disarmed.
+                    watch.armed = if events.intersects(IoEvents::ALWAYS_POLL) {
+                        IoEvents::empty()
+                    } else {
+                        watch.armed - events
+                    };
+                    if !watch.armed.is_empty() {
+                        if let Err(e) =
+                            self.do_epoll_ctl(libc::EPOLL_CTL_MOD, host_fd, Some(watch.armed))
+                        {
+                            warn!("failed to rearm the host file: {:?}", e);
+                        }
+                    }
+                    Some((watch.file.clone(), events, mask))
+                })
+                .collect()
+        };
+        // The notifiers are not triggered with the lock held, since the
+        // observers may watch or unwatch the files.
+        for (file, events, mask) in ready_files {
+            file.update_host_events(&events, &mask, true);
+        }
+    }
+
+    fn do_epoll_ctl(&self, raw_cmd: i32, host_fd: FileDesc, mask: Option<IoEvents>) -> Result<()> {
+        // The watches are one-shot to avoid busy polling the ready host files
+        let c_event = mask.map(|mask| libc::epoll_event {
+            events: mask.to_raw() | EpollFlags::ONE_SHOT.bits(),
+            u64: host_fd as u64,
+        });
+        try_libc!(libc::ocall::epoll_ctl(
+            self.host_epoll_fd.to_raw() as i32,
+            raw_cmd,
+            host_fd as i32,
+            c_event.as_ref().map_or(ptr::null(), |c_event| c_event) as *mut _,
+        ));
+        Ok(())
+    }
+}
+
+extern "C" {
+    fn occlum_ocall_poll_with_eventfd(
+        ret: *mut i32,
+        fds: *mut libc::pollfd,
+        nfds: u32,
+        timeout: *mut timespec_t,
+        eventfd_idx: i32,
+    ) -> sgx_status_t;
+}
@@ -1,6 +1,7 @@
 use super::*;
 
 mod epoll;
+mod host_poller;
 mod poll_new;
 // TODO: the following three modules will soon be removed
 mod io_event;
@@ -8,6 +9,7 @@ mod poll;
 mod select;
 
 pub use self::epoll::{AsEpollFile, EpollCtl, EpollEvent, EpollFile, EpollFlags};
+pub use self::host_poller::{HostPoller, HOST_POLLER};
 pub use self::io_event::{
     clear_notifier_status, notify_thread, wait_for_notification, IoEvent, THREAD_NOTIFIERS,
 };
@@ -4,15 +4,16 @@ use untrusted::{SliceAsMutPtrAndLen, SliceAsPtrAndLen, UntrustedSlice, Untrusted
 
 pub use self::io_multiplexing::{
     clear_notifier_status, notify_thread, wait_for_notification, AsEpollFile, EpollEvent, IoEvent,
-    PollEvent, PollEventFlags, PollFd, THREAD_NOTIFIERS,
+    PollEvent, PollEventFlags, PollFd, HOST_POLLER, THREAD_NOTIFIERS,
 };
 pub use self::socket::{
-    mmsghdr, socketpair, unix_socket, Addr, AnyAddr, AsUnixSocket, Domain, GetAcceptConnCmd,
-    GetDomainCmd, GetErrorCmd, GetOutputAsBytes, GetPeerNameCmd, GetRecvBufSizeCmd,
-    GetRecvTimeoutCmd, GetSendBufSizeCmd, GetSendTimeoutCmd, GetSockOptRawCmd, GetTypeCmd,
-    HostSocket, HostSocketType, Iovs, IovsMut, RecvFlags, SendFlags, SetRecvBufSizeCmd,
-    SetRecvTimeoutCmd, SetSendBufSizeCmd, SetSendTimeoutCmd, SetSockOptRawCmd, Shutdown,
-    SliceAsLibcIovec, SockAddr, SockOptName, SocketFile, SocketType, UnixAddr, UringSocketType,
+    mmsghdr, socketpair, unix_socket, Addr, AnyAddr, AsNetlinkSocket, AsUnixSocket, Domain,
+    GetAcceptConnCmd, GetDomainCmd, GetErrorCmd, GetOutputAsBytes, GetPeerNameCmd,
+    GetRecvBufSizeCmd, GetRecvTimeoutCmd, GetSendBufSizeCmd, GetSendTimeoutCmd, GetSockOptRawCmd,
+    GetTypeCmd, HostSocket, HostSocketType, Iovs, IovsMut, NetlinkSocket, RecvFlags, SendFlags,
+    SetRecvBufSizeCmd, SetRecvTimeoutCmd, SetSendBufSizeCmd, SetSendTimeoutCmd, SetSockOptRawCmd,
+    Shutdown, SliceAsLibcIovec, SockAddr, SockOptName, SocketFile, SocketType, UnixAddr,
+    UringSocketType, NETLINK_ROUTE,
 };
 pub use self::syscalls::*;
 
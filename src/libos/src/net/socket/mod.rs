@@ -1,22 +1,25 @@
 use super::*;
 
 mod host;
+mod netlink;
 mod sockopt;
 mod unix;
 mod uring;
 mod util;
 
 pub use self::host::{HostSocket, HostSocketType};
+pub use self::netlink::{AsNetlinkSocket, NetlinkSocket, NETLINK_ROUTE};
 pub use self::unix::{socketpair, unix_socket, AsUnixSocket};
 pub use self::util::{
-    mmsghdr, Addr, AnyAddr, CMessages, CSockAddr, CmsgData, Domain, Iovs, IovsMut, Ipv4Addr,
-    Ipv4SocketAddr, Ipv6SocketAddr, MsgFlags, RecvFlags, SendFlags, Shutdown, SliceAsLibcIovec,
-    SockAddr, SocketFlags, SocketProtocol, SocketType, UnixAddr,
+    mmsghdr, put_cmsg, sock_filter, sock_fprog, Addr, AnyAddr, BpfFilter, CMessages, CSockAddr,
+    CmsgData, Domain, Iovs, IovsMut, Ipv4Addr, Ipv4SocketAddr, Ipv6SocketAddr, MsgFlags, RecvFlags,
+    SendFlags, Shutdown, SliceAsLibcIovec, SockAddr, SocketFlags, SocketProtocol, SocketType,
+    UnixAddr,
 };
 pub use sockopt::{
-    GetAcceptConnCmd, GetDomainCmd, GetErrorCmd, GetOutputAsBytes, GetPeerNameCmd,
+    GetAcceptConnCmd, GetDomainCmd, GetErrorCmd, GetFilterCmd, GetOutputAsBytes, GetPeerNameCmd,
     GetRecvBufSizeCmd, GetRecvTimeoutCmd, GetSendBufSizeCmd, GetSendTimeoutCmd, GetSockOptRawCmd,
-    GetTypeCmd, SetRecvBufSizeCmd, SetRecvTimeoutCmd, SetSendBufSizeCmd, SetSendTimeoutCmd,
-    SetSockOptRawCmd, SockOptName,
+    GetTypeCmd, HostSockOpt, SetFilterCmd, SetRecvBufSizeCmd, SetRecvTimeoutCmd, SetSendBufSizeCmd,
+    SetSendTimeoutCmd, SetSockOptRawCmd, SockOptName,
 };
 pub use uring::{socket_file::SocketFile, UringSocketType};
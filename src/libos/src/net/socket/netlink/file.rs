@@ -0,0 +1,105 @@
+use std::any::Any;
+
+use super::*;
+use crate::fs::{
+    AccessMode, GetReadBufLen, IoEvents, IoNotifier, IoctlCmd, SetNonBlocking, StatusFlags,
+};
+use rcore_fs::vfs::{FileType, Metadata, Timespec};
+
+impl File for NetlinkSocket {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        let (bytes_recv, _) = self.recvfrom(buf, RecvFlags::empty())?;
+        Ok(bytes_recv)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize> {
+        self.sendto(buf, SendFlags::empty(), None)
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        if offset != 0 {
+            return_errno!(ESPIPE, "a nonzero position is not supported");
+        }
+        self.read(buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        if offset != 0 {
+            return_errno!(ESPIPE, "a nonzero position is not supported");
+        }
+        self.write(buf)
+    }
+
+    fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        let (bytes_recv, _, _, _) = self.recvmsg(bufs, RecvFlags::empty(), None)?;
+        Ok(bytes_recv)
+    }
+
+    fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        self.sendmsg(bufs, SendFlags::empty(), None, None)
+    }
+
+    fn ioctl(&self, cmd: &mut dyn IoctlCmd) -> Result<()> {
+        match_ioctl_cmd_auto_error!(cmd, {
+            cmd : GetReadBufLen => {
+                let bytes_to_read = self.bytes_to_read().min(std::i32::MAX as usize) as i32;
+                cmd.set_output(bytes_to_read as _);
+            },
+            cmd : SetNonBlocking => {
+                let nonblocking = cmd.input();
+                self.set_nonblocking(*nonblocking != 0);
+            }
+        });
+        Ok(())
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDWR)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        // Only O_NONBLOCK is supported
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.set_nonblocking(nonblocking);
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        self.pollee().poll(IoEvents::all(), None)
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(self.pollee().notifier())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        Ok(Metadata {
+            dev: 0,
+            inode: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: FileType::Socket,
+            mode: 0o666,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+}
@@ -0,0 +1,248 @@
+//! The wire format of netlink messages and rtnetlink attributes.
+
+use std::mem::size_of;
+
+use crate::prelude::*;
+
+pub const NLMSG_NOOP: u16 = 1;
+pub const NLMSG_ERROR: u16 = 2;
+pub const NLMSG_DONE: u16 = 3;
+/// Message types below this value are reserved for control messages.
+pub const NLMSG_MIN_TYPE: u16 = 16;
+
+pub const NLM_F_REQUEST: u16 = 0x1;
+pub const NLM_F_MULTI: u16 = 0x2;
+pub const NLM_F_ACK: u16 = 0x4;
+pub const NLM_F_DUMP: u16 = 0x300;
+pub const NLM_F_CAPPED: u16 = 0x100;
+
+pub const RTM_NEWLINK: u16 = 16;
+pub const RTM_GETLINK: u16 = 18;
+pub const RTM_NEWADDR: u16 = 20;
+pub const RTM_GETADDR: u16 = 22;
+pub const RTM_NEWROUTE: u16 = 24;
+pub const RTM_GETROUTE: u16 = 26;
+
+pub const IFLA_ADDRESS: u16 = 1;
+pub const IFLA_BROADCAST: u16 = 2;
+pub const IFLA_IFNAME: u16 = 3;
+pub const IFLA_MTU: u16 = 4;
+pub const IFLA_TXQLEN: u16 = 13;
+pub const IFLA_OPERSTATE: u16 = 16;
+
+pub const IFA_ADDRESS: u16 = 1;
+pub const IFA_LOCAL: u16 = 2;
+pub const IFA_LABEL: u16 = 3;
+pub const IFA_BROADCAST: u16 = 4;
+pub const IFA_FLAGS: u16 = 8;
+
+pub const RTA_DST: u16 = 1;
+pub const RTA_OIF: u16 = 4;
+pub const RTA_GATEWAY: u16 = 5;
+pub const RTA_PRIORITY: u16 = 6;
+pub const RTA_PREFSRC: u16 = 7;
+pub const RTA_TABLE: u16 = 15;
+
+/// The alignment of netlink messages and attributes.
+const NLA_ALIGNTO: usize = 4;
+
+pub fn nla_align(len: usize) -> usize {
+    (len + NLA_ALIGNTO - 1) & !(NLA_ALIGNTO - 1)
+}
+
+/// C structs that can be safely converted from and to bytes.
+///
+/// # Safety
+///
+/// The implementing type must be `repr(C)` without padding, and any bit
+/// pattern must be valid for it.
+pub unsafe trait Pod: Copy + Default {
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) }
+    }
+
+    /// Read the struct from the beginning of the bytes.
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < size_of::<Self>() {
+            return None;
+        }
+        Some(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct nlmsghdr {
+    pub nlmsg_len: u32,
+    pub nlmsg_type: u16,
+    pub nlmsg_flags: u16,
+    pub nlmsg_seq: u32,
+    pub nlmsg_pid: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ifinfomsg {
+    pub ifi_family: u8,
+    pub ifi_pad: u8,
+    pub ifi_type: u16,
+    pub ifi_index: i32,
+    pub ifi_flags: u32,
+    pub ifi_change: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ifaddrmsg {
+    pub ifa_family: u8,
+    pub ifa_prefixlen: u8,
+    pub ifa_flags: u8,
+    pub ifa_scope: u8,
+    pub ifa_index: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct rtmsg {
+    pub rtm_family: u8,
+    pub rtm_dst_len: u8,
+    pub rtm_src_len: u8,
+    pub rtm_tos: u8,
+    pub rtm_table: u8,
+    pub rtm_protocol: u8,
+    pub rtm_scope: u8,
+    pub rtm_type: u8,
+    pub rtm_flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct rtattr {
+    rta_len: u16,
+    rta_type: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sockaddr_nl {
+    pub nl_family: u16,
+    pub nl_pad: u16,
+    pub nl_pid: u32,
+    pub nl_groups: u32,
+}
+
+unsafe impl Pod for nlmsghdr {}
+unsafe impl Pod for ifinfomsg {}
+unsafe impl Pod for ifaddrmsg {}
+unsafe impl Pod for rtmsg {}
+unsafe impl Pod for rtattr {}
+unsafe impl Pod for sockaddr_nl {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for i32 {}
+
+/// A netlink message, whose header and payload have been validated.
+#[derive(Debug)]
+pub struct NlMsg<'a> {
+    pub header: nlmsghdr,
+    pub payload: &'a [u8],
+}
+
+/// Split the bytes into netlink messages.
+///
+/// Like Linux, the parsing stops at the first malformed message.
+pub fn parse_msgs(mut bytes: &[u8]) -> Vec<NlMsg> {
+    let mut msgs = Vec::new();
+    while let Some(header) = nlmsghdr::from_bytes(bytes) {
+        let msg_len = header.nlmsg_len as usize;
+        if msg_len < size_of::<nlmsghdr>() || msg_len > bytes.len() {
+            break;
+        }
+        msgs.push(NlMsg {
+            header,
+            payload: &bytes[size_of::<nlmsghdr>()..msg_len],
+        });
+        bytes = &bytes[nla_align(msg_len).min(bytes.len())..];
+    }
+    msgs
+}
+
+/// Split the bytes into rtnetlink attributes.
+///
+/// Malformed attributes result in an error.
+pub fn parse_attrs(mut bytes: &[u8]) -> Result<Vec<(u16, &[u8])>> {
+    let mut attrs = Vec::new();
+    while bytes.len() >= size_of::<rtattr>() {
+        let attr = rtattr::from_bytes(bytes).unwrap();
+        let attr_len = attr.rta_len as usize;
+        if attr_len < size_of::<rtattr>() || attr_len > bytes.len() {
+            return_errno!(EINVAL, "malformed rtnetlink attribute");
+        }
+        attrs.push((attr.rta_type, &bytes[size_of::<rtattr>()..attr_len]));
+        bytes = &bytes[nla_align(attr_len).min(bytes.len())..];
+    }
+    Ok(attrs)
+}
+
+/// A builder of a netlink message.
+pub struct NlMsgBuilder {
+    buf: Vec<u8>,
+}
+
+impl NlMsgBuilder {
+    pub fn new(msg_type: u16, flags: u16, seq: u32, pid: u32) -> Self {
+        let header = nlmsghdr {
+            nlmsg_len: 0,
+            nlmsg_type: msg_type,
+            nlmsg_flags: flags,
+            nlmsg_seq: seq,
+            nlmsg_pid: pid,
+        };
+        let mut buf = Vec::with_capacity(256);
+        buf.extend_from_slice(header.as_bytes());
+        Self { buf }
+    }
+
+    /// Append the fixed-size part of the payload.
+    pub fn push<T: Pod>(mut self, data: &T) -> Self {
+        self.push_aligned(data.as_bytes());
+        self
+    }
+
+    pub fn push_bytes(mut self, bytes: &[u8]) -> Self {
+        self.push_aligned(bytes);
+        self
+    }
+
+    pub fn attr(mut self, attr_type: u16, value: &[u8]) -> Self {
+        let attr = rtattr {
+            rta_len: (size_of::<rtattr>() + value.len()) as u16,
+            rta_type: attr_type,
+        };
+        self.buf.extend_from_slice(attr.as_bytes());
+        self.push_aligned(value);
+        self
+    }
+
+    pub fn attr_u32(self, attr_type: u16, value: u32) -> Self {
+        self.attr(attr_type, &value.to_ne_bytes())
+    }
+
+    /// Append a string attribute, which is nul-terminated.
+    pub fn attr_str(self, attr_type: u16, value: &str) -> Self {
+        let mut bytes = Vec::with_capacity(value.len() + 1);
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.push(0);
+        self.attr(attr_type, &bytes)
+    }
+
+    pub fn build(mut self) -> Vec<u8> {
+        let len = self.buf.len() as u32;
+        self.buf[..size_of::<u32>()].copy_from_slice(&len.to_ne_bytes());
+        self.buf
+    }
+
+    fn push_aligned(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(nla_align(self.buf.len()), 0);
+    }
+}
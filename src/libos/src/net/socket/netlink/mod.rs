@@ -0,0 +1,30 @@
+//! In-enclave netlink sockets.
+//!
+//! Only NETLINK_ROUTE is implemented, which is enough for getifaddrs(3),
+//! if_nameindex(3) and the route/address queries of most applications.
+//! Other netlink protocols are still handled by the host.
+
+use super::*;
+use crate::fs::File;
+
+mod file;
+mod message;
+mod route;
+mod socket;
+
+pub use self::socket::NetlinkSocket;
+
+/// The protocol number of NETLINK_ROUTE.
+pub const NETLINK_ROUTE: i32 = 0;
+
+pub trait AsNetlinkSocket {
+    fn as_netlink_socket(&self) -> Result<&NetlinkSocket>;
+}
+
+impl AsNetlinkSocket for FileRef {
+    fn as_netlink_socket(&self) -> Result<&NetlinkSocket> {
+        self.as_any()
+            .downcast_ref::<NetlinkSocket>()
+            .ok_or_else(|| errno!(ENOTSOCK, "not a netlink socket"))
+    }
+}
@@ -0,0 +1,409 @@
+//! A validated snapshot of the host's links, addresses and routes.
+//!
+//! The host is untrusted, so its rtnetlink responses are never passed through
+//! to the enclave. Instead, they are parsed into the structs below, which only
+//! keep the fields we know of and have checked, and the responses to the
+//! enclave are regenerated from these structs.
+
+use std::mem::size_of;
+
+use super::message::*;
+use super::*;
+
+/// The max length of an interface name, including the terminating nul.
+const IFNAMSIZ: usize = 16;
+/// The max length of a link-layer address.
+const MAX_ADDR_LEN: usize = 32;
+/// The max number of entries of each kind accepted from the host.
+const MAX_ENTRIES: usize = 1024;
+/// The size of the buffer to receive the responses from the host.
+const HOST_RECV_BUF_SIZE: usize = 64 * 1024;
+
+const AF_UNSPEC: u8 = libc::AF_UNSPEC as u8;
+const AF_INET: u8 = libc::AF_INET as u8;
+const AF_INET6: u8 = libc::AF_INET6 as u8;
+
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub index: i32,
+    pub link_type: u16,
+    pub flags: u32,
+    pub name: String,
+    pub mtu: Option<u32>,
+    pub txqlen: Option<u32>,
+    pub operstate: Option<u8>,
+    pub address: Option<Vec<u8>>,
+    pub broadcast: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IfAddr {
+    pub family: u8,
+    pub prefixlen: u8,
+    pub flags: u8,
+    pub scope: u8,
+    pub index: u32,
+    pub address: Option<Vec<u8>>,
+    pub local: Option<Vec<u8>>,
+    pub broadcast: Option<Vec<u8>>,
+    pub label: Option<String>,
+    pub ext_flags: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub header: rtmsg,
+    pub dst: Option<Vec<u8>>,
+    pub gateway: Option<Vec<u8>>,
+    pub prefsrc: Option<Vec<u8>>,
+    pub oif: Option<u32>,
+    pub priority: Option<u32>,
+    pub table: Option<u32>,
+}
+
+impl Link {
+    pub fn to_msg(&self, msg_flags: u16, seq: u32, pid: u32) -> Vec<u8> {
+        let info = ifinfomsg {
+            ifi_family: AF_UNSPEC,
+            ifi_pad: 0,
+            ifi_type: self.link_type,
+            ifi_index: self.index,
+            ifi_flags: self.flags,
+            ifi_change: 0,
+        };
+        let mut builder = NlMsgBuilder::new(RTM_NEWLINK, msg_flags, seq, pid)
+            .push(&info)
+            .attr_str(IFLA_IFNAME, &self.name);
+        if let Some(mtu) = self.mtu {
+            builder = builder.attr_u32(IFLA_MTU, mtu);
+        }
+        if let Some(txqlen) = self.txqlen {
+            builder = builder.attr_u32(IFLA_TXQLEN, txqlen);
+        }
+        if let Some(operstate) = self.operstate {
+            builder = builder.attr(IFLA_OPERSTATE, &[operstate]);
+        }
+        if let Some(address) = &self.address {
+            builder = builder.attr(IFLA_ADDRESS, address);
+        }
+        if let Some(broadcast) = &self.broadcast {
+            builder = builder.attr(IFLA_BROADCAST, broadcast);
+        }
+        builder.build()
+    }
+}
+
+impl IfAddr {
+    pub fn to_msg(&self, msg_flags: u16, seq: u32, pid: u32) -> Vec<u8> {
+        let info = ifaddrmsg {
+            ifa_family: self.family,
+            ifa_prefixlen: self.prefixlen,
+            ifa_flags: self.flags,
+            ifa_scope: self.scope,
+            ifa_index: self.index,
+        };
+        let mut builder = NlMsgBuilder::new(RTM_NEWADDR, msg_flags, seq, pid).push(&info);
+        if let Some(address) = &self.address {
+            builder = builder.attr(IFA_ADDRESS, address);
+        }
+        if let Some(local) = &self.local {
+            builder = builder.attr(IFA_LOCAL, local);
+        }
+        if let Some(broadcast) = &self.broadcast {
+            builder = builder.attr(IFA_BROADCAST, broadcast);
+        }
+        if let Some(label) = &self.label {
+            builder = builder.attr_str(IFA_LABEL, label);
+        }
+        if let Some(ext_flags) = self.ext_flags {
+            builder = builder.attr_u32(IFA_FLAGS, ext_flags);
+        }
+        builder.build()
+    }
+}
+
+impl Route {
+    pub fn family(&self) -> u8 {
+        self.header.rtm_family
+    }
+
+    pub fn to_msg(&self, msg_flags: u16, seq: u32, pid: u32) -> Vec<u8> {
+        let mut builder = NlMsgBuilder::new(RTM_NEWROUTE, msg_flags, seq, pid).push(&self.header);
+        if let Some(table) = self.table {
+            builder = builder.attr_u32(RTA_TABLE, table);
+        }
+        if let Some(dst) = &self.dst {
+            builder = builder.attr(RTA_DST, dst);
+        }
+        if let Some(priority) = self.priority {
+            builder = builder.attr_u32(RTA_PRIORITY, priority);
+        }
+        if let Some(prefsrc) = &self.prefsrc {
+            builder = builder.attr(RTA_PREFSRC, prefsrc);
+        }
+        if let Some(gateway) = &self.gateway {
+            builder = builder.attr(RTA_GATEWAY, gateway);
+        }
+        if let Some(oif) = self.oif {
+            builder = builder.attr_u32(RTA_OIF, oif);
+        }
+        builder.build()
+    }
+}
+
+/// Get the links of the host.
+pub fn query_links() -> Result<Vec<Link>> {
+    let mut links: Vec<Link> = Vec::new();
+    for payload in dump_from_host(RTM_GETLINK, RTM_NEWLINK)? {
+        match parse_link(&payload) {
+            Ok(link) => {
+                if links
+                    .iter()
+                    .any(|l| l.index == link.index || l.name == link.name)
+                {
+                    warn!("drop duplicated link from host: {:?}", link);
+                    continue;
+                }
+                links.push(link);
+            }
+            Err(e) => warn!("drop invalid link from host: {:?}", e),
+        }
+    }
+    Ok(links)
+}
+
+/// Get the addresses of the host, which must belong to one of the links.
+pub fn query_addrs(links: &[Link]) -> Result<Vec<IfAddr>> {
+    let mut addrs = Vec::new();
+    for payload in dump_from_host(RTM_GETADDR, RTM_NEWADDR)? {
+        match parse_addr(&payload, links) {
+            Ok(addr) => addrs.push(addr),
+            Err(e) => warn!("drop invalid address from host: {:?}", e),
+        }
+    }
+    Ok(addrs)
+}
+
+/// Get the routes of the host, whose output interfaces must be one of the links.
+pub fn query_routes(links: &[Link]) -> Result<Vec<Route>> {
+    let mut routes = Vec::new();
+    for payload in dump_from_host(RTM_GETROUTE, RTM_NEWROUTE)? {
+        match parse_route(&payload, links) {
+            Ok(route) => routes.push(route),
+            Err(e) => warn!("drop invalid route from host: {:?}", e),
+        }
+    }
+    Ok(routes)
+}
+
+/// Send a dump request to the host and collect the payloads of the responses.
+fn dump_from_host(request_type: u16, response_type: u16) -> Result<Vec<Vec<u8>>> {
+    const SEQ: u32 = 1;
+
+    let socket = HostSocket::new(
+        Domain::NETLINK,
+        SocketType::RAW,
+        SocketFlags::SOCK_CLOEXEC,
+        NETLINK_ROUTE,
+    )?;
+    // Every request starts with a family byte, which is AF_UNSPEC for all
+    let request = NlMsgBuilder::new(request_type, NLM_F_REQUEST | NLM_F_DUMP, SEQ, 0)
+        .push_bytes(&[AF_UNSPEC])
+        .build();
+    socket.sendto(&request, SendFlags::empty(), None)?;
+
+    let mut payloads = Vec::new();
+    let mut buf = vec![0; HOST_RECV_BUF_SIZE];
+    loop {
+        let (recv_len, _) = socket.recvfrom(&mut buf, RecvFlags::empty())?;
+        if recv_len == 0 || recv_len > buf.len() {
+            return_errno!(EIO, "unexpected rtnetlink response from host");
+        }
+
+        for msg in parse_msgs(&buf[..recv_len]) {
+            // Skip the messages not for us, e.g., the notifications
+            if msg.header.nlmsg_seq != SEQ {
+                continue;
+            }
+            match msg.header.nlmsg_type {
+                NLMSG_DONE => return Ok(payloads),
+                NLMSG_ERROR => {
+                    // The errno from the host is untrusted, so just report EIO
+                    let errno = i32::from_bytes(msg.payload).unwrap_or(-1);
+                    if errno == 0 {
+                        continue;
+                    }
+                    warn!("rtnetlink dump failed on host with errno {}", -errno);
+                    return_errno!(EIO, "rtnetlink dump failed on host");
+                }
+                t if t == response_type => {
+                    if payloads.len() >= MAX_ENTRIES {
+                        return_errno!(EIO, "too many rtnetlink entries from host");
+                    }
+                    payloads.push(msg.payload.to_vec());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn parse_link(payload: &[u8]) -> Result<Link> {
+    let info = ifinfomsg::from_bytes(payload).ok_or_else(|| errno!(EINVAL, "short ifinfomsg"))?;
+    if info.ifi_index <= 0 {
+        return_errno!(EINVAL, "invalid interface index");
+    }
+
+    let mut link = Link {
+        index: info.ifi_index,
+        link_type: info.ifi_type,
+        flags: info.ifi_flags,
+        name: String::new(),
+        mtu: None,
+        txqlen: None,
+        operstate: None,
+        address: None,
+        broadcast: None,
+    };
+    for (attr_type, value) in parse_attrs(&payload[nla_align(size_of::<ifinfomsg>())..])? {
+        match attr_type {
+            IFLA_IFNAME => link.name = parse_name(value)?,
+            IFLA_MTU => link.mtu = Some(parse_u32(value)?),
+            IFLA_TXQLEN => link.txqlen = Some(parse_u32(value)?),
+            IFLA_OPERSTATE => {
+                link.operstate = Some(*value.first().ok_or_else(|| errno!(EINVAL, "empty"))?)
+            }
+            IFLA_ADDRESS => link.address = Some(parse_hw_addr(value)?),
+            IFLA_BROADCAST => link.broadcast = Some(parse_hw_addr(value)?),
+            // Drop all the attributes that we do not understand
+            _ => {}
+        }
+    }
+    if link.name.is_empty() {
+        return_errno!(EINVAL, "link without name");
+    }
+    Ok(link)
+}
+
+fn parse_addr(payload: &[u8], links: &[Link]) -> Result<IfAddr> {
+    let info = ifaddrmsg::from_bytes(payload).ok_or_else(|| errno!(EINVAL, "short ifaddrmsg"))?;
+    let ip_len = ip_len_of(info.ifa_family)?;
+    if info.ifa_prefixlen as usize > ip_len * 8 {
+        return_errno!(EINVAL, "invalid prefix length");
+    }
+    if !links.iter().any(|l| l.index as u32 == info.ifa_index) {
+        return_errno!(EINVAL, "address of unknown interface");
+    }
+
+    let mut addr = IfAddr {
+        family: info.ifa_family,
+        prefixlen: info.ifa_prefixlen,
+        flags: info.ifa_flags,
+        scope: info.ifa_scope,
+        index: info.ifa_index,
+        address: None,
+        local: None,
+        broadcast: None,
+        label: None,
+        ext_flags: None,
+    };
+    for (attr_type, value) in parse_attrs(&payload[nla_align(size_of::<ifaddrmsg>())..])? {
+        match attr_type {
+            IFA_ADDRESS => addr.address = Some(parse_ip(value, ip_len)?),
+            IFA_LOCAL => addr.local = Some(parse_ip(value, ip_len)?),
+            IFA_BROADCAST => addr.broadcast = Some(parse_ip(value, ip_len)?),
+            IFA_LABEL => addr.label = Some(parse_name(value)?),
+            IFA_FLAGS => addr.ext_flags = Some(parse_u32(value)?),
+            _ => {}
+        }
+    }
+    if addr.address.is_none() && addr.local.is_none() {
+        return_errno!(EINVAL, "address without IP");
+    }
+    Ok(addr)
+}
+
+fn parse_route(payload: &[u8], links: &[Link]) -> Result<Route> {
+    let header = rtmsg::from_bytes(payload).ok_or_else(|| errno!(EINVAL, "short rtmsg"))?;
+    let ip_len = ip_len_of(header.rtm_family)?;
+    if header.rtm_dst_len as usize > ip_len * 8 || header.rtm_src_len as usize > ip_len * 8 {
+        return_errno!(EINVAL, "invalid prefix length");
+    }
+    // Only the well-known route types, i.e., from RTN_UNSPEC to RTN_XRESOLVE
+    if header.rtm_type > 11 {
+        return_errno!(EINVAL, "unknown route type");
+    }
+
+    let mut route = Route {
+        header,
+        dst: None,
+        gateway: None,
+        prefsrc: None,
+        oif: None,
+        priority: None,
+        table: None,
+    };
+    for (attr_type, value) in parse_attrs(&payload[nla_align(size_of::<rtmsg>())..])? {
+        match attr_type {
+            RTA_DST => route.dst = Some(parse_ip(value, ip_len)?),
+            RTA_GATEWAY => route.gateway = Some(parse_ip(value, ip_len)?),
+            RTA_PREFSRC => route.prefsrc = Some(parse_ip(value, ip_len)?),
+            RTA_OIF => {
+                let oif = parse_u32(value)?;
+                if !links.iter().any(|l| l.index as u32 == oif) {
+                    return_errno!(EINVAL, "route of unknown interface");
+                }
+                route.oif = Some(oif);
+            }
+            RTA_PRIORITY => route.priority = Some(parse_u32(value)?),
+            RTA_TABLE => route.table = Some(parse_u32(value)?),
+            _ => {}
+        }
+    }
+    Ok(route)
+}
+
+fn ip_len_of(family: u8) -> Result<usize> {
+    match family {
+        AF_INET => Ok(4),
+        AF_INET6 => Ok(16),
+        _ => return_errno!(EAFNOSUPPORT, "unsupported address family"),
+    }
+}
+
+fn parse_u32(value: &[u8]) -> Result<u32> {
+    if value.len() != size_of::<u32>() {
+        return_errno!(EINVAL, "invalid u32 attribute");
+    }
+    Ok(u32::from_bytes(value).unwrap())
+}
+
+fn parse_ip(value: &[u8], ip_len: usize) -> Result<Vec<u8>> {
+    if value.len() != ip_len {
+        return_errno!(EINVAL, "invalid IP address attribute");
+    }
+    Ok(value.to_vec())
+}
+
+fn parse_hw_addr(value: &[u8]) -> Result<Vec<u8>> {
+    if value.len() > MAX_ADDR_LEN {
+        return_errno!(EINVAL, "invalid hardware address attribute");
+    }
+    Ok(value.to_vec())
+}
+
+/// Parse an interface name, which must be a short, printable and nul-terminated string.
+fn parse_name(value: &[u8]) -> Result<String> {
+    let len = value
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| errno!(EINVAL, "name is not nul-terminated"))?;
+    if len == 0 || len >= IFNAMSIZ {
+        return_errno!(EINVAL, "invalid name length");
+    }
+    let name = &value[..len];
+    if !name.iter().all(|&b| b.is_ascii_graphic() && b != b'/') {
+        return_errno!(EINVAL, "invalid name");
+    }
+    Ok(String::from_utf8(name.to_vec()).unwrap())
+}
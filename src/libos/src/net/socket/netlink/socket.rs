@@ -0,0 +1,652 @@
+use std::collections::{HashSet, VecDeque};
+use std::mem::{size_of, size_of_val};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::message::*;
+use super::route::{self, Link};
+use super::*;
+use crate::events::{Pollee, Poller};
+use crate::fs::IoEvents;
+use crate::net::socket::sockopt::{timeout_to_timeval, timeval_to_timeout};
+
+/// The max length of the datagrams that carry the replies of a dump request.
+///
+/// Like Linux, the replies are packed into datagrams of about a page, which
+/// is the buffer size used by glibc and most netlink libraries.
+const MAX_DATAGRAM_LEN: usize = 4096;
+/// The default size of the send and receive buffers, same as Linux.
+const DEFAULT_BUF_SIZE: usize = 212992;
+
+const SOL_NETLINK: i32 = 270;
+const NETLINK_ADD_MEMBERSHIP: i32 = 1;
+const NETLINK_DROP_MEMBERSHIP: i32 = 2;
+const NETLINK_PKTINFO: i32 = 3;
+const NETLINK_BROADCAST_ERROR: i32 = 4;
+const NETLINK_NO_ENOBUFS: i32 = 5;
+const NETLINK_LISTEN_ALL_NSID: i32 = 8;
+const NETLINK_CAP_ACK: i32 = 10;
+const NETLINK_EXT_ACK: i32 = 11;
+const NETLINK_GET_STRICT_CHK: i32 = 12;
+
+lazy_static! {
+    /// The port IDs that are in use by the netlink sockets in the enclave.
+    static ref PORT_IDS: SgxMutex<HashSet<u32>> = SgxMutex::new(HashSet::new());
+}
+
+/// A NETLINK_ROUTE socket, whose requests are answered inside the enclave.
+///
+/// The links, addresses and routes are queried from the host on each dump
+/// request. The host responses are validated and filtered by the `route`
+/// module, and the replies are rebuilt from the validated entries, so the
+/// host cannot inject arbitrary messages into the enclave.
+///
+/// Like Linux, the replies are limited by the receive buffer. A reply that does
+/// not fit is dropped and ENOBUFS is reported by the next receive, while the
+/// datagrams of a dump are held back until there is room.
+///
+/// Modifying the host network (e.g., RTM_NEWADDR) is not allowed, and the
+/// multicast groups can be joined but no notifications are delivered.
+pub struct NetlinkSocket {
+    socket_type: SocketType,
+    inner: SgxMutex<Inner>,
+    nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+struct Inner {
+    // Zero if not bound yet
+    port_id: u32,
+    groups: u32,
+    rx_queue: VecDeque<Vec<u8>>,
+    // The total length of the datagrams in `rx_queue`
+    rx_len: usize,
+    // The datagrams of the dump in progress, which are not queued yet
+    dump_queue: VecDeque<Vec<u8>>,
+    // Whether some replies are dropped since the last receive
+    is_overrun: bool,
+    recv_buf_size: usize,
+    send_buf_size: usize,
+    recv_timeout: Option<Duration>,
+    send_timeout: Option<Duration>,
+    // The boolean options of SOL_NETLINK, which are accepted but do not
+    // change the behavior
+    options: HashSet<i32>,
+}
+
+impl NetlinkSocket {
+    pub fn new(socket_type: SocketType, flags: SocketFlags, protocol: i32) -> Result<Self> {
+        if socket_type != SocketType::RAW && socket_type != SocketType::DGRAM {
+            return_errno!(ESOCKTNOSUPPORT, "only raw and datagram types are supported");
+        }
+        if protocol != NETLINK_ROUTE {
+            return_errno!(EPROTONOSUPPORT, "only NETLINK_ROUTE is supported");
+        }
+
+        let inner = Inner {
+            port_id: 0,
+            groups: 0,
+            rx_queue: VecDeque::new(),
+            rx_len: 0,
+            dump_queue: VecDeque::new(),
+            is_overrun: false,
+            recv_buf_size: DEFAULT_BUF_SIZE,
+            send_buf_size: DEFAULT_BUF_SIZE,
+            recv_timeout: None,
+            send_timeout: None,
+            options: HashSet::new(),
+        };
+        Ok(Self {
+            socket_type,
+            inner: SgxMutex::new(inner),
+            nonblocking: AtomicBool::new(flags.contains(SocketFlags::SOCK_NONBLOCK)),
+            pollee: Pollee::new(IoEvents::OUT),
+        })
+    }
+
+    pub fn bind(&self, addr: &AnyAddr) -> Result<()> {
+        let addr = parse_sockaddr_nl(addr)?;
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.port_id == 0 {
+            inner.port_id = if addr.nl_pid == 0 {
+                alloc_port_id()
+            } else {
+                reserve_port_id(addr.nl_pid)?
+            };
+        } else if addr.nl_pid != 0 && addr.nl_pid != inner.port_id {
+            return_errno!(EINVAL, "the socket is already bound");
+        }
+        if addr.nl_groups != 0 {
+            warn!("netlink multicast groups are joined, but no notifications are delivered");
+        }
+        inner.groups = addr.nl_groups;
+        Ok(())
+    }
+
+    pub fn connect(&self, addr: &AnyAddr) -> Result<()> {
+        if addr.is_unspec() {
+            return Ok(());
+        }
+        let addr = parse_sockaddr_nl(addr)?;
+        if addr.nl_pid != 0 {
+            return_errno!(ECONNREFUSED, "only the kernel can be connected");
+        }
+
+        self.autobind();
+        Ok(())
+    }
+
+    pub fn addr(&self) -> AnyAddr {
+        let inner = self.inner.lock().unwrap();
+        new_sockaddr_nl(inner.port_id, inner.groups)
+    }
+
+    /// The peer is always the kernel.
+    pub fn peer_addr(&self) -> AnyAddr {
+        new_sockaddr_nl(0, 0)
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        return_errno!(EOPNOTSUPP, "shutdown is not supported by netlink sockets");
+    }
+
+    pub fn sendto(&self, buf: &[u8], flags: SendFlags, addr: Option<AnyAddr>) -> Result<usize> {
+        self.sendmsg(&[buf], flags, addr, None)
+    }
+
+    pub fn sendmsg(
+        &self,
+        bufs: &[&[u8]],
+        flags: SendFlags,
+        addr: Option<AnyAddr>,
+        control: Option<&[u8]>,
+    ) -> Result<usize> {
+        if flags.contains(SendFlags::MSG_OOB) {
+            return_errno!(EOPNOTSUPP, "MSG_OOB is not supported");
+        }
+        if let Some(addr) = addr.as_ref() {
+            let addr = parse_sockaddr_nl(addr)?;
+            if addr.nl_pid != 0 || addr.nl_groups != 0 {
+                return_errno!(ECONNREFUSED, "only the kernel can be sent to");
+            }
+        }
+        if control.is_some() {
+            warn!("the control messages of netlink sockets are ignored");
+        }
+
+        let len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if len > self.inner.lock().unwrap().send_buf_size {
+            return_errno!(EMSGSIZE, "the message is too long");
+        }
+        let request = bufs.concat();
+
+        let port_id = self.autobind();
+        let is_dumping = !self.inner.lock().unwrap().dump_queue.is_empty();
+        let replies = handle_requests(&request, port_id, is_dumping);
+
+        let mut inner = self.inner.lock().unwrap();
+        for datagram in replies.unicasts {
+            inner.push_unicast(datagram);
+        }
+        inner.dump_queue.extend(replies.dump);
+        inner.fill_from_dump();
+        self.update_events(&inner);
+        Ok(len)
+    }
+
+    pub fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, Option<AnyAddr>)> {
+        let (bytes_recv, addr, _, _) = self.recvmsg(&mut [buf], flags, None)?;
+        Ok((bytes_recv, addr))
+    }
+
+    pub fn recvmsg(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+        control: Option<&mut [u8]>,
+    ) -> Result<(usize, Option<AnyAddr>, MsgFlags, usize)> {
+        if flags.intersects(RecvFlags::MSG_OOB | RecvFlags::MSG_ERRQUEUE) {
+            return_errno!(EOPNOTSUPP, "the flags are not supported");
+        }
+
+        let mut poller: Option<Poller> = None;
+        let mut timeout = self.inner.lock().unwrap().recv_timeout;
+        loop {
+            if let Some((bytes_recv, msg_flags)) = self.try_recvmsg(bufs, flags)? {
+                return Ok((bytes_recv, Some(self.peer_addr()), msg_flags, 0));
+            }
+
+            if self.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT) {
+                return_errno!(EAGAIN, "no data are present to be received");
+            }
+
+            // Wait for the replies to be queued
+            if poller.is_none() {
+                let new_poller = Poller::new();
+                self.pollee.connect_poller(IoEvents::IN, &new_poller);
+                poller = Some(new_poller);
+            }
+            let events = self.pollee.poll(IoEvents::IN, None);
+            if events.is_empty() {
+                if let Err(e) = poller.as_ref().unwrap().wait_timeout(timeout.as_mut()) {
+                    match e.errno() {
+                        ETIMEDOUT => return_errno!(EAGAIN, "timeout reached"),
+                        errno => return_errno!(errno, "wait error"),
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_recvmsg(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<Option<(usize, MsgFlags)>> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_overrun {
+            inner.is_overrun = false;
+            self.update_events(&inner);
+            return_errno!(
+                ENOBUFS,
+                "some replies are dropped as the receive buffer is full"
+            );
+        }
+        let datagram = match inner.rx_queue.front() {
+            Some(datagram) => datagram,
+            None => return Ok(None),
+        };
+
+        let mut copied = 0;
+        for buf in bufs.iter_mut() {
+            let copy_len = buf.len().min(datagram.len() - copied);
+            buf[..copy_len].copy_from_slice(&datagram[copied..copied + copy_len]);
+            copied += copy_len;
+        }
+
+        let mut msg_flags = MsgFlags::empty();
+        if copied < datagram.len() {
+            msg_flags |= MsgFlags::MSG_TRUNC;
+        }
+        let bytes_recv = if flags.contains(RecvFlags::MSG_TRUNC) {
+            datagram.len()
+        } else {
+            copied
+        };
+
+        // The rest of a truncated datagram is discarded
+        if !flags.contains(RecvFlags::MSG_PEEK) {
+            let datagram = inner.rx_queue.pop_front().unwrap();
+            inner.rx_len -= datagram.len();
+            inner.fill_from_dump();
+            self.update_events(&inner);
+        }
+        Ok(Some((bytes_recv, msg_flags)))
+    }
+
+    fn update_events(&self, inner: &Inner) {
+        let mut events = IoEvents::empty();
+        if !inner.rx_queue.is_empty() || inner.is_overrun {
+            events |= IoEvents::IN;
+        }
+        if inner.is_overrun {
+            events |= IoEvents::ERR;
+        }
+        self.pollee
+            .del_events(!events & (IoEvents::IN | IoEvents::ERR));
+        self.pollee.add_events(events);
+    }
+
+    pub fn setsockopt(&self, level: i32, optname: i32, optval: &[u8]) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_RCVTIMEO) => {
+                inner.recv_timeout = timeval_to_timeout(optval)?;
+                return Ok(());
+            }
+            (libc::SOL_SOCKET, libc::SO_SNDTIMEO) => {
+                inner.send_timeout = timeval_to_timeout(optval)?;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let val = i32::from_bytes(optval).ok_or_else(|| errno!(EINVAL, "optlen is too short"))?;
+        match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_RCVBUF) | (libc::SOL_SOCKET, libc::SO_RCVBUFFORCE) => {
+                inner.recv_buf_size = (val.max(0) as usize).saturating_mul(2);
+            }
+            (libc::SOL_SOCKET, libc::SO_SNDBUF) | (libc::SOL_SOCKET, libc::SO_SNDBUFFORCE) => {
+                inner.send_buf_size = (val.max(0) as usize).saturating_mul(2);
+            }
+            (libc::SOL_SOCKET, libc::SO_PASSCRED) | (libc::SOL_SOCKET, libc::SO_TIMESTAMP) => {
+                warn!("socket option {} of netlink sockets is ignored", optname);
+            }
+            (SOL_NETLINK, NETLINK_ADD_MEMBERSHIP) | (SOL_NETLINK, NETLINK_DROP_MEMBERSHIP) => {
+                warn!("netlink multicast groups are joined, but no notifications are delivered");
+            }
+            (SOL_NETLINK, NETLINK_PKTINFO)
+            | (SOL_NETLINK, NETLINK_BROADCAST_ERROR)
+            | (SOL_NETLINK, NETLINK_NO_ENOBUFS)
+            | (SOL_NETLINK, NETLINK_LISTEN_ALL_NSID)
+            | (SOL_NETLINK, NETLINK_CAP_ACK)
+            | (SOL_NETLINK, NETLINK_EXT_ACK)
+            | (SOL_NETLINK, NETLINK_GET_STRICT_CHK) => {
+                if val != 0 {
+                    inner.options.insert(optname);
+                } else {
+                    inner.options.remove(&optname);
+                }
+            }
+            _ => return_errno!(ENOPROTOOPT, "unsupported socket option"),
+        }
+        Ok(())
+    }
+
+    pub fn getsockopt(&self, level: i32, optname: i32) -> Result<Vec<u8>> {
+        let inner = self.inner.lock().unwrap();
+        let timeout = match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_RCVTIMEO) => Some(inner.recv_timeout),
+            (libc::SOL_SOCKET, libc::SO_SNDTIMEO) => Some(inner.send_timeout),
+            _ => None,
+        };
+        if let Some(timeout) = timeout {
+            let timeval = timeout_to_timeval(timeout);
+            let bytes = unsafe {
+                std::slice::from_raw_parts(&timeval as *const _ as *const u8, size_of_val(&timeval))
+            };
+            return Ok(bytes.to_vec());
+        }
+
+        let val = match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_RCVBUF) => inner.recv_buf_size as i32,
+            (libc::SOL_SOCKET, libc::SO_SNDBUF) => inner.send_buf_size as i32,
+            (libc::SOL_SOCKET, libc::SO_TYPE) => self.socket_type as i32,
+            (libc::SOL_SOCKET, libc::SO_DOMAIN) => Domain::NETLINK as i32,
+            (libc::SOL_SOCKET, libc::SO_PROTOCOL) => NETLINK_ROUTE,
+            (libc::SOL_SOCKET, libc::SO_ERROR) => 0,
+            (SOL_NETLINK, NETLINK_PKTINFO)
+            | (SOL_NETLINK, NETLINK_BROADCAST_ERROR)
+            | (SOL_NETLINK, NETLINK_NO_ENOBUFS)
+            | (SOL_NETLINK, NETLINK_LISTEN_ALL_NSID)
+            | (SOL_NETLINK, NETLINK_CAP_ACK)
+            | (SOL_NETLINK, NETLINK_EXT_ACK)
+            | (SOL_NETLINK, NETLINK_GET_STRICT_CHK) => inner.options.contains(&optname) as i32,
+            _ => return_errno!(ENOPROTOOPT, "unsupported socket option"),
+        };
+        Ok(val.to_ne_bytes().to_vec())
+    }
+
+    /// The length of the next datagram to receive.
+    pub fn bytes_to_read(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.rx_queue.front().map_or(0, |datagram| datagram.len())
+    }
+
+    pub fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    pub(super) fn pollee(&self) -> &Pollee {
+        &self.pollee
+    }
+
+    /// Assign a port ID to the socket if it is not bound yet.
+    fn autobind(&self) -> u32 {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.port_id == 0 {
+            inner.port_id = alloc_port_id();
+        }
+        inner.port_id
+    }
+}
+
+impl Inner {
+    /// Queue a reply. Like Linux, a reply that does not fit in the receive
+    /// buffer is dropped, unless the queue is empty.
+    fn push_unicast(&mut self, datagram: Vec<u8>) {
+        if !self.rx_queue.is_empty() && self.rx_len + datagram.len() > self.recv_buf_size {
+            if !self.options.contains(&NETLINK_NO_ENOBUFS) {
+                self.is_overrun = true;
+            }
+            return;
+        }
+        self.rx_len += datagram.len();
+        self.rx_queue.push_back(datagram);
+    }
+
+    /// Move the datagrams of the dump in progress to the receive queue while
+    /// there is room.
+    fn fill_from_dump(&mut self) {
+        while let Some(datagram) = self.dump_queue.front() {
+            if !self.rx_queue.is_empty() && self.rx_len + datagram.len() > self.recv_buf_size {
+                break;
+            }
+            let datagram = self.dump_queue.pop_front().unwrap();
+            self.rx_len += datagram.len();
+            self.rx_queue.push_back(datagram);
+        }
+    }
+}
+
+impl Drop for NetlinkSocket {
+    fn drop(&mut self) {
+        let port_id = self.inner.lock().unwrap().port_id;
+        if port_id != 0 {
+            PORT_IDS.lock().unwrap().remove(&port_id);
+        }
+    }
+}
+
+impl std::fmt::Debug for NetlinkSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetlinkSocket")
+            .field("socket_type", &self.socket_type)
+            .field("nonblocking", &self.nonblocking())
+            .finish()
+    }
+}
+
+fn reserve_port_id(port_id: u32) -> Result<u32> {
+    if !PORT_IDS.lock().unwrap().insert(port_id) {
+        return_errno!(EADDRINUSE, "the port ID is in use");
+    }
+    Ok(port_id)
+}
+
+/// Allocate a port ID like Linux, i.e., the process ID for the first socket
+/// of a process and descending numbers from -4096 for the others.
+fn alloc_port_id() -> u32 {
+    let mut port_ids = PORT_IDS.lock().unwrap();
+    let pid = current!().process().pid() as u32;
+    if port_ids.insert(pid) {
+        return pid;
+    }
+    let mut port_id = -4096_i32 as u32;
+    while !port_ids.insert(port_id) {
+        port_id -= 1;
+    }
+    port_id
+}
+
+fn new_sockaddr_nl(port_id: u32, groups: u32) -> AnyAddr {
+    let addr = sockaddr_nl {
+        nl_family: Domain::NETLINK as u16,
+        nl_pad: 0,
+        nl_pid: port_id,
+        nl_groups: groups,
+    };
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let bytes = addr.as_bytes();
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            bytes.as_ptr(),
+            &mut storage as *mut _ as *mut u8,
+            bytes.len(),
+        );
+    }
+    AnyAddr::Raw(SockAddr::from_c_storage(&storage, bytes.len()))
+}
+
+fn parse_sockaddr_nl(addr: &AnyAddr) -> Result<sockaddr_nl> {
+    let (storage, len) = addr.to_c_storage();
+    if len < size_of::<sockaddr_nl>() {
+        return_errno!(EINVAL, "the netlink address is too short");
+    }
+    let addr = unsafe { *(&storage as *const _ as *const sockaddr_nl) };
+    if addr.nl_family != Domain::NETLINK as u16 {
+        return_errno!(EINVAL, "not a netlink address");
+    }
+    Ok(addr)
+}
+
+/// The replies to the requests in a message, packed into datagrams.
+#[derive(Default)]
+struct Replies {
+    /// The replies that are dropped if the receive buffer is full
+    unicasts: Vec<Vec<u8>>,
+    /// The replies of a dump, which wait for room in the receive buffer
+    dump: Vec<Vec<u8>>,
+}
+
+/// Handle the requests in a message from the user. Like Linux, only one dump
+/// can be in progress at a time, and the others fail with EBUSY.
+fn handle_requests(bytes: &[u8], port_id: u32, is_dumping: bool) -> Replies {
+    let mut replies = Replies::default();
+    for msg in parse_msgs(bytes) {
+        let header = &msg.header;
+        if header.nlmsg_flags & NLM_F_REQUEST == 0 || header.nlmsg_type < NLMSG_MIN_TYPE {
+            continue;
+        }
+
+        let is_dump = header.nlmsg_flags & NLM_F_DUMP == NLM_F_DUMP;
+        if is_dump && (is_dumping || !replies.dump.is_empty()) {
+            replies
+                .unicasts
+                .push(new_error_msg(header, EBUSY as i32, port_id));
+            continue;
+        }
+
+        match handle_request(&msg, port_id) {
+            Ok(Reply::Dump(msgs)) => replies.dump.extend(pack_msgs(msgs)),
+            Ok(Reply::Single(reply)) => {
+                replies.unicasts.push(reply);
+                if header.nlmsg_flags & NLM_F_ACK != 0 {
+                    replies.unicasts.push(new_error_msg(header, 0, port_id));
+                }
+            }
+            Err(e) => {
+                debug!("netlink request {:?} failed: {:?}", header, e);
+                replies
+                    .unicasts
+                    .push(new_error_msg(header, e.errno() as i32, port_id));
+            }
+        }
+    }
+    replies
+}
+
+enum Reply {
+    Single(Vec<u8>),
+    /// Multipart messages, ended with NLMSG_DONE.
+    Dump(Vec<Vec<u8>>),
+}
+
+fn handle_request(msg: &NlMsg, port_id: u32) -> Result<Reply> {
+    let header = &msg.header;
+    let seq = header.nlmsg_seq;
+    let is_dump = header.nlmsg_flags & NLM_F_DUMP == NLM_F_DUMP;
+    // The first byte of the payload is always the address family
+    let family = msg.payload.first().copied().unwrap_or(0);
+
+    let msgs = match header.nlmsg_type {
+        RTM_GETLINK if !is_dump => {
+            let links = route::query_links()?;
+            let link = find_link(&links, msg.payload)?;
+            return Ok(Reply::Single(link.to_msg(0, seq, port_id)));
+        }
+        RTM_GETLINK => route::query_links()?
+            .iter()
+            .map(|link| link.to_msg(NLM_F_MULTI, seq, port_id))
+            .collect::<Vec<_>>(),
+        RTM_GETADDR if is_dump => {
+            let links = route::query_links()?;
+            route::query_addrs(&links)?
+                .iter()
+                .filter(|addr| family == 0 || addr.family == family)
+                .map(|addr| addr.to_msg(NLM_F_MULTI, seq, port_id))
+                .collect::<Vec<_>>()
+        }
+        RTM_GETROUTE if is_dump => {
+            let links = route::query_links()?;
+            route::query_routes(&links)?
+                .iter()
+                .filter(|route| family == 0 || route.family() == family)
+                .map(|route| route.to_msg(NLM_F_MULTI, seq, port_id))
+                .collect::<Vec<_>>()
+        }
+        // RTM_NEW*, RTM_DEL* and RTM_SET* of links, addresses and routes
+        16..=27 if header.nlmsg_type % 4 != 2 => {
+            return_errno!(EPERM, "the host network cannot be modified");
+        }
+        _ => return_errno!(EOPNOTSUPP, "unsupported netlink request"),
+    };
+
+    let mut msgs = msgs;
+    msgs.push(
+        NlMsgBuilder::new(NLMSG_DONE, NLM_F_MULTI, seq, port_id)
+            .push(&0_i32)
+            .build(),
+    );
+    Ok(Reply::Dump(msgs))
+}
+
+/// Find the link requested by the index or the name.
+fn find_link<'a>(links: &'a [Link], payload: &[u8]) -> Result<&'a Link> {
+    let info = ifinfomsg::from_bytes(payload).ok_or_else(|| errno!(EINVAL, "short ifinfomsg"))?;
+    let link = if info.ifi_index > 0 {
+        links.iter().find(|link| link.index == info.ifi_index)
+    } else {
+        let attrs = parse_attrs(&payload[nla_align(size_of::<ifinfomsg>()).min(payload.len())..])?;
+        let name = attrs
+            .iter()
+            .find(|(attr_type, _)| *attr_type == IFLA_IFNAME)
+            .map(|(_, value)| value.split(|&b| b == 0).next().unwrap())
+            .ok_or_else(|| errno!(EINVAL, "neither index nor name is given"))?;
+        links.iter().find(|link| link.name.as_bytes() == name)
+    };
+    link.ok_or_else(|| errno!(ENODEV, "no such device"))
+}
+
+/// Build an error message, which is an ACK if the errno is zero.
+fn new_error_msg(request: &nlmsghdr, errno: i32, port_id: u32) -> Vec<u8> {
+    // Only the header of the request is echoed
+    NlMsgBuilder::new(NLMSG_ERROR, NLM_F_CAPPED, request.nlmsg_seq, port_id)
+        .push(&(-errno))
+        .push(request)
+        .build()
+}
+
+/// Pack the messages into as few datagrams as possible.
+fn pack_msgs(msgs: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut datagrams = Vec::new();
+    let mut datagram: Vec<u8> = Vec::with_capacity(MAX_DATAGRAM_LEN);
+    for msg in msgs {
+        if !datagram.is_empty() && datagram.len() + msg.len() > MAX_DATAGRAM_LEN {
+            datagrams.push(std::mem::replace(
+                &mut datagram,
+                Vec::with_capacity(MAX_DATAGRAM_LEN),
+            ));
+        }
+        datagram.extend_from_slice(&msg);
+    }
+    if !datagram.is_empty() {
+        datagrams.push(datagram);
+    }
+    datagrams
+}
@@ -0,0 +1,11 @@
+use crate::fs::IoctlCmd;
+use crate::net::socket::{sock_filter, BpfFilter};
+use crate::prelude::*;
+
+crate::impl_ioctl_cmd! {
+    pub struct SetFilterCmd<Input=Option<Arc<BpfFilter>>, Output=()> {}
+}
+
+crate::impl_ioctl_cmd! {
+    pub struct GetFilterCmd<Input=(), Output=Vec<sock_filter>> {}
+}
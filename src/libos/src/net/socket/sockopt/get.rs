@@ -1,3 +1,4 @@
+use super::HostSockOpt;
 use crate::{fs::IoctlCmd, prelude::*};
 use libc::ocall::getsockopt as do_getsockopt;
 
@@ -7,6 +8,7 @@ pub struct GetSockOptRawCmd {
     optname: i32,
     optval: Box<[u8]>,
     optlen: Option<u32>,
+    host_opt: Option<&'static HostSockOpt>,
 }
 
 impl GetSockOptRawCmd {
@@ -18,19 +20,28 @@ impl GetSockOptRawCmd {
             optname,
             optval,
             optlen: None,
+            host_opt: None,
         }
     }
 
+    /// Create a command to get an option in the table of the host options,
+    /// whose value returned by the host is checked.
+    pub fn new_checked(host_opt: &'static HostSockOpt) -> Result<Self> {
+        host_opt.check_get()?;
+        let mut cmd = Self::new(host_opt.level(), host_opt.optname(), host_opt.max_optlen());
+        cmd.host_opt = Some(host_opt);
+        Ok(cmd)
+    }
+
     pub fn execute(&mut self, fd: FileDesc) -> Result<()> {
         if self.optlen.is_some() {
             return_errno!(EINVAL, "can not execute twice");
         }
-        self.optlen = Some(getsockopt_by_host(
-            fd,
-            self.level,
-            self.optname,
-            &mut self.optval,
-        )?);
+        let optlen = getsockopt_by_host(fd, self.level, self.optname, &mut self.optval)?;
+        if let Some(host_opt) = self.host_opt {
+            host_opt.check_host_optval(&self.optval[..optlen as usize])?;
+        }
+        self.optlen = Some(optlen);
         Ok(())
     }
 
@@ -1,7 +1,7 @@
 use super::{GetRecvTimeoutCmd, GetSendTimeoutCmd};
 
 use super::{
-    GetAcceptConnCmd, GetDomainCmd, GetErrorCmd, GetPeerNameCmd, GetRecvBufSizeCmd,
+    GetAcceptConnCmd, GetDomainCmd, GetErrorCmd, GetFilterCmd, GetPeerNameCmd, GetRecvBufSizeCmd,
     GetSendBufSizeCmd, GetSockOptRawCmd, GetTypeCmd,
 };
 
@@ -102,3 +102,14 @@ impl GetOutputAsBytes for GetSendTimeoutCmd {
         })
     }
 }
+
+impl GetOutputAsBytes for GetFilterCmd {
+    fn get_output_as_bytes(&self) -> Option<&[u8]> {
+        self.output().map(|insns| unsafe {
+            std::slice::from_raw_parts(
+                insns.as_ptr() as *const u8,
+                std::mem::size_of_val(insns.as_slice()),
+            )
+        })
+    }
+}
@@ -0,0 +1,213 @@
+use std::convert::TryInto;
+
+use crate::prelude::*;
+
+const IPPROTO_IP: i32 = 0;
+const IPPROTO_TCP: i32 = 6;
+const IPPROTO_UDP: i32 = 17;
+const IPPROTO_IPV6: i32 = 41;
+
+/// The max length of an interface name, i.e., IFNAMSIZ
+const IFNAMSIZ: usize = 16;
+/// The max length of the name of a TCP congestion control algorithm
+const TCP_CA_NAME_MAX: usize = 16;
+
+/// A socket option that is forwarded to the host socket.
+///
+/// Only the options in the table below are forwarded. The option values are
+/// checked before being set, and the values returned by the host are checked
+/// before being returned to the user.
+#[derive(Debug)]
+pub struct HostSockOpt {
+    level: i32,
+    optname: i32,
+    val_type: OptValType,
+    access: OptAccess,
+}
+
+/// The type of the value of a socket option.
+#[derive(Debug, Clone, Copy)]
+enum OptValType {
+    /// An `i32` that the host returns as 0 or 1
+    Bool,
+    /// An `i32` that the host returns in the inclusive range
+    Int(i32, i32),
+    /// A C struct of the fixed size
+    Struct(usize),
+    /// An array of bytes, e.g., a string, whose length is at most the size
+    Bytes(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OptAccess {
+    ReadWrite,
+    ReadOnly,
+    WriteOnly,
+}
+
+macro_rules! host_sockopt {
+    ($level:expr, $optname:expr, $val_type:expr, $access:ident) => {
+        HostSockOpt {
+            level: $level,
+            optname: $optname,
+            val_type: $val_type,
+            access: OptAccess::$access,
+        }
+    };
+    ($level:expr, $optname:expr, $val_type:expr) => {
+        host_sockopt!($level, $optname, $val_type, ReadWrite)
+    };
+}
+
+use OptValType::*;
+
+static HOST_SOCKOPTS: &[HostSockOpt] = &[
+    // SOL_SOCKET
+    host_sockopt!(libc::SOL_SOCKET, 1, Bool), // SO_DEBUG
+    host_sockopt!(libc::SOL_SOCKET, 2, Bool), // SO_REUSEADDR
+    host_sockopt!(libc::SOL_SOCKET, 5, Bool), // SO_DONTROUTE
+    host_sockopt!(libc::SOL_SOCKET, 6, Bool), // SO_BROADCAST
+    host_sockopt!(libc::SOL_SOCKET, 7, Int(0, i32::MAX)), // SO_SNDBUF
+    host_sockopt!(libc::SOL_SOCKET, 8, Int(0, i32::MAX)), // SO_RCVBUF
+    host_sockopt!(libc::SOL_SOCKET, 9, Bool), // SO_KEEPALIVE
+    host_sockopt!(libc::SOL_SOCKET, 10, Bool), // SO_OOBINLINE
+    host_sockopt!(libc::SOL_SOCKET, 11, Bool), // SO_NO_CHECK
+    host_sockopt!(libc::SOL_SOCKET, 12, Int(0, i32::MAX)), // SO_PRIORITY
+    host_sockopt!(libc::SOL_SOCKET, 13, Struct(8)), // SO_LINGER
+    host_sockopt!(libc::SOL_SOCKET, 15, Bool), // SO_REUSEPORT
+    host_sockopt!(libc::SOL_SOCKET, 18, Int(0, i32::MAX)), // SO_RCVLOWAT
+    host_sockopt!(libc::SOL_SOCKET, 19, Int(0, i32::MAX), ReadOnly), // SO_SNDLOWAT
+    host_sockopt!(libc::SOL_SOCKET, 25, Bytes(IFNAMSIZ)), // SO_BINDTODEVICE
+    host_sockopt!(libc::SOL_SOCKET, 29, Bool), // SO_TIMESTAMP
+    host_sockopt!(libc::SOL_SOCKET, 35, Bool), // SO_TIMESTAMPNS
+    host_sockopt!(libc::SOL_SOCKET, 36, Int(i32::MIN, i32::MAX)), // SO_MARK
+    host_sockopt!(libc::SOL_SOCKET, 38, Int(0, 255), ReadOnly), // SO_PROTOCOL
+    host_sockopt!(libc::SOL_SOCKET, 40, Bool), // SO_RXQ_OVFL
+    host_sockopt!(libc::SOL_SOCKET, 46, Int(0, i32::MAX)), // SO_BUSY_POLL
+    host_sockopt!(libc::SOL_SOCKET, 49, Int(-1, i32::MAX)), // SO_INCOMING_CPU
+    host_sockopt!(libc::SOL_SOCKET, 57, Struct(8), ReadOnly), // SO_COOKIE
+    host_sockopt!(libc::SOL_SOCKET, 60, Bool), // SO_ZEROCOPY
+    // IPPROTO_IP
+    host_sockopt!(IPPROTO_IP, 1, Int(0, 255)), // IP_TOS
+    host_sockopt!(IPPROTO_IP, 2, Int(1, 255)), // IP_TTL
+    host_sockopt!(IPPROTO_IP, 4, Bytes(40)),   // IP_OPTIONS
+    host_sockopt!(IPPROTO_IP, 8, Bool),        // IP_PKTINFO
+    host_sockopt!(IPPROTO_IP, 10, Int(0, 5)),  // IP_MTU_DISCOVER
+    host_sockopt!(IPPROTO_IP, 11, Bool),       // IP_RECVERR
+    host_sockopt!(IPPROTO_IP, 12, Bool),       // IP_RECVTTL
+    host_sockopt!(IPPROTO_IP, 13, Bool),       // IP_RECVTOS
+    host_sockopt!(IPPROTO_IP, 14, Int(0, i32::MAX), ReadOnly), // IP_MTU
+    host_sockopt!(IPPROTO_IP, 15, Bool),       // IP_FREEBIND
+    host_sockopt!(IPPROTO_IP, 19, Bool),       // IP_TRANSPARENT
+    host_sockopt!(IPPROTO_IP, 24, Bool),       // IP_BIND_ADDRESS_NO_PORT
+    host_sockopt!(IPPROTO_IP, 32, Bytes(12)),  // IP_MULTICAST_IF
+    host_sockopt!(IPPROTO_IP, 33, Int(0, 255)), // IP_MULTICAST_TTL
+    host_sockopt!(IPPROTO_IP, 34, Bool),       // IP_MULTICAST_LOOP
+    host_sockopt!(IPPROTO_IP, 35, Bytes(12), WriteOnly), // IP_ADD_MEMBERSHIP
+    host_sockopt!(IPPROTO_IP, 36, Bytes(12), WriteOnly), // IP_DROP_MEMBERSHIP
+    // IPPROTO_TCP
+    host_sockopt!(IPPROTO_TCP, 1, Bool),          // TCP_NODELAY
+    host_sockopt!(IPPROTO_TCP, 2, Int(0, 65535)), // TCP_MAXSEG
+    host_sockopt!(IPPROTO_TCP, 3, Bool),          // TCP_CORK
+    host_sockopt!(IPPROTO_TCP, 4, Int(1, 32767)), // TCP_KEEPIDLE
+    host_sockopt!(IPPROTO_TCP, 5, Int(1, 32767)), // TCP_KEEPINTVL
+    host_sockopt!(IPPROTO_TCP, 6, Int(1, 127)),   // TCP_KEEPCNT
+    host_sockopt!(IPPROTO_TCP, 7, Int(1, 127)),   // TCP_SYNCNT
+    host_sockopt!(IPPROTO_TCP, 8, Int(-1, i32::MAX)), // TCP_LINGER2
+    host_sockopt!(IPPROTO_TCP, 9, Int(0, i32::MAX)), // TCP_DEFER_ACCEPT
+    host_sockopt!(IPPROTO_TCP, 10, Int(0, i32::MAX)), // TCP_WINDOW_CLAMP
+    host_sockopt!(IPPROTO_TCP, 11, Bytes(256), ReadOnly), // TCP_INFO
+    host_sockopt!(IPPROTO_TCP, 12, Bool),         // TCP_QUICKACK
+    host_sockopt!(IPPROTO_TCP, 13, Bytes(TCP_CA_NAME_MAX)), // TCP_CONGESTION
+    host_sockopt!(IPPROTO_TCP, 18, Int(0, i32::MAX)), // TCP_USER_TIMEOUT
+    host_sockopt!(IPPROTO_TCP, 23, Int(0, i32::MAX)), // TCP_FASTOPEN
+    host_sockopt!(IPPROTO_TCP, 25, Int(-1, i32::MAX)), // TCP_NOTSENT_LOWAT
+    // IPPROTO_UDP
+    host_sockopt!(IPPROTO_UDP, 1, Bool), // UDP_CORK
+    // IPPROTO_IPV6
+    host_sockopt!(IPPROTO_IPV6, 16, Int(1, 255)), // IPV6_UNICAST_HOPS
+    host_sockopt!(IPPROTO_IPV6, 17, Int(0, i32::MAX)), // IPV6_MULTICAST_IF
+    host_sockopt!(IPPROTO_IPV6, 18, Int(0, 255)), // IPV6_MULTICAST_HOPS
+    host_sockopt!(IPPROTO_IPV6, 19, Bool),        // IPV6_MULTICAST_LOOP
+    host_sockopt!(IPPROTO_IPV6, 20, Struct(20), WriteOnly), // IPV6_ADD_MEMBERSHIP
+    host_sockopt!(IPPROTO_IPV6, 21, Struct(20), WriteOnly), // IPV6_DROP_MEMBERSHIP
+    host_sockopt!(IPPROTO_IPV6, 23, Int(0, 5)),   // IPV6_MTU_DISCOVER
+    host_sockopt!(IPPROTO_IPV6, 24, Int(0, i32::MAX)), // IPV6_MTU
+    host_sockopt!(IPPROTO_IPV6, 25, Bool),        // IPV6_RECVERR
+    host_sockopt!(IPPROTO_IPV6, 26, Bool),        // IPV6_V6ONLY
+    host_sockopt!(IPPROTO_IPV6, 49, Bool),        // IPV6_RECVPKTINFO
+    host_sockopt!(IPPROTO_IPV6, 66, Bool),        // IPV6_RECVTCLASS
+    host_sockopt!(IPPROTO_IPV6, 67, Int(0, 255)), // IPV6_TCLASS
+];
+
+impl HostSockOpt {
+    /// Look up the option in the table of the options forwarded to the host.
+    pub fn lookup(level: i32, optname: i32) -> Result<&'static Self> {
+        HOST_SOCKOPTS
+            .iter()
+            .find(|opt| opt.level == level && opt.optname == optname)
+            .ok_or_else(|| errno!(ENOPROTOOPT, "the socket option is not supported"))
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn optname(&self) -> i32 {
+        self.optname
+    }
+
+    /// The length of the buffer to get the option from the host.
+    pub fn max_optlen(&self) -> u32 {
+        let len = match self.val_type {
+            Bool | Int(..) => std::mem::size_of::<i32>(),
+            Struct(size) | Bytes(size) => size,
+        };
+        len as u32
+    }
+
+    /// Check the option value to be set.
+    pub fn check_set(&self, optval: &[u8]) -> Result<()> {
+        if self.access == OptAccess::ReadOnly {
+            return_errno!(ENOPROTOOPT, "it's a read-only option");
+        }
+        let is_valid_len = match self.val_type {
+            // Like Linux, the integers of the IP level can also be a byte
+            Bool | Int(..) if self.level == IPPROTO_IP => optval.len() >= 1,
+            Bool | Int(..) => optval.len() >= std::mem::size_of::<i32>(),
+            Struct(size) => optval.len() >= size,
+            Bytes(size) => optval.len() <= size,
+        };
+        if !is_valid_len {
+            return_errno!(EINVAL, "invalid optlen");
+        }
+        Ok(())
+    }
+
+    /// Check whether the option can be got.
+    pub fn check_get(&self) -> Result<()> {
+        if self.access == OptAccess::WriteOnly {
+            return_errno!(ENOPROTOOPT, "it's a write-only option");
+        }
+        Ok(())
+    }
+
+    /// Check the option value returned by the host, which is untrusted.
+    pub fn check_host_optval(&self, optval: &[u8]) -> Result<()> {
+        let is_valid = match self.val_type {
+            Bool => read_i32(optval).map_or(false, |val| val == 0 || val == 1),
+            Int(min, max) => read_i32(optval).map_or(false, |val| min <= val && val <= max),
+            Struct(size) => optval.len() == size,
+            Bytes(size) => optval.len() <= size,
+        };
+        if !is_valid {
+            return_errno!(EINVAL, "host returns an invalid option value");
+        }
+        Ok(())
+    }
+}
+
+fn read_i32(optval: &[u8]) -> Option<i32> {
+    let bytes: [u8; 4] = optval.try_into().ok()?;
+    Some(i32::from_ne_bytes(bytes))
+}
@@ -1,3 +1,4 @@
+mod filter;
 mod get;
 mod get_acceptconn;
 mod get_domain;
@@ -6,10 +7,12 @@ mod get_output;
 mod get_peername;
 mod get_sockbuf;
 mod get_type;
+mod host_opt;
 mod set;
 mod set_sockbuf;
 mod timeout;
 
+pub use filter::{GetFilterCmd, SetFilterCmd};
 pub use get::{getsockopt_by_host, GetSockOptRawCmd};
 pub use get_acceptconn::GetAcceptConnCmd;
 pub use get_domain::GetDomainCmd;
@@ -18,10 +21,12 @@ pub use get_output::*;
 pub use get_peername::{AddrStorage, GetPeerNameCmd};
 pub use get_sockbuf::{GetRecvBufSizeCmd, GetSendBufSizeCmd};
 pub use get_type::GetTypeCmd;
+pub use host_opt::HostSockOpt;
 pub use set::{setsockopt_by_host, SetSockOptRawCmd};
 pub use set_sockbuf::{SetRecvBufSizeCmd, SetSendBufSizeCmd};
 pub use timeout::{
-    timeout_to_timeval, GetRecvTimeoutCmd, GetSendTimeoutCmd, SetRecvTimeoutCmd, SetSendTimeoutCmd,
+    timeout_to_timeval, timeval_to_timeout, GetRecvTimeoutCmd, GetSendTimeoutCmd,
+    SetRecvTimeoutCmd, SetSendTimeoutCmd,
 };
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
@@ -103,3 +108,22 @@ pub enum SockOptName {
     SO_SNDTIMEO_NEW = 67,            // struct timeval
     SO_DETACH_REUSEPORT_BPF = 68,    // BPF-related
 }
+
+/// The credentials of the peer, i.e., the value of `SO_PEERCRED`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct ucred {
+    pub pid: i32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// The value of `SO_LINGER`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+#[allow(non_camel_case_types)]
+pub struct linger {
+    pub l_onoff: i32,
+    pub l_linger: i32,
+}
@@ -33,6 +33,29 @@ pub fn timeout_to_timeval(timeout: Option<Duration>) -> timeval {
     }
 }
 
+/// Parse the value of `SO_RCVTIMEO` or `SO_SNDTIMEO`, where a zero timeval
+/// means no timeout.
+pub fn timeval_to_timeout(optval: &[u8]) -> Result<Option<Duration>> {
+    if optval.len() < std::mem::size_of::<timeval>() {
+        return_errno!(EINVAL, "invalid timeout option");
+    }
+    let timeout = unsafe { (optval.as_ptr() as *const timeval).read_unaligned() };
+    if timeout.usec < 0 || timeout.usec >= 1_000_000 {
+        return_errno!(EDOM, "time struct value is invalid");
+    }
+    // Like Linux, a negative timeout means not to wait at all
+    if timeout.sec < 0 {
+        return Ok(Some(Duration::from_secs(0)));
+    }
+    if timeout.sec == 0 && timeout.usec == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Duration::new(
+        timeout.sec as u64,
+        timeout.usec as u32 * 1000,
+    )))
+}
+
 // Same as libc::timeval
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -104,6 +104,15 @@ impl AddressSpace {
             .ok_or_else(|| errno!(EAGAIN, "No connection is incoming"))
     }
 
+    /// Pop an incoming connection like `pop_incoming`, but never block even
+    /// if the listener is blocking.
+    pub fn try_pop_incoming(&self, addr: &UnixAddr) -> Result<Endpoint> {
+        self.get_listener_ref(addr)
+            .ok_or_else(|| errno!(EINVAL, "the socket is not listening"))?
+            .try_pop_incoming()
+            .ok_or_else(|| errno!(EAGAIN, "No connection is incoming"))
+    }
+
     pub fn get_listener_ref(&self, addr: &UnixAddr) -> Option<Arc<Listener>> {
         let key = Self::get_key(addr);
         if let Some(key) = key {
@@ -2,8 +2,11 @@ use super::*;
 use events::{Event, EventFilter, Notifier, Observer};
 use fs::channel::{Channel, Consumer, Producer};
 use fs::{IoEvents, IoNotifier};
+use process::gid_t;
 use std::any::Any;
+use std::collections::VecDeque;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 pub type Endpoint = Arc<Inner>;
 
@@ -18,6 +21,7 @@ pub fn end_pair(nonblocking: bool) -> Result<(Endpoint, Endpoint)> {
         writer: pro_b,
         peer: Weak::default(),
         ancillary: RwLock::new(None),
+        controls: SgxMutex::new(VecDeque::new()),
     });
     let end_b = Arc::new(Inner {
         addr: RwLock::new(None),
@@ -25,6 +29,7 @@ pub fn end_pair(nonblocking: bool) -> Result<(Endpoint, Endpoint)> {
         writer: pro_a,
         peer: Arc::downgrade(&end_a),
         ancillary: RwLock::new(None),
+        controls: SgxMutex::new(VecDeque::new()),
     });
 
     unsafe {
@@ -44,6 +49,9 @@ pub struct Inner {
     writer: Producer<u8>,
     peer: Weak<Self>,
     ancillary: RwLock<Option<Ancillary>>,
+    // The control messages sent by the peer, which are received along with
+    // the data in order
+    controls: SgxMutex<VecDeque<Vec<u8>>>,
 }
 
 impl Inner {
@@ -86,6 +94,64 @@ impl Inner {
         self.writer.push_slices(bufs)
     }
 
+    /// Read like `readv`, but never block even if the endpoint is blocking.
+    pub fn try_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        self.reader.try_pop_slices(bufs)
+    }
+
+    /// Write like `writev`, but never block even if the endpoint is blocking.
+    pub fn try_writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        self.writer.try_push_slices(bufs)
+    }
+
+    pub fn set_send_timeout(&self, timeout: Option<Duration>) {
+        self.writer.set_timeout(timeout);
+    }
+
+    pub fn set_recv_timeout(&self, timeout: Option<Duration>) {
+        self.reader.set_timeout(timeout);
+    }
+
+    /// Resize the buffer of the data sent to the peer.
+    pub fn resize_send_buf(&self, size: usize) -> Result<()> {
+        match self.peer.upgrade() {
+            Some(peer) => self.writer.resize(&peer.reader, size),
+            None => Ok(()),
+        }
+    }
+
+    /// Resize the buffer of the data received from the peer.
+    pub fn resize_recv_buf(&self, size: usize) -> Result<()> {
+        match self.peer.upgrade() {
+            Some(peer) => peer.writer.resize(&self.reader, size),
+            None => Ok(()),
+        }
+    }
+
+    /// Queue the control messages to the peer, which are received along with
+    /// the data written next.
+    pub fn push_control(&self, control: &[u8]) -> Result<()> {
+        let peer = self
+            .peer
+            .upgrade()
+            .ok_or_else(|| errno!(EPIPE, "the peer has been closed"))?;
+        peer.controls.lock().unwrap().push_back(control.to_vec());
+        Ok(())
+    }
+
+    /// Take back the control messages queued last if the peer has not
+    /// received them, e.g., when the data fails to be written.
+    pub fn cancel_control(&self) {
+        if let Some(peer) = self.peer.upgrade() {
+            peer.controls.lock().unwrap().pop_back();
+        }
+    }
+
+    /// Pop the control messages sent by the peer.
+    pub fn pop_control(&self) -> Option<Vec<u8>> {
+        self.controls.lock().unwrap().pop_front()
+    }
+
     pub fn bytes_to_read(&self) -> usize {
         self.reader.items_to_consume()
     }
@@ -154,18 +220,46 @@ impl Inner {
 }
 
 /// Ancillary data of connected unix socket's sent/received control message.
+/// It also records the credentials of the thread that connects the endpoint,
+/// which are reported by SO_PEERCRED.
 #[derive(Clone, Debug)]
 pub struct Ancillary {
     pub(super) tid: pid_t, // currently store tid to locate file table
+    pid: pid_t,
+    uid: uid_t,
+    gid: gid_t,
 }
 
 impl Ancillary {
+    /// The ancillary data of the current thread
+    pub fn new() -> Self {
+        let current = current!();
+        let credentials = current.process().credentials();
+        Self {
+            tid: current.tid(),
+            pid: current.process().pid(),
+            uid: credentials.euid(),
+            gid: credentials.egid(),
+        }
+    }
+
     pub fn tid(&self) -> pid_t {
         self.tid
     }
+
+    pub fn pid(&self) -> pid_t {
+        self.pid
+    }
+
+    pub fn uid(&self) -> uid_t {
+        self.uid
+    }
+
+    pub fn gid(&self) -> gid_t {
+        self.gid
+    }
 }
 
-// TODO: Add SO_SNDBUF and SO_RCVBUF to set/getsockopt to dynamcally change the size.
 // This value is got from /proc/sys/net/core/rmem_max and wmem_max that are same on linux.
 pub const DEFAULT_BUF_SIZE: usize = 208 * 1024;
 
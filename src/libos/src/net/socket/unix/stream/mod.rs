@@ -3,6 +3,7 @@ use super::*;
 mod address_space;
 mod endpoint;
 mod file;
+mod sockopt;
 mod stream;
 
 pub use stream::Stream;
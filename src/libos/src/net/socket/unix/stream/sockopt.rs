@@ -0,0 +1,190 @@
+use std::convert::TryFrom;
+use std::mem::size_of;
+use std::time::Duration;
+
+use super::endpoint::{Endpoint, DEFAULT_BUF_SIZE};
+use super::stream::{Status, Stream};
+use super::*;
+use net::socket::sockopt::{linger, timeout_to_timeval, timeval_to_timeout, ucred, SockOptName};
+
+/// The min sizes of the send and receive buffers, same as Linux.
+const SOCK_MIN_SNDBUF: usize = 4608;
+const SOCK_MIN_RCVBUF: usize = 2304;
+
+/// The socket options of a unix stream socket.
+///
+/// Like Linux, the linger option is only recorded, since closing a unix
+/// socket never blocks.
+#[derive(Debug, Clone)]
+pub struct SockOptions {
+    pass_cred: bool,
+    // The sizes of the buffers, which are the default size if not set
+    send_buf_size: Option<usize>,
+    recv_buf_size: Option<usize>,
+    send_timeout: Option<Duration>,
+    recv_timeout: Option<Duration>,
+    linger: linger,
+}
+
+impl SockOptions {
+    pub fn new() -> Self {
+        Self {
+            pass_cred: false,
+            send_buf_size: None,
+            recv_buf_size: None,
+            send_timeout: None,
+            recv_timeout: None,
+            linger: linger::default(),
+        }
+    }
+
+    pub fn pass_cred(&self) -> bool {
+        self.pass_cred
+    }
+
+    /// Apply the timeouts and the sizes of the buffers to the endpoint of a
+    /// connected socket.
+    pub fn apply(&self, endpoint: &Endpoint) -> Result<()> {
+        endpoint.set_send_timeout(self.send_timeout);
+        endpoint.set_recv_timeout(self.recv_timeout);
+        if let Some(size) = self.send_buf_size {
+            endpoint.resize_send_buf(size)?;
+        }
+        if let Some(size) = self.recv_buf_size {
+            endpoint.resize_recv_buf(size)?;
+        }
+        Ok(())
+    }
+}
+
+impl Stream {
+    pub fn setsockopt(&self, level: i32, optname: i32, optval: &[u8]) -> Result<()> {
+        if level != libc::SOL_SOCKET {
+            return_errno!(EOPNOTSUPP, "only socket level options are supported");
+        }
+        let opt = SockOptName::try_from(optname)
+            .map_err(|_| errno!(ENOPROTOOPT, "Not a valid optname"))?;
+
+        let mut options = self.options();
+        match opt {
+            SockOptName::SO_PASSCRED => {
+                options.pass_cred = read_i32(optval)? != 0;
+            }
+            SockOptName::SO_SNDBUF | SockOptName::SO_SNDBUFFORCE => {
+                // The kernel doubles the value for the bookkeeping overhead
+                let size = read_i32(optval)?.max(0) as usize;
+                options.send_buf_size = Some((size.min(DEFAULT_BUF_SIZE) * 2).max(SOCK_MIN_SNDBUF));
+            }
+            SockOptName::SO_RCVBUF | SockOptName::SO_RCVBUFFORCE => {
+                let size = read_i32(optval)?.max(0) as usize;
+                options.recv_buf_size = Some((size.min(DEFAULT_BUF_SIZE) * 2).max(SOCK_MIN_RCVBUF));
+            }
+            SockOptName::SO_RCVTIMEO_OLD | SockOptName::SO_SNDTIMEO_OLD => {
+                let timeout = timeval_to_timeout(optval)?;
+                if opt == SockOptName::SO_RCVTIMEO_OLD {
+                    options.recv_timeout = timeout;
+                } else {
+                    options.send_timeout = timeout;
+                }
+            }
+            SockOptName::SO_LINGER => {
+                if optval.len() < size_of::<linger>() {
+                    return_errno!(EINVAL, "invalid linger option");
+                }
+                options.linger = unsafe { (optval.as_ptr() as *const linger).read_unaligned() };
+            }
+            SockOptName::SO_ACCEPTCONN
+            | SockOptName::SO_DOMAIN
+            | SockOptName::SO_PEERNAME
+            | SockOptName::SO_TYPE
+            | SockOptName::SO_ERROR
+            | SockOptName::SO_PEERCRED
+            | SockOptName::SO_SNDLOWAT
+            | SockOptName::SO_PEERSEC
+            | SockOptName::SO_PROTOCOL
+            | SockOptName::SO_MEMINFO
+            | SockOptName::SO_INCOMING_NAPI_ID
+            | SockOptName::SO_COOKIE
+            | SockOptName::SO_PEERGROUPS => return_errno!(ENOPROTOOPT, "it's a read-only option"),
+            _ => warn!("socket option {:?} of unix sockets is ignored", opt),
+        }
+
+        let should_apply = matches!(
+            opt,
+            SockOptName::SO_SNDBUF
+                | SockOptName::SO_SNDBUFFORCE
+                | SockOptName::SO_RCVBUF
+                | SockOptName::SO_RCVBUFFORCE
+                | SockOptName::SO_RCVTIMEO_OLD
+                | SockOptName::SO_SNDTIMEO_OLD
+        );
+        if should_apply {
+            // The status is locked before the options in connect
+            let new_options = options.clone();
+            drop(options);
+            if let Status::Connected(endpoint) = &*self.inner() {
+                new_options.apply(endpoint)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn getsockopt(&self, level: i32, optname: i32) -> Result<Vec<u8>> {
+        if level != libc::SOL_SOCKET {
+            return_errno!(EOPNOTSUPP, "only socket level options are supported");
+        }
+        let opt = SockOptName::try_from(optname)
+            .map_err(|_| errno!(ENOPROTOOPT, "Not a valid optname"))?;
+
+        let options = self.options().clone();
+        let val = match opt {
+            SockOptName::SO_PASSCRED => options.pass_cred as i32,
+            SockOptName::SO_SNDBUF => options.send_buf_size.unwrap_or(DEFAULT_BUF_SIZE) as i32,
+            SockOptName::SO_RCVBUF => options.recv_buf_size.unwrap_or(DEFAULT_BUF_SIZE) as i32,
+            SockOptName::SO_RCVTIMEO_OLD => {
+                return Ok(to_bytes(&timeout_to_timeval(options.recv_timeout)));
+            }
+            SockOptName::SO_SNDTIMEO_OLD => {
+                return Ok(to_bytes(&timeout_to_timeval(options.send_timeout)));
+            }
+            SockOptName::SO_LINGER => return Ok(to_bytes(&options.linger)),
+            SockOptName::SO_PEERCRED => {
+                // Like Linux, the pid is 0 and the ids are -1 without a peer
+                let cred = match self.peer_ancillary() {
+                    Some(peer) => ucred {
+                        pid: peer.pid() as i32,
+                        uid: peer.uid(),
+                        gid: peer.gid(),
+                    },
+                    None => ucred {
+                        pid: 0,
+                        uid: u32::MAX,
+                        gid: u32::MAX,
+                    },
+                };
+                return Ok(to_bytes(&cred));
+            }
+            SockOptName::SO_ACCEPTCONN => matches!(&*self.inner(), Status::Listening(_)) as i32,
+            SockOptName::SO_TYPE => SocketType::STREAM as i32,
+            SockOptName::SO_DOMAIN => Domain::LOCAL as i32,
+            SockOptName::SO_PROTOCOL => 0,
+            SockOptName::SO_ERROR => 0,
+            SockOptName::SO_CNX_ADVICE => return_errno!(ENOPROTOOPT, "it's a write-only option"),
+            _ => return_errno!(ENOPROTOOPT, "unsupported socket option"),
+        };
+        Ok(val.to_ne_bytes().to_vec())
+    }
+}
+
+fn read_i32(optval: &[u8]) -> Result<i32> {
+    if optval.len() < size_of::<i32>() {
+        return_errno!(EINVAL, "optlen is too short");
+    }
+    let mut bytes = [0_u8; size_of::<i32>()];
+    bytes.copy_from_slice(&optval[..size_of::<i32>()]);
+    Ok(i32::from_ne_bytes(bytes))
+}
+
+fn to_bytes<T: Copy>(val: &T) -> Vec<u8> {
+    unsafe { std::slice::from_raw_parts(val as *const T as *const u8, size_of::<T>()) }.to_vec()
+}
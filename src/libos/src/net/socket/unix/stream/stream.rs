@@ -1,11 +1,13 @@
 use super::address_space::ADDRESS_SPACE;
 use super::endpoint::{end_pair, Ancillary, Endpoint, RelayNotifier};
+use super::sockopt::SockOptions;
 use super::*;
 use events::{Event, EventFilter, Notifier, Observer};
 use fs::channel::Channel;
 use fs::IoEvents;
 use fs::{CreationFlags, FileMode};
-use net::socket::{CMessages, CmsgData};
+use net::socket::sockopt::ucred;
+use net::socket::{put_cmsg, CMessages, CmsgData};
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -20,6 +22,8 @@ pub struct Stream {
     // Use the internal notifier of RelayNotifier as the notifier of stream socket. It relays the
     // events of the endpoint, too.
     pub(super) notifier: Arc<RelayNotifier>,
+    // The socket options, which are kept when the status changes
+    options: SgxMutex<SockOptions>,
 }
 
 impl Stream {
@@ -29,12 +33,15 @@ impl Stream {
                 flags.contains(SocketFlags::SOCK_NONBLOCK),
             ))),
             notifier: Arc::new(RelayNotifier::new()),
+            options: SgxMutex::new(SockOptions::new()),
         }
     }
 
     pub fn socketpair(flags: SocketFlags) -> Result<(Self, Self)> {
         let nonblocking = flags.contains(SocketFlags::SOCK_NONBLOCK);
         let (end_a, end_b) = end_pair(nonblocking)?;
+        end_a.set_ancillary(Ancillary::new());
+        end_b.set_ancillary(Ancillary::new());
         let notifier_a = Arc::new(RelayNotifier::new());
         let notifier_b = Arc::new(RelayNotifier::new());
         notifier_a.observe_endpoint(&end_a);
@@ -43,11 +50,13 @@ impl Stream {
         let socket_a = Self {
             inner: SgxMutex::new(Status::Connected(end_a)),
             notifier: notifier_a,
+            options: SgxMutex::new(SockOptions::new()),
         };
 
         let socket_b = Self {
             inner: SgxMutex::new(Status::Connected(end_b)),
             notifier: notifier_b,
+            options: SgxMutex::new(SockOptions::new()),
         };
 
         Ok((socket_a, socket_b))
@@ -166,9 +175,8 @@ impl Stream {
                 if let Some(self_addr) = self_addr_opt {
                     end_self.set_addr(self_addr);
                 }
-                end_self.set_ancillary(Ancillary {
-                    tid: current!().tid(),
-                });
+                end_self.set_ancillary(Ancillary::new());
+                self.options().apply(&end_self)?;
 
                 ADDRESS_SPACE
                     .push_incoming(addr, end_incoming)
@@ -193,16 +201,30 @@ impl Stream {
     }
 
     pub fn accept(&self, flags: SocketFlags) -> Result<(Self, Option<UnixAddr>)> {
+        self.do_accept(flags, false)
+    }
+
+    /// Accept like `accept`, but never block even if the socket is blocking.
+    pub fn try_accept(&self, flags: SocketFlags) -> Result<(Self, Option<UnixAddr>)> {
+        self.do_accept(flags, true)
+    }
+
+    fn do_accept(&self, flags: SocketFlags, nonblocking: bool) -> Result<(Self, Option<UnixAddr>)> {
         let status = (*self.inner()).clone();
         match status {
             Status::Listening(addr) => {
-                let endpoint = ADDRESS_SPACE.pop_incoming(&addr)?;
+                let endpoint = if nonblocking {
+                    ADDRESS_SPACE.try_pop_incoming(&addr)?
+                } else {
+                    ADDRESS_SPACE.pop_incoming(&addr)?
+                };
                 endpoint.set_nonblocking(flags.contains(SocketFlags::SOCK_NONBLOCK));
-                endpoint.set_ancillary(Ancillary {
-                    tid: current!().tid(),
-                });
+                endpoint.set_ancillary(Ancillary::new());
                 let notifier = Arc::new(RelayNotifier::new());
                 notifier.observe_endpoint(&endpoint);
+                // Like Linux, the accepted socket inherits the options of the listener
+                let options = self.options().clone();
+                options.apply(&endpoint)?;
 
                 let peer_addr = endpoint.peer_addr();
 
@@ -212,6 +234,7 @@ impl Stream {
                     Self {
                         inner: SgxMutex::new(Status::Connected(endpoint)),
                         notifier: notifier,
+                        options: SgxMutex::new(options),
                     },
                     peer_addr,
                 ))
@@ -220,14 +243,14 @@ impl Stream {
         }
     }
 
-    // TODO: handle flags
+    // TODO: handle flags other than MSG_DONTWAIT
     pub fn sendto(&self, buf: &[u8], flags: SendFlags, addr: Option<&UnixAddr>) -> Result<usize> {
-        self.write(buf)
+        self.send_bufs(&[buf], flags)
     }
 
-    // TODO: handle flags
+    // TODO: handle flags other than MSG_DONTWAIT
     pub fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, Option<UnixAddr>)> {
-        let data_len = self.read(buf)?;
+        let data_len = self.recv_bufs(&mut [buf], flags)?;
         let addr = self.peer_addr().ok();
 
         debug!("recvfrom {:?}", addr);
@@ -241,16 +264,24 @@ impl Stream {
         flags: SendFlags,
         control: Option<&[u8]>,
     ) -> Result<usize> {
-        if !flags.is_empty() {
+        if !(flags - SendFlags::MSG_DONTWAIT).is_empty() {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let data_len = self.writev(bufs)?;
-        if let Some(msg_control) = control {
-            self.write(msg_control)?;
+        // The control messages are queued before the data, so that the peer
+        // receives them along with the data
+        if let Some(msg_control) = control.filter(|control| !control.is_empty()) {
+            check_credentials(msg_control)?;
+            let endpoint = self.endpoint()?;
+            endpoint.push_control(msg_control)?;
+            let res = self.send_bufs(bufs, flags);
+            if !matches!(res, Ok(data_len) if data_len > 0) {
+                endpoint.cancel_control();
+            }
+            return res;
         }
 
-        Ok(data_len)
+        self.send_bufs(bufs, flags)
     }
 
     pub fn recvmsg(
@@ -259,39 +290,110 @@ impl Stream {
         flags: RecvFlags,
         control: Option<&mut [u8]>,
     ) -> Result<(usize, Option<AnyAddr>, MsgFlags, usize)> {
-        if !flags.is_empty() {
+        if !(flags - RecvFlags::MSG_DONTWAIT).is_empty() {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let data_len = self.readv(bufs)?;
+        let data_len = self.recv_bufs(bufs, flags)?;
 
         // For stream socket, the msg_name is ignored. And other fields are not supported.
-        let control_len = if let Some(msg_control) = control {
-            let control_len = self.read(msg_control)?;
-
-            // For each control message that contains file descriptors (SOL_SOCKET and SCM_RIGHTS),
-            // reassign each fd in the message in receive end.
-            for cmsg in CMessages::from_bytes(msg_control) {
-                if let CmsgData::ScmRights(mut scm_rights) = cmsg {
-                    let send_tid = self.peer_ancillary().unwrap().tid();
-                    scm_rights.iter_and_reassign_fds(|send_fd| {
-                        let ipc_file = process::table::get_thread(send_tid)
-                            .unwrap()
-                            .files()
-                            .lock()
-                            .get(send_fd)
-                            .unwrap();
-                        current!().add_file(ipc_file.clone(), false)
-                    })
+        let mut msg_flags = MsgFlags::empty();
+        let control_len = match control {
+            Some(msg_control) if data_len > 0 => self.recv_control(msg_control, &mut msg_flags)?,
+            _ => 0,
+        };
+
+        Ok((data_len, None, msg_flags, control_len))
+    }
+
+    /// Receive the control messages sent along with the data, as well as the
+    /// credentials of the peer if SO_PASSCRED is set.
+    fn recv_control(&self, msg_control: &mut [u8], msg_flags: &mut MsgFlags) -> Result<usize> {
+        let endpoint = self.endpoint()?;
+        let mut control_len = 0;
+        let mut has_credentials = false;
+
+        if let Some(control) = endpoint.pop_control() {
+            if control.len() <= msg_control.len() {
+                control_len = control.len();
+                let msg_control = &mut msg_control[..control_len];
+                msg_control.copy_from_slice(&control);
+
+                // For each control message that contains file descriptors (SOL_SOCKET and
+                // SCM_RIGHTS), reassign each fd in the message in receive end.
+                for cmsg in CMessages::from_bytes(msg_control) {
+                    match cmsg {
+                        CmsgData::ScmRights(mut scm_rights) => {
+                            let send_tid = endpoint.peer_ancillary().unwrap().tid();
+                            scm_rights.iter_and_reassign_fds(|send_fd| {
+                                let ipc_file = process::table::get_thread(send_tid)
+                                    .unwrap()
+                                    .files()
+                                    .lock()
+                                    .get(send_fd)
+                                    .unwrap();
+                                current!().add_file(ipc_file.clone(), false)
+                            })
+                        }
+                        // The credentials sent by the peer have been checked when sent
+                        CmsgData::ScmCredentials(_) => has_credentials = true,
+                    }
                 }
-                // Unix credentials need not to be handled here
+            } else {
+                // Like Linux, the control messages are discarded if the buffer is too small
+                *msg_flags |= MsgFlags::MSG_CTRUNC;
             }
-            control_len
-        } else {
-            0
-        };
+        }
+
+        if self.options().pass_cred() && !has_credentials {
+            if let Some(peer) = endpoint.peer_ancillary() {
+                let cred = ucred {
+                    pid: peer.pid() as i32,
+                    uid: peer.uid(),
+                    gid: peer.gid(),
+                };
+                let cred_bytes = unsafe {
+                    std::slice::from_raw_parts(
+                        &cred as *const ucred as *const u8,
+                        std::mem::size_of::<ucred>(),
+                    )
+                };
+                match put_cmsg(
+                    msg_control,
+                    control_len,
+                    libc::SOL_SOCKET,
+                    libc::SCM_CREDENTIALS,
+                    cred_bytes,
+                ) {
+                    Some(len) => control_len = len,
+                    None => *msg_flags |= MsgFlags::MSG_CTRUNC,
+                }
+            }
+        }
 
-        Ok((data_len, None, MsgFlags::empty(), control_len))
+        Ok(control_len)
+    }
+
+    fn send_bufs(&self, bufs: &[&[u8]], flags: SendFlags) -> Result<usize> {
+        let status = (*self.inner()).clone();
+        match status {
+            Status::Connected(endpoint) if flags.contains(SendFlags::MSG_DONTWAIT) => {
+                endpoint.try_writev(bufs)
+            }
+            Status::Connected(endpoint) => endpoint.writev(bufs),
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        }
+    }
+
+    fn recv_bufs(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<usize> {
+        let status = (*self.inner()).clone();
+        match status {
+            Status::Connected(endpoint) if flags.contains(RecvFlags::MSG_DONTWAIT) => {
+                endpoint.try_readv(bufs)
+            }
+            Status::Connected(endpoint) => endpoint.readv(bufs),
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        }
     }
 
     /// perform shutdown on the socket.
@@ -326,6 +428,17 @@ impl Stream {
         self.inner.lock().unwrap()
     }
 
+    fn endpoint(&self) -> Result<Endpoint> {
+        match &*self.inner() {
+            Status::Connected(endpoint) => Ok(endpoint.clone()),
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        }
+    }
+
+    pub(super) fn options(&self) -> SgxMutexGuard<'_, SockOptions> {
+        self.options.lock().unwrap()
+    }
+
     fn ancillary(&self) -> Option<Ancillary> {
         match &*self.inner() {
             Status::Idle(_) => None,
@@ -334,7 +447,7 @@ impl Stream {
         }
     }
 
-    fn peer_ancillary(&self) -> Option<Ancillary> {
+    pub(super) fn peer_ancillary(&self) -> Option<Ancillary> {
         if let Status::Connected(endpoint) = &*self.inner() {
             endpoint.peer_ancillary()
         } else {
@@ -349,6 +462,35 @@ impl Stream {
     }
 }
 
+/// Check the credentials in the control messages to be sent, like
+/// `scm_check_creds` of Linux. Unless the sender is root, the pid must be the
+/// sender's, and the uid and gid must be one of the real, effective and saved
+/// IDs of the sender.
+fn check_credentials(msg_control: &[u8]) -> Result<()> {
+    let mut control = msg_control.to_vec();
+    for cmsg in CMessages::from_bytes(&mut control) {
+        if let CmsgData::ScmCredentials(scm_credentials) = cmsg {
+            let cred = scm_credentials
+                .ucred()
+                .ok_or_else(|| errno!(EINVAL, "invalid credentials"))?;
+
+            let current = current!();
+            let process = current.process();
+            let credentials = process.credentials();
+            if credentials.is_privileged() {
+                continue;
+            }
+            let is_valid = cred.pid == process.pid() as i32
+                && [credentials.ruid(), credentials.euid(), credentials.suid()].contains(&cred.uid)
+                && [credentials.rgid(), credentials.egid(), credentials.sgid()].contains(&cred.gid);
+            if !is_valid {
+                return_errno!(EPERM, "the credentials are not of the sender");
+            }
+        }
+    }
+    Ok(())
+}
+
 impl Debug for Stream {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Stream")
@@ -481,6 +623,11 @@ impl Listener {
         channel.pop().ok().flatten()
     }
 
+    pub fn try_pop_incoming(&self) -> Option<Endpoint> {
+        let channel = self.channel.read().unwrap();
+        channel.try_pop().ok().flatten()
+    }
+
     pub fn remaining(&self) -> usize {
         let channel = self.channel.read().unwrap();
         channel.items_to_consume()
@@ -1,9 +1,11 @@
 use core::time::Duration;
 use std::marker::PhantomData;
+use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use super::Timeout;
 use io_uring_callback::IoUring;
+use sgx_untrusted_alloc::UntrustedBox;
 
 use libc::ocall::getsockname as do_getsockname;
 use libc::ocall::shutdown as do_shutdown;
@@ -201,6 +203,116 @@ impl<A: Addr + 'static, R: Runtime> Common<A, R> {
         }
         Ok(())
     }
+
+    /// Send urgent data through the host socket synchronously.
+    ///
+    /// The last byte of `buf` becomes the urgent byte. The caller must ensure
+    /// that all the data buffered before are sent to keep the order.
+    pub fn host_send_oob(&self, buf: &[u8], flags: SendFlags) -> Result<usize> {
+        let mut u_buf = UntrustedBox::<[u8]>::new_uninit_slice(buf.len());
+        u_buf.copy_from_slice(buf);
+        let iovec = libc::iovec {
+            iov_base: u_buf.as_mut_ptr() as _,
+            iov_len: u_buf.len(),
+        };
+        // Never block the host thread or raise SIGPIPE on the host
+        let raw_flags = (flags | SendFlags::MSG_NOSIGNAL | SendFlags::MSG_DONTWAIT).bits();
+
+        let mut retval: isize = 0;
+        let status = unsafe {
+            occlum_ocall_sendmsg(
+                &mut retval as *mut isize,
+                self.host_fd as _,
+                ptr::null(),
+                0,
+                &iovec as *const libc::iovec,
+                1,
+                ptr::null(),
+                0,
+                raw_flags,
+            )
+        };
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        let bytes_sent = if flags.contains(SendFlags::MSG_NOSIGNAL) {
+            try_libc!(retval)
+        } else {
+            try_libc_may_epipe!(retval)
+        } as usize;
+        if bytes_sent > buf.len() {
+            return_errno!(EINVAL, "the host returned an invalid length");
+        }
+        Ok(bytes_sent)
+    }
+
+    /// Receive urgent data from the host socket synchronously.
+    ///
+    /// Unless SO_OOBINLINE is set, the urgent data is kept by the host TCP
+    /// stack instead of being put into the receive buffer. Like Linux, this
+    /// never blocks.
+    pub fn host_recv_oob(&self, buf: &mut [u8], flags: RecvFlags) -> Result<usize> {
+        let mut u_buf = UntrustedBox::<[u8]>::new_uninit_slice(buf.len());
+        let mut iovec = libc::iovec {
+            iov_base: u_buf.as_mut_ptr() as _,
+            iov_len: u_buf.len(),
+        };
+        let raw_flags = (flags & (RecvFlags::MSG_OOB | RecvFlags::MSG_PEEK)).bits();
+
+        let mut retval: isize = 0;
+        let mut msg_namelen_recvd = 0_u32;
+        let mut msg_controllen_recvd = 0_usize;
+        let mut msg_flags_recvd = 0_i32;
+        let status = unsafe {
+            occlum_ocall_recvmsg(
+                &mut retval as *mut isize,
+                self.host_fd as _,
+                ptr::null_mut(),
+                0,
+                &mut msg_namelen_recvd as *mut u32,
+                &mut iovec as *mut libc::iovec,
+                1,
+                ptr::null_mut(),
+                0,
+                &mut msg_controllen_recvd as *mut usize,
+                &mut msg_flags_recvd as *mut i32,
+                raw_flags,
+            )
+        };
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        let bytes_recvd = try_libc!(retval) as usize;
+        if bytes_recvd > buf.len() {
+            return_errno!(EINVAL, "the host returned an invalid length");
+        }
+        buf[..bytes_recvd].copy_from_slice(&u_buf[..bytes_recvd]);
+        Ok(bytes_recvd)
+    }
+}
+
+extern "C" {
+    fn occlum_ocall_sendmsg(
+        ret: *mut ssize_t,
+        fd: c_int,
+        msg_name: *const c_void,
+        msg_namelen: libc::socklen_t,
+        msg_data: *const libc::iovec,
+        msg_datalen: size_t,
+        msg_control: *const c_void,
+        msg_controllen: size_t,
+        flags: c_int,
+    ) -> sgx_status_t;
+    fn occlum_ocall_recvmsg(
+        ret: *mut ssize_t,
+        fd: c_int,
+        msg_name: *mut c_void,
+        msg_namelen: libc::socklen_t,
+        msg_namelen_recv: *mut libc::socklen_t,
+        msg_data: *mut libc::iovec,
+        msg_datalen: size_t,
+        msg_control: *mut c_void,
+        msg_controllen: size_t,
+        msg_controllen_recv: *mut size_t,
+        msg_flags: *mut c_int,
+        flags: c_int,
+    ) -> sgx_status_t;
 }
 
 impl<A: Addr + 'static, R: Runtime> std::fmt::Debug for Common<A, R> {
@@ -358,6 +358,16 @@ impl<A: Addr, R: Runtime> DatagramSocket<A, R> {
             cmd: GetIfConf => {
                 cmd.execute(self.host_fd())?;
             },
+            cmd: SetFilterCmd => {
+                self.receiver.set_filter(cmd.input().clone())?;
+            },
+            cmd: GetFilterCmd => {
+                let insns = self
+                    .receiver
+                    .filter()
+                    .map_or(Vec::new(), |filter| filter.insns().to_vec());
+                cmd.set_output(insns);
+            },
             cmd: GetReadBufLen => {
                 let read_buf_len = self.receiver.ready_len();
                 cmd.set_output(read_buf_len as _);
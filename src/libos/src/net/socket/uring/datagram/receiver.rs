@@ -2,7 +2,7 @@ use core::time::Duration;
 use std::mem::MaybeUninit;
 
 use crate::events::Poller;
-use crate::net::socket::MsgFlags;
+use crate::net::socket::{BpfFilter, MsgFlags};
 use io_uring_callback::{Fd, IoHandle};
 use sgx_untrusted_alloc::{MaybeUntrusted, UntrustedBox};
 
@@ -11,6 +11,9 @@ use crate::net::socket::uring::common::Common;
 use crate::net::socket::uring::runtime::Runtime;
 use crate::prelude::*;
 
+/// The length of the UDP header seen by the socket filters
+const UDP_HDR_LEN: usize = 8;
+
 pub struct Receiver<A: Addr + 'static, R: Runtime> {
     common: Arc<Common<A, R>>,
     inner: Mutex<Inner>,
@@ -80,9 +83,9 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
     ) -> Result<(usize, Option<A>, MsgFlags, usize)> {
         let mut inner = self.inner.lock();
 
-        if !flags.is_empty() && flags.contains(RecvFlags::MSG_OOB | RecvFlags::MSG_CMSG_CLOEXEC) {
-            // todo!("Support other flags");
-            return_errno!(EINVAL, "the socket flags is not supported");
+        // Datagram sockets have no notion of urgent data
+        if flags.contains(RecvFlags::MSG_OOB) {
+            return_errno!(EOPNOTSUPP, "MSG_OOB is not supported by datagram sockets");
         }
 
         // Mark the socket as non-readable since Datagram uses single packet
@@ -137,6 +140,9 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
             // it is time to send blocking request to iouring for notifying events.
             if !flags.contains(RecvFlags::MSG_PEEK) {
                 self.do_recv(&mut inner);
+            } else {
+                // The peeked datagram is still readable
+                self.common.pollee().add_events(Events::IN);
             }
             return Ok((recv_bytes, recv_addr, msg_flags, msg_controllen));
         }
@@ -177,6 +183,7 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
         inner.recv_len.take();
         inner.control_len.take();
         inner.error.take();
+        inner.filtered_buf.take();
 
         if inner.is_shutdown {
             info!("do_recv early return, the socket recv has been shutdown");
@@ -210,6 +217,14 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
             // Handle the normal case of a successful read
             inner.recv_len = Some(retval as usize);
 
+            if let Some(filter) = inner.filter.clone() {
+                if !receiver.filter_packet(&mut inner, &filter) {
+                    // Drop the datagram and receive the next one
+                    receiver.do_recv(&mut inner);
+                    return;
+                }
+            }
+
             let control_len = inner.req.msg.msg_controllen;
             inner.control_len = Some(control_len);
 
@@ -228,6 +243,56 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
         inner.io_handle.replace(handle);
     }
 
+    /// Run the filter on the received datagram.
+    ///
+    /// Like Linux, the filter sees the UDP header before the payload, and its
+    /// return value is the number of bytes to keep, which is at least the
+    /// header. The header is built in the LibOS, with the destination port
+    /// known by the LibOS and a zero checksum. The kept payload is copied into
+    /// the enclave, so that the host cannot change it after being filtered.
+    ///
+    /// Return false if the datagram is dropped.
+    fn filter_packet(&self, inner: &mut Inner, filter: &BpfFilter) -> bool {
+        let payload_len = inner.recv_len.unwrap().min(inner.recv_buf.len());
+        let src_port = port_of(&inner.req.addr);
+        let dst_port = self
+            .common
+            .addr()
+            .map_or([0; 2], |addr| port_of(&addr.to_c_storage().0));
+
+        let mut packet = Vec::with_capacity(UDP_HDR_LEN + payload_len);
+        packet.extend_from_slice(&src_port);
+        packet.extend_from_slice(&dst_port);
+        packet.extend_from_slice(&((UDP_HDR_LEN + payload_len) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0; 2]);
+        packet.extend_from_slice(&inner.recv_buf[..payload_len]);
+
+        let keep_len = filter.run(&packet) as usize;
+        if keep_len == 0 {
+            return false;
+        }
+        packet.drain(..UDP_HDR_LEN);
+        packet.truncate(keep_len.max(UDP_HDR_LEN) - UDP_HDR_LEN);
+        inner.recv_len = Some(packet.len());
+        inner.filtered_buf = Some(packet);
+        true
+    }
+
+    /// Attach the filter, or detach the filter if it is `None`.
+    pub fn set_filter(&self, filter: Option<Arc<BpfFilter>>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if filter.is_none() && inner.filter.is_none() {
+            return_errno!(ENOENT, "no filter is attached");
+        }
+        inner.filter = filter;
+        Ok(())
+    }
+
+    pub fn filter(&self) -> Option<Arc<BpfFilter>> {
+        let inner = self.inner.lock();
+        inner.filter.clone()
+    }
+
     pub fn initiate_async_recv(self: &Arc<Self>) {
         let mut inner = self.inner.lock();
         self.do_recv(&mut inner);
@@ -300,6 +365,9 @@ struct Inner {
     io_handle: Option<IoHandle>,
     error: Option<Errno>,
     is_shutdown: bool,
+    filter: Option<Arc<BpfFilter>>,
+    // The trusted copy of the datagram accepted by the filter
+    filtered_buf: Option<Vec<u8>>,
 }
 
 unsafe impl Send for Inner {}
@@ -316,6 +384,8 @@ impl Inner {
             io_handle: None,
             error: None,
             is_shutdown: false,
+            filter: None,
+            filtered_buf: None,
         }
     }
 
@@ -344,9 +414,13 @@ impl Inner {
 
     pub fn try_copy_buf(&self, bufs: &mut [&mut [u8]]) -> Option<usize> {
         self.recv_len.map(|recv_len| {
+            let packet = match &self.filtered_buf {
+                Some(filtered_buf) => &filtered_buf[..],
+                None => &self.recv_buf[..recv_len],
+            };
             let mut copy_len = 0;
             for buf in bufs {
-                let recv_buf = &self.recv_buf[copy_len..recv_len];
+                let recv_buf = &packet[copy_len..];
                 if buf.len() <= recv_buf.len() {
                     buf.copy_from_slice(&recv_buf[..buf.len()]);
                     copy_len += buf.len();
@@ -372,6 +446,13 @@ impl Inner {
     }
 }
 
+/// The port of an IPv4 or IPv6 address in network byte order.
+fn port_of(storage: &libc::sockaddr_storage) -> [u8; 2] {
+    // The ports of sockaddr_in and sockaddr_in6 are at the same offset
+    let addr = storage as *const _ as *const libc::sockaddr_in;
+    unsafe { (*addr).sin_port.to_ne_bytes() }
+}
+
 #[repr(C)]
 struct RecvReq {
     msg: libc::msghdr,
@@ -99,6 +99,9 @@ impl<A: Addr, R: Runtime> Sender<A, R> {
         flags: SendFlags,
         control: Option<&[u8]>,
     ) -> Result<usize> {
+        if flags.contains(SendFlags::MSG_OOB) {
+            return_errno!(EOPNOTSUPP, "MSG_OOB is not supported by datagram sockets");
+        }
         if !flags.is_empty()
             && flags.intersects(!(SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL))
         {
@@ -179,14 +179,32 @@ impl SocketFile {
     }
 
     pub fn connect(&self, addr: &AnyAddr) -> Result<()> {
+        self.do_connect(addr, false)
+    }
+
+    /// Connect like `connect`, but never wait for the connection to be
+    /// established even if the socket is blocking.
+    pub fn try_connect(&self, addr: &AnyAddr) -> Result<()> {
+        self.do_connect(addr, true)
+    }
+
+    fn do_connect(&self, addr: &AnyAddr, nonblocking: bool) -> Result<()> {
         match &self.socket {
             AnySocket::Ipv4Stream(ipv4_stream) => {
                 let ip_addr = addr.to_ipv4()?;
-                ipv4_stream.connect(ip_addr)
+                if nonblocking {
+                    ipv4_stream.try_connect(ip_addr)
+                } else {
+                    ipv4_stream.connect(ip_addr)
+                }
             }
             AnySocket::Ipv6Stream(ipv6_stream) => {
                 let ip_addr = addr.to_ipv6()?;
-                ipv6_stream.connect(ip_addr)
+                if nonblocking {
+                    ipv6_stream.try_connect(ip_addr)
+                } else {
+                    ipv6_stream.connect(ip_addr)
+                }
             }
             AnySocket::Ipv4Datagram(ipv4_datagram) => {
                 let mut ip_addr = None;
@@ -247,13 +265,30 @@ impl SocketFile {
     }
 
     pub fn accept(&self, nonblocking: bool) -> Result<Self> {
+        self.do_accept(nonblocking, false)
+    }
+
+    /// Accept like `accept`, but never block even if the socket is blocking.
+    pub fn try_accept(&self, nonblocking: bool) -> Result<Self> {
+        self.do_accept(nonblocking, true)
+    }
+
+    fn do_accept(&self, nonblocking: bool, no_wait: bool) -> Result<Self> {
         let accepted_any_socket = match &self.socket {
             AnySocket::Ipv4Stream(ipv4_stream) => {
-                let accepted_ipv4_stream = ipv4_stream.accept(nonblocking)?;
+                let accepted_ipv4_stream = if no_wait {
+                    ipv4_stream.try_accept(nonblocking)?
+                } else {
+                    ipv4_stream.accept(nonblocking)?
+                };
                 AnySocket::Ipv4Stream(accepted_ipv4_stream)
             }
             AnySocket::Ipv6Stream(ipv6_stream) => {
-                let accepted_ipv6_stream = ipv6_stream.accept(nonblocking)?;
+                let accepted_ipv6_stream = if no_wait {
+                    ipv6_stream.try_accept(nonblocking)?
+                } else {
+                    ipv6_stream.accept(nonblocking)?
+                };
                 AnySocket::Ipv6Stream(accepted_ipv6_stream)
             }
             _ => {
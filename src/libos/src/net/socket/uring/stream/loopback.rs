@@ -0,0 +1,498 @@
+//! The in-enclave fast path for stream sockets connected over the loopback
+//! interface.
+//!
+//! When a stream socket connects to a loopback address (e.g., 127.0.0.1 or
+//! ::1) on which an in-enclave socket is listening, the two ends are connected
+//! by a pair of in-enclave channels, instead of a host TCP connection. This
+//! saves the round-trips through the untrusted host for processes talking to
+//! each other inside the same enclave.
+//!
+//! The host sockets of the two ends are still created so that the port
+//! reservation and the socket options work as usual.
+//!
+//! A connection that is still in the backlog when the listener is closed is
+//! reset, i.e., the connecting end gets ECONNRESET, like on Linux.
+//!
+//! Scope: only the stream sockets backed by io_uring take the fast path. The
+//! datagram sockets and the host sockets, which are used when io_uring is
+//! disabled, always go through the host. Urgent data (MSG_OOB) is not
+//! supported.
+
+use core::time::Duration;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+
+use super::states::{ConnectedStream, ListenerStream};
+use super::RECV_BUF_SIZE;
+use crate::events::{Observer, Poller};
+use crate::fs::channel::{Channel, Consumer, Producer};
+use crate::fs::IoEvents as Events;
+use crate::net::socket::uring::common::{do_bind, Common};
+use crate::net::socket::uring::runtime::Runtime;
+use crate::prelude::*;
+use crate::util::sync::Mutex;
+
+/// The address family, the IP address and the port of a socket. An IPv4
+/// address is stored in the lower 32 bits.
+type ListenerKey = (libc::sa_family_t, u128, u16);
+
+lazy_static! {
+    /// The in-enclave listeners that can accept loopback connections, indexed
+    /// by the addresses that they are bound to.
+    static ref LISTENERS: SgxMutex<HashMap<ListenerKey, Weak<dyn Any + Send + Sync>>> =
+        SgxMutex::new(HashMap::new());
+}
+
+/// Make a listener visible to the loopback connections in the enclave.
+///
+/// Only the listeners bound to a loopback or an unspecified address are
+/// registered.
+pub fn register_listener<A: Addr + 'static, R: Runtime>(
+    listener: &Arc<ListenerStream<A, R>>,
+) -> Result<()> {
+    let addr = listener.common().get_addr_from_host()?;
+    let key = match parse_addr(&addr) {
+        Some(key) if is_loopback(&key) || is_unspecified(&key) => key,
+        _ => return Ok(()),
+    };
+
+    let listener = Arc::downgrade(listener) as Weak<dyn Any + Send + Sync>;
+    let mut listeners = LISTENERS.lock().unwrap();
+    listeners.retain(|_, listener| listener.strong_count() > 0);
+    listeners.insert(key, listener);
+    Ok(())
+}
+
+/// Make a listener invisible to the loopback connections in the enclave.
+pub fn unregister_listener<A: Addr + 'static, R: Runtime>(listener: &ListenerStream<A, R>) {
+    let listener_ptr = listener as *const _ as *const u8;
+    let mut listeners = LISTENERS.lock().unwrap();
+    listeners.retain(|_, listener| {
+        listener.strong_count() > 0 && listener.as_ptr() as *const u8 != listener_ptr
+    });
+}
+
+/// Try to connect to an in-enclave listener through the loopback fast path.
+///
+/// If there is no in-enclave listener on the peer address, `None` is
+/// returned and the caller should connect through the host as usual.
+pub fn try_connect<A: Addr + 'static, R: Runtime>(
+    common: &Arc<Common<A, R>>,
+    peer_addr: &A,
+) -> Result<Option<Arc<ConnectedStream<A, R>>>> {
+    let (family, ip, port) = match parse_addr(peer_addr) {
+        Some(key) if is_loopback(&key) => key,
+        _ => return Ok(None),
+    };
+    // The listener bound to the exact address takes precedence over the one
+    // bound to the unspecified address
+    let listener = {
+        let listeners = LISTENERS.lock().unwrap();
+        listeners
+            .get(&(family, ip, port))
+            .or_else(|| listeners.get(&(family, 0, port)))
+            .and_then(|listener| listener.upgrade())
+            .and_then(|listener| listener.downcast::<ListenerStream<A, R>>().ok())
+    };
+    let listener = match listener {
+        Some(listener) if !listener.common().is_closed() => listener,
+        _ => return Ok(None),
+    };
+
+    // Like the host, bind the socket to an ephemeral port if it is not bound
+    if common.addr().is_none() {
+        do_bind(common.host_fd(), &with_port(peer_addr, 0)?)?;
+    }
+    let addr = {
+        let addr = common.get_addr_from_host()?;
+        match parse_addr(&addr) {
+            Some(key) if is_loopback(&key) => addr,
+            // The socket is bound to an unspecified address
+            Some((_, _, port)) => with_port(peer_addr, port)?,
+            None => return_errno!(EINVAL, "invalid address of the socket"),
+        }
+    };
+
+    let peer_common = Arc::new(Common::new(SocketType::STREAM, false, None)?);
+    peer_common.set_addr(peer_addr);
+    peer_common.set_peer_addr(&addr);
+
+    let buf_size = RECV_BUF_SIZE.load(Ordering::Relaxed);
+    let (producer, peer_consumer) = Channel::new(buf_size)?.split();
+    let (peer_producer, consumer) = Channel::new(buf_size)?.split();
+    let is_reset = Arc::new(AtomicBool::new(false));
+    let peer_stream =
+        ConnectedStream::new_loopback(peer_common, peer_consumer, peer_producer, is_reset.clone());
+    if !listener.push_loopback_conn(peer_stream) {
+        // The backlog is full. Let the host handle the connection.
+        return Ok(None);
+    }
+
+    common.set_addr(&addr);
+    common.set_peer_addr(peer_addr);
+    let stream = ConnectedStream::new_loopback(common.clone(), consumer, producer, is_reset);
+    Ok(Some(stream))
+}
+
+/// One end of a loopback connection.
+pub struct LoopbackEnd<A: Addr + 'static, R: Runtime> {
+    common: Arc<Common<A, R>>,
+    reader: Consumer<u8>,
+    writer: Producer<u8>,
+    // Whether the connection is reset, which is shared by the two ends and
+    // reported once as ECONNRESET
+    is_reset: Arc<AtomicBool>,
+    // Serialize the updates of the events so that stale events never
+    // overwrite the latest ones
+    events_lock: Mutex<()>,
+}
+
+impl<A: Addr + 'static, R: Runtime> LoopbackEnd<A, R> {
+    pub fn new(
+        common: Arc<Common<A, R>>,
+        reader: Consumer<u8>,
+        writer: Producer<u8>,
+        is_reset: Arc<AtomicBool>,
+    ) -> Arc<Self> {
+        // The blocking behavior is implemented with the pollee of the socket
+        reader.set_nonblocking(true);
+        writer.set_nonblocking(true);
+
+        let new_self = Arc::new(Self {
+            common,
+            reader,
+            writer,
+            is_reset,
+            events_lock: Mutex::new(()),
+        });
+
+        // The events of the reader and writer are triggered by the peer
+        let observer = Arc::downgrade(&new_self) as Weak<dyn Observer<_>>;
+        new_self
+            .reader
+            .notifier()
+            .register(observer.clone(), None, None);
+        new_self.writer.notifier().register(observer, None, None);
+        new_self.update_events();
+        new_self
+    }
+
+    pub fn recvmsg(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<usize> {
+        if flags.contains(RecvFlags::MSG_OOB) {
+            return_errno!(EINVAL, "no urgent data in loopback connections");
+        }
+        if !flags.is_empty()
+            && flags.intersects(
+                !(RecvFlags::MSG_DONTWAIT
+                    | RecvFlags::MSG_WAITALL
+                    | RecvFlags::MSG_PEEK
+                    | RecvFlags::MSG_TRUNC),
+            )
+        {
+            warn!("Unsupported flags: {:?}", flags);
+            return_errno!(EINVAL, "flags not supported");
+        }
+
+        let total_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if total_len == 0 {
+            return Ok(0);
+        }
+
+        let mut total_received = 0;
+        let mut poller = None;
+        let mut timeout = self.common.recv_timeout();
+        loop {
+            match self.try_recvmsg(bufs, total_received, flags) {
+                Ok(received) => {
+                    total_received += received;
+                    // Return on EOF, or if there is no need to receive more
+                    if received == 0
+                        || !flags.contains(RecvFlags::MSG_WAITALL)
+                        || flags.contains(RecvFlags::MSG_PEEK)
+                        || total_received == total_len
+                    {
+                        return Ok(total_received);
+                    }
+                }
+                Err(e) if e.errno() != EAGAIN => return Err(e),
+                Err(_) => {}
+            }
+
+            if self.common.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT) {
+                if total_received > 0 {
+                    return Ok(total_received);
+                }
+                return_errno!(EAGAIN, "no data are present to be received");
+            }
+
+            if let Err(e) = self.wait_events(Events::IN, &mut poller, timeout.as_mut()) {
+                if total_received > 0 {
+                    return Ok(total_received);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    fn try_recvmsg(&self, bufs: &mut [&mut [u8]], skip: usize, flags: RecvFlags) -> Result<usize> {
+        if self.is_reset.swap(false, Ordering::AcqRel) {
+            self.update_events();
+            return_errno!(ECONNRESET, "the connection is reset by the peer");
+        }
+
+        let mut rest = advance_bufs(bufs, skip);
+        let res = if flags.contains(RecvFlags::MSG_PEEK) {
+            // The peeked bytes stay in the channel until they are received
+            self.reader.peek_slices(&mut rest)
+        } else if flags.contains(RecvFlags::MSG_TRUNC) {
+            // Like Linux, the received data are discarded with MSG_TRUNC for TCP.
+            // The scratch buffer is bounded by the data in the channel rather
+            // than the buffers of the user, which can be huge.
+            let len = rest
+                .iter()
+                .map(|buf| buf.len())
+                .sum::<usize>()
+                .min(self.reader.items_to_consume());
+            let mut scratch = vec![0; len];
+            self.reader.pop_slice(&mut scratch)
+        } else {
+            self.reader.pop_slices(&mut rest)
+        };
+        self.update_events();
+
+        let received = match res {
+            Ok(received) => received,
+            Err(e) if e.errno() == EAGAIN => 0,
+            Err(e) => return Err(e),
+        };
+        if received == 0 && !self.reader.is_peer_shutdown() {
+            return_errno!(EAGAIN, "try read again");
+        }
+        Ok(received)
+    }
+
+    pub fn sendmsg(&self, bufs: &[&[u8]], flags: SendFlags) -> Result<usize> {
+        if flags.contains(SendFlags::MSG_OOB) {
+            return_errno!(
+                EOPNOTSUPP,
+                "MSG_OOB is not supported by loopback connections"
+            );
+        }
+        if !flags.is_empty()
+            && flags.intersects(
+                !(SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL | SendFlags::MSG_MORE),
+            )
+        {
+            warn!("Not supported flags: {:?}", flags);
+            return_errno!(EINVAL, "not supported flags");
+        }
+
+        let total_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if total_len == 0 {
+            return Ok(0);
+        }
+        if self.is_reset.swap(false, Ordering::AcqRel) {
+            self.update_events();
+            return_errno!(ECONNRESET, "the connection is reset by the peer");
+        }
+
+        let mut total_sent = 0;
+        let mut poller = None;
+        let mut timeout = self.common.send_timeout();
+        loop {
+            let res = {
+                let rest: Vec<&[u8]> = bufs
+                    .iter()
+                    .scan(total_sent, |skip, buf| {
+                        let skipped = (*skip).min(buf.len());
+                        *skip -= skipped;
+                        Some(&buf[skipped..])
+                    })
+                    .filter(|buf| buf.len() > 0)
+                    .collect();
+                self.writer.push_slices(&rest)
+            };
+            self.update_events();
+            match res {
+                Ok(sent) => {
+                    total_sent += sent;
+                    if total_sent == total_len {
+                        return Ok(total_sent);
+                    }
+                }
+                Err(e) if e.errno() != EAGAIN => {
+                    if total_sent > 0 {
+                        return Ok(total_sent);
+                    }
+                    return Err(e);
+                }
+                Err(_) => {}
+            }
+
+            if self.common.nonblocking() || flags.contains(SendFlags::MSG_DONTWAIT) {
+                if total_sent > 0 {
+                    return Ok(total_sent);
+                }
+                return_errno!(EAGAIN, "try write again");
+            }
+
+            if let Err(e) = self.wait_events(Events::OUT, &mut poller, timeout.as_mut()) {
+                if total_sent > 0 {
+                    return Ok(total_sent);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    fn wait_events(
+        &self,
+        mask: Events,
+        poller: &mut Option<Poller>,
+        timeout: Option<&mut Duration>,
+    ) -> Result<()> {
+        // Initialize the poller only when needed
+        if poller.is_none() {
+            let new_poller = Poller::new();
+            self.common.pollee().connect_poller(mask, &new_poller);
+            *poller = Some(new_poller);
+        }
+
+        let events = self.common.pollee().poll(mask, None);
+        if !events.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = poller.as_ref().unwrap().wait_timeout(timeout) {
+            match e.errno() {
+                ETIMEDOUT => return_errno!(EAGAIN, "timeout reached"),
+                errno => return_errno!(errno, "wait error"),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        if how.should_shut_read() {
+            self.reader.shutdown();
+        }
+        if how.should_shut_write() {
+            self.writer.shutdown();
+        }
+        self.update_events();
+        Ok(())
+    }
+
+    /// Reset the connection, e.g., when it is never accepted by the listener.
+    pub fn reset(&self) {
+        self.is_reset.store(true, Ordering::Release);
+        self.reader.shutdown();
+        self.writer.shutdown();
+        self.update_events();
+    }
+
+    pub fn bytes_to_consume(&self) -> usize {
+        self.reader.items_to_consume()
+    }
+
+    // Keep the events of the pollee consistent with the states of the channels.
+    fn update_events(&self) {
+        let _events_lock = self.events_lock.lock();
+        let reader_events = self.reader.poll();
+        let writer_events = self.writer.poll();
+
+        let mut events = Events::empty();
+        if reader_events.contains(Events::HUP) || self.reader.is_self_shutdown() {
+            events |= Events::RDHUP | Events::IN;
+            if writer_events.contains(Events::ERR) || self.writer.is_self_shutdown() {
+                events |= Events::HUP | Events::OUT;
+            }
+        }
+        events |= (reader_events & Events::IN) | (writer_events & Events::OUT);
+        if self.is_reset.load(Ordering::Acquire) {
+            events |= Events::ERR | Events::HUP | Events::RDHUP | Events::IN | Events::OUT;
+        }
+
+        let pollee = self.common.pollee();
+        pollee.del_events(!events);
+        pollee.add_events(events);
+    }
+}
+
+impl<A: Addr + 'static, R: Runtime> Observer<Events> for LoopbackEnd<A, R> {
+    fn on_event(&self, _event: &Events, _metadata: &Option<Weak<dyn Any + Send + Sync>>) {
+        self.update_events();
+    }
+}
+
+impl<A: Addr + 'static, R: Runtime> std::fmt::Debug for LoopbackEnd<A, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoopbackEnd")
+            .field("bytes_to_consume", &self.bytes_to_consume())
+            .finish()
+    }
+}
+
+/// Returns the address family, the IP address and the port of an address.
+fn parse_addr<A: Addr>(addr: &A) -> Option<ListenerKey> {
+    let (c_addr, _) = addr.to_c_storage();
+    match c_addr.ss_family as i32 {
+        libc::AF_INET => {
+            let c_addr = unsafe { &*(&c_addr as *const _ as *const libc::sockaddr_in) };
+            let ip = u32::from_be(c_addr.sin_addr.s_addr);
+            let port = u16::from_be(c_addr.sin_port);
+            Some((c_addr.sin_family, ip as u128, port))
+        }
+        libc::AF_INET6 => {
+            let c_addr = unsafe { &*(&c_addr as *const _ as *const libc::sockaddr_in6) };
+            let ip = u128::from_be_bytes(c_addr.sin6_addr.s6_addr);
+            let port = u16::from_be(c_addr.sin6_port);
+            Some((c_addr.sin6_family, ip, port))
+        }
+        _ => None,
+    }
+}
+
+fn is_loopback(key: &ListenerKey) -> bool {
+    let (family, ip, _) = *key;
+    match family as i32 {
+        libc::AF_INET => ip >> 24 == 127,
+        _ => ip == 1,
+    }
+}
+
+fn is_unspecified(key: &ListenerKey) -> bool {
+    key.1 == 0
+}
+
+/// Returns the address with the same IP address but a different port.
+fn with_port<A: Addr>(addr: &A, port: u16) -> Result<A> {
+    let (mut c_addr, c_addr_len) = addr.to_c_storage();
+    match c_addr.ss_family as i32 {
+        libc::AF_INET => {
+            let c_addr = unsafe { &mut *(&mut c_addr as *mut _ as *mut libc::sockaddr_in) };
+            c_addr.sin_port = port.to_be();
+        }
+        libc::AF_INET6 => {
+            let c_addr = unsafe { &mut *(&mut c_addr as *mut _ as *mut libc::sockaddr_in6) };
+            c_addr.sin6_port = port.to_be();
+        }
+        _ => return_errno!(EINVAL, "not an IP address"),
+    }
+    A::from_c_storage(&c_addr, c_addr_len)
+}
+
+/// Returns the buffers after skipping the first `skip` bytes.
+fn advance_bufs<'a>(bufs: &'a mut [&mut [u8]], mut skip: usize) -> Vec<&'a mut [u8]> {
+    let mut rest = Vec::with_capacity(bufs.len());
+    for buf in bufs.iter_mut() {
+        if skip >= buf.len() {
+            skip -= buf.len();
+            continue;
+        }
+        rest.push(&mut buf[skip..]);
+        skip = 0;
+    }
+    rest
+}
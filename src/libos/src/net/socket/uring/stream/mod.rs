@@ -1,3 +1,4 @@
+mod loopback;
 mod states;
 
 use core::hint;
@@ -133,6 +134,7 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
             State::Init(init_stream) => {
                 let common = init_stream.common().clone();
                 let listener = ListenerStream::new(backlog, common)?;
+                loopback::register_listener(&listener)?;
                 *state = State::Listen(listener);
                 Ok(())
             }
@@ -143,12 +145,30 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
     }
 
     pub fn connect(&self, peer_addr: &A) -> Result<()> {
+        self.do_connect(peer_addr, false)
+    }
+
+    /// Connect like `connect`, but never wait for the connection to be
+    /// established even if the socket is blocking.
+    pub fn try_connect(&self, peer_addr: &A) -> Result<()> {
+        self.do_connect(peer_addr, true)
+    }
+
+    fn do_connect(&self, peer_addr: &A, nonblocking: bool) -> Result<()> {
         // Create the new intermediate state of connecting and save the
         // old state of init in case of failure to connect.
         let (init_stream, connecting_stream) = {
             let mut state = self.state.write().unwrap();
             match &*state {
                 State::Init(init_stream) => {
+                    // Connect to the listener in the same enclave directly
+                    if let Some(connected_stream) =
+                        loopback::try_connect(init_stream.common(), peer_addr)?
+                    {
+                        *state = State::Connected(connected_stream);
+                        return Ok(());
+                    }
+
                     let connecting_stream = {
                         let common = init_stream.common().clone();
                         ConnectingStream::new(peer_addr, common)?
@@ -163,6 +183,13 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
                     {
                         *state = State::Connected(connected_stream);
                         return_errno!(EISCONN, "the socket is already connected");
+                    } else if let Some(errno) = connecting_stream.connect_errno() {
+                        // Like Linux, the failure of a non-blocking connect is
+                        // reported once, and then the socket can connect again
+                        let init_stream =
+                            InitStream::new_with_common(connecting_stream.common().clone())?;
+                        *state = State::Init(init_stream);
+                        return_errno!(errno, "connect failed");
                     } else {
                         // Not connected, keep the connecting state and try connect
                         let init_stream =
@@ -179,7 +206,7 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
             }
         };
 
-        let res = connecting_stream.connect();
+        let res = connecting_stream.connect(nonblocking);
 
         // If success, then the state is switched to connected; otherwise, for blocking socket
         // the state is restored to the init state, and for non-blocking socket, the state
@@ -196,7 +223,7 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
                 *state = State::Connected(connected_stream);
             }
             Err(_) => {
-                if !connecting_stream.common().nonblocking() {
+                if !nonblocking && !connecting_stream.common().nonblocking() {
                     let mut state = self.state.write().unwrap();
                     *state = State::Init(init_stream);
                 }
@@ -206,6 +233,15 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
     }
 
     pub fn accept(&self, nonblocking: bool) -> Result<Self> {
+        self.do_accept(nonblocking, false)
+    }
+
+    /// Accept like `accept`, but never block even if the socket is blocking.
+    pub fn try_accept(&self, nonblocking: bool) -> Result<Self> {
+        self.do_accept(nonblocking, true)
+    }
+
+    fn do_accept(&self, nonblocking: bool, no_wait: bool) -> Result<Self> {
         let listener_stream = {
             let state = self.state.read().unwrap();
             match &*state {
@@ -216,7 +252,11 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
             }
         };
 
-        let connected_stream = listener_stream.accept(nonblocking)?;
+        let connected_stream = if no_wait {
+            listener_stream.try_accept(nonblocking)?
+        } else {
+            listener_stream.accept(nonblocking)?
+        };
 
         let new_self = Self::new_connected(connected_stream);
         Ok(new_self)
@@ -307,6 +347,13 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
         let state = self.state.read().unwrap();
         let common = state.common();
 
+        // The host socket of a loopback stream is not connected
+        if let State::Connected(connected_stream) = &*state {
+            if connected_stream.is_loopback() {
+                return Ok(common.addr().unwrap());
+            }
+        }
+
         // Always get addr from host.
         // Because for IP socket, users can specify "0" as port and the kernel should select a usable port for him.
         // Thus, when calling getsockname, this should be updated.
@@ -486,6 +533,8 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
                 // listening socket can be shutdown and then re-use by calling listen again.
                 listener_stream.shutdown(shutdown)?;
                 if shutdown.should_shut_read() {
+                    loopback::unregister_listener(listener_stream);
+                    listener_stream.reset_loopback_conns();
                     // Cancel pending accept requests. This is necessary because the socket is reusable.
                     listener_stream.cancel_accept_requests();
                     // Set init state
@@ -522,6 +571,8 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
         match &*state {
             State::Init(_) => {}
             State::Listen(listener_stream) => {
+                loopback::unregister_listener(listener_stream);
+                listener_stream.reset_loopback_conns();
                 listener_stream.common().set_closed();
                 listener_stream.cancel_accept_requests();
             }
@@ -40,13 +40,15 @@ impl<A: Addr + 'static, R: Runtime> ConnectingStream<A, R> {
     }
 
     /// Connect to the peer address.
-    pub fn connect(self: &Arc<Self>) -> Result<()> {
+    /// Connect to the peer address. The connection is established in the
+    /// background if the socket is non-blocking or `nonblocking` is true.
+    pub fn connect(self: &Arc<Self>, nonblocking: bool) -> Result<()> {
         let pollee = self.common.pollee();
         pollee.reset_events();
 
         self.initiate_async_connect();
 
-        if self.common.nonblocking() {
+        if nonblocking || self.common.nonblocking() {
             return_errno!(EINPROGRESS, "non-blocking connect request in progress");
         }
 
@@ -191,6 +193,15 @@ impl<A: Addr + 'static, R: Runtime> ConnectingStream<A, R> {
         // It is fine whether the load happens before or after the store operation
         self.connected.load(Ordering::Relaxed)
     }
+
+    /// The errno of the connect request if it failed.
+    pub fn connect_errno(&self) -> Option<Errno> {
+        let req = self.req.lock();
+        if req.io_handle.is_some() {
+            return None;
+        }
+        req.errno
+    }
 }
 
 impl<A: Addr> ConnectReq<A> {
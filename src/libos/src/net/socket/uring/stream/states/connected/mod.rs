@@ -1,11 +1,14 @@
 use atomic::Ordering;
+use std::sync::atomic::AtomicBool;
 
 use self::recv::Receiver;
 use self::send::Sender;
+use crate::fs::channel::{Consumer, Producer};
 use crate::fs::IoEvents as Events;
 use crate::net::socket::sockopt::SockOptName;
 use crate::net::socket::uring::common::Common;
 use crate::net::socket::uring::runtime::Runtime;
+use crate::net::socket::uring::stream::loopback::LoopbackEnd;
 use crate::prelude::*;
 
 mod recv;
@@ -15,6 +18,8 @@ pub struct ConnectedStream<A: Addr + 'static, R: Runtime> {
     common: Arc<Common<A, R>>,
     sender: Sender,
     receiver: Receiver,
+    // Set if the stream is connected through the in-enclave loopback fast path
+    loopback: Option<Arc<LoopbackEnd<A, R>>>,
 }
 
 impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
@@ -30,6 +35,7 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
             common,
             sender,
             receiver,
+            loopback: None,
         });
 
         // Start async recv requests right as early as possible to support poll and
@@ -41,11 +47,44 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         new_self
     }
 
+    /// Creates a stream connected to an in-enclave peer through the channels.
+    pub fn new_loopback(
+        common: Arc<Common<A, R>>,
+        reader: Consumer<u8>,
+        writer: Producer<u8>,
+        is_reset: Arc<AtomicBool>,
+    ) -> Arc<Self> {
+        common.pollee().reset_events();
+        let loopback = LoopbackEnd::new(common.clone(), reader, writer, is_reset);
+        Arc::new(Self {
+            common,
+            sender: Sender::new(),
+            receiver: Receiver::new(),
+            loopback: Some(loopback),
+        })
+    }
+
     pub fn common(&self) -> &Arc<Common<A, R>> {
         &self.common
     }
 
+    pub fn is_loopback(&self) -> bool {
+        self.loopback.is_some()
+    }
+
+    /// Reset a loopback stream that is never accepted.
+    pub fn reset_loopback(&self) {
+        if let Some(loopback) = &self.loopback {
+            loopback.reset();
+        }
+        self.common.set_closed();
+    }
+
     pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        if let Some(loopback) = &self.loopback {
+            return loopback.shutdown(how);
+        }
+
         // Do host shutdown
         // For shutdown write, don't call host_shutdown until the content in the pending buffer is sent.
         // For shutdown read, ignore the pending buffer.
@@ -86,6 +125,10 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         // Mark the sender and receiver to shutdown to prevent submitting new requests.
         self.receiver.shutdown();
         self.sender.shutdown();
+        if let Some(loopback) = &self.loopback {
+            // The data sent before are still readable by the peer
+            let _ = loopback.shutdown(Shutdown::Both);
+        }
 
         self.common.set_closed();
     }
@@ -99,6 +142,7 @@ impl<A: Addr + 'static, R: Runtime> std::fmt::Debug for ConnectedStream<A, R> {
             .field("common", &self.common)
             .field("sender", &self.sender)
             .field("receiver", &self.receiver)
+            .field("loopback", &self.loopback)
             .finish()
     }
 }
@@ -25,6 +25,15 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
             return Ok(0);
         }
 
+        if let Some(loopback) = &self.loopback {
+            return loopback.recvmsg(bufs, flags);
+        }
+
+        // The urgent data is not in the receive buffer
+        if flags.contains(RecvFlags::MSG_OOB) {
+            return self.recv_oob(bufs, flags);
+        }
+
         let mut total_received = 0;
         let mut iov_buffer_index = 0;
         let mut iov_buffer_offset = 0;
@@ -41,7 +50,12 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
                 Ok((received_size, index, offset)) => {
                     total_received += received_size;
 
-                    if !flags.contains(RecvFlags::MSG_WAITALL) || total_received == total_len {
+                    // Peeking never waits for more data, since the peeked data
+                    // remain in the receive buffer and would be peeked again.
+                    if !flags.contains(RecvFlags::MSG_WAITALL)
+                        || flags.contains(RecvFlags::MSG_PEEK)
+                        || total_received == total_len
+                    {
                         return Ok(total_received);
                     } else {
                         // save the index and offset for the next round
@@ -98,7 +112,12 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         let mut inner = self.receiver.inner.lock();
 
         if !flags.is_empty()
-            && flags.intersects(!(RecvFlags::MSG_DONTWAIT | RecvFlags::MSG_WAITALL))
+            && flags.intersects(
+                !(RecvFlags::MSG_DONTWAIT
+                    | RecvFlags::MSG_WAITALL
+                    | RecvFlags::MSG_PEEK
+                    | RecvFlags::MSG_TRUNC),
+            )
         {
             warn!("Unsupported flags: {:?}", flags);
             return_errno!(EINVAL, "flags not supported");
@@ -111,7 +130,15 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
 
             // save the received data from bufs[iov_buffer_index][iov_buffer_offset..]
             for (_, buf) in bufs.iter_mut().skip(iov_buffer_index).enumerate() {
-                let this_consumed = inner.recv_buf.consume(&mut buf[iov_buffer_offset..]);
+                let buf = &mut buf[iov_buffer_offset..];
+                let this_consumed = if flags.contains(RecvFlags::MSG_PEEK) {
+                    inner.recv_buf.peek(total_consumed, buf)
+                } else if flags.contains(RecvFlags::MSG_TRUNC) {
+                    // Like Linux, the received data are discarded for TCP
+                    inner.recv_buf.consume_without_copy(buf.len())
+                } else {
+                    inner.recv_buf.consume(buf)
+                };
                 if this_consumed == 0 {
                     break;
                 }
@@ -119,7 +146,7 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
 
                 // if the buffer is not full, then the try_recvmsg will be used again
                 // next time, the data will be stored from the offset
-                if this_consumed < buf[iov_buffer_offset..].len() {
+                if this_consumed < buf.len() {
                     iov_buffer_offset += this_consumed;
                     break;
                 } else {
@@ -171,6 +198,18 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         return_errno!(EAGAIN, "try read again");
     }
 
+    fn recv_oob(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<usize> {
+        if flags.intersects(RecvFlags::MSG_WAITALL | RecvFlags::MSG_TRUNC) {
+            return_errno!(EINVAL, "flags not supported with MSG_OOB");
+        }
+        if self.receiver.inner.lock().is_shutdown {
+            return_errno!(EPIPE, "read side is shutdown");
+        }
+        // There is at most one byte of urgent data
+        let buf = bufs.iter_mut().find(|buf| buf.len() > 0).unwrap();
+        self.common.host_recv_oob(&mut buf[..1], flags)
+    }
+
     fn do_recv(self: &Arc<Self>, inner: &mut MutexGuard<Inner>) {
         if inner.recv_buf.is_full()
             || inner.is_shutdown
@@ -254,6 +293,9 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
     }
 
     pub fn cancel_recv_requests(&self) {
+        if self.loopback.is_some() {
+            return;
+        }
         {
             let inner = self.receiver.inner.lock();
             if let Some(io_handle) = &inner.io_handle {
@@ -289,6 +331,9 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
     }
 
     pub fn bytes_to_consume(self: &Arc<Self>) -> usize {
+        if let Some(loopback) = &self.loopback {
+            return loopback.bytes_to_consume();
+        }
         let inner = self.receiver.inner.lock();
         inner.recv_buf.consumable()
     }
@@ -298,7 +343,8 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
     // buffer when a recv request is done and the kernel buffer is empty. Here, we just set the update flag.
     pub fn try_update_recv_buf_size(&self, buf_size: usize) {
         let pre_buf_size = RECV_BUF_SIZE.swap(buf_size, Ordering::Relaxed);
-        if buf_size == pre_buf_size {
+        // The size of the channels of a loopback stream is fixed
+        if buf_size == pre_buf_size || self.loopback.is_some() {
             return;
         }
 
@@ -28,6 +28,14 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
             return Ok(0);
         }
 
+        if let Some(loopback) = &self.loopback {
+            return loopback.sendmsg(bufs, flags);
+        }
+
+        if flags.contains(SendFlags::MSG_OOB) {
+            return self.send_oob(bufs, flags);
+        }
+
         let mut send_len = 0;
         // variables to track the position of async sendmsg.
         let mut iov_buf_id = 0; // user buffer id tracker
@@ -158,6 +166,67 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         }
     }
 
+    // The urgent data are sent by the host synchronously. To keep the order of
+    // the byte stream, the send buffer must be flushed before.
+    fn send_oob(self: &Arc<Self>, bufs: &[&[u8]], flags: SendFlags) -> Result<usize> {
+        if !flags.is_empty()
+            && flags.intersects(
+                !(SendFlags::MSG_OOB | SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL),
+            )
+        {
+            error!("Not supported flags: {:?}", flags);
+            return_errno!(EINVAL, "not supported flags");
+        }
+
+        let buf: Vec<u8> = bufs.concat();
+        let mask = Events::OUT | Events::ERR;
+        // Initialize the poller only when needed
+        let mut poller = None;
+        let mut timeout = self.common.send_timeout();
+        loop {
+            {
+                let mut inner = self.sender.inner.lock();
+                if inner.is_shutdown() {
+                    return_errno!(EPIPE, "write side is shutdown");
+                }
+                if let Some(errno) = inner.fatal {
+                    // Reset error
+                    inner.fatal = None;
+                    self.common.pollee().del_events(Events::ERR);
+                    return_errno!(errno, "write failed");
+                }
+                if inner.send_buf.is_empty() && inner.io_handle.is_none() {
+                    // Hold the lock so that no other data can be sent in between
+                    return self.common.host_send_oob(&buf, flags);
+                }
+            }
+
+            if self.common.nonblocking() || flags.contains(SendFlags::MSG_DONTWAIT) {
+                return_errno!(EAGAIN, "try write again");
+            }
+
+            if poller.is_none() {
+                let new_poller = Poller::new();
+                self.common.pollee().connect_poller(mask, &new_poller);
+                poller = Some(new_poller);
+                // Check the send buffer again to avoid missing any event
+                continue;
+            }
+            let ret = poller.as_ref().unwrap().wait_timeout(timeout.as_mut());
+            if let Err(e) = ret {
+                warn!("send oob wait errno = {:?}", e.errno());
+                match e.errno() {
+                    ETIMEDOUT => {
+                        return_errno!(EAGAIN, "timeout reached")
+                    }
+                    _ => {
+                        return_errno!(e.errno(), "wait error")
+                    }
+                }
+            }
+        }
+    }
+
     fn do_send(self: &Arc<Self>, inner: &mut MutexGuard<Inner>) {
         // This function can also be called even if the socket is set to shutdown by shutdown syscall. This is due to the
         // async behaviour that the kernel may return to user before actually issuing the request. We should
@@ -241,7 +310,8 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
     // If the kernel buf is not empty, update the flag in Sender and update the kernel buf after send.
     pub fn try_update_send_buf_size(&self, buf_size: usize) {
         let pre_buf_size = SEND_BUF_SIZE.swap(buf_size, Ordering::Relaxed);
-        if pre_buf_size == buf_size {
+        // The size of the channels of a loopback stream is fixed
+        if pre_buf_size == buf_size || self.loopback.is_some() {
             return;
         }
 
@@ -264,7 +334,7 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         // let inner = self.sender.inner.lock().unwrap();
         let inner = self.sender.inner.lock();
         debug_assert!(inner.is_shutdown());
-        if inner.send_buf.is_empty() {
+        if inner.send_buf.is_empty() || self.loopback.is_some() {
             return;
         }
 
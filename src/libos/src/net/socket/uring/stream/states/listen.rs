@@ -22,6 +22,9 @@ const PENDING_ASYNC_ACCEPT_NUM_MAX: usize = 128;
 pub struct ListenerStream<A: Addr + 'static, R: Runtime> {
     common: Arc<Common<A, R>>,
     inner: Mutex<Inner<A>>,
+    // The connections from the same enclave through the loopback fast path
+    loopback_conns: Mutex<VecDeque<Arc<ConnectedStream<A, R>>>>,
+    loopback_backlog: usize,
 }
 
 impl<A: Addr + 'static, R: Runtime> ListenerStream<A, R> {
@@ -41,6 +44,8 @@ impl<A: Addr + 'static, R: Runtime> ListenerStream<A, R> {
         let new_self = Arc::new(Self {
             common,
             inner: Mutex::new(inner),
+            loopback_conns: Mutex::new(VecDeque::new()),
+            loopback_backlog: libos_backlog.max(1) as usize,
         });
 
         // Start async accept requests right as early as possible to improve performance
@@ -100,6 +105,17 @@ impl<A: Addr + 'static, R: Runtime> ListenerStream<A, R> {
     }
 
     pub fn try_accept(self: &Arc<Self>, nonblocking: bool) -> Result<Arc<ConnectedStream<A, R>>> {
+        // Hold the lock until the events are updated so that no new loopback
+        // connection is missed
+        let mut loopback_conns = self.loopback_conns.lock();
+        if let Some(accepted_stream) = loopback_conns.pop_front() {
+            if loopback_conns.is_empty() && !self.inner.lock().backlog.has_completed_reqs() {
+                self.common.pollee().del_events(IoEvents::IN);
+            }
+            accepted_stream.common().set_nonblocking(nonblocking);
+            return Ok(accepted_stream);
+        }
+
         let mut inner = self.inner.lock();
 
         if let Some(errno) = inner.fatal {
@@ -133,6 +149,28 @@ impl<A: Addr + 'static, R: Runtime> ListenerStream<A, R> {
         Ok(accepted_stream)
     }
 
+    /// Queue a connection from the same enclave, which is to be accepted.
+    ///
+    /// Returns false if the backlog is full.
+    pub fn push_loopback_conn(&self, stream: Arc<ConnectedStream<A, R>>) -> bool {
+        let mut loopback_conns = self.loopback_conns.lock();
+        if loopback_conns.len() >= self.loopback_backlog {
+            return false;
+        }
+        loopback_conns.push_back(stream);
+        self.common.pollee().add_events(IoEvents::IN);
+        true
+    }
+
+    /// Reset the loopback connections that are not accepted yet, e.g., when
+    /// the listener is closed.
+    pub fn reset_loopback_conns(&self) {
+        let loopback_conns = std::mem::take(&mut *self.loopback_conns.lock());
+        for stream in loopback_conns {
+            stream.reset_loopback();
+        }
+    }
+
     fn initiate_async_accepts(self: &Arc<Self>, mut inner: MutexGuard<Inner<A>>) {
         let backlog = &mut inner.backlog;
         while backlog.has_free_entries() {
@@ -145,6 +183,11 @@ impl<A: Addr + 'static, R: Runtime> ListenerStream<A, R> {
     }
 
     pub fn cancel_accept_requests(&self) {
+        // The loopback connections not accepted yet are reset
+        for stream in self.loopback_conns.lock().drain(..) {
+            stream.set_closed();
+        }
+
         {
             // Set the listener stream as closed to prevent submitting new request in the callback fn
             self.common().set_closed();
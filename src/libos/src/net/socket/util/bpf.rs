@@ -0,0 +1,364 @@
+//! Classic BPF (cBPF) socket filters.
+//!
+//! The filters attached by `SO_ATTACH_FILTER` are verified and run in the
+//! LibOS, since the packets are received from the untrusted host. The
+//! verifier follows `bpf_check_classic` of Linux. The ancillary loads (with
+//! the negative offsets of `SKF_AD_OFF`, `SKF_NET_OFF` and `SKF_LL_OFF`) are
+//! not supported, as the headers below the transport layer are not visible
+//! in the LibOS.
+
+use crate::prelude::*;
+
+/// The max number of the instructions of a filter, i.e., BPF_MAXINSNS
+const BPF_MAXINSNS: usize = 4096;
+/// The number of the words of the scratch memory, i.e., BPF_MEMWORDS
+const BPF_MEMWORDS: usize = 16;
+/// The start of the negative offsets of the ancillary loads
+const SKF_NEG_OFF: u32 = -0x200000_i32 as u32;
+
+// Instruction classes
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ST: u16 = 0x02;
+const BPF_STX: u16 = 0x03;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_MISC: u16 = 0x07;
+
+// Sizes of the loads
+const BPF_W: u16 = 0x00;
+const BPF_H: u16 = 0x08;
+const BPF_B: u16 = 0x10;
+
+// Modes of the loads
+const BPF_IMM: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_IND: u16 = 0x40;
+const BPF_MEM: u16 = 0x60;
+const BPF_LEN: u16 = 0x80;
+const BPF_MSH: u16 = 0xa0;
+
+// ALU and jump operations
+const BPF_ADD: u16 = 0x00;
+const BPF_SUB: u16 = 0x10;
+const BPF_MUL: u16 = 0x20;
+const BPF_DIV: u16 = 0x30;
+const BPF_OR: u16 = 0x40;
+const BPF_AND: u16 = 0x50;
+const BPF_LSH: u16 = 0x60;
+const BPF_RSH: u16 = 0x70;
+const BPF_NEG: u16 = 0x80;
+const BPF_MOD: u16 = 0x90;
+const BPF_XOR: u16 = 0xa0;
+
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// Sources of the operands
+const BPF_K: u16 = 0x00;
+const BPF_X: u16 = 0x08;
+// Return value of BPF_RET
+const BPF_A: u16 = 0x10;
+
+// Operations of BPF_MISC
+const BPF_TAX: u16 = 0x00;
+const BPF_TXA: u16 = 0x80;
+
+/// An instruction of a classic BPF program, same as `struct sock_filter`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+#[allow(non_camel_case_types)]
+pub struct sock_filter {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+/// The program passed to `SO_ATTACH_FILTER`, same as `struct sock_fprog`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct sock_fprog {
+    pub len: u16,
+    pub filter: *const sock_filter,
+}
+
+/// A verified classic BPF filter.
+#[derive(Debug)]
+pub struct BpfFilter {
+    insns: Vec<sock_filter>,
+}
+
+impl BpfFilter {
+    /// Verify the program and create a filter.
+    pub fn new(insns: Vec<sock_filter>) -> Result<Self> {
+        check_insns(&insns)?;
+        Ok(Self { insns })
+    }
+
+    pub fn insns(&self) -> &[sock_filter] {
+        &self.insns
+    }
+
+    /// Run the filter on the packet, returning the number of bytes of the
+    /// packet to accept. Zero means dropping the packet.
+    pub fn run(&self, packet: &[u8]) -> u32 {
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem = [0_u32; BPF_MEMWORDS];
+        let mut pc = 0;
+        // The verifier makes sure that the jumps are forward and in range,
+        // and that the program ends with a return.
+        loop {
+            let insn = &self.insns[pc];
+            let k = insn.k;
+            pc += 1;
+            match insn.code {
+                code if code == BPF_LD | BPF_W | BPF_ABS => match load(packet, k, 4) {
+                    Some(val) => a = val,
+                    None => return 0,
+                },
+                code if code == BPF_LD | BPF_H | BPF_ABS => match load(packet, k, 2) {
+                    Some(val) => a = val,
+                    None => return 0,
+                },
+                code if code == BPF_LD | BPF_B | BPF_ABS => match load(packet, k, 1) {
+                    Some(val) => a = val,
+                    None => return 0,
+                },
+                code if code == BPF_LD | BPF_W | BPF_IND => {
+                    match load(packet, x.wrapping_add(k), 4) {
+                        Some(val) => a = val,
+                        None => return 0,
+                    }
+                }
+                code if code == BPF_LD | BPF_H | BPF_IND => {
+                    match load(packet, x.wrapping_add(k), 2) {
+                        Some(val) => a = val,
+                        None => return 0,
+                    }
+                }
+                code if code == BPF_LD | BPF_B | BPF_IND => {
+                    match load(packet, x.wrapping_add(k), 1) {
+                        Some(val) => a = val,
+                        None => return 0,
+                    }
+                }
+                code if code == BPF_LD | BPF_W | BPF_LEN => a = packet.len() as u32,
+                code if code == BPF_LDX | BPF_W | BPF_LEN => x = packet.len() as u32,
+                code if code == BPF_LD | BPF_IMM => a = k,
+                code if code == BPF_LDX | BPF_IMM => x = k,
+                code if code == BPF_LD | BPF_MEM => a = mem[k as usize],
+                code if code == BPF_LDX | BPF_MEM => x = mem[k as usize],
+                code if code == BPF_LDX | BPF_B | BPF_MSH => match load(packet, k, 1) {
+                    Some(val) => x = (val & 0xf) << 2,
+                    None => return 0,
+                },
+                BPF_ST => mem[k as usize] = a,
+                BPF_STX => mem[k as usize] = x,
+                code if code == BPF_MISC | BPF_TAX => x = a,
+                code if code == BPF_MISC | BPF_TXA => a = x,
+                code if code == BPF_RET | BPF_K => return k,
+                code if code == BPF_RET | BPF_A => return a,
+                code if code & 0x07 == BPF_ALU => {
+                    let src = if code & BPF_X != 0 { x } else { k };
+                    a = match code & 0xf0 {
+                        BPF_ADD => a.wrapping_add(src),
+                        BPF_SUB => a.wrapping_sub(src),
+                        BPF_MUL => a.wrapping_mul(src),
+                        // Like Linux, dividing by zero in X drops the packet
+                        BPF_DIV => match a.checked_div(src) {
+                            Some(val) => val,
+                            None => return 0,
+                        },
+                        BPF_MOD => match a.checked_rem(src) {
+                            Some(val) => val,
+                            None => return 0,
+                        },
+                        BPF_OR => a | src,
+                        BPF_AND => a & src,
+                        BPF_XOR => a ^ src,
+                        BPF_LSH => a.checked_shl(src).unwrap_or(0),
+                        BPF_RSH => a.checked_shr(src).unwrap_or(0),
+                        BPF_NEG => a.wrapping_neg(),
+                        _ => unreachable!(),
+                    };
+                }
+                code if code == BPF_JMP | BPF_JA => pc += k as usize,
+                code if code & 0x07 == BPF_JMP => {
+                    let src = if code & BPF_X != 0 { x } else { k };
+                    let is_true = match code & 0xf0 {
+                        BPF_JEQ => a == src,
+                        BPF_JGT => a > src,
+                        BPF_JGE => a >= src,
+                        BPF_JSET => a & src != 0,
+                        _ => unreachable!(),
+                    };
+                    pc += if is_true { insn.jt } else { insn.jf } as usize;
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+/// Load a big-endian integer of the size at the offset of the packet.
+fn load(packet: &[u8], offset: u32, size: usize) -> Option<u32> {
+    let start = offset as usize;
+    let bytes = packet.get(start..start.checked_add(size)?)?;
+    Some(bytes.iter().fold(0, |val, byte| (val << 8) | *byte as u32))
+}
+
+/// Check the program like `bpf_check_classic` of Linux.
+fn check_insns(insns: &[sock_filter]) -> Result<()> {
+    let len = insns.len();
+    if len == 0 || len > BPF_MAXINSNS {
+        return_errno!(EINVAL, "invalid length of the filter");
+    }
+
+    for (pc, insn) in insns.iter().enumerate() {
+        let code = insn.code;
+        let k = insn.k;
+        if !is_valid_code(code) {
+            return_errno!(EINVAL, "invalid instruction of the filter");
+        }
+        match code & 0x07 {
+            BPF_ALU => {
+                let op = code & 0xf0;
+                if code & BPF_X == 0 {
+                    if (op == BPF_DIV || op == BPF_MOD) && k == 0 {
+                        return_errno!(EINVAL, "the filter divides by zero");
+                    }
+                    if (op == BPF_LSH || op == BPF_RSH) && k >= 32 {
+                        return_errno!(EINVAL, "the filter shifts too much");
+                    }
+                }
+            }
+            BPF_LD | BPF_LDX if code & 0xe0 == BPF_MEM => {
+                if k as usize >= BPF_MEMWORDS {
+                    return_errno!(EINVAL, "invalid memory word of the filter");
+                }
+            }
+            BPF_LD | BPF_LDX if code & 0xe0 == BPF_ABS || code & 0xe0 == BPF_MSH => {
+                if k >= SKF_NEG_OFF {
+                    return_errno!(EINVAL, "the ancillary loads are not supported");
+                }
+            }
+            BPF_ST | BPF_STX => {
+                if k as usize >= BPF_MEMWORDS {
+                    return_errno!(EINVAL, "invalid memory word of the filter");
+                }
+            }
+            BPF_JMP => {
+                let remaining = len - pc - 1;
+                if code & 0xf0 == BPF_JA {
+                    if k as usize >= remaining {
+                        return_errno!(EINVAL, "the jump of the filter is out of range");
+                    }
+                } else if insn.jt as usize >= remaining || insn.jf as usize >= remaining {
+                    return_errno!(EINVAL, "the jump of the filter is out of range");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let last_code = insns[len - 1].code;
+    if last_code != BPF_RET | BPF_K && last_code != BPF_RET | BPF_A {
+        return_errno!(EINVAL, "the filter does not end with a return");
+    }
+    check_loads_and_stores(insns)
+}
+
+fn is_valid_code(code: u16) -> bool {
+    const VALID_CODES: &[u16] = &[
+        BPF_ALU | BPF_ADD | BPF_K,
+        BPF_ALU | BPF_ADD | BPF_X,
+        BPF_ALU | BPF_SUB | BPF_K,
+        BPF_ALU | BPF_SUB | BPF_X,
+        BPF_ALU | BPF_MUL | BPF_K,
+        BPF_ALU | BPF_MUL | BPF_X,
+        BPF_ALU | BPF_DIV | BPF_K,
+        BPF_ALU | BPF_DIV | BPF_X,
+        BPF_ALU | BPF_MOD | BPF_K,
+        BPF_ALU | BPF_MOD | BPF_X,
+        BPF_ALU | BPF_AND | BPF_K,
+        BPF_ALU | BPF_AND | BPF_X,
+        BPF_ALU | BPF_OR | BPF_K,
+        BPF_ALU | BPF_OR | BPF_X,
+        BPF_ALU | BPF_XOR | BPF_K,
+        BPF_ALU | BPF_XOR | BPF_X,
+        BPF_ALU | BPF_LSH | BPF_K,
+        BPF_ALU | BPF_LSH | BPF_X,
+        BPF_ALU | BPF_RSH | BPF_K,
+        BPF_ALU | BPF_RSH | BPF_X,
+        BPF_ALU | BPF_NEG,
+        BPF_LD | BPF_W | BPF_ABS,
+        BPF_LD | BPF_H | BPF_ABS,
+        BPF_LD | BPF_B | BPF_ABS,
+        BPF_LD | BPF_W | BPF_LEN,
+        BPF_LD | BPF_W | BPF_IND,
+        BPF_LD | BPF_H | BPF_IND,
+        BPF_LD | BPF_B | BPF_IND,
+        BPF_LD | BPF_IMM,
+        BPF_LD | BPF_MEM,
+        BPF_LDX | BPF_W | BPF_LEN,
+        BPF_LDX | BPF_B | BPF_MSH,
+        BPF_LDX | BPF_IMM,
+        BPF_LDX | BPF_MEM,
+        BPF_ST,
+        BPF_STX,
+        BPF_MISC | BPF_TAX,
+        BPF_MISC | BPF_TXA,
+        BPF_RET | BPF_K,
+        BPF_RET | BPF_A,
+        BPF_JMP | BPF_JA,
+        BPF_JMP | BPF_JEQ | BPF_K,
+        BPF_JMP | BPF_JEQ | BPF_X,
+        BPF_JMP | BPF_JGE | BPF_K,
+        BPF_JMP | BPF_JGE | BPF_X,
+        BPF_JMP | BPF_JGT | BPF_K,
+        BPF_JMP | BPF_JGT | BPF_X,
+        BPF_JMP | BPF_JSET | BPF_K,
+        BPF_JMP | BPF_JSET | BPF_X,
+    ];
+    VALID_CODES.contains(&code)
+}
+
+/// Check that no memory word is loaded before being stored on any path.
+///
+/// Since the jumps are forward only, a single pass suffices, where `masks`
+/// holds the words stored on all the paths jumping to each instruction.
+fn check_loads_and_stores(insns: &[sock_filter]) -> Result<()> {
+    let mut masks = vec![u16::MAX; insns.len()];
+    let mut mem_valid = 0_u16;
+    for (pc, insn) in insns.iter().enumerate() {
+        mem_valid &= masks[pc];
+        let code = insn.code;
+        match code {
+            BPF_ST | BPF_STX => mem_valid |= 1 << insn.k,
+            code if code == BPF_LD | BPF_MEM || code == BPF_LDX | BPF_MEM => {
+                if mem_valid & (1 << insn.k) == 0 {
+                    return_errno!(EINVAL, "the filter loads an uninitialized memory word");
+                }
+            }
+            code if code == BPF_JMP | BPF_JA => {
+                masks[pc + 1 + insn.k as usize] &= mem_valid;
+                mem_valid = u16::MAX;
+            }
+            code if code & 0x07 == BPF_JMP => {
+                masks[pc + 1 + insn.jt as usize] &= mem_valid;
+                masks[pc + 1 + insn.jf as usize] &= mem_valid;
+                mem_valid = u16::MAX;
+            }
+            code if code & 0x07 == BPF_RET => mem_valid = u16::MAX,
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -6,6 +6,7 @@ use std;
 
 mod addr;
 mod any_addr;
+mod bpf;
 mod domain;
 mod flags;
 mod iovs;
@@ -18,10 +19,11 @@ pub use self::addr::{
     Addr, CSockAddr, Ipv4Addr, Ipv4SocketAddr, Ipv6SocketAddr, SockAddr, UnixAddr,
 };
 pub use self::any_addr::AnyAddr;
+pub use self::bpf::{sock_filter, sock_fprog, BpfFilter};
 pub use self::domain::Domain;
 pub use self::flags::{mmsghdr, MsgFlags, RecvFlags, SendFlags, SocketFlags};
 pub use self::iovs::{Iovs, IovsMut, SliceAsLibcIovec};
-pub use self::msg::{CMessages, CmsgData};
+pub use self::msg::{put_cmsg, CMessages, CmsgData};
 pub use self::protocol::SocketProtocol;
 pub use self::r#type::SocketType;
 pub use self::shutdown::Shutdown;
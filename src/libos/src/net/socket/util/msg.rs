@@ -1,5 +1,6 @@
 /// Socket message and its flags.
 use super::*;
+use crate::net::socket::sockopt::ucred;
 
 /// This struct is used to iterate through the control messages.
 ///
@@ -40,10 +41,43 @@ impl<'a> CMessages<'a> {
     }
 }
 
+/// Put a control message after the first `len` bytes of the buffer, which are
+/// the control messages already in the buffer.
+///
+/// Returns the length of all the control messages, or `None` if the buffer is
+/// too small.
+pub fn put_cmsg(
+    buffer: &mut [u8],
+    len: usize,
+    level: i32,
+    cmsg_type: i32,
+    data: &[u8],
+) -> Option<usize> {
+    let offset = (len + core::mem::size_of::<usize>() - 1) & !(core::mem::size_of::<usize>() - 1);
+    let cmsg_len = unsafe { libc::CMSG_LEN(data.len() as u32) } as usize;
+    if offset + cmsg_len > buffer.len() {
+        return None;
+    }
+
+    let mut cmsg: libc::cmsghdr = unsafe { core::mem::zeroed() };
+    cmsg.cmsg_len = cmsg_len as _;
+    cmsg.cmsg_level = level;
+    cmsg.cmsg_type = cmsg_type;
+    unsafe {
+        (buffer[offset..].as_mut_ptr() as *mut libc::cmsghdr).write_unaligned(cmsg);
+    }
+    let data_offset = offset + cmsg_len - data.len();
+    buffer[data_offset..offset + cmsg_len].copy_from_slice(data);
+
+    // Like Linux, the padding is included if there is enough space
+    let cmsg_space = unsafe { libc::CMSG_SPACE(data.len() as u32) } as usize;
+    Some((offset + cmsg_space).min(buffer.len()))
+}
+
 /// Control message data of variable type. The data resides next to `cmsghdr`.
 pub enum CmsgData<'a> {
     ScmRights(ScmRights<'a>),
-    ScmCredentials,
+    ScmCredentials(ScmCredentials<'a>),
 }
 
 impl<'a> CmsgData<'a> {
@@ -64,8 +98,9 @@ impl<'a> CmsgData<'a> {
     ///
     /// `data` must contain a valid control message and the control message must be type of
     /// `SOL_SOCKET` and level of `SCM_CREDENTIALS`.
-    unsafe fn as_credentials(_data: &'a [u8]) -> Self {
-        CmsgData::ScmCredentials
+    unsafe fn as_credentials(data: &'a [u8]) -> Self {
+        let scm_credentials = ScmCredentials { data };
+        CmsgData::ScmCredentials(scm_credentials)
     }
 
     fn try_from_cmsghdr(cmsg: &'a libc::cmsghdr) -> Option<Self> {
@@ -113,3 +148,20 @@ impl<'a> ScmRights<'a> {
             .map(|fd_bytes| FileDesc::from_ne_bytes(fd_bytes.try_into().unwrap()))
     }
 }
+
+/// The data of this control message is the credentials of the sender.
+///
+/// The level is equal to `SOL_SOCKET` and the type is equal to `SCM_CREDENTIALS`.
+pub struct ScmCredentials<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ScmCredentials<'a> {
+    /// Returns the credentials, or `None` if the length of the data is invalid.
+    pub fn ucred(&self) -> Option<ucred> {
+        if self.data.len() != core::mem::size_of::<ucred>() {
+            return None;
+        }
+        Some(unsafe { (self.data.as_ptr() as *const ucred).read_unaligned() })
+    }
+}
@@ -1,4 +1,7 @@
-use super::socket::{mmsghdr, MsgFlags, SocketFlags, SocketProtocol};
+use super::socket::{
+    mmsghdr, sock_filter, sock_fprog, BpfFilter, GetFilterCmd, HostSockOpt, MsgFlags, SetFilterCmd,
+    SocketFlags, SocketProtocol,
+};
 
 use atomic::Ordering;
 use core::f32::consts::E;
@@ -7,8 +10,10 @@ use std::mem::MaybeUninit;
 use std::ptr;
 use std::time::Duration;
 
-use super::io_multiplexing::{AsEpollFile, EpollCtl, EpollFile, EpollFlags, FdSetExt, PollFd};
-use fs::{CreationFlags, File, FileDesc, FileRef, IoctlCmd};
+use super::io_multiplexing::{
+    AsEpollFile, EpollCtl, EpollFile, EpollFlags, FdSetExt, PollFd, HOST_POLLER,
+};
+use fs::{CreationFlags, File, FileDesc, FileRef, IoEvents, IoctlCmd};
 use misc::resource_t;
 use process::Process;
 use signal::{sigset_t, MaskOp, SigSet, SIGKILL, SIGSTOP};
@@ -71,6 +76,10 @@ pub fn do_socket(domain: c_int, socket_type: c_int, protocol: c_int) -> Result<i
                 let unix_socket = unix_socket(socket_type, flags, protocol)?;
                 file_ref = Some(Arc::new(unix_socket));
             }
+            Domain::NETLINK if protocol == NETLINK_ROUTE => {
+                let netlink_socket = NetlinkSocket::new(socket_type, flags, protocol)?;
+                file_ref = Some(Arc::new(netlink_socket));
+            }
             _ => {
                 let socket = HostSocket::new(domain, socket_type, flags, protocol)?;
                 file_ref = Some(Arc::new(socket));
@@ -102,6 +111,8 @@ pub fn do_bind(fd: c_int, addr: *const libc::sockaddr, addr_len: libc::socklen_t
         unix_socket.bind(unix_addr)?;
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         uring_socket.bind(&addr)?;
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.bind(&addr)?;
     } else {
         return_errno!(ENOTSOCK, "not a socket");
     }
@@ -124,6 +135,8 @@ pub fn do_listen(fd: c_int, backlog: c_int) -> Result<isize> {
             backlog as u32
         };
         uring_socket.listen(backlog)?;
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        return_errno!(EOPNOTSUPP, "netlink sockets cannot listen");
     } else {
         return_errno!(ENOTSOCK, "not a socket");
     }
@@ -135,6 +148,28 @@ pub fn do_connect(
     fd: c_int,
     addr: *const libc::sockaddr,
     addr_len: libc::socklen_t,
+) -> Result<isize> {
+    connect(fd, addr, addr_len, false)
+}
+
+/// Connect like `do_connect`, but never wait for the connection to be
+/// established even if the socket is blocking, e.g., for io_uring.
+///
+/// A host socket still connects as it is, since the host has no way to
+/// connect a blocking socket without blocking.
+pub fn do_connect_nonblocking(
+    fd: c_int,
+    addr: *const libc::sockaddr,
+    addr_len: libc::socklen_t,
+) -> Result<isize> {
+    connect(fd, addr, addr_len, true)
+}
+
+fn connect(
+    fd: c_int,
+    addr: *const libc::sockaddr,
+    addr_len: libc::socklen_t,
+    nonblocking: bool,
 ) -> Result<isize> {
     // For SOCK_DGRAM sockets not initiated in connection-mode,
     // if address is a null address for the protocol,
@@ -167,7 +202,13 @@ pub fn do_connect(
         // TODO: support AF_UNSPEC address for datagram socket use
         unix_socket.connect(addr.to_unix()?)?;
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
-        uring_socket.connect(&addr)?;
+        if nonblocking {
+            uring_socket.try_connect(&addr)?;
+        } else {
+            uring_socket.connect(&addr)?;
+        }
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.connect(&addr)?;
     } else {
         return_errno!(ENOTSOCK, "not a socket");
     }
@@ -188,6 +229,27 @@ pub fn do_accept4(
     addr: *mut libc::sockaddr,
     addr_len: *mut libc::socklen_t,
     flags: c_int,
+) -> Result<isize> {
+    accept4(fd, addr, addr_len, flags, false)
+}
+
+/// Accept like `do_accept4`, but never block even if the socket is blocking,
+/// e.g., for io_uring.
+pub fn do_accept4_nonblocking(
+    fd: c_int,
+    addr: *mut libc::sockaddr,
+    addr_len: *mut libc::socklen_t,
+    flags: c_int,
+) -> Result<isize> {
+    accept4(fd, addr, addr_len, flags, true)
+}
+
+fn accept4(
+    fd: c_int,
+    addr: *mut libc::sockaddr,
+    addr_len: *mut libc::socklen_t,
+    flags: c_int,
+    nonblocking: bool,
 ) -> Result<isize> {
     let addr_and_addr_len = get_slice_from_sock_addr_ptr_mut(addr, addr_len)?;
     let sock_flags =
@@ -199,22 +261,39 @@ pub fn do_accept4(
     // Accept the socket
     let (new_file_ref, sock_addr_option): (Arc<dyn File>, Option<AnyAddr>) =
         if let Ok(socket) = file_ref.as_host_socket() {
+            // The host has no way to accept without blocking on a blocking
+            // socket, so accept only if a connection is ready. It may still
+            // block in the rare case that another thread takes the connection
+            // in between.
+            if nonblocking && HOST_POLLER.poll_now(&file_ref, IoEvents::IN)?.is_empty() {
+                return_errno!(EAGAIN, "no connections are present to be accepted");
+            }
             let (new_socket_file, sock_addr_option) = socket.accept(sock_flags)?;
             (
                 Arc::new(new_socket_file),
                 sock_addr_option.map(|raw_addr| AnyAddr::Raw(raw_addr)),
             )
         } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
-            let (new_socket_file, sock_addr_option) = unix_socket.accept(sock_flags)?;
+            let (new_socket_file, sock_addr_option) = if nonblocking {
+                unix_socket.try_accept(sock_flags)?
+            } else {
+                unix_socket.accept(sock_flags)?
+            };
             (
                 Arc::new(new_socket_file),
                 sock_addr_option.map(|unix_addr| AnyAddr::Unix(unix_addr)),
             )
         } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
-            let nonblocking = sock_flags.contains(SocketFlags::SOCK_NONBLOCK);
-            let accepted_socket = uring_socket.accept(nonblocking)?;
+            let is_new_nonblocking = sock_flags.contains(SocketFlags::SOCK_NONBLOCK);
+            let accepted_socket = if nonblocking {
+                uring_socket.try_accept(is_new_nonblocking)?
+            } else {
+                uring_socket.accept(is_new_nonblocking)?
+            };
             let sock_addr = accepted_socket.peer_addr()?;
             (Arc::new(accepted_socket), Some(sock_addr))
+        } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+            return_errno!(EOPNOTSUPP, "netlink sockets cannot accept");
         } else {
             return_errno!(ENOTSOCK, "not a socket");
         };
@@ -245,6 +324,8 @@ pub fn do_shutdown(fd: c_int, how: c_int) -> Result<isize> {
         unix_socket.shutdown(how)?;
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         uring_socket.shutdown(how)?;
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.shutdown(how)?;
     } else {
         return_errno!(EBADF, "not a host socket")
     }
@@ -271,10 +352,12 @@ pub fn do_setsockopt(
         let mut cmd = new_host_setsockopt_cmd(level, optname, optval)?;
         host_socket.ioctl(cmd.as_mut())?;
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
-        warn!("setsockopt for unix socket is unimplemented");
+        unix_socket.setsockopt(level, optname, optval)?;
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         let mut cmd = new_uring_setsockopt_cmd(level, optname, optval, uring_socket.get_type())?;
         uring_socket.ioctl(cmd.as_mut())?;
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.setsockopt(level, optname, optval)?;
     } else {
         return_errno!(ENOTSOCK, "not a socket")
     }
@@ -311,12 +394,20 @@ pub fn do_getsockopt(
         let src_optval = get_optval(cmd.as_ref())?;
         copy_bytes_to_user(src_optval, optval_mut, optlen_mut);
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
-        warn!("getsockopt for unix socket is unimplemented");
+        let src_optval = unix_socket.getsockopt(level, optname)?;
+        copy_bytes_to_user(&src_optval, optval_mut, optlen_mut);
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         let mut cmd = new_uring_getsockopt_cmd(level, optname, optlen, uring_socket.get_type())?;
         uring_socket.ioctl(cmd.as_mut())?;
         let src_optval = get_optval(cmd.as_ref())?;
-        copy_bytes_to_user(src_optval, optval_mut, optlen_mut);
+        if cmd.downcast_ref::<GetFilterCmd>().is_some() {
+            copy_filter_to_user(src_optval, optval, optlen_mut)?;
+        } else {
+            copy_bytes_to_user(src_optval, optval_mut, optlen_mut);
+        }
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let src_optval = netlink_socket.getsockopt(level, optname)?;
+        copy_bytes_to_user(&src_optval, optval_mut, optlen_mut);
     } else {
         return_errno!(ENOTSOCK, "not a socket")
     }
@@ -340,6 +431,8 @@ pub fn do_getpeername(
         unix_socket.peer_addr()?.to_c_storage()
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         uring_socket.peer_addr()?.to_c_storage()
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.peer_addr().to_c_storage()
     } else {
         return_errno!(ENOTSOCK, "not a socket")
     };
@@ -368,6 +461,8 @@ pub fn do_getsockname(
         unix_socket.addr().to_c_storage()
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         uring_socket.addr()?.to_c_storage()
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.addr().to_c_storage()
     } else {
         return_errno!(ENOTSOCK, "not a socket");
     };
@@ -422,6 +517,10 @@ pub fn do_sendto(
         uring_socket
             .sendto(&buf, addr, send_flags)
             .map(|bytes_send| bytes_send as isize)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket
+            .sendto(buf, send_flags, addr)
+            .map(|bytes_send| bytes_send as isize)
     } else {
         return_errno!(EBADF, "unsupported file type");
     }
@@ -454,6 +553,8 @@ pub fn do_recvfrom(
             .map(|(len, addr_recv)| (len, addr_recv.map(|unix_addr| AnyAddr::Unix(unix_addr))))?
     } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
         uring_socket.recvfrom(&mut buf, recv_flags)?
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.recvfrom(buf, recv_flags)?
     } else {
         return_errno!(ENOTSOCK, "not a socket");
     };
@@ -524,6 +625,10 @@ pub fn do_sendmsg(fd: c_int, msg_ptr: *const libc::msghdr, flags_c: c_int) -> Re
         uring_socket
             .sendmsg(&bufs[..], addr, flags, control)
             .map(|bytes_send| bytes_send as isize)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket
+            .sendmsg(&bufs[..], flags, addr, control)
+            .map(|bytes_send| bytes_send as isize)
     } else {
         return_errno!(ENOTSOCK, "not a socket")
     }
@@ -545,6 +650,8 @@ pub fn do_recvmsg(fd: c_int, msg_mut_ptr: *mut libc::msghdr, flags_c: c_int) ->
             unix_socket.recvmsg(&mut bufs[..], flags, control)?
         } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
             uring_socket.recvmsg(&mut bufs[..], flags, control)?
+        } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+            netlink_socket.recvmsg(&mut bufs[..], flags, control)?
         } else {
             return_errno!(ENOTSOCK, "not a socket")
         };
@@ -565,6 +672,49 @@ pub fn do_recvmsg(fd: c_int, msg_mut_ptr: *mut libc::msghdr, flags_c: c_int) ->
     Ok(bytes_recv as isize)
 }
 
+/// Whether the file is a socket of any type.
+pub fn is_socket(file_ref: &FileRef) -> bool {
+    file_ref.as_host_socket().is_ok()
+        || file_ref.as_unix_socket().is_ok()
+        || file_ref.as_uring_socket().is_ok()
+        || file_ref.as_netlink_socket().is_ok()
+}
+
+/// Receive data from a socket without blocking even if the socket is
+/// blocking, e.g., to read a socket for io_uring.
+pub fn socket_recv_nonblocking(file_ref: &FileRef, bufs: &mut [&mut [u8]]) -> Result<usize> {
+    let flags = RecvFlags::MSG_DONTWAIT;
+    let (bytes_recv, _, _, _) = if let Ok(host_socket) = file_ref.as_host_socket() {
+        host_socket.recvmsg(bufs, flags, None)?
+    } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
+        unix_socket.recvmsg(bufs, flags, None)?
+    } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
+        uring_socket.recvmsg(bufs, flags, None)?
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.recvmsg(bufs, flags, None)?
+    } else {
+        return_errno!(ENOTSOCK, "not a socket");
+    };
+    Ok(bytes_recv)
+}
+
+/// Send data to a socket without blocking even if the socket is blocking,
+/// e.g., to write a socket for io_uring.
+pub fn socket_send_nonblocking(file_ref: &FileRef, bufs: &[&[u8]]) -> Result<usize> {
+    let flags = SendFlags::MSG_DONTWAIT;
+    if let Ok(host_socket) = file_ref.as_host_socket() {
+        host_socket.sendmsg(bufs, flags, None, None)
+    } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
+        unix_socket.sendmsg(bufs, flags, None)
+    } else if let Ok(uring_socket) = file_ref.as_uring_socket() {
+        uring_socket.sendmsg(bufs, None, flags, None)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.sendmsg(bufs, flags, None, None)
+    } else {
+        return_errno!(ENOTSOCK, "not a socket");
+    }
+}
+
 pub fn do_sendmmsg(
     fd: c_int,
     msgvec_ptr: *mut mmsghdr,
@@ -619,6 +769,23 @@ pub fn do_sendmmsg(
                 break;
             }
         }
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        for mmsg in (msgvec) {
+            let (addr, bufs, control) = extract_msghdr_from_user(&mmsg.msg_hdr)?;
+
+            if netlink_socket
+                .sendmsg(&bufs[..], flags, addr, control)
+                .map(|bytes_send| {
+                    mmsg.msg_len = bytes_send as c_uint;
+                    bytes_send as isize
+                })
+                .is_ok()
+            {
+                send_count += 1;
+            } else {
+                break;
+            }
+        }
     } else {
         return_errno!(ENOTSOCK, "not a socket")
     }
@@ -1052,6 +1219,10 @@ fn new_host_getsockopt_cmd(level: i32, optname: i32, optlen: u32) -> Result<Box<
 
     Ok(match opt {
         SockOptName::SO_CNX_ADVICE => return_errno!(ENOPROTOOPT, "it's a write-only option"),
+        // SO_GET_FILTER has the same value as SO_ATTACH_FILTER
+        SockOptName::SO_ATTACH_FILTER => {
+            return_errno!(EOPNOTSUPP, "filters of host sockets are not supported")
+        }
         _ => Box::new(GetSockOptRawCmd::new(level, optname, optlen)),
     })
 }
@@ -1064,7 +1235,7 @@ fn new_uring_getsockopt_cmd(
     socket_type: SocketType,
 ) -> Result<Box<dyn IoctlCmd>> {
     if level != libc::SOL_SOCKET {
-        return Ok(Box::new(GetSockOptRawCmd::new(level, optname, optlen)));
+        return new_uring_host_getsockopt_cmd(level, optname);
     }
 
     let opt =
@@ -1078,14 +1249,21 @@ fn new_uring_getsockopt_cmd(
         SockOptName::SO_TYPE => Box::new(GetTypeCmd::new(())),
         SockOptName::SO_RCVTIMEO_OLD => Box::new(GetRecvTimeoutCmd::new(())),
         SockOptName::SO_SNDTIMEO_OLD => Box::new(GetSendTimeoutCmd::new(())),
-        SockOptName::SO_SNDBUF => Box::new(GetSockOptRawCmd::new(level, optname, optlen)),
-        SockOptName::SO_RCVBUF => Box::new(GetSockOptRawCmd::new(level, optname, optlen)),
+        // SO_GET_FILTER has the same value as SO_ATTACH_FILTER
+        SockOptName::SO_ATTACH_FILTER => Box::new(GetFilterCmd::new(())),
 
         SockOptName::SO_CNX_ADVICE => return_errno!(ENOPROTOOPT, "it's a write-only option"),
-        _ => Box::new(GetSockOptRawCmd::new(level, optname, optlen)),
+        _ => return new_uring_host_getsockopt_cmd(level, optname),
     })
 }
 
+/// Create a new ioctl command for uring socket getsockopt syscall, which gets
+/// an option forwarded to the host socket
+fn new_uring_host_getsockopt_cmd(level: i32, optname: i32) -> Result<Box<dyn IoctlCmd>> {
+    let host_opt = HostSockOpt::lookup(level, optname)?;
+    Ok(Box::new(GetSockOptRawCmd::new_checked(host_opt)?))
+}
+
 /// Create a new ioctl command for host socket setsockopt syscall
 fn new_host_setsockopt_cmd(
     level: i32,
@@ -1113,6 +1291,16 @@ fn new_host_setsockopt_cmd(
         | SockOptName::SO_INCOMING_NAPI_ID
         | SockOptName::SO_COOKIE
         | SockOptName::SO_PEERGROUPS => return_errno!(ENOPROTOOPT, "it's a read-only option"),
+        // The filters are given by the pointers or the fds in the enclave,
+        // which cannot be forwarded to the host. And the filters must be
+        // enforced in the enclave rather than by the untrusted host.
+        SockOptName::SO_ATTACH_FILTER
+        | SockOptName::SO_DETACH_FILTER
+        | SockOptName::SO_ATTACH_BPF
+        | SockOptName::SO_ATTACH_REUSEPORT_CBPF
+        | SockOptName::SO_ATTACH_REUSEPORT_EBPF => {
+            return_errno!(EOPNOTSUPP, "filters of host sockets are not supported")
+        }
         _ => Box::new(SetSockOptRawCmd::new(level, optname, optval)),
     })
 }
@@ -1125,7 +1313,7 @@ fn new_uring_setsockopt_cmd(
     socket_type: SocketType,
 ) -> Result<Box<dyn IoctlCmd>> {
     if level != libc::SOL_SOCKET {
-        return Ok(Box::new(SetSockOptRawCmd::new(level, optname, optval)));
+        return new_uring_host_setsockopt_cmd(level, optname, optval);
     }
 
     if optval.len() == 0 {
@@ -1198,7 +1386,7 @@ fn new_uring_setsockopt_cmd(
         SockOptName::SO_SNDBUF => {
             // Implement dynamic buf size for stream socket only.
             if socket_type != SocketType::STREAM {
-                Box::new(SetSockOptRawCmd::new(level, optname, optval))
+                return new_uring_host_setsockopt_cmd(level, optname, optval);
             } else {
                 // For the max value, we choose 4MB (doubled) to assure the libos kernel buf won't be the bottleneck.
                 let max_size = 2 * 1024 * 1024;
@@ -1225,7 +1413,7 @@ fn new_uring_setsockopt_cmd(
         }
         SockOptName::SO_RCVBUF => {
             if socket_type != SocketType::STREAM {
-                Box::new(SetSockOptRawCmd::new(level, optname, optval))
+                return new_uring_host_setsockopt_cmd(level, optname, optval);
             } else {
                 // Implement dynamic buf size for stream socket only.
                 info!("optval = {:?}", optval);
@@ -1252,10 +1440,50 @@ fn new_uring_setsockopt_cmd(
                 Box::new(SetRecvBufSizeCmd::new(recv_buf_size))
             }
         }
-        _ => Box::new(SetSockOptRawCmd::new(level, optname, optval)),
+        SockOptName::SO_ATTACH_FILTER => {
+            if socket_type != SocketType::DGRAM {
+                return_errno!(EOPNOTSUPP, "filters are only supported by datagram sockets");
+            }
+            let filter = BpfFilter::new(read_filter_from_user(optval)?)?;
+            Box::new(SetFilterCmd::new(Some(Arc::new(filter))))
+        }
+        SockOptName::SO_DETACH_FILTER => {
+            if socket_type != SocketType::DGRAM {
+                return_errno!(EOPNOTSUPP, "filters are only supported by datagram sockets");
+            }
+            Box::new(SetFilterCmd::new(None))
+        }
+        _ => return new_uring_host_setsockopt_cmd(level, optname, optval),
     })
 }
 
+/// Create a new ioctl command for uring socket setsockopt syscall, which sets
+/// an option forwarded to the host socket
+fn new_uring_host_setsockopt_cmd(
+    level: i32,
+    optname: i32,
+    optval: &'static [u8],
+) -> Result<Box<dyn IoctlCmd>> {
+    let host_opt = HostSockOpt::lookup(level, optname)?;
+    host_opt.check_set(optval)?;
+    Ok(Box::new(SetSockOptRawCmd::new(level, optname, optval)))
+}
+
+/// Read the instructions of the filter given by the `sock_fprog` of
+/// `SO_ATTACH_FILTER`.
+fn read_filter_from_user(optval: &[u8]) -> Result<Vec<sock_filter>> {
+    if optval.len() < std::mem::size_of::<sock_fprog>() {
+        return_errno!(EINVAL, "invalid filter option");
+    }
+    let fprog = unsafe { (optval.as_ptr() as *const sock_fprog).read_unaligned() };
+    if fprog.len == 0 {
+        return_errno!(EINVAL, "the filter is empty");
+    }
+    from_user::check_array(fprog.filter, fprog.len as usize)?;
+    let insns = unsafe { std::slice::from_raw_parts(fprog.filter, fprog.len as usize) };
+    Ok(insns.to_vec())
+}
+
 fn get_optval(cmd: &dyn IoctlCmd) -> Result<&[u8]> {
     crate::match_ioctl_cmd_ref!(cmd, {
         cmd : GetAcceptConnCmd => {
@@ -1288,6 +1516,9 @@ fn get_optval(cmd: &dyn IoctlCmd) -> Result<&[u8]> {
         cmd : GetRecvBufSizeCmd => {
             cmd.get_output_as_bytes()
         },
+        cmd : GetFilterCmd => {
+            cmd.get_output_as_bytes()
+        },
         _ => {
             return_errno!(EINVAL, "invalid sockopt command");
         }
@@ -1295,6 +1526,24 @@ fn get_optval(cmd: &dyn IoctlCmd) -> Result<&[u8]> {
     .ok_or_else(|| errno!(EINVAL, "no available output"))
 }
 
+/// Copy the instructions of the filter got by `SO_GET_FILTER` to the user.
+///
+/// Like Linux, the optlen of `SO_GET_FILTER` is the number of instructions
+/// rather than bytes, and a too small buffer is an error instead of being
+/// truncated.
+fn copy_filter_to_user(src_buf: &[u8], dst_ptr: *mut c_void, dst_len: &mut u32) -> Result<()> {
+    let count = src_buf.len() / std::mem::size_of::<sock_filter>();
+    if (*dst_len as usize) < count {
+        return_errno!(EINVAL, "the buffer is too small for the filter");
+    }
+    if count > 0 {
+        let dst_buf = from_user::make_mut_slice(dst_ptr as *mut u8, src_buf.len())?;
+        dst_buf.copy_from_slice(src_buf);
+    }
+    *dst_len = count as u32;
+    Ok(())
+}
+
 fn copy_bytes_to_user(src_buf: &[u8], dst_buf: &mut [u8], dst_len: &mut u32) {
     let copy_len = dst_buf.len().min(src_buf.len());
     dst_buf[..copy_len].copy_from_slice(&src_buf[..copy_len]);
@@ -0,0 +1,268 @@
+use super::{gid_t, uid_t};
+use crate::prelude::*;
+
+/// The maximum number of supplementary groups, i.e., NGROUPS_MAX
+pub const NGROUPS_MAX: usize = 65536;
+
+/// The user and group identities of a process.
+///
+/// The privileged operations are allowed if the effective user ID is root,
+/// since Occlum does not support capabilities.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    ruid: uid_t,
+    euid: uid_t,
+    suid: uid_t,
+    fsuid: uid_t,
+    rgid: gid_t,
+    egid: gid_t,
+    sgid: gid_t,
+    fsgid: gid_t,
+    groups: Vec<gid_t>,
+}
+
+impl Credentials {
+    /// The credentials of root, which is the default of the init process
+    pub fn new_root() -> Self {
+        Self {
+            ruid: 0,
+            euid: 0,
+            suid: 0,
+            fsuid: 0,
+            rgid: 0,
+            egid: 0,
+            sgid: 0,
+            fsgid: 0,
+            groups: vec![0],
+        }
+    }
+
+    pub fn ruid(&self) -> uid_t {
+        self.ruid
+    }
+
+    pub fn euid(&self) -> uid_t {
+        self.euid
+    }
+
+    pub fn suid(&self) -> uid_t {
+        self.suid
+    }
+
+    pub fn fsuid(&self) -> uid_t {
+        self.fsuid
+    }
+
+    pub fn rgid(&self) -> gid_t {
+        self.rgid
+    }
+
+    pub fn egid(&self) -> gid_t {
+        self.egid
+    }
+
+    pub fn sgid(&self) -> gid_t {
+        self.sgid
+    }
+
+    pub fn fsgid(&self) -> gid_t {
+        self.fsgid
+    }
+
+    pub fn groups(&self) -> &[gid_t] {
+        &self.groups
+    }
+
+    pub fn is_privileged(&self) -> bool {
+        self.euid == 0
+    }
+
+    /// Whether the file system group ID or one of the supplementary groups is `gid`
+    pub fn in_group(&self, gid: gid_t) -> bool {
+        self.fsgid == gid || self.groups.contains(&gid)
+    }
+
+    pub fn setuid(&mut self, uid: uid_t) -> Result<()> {
+        if self.is_privileged() {
+            self.ruid = uid;
+            self.suid = uid;
+        } else if uid != self.ruid && uid != self.suid {
+            return_errno!(EPERM, "not allowed to set the user ID");
+        }
+        self.euid = uid;
+        self.fsuid = uid;
+        Ok(())
+    }
+
+    pub fn setgid(&mut self, gid: gid_t) -> Result<()> {
+        if self.is_privileged() {
+            self.rgid = gid;
+            self.sgid = gid;
+        } else if gid != self.rgid && gid != self.sgid {
+            return_errno!(EPERM, "not allowed to set the group ID");
+        }
+        self.egid = gid;
+        self.fsgid = gid;
+        Ok(())
+    }
+
+    /// Set the real and effective user IDs, `None` means unchanged.
+    pub fn setreuid(&mut self, ruid: Option<uid_t>, euid: Option<uid_t>) -> Result<()> {
+        if !self.is_privileged() {
+            if ruid.map_or(false, |ruid| ruid != self.ruid && ruid != self.euid) {
+                return_errno!(EPERM, "not allowed to set the real user ID");
+            }
+            if euid.map_or(false, |euid| {
+                euid != self.ruid && euid != self.euid && euid != self.suid
+            }) {
+                return_errno!(EPERM, "not allowed to set the effective user ID");
+            }
+        }
+        let old_ruid = self.ruid;
+        if let Some(ruid) = ruid {
+            self.ruid = ruid;
+        }
+        if let Some(euid) = euid {
+            self.euid = euid;
+        }
+        // The saved user ID follows the effective one if the real user ID is
+        // set or the effective user ID is set to a value other than the
+        // previous real user ID.
+        if ruid.is_some() || euid.map_or(false, |euid| euid != old_ruid) {
+            self.suid = self.euid;
+        }
+        self.fsuid = self.euid;
+        Ok(())
+    }
+
+    /// Set the real and effective group IDs, `None` means unchanged.
+    pub fn setregid(&mut self, rgid: Option<gid_t>, egid: Option<gid_t>) -> Result<()> {
+        if !self.is_privileged() {
+            if rgid.map_or(false, |rgid| rgid != self.rgid && rgid != self.egid) {
+                return_errno!(EPERM, "not allowed to set the real group ID");
+            }
+            if egid.map_or(false, |egid| {
+                egid != self.rgid && egid != self.egid && egid != self.sgid
+            }) {
+                return_errno!(EPERM, "not allowed to set the effective group ID");
+            }
+        }
+        let old_rgid = self.rgid;
+        if let Some(rgid) = rgid {
+            self.rgid = rgid;
+        }
+        if let Some(egid) = egid {
+            self.egid = egid;
+        }
+        if rgid.is_some() || egid.map_or(false, |egid| egid != old_rgid) {
+            self.sgid = self.egid;
+        }
+        self.fsgid = self.egid;
+        Ok(())
+    }
+
+    /// Set the real, effective and saved user IDs, `None` means unchanged.
+    pub fn setresuid(
+        &mut self,
+        ruid: Option<uid_t>,
+        euid: Option<uid_t>,
+        suid: Option<uid_t>,
+    ) -> Result<()> {
+        if !self.is_privileged() {
+            let is_allowed = |uid: uid_t| uid == self.ruid || uid == self.euid || uid == self.suid;
+            if [ruid, euid, suid]
+                .iter()
+                .any(|uid| uid.map_or(false, |uid| !is_allowed(uid)))
+            {
+                return_errno!(EPERM, "not allowed to set the user IDs");
+            }
+        }
+        if let Some(ruid) = ruid {
+            self.ruid = ruid;
+        }
+        if let Some(euid) = euid {
+            self.euid = euid;
+        }
+        if let Some(suid) = suid {
+            self.suid = suid;
+        }
+        self.fsuid = self.euid;
+        Ok(())
+    }
+
+    /// Set the real, effective and saved group IDs, `None` means unchanged.
+    pub fn setresgid(
+        &mut self,
+        rgid: Option<gid_t>,
+        egid: Option<gid_t>,
+        sgid: Option<gid_t>,
+    ) -> Result<()> {
+        if !self.is_privileged() {
+            let is_allowed = |gid: gid_t| gid == self.rgid || gid == self.egid || gid == self.sgid;
+            if [rgid, egid, sgid]
+                .iter()
+                .any(|gid| gid.map_or(false, |gid| !is_allowed(gid)))
+            {
+                return_errno!(EPERM, "not allowed to set the group IDs");
+            }
+        }
+        if let Some(rgid) = rgid {
+            self.rgid = rgid;
+        }
+        if let Some(egid) = egid {
+            self.egid = egid;
+        }
+        if let Some(sgid) = sgid {
+            self.sgid = sgid;
+        }
+        self.fsgid = self.egid;
+        Ok(())
+    }
+
+    /// Set the file system user ID, return the previous one.
+    ///
+    /// Like Linux, the previous value is returned even if the call fails.
+    pub fn setfsuid(&mut self, fsuid: uid_t) -> uid_t {
+        let old_fsuid = self.fsuid;
+        if self.is_privileged()
+            || fsuid == self.ruid
+            || fsuid == self.euid
+            || fsuid == self.suid
+            || fsuid == self.fsuid
+        {
+            self.fsuid = fsuid;
+        }
+        old_fsuid
+    }
+
+    /// Set the file system group ID, return the previous one.
+    pub fn setfsgid(&mut self, fsgid: gid_t) -> gid_t {
+        let old_fsgid = self.fsgid;
+        if self.is_privileged()
+            || fsgid == self.rgid
+            || fsgid == self.egid
+            || fsgid == self.sgid
+            || fsgid == self.fsgid
+        {
+            self.fsgid = fsgid;
+        }
+        old_fsgid
+    }
+
+    pub fn setgroups(&mut self, groups: &[gid_t]) -> Result<()> {
+        if !self.is_privileged() {
+            return_errno!(EPERM, "not allowed to set the supplementary groups");
+        }
+        if groups.len() > NGROUPS_MAX {
+            return_errno!(EINVAL, "too many groups");
+        }
+        self.groups = groups.to_vec();
+        Ok(())
+    }
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Self::new_root()
+    }
+}
@@ -11,6 +11,18 @@ pub fn get() -> ThreadRef {
     current_ref_clone
 }
 
+/// Run `f` with a reference to the current thread, which avoids cloning it as
+/// `get` does. It is used in the hot paths, e.g., at every syscall.
+pub fn with<F, R>(f: F) -> R
+where
+    F: FnOnce(&Thread) -> R,
+{
+    let current_ptr = CURRENT_THREAD_PTR.with(|cell| cell.get());
+    // Safety. The current thread is kept alive by the reference held in
+    // CURRENT_THREAD_PTR, which is not replaced during `f`.
+    f(unsafe { &*current_ptr })
+}
+
 pub(super) fn set(thread_ref: ThreadRef) {
     assert!(thread_ref.tid() > 0);
     replace(thread_ref);
@@ -49,6 +49,8 @@ pub fn do_clone(
         };
         let files = current.files().clone();
         let nice = current.nice().clone();
+        // The new thread inherits the scheduler settings
+        let sched = Arc::new(SgxMutex::new(current.sched().lock().unwrap().clone()));
         let rlimits = current.rlimits().clone();
         let fs = current.fs().clone();
         let name = current.name().clone();
@@ -61,6 +63,7 @@ pub fn do_clone(
             .fs(fs)
             .files(files)
             .name(name)
+            .sched(sched)
             .nice(nice)
             .rlimits(rlimits)
             .sig_mask(sig_mask);
@@ -3,13 +3,17 @@ use crate::signal::constants::*;
 use std::intrinsics::atomic_store_seqcst;
 
 use super::do_futex::futex_wake;
+use super::do_ptrace::release_tracees;
 use super::do_vfork::{is_vforked_child_process, vfork_return_to_parent};
 use super::pgrp::clean_pgrp_when_exit;
-use super::process::{Process, ProcessFilter};
-use super::{table, ProcessRef, TermStatus, ThreadRef, ThreadStatus};
+use super::process::{Process, ProcessFilter, ProcessInner};
+use super::rusage::account_execve;
+use super::session::hangup_session_when_exit;
+use super::{table, ProcessRef, ProcessStatus, TermStatus, ThreadRef, ThreadStatus};
+use crate::fs::{release_aio_contexts_when_process_exit, release_io_urings_when_process_exit};
 use crate::ipc::SYSTEM_V_SHM_MANAGER;
 use crate::prelude::*;
-use crate::signal::{KernelSignal, SigNum};
+use crate::signal::{prepare_signal, KernelSignal, SigNum};
 use crate::syscall::CpuContext;
 use crate::vm::USER_SPACE_VM_MANAGER;
 
@@ -81,7 +85,17 @@ fn exit_thread(term_status: TermStatus) {
     // If this thread is the last thread, close all files then exit the process
     if num_remaining_threads == 0 {
         thread.close_all_files();
+        let children = thread
+            .process()
+            .inner()
+            .children()
+            .map(|children| children.clone())
+            .unwrap_or_default();
         exit_process(&thread, term_status);
+        // Notify the pidfds that the process has become a zombie
+        thread.process().exit_notifier().notify_exit();
+        // Send the parent death signals to the orphaned children
+        send_pdeath_signals(&children);
     }
 
     // Notify a thread, if any, that wait on this thread to exit.
@@ -93,11 +107,32 @@ fn exit_process(thread: &ThreadRef, term_status: TermStatus) {
     let process = thread.process();
     let pid = process.pid();
 
+    // Hang up the controlling terminal if the process is a session leader
+    hangup_session_when_exit(process);
+
+    // The children are adopted by the nearest child subreaper among the
+    // ancestors if there is any, otherwise by the idle process.
+    let subreaper = find_child_subreaper(process);
+
     // Deadlock note: always lock parent first, then child.
 
     // Lock the idle process since it may adopt new children.
     let idle_ref = super::IDLE.process().clone();
     let mut idle_inner = idle_ref.inner();
+    // Lock the subreaper if it is not the parent, which is locked below. The
+    // subreaper is an ancestor of the parent, so it is locked first.
+    let mut subreaper_inner = match subreaper.as_ref() {
+        Some(subreaper) if subreaper.pid() != process.parent().pid() => {
+            let subreaper_inner = subreaper.inner();
+            // The subreaper may have exited after it is found
+            if subreaper_inner.status().is_alive() {
+                Some(subreaper_inner)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
     // Lock the parent process as we want to prevent race conditions between
     // current's exit() and parent's wait5().
     let mut parent;
@@ -118,9 +153,13 @@ fn exit_process(thread: &ThreadRef, term_status: TermStatus) {
     };
     // Lock the current process
     let mut process_inner = process.inner();
+    // The tracees are released when the tracer exits
+    release_tracees(pid, process_inner.children().unwrap());
     // Clean used VM
+    release_io_urings_when_process_exit(pid);
     USER_SPACE_VM_MANAGER.free_chunks_when_exit(thread);
     SYSTEM_V_SHM_MANAGER.detach_shm_when_process_exit(thread);
+    release_aio_contexts_when_process_exit(pid);
 
     // The parent is the idle process
     if parent_inner.is_none() {
@@ -142,34 +181,77 @@ fn exit_process(thread: &ThreadRef, term_status: TermStatus) {
     // Otherwise, we need to notify the parent process
     let mut parent_inner = parent_inner.unwrap();
 
-    process_inner.exit(term_status, &idle_ref, &mut idle_inner);
+    let orphans = process_inner.children().unwrap().clone();
+    let (new_parent, new_parent_inner) = if let Some(subreaper_inner) = subreaper_inner.as_mut() {
+        (subreaper.as_ref().unwrap(), subreaper_inner)
+    } else if subreaper
+        .as_ref()
+        .map_or(false, |subreaper| subreaper.pid() == parent.pid())
+    {
+        (&parent, &mut parent_inner)
+    } else {
+        (&idle_ref, &mut idle_inner)
+    };
+    process_inner.exit(term_status, new_parent, new_parent_inner);
+    // The zombies adopted by a subreaper are waited by it, not the idle process
+    if new_parent.pid() != 0 {
+        for orphan in orphans.iter() {
+            if orphan.status() == ProcessStatus::Zombie {
+                notify_parent_of_zombie(orphan, new_parent, new_parent_inner);
+            }
+        }
+    }
 
     // For vfork-and-exit children, just clean them to free the pid
     let _ = reap_zombie_child_created_with_vfork(pid);
 
+    notify_parent_of_zombie(process, &parent, &mut parent_inner);
+}
+
+// Send SIGCHLD to the parent and wake it up if it is waiting on the zombie child
+fn notify_parent_of_zombie(
+    process: &ProcessRef,
+    parent: &ProcessRef,
+    parent_inner: &mut SgxMutexGuard<ProcessInner>,
+) {
     //Send SIGCHLD to parent
-    send_sigchld_to(&parent);
+    send_sigchld_to(parent);
 
     // Wake up the parent if it is waiting on this child
     let waiting_children = parent_inner.waiting_children_mut().unwrap();
     waiting_children.del_and_wake_one_waiter(|waiter_data| -> Option<pid_t> {
-        match waiter_data {
-            ProcessFilter::WithAnyPid => {}
-            ProcessFilter::WithPid(required_pid) => {
-                if process.pid() != *required_pid {
-                    return None;
-                }
-            }
-            ProcessFilter::WithPgid(required_pgid) => {
-                if process.pgid() != *required_pgid {
-                    return None;
-                }
-            }
+        if !waiter_data.matches(process) {
+            return None;
         }
         Some(process.pid())
     });
 }
 
+// Find the nearest living ancestor which is a child subreaper
+fn find_child_subreaper(process: &ProcessRef) -> Option<ProcessRef> {
+    let mut ancestor = process.parent();
+    while ancestor.pid() != 0 {
+        if ancestor.prctl_attrs().child_subreaper && ancestor.status().is_alive() {
+            return Some(ancestor);
+        }
+        ancestor = ancestor.parent();
+    }
+    None
+}
+
+fn send_pdeath_signals(children: &[ProcessRef]) {
+    for child in children {
+        if let Some(signum) = child.prctl_attrs().pdeath_signal {
+            if child.status() == ProcessStatus::Zombie {
+                continue;
+            }
+            prepare_signal(child, signum);
+            let signal = Box::new(KernelSignal::new(signum));
+            child.sig_queues().write().unwrap().enqueue(signal);
+        }
+    }
+}
+
 fn send_sigchld_to(parent: &Arc<Process>) {
     let signal = Box::new(KernelSignal::new(SigNum::from(SIGCHLD)));
     let mut sig_queues = parent.sig_queues().write().unwrap();
@@ -188,6 +270,8 @@ pub fn exit_old_process_for_execve(term_status: TermStatus, new_parent_ref: Proc
     }
 
     debug_assert!(num_remaining_threads == 0);
+    // The new process inherits the resource usage of the old one
+    account_execve(thread.process(), &new_parent_ref);
     exit_process_for_execve(&thread, new_parent_ref, term_status);
 }
 
@@ -225,8 +309,12 @@ fn exit_process_for_execve(
 
     // Lock the current process
     let mut process_inner = process.inner();
+    // The tracees are released when the tracer exits
+    release_tracees(pid, process_inner.children().unwrap());
     // Clean used VM
+    release_io_urings_when_process_exit(process.pid());
     USER_SPACE_VM_MANAGER.free_chunks_when_exit(thread);
+    release_aio_contexts_when_process_exit(process.pid());
 
     let mut new_parent_inner = new_parent_ref.inner();
     let pid = process.pid();
@@ -513,7 +513,7 @@ fn wait_event_timeout(thread: *const c_void, timeout: &Option<FutexTimeout>) ->
         })
         .unwrap_or((0, 0 as *const _, 0));
     let mut errno: c_int = 0;
-    unsafe {
+    super::account_sleep(|| unsafe {
         sgx_ret = sgx_thread_wait_untrusted_event_timeout_ocall(
             &mut ret as *mut c_int,
             thread,
@@ -524,7 +524,7 @@ fn wait_event_timeout(thread: *const c_void, timeout: &Option<FutexTimeout>) ->
         );
         assert!(sgx_ret == 0);
         assert!(ret == 0);
-    }
+    });
     if errno != 0 {
         // Do sanity check here, only possible errnos here are ETIMEDOUT, EAGAIN and EINTR
         assert!(
@@ -0,0 +1,484 @@
+//! A lite version of ptrace for debugging inside debug enclaves.
+//!
+//! Only a subset of ptrace is supported, which is enough for an in-enclave
+//! strace-like tracer:
+//!
+//! * A process can only trace its children, which are attached with
+//! PTRACE_ATTACH or PTRACE_SEIZE. Only the main thread of a tracee is traced.
+//! * After PTRACE_SYSCALL, the tracee stops at the entry and the exit of its
+//! next syscall, where its registers can be read with PTRACE_GETREGS.
+//! * The memory of a stopped tracee can be read and written word by word with
+//! PTRACE_PEEKDATA and PTRACE_POKEDATA.
+//!
+//! The tracing ends when the tracer detaches or exits, or when the tracee
+//! executes a new program.
+//!
+//! All processes share the same enclave, so ptrace gives the tracer full
+//! control over the tracee. Thus, it is only available in debug enclaves.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::do_stop::continue_process;
+use super::do_wait4::{notify_parent_status_change, WaitStatus};
+use super::untrusted_event::{set_event, wait_event};
+use super::{table, ProcessRef, ThreadStatus};
+use crate::prelude::*;
+use crate::signal::{prepare_signal, KernelSignal, SigNum, SIGSTOP, SIGTRAP};
+use crate::syscall::CpuContext;
+use crate::util::sgx::allow_debug as sgx_allow_debug;
+use crate::vm::{VMPerms, VMRange};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PtraceRequest {
+    /// Also PTRACE_PEEKTEXT, which is the same on Linux
+    PTRACE_PEEKDATA,
+    /// Also PTRACE_POKETEXT, which is the same on Linux
+    PTRACE_POKEDATA,
+    PTRACE_CONT,
+    PTRACE_GETREGS,
+    PTRACE_ATTACH,
+    PTRACE_DETACH,
+    PTRACE_SYSCALL,
+    PTRACE_SETOPTIONS,
+    PTRACE_SEIZE,
+}
+
+impl PtraceRequest {
+    pub fn from_i32(raw: i32) -> Result<Self> {
+        let request = match raw {
+            1 | 2 => Self::PTRACE_PEEKDATA,
+            4 | 5 => Self::PTRACE_POKEDATA,
+            7 => Self::PTRACE_CONT,
+            12 => Self::PTRACE_GETREGS,
+            16 => Self::PTRACE_ATTACH,
+            17 => Self::PTRACE_DETACH,
+            24 => Self::PTRACE_SYSCALL,
+            0x4200 => Self::PTRACE_SETOPTIONS,
+            0x4206 => Self::PTRACE_SEIZE,
+            _ => return_errno!(EIO, "unsupported ptrace request"),
+        };
+        Ok(request)
+    }
+}
+
+bitflags! {
+    pub struct PtraceOptions: u32 {
+        /// Report syscall stops as SIGTRAP | 0x80
+        const PTRACE_O_TRACESYSGOOD = 0x1;
+    }
+}
+
+impl PtraceOptions {
+    pub fn from_u32(bits: u32) -> Result<Self> {
+        Self::from_bits(bits).ok_or_else(|| errno!(EINVAL, "unsupported ptrace options"))
+    }
+}
+
+impl Default for PtraceOptions {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// The registers of a tracee, in the layout of `struct user_regs_struct`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct user_regs_t {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+// The segment selectors of the user space on x86-64 Linux
+const USER_CS: u64 = 0x33;
+const USER_SS: u64 = 0x2b;
+
+impl user_regs_t {
+    fn new(context: &CpuContext, orig_rax: u64, fs_base: u64) -> Self {
+        Self {
+            r15: context.r15,
+            r14: context.r14,
+            r13: context.r13,
+            r12: context.r12,
+            rbp: context.rbp,
+            rbx: context.rbx,
+            r11: context.r11,
+            r10: context.r10,
+            r9: context.r9,
+            r8: context.r8,
+            rax: context.rax,
+            rcx: context.rcx,
+            rdx: context.rdx,
+            rsi: context.rsi,
+            rdi: context.rdi,
+            orig_rax,
+            rip: context.rip,
+            cs: USER_CS,
+            eflags: context.rflags,
+            rsp: context.rsp,
+            ss: USER_SS,
+            fs_base,
+            ..Default::default()
+        }
+    }
+}
+
+/// The ptrace state of a process as a tracee.
+pub struct PtraceStatus {
+    // Whether the tracee stops at syscalls, which is checked at every syscall
+    // without taking the lock
+    trace_syscalls: AtomicBool,
+    inner: SgxMutex<PtraceStatusInner>,
+}
+
+#[derive(Debug, Default)]
+struct PtraceStatusInner {
+    tracer: Option<pid_t>,
+    options: PtraceOptions,
+    // The registers of the tracee if it is in a syscall stop
+    stopped_regs: Option<user_regs_t>,
+    // The raw pointer of the tracee thread if it is in a syscall stop
+    parked_thread: Option<usize>,
+}
+
+impl PtraceStatus {
+    pub fn new() -> Self {
+        Self {
+            trace_syscalls: AtomicBool::new(false),
+            inner: SgxMutex::new(PtraceStatusInner::default()),
+        }
+    }
+
+    pub fn tracer(&self) -> Option<pid_t> {
+        self.inner.lock().unwrap().tracer
+    }
+
+    fn is_traced_by(&self, tracer_pid: pid_t) -> bool {
+        self.tracer() == Some(tracer_pid)
+    }
+
+    fn attach(&self, tracer_pid: pid_t, options: PtraceOptions) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.tracer.is_some() {
+            return_errno!(EPERM, "the process is already traced");
+        }
+        inner.tracer = Some(tracer_pid);
+        inner.options = options;
+        Ok(())
+    }
+
+    fn set_options(&self, options: PtraceOptions) {
+        self.inner.lock().unwrap().options = options;
+    }
+
+    fn is_in_syscall_stop(&self) -> bool {
+        self.inner.lock().unwrap().stopped_regs.is_some()
+    }
+
+    fn stopped_regs(&self) -> Option<user_regs_t> {
+        self.inner.lock().unwrap().stopped_regs
+    }
+
+    /// Resume the tracee from the syscall stop, if any.
+    ///
+    /// The tracee stops at the next syscall if `trace_syscalls` is set.
+    fn resume(&self, trace_syscalls: bool) {
+        let parked_thread = {
+            let mut inner = self.inner.lock().unwrap();
+            self.trace_syscalls
+                .store(trace_syscalls && inner.tracer.is_some(), Ordering::Relaxed);
+            inner.stopped_regs = None;
+            inner.parked_thread.take()
+        };
+        if let Some(thread_ptr) = parked_thread {
+            set_event(thread_ptr as *const c_void);
+        }
+    }
+
+    fn detach(&self) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.tracer = None;
+            inner.options = PtraceOptions::empty();
+        }
+        self.resume(false);
+    }
+
+    /// Wake up the tracee in a syscall stop so that it can exit, which is the
+    /// case when the tracee is killed or forced to exit.
+    pub fn release_stop(&self) {
+        let trace_syscalls = self.trace_syscalls.load(Ordering::Relaxed);
+        self.resume(trace_syscalls);
+    }
+}
+
+impl Debug for PtraceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PtraceStatus")
+            .field("trace_syscalls", &self.trace_syscalls)
+            .field("inner", &*self.inner.lock().unwrap())
+            .finish()
+    }
+}
+
+/// Stop the current thread at the entry of a syscall if it is traced.
+pub fn ptrace_syscall_enter(user_context: &CpuContext) {
+    if !is_tracing_syscalls() {
+        return;
+    }
+    // Like Linux, rax is -ENOSYS at the entry and the syscall number is
+    // reported as orig_rax.
+    let mut context = *user_context;
+    context.rax = -(ENOSYS as i64) as u64;
+    syscall_stop(&context, user_context.rax);
+}
+
+/// Stop the current thread at the exit of a syscall if it is traced.
+pub fn ptrace_syscall_exit(user_context: &CpuContext, syscall_num: u32) {
+    if !is_tracing_syscalls() {
+        return;
+    }
+    syscall_stop(user_context, syscall_num as u64);
+}
+
+fn is_tracing_syscalls() -> bool {
+    let thread = current!();
+    let process = thread.process();
+    process
+        .ptrace_status()
+        .trace_syscalls
+        .load(Ordering::Relaxed)
+        && thread.tid() == process.pid()
+        // The thread may have exited in the syscall, e.g., exit and execve
+        && thread.status() != ThreadStatus::Exited
+}
+
+fn syscall_stop(context: &CpuContext, orig_rax: u64) {
+    let thread = current!();
+    let process = thread.process();
+    let ptrace_status = process.ptrace_status();
+    let thread_ptr = thread.raw_ptr();
+
+    let code = {
+        let mut inner = ptrace_status.inner.lock().unwrap();
+        if inner.tracer.is_none() || process.is_forced_to_exit() {
+            return;
+        }
+        let fs_base = thread.task().user_fs() as u64;
+        inner.stopped_regs = Some(user_regs_t::new(context, orig_rax, fs_base));
+        inner.parked_thread = Some(thread_ptr);
+        if inner.options.contains(PtraceOptions::PTRACE_O_TRACESYSGOOD) {
+            SIGTRAP.as_u8() | 0x80
+        } else {
+            SIGTRAP.as_u8()
+        }
+    };
+    notify_parent_status_change(process, WaitStatus::Traced(code));
+
+    loop {
+        if !ptrace_status.is_in_syscall_stop() || process.is_forced_to_exit() {
+            break;
+        }
+        // Spurious wakeups are fine since the stop state is checked again
+        wait_event(thread_ptr as *const c_void);
+    }
+}
+
+/// Release the tracees of an exiting tracer.
+pub(super) fn release_tracees(tracer_pid: pid_t, children: &[ProcessRef]) {
+    for child in children {
+        if child.ptrace_status().is_traced_by(tracer_pid) {
+            child.ptrace_status().detach();
+        }
+    }
+}
+
+/// Check whether the tracer may access the tracee, like `__ptrace_may_access`
+/// of Linux.
+///
+/// Unless the tracer is root, the real, effective and saved IDs of the tracee
+/// must all be the real IDs of the tracer. A non-dumpable tracee can never be
+/// accessed, even by root.
+pub fn check_ptrace_access(tracer: &ProcessRef, tracee: &ProcessRef) -> Result<()> {
+    if Arc::ptr_eq(tracer, tracee) {
+        return Ok(());
+    }
+
+    let tracer_cred = tracer.credentials().clone();
+    let tracee_cred = tracee.credentials().clone();
+    let is_same_user = [tracee_cred.ruid(), tracee_cred.euid(), tracee_cred.suid()]
+        .iter()
+        .all(|&uid| uid == tracer_cred.ruid())
+        && [tracee_cred.rgid(), tracee_cred.egid(), tracee_cred.sgid()]
+            .iter()
+            .all(|&gid| gid == tracer_cred.rgid());
+    if !is_same_user && !tracer_cred.is_privileged() {
+        return_errno!(EPERM, "the credentials of the tracee do not match");
+    }
+    if !tracee.prctl_attrs().dumpable {
+        return_errno!(EPERM, "the tracee is not dumpable");
+    }
+    Ok(())
+}
+
+pub fn do_ptrace_attach(pid: pid_t, options: PtraceOptions, seize: bool) -> Result<()> {
+    debug!(
+        "do_ptrace_attach: pid: {}, options: {:?}, seize: {}",
+        pid, options, seize
+    );
+    if !sgx_allow_debug() {
+        return_errno!(EPERM, "ptrace is only supported in debug enclaves");
+    }
+    let current = current!();
+    let tracer = current.process();
+    let tracee = table::get_process(pid)?;
+    if tracee.pid() == tracer.pid() {
+        return_errno!(EPERM, "a process cannot trace itself");
+    }
+    if tracee.parent().pid() != tracer.pid() {
+        return_errno!(EPERM, "only the children can be traced");
+    }
+    check_ptrace_access(&tracer, &tracee)?;
+    if !tracee.status().is_alive() {
+        return_errno!(ESRCH, "the process has exited");
+    }
+    tracee.ptrace_status().attach(tracer.pid(), options)?;
+
+    // Unlike PTRACE_SEIZE, PTRACE_ATTACH stops the tracee with SIGSTOP
+    if !seize {
+        send_signal(&tracee, SIGSTOP);
+    }
+    Ok(())
+}
+
+pub fn do_ptrace_setoptions(pid: pid_t, options: PtraceOptions) -> Result<()> {
+    let tracee = get_stopped_tracee(pid)?;
+    tracee.ptrace_status().set_options(options);
+    Ok(())
+}
+
+/// Resume a stopped tracee, delivering `signum` to it if any.
+pub fn do_ptrace_resume(pid: pid_t, trace_syscalls: bool, signum: Option<SigNum>) -> Result<()> {
+    debug!(
+        "do_ptrace_resume: pid: {}, trace_syscalls: {}, signum: {:?}",
+        pid, trace_syscalls, signum
+    );
+    let tracee = get_stopped_tracee(pid)?;
+    tracee.ptrace_status().resume(trace_syscalls);
+    resume_tracee(&tracee, signum);
+    Ok(())
+}
+
+pub fn do_ptrace_detach(pid: pid_t, signum: Option<SigNum>) -> Result<()> {
+    debug!("do_ptrace_detach: pid: {}, signum: {:?}", pid, signum);
+    let tracee = get_stopped_tracee(pid)?;
+    tracee.ptrace_status().detach();
+    resume_tracee(&tracee, signum);
+    Ok(())
+}
+
+pub fn do_ptrace_getregs(pid: pid_t) -> Result<user_regs_t> {
+    let tracee = get_stopped_tracee(pid)?;
+    tracee
+        .ptrace_status()
+        .stopped_regs()
+        .ok_or_else(|| errno!(EIO, "the registers are only available at syscall stops"))
+}
+
+pub fn do_ptrace_peekdata(pid: pid_t, addr: usize) -> Result<usize> {
+    let tracee = get_stopped_tracee(pid)?;
+    access_tracee_memory(&tracee, addr, VMPerms::READ, || unsafe {
+        (addr as *const usize).read_unaligned()
+    })
+}
+
+/// Write a word to the memory of the tracee, which is allowed even if the
+/// memory is read-only, e.g., to set a breakpoint in the code.
+pub fn do_ptrace_pokedata(pid: pid_t, addr: usize, word: usize) -> Result<()> {
+    let tracee = get_stopped_tracee(pid)?;
+    access_tracee_memory(&tracee, addr, VMPerms::WRITE, || unsafe {
+        (addr as *mut usize).write_unaligned(word)
+    })
+}
+
+/// Get a tracee of the current process, which must be stopped.
+fn get_stopped_tracee(pid: pid_t) -> Result<ProcessRef> {
+    let tracee = table::get_process(pid)?;
+    let ptrace_status = tracee.ptrace_status();
+    if !ptrace_status.is_traced_by(current!().process().pid()) {
+        return_errno!(ESRCH, "the process is not traced by the current process");
+    }
+    if !ptrace_status.is_in_syscall_stop() && !tracee.is_stopped() {
+        return_errno!(ESRCH, "the tracee is not stopped");
+    }
+    Ok(tracee)
+}
+
+/// Access a word at `addr` mapped by the tracee with `perms` forced. The
+/// memory mapping is locked during the access to avoid it being changed after
+/// being checked.
+fn access_tracee_memory<F, R>(tracee: &ProcessRef, addr: usize, perms: VMPerms, f: F) -> Result<R>
+where
+    F: FnOnce() -> R,
+{
+    let main_thread = tracee
+        .main_thread()
+        .ok_or_else(|| errno!(ESRCH, "the tracee has exited"))?;
+    let end = addr
+        .checked_add(std::mem::size_of::<usize>())
+        .ok_or_else(|| errno!(EIO, "invalid address"))?;
+    let range = VMRange::new(align_down(addr, PAGE_SIZE), align_up(end, PAGE_SIZE))?;
+    main_thread
+        .vm()
+        .access_force(tracee.pid(), &range, perms, f)
+        .map_err(|_| errno!(EIO, "the memory of the tracee is not mapped"))
+}
+
+/// Continue a tracee stopped by a stop signal and deliver `signum` to it.
+fn resume_tracee(tracee: &ProcessRef, signum: Option<SigNum>) {
+    // The stop status is reported to the tracer, so don't report it again
+    {
+        let mut tracee_inner = tracee.inner();
+        if let Some(WaitStatus::Traced(_)) | Some(WaitStatus::Stopped(_)) =
+            tracee_inner.unwaited_status()
+        {
+            tracee_inner.set_unwaited_status(None);
+        }
+    }
+    continue_process(tracee, false);
+    if let Some(signum) = signum {
+        send_signal(tracee, signum);
+    }
+}
+
+fn send_signal(process: &ProcessRef, signum: SigNum) {
+    prepare_signal(process, signum);
+    let signal = Box::new(KernelSignal::new(signum));
+    process.sig_queues().write().unwrap().enqueue(signal);
+}
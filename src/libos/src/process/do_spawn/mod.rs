@@ -4,6 +4,7 @@ use std::path::Path;
 use self::aux_vec::{AuxKey, AuxVec};
 use self::exec_loader::{load_exec_file_hdr_to_vec, load_file_hdr_to_vec};
 use super::elf_file::{ElfFile, ElfHeader, ProgramHeaderExt};
+use super::prctl::PrctlAttrs;
 use super::process::ProcessBuilder;
 use super::spawn_attribute::SpawnAttr;
 use super::task::Task;
@@ -293,6 +294,32 @@ fn new_process_common(
 
         let mut process_builder = ProcessBuilder::new();
 
+        // The process keeps its pid after execve, and so do the pidfds referring to it
+        let is_execve = reuse_tid
+            .as_ref()
+            .map_or(false, |tid| tid.as_u32() == process_ref.pid());
+        if is_execve {
+            process_builder = process_builder.exit_notifier(process_ref.exit_notifier().clone());
+        }
+
+        // The prctl attributes are kept after execve except that the process
+        // becomes dumpable again, while a child only inherits no_new_privs.
+        let prctl_attrs = {
+            let attrs = process_ref.prctl_attrs();
+            if is_execve {
+                PrctlAttrs {
+                    dumpable: true,
+                    ..attrs
+                }
+            } else {
+                PrctlAttrs {
+                    no_new_privs: attrs.no_new_privs,
+                    ..Default::default()
+                }
+            }
+        };
+        process_builder = process_builder.prctl_attrs(prctl_attrs);
+
         // Use specified tid if any
         if let Some(reuse_tid) = reuse_tid {
             process_builder = process_builder.tid(reuse_tid);
@@ -309,6 +336,7 @@ fn new_process_common(
             .vm(vm_ref)
             .exec_path(&elf_path)
             .umask(parent.umask())
+            .credentials(current_ref.process().credentials().clone())
             .parent(parent)
             .task(task)
             .sched(sched_ref)
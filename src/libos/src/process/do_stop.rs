@@ -0,0 +1,142 @@
+//! Stop and continue processes by job control signals.
+//!
+//! A process is stopped when one of its threads handles a stop signal (e.g.,
+//! SIGSTOP) with the default action. The other threads are interrupted and
+//! park themselves at their next syscall or interrupt boundary. All the
+//! parked threads are waken up when SIGCONT or SIGKILL is sent to the process,
+//! or when the process is forced to exit.
+
+use super::do_wait4::{notify_parent_status_change, WaitStatus};
+use super::untrusted_event::{set_event, wait_event};
+use super::{ProcessRef, ThreadRef};
+use crate::interrupt::broadcast_interrupts;
+use crate::prelude::*;
+use crate::signal::SigNum;
+
+/// The job control stop state of a process.
+pub struct StopStatus {
+    inner: SgxMutex<StopStatusInner>,
+}
+
+#[derive(Debug, Default)]
+struct StopStatusInner {
+    stopped: bool,
+    // The tids and raw pointers of the threads which are parked
+    parked_threads: Vec<(pid_t, usize)>,
+}
+
+impl StopStatus {
+    pub fn new() -> Self {
+        Self {
+            inner: SgxMutex::new(StopStatusInner::default()),
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.inner.lock().unwrap().stopped
+    }
+
+    /// Whether the thread should be parked but has not been parked yet.
+    pub fn is_pending_for(&self, thread: &ThreadRef) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.stopped
+            && !inner
+                .parked_threads
+                .iter()
+                .any(|(tid, _)| *tid == thread.tid())
+    }
+
+    /// Mark the process as stopped, return false if it has been stopped.
+    fn stop(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.stopped {
+            return false;
+        }
+        inner.stopped = true;
+        true
+    }
+
+    /// Mark the process as running and wake up all the parked threads.
+    ///
+    /// Return false if the process is not stopped.
+    pub fn resume(&self) -> bool {
+        let parked_threads = {
+            let mut inner = self.inner.lock().unwrap();
+            if !inner.stopped {
+                return false;
+            }
+            inner.stopped = false;
+            std::mem::take(&mut inner.parked_threads)
+        };
+        for (_, thread_ptr) in parked_threads {
+            set_event(thread_ptr as *const c_void);
+        }
+        true
+    }
+}
+
+impl Debug for StopStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("StopStatus")
+            .field("inner", &*self.inner.lock().unwrap())
+            .finish()
+    }
+}
+
+/// Stop the current process by the stop signal `signum`.
+///
+/// The parent is notified and the current thread is parked until the
+/// process is continued.
+pub fn stop_current_process(signum: SigNum) {
+    let thread = current!();
+    let process = thread.process();
+    if process.stop_status().stop() {
+        debug!("process {:?} is stopped by {:?}", process.pid(), signum);
+        notify_parent_status_change(process, WaitStatus::Stopped(signum));
+        if process.threads().len() > 1 {
+            // Don't wait. The other threads are parked once interrupted.
+            let _ = broadcast_interrupts();
+        }
+    }
+    handle_stop();
+}
+
+/// Continue a stopped process.
+///
+/// If `should_notify_parent` is set, the parent is notified of the continued
+/// status, which is the case for SIGCONT but not for SIGKILL.
+pub fn continue_process(process: &ProcessRef, should_notify_parent: bool) {
+    if process.stop_status().resume() {
+        debug!("process {:?} is continued", process.pid());
+        if should_notify_parent {
+            notify_parent_status_change(process, WaitStatus::Continued);
+        }
+    }
+}
+
+/// Park the current thread while its process is stopped.
+///
+/// This must be called at the syscall or interrupt boundary, where the thread
+/// holds no locks.
+pub fn handle_stop() {
+    let thread = current!();
+    let process = thread.process();
+    let thread_ptr = thread.raw_ptr();
+    loop {
+        {
+            let mut inner = process.stop_status().inner.lock().unwrap();
+            if !inner.stopped || process.is_forced_to_exit() {
+                break;
+            }
+            if !inner
+                .parked_threads
+                .iter()
+                .any(|(tid, _)| *tid == thread.tid())
+            {
+                inner.parked_threads.push((thread.tid(), thread_ptr));
+            }
+        }
+        // Spurious wakeups are fine since the stop state is checked again
+        wait_event(thread_ptr as *const c_void);
+    }
+}
@@ -1,6 +1,6 @@
 use super::untrusted_event::{set_event, wait_event};
 use super::{ProcessFilter, ProcessRef, ProcessStatus, TermStatus, ThreadId, ThreadRef};
-use crate::fs::FileTable;
+use crate::fs::{FileTable, FsView};
 use crate::interrupt::broadcast_interrupts;
 use crate::prelude::*;
 use crate::syscall::CpuContext;
@@ -34,6 +34,9 @@ lazy_static! {
     // Store all the parents's file tables who call vfork. It will be recovered when the child exits or has its own task.
     // K: parent pid, V: parent file table
     static ref VFORK_PARENT_FILE_TABLES: SgxMutex<HashMap<pid_t, FileTable>> = SgxMutex::new(HashMap::new());
+    // Store all the parents's FS views who call vfork, so that chdir or chroot in the child doesn't affect the parent.
+    // K: parent pid, V: parent FS view
+    static ref VFORK_PARENT_FS_VIEWS: SgxMutex<HashMap<pid_t, FsView>> = SgxMutex::new(HashMap::new());
     // Store all the child process's exit status which are created with vfork and directly exit without calling execve. Because
     // these children process are only allocated with a pid, they are not managed by the usual way including exit and wait. Use
     // this special structure to record these children.
@@ -85,6 +88,9 @@ pub fn do_vfork(mut context: *mut CpuContext) -> Result<isize> {
     // Save parent's file table.
     vfork_save_file_table(&current)?;
 
+    // Save parent's FS view.
+    vfork_save_fs_view(&current);
+
     // This is the first time return and will return as child.
     // The second time return will return as parent in vfork_return_to_parent.
     info!("vfork child pid = {:?}", child_pid);
@@ -116,6 +122,16 @@ fn vfork_save_file_table(current: &ThreadRef) -> Result<()> {
     Ok(())
 }
 
+fn vfork_save_fs_view(current: &ThreadRef) {
+    let parent_pid = current.process().pid();
+    let parent_fs_view = current.fs().read().unwrap().clone();
+    let ret = VFORK_PARENT_FS_VIEWS
+        .lock()
+        .unwrap()
+        .insert(parent_pid, parent_fs_view);
+    debug_assert!(ret.is_none());
+}
+
 fn vfork_stop_all_child_thread(current: &ThreadRef) {
     // stop all other child threads
     loop {
@@ -201,6 +217,12 @@ fn restore_parent_process(mut context: *mut CpuContext, current_ref: &ThreadRef)
 
     let mut current_file_table = current_ref.files().lock();
     *current_file_table = parent_file_table;
+    drop(current_file_table);
+
+    // Restore parent FS view
+    if let Some(parent_fs_view) = VFORK_PARENT_FS_VIEWS.lock().unwrap().remove(&current_pid) {
+        *current_ref.fs().write().unwrap() = parent_fs_view;
+    }
 
     // Get child pid and restore CpuContext
     let mut child_pid = 0;
@@ -275,24 +297,32 @@ pub fn handle_force_stop() {
 }
 
 // Wait4 unwaited child which are created with vfork and directly exit without calling execve.
+// If `reap` is not set, the child is left in the unwaited state.
 pub fn wait4_exit_child_created_with_vfork(
     parent_pid: pid_t,
     child_filter: &ProcessFilter,
-) -> Option<(pid_t, i32)> {
+    reap: bool,
+) -> Option<(pid_t, TermStatus)> {
     let mut children_status = EXIT_CHILDREN_STATUS.lock().unwrap();
     if let Some(children) = children_status.get_mut(&parent_pid) {
         let unwaited_child_idx = children.iter().position(|child| match child_filter {
             ProcessFilter::WithAnyPid => true,
             ProcessFilter::WithPid(pid) => pid == child.pid(),
             ProcessFilter::WithPgid(pgid) => todo!(), // This case should be rare.
+            // The exited vfork children have no process structures any more
+            ProcessFilter::WithProcess(pid, _) => pid == child.pid(),
         });
 
         if let Some(child_idx) = unwaited_child_idx {
+            if !reap {
+                let child = &children[child_idx];
+                return Some((*child.pid(), *child.status()));
+            }
             let child = children.remove(child_idx);
             if children.is_empty() {
                 children_status.remove(&parent_pid);
             }
-            return Some((*child.pid(), child.status().as_u32() as i32));
+            return Some((*child.pid(), *child.status()));
         }
     }
 
@@ -1,9 +1,14 @@
 use super::do_vfork::wait4_exit_child_created_with_vfork;
 use super::pgrp::clean_pgrp_when_exit;
 use super::process::{ProcessFilter, ProcessInner};
+use super::rusage::account_reaped_child;
 use super::wait::Waiter;
-use super::{table, ProcessRef, ProcessStatus};
+use super::{table, ProcessRef, ProcessStatus, TermStatus};
 use crate::prelude::*;
+use crate::signal::{
+    KernelSignal, SigNum, CLD_CONTINUED, CLD_EXITED, CLD_KILLED, CLD_STOPPED, CLD_TRAPPED, SIGCHLD,
+    SIGCONT,
+};
 
 // Children process exits without parent calls wait4 should be reaped by Idle process in the end.
 // Without this, there might be memory leakage when exit.
@@ -35,72 +40,152 @@ pub fn idle_reap_zombie_children() -> Result<()> {
     return Ok(());
 }
 
-pub fn do_wait4(child_filter: &ProcessFilter, options: WaitOptions) -> Result<(pid_t, i32)> {
-    // Lock the process early to ensure that we do not miss any changes in
-    // children processes
+pub fn do_wait4(child_filter: &ProcessFilter, options: WaitOptions) -> Result<Option<WaitInfo>> {
     let thread = current!();
     let process = thread.process();
-    // Lock order: always lock parent then child to avoid deadlock
-    let mut process_inner = process.inner();
-
-    let unwaited_children = process_inner
-        .children()
-        .unwrap()
-        .iter()
-        .filter(|child| match child_filter {
-            ProcessFilter::WithAnyPid => true,
-            ProcessFilter::WithPid(required_pid) => child.pid() == *required_pid,
-            ProcessFilter::WithPgid(required_pgid) => child.pgid() == *required_pgid,
-        })
-        .collect::<Vec<&ProcessRef>>();
-
-    // Return immediately if a child that we wait for has already exited
-    let zombie_child = unwaited_children
-        .iter()
-        .find(|child| child.status() == ProcessStatus::Zombie);
-    if let Some(zombie_child) = zombie_child {
-        let zombie_pid = zombie_child.pid();
-        let exit_status = free_zombie_child(process_inner, zombie_pid);
-        return Ok((zombie_pid, exit_status));
-    }
+    let reap = !options.contains(WaitOptions::WNOWAIT);
+
+    loop {
+        // Lock the process early to ensure that we do not miss any changes in
+        // children processes
+        // Lock order: always lock parent then child to avoid deadlock
+        let mut process_inner = process.inner();
 
-    // Check again for vfork-and-exit child process which doesn't have a real structure of a process
-    if let Some(child_status) = wait4_exit_child_created_with_vfork(process.pid(), child_filter) {
-        return Ok(child_status);
-    } else if unwaited_children.len() == 0 {
-        // No unwaited children or vforked children, return immediately
-        return_errno!(ECHILD, "Cannot find any unwaited children");
+        let unwaited_children = process_inner
+            .children()
+            .unwrap()
+            .iter()
+            .filter(|child| child_filter.matches(child))
+            .cloned()
+            .collect::<Vec<ProcessRef>>();
+
+        // Return immediately if a child that we wait for has changed its status
+        for child in unwaited_children.iter() {
+            let mut child_inner = child.inner();
+            let status = match child_inner.status() {
+                ProcessStatus::Zombie if options.contains(WaitOptions::WEXITED) => {
+                    WaitStatus::Exited(child_inner.term_status().unwrap())
+                }
+                ProcessStatus::Zombie => continue,
+                _ => match child_inner.unwaited_status() {
+                    // The stops of a tracee are always reported to its tracer
+                    Some(status @ WaitStatus::Traced(_)) => status,
+                    Some(status @ WaitStatus::Stopped(_))
+                        if options.contains(WaitOptions::WSTOPPED)
+                            || child.ptrace_status().tracer() == Some(process.pid()) =>
+                    {
+                        status
+                    }
+                    Some(status @ WaitStatus::Continued)
+                        if options.contains(WaitOptions::WCONTINUED) =>
+                    {
+                        status
+                    }
+                    _ => continue,
+                },
+            };
+            let info = WaitInfo {
+                pid: child.pid(),
+                uid: child.credentials().ruid(),
+                status,
+            };
+
+            if reap {
+                if let WaitStatus::Exited(_) = status {
+                    drop(child_inner);
+                    account_reaped_child(process, child);
+                    free_zombie_child(process_inner, info.pid);
+                } else {
+                    child_inner.set_unwaited_status(None);
+                }
+            }
+            return Ok(Some(info));
+        }
+
+        // Check again for vfork-and-exit child process which doesn't have a real structure of a process
+        let vforked_child = if options.contains(WaitOptions::WEXITED) {
+            wait4_exit_child_created_with_vfork(process.pid(), child_filter, reap)
+        } else {
+            None
+        };
+        if let Some((child_pid, term_status)) = vforked_child {
+            // The vforked child shares the credentials with the parent
+            return Ok(Some(WaitInfo {
+                pid: child_pid,
+                uid: process.credentials().ruid(),
+                status: WaitStatus::Exited(term_status),
+            }));
+        } else if unwaited_children.len() == 0 {
+            // No unwaited children or vforked children, return immediately
+            return_errno!(ECHILD, "Cannot find any unwaited children");
+        }
+
+        // If the WNOHANG bit is set in OPTIONS, and no child has changed
+        // its status, return None.
+        if options.contains(WaitOptions::WNOHANG) {
+            return Ok(None);
+        }
+
+        let mut waiter = Waiter::new(child_filter);
+        process_inner
+            .waiting_children_mut()
+            .unwrap()
+            .add_waiter(&waiter);
+        // After adding the waiter, we can safely release the lock on the process inner
+        // without risking missing events from the process's children.
+        drop(process_inner);
+        // Wait until a child has interesting events, then check the children again
+        if waiter.sleep_until_woken_with_result().is_none() {
+            // The wait is interrupted
+            return_errno!(EINTR, "wait is interrupted and not get any children");
+        }
     }
+}
 
-    // TODO: Support these options
-    if !options.supported() {
-        warn!("Unsupported options contained. wait options: {:?}", options);
+/// Record a stopped or continued status of a process and notify its parent.
+///
+/// The parent receives SIGCHLD unless SA_NOCLDSTOP is set, and the waiters of
+/// the parent are woken up to check the new status.
+pub fn notify_parent_status_change(process: &ProcessRef, status: WaitStatus) {
+    debug_assert!(!matches!(status, WaitStatus::Exited(_)));
+
+    // Lock order: always lock parent then child to avoid deadlock
+    let (parent, mut parent_inner) = loop {
+        let parent = process.parent();
+        let parent_inner = parent.inner();
+        // The parent may be changed after `parent()` but before `parent().inner()`
+        if parent.pid() != process.parent().pid() {
+            continue;
+        }
+        break (parent, parent_inner);
+    };
+
+    {
+        let mut process_inner = process.inner();
+        if !process_inner.status().is_alive() {
+            return;
+        }
+        process_inner.set_unwaited_status(Some(status));
     }
 
-    // If the WNOHANG bit is set in OPTIONS, and that child
-    // is not already dead, return (pid_t) 0.  If successful,
-    // return PID and store the dead child's status in STAT_LOC.
-    if options.contains(WaitOptions::WNOHANG) {
-        return Ok((0, 0));
+    // Nobody waits for the children of the idle process
+    if parent.pid() == 0 {
+        return;
     }
 
-    let mut waiter = Waiter::new(child_filter);
-    process_inner
-        .waiting_children_mut()
-        .unwrap()
-        .add_waiter(&waiter);
-    // After adding the waiter, we can safely release the lock on the process inner
-    // without risking missing events from the process's children.
-    drop(process_inner);
-    // Wait until a child has interesting events
-    if let Some(zombie_pid) = waiter.sleep_until_woken_with_result() {
-        let mut process_inner = process.inner();
-        let exit_status = free_zombie_child(process_inner, zombie_pid);
-        Ok((zombie_pid, exit_status))
-    } else {
-        // The wait is interrupted
-        return_errno!(EINTR, "wait is interrupted and not get any children");
+    if !parent.sig_dispositions().read().unwrap().is_nocldstop() {
+        let signal = Box::new(KernelSignal::new(SIGCHLD));
+        parent.sig_queues().write().unwrap().enqueue(signal);
     }
+
+    let pid = process.pid();
+    let waiting_children = parent_inner.waiting_children_mut().unwrap();
+    waiting_children.del_and_wake_one_waiter(|waiter_data| -> Option<pid_t> {
+        if !waiter_data.matches(process) {
+            return None;
+        }
+        Some(pid)
+    });
 }
 
 fn free_zombie_child(mut parent_inner: SgxMutexGuard<ProcessInner>, zombie_pid: pid_t) -> i32 {
@@ -119,24 +204,58 @@ fn free_zombie_child(mut parent_inner: SgxMutexGuard<ProcessInner>, zombie_pid:
 }
 
 // Based on waitflags.h
-// WNOWAIT is not listed here which can only be used in "waitid" syscall.
 bitflags! {
     pub struct WaitOptions: u32 {
         const WNOHANG = 0x1;
-        //Note: Below flags are not supported yet
         const WSTOPPED = 0x2; // Same as WUNTRACED
         const WEXITED = 0x4;
         const WCONTINUED = 0x8;
+        // Can only be used in "waitid" syscall
+        const WNOWAIT = 0x1000000;
     }
 }
 
-impl WaitOptions {
-    fn supported(&self) -> bool {
-        let unsupported_flags = WaitOptions::all() - WaitOptions::WNOHANG;
-        !self.intersects(unsupported_flags)
+/// The status of a child reported by wait.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WaitStatus {
+    Exited(TermStatus),
+    Stopped(SigNum),
+    /// Stopped by ptrace with the signal number reported to the tracer
+    Traced(u8),
+    Continued,
+}
+
+impl WaitStatus {
+    /// Return as a 32-bit integer encoded as specified in wait(2) man page.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            WaitStatus::Exited(term_status) => term_status.as_u32(),
+            WaitStatus::Stopped(signum) => ((signum.as_u8() as u32) << 8) | WAIT_STATUS_STOPPED,
+            WaitStatus::Traced(code) => ((code as u32) << 8) | WAIT_STATUS_STOPPED,
+            WaitStatus::Continued => WAIT_STATUS_CONTINUED,
+        }
+    }
+
+    /// Return the `si_code` and `si_status` of the siginfo_t filled by waitid.
+    pub fn as_siginfo_code_and_status(&self) -> (i32, i32) {
+        match *self {
+            WaitStatus::Exited(TermStatus::Exited(status)) => (CLD_EXITED, status as i32),
+            WaitStatus::Exited(TermStatus::Killed(signum)) => (CLD_KILLED, signum.as_u8() as i32),
+            WaitStatus::Stopped(signum) => (CLD_STOPPED, signum.as_u8() as i32),
+            WaitStatus::Traced(code) => (CLD_TRAPPED, code as i32),
+            WaitStatus::Continued => (CLD_CONTINUED, SIGCONT.as_u8() as i32),
+        }
     }
 }
 
+/// The information of a child whose status has changed.
+#[derive(Debug, Clone, Copy)]
+pub struct WaitInfo {
+    pub pid: pid_t,
+    pub uid: uid_t,
+    pub status: WaitStatus,
+}
+
 // Based on waitstatus.h
-const WAIT_STATUS_STOPPED: i32 = 0x7f;
-const WAIT_STATUS_CONTINUED: i32 = 0xffff;
+const WAIT_STATUS_STOPPED: u32 = 0x7f;
+const WAIT_STATUS_CONTINUED: u32 = 0xffff;
@@ -21,13 +21,25 @@ use self::process::{ProcessBuilder, ProcessInner};
 use self::thread::{ThreadBuilder, ThreadId, ThreadInner};
 use self::wait::{WaitQueue, Waiter};
 
+pub use self::credentials::Credentials;
 pub use self::do_exit::handle_force_exit;
 pub use self::do_futex::{futex_wait, futex_wake};
+pub use self::do_ptrace::{check_ptrace_access, ptrace_syscall_enter, ptrace_syscall_exit};
 pub use self::do_robust_list::RobustListHead;
 pub use self::do_spawn::{do_spawn, do_spawn_without_exec};
+pub use self::do_stop::{continue_process, handle_stop, stop_current_process};
 pub use self::do_vfork::{do_vfork, handle_force_stop};
-pub use self::do_wait4::idle_reap_zombie_children;
+pub use self::do_wait4::{idle_reap_zombie_children, notify_parent_status_change, WaitStatus};
+pub use self::pidfd::{AsPidFile, PidFile};
 pub use self::process::{Process, ProcessFilter, ProcessStatus, IDLE};
+pub use self::rusage::{
+    account_sleep, children_usage, duration_to_ticks, process_usage, rusage_t, thread_usage, tms_t,
+    ResourceUsage, ThreadUsage,
+};
+pub use self::session::{
+    tty_get_foreground_pgrp, tty_get_sid, tty_release_ctty, tty_set_ctty, tty_set_foreground_pgrp,
+    Session, SessionRef,
+};
 pub use self::spawn_attribute::posix_spawnattr_t;
 pub use self::spawn_attribute::SpawnAttr;
 pub use self::syscalls::*;
@@ -35,20 +47,26 @@ pub use self::task::Task;
 pub use self::term_status::{ForcedExitStatus, TermStatus};
 pub use self::thread::{Thread, ThreadStatus, IO_BUF_SIZE};
 
+mod credentials;
 mod do_arch_prctl;
 mod do_clone;
 mod do_exec;
 mod do_exit;
 mod do_futex;
 mod do_getpid;
+mod do_ptrace;
 mod do_robust_list;
 mod do_set_tid_address;
 mod do_spawn;
+mod do_stop;
 mod do_vfork;
 mod do_wait4;
 mod pgrp;
+mod pidfd;
 mod prctl;
 mod process;
+mod rusage;
+mod session;
 mod spawn_attribute;
 mod syscalls;
 mod term_status;
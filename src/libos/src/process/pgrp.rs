@@ -1,3 +1,4 @@
+use super::session::{Session, SessionRef};
 use super::*;
 use crate::process;
 
@@ -6,6 +7,7 @@ struct PgrpInner {
     pgid: pid_t,
     process_group: HashMap<pid_t, ProcessRef>, // process id, process ref
     leader_process: Option<ProcessRef>,
+    session: SessionRef,
 }
 
 #[derive(Debug)]
@@ -20,6 +22,7 @@ impl ProcessGrp {
                 pgid: 0,
                 process_group: HashMap::new(),
                 leader_process: None,
+                session: Arc::new(Session::default()),
             }),
         }
     }
@@ -32,6 +35,10 @@ impl ProcessGrp {
         self.inner.read().unwrap().process_group.len()
     }
 
+    pub fn session(&self) -> SessionRef {
+        self.inner.read().unwrap().session.clone()
+    }
+
     pub fn set_pgid(&self, pgid: pid_t) {
         self.inner.write().unwrap().pgid = pgid;
     }
@@ -66,9 +73,16 @@ impl ProcessGrp {
             .collect()
     }
 
-    // Create a new process group
+    // Create a new process group in the session of the process
     pub fn new(process: ProcessRef) -> Result<Self> {
+        let session = process.session();
+        Self::new_in_session(process, session)
+    }
+
+    // Create a new process group in the given session
+    pub fn new_in_session(process: ProcessRef, session: SessionRef) -> Result<Self> {
         let pgrp = Self::default();
+        pgrp.inner.write().unwrap().session = session;
         let pid = process.pid();
         pgrp.set_pgid(pid);
         pgrp.set_leader_process(process.clone());
@@ -151,8 +165,17 @@ pub fn do_setpgid(pid: pid_t, pgid: pid_t, is_executing: bool) -> Result<isize>
         return_errno!(EACCES, "can't setpgid to a running child process");
     }
 
+    // The process group of a session leader can't be changed
+    let session = process.session();
+    if session.is_leader(&process) {
+        return_errno!(EPERM, "can't setpgid to a session leader");
+    }
+
     if let Ok(pgrp) = table::get_pgrp(pgid) {
-        // pgrp exists
+        // pgrp exists, and it must be in the same session
+        if !Arc::ptr_eq(&pgrp.session(), &session) {
+            return_errno!(EPERM, "process group is in a different session");
+        }
         let pgrp_ref = process.pgrp();
         pgrp_ref.remove_process(&process);
         process.update_pgrp(pgrp.clone());
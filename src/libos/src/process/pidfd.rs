@@ -0,0 +1,149 @@
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+use crate::fs::{AccessMode, IoEvents, IoNotifier, StatusFlags};
+use crate::signal::{do_kill_process, SigNum};
+
+/// The exit state of a process that is observed by pidfds.
+///
+/// A process keeps its pid across execve, so the exit notifier is shared by
+/// the old and the new `Process` structs.
+pub struct ExitNotifier {
+    exited: AtomicBool,
+    notifier: IoNotifier,
+}
+
+impl ExitNotifier {
+    pub fn new() -> Self {
+        Self {
+            exited: AtomicBool::new(false),
+            notifier: IoNotifier::new(),
+        }
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::Acquire)
+    }
+
+    /// Mark the process as exited and wake up the pollers of its pidfds.
+    pub fn notify_exit(&self) {
+        self.exited.store(true, Ordering::Release);
+        self.notifier.broadcast(&IoEvents::IN);
+    }
+}
+
+impl Debug for ExitNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ExitNotifier")
+            .field("exited", &self.has_exited())
+            .finish()
+    }
+}
+
+/// A file that refers to a process, i.e., a pidfd.
+///
+/// The pidfd becomes readable once the process exits.
+#[derive(Debug)]
+pub struct PidFile {
+    pid: pid_t,
+    exit_notifier: Arc<ExitNotifier>,
+    status_flags: RwLock<StatusFlags>,
+}
+
+impl PidFile {
+    pub fn new(process: &ProcessRef, status_flags: StatusFlags) -> Self {
+        Self {
+            pid: process.pid(),
+            exit_notifier: process.exit_notifier().clone(),
+            status_flags: RwLock::new(status_flags),
+        }
+    }
+
+    pub fn pid(&self) -> pid_t {
+        self.pid
+    }
+
+    /// Get the process referred by the pidfd.
+    ///
+    /// Fail if the process has been reaped, even if its pid has been reused.
+    pub fn process(&self) -> Result<ProcessRef> {
+        let process = table::get_process(self.pid)
+            .map_err(|_| errno!(ESRCH, "the process has been reaped"))?;
+        if !Arc::ptr_eq(process.exit_notifier(), &self.exit_notifier) {
+            return_errno!(ESRCH, "the process has been reaped");
+        }
+        Ok(process)
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.status_flags
+            .read()
+            .unwrap()
+            .contains(StatusFlags::O_NONBLOCK)
+    }
+}
+
+impl File for PidFile {
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDWR)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        Ok(*self.status_flags.read().unwrap())
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        *self.status_flags.write().unwrap() = new_status_flags;
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        if self.exit_notifier.has_exited() {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(&self.exit_notifier.notifier)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub trait AsPidFile {
+    fn as_pidfd(&self) -> Result<&PidFile>;
+}
+
+impl AsPidFile for FileRef {
+    fn as_pidfd(&self) -> Result<&PidFile> {
+        self.as_any()
+            .downcast_ref::<PidFile>()
+            .ok_or_else(|| errno!(EBADF, "not a pidfd"))
+    }
+}
+
+pub fn do_pidfd_open(pid: pid_t, status_flags: StatusFlags) -> Result<FileDesc> {
+    let process =
+        table::get_process(pid).map_err(|_| errno!(ESRCH, "pid does not match any process"))?;
+    let pidfd: FileRef = Arc::new(PidFile::new(&process, status_flags));
+    // The pidfd is always close-on-exec
+    let fd = current!().add_file(pidfd, true);
+    Ok(fd)
+}
+
+/// Send a signal to the process referred by the pidfd.
+///
+/// If `signum` is `None`, only check the existence of the process.
+pub fn do_pidfd_send_signal(fd: FileDesc, signum: Option<SigNum>) -> Result<()> {
+    let file_ref = current!().file(fd)?;
+    let process = file_ref.as_pidfd()?.process()?;
+    if let Some(signum) = signum {
+        do_kill_process(&process, signum)?;
+    }
+    Ok(())
+}
@@ -6,7 +6,8 @@ use std::os::raw::c_char;
 use super::super::time::timer_slack::TIMERSLACK;
 use super::thread::ThreadName;
 use crate::prelude::*;
-use crate::util::mem_util::from_user::{check_array, clone_cstring_safely};
+use crate::signal::SigNum;
+use crate::util::mem_util::from_user::{check_array, check_mut_ptr, clone_cstring_safely};
 
 #[macro_use]
 mod macros;
@@ -16,15 +17,64 @@ mod macros;
 impl_prctl_nums_and_cmds! {
     // Format:
     // prctl_name => (prctl_num, prctl_type_arg, ...
+    PR_SET_PDEATHSIG => (1, (Option<SigNum>)),
+    PR_GET_PDEATHSIG => (2, (&'a mut i32)),
+    PR_GET_DUMPABLE => (3, ()),
+    PR_SET_DUMPABLE => (4, bool),
     PR_SET_NAME => (15, ThreadName),
     PR_GET_NAME => (16, (&'a mut [u8])),
     PR_SET_TIMERSLACK => (29, u64),
     PR_GET_TIMERSLACK => (30, ()),
+    PR_SET_CHILD_SUBREAPER => (36, bool),
+    PR_GET_CHILD_SUBREAPER => (37, (&'a mut i32)),
+    PR_SET_NO_NEW_PRIVS => (38, ()),
+    PR_GET_NO_NEW_PRIVS => (39, ()),
+}
+
+/// The per-process attributes which are set by prctl.
+#[derive(Debug, Clone, Copy)]
+pub struct PrctlAttrs {
+    /// The signal sent to the process when its parent exits
+    pub pdeath_signal: Option<SigNum>,
+    pub dumpable: bool,
+    /// Once set, it is inherited by the children and can't be unset
+    pub no_new_privs: bool,
+    /// Whether the process adopts its orphaned descendants instead of init
+    pub child_subreaper: bool,
+}
+
+impl Default for PrctlAttrs {
+    fn default() -> Self {
+        Self {
+            pdeath_signal: None,
+            dumpable: true,
+            no_new_privs: false,
+            child_subreaper: false,
+        }
+    }
 }
 
 impl<'a> PrctlCmd<'a> {
     pub fn from_raw(cmd: i32, arg2: u64, arg3: u64, arg4: u64, arg5: u64) -> Result<PrctlCmd<'a>> {
         Ok(match cmd {
+            PR_SET_PDEATHSIG => {
+                let signum = match arg2 {
+                    0 => None,
+                    signum if signum <= u8::MAX as u64 => Some(SigNum::from_u8(signum as u8)?),
+                    _ => return_errno!(EINVAL, "invalid signal"),
+                };
+                PrctlCmd::PR_SET_PDEATHSIG(signum)
+            }
+            PR_GET_PDEATHSIG => PrctlCmd::PR_GET_PDEATHSIG(int_from_user(arg2)?),
+            PR_GET_DUMPABLE => PrctlCmd::PR_GET_DUMPABLE(()),
+            PR_SET_DUMPABLE => {
+                let dumpable = match arg2 {
+                    0 => false,
+                    1 => true,
+                    _ => return_errno!(EINVAL, "invalid dumpable value"),
+                };
+                PrctlCmd::PR_SET_DUMPABLE(dumpable)
+            }
             PR_SET_NAME => {
                 check_array(arg2 as *const u8, ThreadName::max_len())?;
                 let raw_name =
@@ -43,6 +93,20 @@ impl<'a> PrctlCmd<'a> {
             }
             PR_SET_TIMERSLACK => PrctlCmd::PR_SET_TIMERSLACK(arg2),
             PR_GET_TIMERSLACK => PrctlCmd::PR_GET_TIMERSLACK(()),
+            PR_SET_CHILD_SUBREAPER => PrctlCmd::PR_SET_CHILD_SUBREAPER(arg2 != 0),
+            PR_GET_CHILD_SUBREAPER => PrctlCmd::PR_GET_CHILD_SUBREAPER(int_from_user(arg2)?),
+            PR_SET_NO_NEW_PRIVS => {
+                if arg2 != 1 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                    return_errno!(EINVAL, "invalid arguments for PR_SET_NO_NEW_PRIVS");
+                }
+                PrctlCmd::PR_SET_NO_NEW_PRIVS(())
+            }
+            PR_GET_NO_NEW_PRIVS => {
+                if arg2 != 0 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                    return_errno!(EINVAL, "invalid arguments for PR_GET_NO_NEW_PRIVS");
+                }
+                PrctlCmd::PR_GET_NO_NEW_PRIVS(())
+            }
             _ => {
                 debug!("prctl cmd num: {}", cmd);
                 return_errno!(EINVAL, "unsupported prctl command");
@@ -51,11 +115,33 @@ impl<'a> PrctlCmd<'a> {
     }
 }
 
+fn int_from_user<'a>(addr: u64) -> Result<&'a mut i32> {
+    let ptr = addr as *mut i32;
+    check_mut_ptr(ptr)?;
+    Ok(unsafe { &mut *ptr })
+}
+
 pub fn do_prctl(cmd: PrctlCmd) -> Result<isize> {
     debug!("prctl: {:?}", cmd);
 
     let current = current!();
+    let process = current.process();
     match cmd {
+        PrctlCmd::PR_SET_PDEATHSIG(signum) => {
+            process.update_prctl_attrs(|attrs| attrs.pdeath_signal = signum);
+        }
+        PrctlCmd::PR_GET_PDEATHSIG(signum) => {
+            *signum = process
+                .prctl_attrs()
+                .pdeath_signal
+                .map_or(0, |signum| signum.as_u8() as i32);
+        }
+        PrctlCmd::PR_GET_DUMPABLE(()) => {
+            return Ok(process.prctl_attrs().dumpable as isize);
+        }
+        PrctlCmd::PR_SET_DUMPABLE(dumpable) => {
+            process.update_prctl_attrs(|attrs| attrs.dumpable = dumpable);
+        }
         PrctlCmd::PR_SET_NAME(name) => {
             current.set_name(name);
         }
@@ -73,6 +159,18 @@ pub fn do_prctl(cmd: PrctlCmd) -> Result<isize> {
             let nanoseconds = (*TIMERSLACK).to_u32();
             return Ok(nanoseconds as isize);
         }
+        PrctlCmd::PR_SET_CHILD_SUBREAPER(is_subreaper) => {
+            process.update_prctl_attrs(|attrs| attrs.child_subreaper = is_subreaper);
+        }
+        PrctlCmd::PR_GET_CHILD_SUBREAPER(is_subreaper) => {
+            *is_subreaper = process.prctl_attrs().child_subreaper as i32;
+        }
+        PrctlCmd::PR_SET_NO_NEW_PRIVS(()) => {
+            process.update_prctl_attrs(|attrs| attrs.no_new_privs = true);
+        }
+        PrctlCmd::PR_GET_NO_NEW_PRIVS(()) => {
+            return Ok(process.prctl_attrs().no_new_privs as isize);
+        }
         _ => return_errno!(EINVAL, "Prctl command not supported"),
     }
 
@@ -1,3 +1,9 @@
+use super::super::credentials::Credentials;
+use super::super::do_ptrace::PtraceStatus;
+use super::super::do_stop::StopStatus;
+use super::super::pidfd::ExitNotifier;
+use super::super::prctl::PrctlAttrs;
+use super::super::rusage::ProcessUsage;
 use super::super::table;
 use super::super::task::Task;
 use super::super::thread::{ThreadBuilder, ThreadId, ThreadName};
@@ -20,6 +26,9 @@ pub struct ProcessBuilder {
     // Optional fields, which have reasonable default values
     exec_path: Option<String>,
     umask: Option<FileMode>,
+    credentials: Option<Credentials>,
+    prctl_attrs: Option<PrctlAttrs>,
+    exit_notifier: Option<Arc<ExitNotifier>>,
     parent: Option<ProcessRef>,
     no_parent: bool,
     sig_dispositions: Option<SigDispositions>,
@@ -35,6 +44,9 @@ impl ProcessBuilder {
             pgrp: None,
             exec_path: None,
             umask: None,
+            credentials: None,
+            prctl_attrs: None,
+            exit_notifier: None,
             parent: None,
             no_parent: false,
             sig_dispositions: None,
@@ -56,6 +68,21 @@ impl ProcessBuilder {
         self
     }
 
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    pub fn prctl_attrs(mut self, prctl_attrs: PrctlAttrs) -> Self {
+        self.prctl_attrs = Some(prctl_attrs);
+        self
+    }
+
+    pub fn exit_notifier(mut self, exit_notifier: Arc<ExitNotifier>) -> Self {
+        self.exit_notifier = Some(exit_notifier);
+        self
+    }
+
     pub fn parent(mut self, parent: ProcessRef) -> Self {
         self.parent = Some(parent);
         self
@@ -129,24 +156,39 @@ impl ProcessBuilder {
         let new_process = {
             let exec_path = self.exec_path.take().unwrap_or_default();
             let umask = RwLock::new(self.umask.unwrap_or(FileMode::default_umask()));
+            let credentials = RwLock::new(self.credentials.take().unwrap_or_default());
+            let prctl_attrs = RwLock::new(self.prctl_attrs.take().unwrap_or_default());
             let parent = self.parent.take().map(|parent| RwLock::new(parent));
             let pgrp = RwLock::new(self.pgrp.clone());
             let inner = SgxMutex::new(ProcessInner::new());
             let sig_dispositions = RwLock::new(self.sig_dispositions.unwrap_or_default());
             let sig_queues = RwLock::new(SigQueues::new());
             let forced_exit_status = ForcedExitStatus::new();
+            let stop_status = StopStatus::new();
+            let ptrace_status = PtraceStatus::new();
+            let exit_notifier = self
+                .exit_notifier
+                .take()
+                .unwrap_or_else(|| Arc::new(ExitNotifier::new()));
+            let rusage = SgxMutex::new(ProcessUsage::new());
             let start_time = crate::time::up_time::get().unwrap();
             Arc::new(Process {
                 pid,
                 exec_path,
                 start_time,
                 umask,
+                credentials,
+                prctl_attrs,
                 parent,
                 pgrp,
                 inner,
                 sig_dispositions,
                 sig_queues,
                 forced_exit_status,
+                stop_status,
+                ptrace_status,
+                exit_notifier,
+                rusage,
             })
         };
 
@@ -164,11 +206,15 @@ impl ProcessBuilder {
                 .push(new_process.clone());
         }
 
-        // Only set leader process and process group id during process building when idle process first time init
+        // Only set leader process, process group id and session id during process building when idle process first time init
         let pgrp_ref = new_process.pgrp();
         if !pgrp_ref.leader_process_is_set() && pgrp_ref.pgid() == 0 {
             pgrp_ref.set_leader_process(new_process.clone());
             pgrp_ref.set_pgid(pid);
+            let session = pgrp_ref.session();
+            if session.sid() == 0 {
+                session.set_sid(pid);
+            }
         }
 
         Ok(new_process)
@@ -1,11 +1,20 @@
 use std::fmt;
 use std::time::Duration;
 
+use super::credentials::Credentials;
+use super::do_ptrace::PtraceStatus;
+use super::do_stop::StopStatus;
+use super::do_wait4::WaitStatus;
+use super::pidfd::ExitNotifier;
+use super::prctl::PrctlAttrs;
+use super::rusage::ProcessUsage;
+use super::session::SessionRef;
 use super::wait::WaitQueue;
 use super::{ForcedExitStatus, ProcessGrpRef, ProcessRef, TermStatus, ThreadRef};
 use crate::fs::FileMode;
 use crate::prelude::*;
 use crate::signal::{SigDispositions, SigNum, SigQueues};
+use crate::util::sync::RwLockReadGuard;
 
 pub use self::builder::ProcessBuilder;
 pub use self::idle::IDLE;
@@ -23,10 +32,17 @@ pub struct Process {
     pgrp: RwLock<Option<ProcessGrpRef>>,
     inner: SgxMutex<ProcessInner>,
     umask: RwLock<FileMode>,
+    credentials: RwLock<Credentials>,
+    prctl_attrs: RwLock<PrctlAttrs>,
     // Signal
     sig_dispositions: RwLock<SigDispositions>,
     sig_queues: RwLock<SigQueues>,
     forced_exit_status: ForcedExitStatus,
+    stop_status: StopStatus,
+    ptrace_status: PtraceStatus,
+    exit_notifier: Arc<ExitNotifier>,
+    // Resource usage
+    rusage: SgxMutex<ProcessUsage>,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -56,6 +72,11 @@ impl Process {
         self.pgrp().pgid()
     }
 
+    /// Get session ID
+    pub fn sid(&self) -> pid_t {
+        self.session().sid()
+    }
+
     /// Get the parent process.
     ///
     /// Precondition. The process is not the idle process.
@@ -81,6 +102,11 @@ impl Process {
             .clone()
     }
 
+    /// Get the session, which is the session of the process group.
+    pub fn session(&self) -> SessionRef {
+        self.pgrp().session()
+    }
+
     /// Update process group when setpgid is called
     pub fn update_pgrp(&self, new_pgrp: ProcessGrpRef) {
         let mut pgrp = self.pgrp.write().unwrap();
@@ -147,6 +173,36 @@ impl Process {
         self.umask.read().unwrap().clone()
     }
 
+    /// Get the credentials, which are borrowed to avoid cloning the groups
+    pub fn credentials(&self) -> RwLockReadGuard<'_, Credentials> {
+        self.credentials.read().unwrap()
+    }
+
+    /// Update the credentials with `f`, which fails without any change
+    pub fn update_credentials<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Credentials) -> Result<T>,
+    {
+        let mut credentials = self.credentials.write().unwrap();
+        let mut new_credentials = credentials.clone();
+        let ret = f(&mut new_credentials)?;
+        *credentials = new_credentials;
+        Ok(ret)
+    }
+
+    /// Get the attributes set by prctl
+    pub fn prctl_attrs(&self) -> PrctlAttrs {
+        *self.prctl_attrs.read().unwrap()
+    }
+
+    /// Update the attributes set by prctl with `f`
+    pub fn update_prctl_attrs<F>(&self, f: F)
+    where
+        F: FnOnce(&mut PrctlAttrs),
+    {
+        f(&mut self.prctl_attrs.write().unwrap());
+    }
+
     /// Set the file mode creation mask, return the previous value
     pub fn set_umask(&self, new_mask: FileMode) -> FileMode {
         let mut mask = self.umask.write().unwrap();
@@ -169,6 +225,31 @@ impl Process {
         self.forced_exit_status.term_status()
     }
 
+    /// Get the job control stop state.
+    pub fn stop_status(&self) -> &StopStatus {
+        &self.stop_status
+    }
+
+    /// Get the ptrace state of the process as a tracee.
+    pub fn ptrace_status(&self) -> &PtraceStatus {
+        &self.ptrace_status
+    }
+
+    /// Check whether the process is stopped by a stop signal.
+    pub fn is_stopped(&self) -> bool {
+        self.stop_status.is_stopped()
+    }
+
+    /// Get the notifier of the process exit, which is used by pidfds.
+    pub fn exit_notifier(&self) -> &Arc<ExitNotifier> {
+        &self.exit_notifier
+    }
+
+    /// Get the resource usage accounting of the process.
+    pub(super) fn rusage(&self) -> &SgxMutex<ProcessUsage> {
+        &self.rusage
+    }
+
     /// Check whether the process has been forced to exit.
     pub fn is_forced_to_exit(&self) -> bool {
         self.forced_exit_status.is_forced_to_exit()
@@ -183,6 +264,9 @@ impl Process {
     /// A process may be forced to exit many times, but only the first time counts.
     pub fn force_exit(&self, term_status: TermStatus) {
         self.forced_exit_status.force_exit(term_status);
+        // The threads parked by a stop signal or ptrace must wake up to exit
+        self.stop_status.resume();
+        self.ptrace_status.release_stop();
     }
 
     /// Get the internal representation of the process.
@@ -199,6 +283,8 @@ pub enum ProcessInner {
         children: Vec<ProcessRef>,
         waiting_children: WaitQueue<ProcessFilter, pid_t>,
         threads: Vec<ThreadRef>,
+        // The stopped or continued status which is not yet waited by the parent
+        unwaited_status: Option<WaitStatus>,
     },
     Zombie {
         term_status: TermStatus,
@@ -212,6 +298,7 @@ impl ProcessInner {
             children: Vec::new(),
             waiting_children: WaitQueue::new(),
             threads: Vec::new(),
+            unwaited_status: None,
         }
     }
 
@@ -236,6 +323,27 @@ impl ProcessInner {
         }
     }
 
+    /// Get the stopped or continued status to be reported to the parent by wait.
+    pub fn unwaited_status(&self) -> Option<WaitStatus> {
+        match self {
+            Self::Live {
+                unwaited_status, ..
+            } => *unwaited_status,
+            Self::Zombie { .. } => None,
+        }
+    }
+
+    /// Record a stopped or continued status, which replaces the previous one
+    /// if it is not waited yet.
+    pub fn set_unwaited_status(&mut self, new_status: Option<WaitStatus>) {
+        match self {
+            Self::Live {
+                unwaited_status, ..
+            } => *unwaited_status = new_status,
+            Self::Zombie { .. } => unreachable!(),
+        }
+    }
+
     pub fn children(&self) -> Option<&Vec<ProcessRef>> {
         match self {
             Self::Live { children, .. } => Some(children),
@@ -431,6 +539,27 @@ pub enum ProcessFilter {
     WithAnyPid,
     WithPid(pid_t),
     WithPgid(pid_t),
+    /// The exact process with the pid, e.g., the one referred by a pidfd,
+    /// which is not matched by a new process reusing the pid. The process
+    /// must be kept alive while the filter is used.
+    WithProcess(pid_t, *const Process),
+}
+
+impl ProcessFilter {
+    /// Create a filter matching the exact process.
+    pub fn with_process(process: &ProcessRef) -> Self {
+        ProcessFilter::WithProcess(process.pid(), Arc::as_ptr(process))
+    }
+
+    /// Whether the filter matches the process.
+    pub fn matches(&self, process: &ProcessRef) -> bool {
+        match self {
+            ProcessFilter::WithAnyPid => true,
+            ProcessFilter::WithPid(pid) => process.pid() == *pid,
+            ProcessFilter::WithPgid(pgid) => process.pgid() == *pgid,
+            ProcessFilter::WithProcess(_, ptr) => std::ptr::eq(Arc::as_ptr(process), *ptr),
+        }
+    }
 }
 
 // TODO: is this necessary?
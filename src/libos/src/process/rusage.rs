@@ -0,0 +1,450 @@
+//! Resource usage accounting of threads and processes.
+//!
+//! The CPU time of a thread is measured at the boundaries between the user
+//! space and the LibOS, i.e., syscalls, exceptions and interrupts. The time
+//! spent in the user space is user time and the time spent in the LibOS is
+//! system time, except for the time that the thread sleeps on the host,
+//! which is counted as a voluntary context switch instead. An interrupt means
+//! that the thread is preempted, which is counted as an involuntary context
+//! switch. Without vDSO, reading the clock at every boundary is too expensive,
+//! so the CPU time of a thread is read from the host thread running it, whose
+//! user time covers both the user space and the LibOS.
+//!
+//! A page fault is major if the memory is initialized from the backing file,
+//! and minor otherwise.
+//!
+//! When a thread exits, its usage is added to its process. When a zombie
+//! process is reaped, its usage and the usage of its reaped children are
+//! added to the children usage of the parent.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::time::Duration;
+
+use super::{current, Process, ProcessRef, Thread, ThreadRef};
+use crate::prelude::*;
+use crate::time::{self, clock_t, timeval_t, SC_CLK_TCK};
+
+/// Resource usage of a thread, a process or the reaped children of a process.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ResourceUsage {
+    utime: Duration,
+    stime: Duration,
+    // The maximum resident set size in bytes
+    maxrss: usize,
+    minflt: u64,
+    majflt: u64,
+    nvcsw: u64,
+    nivcsw: u64,
+}
+
+impl ResourceUsage {
+    pub fn utime(&self) -> Duration {
+        self.utime
+    }
+
+    pub fn stime(&self) -> Duration {
+        self.stime
+    }
+
+    pub fn maxrss(&self) -> usize {
+        self.maxrss
+    }
+
+    pub fn minflt(&self) -> u64 {
+        self.minflt
+    }
+
+    pub fn majflt(&self) -> u64 {
+        self.majflt
+    }
+
+    pub fn nvcsw(&self) -> u64 {
+        self.nvcsw
+    }
+
+    pub fn nivcsw(&self) -> u64 {
+        self.nivcsw
+    }
+
+    /// Accumulate another usage. The max RSS is the larger one of the two.
+    pub fn add(&mut self, other: &ResourceUsage) {
+        self.utime += other.utime;
+        self.stime += other.stime;
+        self.maxrss = self.maxrss.max(other.maxrss);
+        self.minflt += other.minflt;
+        self.majflt += other.majflt;
+        self.nvcsw += other.nvcsw;
+        self.nivcsw += other.nivcsw;
+    }
+}
+
+/// The resource usage accounting of a thread.
+///
+/// The accounting is updated at every syscall, so it is lock-free. The times
+/// and the mode are only updated by the thread itself, which are published to
+/// the readers with a sequence counter.
+#[derive(Debug)]
+pub struct ThreadUsage {
+    // Odd when the thread is updating the times and the mode
+    seq: AtomicU64,
+    // In nanoseconds
+    utime: AtomicU64,
+    stime: AtomicU64,
+    mode: AtomicU8,
+    // The time when the thread entered the current mode, in nanoseconds
+    since: AtomicU64,
+    minflt: AtomicU64,
+    majflt: AtomicU64,
+    // Whether the page fault being handled is major
+    is_major_fault: AtomicBool,
+    nvcsw: AtomicU64,
+    nivcsw: AtomicU64,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+enum ExecMode {
+    User = 0,
+    Kernel = 1,
+    Sleeping = 2,
+}
+
+impl ExecMode {
+    fn from_u8(mode: u8) -> Self {
+        match mode {
+            0 => ExecMode::User,
+            1 => ExecMode::Kernel,
+            _ => ExecMode::Sleeping,
+        }
+    }
+}
+
+impl ThreadUsage {
+    pub fn new() -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            utime: AtomicU64::new(0),
+            stime: AtomicU64::new(0),
+            mode: AtomicU8::new(ExecMode::User as u8),
+            since: AtomicU64::new(now().map_or(0, |now| now.as_nanos() as u64)),
+            minflt: AtomicU64::new(0),
+            majflt: AtomicU64::new(0),
+            is_major_fault: AtomicBool::new(false),
+            nvcsw: AtomicU64::new(0),
+            nivcsw: AtomicU64::new(0),
+        }
+    }
+
+    /// Enter the LibOS from the user space.
+    pub fn enter_kernel(&self) {
+        self.switch_to(ExecMode::Kernel);
+    }
+
+    /// Return to the user space from the LibOS.
+    pub fn exit_kernel(&self) {
+        self.switch_to(ExecMode::User);
+    }
+
+    /// The thread is going to sleep on the host.
+    pub fn sleep(&self) {
+        self.switch_to(ExecMode::Sleeping);
+    }
+
+    /// The thread wakes up from sleeping on the host.
+    pub fn wake_up(&self) {
+        if self.mode() == ExecMode::Sleeping {
+            self.nvcsw.fetch_add(1, Ordering::Relaxed);
+        }
+        self.switch_to(ExecMode::Kernel);
+    }
+
+    /// The thread is preempted by an interrupt.
+    pub fn interrupted(&self) {
+        self.nivcsw.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A page fault is handled by committing memory for the thread.
+    pub fn page_fault(&self) {
+        if self.is_major_fault.swap(false, Ordering::Relaxed) {
+            self.majflt.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.minflt.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The page fault being handled is major, i.e., it reads the backing file.
+    pub fn major_page_fault(&self) {
+        self.is_major_fault.store(true, Ordering::Relaxed);
+    }
+
+    /// Get the usage, including the time elapsed in the current mode.
+    pub fn usage(&self) -> ResourceUsage {
+        let (utime, stime, mode, since) = loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let times = (
+                self.utime.load(Ordering::Acquire),
+                self.stime.load(Ordering::Acquire),
+                self.mode(),
+                self.since.load(Ordering::Acquire),
+            );
+            if self.seq.load(Ordering::Acquire) == seq {
+                break times;
+            }
+        };
+
+        let mut usage = ResourceUsage {
+            utime: Duration::from_nanos(utime),
+            stime: Duration::from_nanos(stime),
+            minflt: self.minflt.load(Ordering::Relaxed),
+            majflt: self.majflt.load(Ordering::Relaxed),
+            nvcsw: self.nvcsw.load(Ordering::Relaxed),
+            nivcsw: self.nivcsw.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+        if let Some(now) = now() {
+            let elapsed = now.saturating_sub(Duration::from_nanos(since));
+            match mode {
+                ExecMode::User => usage.utime += elapsed,
+                ExecMode::Kernel => usage.stime += elapsed,
+                ExecMode::Sleeping => {}
+            }
+        }
+        usage
+    }
+
+    fn mode(&self) -> ExecMode {
+        ExecMode::from_u8(self.mode.load(Ordering::Acquire))
+    }
+
+    // Only called by the thread itself, so there is no concurrent writer
+    fn switch_to(&self, mode: ExecMode) {
+        self.seq.fetch_add(1, Ordering::AcqRel);
+        if let Some(now) = now() {
+            let now = now.as_nanos() as u64;
+            let elapsed = now.saturating_sub(self.since.load(Ordering::Relaxed));
+            match self.mode() {
+                ExecMode::User => self.utime.fetch_add(elapsed, Ordering::Release),
+                ExecMode::Kernel => self.stime.fetch_add(elapsed, Ordering::Release),
+                ExecMode::Sleeping => 0,
+            };
+            self.since.store(now, Ordering::Release);
+        }
+        self.mode.store(mode as u8, Ordering::Release);
+        self.seq.fetch_add(1, Ordering::Release);
+    }
+}
+
+// Reading the clock is an OCall if vDSO is not available, which is too
+// expensive to be done at every syscall. In this case, CPU time is not
+// accounted here, but read from the host threads.
+fn now() -> Option<Duration> {
+    if !time::is_enable_vdso() {
+        return None;
+    }
+    time::up_time::get()
+}
+
+/// The resource usage accounting of a process.
+#[derive(Debug, Default)]
+pub struct ProcessUsage {
+    // The usage of the exited threads
+    exited_threads: ResourceUsage,
+    // The usage of the reaped children and their reaped descendants
+    children: ResourceUsage,
+}
+
+impl ProcessUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Run `f`, during which the current thread sleeps on the host.
+pub fn account_sleep<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    current::with(|thread| thread.usage().sleep());
+    let ret = f();
+    current::with(|thread| thread.usage().wake_up());
+    ret
+}
+
+// Get the usage of a thread, whose CPU time is read from its host thread if
+// the CPU time is not accounted.
+fn thread_cpu_usage(thread: &Thread) -> ResourceUsage {
+    let mut usage = thread.usage().usage();
+    if time::is_enable_vdso() {
+        return usage;
+    }
+    let host_tid = thread.sched().lock().unwrap().host_tid();
+    if let Some(Ok((utime, stime))) = host_tid.map(time::do_host_thread_cputime) {
+        usage.utime = utime;
+        usage.stime = stime;
+    }
+    usage
+}
+
+/// Get the resource usage of a thread.
+pub fn thread_usage(thread: &ThreadRef) -> ResourceUsage {
+    let mut usage = thread_cpu_usage(thread);
+    usage.maxrss = thread.vm().max_resident_size(thread.process().pid());
+    usage
+}
+
+/// Get the resource usage of a process, i.e., the sum of its threads.
+pub fn process_usage(process: &ProcessRef) -> ResourceUsage {
+    // Hold the process lock so that an exiting thread is counted exactly once
+    let process_inner = process.inner();
+    let mut usage = process.rusage().lock().unwrap().exited_threads;
+    if let Some(threads) = process_inner.threads() {
+        for thread in threads {
+            usage.add(&thread_cpu_usage(thread));
+        }
+        if let Some(thread) = threads.first() {
+            usage.maxrss = usage
+                .maxrss
+                .max(thread.vm().max_resident_size(process.pid()));
+        }
+    }
+    usage
+}
+
+/// Get the resource usage of the reaped children of a process.
+pub fn children_usage(process: &ProcessRef) -> ResourceUsage {
+    process.rusage().lock().unwrap().children
+}
+
+/// Add the usage of an exiting thread to its process.
+///
+/// The caller must hold the lock of the process.
+pub(super) fn account_thread_exit(thread: &Thread) {
+    let mut usage = thread_cpu_usage(thread);
+    usage.maxrss = thread.vm().max_resident_size(thread.process().pid());
+    thread
+        .process()
+        .rusage()
+        .lock()
+        .unwrap()
+        .exited_threads
+        .add(&usage);
+}
+
+/// Add the usage of a reaped zombie child to its parent.
+pub(super) fn account_reaped_child(parent: &Process, zombie: &Process) {
+    let zombie_usage = zombie.rusage().lock().unwrap();
+    let mut parent_usage = parent.rusage().lock().unwrap();
+    parent_usage.children.add(&zombie_usage.exited_threads);
+    parent_usage.children.add(&zombie_usage.children);
+}
+
+/// Inherit the usage of the old process replaced by execve.
+pub(super) fn account_execve(old_process: &Process, new_process: &Process) {
+    let old_usage = old_process.rusage().lock().unwrap();
+    let mut new_usage = new_process.rusage().lock().unwrap();
+    new_usage.exited_threads.add(&old_usage.exited_threads);
+    new_usage.children.add(&old_usage.children);
+}
+
+/// The resource usage used by getrusage.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct rusage_t {
+    ru_utime: timeval_t,
+    ru_stime: timeval_t,
+    // In kilobytes
+    ru_maxrss: i64,
+    ru_ixrss: i64,
+    ru_idrss: i64,
+    ru_isrss: i64,
+    ru_minflt: i64,
+    ru_majflt: i64,
+    ru_nswap: i64,
+    ru_inblock: i64,
+    ru_oublock: i64,
+    ru_msgsnd: i64,
+    ru_msgrcv: i64,
+    ru_nsignals: i64,
+    ru_nvcsw: i64,
+    ru_nivcsw: i64,
+}
+
+impl From<ResourceUsage> for rusage_t {
+    fn from(usage: ResourceUsage) -> Self {
+        Self {
+            ru_utime: timeval_t::from(usage.utime),
+            ru_stime: timeval_t::from(usage.stime),
+            ru_maxrss: (usage.maxrss / 1024) as i64,
+            ru_minflt: usage.minflt as i64,
+            ru_majflt: usage.majflt as i64,
+            ru_nvcsw: usage.nvcsw as i64,
+            ru_nivcsw: usage.nivcsw as i64,
+            ..Default::default()
+        }
+    }
+}
+
+/// The process times used by times.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct tms_t {
+    tms_utime: clock_t,
+    tms_stime: clock_t,
+    tms_cutime: clock_t,
+    tms_cstime: clock_t,
+}
+
+impl tms_t {
+    pub fn new(usage: &ResourceUsage, children_usage: &ResourceUsage) -> Self {
+        Self {
+            tms_utime: duration_to_ticks(usage.utime),
+            tms_stime: duration_to_ticks(usage.stime),
+            tms_cutime: duration_to_ticks(children_usage.utime),
+            tms_cstime: duration_to_ticks(children_usage.stime),
+        }
+    }
+}
+
+/// Convert a duration to clock ticks.
+pub fn duration_to_ticks(duration: Duration) -> clock_t {
+    (duration.as_millis() * SC_CLK_TCK as u128 / 1000) as clock_t
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RusageWho {
+    Process,
+    Children,
+    Thread,
+}
+
+impl RusageWho {
+    pub fn from_i32(who: i32) -> Result<Self> {
+        match who {
+            0 => Ok(RusageWho::Process),
+            -1 => Ok(RusageWho::Children),
+            1 => Ok(RusageWho::Thread),
+            _ => return_errno!(EINVAL, "invalid who"),
+        }
+    }
+}
+
+pub fn do_getrusage(who: RusageWho) -> ResourceUsage {
+    let thread = current!();
+    match who {
+        RusageWho::Process => process_usage(thread.process()),
+        RusageWho::Children => children_usage(thread.process()),
+        RusageWho::Thread => thread_usage(&thread),
+    }
+}
+
+pub fn do_times() -> tms_t {
+    let thread = current!();
+    let process = thread.process();
+    tms_t::new(&process_usage(process), &children_usage(process))
+}
@@ -0,0 +1,242 @@
+use super::pgrp::ProcessGrp;
+use super::*;
+use crate::signal::{prepare_signal, KernelSignal, SigNum, SIGCONT, SIGHUP};
+
+/// A session is a collection of process groups.
+///
+/// The session ID equals to the pid of the session leader, i.e., the process
+/// which creates the session by calling setsid.
+///
+/// The initial session, which contains the processes spawned from the host,
+/// is not created by setsid and has no leader. Like the initial process group,
+/// its ID is the pid of the first process.
+#[derive(Debug)]
+pub struct Session {
+    sid: RwLock<pid_t>,
+    has_leader: bool,
+}
+
+pub type SessionRef = Arc<Session>;
+
+impl Session {
+    pub fn default() -> Self {
+        Session {
+            sid: RwLock::new(0),
+            has_leader: false,
+        }
+    }
+
+    // Create a new session with the leader's pid
+    pub fn new(sid: pid_t) -> Self {
+        Session {
+            sid: RwLock::new(sid),
+            has_leader: true,
+        }
+    }
+
+    pub fn sid(&self) -> pid_t {
+        *self.sid.read().unwrap()
+    }
+
+    pub fn set_sid(&self, sid: pid_t) {
+        *self.sid.write().unwrap() = sid;
+    }
+
+    pub fn is_leader(&self, process: &ProcessRef) -> bool {
+        self.has_leader && self.sid() == process.pid()
+    }
+}
+
+/// The state of the host terminal as a controlling terminal.
+///
+/// The standard I/O files are the only terminals in the LibOS and all of
+/// them are backed by the terminal of the host. Thus, there is at most one
+/// session which has a controlling terminal. By default, it is the session
+/// of the init process, whose process group is in the foreground.
+struct HostTty {
+    session: Option<Weak<Session>>,
+    foreground_pgrp: Option<Weak<ProcessGrp>>,
+}
+
+lazy_static! {
+    static ref HOST_TTY: SgxMutex<HostTty> = {
+        let idle_pgrp = IDLE.process().pgrp();
+        SgxMutex::new(HostTty {
+            session: Some(Arc::downgrade(&idle_pgrp.session())),
+            foreground_pgrp: Some(Arc::downgrade(&idle_pgrp)),
+        })
+    };
+}
+
+impl HostTty {
+    fn session(&self) -> Option<SessionRef> {
+        self.session.as_ref().and_then(|session| session.upgrade())
+    }
+
+    fn is_ctty_of(&self, session: &SessionRef) -> bool {
+        self.session()
+            .map_or(false, |tty_session| Arc::ptr_eq(&tty_session, session))
+    }
+
+    fn foreground_pgrp(&self) -> Option<ProcessGrpRef> {
+        self.foreground_pgrp
+            .as_ref()
+            .and_then(|pgrp| pgrp.upgrade())
+    }
+
+    fn disassociate(&mut self) {
+        self.session = None;
+        self.foreground_pgrp = None;
+    }
+
+    // Send SIGHUP and SIGCONT to the foreground process group
+    fn hangup_foreground(&self) {
+        let pgrp = match self.foreground_pgrp() {
+            Some(pgrp) => pgrp,
+            None => return,
+        };
+        for process in pgrp.get_all_processes() {
+            if process.status() == ProcessStatus::Zombie {
+                continue;
+            }
+            prepare_signal(&process, SIGCONT);
+            let mut sig_queues = process.sig_queues().write().unwrap();
+            sig_queues.enqueue(Box::new(KernelSignal::new(SigNum::from(SIGHUP))));
+            sig_queues.enqueue(Box::new(KernelSignal::new(SigNum::from(SIGCONT))));
+        }
+    }
+}
+
+pub fn do_getsid(pid: pid_t) -> Result<pid_t> {
+    let process =
+        table::get_process(pid).map_err(|_| errno!(ESRCH, "pid does not match any process"))?;
+    Ok(process.sid())
+}
+
+// Create a new session and a new process group with the calling process as
+// the leader of both. The new session has no controlling terminal.
+pub fn do_setsid() -> Result<pid_t> {
+    let process = current!().process().clone();
+    let pid = process.pid();
+
+    // A process group leader can't create a new session, otherwise the
+    // process group would span two sessions.
+    if table::get_pgrp(pid).is_ok() {
+        return_errno!(
+            EPERM,
+            "the calling process is already a process group leader"
+        );
+    }
+
+    let session = Arc::new(Session::new(pid));
+    let pgrp_ref = process.pgrp();
+    pgrp_ref.remove_process(&process);
+    let new_pgrp_ref = Arc::new(ProcessGrp::new_in_session(process.clone(), session)?);
+    process.update_pgrp(new_pgrp_ref.clone());
+    table::add_pgrp(new_pgrp_ref);
+
+    debug!("setsid: new session {:?}", pid);
+    Ok(pid)
+}
+
+/// Get the foreground process group of the controlling terminal.
+pub fn tty_get_foreground_pgrp() -> Result<pid_t> {
+    let session = current!().process().session();
+    let tty = HOST_TTY.lock().unwrap();
+    if !tty.is_ctty_of(&session) {
+        return_errno!(ENOTTY, "not the controlling terminal");
+    }
+    Ok(tty.foreground_pgrp().map_or(0, |pgrp| pgrp.pgid()))
+}
+
+/// Set the foreground process group of the controlling terminal.
+///
+/// The process group must be in the same session as the calling process.
+pub fn tty_set_foreground_pgrp(pgid: i32) -> Result<()> {
+    if pgid < 0 {
+        return_errno!(EINVAL, "invalid pgid");
+    }
+    let session = current!().process().session();
+    let mut tty = HOST_TTY.lock().unwrap();
+    if !tty.is_ctty_of(&session) {
+        return_errno!(ENOTTY, "not the controlling terminal");
+    }
+
+    let pgrp = table::get_pgrp(pgid as pid_t)
+        .map_err(|_| errno!(ESRCH, "process group does not exist"))?;
+    if !Arc::ptr_eq(&pgrp.session(), &session) {
+        return_errno!(EPERM, "process group is in a different session");
+    }
+    tty.foreground_pgrp = Some(Arc::downgrade(&pgrp));
+    Ok(())
+}
+
+/// Get the session ID of the controlling terminal.
+pub fn tty_get_sid() -> Result<pid_t> {
+    let session = current!().process().session();
+    let tty = HOST_TTY.lock().unwrap();
+    if !tty.is_ctty_of(&session) {
+        return_errno!(ENOTTY, "not the controlling terminal");
+    }
+    Ok(session.sid())
+}
+
+/// Make the terminal the controlling terminal of the calling process's session.
+///
+/// The calling process must be a session leader. Stealing the terminal from
+/// another session is not supported.
+pub fn tty_set_ctty() -> Result<()> {
+    let process = current!().process().clone();
+    let session = process.session();
+    let mut tty = HOST_TTY.lock().unwrap();
+    if tty.is_ctty_of(&session) {
+        return Ok(());
+    }
+    if !session.is_leader(&process) {
+        return_errno!(EPERM, "the calling process is not a session leader");
+    }
+    if tty.session().is_some() {
+        return_errno!(
+            EPERM,
+            "the terminal is the controlling terminal of another session"
+        );
+    }
+
+    tty.session = Some(Arc::downgrade(&session));
+    tty.foreground_pgrp = Some(Arc::downgrade(&process.pgrp()));
+    Ok(())
+}
+
+/// Give up the controlling terminal.
+///
+/// If the calling process is the session leader, the foreground process group
+/// is sent SIGHUP and SIGCONT, and the session loses the controlling terminal.
+pub fn tty_release_ctty() -> Result<()> {
+    let process = current!().process().clone();
+    let session = process.session();
+    let mut tty = HOST_TTY.lock().unwrap();
+    if !tty.is_ctty_of(&session) {
+        return_errno!(ENOTTY, "not the controlling terminal");
+    }
+    if session.is_leader(&process) {
+        tty.hangup_foreground();
+        tty.disassociate();
+    }
+    Ok(())
+}
+
+// When a session leader exits, the foreground process group of its
+// controlling terminal is sent SIGHUP and SIGCONT, and the terminal is
+// disassociated from the session.
+pub fn hangup_session_when_exit(process: &ProcessRef) {
+    let session = process.session();
+    if !session.is_leader(process) {
+        return;
+    }
+
+    let mut tty = HOST_TTY.lock().unwrap();
+    if tty.is_ctty_of(&session) {
+        tty.hangup_foreground();
+        tty.disassociate();
+    }
+}
@@ -1,15 +1,21 @@
+use super::credentials::NGROUPS_MAX;
 use super::do_arch_prctl::ArchPrctlCode;
 use super::do_clone::CloneFlags;
 use super::do_exec::do_exec;
 use super::do_futex::{FutexFlags, FutexOp, FutexTimeout};
+use super::do_ptrace::{user_regs_t, PtraceOptions, PtraceRequest};
 use super::do_robust_list::RobustListHead;
 use super::do_spawn::FileAction;
 use super::do_wait4::WaitOptions;
 use super::pgrp::*;
+use super::pidfd::AsPidFile;
 use super::prctl::PrctlCmd;
 use super::process::ProcessFilter;
+use super::rusage::{duration_to_ticks, rusage_t, tms_t, RusageWho};
 use super::spawn_attribute::{clone_spawn_atrributes_safely, posix_spawnattr_t, SpawnAttr};
+use crate::fs::StatusFlags;
 use crate::prelude::*;
+use crate::signal::{siginfo_t, SigNum, SIGCHLD};
 use crate::syscall::CpuContext;
 use crate::time::{timespec_t, ClockId};
 use crate::util::mem_util::from_user::*;
@@ -343,6 +349,61 @@ pub fn do_prctl(option: i32, arg2: u64, arg3: u64, arg4: u64, arg5: u64) -> Resu
     super::prctl::do_prctl(prctl_cmd)
 }
 
+pub fn do_ptrace(request: i32, pid: pid_t, addr: usize, data: usize) -> Result<isize> {
+    let request = PtraceRequest::from_i32(request)?;
+    // The signal delivered to the tracee when it is resumed
+    let signum = || -> Result<Option<SigNum>> {
+        match data {
+            0 => Ok(None),
+            signum if signum <= u8::MAX as usize => Ok(Some(SigNum::from_u8(signum as u8)?)),
+            _ => return_errno!(EIO, "invalid signal"),
+        }
+    };
+    match request {
+        PtraceRequest::PTRACE_ATTACH => {
+            super::do_ptrace::do_ptrace_attach(pid, PtraceOptions::empty(), false)?;
+        }
+        PtraceRequest::PTRACE_SEIZE => {
+            let options = PtraceOptions::from_u32(data as u32)?;
+            super::do_ptrace::do_ptrace_attach(pid, options, true)?;
+        }
+        PtraceRequest::PTRACE_SETOPTIONS => {
+            let options = PtraceOptions::from_u32(data as u32)?;
+            super::do_ptrace::do_ptrace_setoptions(pid, options)?;
+        }
+        PtraceRequest::PTRACE_CONT => {
+            super::do_ptrace::do_ptrace_resume(pid, false, signum()?)?;
+        }
+        PtraceRequest::PTRACE_SYSCALL => {
+            super::do_ptrace::do_ptrace_resume(pid, true, signum()?)?;
+        }
+        PtraceRequest::PTRACE_DETACH => {
+            super::do_ptrace::do_ptrace_detach(pid, signum()?)?;
+        }
+        PtraceRequest::PTRACE_GETREGS => {
+            let regs_ptr = data as *mut user_regs_t;
+            check_mut_ptr(regs_ptr)?;
+            let regs = super::do_ptrace::do_ptrace_getregs(pid)?;
+            unsafe {
+                *regs_ptr = regs;
+            }
+        }
+        PtraceRequest::PTRACE_PEEKDATA => {
+            // Like the raw syscall of Linux, the word is stored in `data`
+            let word_ptr = data as *mut usize;
+            check_mut_ptr(word_ptr)?;
+            let word = super::do_ptrace::do_ptrace_peekdata(pid, addr)?;
+            unsafe {
+                *word_ptr = word;
+            }
+        }
+        PtraceRequest::PTRACE_POKEDATA => {
+            super::do_ptrace::do_ptrace_pokedata(pid, addr, data)?;
+        }
+    }
+    Ok(0)
+}
+
 pub fn do_arch_prctl(code: u32, addr: *mut usize) -> Result<isize> {
     let code = ArchPrctlCode::from_u32(code)?;
     super::do_arch_prctl::do_arch_prctl(code, addr).map(|_| 0)
@@ -385,18 +446,167 @@ pub fn do_wait4(pid: i32, exit_status_ptr: *mut i32, options: u32) -> Result<isi
 
     let wait_options =
         WaitOptions::from_bits(options).ok_or_else(|| errno!(EINVAL, "options not recognized"))?;
-    let mut exit_status = 0;
-    match super::do_wait4::do_wait4(&child_process_filter, wait_options) {
-        Ok((pid, exit_status)) => {
+    if wait_options.intersects(WaitOptions::WEXITED | WaitOptions::WNOWAIT) {
+        return_errno!(EINVAL, "options can only be used in waitid");
+    }
+    // Wait4 always waits for the exited children
+    let wait_options = wait_options | WaitOptions::WEXITED;
+
+    match super::do_wait4::do_wait4(&child_process_filter, wait_options)? {
+        Some(info) => {
             if !exit_status_ptr.is_null() {
                 unsafe {
-                    *exit_status_ptr = exit_status;
+                    *exit_status_ptr = info.status.as_u32() as i32;
                 }
             }
-            Ok(pid as isize)
+            Ok(info.pid as isize)
+        }
+        None => Ok(0),
+    }
+}
+
+pub fn do_waitid(idtype: u32, id: i32, infop: *mut siginfo_t, options: u32) -> Result<isize> {
+    if !infop.is_null() {
+        check_mut_ptr(infop)?;
+    }
+
+    const P_ALL: u32 = 0;
+    const P_PID: u32 = 1;
+    const P_PGID: u32 = 2;
+    const P_PIDFD: u32 = 3;
+    let mut is_pidfd_nonblocking = false;
+    // Keep the process referred by the pidfd alive while waiting for it
+    let mut _pidfd_process = None;
+    let child_process_filter = match idtype {
+        P_ALL => ProcessFilter::WithAnyPid,
+        P_PID => {
+            if id <= 0 {
+                return_errno!(EINVAL, "invalid pid");
+            }
+            ProcessFilter::WithPid(id as pid_t)
+        }
+        P_PGID => {
+            if id < 0 {
+                return_errno!(EINVAL, "invalid pgid");
+            }
+            let pgid = if id == 0 {
+                current!().process().pgid()
+            } else {
+                id as pid_t
+            };
+            ProcessFilter::WithPgid(pgid)
+        }
+        P_PIDFD => {
+            let file_ref = current!().file(id as FileDesc)?;
+            let pidfd = file_ref.as_pidfd()?;
+            is_pidfd_nonblocking = pidfd.is_nonblocking();
+            // Wait for the exact process, even if its pid has been reused
+            let process = pidfd
+                .process()
+                .map_err(|_| errno!(ECHILD, "the process has been reaped"))?;
+            _pidfd_process = Some(process.clone());
+            ProcessFilter::with_process(&process)
+        }
+        _ => {
+            return_errno!(EINVAL, "invalid idtype");
+        }
+    };
+
+    let mut wait_options =
+        WaitOptions::from_bits(options).ok_or_else(|| errno!(EINVAL, "options not recognized"))?;
+    if !wait_options
+        .intersects(WaitOptions::WEXITED | WaitOptions::WSTOPPED | WaitOptions::WCONTINUED)
+    {
+        return_errno!(EINVAL, "no state changes to wait for");
+    }
+    // Waiting on a nonblocking pidfd never blocks, but fails with EAGAIN
+    // instead of returning zero when the child has not changed its status.
+    let should_fail_if_none = is_pidfd_nonblocking && !wait_options.contains(WaitOptions::WNOHANG);
+    if is_pidfd_nonblocking {
+        wait_options |= WaitOptions::WNOHANG;
+    }
+
+    let info = super::do_wait4::do_wait4(&child_process_filter, wait_options)?;
+    if info.is_none() && should_fail_if_none {
+        return_errno!(
+            EAGAIN,
+            "the child referred by the nonblocking pidfd is running"
+        );
+    }
+    if infop.is_null() {
+        return Ok(0);
+    }
+    let siginfo = match info {
+        Some(info) => {
+            let (code, status) = info.status.as_siginfo_code_and_status();
+            let mut siginfo = siginfo_t::new(SIGCHLD, code);
+            siginfo.set_si_pid(info.pid);
+            siginfo.set_si_uid(info.uid);
+            siginfo.set_si_satus(status);
+            siginfo
         }
-        Err(e) => Err(e),
+        // No child has changed its status with WNOHANG, zero out the siginfo
+        None => unsafe { std::mem::zeroed() },
+    };
+    unsafe {
+        *infop = siginfo;
     }
+    Ok(0)
+}
+
+pub fn do_pidfd_open(pid: i32, flags: u32) -> Result<isize> {
+    if pid <= 0 {
+        return_errno!(EINVAL, "invalid pid");
+    }
+    let status_flags = StatusFlags::from_bits(flags)
+        .filter(|flags| (*flags - StatusFlags::O_NONBLOCK).is_empty())
+        .ok_or_else(|| errno!(EINVAL, "flags not recognized"))?;
+    let fd = super::pidfd::do_pidfd_open(pid as pid_t, status_flags)?;
+    Ok(fd as isize)
+}
+
+pub fn do_pidfd_send_signal(
+    pidfd: FileDesc,
+    sig: c_int,
+    info: *const siginfo_t,
+    flags: u32,
+) -> Result<isize> {
+    if flags != 0 {
+        return_errno!(EINVAL, "flags must be zero");
+    }
+    if !info.is_null() {
+        return_errno!(EINVAL, "siginfo is not supported");
+    }
+    let signum = if sig == 0 {
+        None
+    } else {
+        Some(SigNum::from_u8(sig as u8)?)
+    };
+    super::pidfd::do_pidfd_send_signal(pidfd, signum)?;
+    Ok(0)
+}
+
+pub fn do_getrusage(who: i32, ru_u: *mut rusage_t) -> Result<isize> {
+    let who = RusageWho::from_i32(who)?;
+    check_mut_ptr(ru_u)?;
+    let usage = super::rusage::do_getrusage(who);
+    unsafe {
+        *ru_u = rusage_t::from(usage);
+    }
+    Ok(0)
+}
+
+pub fn do_times(buf: *mut tms_t) -> Result<isize> {
+    // The buffer can be NULL, in which case only the elapsed time is returned
+    if !buf.is_null() {
+        check_mut_ptr(buf)?;
+        let tms = super::rusage::do_times();
+        unsafe {
+            *buf = tms;
+        }
+    }
+    let up_time = crate::time::up_time::get().unwrap_or_default();
+    Ok(duration_to_ticks(up_time) as isize)
 }
 
 pub fn do_getpid() -> Result<isize> {
@@ -452,40 +662,180 @@ pub fn do_setpgid(pid: i32, pgid: i32) -> Result<isize> {
     Ok(ret)
 }
 
-// TODO: implement uid, gid, euid, egid
+pub fn do_getsid(pid: i32) -> Result<isize> {
+    if pid < 0 {
+        return_errno!(ESRCH, "process with negative pid is not found");
+    }
+
+    let real_pid = if pid == 0 {
+        do_getpid()? as pid_t
+    } else {
+        pid as pid_t
+    };
+    let sid = super::session::do_getsid(real_pid)?;
+    Ok(sid as isize)
+}
+
+pub fn do_setsid() -> Result<isize> {
+    let sid = super::session::do_setsid()?;
+    Ok(sid as isize)
+}
 
 pub fn do_getuid() -> Result<isize> {
-    Ok(0)
+    Ok(current!().process().credentials().ruid() as isize)
 }
 
 pub fn do_getgid() -> Result<isize> {
-    Ok(0)
+    Ok(current!().process().credentials().rgid() as isize)
 }
 
 pub fn do_geteuid() -> Result<isize> {
-    Ok(0)
+    Ok(current!().process().credentials().euid() as isize)
 }
 
 pub fn do_getegid() -> Result<isize> {
+    Ok(current!().process().credentials().egid() as isize)
+}
+
+pub fn do_setuid(uid: u32) -> Result<isize> {
+    let uid = to_id(uid).ok_or_else(|| errno!(EINVAL, "invalid uid"))?;
+    current!()
+        .process()
+        .update_credentials(|credentials| credentials.setuid(uid))?;
     Ok(0)
 }
 
-// Occlum is a single user enviroment, so only group 0 is supported
+pub fn do_setgid(gid: u32) -> Result<isize> {
+    let gid = to_id(gid).ok_or_else(|| errno!(EINVAL, "invalid gid"))?;
+    current!()
+        .process()
+        .update_credentials(|credentials| credentials.setgid(gid))?;
+    Ok(0)
+}
+
+pub fn do_setreuid(ruid: u32, euid: u32) -> Result<isize> {
+    current!()
+        .process()
+        .update_credentials(|credentials| credentials.setreuid(to_id(ruid), to_id(euid)))?;
+    Ok(0)
+}
+
+pub fn do_setregid(rgid: u32, egid: u32) -> Result<isize> {
+    current!()
+        .process()
+        .update_credentials(|credentials| credentials.setregid(to_id(rgid), to_id(egid)))?;
+    Ok(0)
+}
+
+pub fn do_setresuid(ruid: u32, euid: u32, suid: u32) -> Result<isize> {
+    current!().process().update_credentials(|credentials| {
+        credentials.setresuid(to_id(ruid), to_id(euid), to_id(suid))
+    })?;
+    Ok(0)
+}
+
+pub fn do_setresgid(rgid: u32, egid: u32, sgid: u32) -> Result<isize> {
+    current!().process().update_credentials(|credentials| {
+        credentials.setresgid(to_id(rgid), to_id(egid), to_id(sgid))
+    })?;
+    Ok(0)
+}
+
+pub fn do_getresuid(ruid_ptr: *mut u32, euid_ptr: *mut u32, suid_ptr: *mut u32) -> Result<isize> {
+    check_mut_ptr(ruid_ptr)?;
+    check_mut_ptr(euid_ptr)?;
+    check_mut_ptr(suid_ptr)?;
+
+    let current = current!();
+
+    let credentials = current.process().credentials();
+    unsafe {
+        *ruid_ptr = credentials.ruid();
+        *euid_ptr = credentials.euid();
+        *suid_ptr = credentials.suid();
+    }
+    Ok(0)
+}
+
+pub fn do_getresgid(rgid_ptr: *mut u32, egid_ptr: *mut u32, sgid_ptr: *mut u32) -> Result<isize> {
+    check_mut_ptr(rgid_ptr)?;
+    check_mut_ptr(egid_ptr)?;
+    check_mut_ptr(sgid_ptr)?;
+
+    let current = current!();
+
+    let credentials = current.process().credentials();
+    unsafe {
+        *rgid_ptr = credentials.rgid();
+        *egid_ptr = credentials.egid();
+        *sgid_ptr = credentials.sgid();
+    }
+    Ok(0)
+}
+
+pub fn do_setfsuid(fsuid: u32) -> Result<isize> {
+    let old_fsuid = current!().process().update_credentials(|credentials| {
+        // An invalid ID just queries the current value
+        Ok(match to_id(fsuid) {
+            Some(fsuid) => credentials.setfsuid(fsuid),
+            None => credentials.fsuid(),
+        })
+    })?;
+    Ok(old_fsuid as isize)
+}
+
+pub fn do_setfsgid(fsgid: u32) -> Result<isize> {
+    let old_fsgid = current!().process().update_credentials(|credentials| {
+        Ok(match to_id(fsgid) {
+            Some(fsgid) => credentials.setfsgid(fsgid),
+            None => credentials.fsgid(),
+        })
+    })?;
+    Ok(old_fsgid as isize)
+}
+
 pub fn do_getgroups(size: isize, buf_ptr: *mut u32) -> Result<isize> {
     if size < 0 {
         return_errno!(EINVAL, "buffer size is incorrect");
-    } else if size == 0 {
-        //Occlum only has 1 group
-        Ok(1)
-    } else {
-        let size = size as usize;
-        check_array(buf_ptr, size)?;
+    }
+    let current = current!();
+    let credentials = current.process().credentials();
+    let groups = credentials.groups();
+    if size == 0 {
+        return Ok(groups.len() as isize);
+    }
+    let size = size as usize;
+    if size < groups.len() {
+        return_errno!(EINVAL, "buffer size is too small");
+    }
+    check_mut_array(buf_ptr, size)?;
+    let group_list = unsafe { std::slice::from_raw_parts_mut(buf_ptr, size) };
+    group_list[..groups.len()].copy_from_slice(groups);
+    Ok(groups.len() as isize)
+}
 
-        let group_list = unsafe { std::slice::from_raw_parts_mut(buf_ptr, size) };
-        group_list[0] = 0;
+pub fn do_setgroups(size: isize, buf_ptr: *const u32) -> Result<isize> {
+    if size < 0 || size as usize > NGROUPS_MAX {
+        return_errno!(EINVAL, "buffer size is incorrect");
+    }
+    let groups = if size == 0 {
+        Vec::new()
+    } else {
+        check_array(buf_ptr, size as usize)?;
+        unsafe { std::slice::from_raw_parts(buf_ptr, size as usize) }.to_vec()
+    };
+    current!()
+        .process()
+        .update_credentials(|credentials| credentials.setgroups(&groups))?;
+    Ok(0)
+}
 
-        //Occlum only has 1 group
-        Ok(1)
+/// Convert the raw ID to `None` if it is -1, which means unchanged.
+fn to_id(raw_id: u32) -> Option<u32> {
+    if raw_id == u32::MAX {
+        None
+    } else {
+        Some(raw_id)
     }
 }
 
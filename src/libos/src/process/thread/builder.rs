@@ -4,7 +4,7 @@ use std::ptr::NonNull;
 use super::{
     FileTableRef, FsViewRef, NiceValueRef, ProcessRef, ProcessVM, ProcessVMRef, ResourceLimitsRef,
     RobustListHead, SchedAgentRef, SigQueues, SigSet, Task, Thread, ThreadId, ThreadInner,
-    ThreadName, ThreadRef,
+    ThreadName, ThreadRef, ThreadUsage,
 };
 use crate::events::HostEventFd;
 use crate::prelude::*;
@@ -142,6 +142,7 @@ impl ThreadBuilder {
         } else {
             SgxMutex::new(None)
         };
+        let usage = ThreadUsage::new();
         let host_eventfd = Arc::new(HostEventFd::new()?);
         let raw_ptr = RwLock::new(0);
         let io_buffer = Once::new();
@@ -165,6 +166,7 @@ impl ThreadBuilder {
             sig_tmp_mask,
             sig_stack,
             profiler,
+            usage,
             host_eventfd,
             raw_ptr,
             io_buffer,
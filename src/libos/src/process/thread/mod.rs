@@ -2,6 +2,7 @@ use spin::Once;
 use std::fmt;
 use std::ptr::NonNull;
 
+use super::rusage::{self, ThreadUsage};
 use super::task::Task;
 use super::{
     FileTableRef, ForcedExitStatus, FsViewRef, NiceValueRef, ProcessRef, ProcessVM, ProcessVMRef,
@@ -55,6 +56,8 @@ pub struct Thread {
     sig_stack: SgxMutex<Option<SigStack>>,
     // System call timing
     profiler: SgxMutex<Option<ThreadProfiler>>,
+    // Resource usage
+    usage: ThreadUsage,
     // Misc
     host_eventfd: Arc<HostEventFd>,
     raw_ptr: RwLock<usize>,
@@ -128,6 +131,11 @@ impl Thread {
         &self.profiler
     }
 
+    /// Get the resource usage accounting of this thread.
+    pub fn usage(&self) -> &ThreadUsage {
+        &self.usage
+    }
+
     /// Get the host thread's raw pointer of this libos thread
     pub fn raw_ptr(&self) -> usize {
         self.raw_ptr.read().unwrap().clone()
@@ -295,6 +303,7 @@ impl Thread {
 
         // Remove this thread from its owner process
         let mut process_inner = self.process.inner();
+        rusage::account_thread_exit(self);
         let threads = process_inner.threads_mut().unwrap();
         let thread_i = threads
             .iter()
@@ -1,11 +1,12 @@
+use super::account_sleep;
 use crate::prelude::*;
 
 pub(crate) fn wait_event(thread: *const c_void) {
     let mut ret: c_int = 0;
     let mut sgx_ret: c_int = 0;
-    unsafe {
+    account_sleep(|| unsafe {
         sgx_ret = sgx_thread_wait_untrusted_event_ocall(&mut ret as *mut c_int, thread);
-    }
+    });
     if ret != 0 || sgx_ret != 0 {
         panic!("ERROR: OCall failed!");
     }
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use super::do_sched_affinity::get_thread_by_tid;
+use super::policy::{SchedParam, SchedPolicy};
+use super::priority::NiceValue;
+use crate::prelude::*;
+use crate::process::ThreadRef;
+
+/// The time slice of SCHED_RR, which is the same as the default one of Linux.
+const RR_TIMESLICE: Duration = Duration::from_millis(100);
+
+pub fn do_sched_getparam(tid: pid_t) -> Result<SchedParam> {
+    debug!("do_sched_getparam tid: {}", tid);
+    let thread = get_thread_by_tid(tid)?;
+    let sched = thread.sched().lock().unwrap();
+    let param = *sched.param();
+    Ok(param)
+}
+
+pub fn do_sched_setparam(tid: pid_t, new_param: SchedParam) -> Result<()> {
+    debug!(
+        "do_sched_setparam tid: {}, new_param = {:?}",
+        tid, &new_param
+    );
+    let thread = get_thread_by_tid(tid)?;
+    check_sched_permission(&thread, &new_param, None)?;
+    let mut sched = thread.sched().lock().unwrap();
+    sched.set_param(new_param);
+    Ok(())
+}
+
+pub fn do_sched_getattr(tid: pid_t) -> Result<(SchedParam, NiceValue)> {
+    debug!("do_sched_getattr tid: {}", tid);
+    let thread = get_thread_by_tid(tid)?;
+    let param = *thread.sched().lock().unwrap().param();
+    let nice = *thread.nice().read().unwrap();
+    Ok((param, nice))
+}
+
+/// Set the scheduling policy and, for non-realtime policies, the nice value.
+pub fn do_sched_setattr(tid: pid_t, new_param: SchedParam, nice: Option<NiceValue>) -> Result<()> {
+    debug!(
+        "do_sched_setattr tid: {}, new_param = {:?}, nice = {:?}",
+        tid, &new_param, nice
+    );
+    let thread = get_thread_by_tid(tid)?;
+    check_sched_permission(&thread, &new_param, nice)?;
+    thread.sched().lock().unwrap().set_param(new_param);
+    if let Some(nice) = nice {
+        *thread.nice().write().unwrap() = nice;
+    }
+    Ok(())
+}
+
+/// Check whether the current thread can change the scheduling of the thread.
+///
+/// Like Linux, unless the current process is root, the thread must belong to
+/// the same user, i.e., the effective uid of the current process is the real or
+/// effective uid of the thread. And only root can switch to a realtime policy,
+/// raise the realtime priority or lower the nice value, since the RLIMIT_RTPRIO
+/// and RLIMIT_NICE are not supported.
+fn check_sched_permission(
+    thread: &ThreadRef,
+    new_param: &SchedParam,
+    new_nice: Option<NiceValue>,
+) -> Result<()> {
+    let current = current!();
+    let euid = current.process().credentials().euid();
+    if euid == 0 {
+        return Ok(());
+    }
+
+    if !Arc::ptr_eq(current.process(), thread.process()) {
+        let credentials = thread.process().credentials();
+        if euid != credentials.ruid() && euid != credentials.euid() {
+            return_errno!(EPERM, "the thread belongs to another user");
+        }
+    }
+
+    let old_param = *thread.sched().lock().unwrap().param();
+    if new_param.policy().is_realtime()
+        && (new_param.policy() != old_param.policy() || new_param.priority() > old_param.priority())
+    {
+        return_errno!(EPERM, "only root can raise the realtime priority");
+    }
+    if let Some(new_nice) = new_nice {
+        if new_nice < *thread.nice().read().unwrap() {
+            return_errno!(EPERM, "only root can lower the nice value");
+        }
+    }
+    Ok(())
+}
+
+pub fn do_sched_rr_get_interval(tid: pid_t) -> Result<Duration> {
+    let param = do_sched_getparam(tid)?;
+    let interval = match param.policy() {
+        SchedPolicy::SCHED_RR => RR_TIMESLICE,
+        // FIFO threads run until they yield. For the other policies, the
+        // time slice is decided by the host.
+        _ => Duration::from_secs(0),
+    };
+    Ok(interval)
+}
@@ -3,12 +3,15 @@ mod cpu_set;
 mod do_getcpu;
 mod do_priority;
 mod do_sched_affinity;
+mod do_sched_policy;
 mod do_sched_yield;
+mod policy;
 mod priority;
 mod sched_agent;
 mod syscalls;
 
 pub use cpu_set::NCORES;
+pub use policy::{sched_attr_t, sched_param_t, SchedParam, SchedPolicy};
 pub use priority::NiceValue;
 pub use sched_agent::SchedAgent;
 pub use syscalls::*;
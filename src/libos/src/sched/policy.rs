@@ -0,0 +1,144 @@
+use crate::prelude::*;
+use core::convert::TryFrom;
+
+/// Scheduling policies.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u32)]
+pub enum SchedPolicy {
+    SCHED_NORMAL = 0,
+    SCHED_FIFO = 1,
+    SCHED_RR = 2,
+    SCHED_BATCH = 3,
+    SCHED_IDLE = 5,
+}
+
+const SCHED_DEADLINE: i32 = 6;
+
+/// The flag that can be ORed into the policy of sched_setscheduler to reset
+/// the policy of the children to the default one.
+pub const SCHED_RESET_ON_FORK: i32 = 0x40000000;
+
+impl SchedPolicy {
+    pub fn is_realtime(&self) -> bool {
+        *self == Self::SCHED_FIFO || *self == Self::SCHED_RR
+    }
+
+    /// The maximum static priority of the policy.
+    pub fn priority_max(&self) -> u32 {
+        if self.is_realtime() {
+            99
+        } else {
+            0
+        }
+    }
+
+    /// The minimum static priority of the policy.
+    pub fn priority_min(&self) -> u32 {
+        if self.is_realtime() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for SchedPolicy {
+    fn default() -> Self {
+        Self::SCHED_NORMAL
+    }
+}
+
+impl TryFrom<i32> for SchedPolicy {
+    type Error = crate::error::Error;
+
+    fn try_from(raw: i32) -> Result<Self> {
+        let policy = match raw {
+            0 => Self::SCHED_NORMAL,
+            1 => Self::SCHED_FIFO,
+            2 => Self::SCHED_RR,
+            3 => Self::SCHED_BATCH,
+            5 => Self::SCHED_IDLE,
+            SCHED_DEADLINE => return_errno!(EINVAL, "SCHED_DEADLINE is not supported"),
+            _ => return_errno!(EINVAL, "invalid policy"),
+        };
+        Ok(policy)
+    }
+}
+
+/// The scheduling policy of a thread and its parameters.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SchedParam {
+    policy: SchedPolicy,
+    priority: u32,
+    reset_on_fork: bool,
+}
+
+impl SchedParam {
+    pub fn new(policy: SchedPolicy, priority: u32, reset_on_fork: bool) -> Result<Self> {
+        if priority < policy.priority_min() || priority > policy.priority_max() {
+            return_errno!(EINVAL, "invalid priority for the policy");
+        }
+        Ok(Self {
+            policy,
+            priority,
+            reset_on_fork,
+        })
+    }
+
+    pub fn policy(&self) -> SchedPolicy {
+        self.policy
+    }
+
+    /// The static priority, which is only meaningful for realtime policies.
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    pub fn reset_on_fork(&self) -> bool {
+        self.reset_on_fork
+    }
+
+    /// Get the parameters inherited by a new thread or process.
+    pub fn inherited(&self) -> Self {
+        if self.reset_on_fork {
+            Self::default()
+        } else {
+            *self
+        }
+    }
+}
+
+/// The scheduling parameters used by sched_setparam and sched_getparam.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sched_param_t {
+    pub sched_priority: i32,
+}
+
+/// The scheduling attributes used by sched_setattr and sched_getattr.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sched_attr_t {
+    pub size: u32,
+    pub sched_policy: u32,
+    pub sched_flags: u64,
+    pub sched_nice: i32,
+    pub sched_priority: u32,
+    pub sched_runtime: u64,
+    pub sched_deadline: u64,
+    pub sched_period: u64,
+}
+
+/// The size of the first published version of sched_attr_t.
+pub const SCHED_ATTR_SIZE_VER0: u32 = 48;
+
+bitflags! {
+    pub struct SchedAttrFlags: u64 {
+        const SCHED_FLAG_RESET_ON_FORK = 0x01;
+        const SCHED_FLAG_KEEP_POLICY = 0x08;
+        const SCHED_FLAG_KEEP_PARAMS = 0x10;
+    }
+}
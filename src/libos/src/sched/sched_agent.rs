@@ -2,8 +2,9 @@
 //!
 //! # Scheduler Settings
 //!
-//! Currently, the only scheduler setting that SchedAgent can access and update
-//! is the CPU affinity of a thread. Other settings will be added in the future.
+//! Currently, the scheduler settings that SchedAgent can access and update
+//! are the CPU affinity and the scheduling policy of a thread. Other settings
+//! will be added in the future.
 //!
 //! # The Two Modes: Attached vs Detached
 //!
@@ -22,6 +23,7 @@
 //! host OS thread.
 
 use super::cpu_set::{CpuSet, AVAIL_CPUSET};
+use super::policy::SchedParam;
 use crate::prelude::*;
 use crate::util::dirty::Dirty;
 
@@ -48,30 +50,39 @@ impl Clone for SchedAgent {
     ///
     /// This implementation carefully handles the two points above.
     fn clone(&self) -> Self {
-        let mut affinity = Dirty::new(match self.inner() {
-            Inner::Detached { affinity } => affinity.as_ref().clone(),
-            Inner::Attached { affinity, .. } => affinity.clone(),
-        });
+        let mut affinity = Dirty::new(self.affinity().clone());
         if affinity.as_ref().as_slice() != AVAIL_CPUSET.as_slice() {
             affinity.set_dirty();
         }
+        let mut param = Dirty::new(self.param().inherited());
+        if *param.as_ref() != SchedParam::default() {
+            param.set_dirty();
+        }
         Self {
-            inner: Some(Inner::Detached { affinity }),
+            inner: Some(Inner::Detached { affinity, param }),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 enum Inner {
-    Detached { affinity: Dirty<CpuSet> },
-    Attached { host_tid: pid_t, affinity: CpuSet },
+    Detached {
+        affinity: Dirty<CpuSet>,
+        param: Dirty<SchedParam>,
+    },
+    Attached {
+        host_tid: pid_t,
+        affinity: CpuSet,
+        param: SchedParam,
+    },
 }
 
 impl SchedAgent {
     pub fn new() -> Self {
         let inner = Some({
             let affinity = Dirty::new(AVAIL_CPUSET.clone());
-            Inner::Detached { affinity }
+            let param = Dirty::new(SchedParam::default());
+            Inner::Detached { affinity, param }
         });
         Self { inner }
     }
@@ -85,11 +96,33 @@ impl SchedAgent {
 
     pub fn affinity(&self) -> &CpuSet {
         match self.inner() {
-            Inner::Detached { affinity } => affinity.as_ref(),
+            Inner::Detached { affinity, .. } => affinity.as_ref(),
             Inner::Attached { affinity, .. } => affinity,
         }
     }
 
+    /// Get the scheduling policy and its parameters.
+    pub fn param(&self) -> &SchedParam {
+        match self.inner() {
+            Inner::Detached { param, .. } => param.as_ref(),
+            Inner::Attached { param, .. } => param,
+        }
+    }
+
+    pub fn set_param(&mut self, new_param: SchedParam) {
+        match self.inner_mut() {
+            Inner::Detached { param, .. } => {
+                *param.as_mut() = new_param;
+            }
+            Inner::Attached {
+                host_tid, param, ..
+            } => {
+                update_param(*host_tid, &new_param);
+                *param = new_param;
+            }
+        };
+    }
+
     pub fn set_affinity(&mut self, new_affinity: CpuSet) -> Result<()> {
         if new_affinity.empty() {
             return_errno!(EINVAL, "there must be at least one CPU core in the CpuSet");
@@ -101,10 +134,12 @@ impl SchedAgent {
             );
         }
         match self.inner_mut() {
-            Inner::Detached { affinity } => {
+            Inner::Detached { affinity, .. } => {
                 *affinity.as_mut() = new_affinity;
             }
-            Inner::Attached { host_tid, affinity } => {
+            Inner::Attached {
+                host_tid, affinity, ..
+            } => {
                 update_affinity(*host_tid, &new_affinity);
                 *affinity = new_affinity;
             }
@@ -114,14 +149,24 @@ impl SchedAgent {
 
     pub fn attach(&mut self, host_tid: pid_t) {
         self.update_inner(|inner| match inner {
-            Inner::Detached { affinity } => {
+            Inner::Detached { affinity, param } => {
                 let affinity = {
                     if affinity.dirty() {
                         update_affinity(host_tid, affinity.as_ref())
                     }
                     affinity.unwrap()
                 };
-                Inner::Attached { host_tid, affinity }
+                let param = {
+                    if param.dirty() {
+                        update_param(host_tid, param.as_ref())
+                    }
+                    param.unwrap()
+                };
+                Inner::Attached {
+                    host_tid,
+                    affinity,
+                    param,
+                }
             }
             Inner::Attached { .. } => panic!("cannot attach when the agent is already attached"),
         });
@@ -130,9 +175,19 @@ impl SchedAgent {
     pub fn detach(&mut self) {
         self.update_inner(|inner| match inner {
             Inner::Detached { .. } => panic!("cannot detach when the agent is already detached"),
-            Inner::Attached { affinity, .. } => {
+            Inner::Attached {
+                host_tid,
+                affinity,
+                param,
+            } => {
+                // The host thread may run other LibOS threads later. So don't
+                // leave a realtime policy on it.
+                if param != SchedParam::default() {
+                    update_param(host_tid, &SchedParam::default());
+                }
                 let affinity = Dirty::new(affinity);
-                Inner::Detached { affinity }
+                let param = Dirty::new(param);
+                Inner::Detached { affinity, param }
             }
         });
     }
@@ -179,7 +234,37 @@ fn update_affinity(host_tid: pid_t, affinity: &CpuSet) {
     assert!(retval == 0);
 }
 
+// Applying the scheduling policy to the host thread is best-effort. The host
+// may refuse to do so, e.g., setting a realtime policy without CAP_SYS_NICE,
+// in which case the policy is still recorded and reported by the LibOS.
+fn update_param(host_tid: pid_t, param: &SchedParam) {
+    let mut retval = 0;
+    let sgx_status = unsafe {
+        occlum_ocall_sched_setscheduler(
+            &mut retval,
+            host_tid as i32,
+            param.policy() as i32,
+            param.priority() as i32,
+        )
+    };
+    assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
+    if retval < 0 {
+        warn!(
+            "failed to apply {:?} to the host thread {}: errno = {}",
+            param,
+            host_tid,
+            libc::errno()
+        );
+    }
+}
+
 extern "C" {
+    fn occlum_ocall_sched_setscheduler(
+        ret: *mut i32,
+        host_tid: i32,
+        policy: i32,
+        priority: i32,
+    ) -> sgx_status_t;
     fn occlum_ocall_sched_setaffinity(
         ret: *mut i32,
         host_tid: i32,
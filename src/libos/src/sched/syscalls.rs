@@ -1,7 +1,13 @@
 use super::cpu_set::{CpuSet, AVAIL_CPUSET};
+use super::policy::{
+    sched_attr_t, sched_param_t, SchedAttrFlags, SchedParam, SchedPolicy, SCHED_ATTR_SIZE_VER0,
+    SCHED_RESET_ON_FORK,
+};
 use super::priority::{NiceValue, PrioWhich};
 use crate::prelude::*;
+use crate::time::timespec_t;
 use crate::util::mem_util::from_user::*;
+use crate::vm::PAGE_SIZE;
 use core::convert::TryFrom;
 
 pub fn do_sched_yield() -> Result<isize> {
@@ -96,3 +102,182 @@ pub fn do_get_priority(which: i32, who: i32) -> Result<isize> {
     // has been offset by 20 (ie it returns 40..1 instead of -20..19)
     Ok((20 - nice.to_raw_val()) as _)
 }
+
+pub fn do_sched_setscheduler(
+    pid: i32,
+    policy: i32,
+    param_ptr: *const sched_param_t,
+) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    let priority = read_priority(param_ptr)?;
+    let reset_on_fork = policy & SCHED_RESET_ON_FORK != 0;
+    let policy = SchedPolicy::try_from(policy & !SCHED_RESET_ON_FORK)?;
+    let new_param = SchedParam::new(policy, priority, reset_on_fork)?;
+    super::do_sched_policy::do_sched_setparam(tid, new_param)?;
+    Ok(0)
+}
+
+pub fn do_sched_getscheduler(pid: i32) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    let param = super::do_sched_policy::do_sched_getparam(tid)?;
+    let mut policy = param.policy() as i32;
+    if param.reset_on_fork() {
+        policy |= SCHED_RESET_ON_FORK;
+    }
+    Ok(policy as isize)
+}
+
+pub fn do_sched_setparam(pid: i32, param_ptr: *const sched_param_t) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    let priority = read_priority(param_ptr)?;
+    let old_param = super::do_sched_policy::do_sched_getparam(tid)?;
+    let new_param = SchedParam::new(old_param.policy(), priority, old_param.reset_on_fork())?;
+    super::do_sched_policy::do_sched_setparam(tid, new_param)?;
+    Ok(0)
+}
+
+pub fn do_sched_getparam(pid: i32, param_ptr: *mut sched_param_t) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    if param_ptr.is_null() {
+        return_errno!(EINVAL, "param must not be null");
+    }
+    check_mut_ptr(param_ptr)?;
+    let param = super::do_sched_policy::do_sched_getparam(tid)?;
+    unsafe {
+        *param_ptr = sched_param_t {
+            sched_priority: param.priority() as i32,
+        };
+    }
+    Ok(0)
+}
+
+pub fn do_sched_get_priority_max(policy: i32) -> Result<isize> {
+    let policy = SchedPolicy::try_from(policy)?;
+    Ok(policy.priority_max() as isize)
+}
+
+pub fn do_sched_get_priority_min(policy: i32) -> Result<isize> {
+    let policy = SchedPolicy::try_from(policy)?;
+    Ok(policy.priority_min() as isize)
+}
+
+pub fn do_sched_rr_get_interval(pid: i32, tp: *mut timespec_t) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    check_mut_ptr(tp)?;
+    let interval = super::do_sched_policy::do_sched_rr_get_interval(tid)?;
+    unsafe {
+        *tp = timespec_t::from(interval);
+    }
+    Ok(0)
+}
+
+pub fn do_sched_setattr(pid: i32, attr_ptr: *const sched_attr_t, flags: u32) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    if attr_ptr.is_null() || flags != 0 {
+        return_errno!(EINVAL, "invalid arguments");
+    }
+    let attr = {
+        let size_ptr = attr_ptr as *const u32;
+        check_ptr(size_ptr)?;
+        let size = match unsafe { *size_ptr } {
+            0 => SCHED_ATTR_SIZE_VER0,
+            size => size,
+        };
+        if size < SCHED_ATTR_SIZE_VER0 || size as usize > PAGE_SIZE {
+            return_errno!(E2BIG, "invalid size of sched_attr");
+        }
+        check_array(attr_ptr as *const u8, size as usize)?;
+        // The fields unknown to us must be zeros
+        let bytes = unsafe { std::slice::from_raw_parts(attr_ptr as *const u8, size as usize) };
+        if bytes[SCHED_ATTR_SIZE_VER0 as usize..]
+            .iter()
+            .any(|byte| *byte != 0)
+        {
+            return_errno!(E2BIG, "unknown fields of sched_attr are set");
+        }
+        unsafe { *attr_ptr }
+    };
+    let attr_flags = SchedAttrFlags::from_bits(attr.sched_flags)
+        .ok_or_else(|| errno!(EINVAL, "unsupported sched flags"))?;
+
+    let old_param = super::do_sched_policy::do_sched_getparam(tid)?;
+    let policy = if attr_flags.contains(SchedAttrFlags::SCHED_FLAG_KEEP_POLICY) {
+        old_param.policy()
+    } else {
+        SchedPolicy::try_from(attr.sched_policy as i32)?
+    };
+    let keep_params = attr_flags.contains(SchedAttrFlags::SCHED_FLAG_KEEP_PARAMS);
+    let priority = if keep_params {
+        // The non-realtime policies have no priority
+        if policy.is_realtime() {
+            old_param.priority()
+        } else {
+            0
+        }
+    } else {
+        attr.sched_priority
+    };
+    let reset_on_fork = attr_flags.contains(SchedAttrFlags::SCHED_FLAG_RESET_ON_FORK);
+    let new_param = SchedParam::new(policy, priority, reset_on_fork)?;
+    let nice = if !policy.is_realtime() && !keep_params {
+        Some(NiceValue::from(attr.sched_nice))
+    } else {
+        None
+    };
+    super::do_sched_policy::do_sched_setattr(tid, new_param, nice)?;
+    Ok(0)
+}
+
+pub fn do_sched_getattr(
+    pid: i32,
+    attr_ptr: *mut sched_attr_t,
+    size: u32,
+    flags: u32,
+) -> Result<isize> {
+    let tid = tid_from_pid(pid)?;
+    if attr_ptr.is_null() || flags != 0 || size < SCHED_ATTR_SIZE_VER0 || size as usize > PAGE_SIZE
+    {
+        return_errno!(EINVAL, "invalid arguments");
+    }
+    check_mut_ptr(attr_ptr)?;
+    let (param, nice) = super::do_sched_policy::do_sched_getattr(tid)?;
+    let mut sched_flags = SchedAttrFlags::empty();
+    if param.reset_on_fork() {
+        sched_flags |= SchedAttrFlags::SCHED_FLAG_RESET_ON_FORK;
+    }
+    let attr = sched_attr_t {
+        size: SCHED_ATTR_SIZE_VER0,
+        sched_policy: param.policy() as u32,
+        sched_flags: sched_flags.bits(),
+        sched_nice: if param.policy().is_realtime() {
+            0
+        } else {
+            nice.to_raw_val() as i32
+        },
+        sched_priority: param.priority(),
+        ..Default::default()
+    };
+    unsafe {
+        *attr_ptr = attr;
+    }
+    Ok(0)
+}
+
+fn tid_from_pid(pid: i32) -> Result<pid_t> {
+    if pid < 0 {
+        return_errno!(EINVAL, "pid must not be negative");
+    }
+    Ok(pid as pid_t)
+}
+
+fn read_priority(param_ptr: *const sched_param_t) -> Result<u32> {
+    if param_ptr.is_null() {
+        return_errno!(EINVAL, "param must not be null");
+    }
+    check_ptr(param_ptr)?;
+    let priority = unsafe { (*param_ptr).sched_priority };
+    if priority < 0 {
+        return_errno!(EINVAL, "priority must not be negative");
+    }
+    Ok(priority as u32)
+}
@@ -115,7 +115,7 @@ union siginfo_common_second_t {
 
 #[derive(Clone, Copy)]
 #[repr(C)]
-union siginfo_sigchild_t {
+struct siginfo_sigchild_t {
     status: i32,
     utime: clock_t,
     stime: clock_t,
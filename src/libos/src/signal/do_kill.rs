@@ -1,27 +1,77 @@
+use super::c_types::{siginfo_t, SI_TKILL};
 use super::constants::*;
 use super::signals::{KernelSignal, UserSignal, UserSignalKind};
-use super::{SigNum, Signal};
+use super::{SigNum, SigSet, Signal};
+use crate::misc::resource_t;
 use crate::prelude::*;
-use crate::process::{table, ProcessFilter, ProcessRef, ProcessStatus, ThreadRef, ThreadStatus};
+use crate::process::{
+    continue_process, table, ProcessFilter, ProcessRef, ProcessStatus, ThreadRef, ThreadStatus,
+};
 
 pub fn do_kill(filter: ProcessFilter, signum: SigNum) -> Result<()> {
     debug!("do_kill: filter: {:?}, signum: {:?}", &filter, &signum);
 
-    let pid = current!().process().pid();
-    let uid = 0;
     let processes = get_processes(&filter)?;
     for process in processes {
-        if process.status() == ProcessStatus::Zombie {
-            continue;
-        }
+        do_kill_process(&process, signum)?;
+    }
+    Ok(())
+}
 
-        let signal = Box::new(UserSignal::new(signum, UserSignalKind::Kill, pid, uid));
-        let mut sig_queues = process.sig_queues().write().unwrap();
-        sig_queues.enqueue(signal);
+/// Send a signal to a specific process, e.g., the one referred by a pidfd.
+pub fn do_kill_process(process: &ProcessRef, signum: SigNum) -> Result<()> {
+    if process.status() == ProcessStatus::Zombie {
+        return Ok(());
     }
+
+    let current = current!();
+    let pid = current.process().pid();
+    let uid = current.process().credentials().ruid();
+    let signal = Box::new(UserSignal::new(signum, UserSignalKind::Kill, pid, uid));
+    prepare_signal(process, signum);
+    let mut sig_queues = process.sig_queues().write().unwrap();
+    sig_queues.enqueue(signal);
     Ok(())
 }
 
+/// Apply the side effects of sending a signal to a process, which take
+/// effect even if the signal is blocked or ignored.
+///
+/// Sending SIGCONT discards the pending stop signals and continues the process.
+/// Sending a stop signal discards the pending SIGCONT. Sending SIGKILL wakes up
+/// a stopped process so that the signal can be delivered.
+pub fn prepare_signal(process: &ProcessRef, signum: SigNum) {
+    let stop_sigs = SigSet::from(SIGSTOP) + SIGTSTP + SIGTTIN + SIGTTOU;
+    let discarded_sigs = if signum == SIGCONT {
+        stop_sigs
+    } else if stop_sigs.contains(signum) {
+        SigSet::from(SIGCONT)
+    } else {
+        SigSet::new_empty()
+    };
+    if !discarded_sigs.empty() {
+        process
+            .sig_queues()
+            .write()
+            .unwrap()
+            .discard_std(&discarded_sigs);
+        for thread in process.threads() {
+            thread
+                .sig_queues()
+                .write()
+                .unwrap()
+                .discard_std(&discarded_sigs);
+        }
+    }
+
+    if signum == SIGCONT {
+        continue_process(process, true);
+    } else if signum == SIGKILL {
+        continue_process(process, false);
+        process.ptrace_status().release_stop();
+    }
+}
+
 /// Send a signal from the outside the enclave.
 ///
 /// Such a call must be performed very carefully. The obvious reason
@@ -42,6 +92,7 @@ pub fn do_kill_from_outside_enclave(filter: ProcessFilter, signum: SigNum) -> Re
             continue;
         }
 
+        prepare_signal(&process, signum);
         let mut sig_queues = process.sig_queues().write().unwrap();
         sig_queues.enqueue(signal.clone());
     }
@@ -63,6 +114,13 @@ fn get_processes(filter: &ProcessFilter) -> Result<Vec<ProcessRef>> {
             }
             processes
         }
+        ProcessFilter::WithProcess(pid, _) => {
+            let process = table::get_process(*pid)?;
+            if !filter.matches(&process) {
+                return_errno!(ESRCH, "the process has been reaped");
+            }
+            vec![process]
+        }
     };
     Ok(processes)
 }
@@ -85,8 +143,9 @@ pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
     }
 
     let signal = {
-        let src_pid = current!().process().pid();
-        let src_uid = 0;
+        let current = current!();
+        let src_pid = current.process().pid();
+        let src_uid = current.process().credentials().ruid();
         Box::new(UserSignal::new(
             signum,
             UserSignalKind::Tkill,
@@ -94,7 +153,117 @@ pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
             src_uid,
         ))
     };
+    prepare_signal(thread.process(), signum);
     let mut sig_queues = thread.sig_queues().write().unwrap();
     sig_queues.enqueue(signal);
     Ok(())
 }
+
+/// Queue a signal with the value in `info` to a process, or to the thread
+/// `tid` of the process if it is given, i.e., sigqueue(3).
+///
+/// If `signum` is `None`, only check the existence of the target.
+pub fn do_sigqueue(
+    pid: pid_t,
+    tid: Option<pid_t>,
+    signum: Option<SigNum>,
+    info: &siginfo_t,
+) -> Result<()> {
+    debug!(
+        "do_sigqueue: pid: {:?}, tid: {:?}, signum: {:?}",
+        &pid, &tid, &signum
+    );
+
+    let current = current!();
+    let src_pid = current.process().pid();
+    // Only a process can send the signals with any code to itself. Otherwise,
+    // the signals could pretend to be sent by kill or the kernel.
+    if pid != src_pid && (info.si_code >= 0 || info.si_code == SI_TKILL) {
+        return_errno!(EPERM, "not allowed to send a signal with this code");
+    }
+
+    let (process, thread) = match tid {
+        Some(tid) => {
+            let thread =
+                table::get_thread(tid).map_err(|_| errno!(ESRCH, "thread does not exist"))?;
+            if thread.process().pid() != pid {
+                return_errno!(ESRCH, "the thread is not in the process");
+            }
+            (thread.process().clone(), Some(thread))
+        }
+        None => {
+            let process =
+                table::get_process(pid).map_err(|_| errno!(ESRCH, "process does not exist"))?;
+            (process, None)
+        }
+    };
+
+    let signum = match signum {
+        Some(signum) => signum,
+        None => return Ok(()),
+    };
+    if process.status() == ProcessStatus::Zombie
+        || thread
+            .as_ref()
+            .map_or(false, |thread| thread.status() == ThreadStatus::Exited)
+    {
+        return Ok(());
+    }
+
+    // Unlike the real-time signals sent by kill, the queued ones are limited
+    if signum.is_real_time() {
+        check_sigpending_limit(&process)?;
+    }
+
+    let signal = {
+        let src_uid = current.process().credentials().ruid();
+        let value = info.si_value();
+        Box::new(UserSignal::new(
+            signum,
+            UserSignalKind::Sigqueue(info.si_code, value),
+            src_pid,
+            src_uid,
+        ))
+    };
+    prepare_signal(&process, signum);
+    match thread {
+        Some(thread) => thread.sig_queues().write().unwrap().enqueue(signal),
+        None => process.sig_queues().write().unwrap().enqueue(signal),
+    }
+    Ok(())
+}
+
+// Like Linux, the pending signals of all the processes of the same real user
+// are limited by the RLIMIT_SIGPENDING of the target process.
+fn check_sigpending_limit(process: &ProcessRef) -> Result<()> {
+    let limit = match process.threads().first() {
+        Some(thread) => thread
+            .rlimits()
+            .lock()
+            .unwrap()
+            .get(resource_t::RLIMIT_SIGPENDING)
+            .get_cur(),
+        None => return Ok(()),
+    };
+
+    let ruid = process.credentials().ruid();
+    let num_pending: usize = table::get_all_processes()
+        .iter()
+        .filter(|process| process.credentials().ruid() == ruid)
+        .map(|process| num_pending_signals(process))
+        .sum();
+    if num_pending as u64 >= limit {
+        return_errno!(EAGAIN, "too many pending signals");
+    }
+    Ok(())
+}
+
+// The pending signals of a process, including the ones of its threads
+fn num_pending_signals(process: &ProcessRef) -> usize {
+    process.sig_queues().read().unwrap().len()
+        + process
+            .threads()
+            .iter()
+            .map(|thread| thread.sig_queues().read().unwrap().len())
+            .sum::<usize>()
+}
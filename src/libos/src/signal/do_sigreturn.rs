@@ -4,7 +4,7 @@ use super::sig_stack::SigStackFlags;
 use super::{SigAction, SigActionFlags, SigDefaultAction, SigSet, Signal};
 use crate::lazy_static::__Deref;
 use crate::prelude::*;
-use crate::process::{ProcessRef, TermStatus, ThreadRef};
+use crate::process::{stop_current_process, ProcessRef, TermStatus, ThreadRef};
 use crate::syscall::{BoxXsaveArea, CpuContext, ExtraContext, FpRegs};
 use aligned::{Aligned, A16};
 use std::mem::ManuallyDrop;
@@ -197,13 +197,12 @@ fn handle_signal(
                     false
                 }
                 SigDefaultAction::Stop => {
-                    warn!("SIGSTOP is unsupported");
-                    true
-                }
-                SigDefaultAction::Cont => {
-                    warn!("SIGCONT is unsupported");
+                    // The thread is parked until the process is continued
+                    stop_current_process(signal.num());
                     true
                 }
+                // The process has been continued when SIGCONT is sent
+                SigDefaultAction::Cont => true,
             }
         }
         SigAction::User {
@@ -4,9 +4,13 @@ use crate::prelude::*;
 
 use sig_action::{SigAction, SigActionFlags, SigDefaultAction};
 
-pub use self::c_types::{sigaction_t, siginfo_t, sigset_t, stack_t};
+pub use self::c_types::{
+    sigaction_t, siginfo_t, sigset_t, stack_t, CLD_CONTINUED, CLD_EXITED, CLD_KILLED, CLD_STOPPED,
+    CLD_TRAPPED,
+};
 pub use self::constants::*;
 pub use self::do_kill::do_kill_from_outside_enclave;
+pub use self::do_kill::{do_kill_process, prepare_signal};
 pub use self::do_sigprocmask::MaskOp;
 pub use self::do_sigreturn::{deliver_signal, force_signal};
 pub use self::sig_dispositions::SigDispositions;
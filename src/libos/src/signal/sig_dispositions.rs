@@ -1,7 +1,7 @@
 use std::fmt;
 
 use super::constants::*;
-use super::{SigAction, SigNum};
+use super::{SigAction, SigActionFlags, SigNum};
 use crate::prelude::*;
 
 #[derive(Copy, Clone)]
@@ -32,6 +32,15 @@ impl SigDispositions {
         self.map[idx] = SigAction::Dfl;
     }
 
+    /// Whether SA_NOCLDSTOP is set for SIGCHLD, i.e., no SIGCHLD is sent when
+    /// a child stops or continues.
+    pub fn is_nocldstop(&self) -> bool {
+        match self.get(SIGCHLD) {
+            SigAction::User { flags, .. } => flags.contains(SigActionFlags::SA_NOCLDSTOP),
+            _ => false,
+        }
+    }
+
     pub fn iter<'a>(&'a self) -> SigDispositionsIter<'a> {
         SigDispositionsIter::new(self)
     }
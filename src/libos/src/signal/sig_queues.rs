@@ -38,6 +38,11 @@ impl SigQueues {
         self.count == 0
     }
 
+    /// The number of pending signals.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
     pub fn enqueue(&mut self, signal: Box<dyn Signal>) {
         let signum = signal.num();
         if signum.is_std() {
@@ -127,6 +132,20 @@ impl SigQueues {
         None
     }
 
+    /// Discard the pending standard signals in `sigs`.
+    pub fn discard_std(&mut self, sigs: &SigSet) {
+        for &signum in &Self::ORDERED_STD_SIGS {
+            if !sigs.contains(signum) {
+                continue;
+            }
+
+            let queue = self.get_std_queue_mut(signum);
+            if queue.take().is_some() {
+                self.count -= 1;
+            }
+        }
+    }
+
     pub fn notifier(&self) -> &Notifier<SigNum, SigSet> {
         &self.notifier
     }
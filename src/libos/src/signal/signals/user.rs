@@ -15,7 +15,8 @@ pub struct UserSignal {
 pub enum UserSignalKind {
     Kill,
     Tkill,
-    Sigqueue(sigval_t),
+    /// The code given by the sender, which is not rewritten, and the value
+    Sigqueue(i32, sigval_t),
 }
 
 unsafe impl Sync for UserSignalKind {}
@@ -53,13 +54,13 @@ impl Signal for UserSignal {
         let code = match self.kind {
             UserSignalKind::Kill => SI_USER,
             UserSignalKind::Tkill => SI_TKILL,
-            UserSignalKind::Sigqueue(_) => SI_QUEUE,
+            UserSignalKind::Sigqueue(code, _) => code,
         };
 
         let mut info = siginfo_t::new(self.num, code);
         info.set_si_pid(self.pid);
         info.set_si_uid(self.uid);
-        if let UserSignalKind::Sigqueue(val) = self.kind {
+        if let UserSignalKind::Sigqueue(_, val) = self.kind {
             info.set_si_value(val);
         }
 
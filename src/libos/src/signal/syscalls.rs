@@ -86,6 +86,38 @@ pub fn do_tgkill(pid: i32, tid: pid_t, sig: c_int) -> Result<isize> {
     Ok(0)
 }
 
+pub fn do_rt_sigqueueinfo(pid: i32, sig: c_int, info_ptr: *const siginfo_t) -> Result<isize> {
+    from_user::check_ptr(info_ptr)?;
+    let info = unsafe { &*info_ptr };
+    let signum = if sig == 0 {
+        None
+    } else {
+        Some(SigNum::from_u8(sig as u8)?)
+    };
+    super::do_kill::do_sigqueue(pid as pid_t, None, signum, info)?;
+    Ok(0)
+}
+
+pub fn do_rt_tgsigqueueinfo(
+    pid: i32,
+    tid: i32,
+    sig: c_int,
+    info_ptr: *const siginfo_t,
+) -> Result<isize> {
+    if pid <= 0 || tid <= 0 {
+        return_errno!(EINVAL, "invalid pid or tid");
+    }
+    from_user::check_ptr(info_ptr)?;
+    let info = unsafe { &*info_ptr };
+    let signum = if sig == 0 {
+        None
+    } else {
+        Some(SigNum::from_u8(sig as u8)?)
+    };
+    super::do_kill::do_sigqueue(pid as pid_t, Some(tid as pid_t), signum, info)?;
+    Ok(0)
+}
+
 pub fn do_rt_sigprocmask(
     how: c_int,
     set_ptr: *const sigset_t,
@@ -25,17 +25,19 @@ use util::mem_util::from_user::*;
 use crate::config::user_rootfs_config;
 use crate::exception::do_handle_exception;
 use crate::fs::{
-    do_access, do_chdir, do_chmod, do_chown, do_close, do_creat, do_dup, do_dup2, do_dup3,
-    do_eventfd, do_eventfd2, do_faccessat, do_fallocate, do_fchdir, do_fchmod, do_fchmodat,
-    do_fchown, do_fchownat, do_fcntl, do_fdatasync, do_flock, do_fstat, do_fstatat, do_fstatfs,
-    do_fsync, do_ftruncate, do_futimesat, do_getcwd, do_getdents, do_getdents64, do_ioctl,
-    do_lchown, do_link, do_linkat, do_lseek, do_lstat, do_mkdir, do_mkdirat, do_mount,
-    do_mount_rootfs, do_open, do_openat, do_pipe, do_pipe2, do_pread, do_preadv, do_pwrite,
-    do_pwritev, do_read, do_readlink, do_readlinkat, do_readv, do_rename, do_renameat, do_rmdir,
-    do_sendfile, do_stat, do_statfs, do_symlink, do_symlinkat, do_sync, do_timerfd_create,
-    do_timerfd_gettime, do_timerfd_settime, do_truncate, do_umask, do_umount, do_unlink,
-    do_unlinkat, do_utime, do_utimensat, do_utimes, do_write, do_writev, iovec_t, utimbuf_t,
-    AsTimer, File, FileDesc, FileRef, HostStdioFds, Stat, Statfs,
+    do_access, do_chdir, do_chmod, do_chown, do_chroot, do_close, do_creat, do_dup, do_dup2,
+    do_dup3, do_eventfd, do_eventfd2, do_faccessat, do_fallocate, do_fchdir, do_fchmod,
+    do_fchmodat, do_fchown, do_fchownat, do_fcntl, do_fdatasync, do_flock, do_fstat, do_fstatat,
+    do_fstatfs, do_fsync, do_ftruncate, do_futimesat, do_getcwd, do_getdents, do_getdents64,
+    do_io_cancel, do_io_destroy, do_io_getevents, do_io_pgetevents, do_io_setup, do_io_submit,
+    do_io_uring_enter, do_io_uring_register, do_io_uring_setup, do_ioctl, do_lchown, do_link,
+    do_linkat, do_lseek, do_lstat, do_mkdir, do_mkdirat, do_mount, do_mount_rootfs, do_open,
+    do_openat, do_pipe, do_pipe2, do_pivot_root, do_pread, do_preadv, do_pwrite, do_pwritev,
+    do_read, do_readlink, do_readlinkat, do_readv, do_rename, do_renameat, do_rmdir, do_sendfile,
+    do_stat, do_statfs, do_symlink, do_symlinkat, do_sync, do_timerfd_create, do_timerfd_gettime,
+    do_timerfd_settime, do_truncate, do_umask, do_umount, do_unlink, do_unlinkat, do_utime,
+    do_utimensat, do_utimes, do_write, do_writev, io_event, io_uring_params, iocb, iovec_t,
+    utimbuf_t, AsTimer, File, FileDesc, FileRef, HostStdioFds, Stat, Statfs,
 };
 use crate::interrupt::{do_handle_interrupt, sgx_interrupt_info_t};
 use crate::ipc::{do_shmat, do_shmctl, do_shmdt, do_shmget, key_t, shmids_t};
@@ -49,18 +51,23 @@ use crate::net::{
 use crate::process::{
     do_arch_prctl, do_clone, do_execve, do_exit, do_exit_group, do_futex, do_get_robust_list,
     do_getegid, do_geteuid, do_getgid, do_getgroups, do_getpgid, do_getpgrp, do_getpid, do_getppid,
-    do_gettid, do_getuid, do_prctl, do_set_robust_list, do_set_tid_address, do_setpgid,
-    do_spawn_for_glibc, do_spawn_for_musl, do_vfork, do_wait4, pid_t, posix_spawnattr_t, FdOp,
-    RobustListHead, SpawnFileActions, ThreadStatus,
+    do_getresgid, do_getresuid, do_getrusage, do_getsid, do_gettid, do_getuid, do_pidfd_open,
+    do_pidfd_send_signal, do_prctl, do_ptrace, do_set_robust_list, do_set_tid_address, do_setfsgid,
+    do_setfsuid, do_setgid, do_setgroups, do_setpgid, do_setregid, do_setresgid, do_setresuid,
+    do_setreuid, do_setsid, do_setuid, do_spawn_for_glibc, do_spawn_for_musl, do_times, do_vfork,
+    do_wait4, do_waitid, pid_t, posix_spawnattr_t, rusage_t, tms_t, FdOp, RobustListHead,
+    SpawnFileActions, ThreadStatus,
 };
 use crate::sched::{
-    do_get_priority, do_getcpu, do_sched_getaffinity, do_sched_setaffinity, do_sched_yield,
-    do_set_priority,
+    do_get_priority, do_getcpu, do_sched_get_priority_max, do_sched_get_priority_min,
+    do_sched_getaffinity, do_sched_getattr, do_sched_getparam, do_sched_getscheduler,
+    do_sched_rr_get_interval, do_sched_setaffinity, do_sched_setattr, do_sched_setparam,
+    do_sched_setscheduler, do_sched_yield, do_set_priority, sched_attr_t, sched_param_t,
 };
 use crate::signal::{
-    do_kill, do_rt_sigaction, do_rt_sigpending, do_rt_sigprocmask, do_rt_sigreturn,
-    do_rt_sigsuspend, do_rt_sigtimedwait, do_sigaltstack, do_tgkill, do_tkill, sigaction_t,
-    siginfo_t, sigset_t, stack_t,
+    do_kill, do_rt_sigaction, do_rt_sigpending, do_rt_sigprocmask, do_rt_sigqueueinfo,
+    do_rt_sigreturn, do_rt_sigsuspend, do_rt_sigtimedwait, do_rt_tgsigqueueinfo, do_sigaltstack,
+    do_tgkill, do_tkill, sigaction_t, siginfo_t, sigset_t, stack_t,
 };
 use crate::vm::{MMapFlags, MRemapFlags, MSyncFlags, MadviceFlags, VMPerms};
 use crate::{fs, process, std, vm};
@@ -194,38 +201,38 @@ macro_rules! process_syscall_table_with_callback {
             (Umask = 95) => do_umask(mask: u16),
             (Gettimeofday = 96) => do_gettimeofday(tv_u: *mut timeval_t),
             (Getrlimit = 97) => do_gettrlimit(resource: u32, rlim: *mut rlimit_t),
-            (Getrusage = 98) => handle_unsupported(),
+            (Getrusage = 98) => do_getrusage(who: i32, ru_u: *mut rusage_t),
             (SysInfo = 99) => do_sysinfo(info: *mut sysinfo_t),
-            (Times = 100) => handle_unsupported(),
-            (Ptrace = 101) => handle_unsupported(),
+            (Times = 100) => do_times(buf: *mut tms_t),
+            (Ptrace = 101) => do_ptrace(request: i32, pid: pid_t, addr: usize, data: usize),
             (Getuid = 102) => do_getuid(),
             (SysLog = 103) => handle_unsupported(),
             (Getgid = 104) => do_getgid(),
-            (Setuid = 105) => handle_unsupported(),
-            (Setgid = 106) => handle_unsupported(),
+            (Setuid = 105) => do_setuid(uid: u32),
+            (Setgid = 106) => do_setgid(gid: u32),
             (Geteuid = 107) => do_geteuid(),
             (Getegid = 108) => do_getegid(),
             (Setpgid = 109) => do_setpgid(pid: i32, pgid: i32),
             (Getppid = 110) => do_getppid(),
             (Getpgrp = 111) => do_getpgrp(),
-            (Setsid = 112) => handle_unsupported(),
-            (Setreuid = 113) => handle_unsupported(),
-            (Setregid = 114) => handle_unsupported(),
+            (Setsid = 112) => do_setsid(),
+            (Setreuid = 113) => do_setreuid(ruid: u32, euid: u32),
+            (Setregid = 114) => do_setregid(rgid: u32, egid: u32),
             (Getgroups = 115) => do_getgroups(size: isize, buf_ptr: *mut u32),
-            (Setgroups = 116) => handle_unsupported(),
-            (Setresuid = 117) => handle_unsupported(),
-            (Getresuid = 118) => handle_unsupported(),
-            (Setresgid = 119) => handle_unsupported(),
-            (Getresgid = 120) => handle_unsupported(),
+            (Setgroups = 116) => do_setgroups(size: isize, buf_ptr: *const u32),
+            (Setresuid = 117) => do_setresuid(ruid: u32, euid: u32, suid: u32),
+            (Getresuid = 118) => do_getresuid(ruid_ptr: *mut u32, euid_ptr: *mut u32, suid_ptr: *mut u32),
+            (Setresgid = 119) => do_setresgid(rgid: u32, egid: u32, sgid: u32),
+            (Getresgid = 120) => do_getresgid(rgid_ptr: *mut u32, egid_ptr: *mut u32, sgid_ptr: *mut u32),
             (Getpgid = 121) => do_getpgid(pid: i32),
-            (Setfsuid = 122) => handle_unsupported(),
-            (Setfsgid = 123) => handle_unsupported(),
-            (Getsid = 124) => handle_unsupported(),
+            (Setfsuid = 122) => do_setfsuid(fsuid: u32),
+            (Setfsgid = 123) => do_setfsgid(fsgid: u32),
+            (Getsid = 124) => do_getsid(pid: i32),
             (Capget = 125) => handle_unsupported(),
             (Capset = 126) => handle_unsupported(),
             (RtSigpending = 127) => do_rt_sigpending(buf_ptr: *mut sigset_t, buf_size: usize),
             (RtSigtimedwait = 128) => do_rt_sigtimedwait(mask_ptr: *const sigset_t, info_ptr: *mut siginfo_t, timeout_ptr: *const timespec_t, mask_size: usize),
-            (RtSigqueueinfo = 129) => handle_unsupported(),
+            (RtSigqueueinfo = 129) => do_rt_sigqueueinfo(pid: i32, sig: c_int, info_ptr: *const siginfo_t),
             (RtSigsuspend = 130) => do_rt_sigsuspend(mask_ptr: *const sigset_t),
             (Sigaltstack = 131) => do_sigaltstack(ss: *const stack_t, old_ss: *mut stack_t, context: *const CpuContext),
             (Utime = 132) => do_utime(path: *const i8, times: *const utimbuf_t),
@@ -238,26 +245,26 @@ macro_rules! process_syscall_table_with_callback {
             (SysFs = 139) => handle_unsupported(),
             (Getpriority = 140) => do_get_priority(which: i32, who: i32),
             (Setpriority = 141) => do_set_priority(which: i32, who: i32, prio: i32),
-            (SchedSetparam = 142) => handle_unsupported(),
-            (SchedGetparam = 143) => handle_unsupported(),
-            (SchedSetscheduler = 144) => handle_unsupported(),
-            (SchedGetscheduler = 145) => handle_unsupported(),
-            (SchedGetPriorityMax = 146) => handle_unsupported(),
-            (SchedGetPriorityMin = 147) => handle_unsupported(),
-            (SchedRrGetInterval = 148) => handle_unsupported(),
+            (SchedSetparam = 142) => do_sched_setparam(pid: i32, param: *const sched_param_t),
+            (SchedGetparam = 143) => do_sched_getparam(pid: i32, param: *mut sched_param_t),
+            (SchedSetscheduler = 144) => do_sched_setscheduler(pid: i32, policy: i32, param: *const sched_param_t),
+            (SchedGetscheduler = 145) => do_sched_getscheduler(pid: i32),
+            (SchedGetPriorityMax = 146) => do_sched_get_priority_max(policy: i32),
+            (SchedGetPriorityMin = 147) => do_sched_get_priority_min(policy: i32),
+            (SchedRrGetInterval = 148) => do_sched_rr_get_interval(pid: i32, tp: *mut timespec_t),
             (Mlock = 149) => handle_unsupported(),
             (Munlock = 150) => handle_unsupported(),
             (Mlockall = 151) => handle_unsupported(),
             (Munlockall = 152) => handle_unsupported(),
             (Vhangup = 153) => handle_unsupported(),
             (ModifyLdt = 154) => handle_unsupported(),
-            (PivotRoot = 155) => handle_unsupported(),
+            (PivotRoot = 155) => do_pivot_root(new_root: *const i8, put_old: *const i8),
             (SysCtl = 156) => handle_unsupported(),
             (Prctl = 157) => do_prctl(option: i32, arg2: u64, arg3: u64, arg4: u64, arg5: u64),
             (ArchPrctl = 158) => do_arch_prctl(code: u32, addr: *mut usize),
             (Adjtimex = 159) => handle_unsupported(),
             (Setrlimit = 160) => do_settrlimit(resource: u32, rlim: *const rlimit_t),
-            (Chroot = 161) => handle_unsupported(),
+            (Chroot = 161) => do_chroot(path: *const i8),
             (Sync = 162) => do_sync(),
             (Acct = 163) => handle_unsupported(),
             (Settimeofday = 164) => handle_unsupported(),
@@ -302,11 +309,11 @@ macro_rules! process_syscall_table_with_callback {
             (SchedSetaffinity = 203) => do_sched_setaffinity(pid: pid_t, cpusize: size_t, buf: *const c_uchar),
             (SchedGetaffinity = 204) => do_sched_getaffinity(pid: pid_t, cpusize: size_t, buf: *mut c_uchar),
             (SetThreadArea = 205) => handle_unsupported(),
-            (IoSetup = 206) => handle_unsupported(),
-            (IoDestroy = 207) => handle_unsupported(),
-            (IoGetevents = 208) => handle_unsupported(),
-            (IoSubmit = 209) => handle_unsupported(),
-            (IoCancel = 210) => handle_unsupported(),
+            (IoSetup = 206) => do_io_setup(nr_events: u32, ctx_ptr: *mut u64),
+            (IoDestroy = 207) => do_io_destroy(ctx_id: u64),
+            (IoGetevents = 208) => do_io_getevents(ctx_id: u64, min_nr: i64, nr: i64, events_ptr: *mut io_event, timeout_ptr: *const timespec_t),
+            (IoSubmit = 209) => do_io_submit(ctx_id: u64, nr: i64, iocbs_ptr: *const *const iocb),
+            (IoCancel = 210) => do_io_cancel(ctx_id: u64, iocb_ptr: *const iocb, result: *mut io_event),
             (GetThreadArea = 211) => handle_unsupported(),
             (LookupDcookie = 212) => handle_unsupported(),
             (EpollCreate = 213) => do_epoll_create(size: c_int),
@@ -343,7 +350,7 @@ macro_rules! process_syscall_table_with_callback {
             (MqNotify = 244) => handle_unsupported(),
             (MqGetsetattr = 245) => handle_unsupported(),
             (KexecLoad = 246) => handle_unsupported(),
-            (Waitid = 247) => handle_unsupported(),
+            (Waitid = 247) => do_waitid(idtype: u32, id: i32, infop: *mut siginfo_t, options: u32),
             (AddKey = 248) => handle_unsupported(),
             (RequestKey = 249) => handle_unsupported(),
             (Keyctl = 250) => handle_unsupported(),
@@ -393,7 +400,7 @@ macro_rules! process_syscall_table_with_callback {
             (InotifyInit1 = 294) => handle_unsupported(),
             (Preadv = 295) => do_preadv(fd: FileDesc, iov: *mut iovec_t, count: i32, offset: off_t),
             (Pwritev = 296) => do_pwritev(fd: FileDesc, iov: *const iovec_t, count: i32, offset: off_t),
-            (RtTgsigqueueinfo = 297) => handle_unsupported(),
+            (RtTgsigqueueinfo = 297) => do_rt_tgsigqueueinfo(pid: i32, tid: i32, sig: c_int, info_ptr: *const siginfo_t),
             (PerfEventOpen = 298) => handle_unsupported(),
             (Recvmmsg = 299) => handle_unsupported(),
             (FanotifyInit = 300) => handle_unsupported(),
@@ -410,8 +417,8 @@ macro_rules! process_syscall_table_with_callback {
             (ProcessVmWritev = 311) => handle_unsupported(),
             (Kcmp = 312) => handle_unsupported(),
             (FinitModule = 313) => handle_unsupported(),
-            (SchedSetattr = 314) => handle_unsupported(),
-            (SchedGetattr = 315) => handle_unsupported(),
+            (SchedSetattr = 314) => do_sched_setattr(pid: i32, attr: *const sched_attr_t, flags: u32),
+            (SchedGetattr = 315) => do_sched_getattr(pid: i32, attr: *mut sched_attr_t, size: u32, flags: u32),
             (Renameat2 = 316) => handle_unsupported(),
             (Seccomp = 317) => handle_unsupported(),
             (Getrandom = 318) => do_getrandom(buf: *mut u8, len: size_t, flags: u32),
@@ -429,8 +436,13 @@ macro_rules! process_syscall_table_with_callback {
             (PkeyAlloc = 330) => handle_unsupported(),
             (PkeyFree = 331) => handle_unsupported(),
             (Statx = 332) => handle_unsupported(),
-            (IoPgetevents = 333) => handle_unsupported(),
+            (IoPgetevents = 333) => do_io_pgetevents(ctx_id: u64, min_nr: i64, nr: i64, events_ptr: *mut io_event, timeout_ptr: *const timespec_t, sigmask: *const c_void),
             (Rseq = 334) => handle_unsupported(),
+            (PidfdSendSignal = 424) => do_pidfd_send_signal(pidfd: FileDesc, sig: c_int, info: *const siginfo_t, flags: u32),
+            (IoUringSetup = 425) => do_io_uring_setup(entries: u32, params: *mut io_uring_params),
+            (IoUringEnter = 426) => do_io_uring_enter(fd: FileDesc, to_submit: u32, min_complete: u32, flags: u32, sigmask: *const c_void, sigsz: size_t),
+            (IoUringRegister = 427) => do_io_uring_register(fd: FileDesc, opcode: u32, arg: *const c_void, nr_args: u32),
+            (PidfdOpen = 434) => do_pidfd_open(pid: i32, flags: u32),
 
             // Occlum-specific system calls
             (SpawnGlibc = 359) => do_spawn_for_glibc(child_pid_ptr: *mut u32, path: *const i8, argv: *const *const i8, envp: *const *const i8, fa: *const SpawnFileActions, attribute_list: *const posix_spawnattr_t),
@@ -639,6 +651,16 @@ fn do_syscall(user_context: &mut CpuContext) {
     let arg4 = user_context.r8 as isize;
     let arg5 = user_context.r9 as isize;
 
+    // Account the time spent in the user space until now
+    crate::process::current::with(|thread| thread.usage().enter_kernel());
+
+    // Exceptions and interrupts are not syscalls from the view of a tracer
+    let is_traceable =
+        num != SyscallNum::HandleException as u32 && num != SyscallNum::HandleInterrupt as u32;
+    if is_traceable {
+        crate::process::ptrace_syscall_enter(user_context);
+    }
+
     let ret = Syscall::new(num, arg0, arg1, arg2, arg3, arg4, arg5).and_then(|mut syscall| {
         log::set_round_desc(Some(syscall.num.as_str()));
         trace!("{:?}", &syscall);
@@ -723,7 +745,6 @@ fn do_syscall(user_context: &mut CpuContext) {
                         _ => true,
                     },
                     ENOSYS => match syscall_num {
-                        SyscallNum::Getrusage => false,
                         SyscallNum::Madvise => false,
                         SyscallNum::Ioctl => false,
                         _ => true,
@@ -759,11 +780,20 @@ fn do_syscall(user_context: &mut CpuContext) {
         user_context.rax = retval as u64;
     }
 
+    if is_traceable {
+        crate::process::ptrace_syscall_exit(user_context, num);
+    }
+
+    // Park the thread if its process has been stopped by a stop signal
+    crate::process::handle_stop();
+
     crate::signal::deliver_signal(user_context);
 
     crate::process::handle_force_stop();
 
     crate::process::handle_force_exit();
+
+    crate::process::current::with(|thread| thread.usage().exit_kernel());
 }
 
 /// Return to the user space according to the given CPU context
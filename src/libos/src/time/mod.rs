@@ -47,7 +47,7 @@ fn init_vdso() {
 }
 
 #[inline(always)]
-fn is_enable_vdso() -> bool {
+pub(crate) fn is_enable_vdso() -> bool {
     IS_ENABLE_VDSO.get().map_or(false, |is_enable| *is_enable)
 }
 
@@ -242,9 +242,9 @@ pub fn do_clock_nanosleep(
             return_errno!(EOPNOTSUPP, "does not support sleeping against this clockid");
         }
     }
-    let sgx_status = unsafe {
+    let sgx_status = crate::process::account_sleep(|| unsafe {
         occlum_ocall_clock_nanosleep(&mut ret, clockid as clockid_t, flags, req, &mut u_rem)
-    };
+    });
     assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
     assert!(ret == 0 || ret == Errno::EINTR as i32);
     if ret != 0 {
@@ -285,6 +285,29 @@ pub fn do_thread_getcpuclock() -> Result<timespec_t> {
     Ok(tv)
 }
 
+/// Get the user and system CPU time of a host thread.
+pub fn do_host_thread_cputime(host_tid: pid_t) -> Result<(Duration, Duration)> {
+    extern "C" {
+        fn occlum_ocall_get_thread_cputime(
+            ret: *mut c_int,
+            host_tid: c_int,
+            utime: *mut u64,
+            stime: *mut u64,
+        ) -> sgx_status_t;
+    }
+
+    let mut utime = 0;
+    let mut stime = 0;
+    try_libc!({
+        let mut retval: i32 = 0;
+        let status =
+            occlum_ocall_get_thread_cputime(&mut retval, host_tid as c_int, &mut utime, &mut stime);
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        retval
+    });
+    Ok((Duration::from_nanos(utime), Duration::from_nanos(stime)))
+}
+
 pub fn do_rdtsc() -> (u32, u32) {
     extern "C" {
         fn occlum_ocall_rdtsc(low: *mut u32, high: *mut u32) -> sgx_status_t;
@@ -135,6 +135,24 @@ impl UntrustedCircularBuf {
         })
     }
 
+    /// Copy the bytes starting at `offset` of the consumable bytes to `buf`
+    /// without consuming them.
+    pub fn peek(&self, offset: usize, buf: &mut [u8]) -> usize {
+        let consumable = self.consumable();
+        if offset >= consumable {
+            return 0;
+        }
+        let peek_len = buf.len().min(consumable - offset);
+        let len = self.buf.len();
+        let start = (self.head + offset) % len;
+        // The bytes to peek may wrap around the end of the buffer
+        let part0_len = peek_len.min(len - start);
+        buf[..part0_len].copy_from_slice(&self.buf[start..start + part0_len]);
+        let part1_len = peek_len - part0_len;
+        buf[part0_len..peek_len].copy_from_slice(&self.buf[..part1_len]);
+        peek_len
+    }
+
     pub fn consume_without_copy(&mut self, len: usize) -> usize {
         self.with_consumer_view(|part0, part1| len.min(part0.len() + part1.len()))
     }
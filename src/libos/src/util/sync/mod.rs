@@ -1,7 +1,7 @@
 use super::*;
 
 pub use mutex::{Mutex, MutexGuard};
-pub use rw_lock::RwLock;
+pub use rw_lock::{RwLock, RwLockReadGuard};
 
 pub mod mutex;
 pub mod rw_lock;
@@ -57,7 +57,10 @@ running in the same Occlum instance can use dramatically different sizes of memo
 */
 
 use super::*;
-use fs::{File, FileDesc, FileRef};
+use fs::{
+    check_io_urings_mprotect, detach_io_urings_in_range, AsINodeFile, AsIoUring, File, FileDesc,
+    FileRef,
+};
 use process::{Process, ProcessRef};
 use std::fmt;
 
@@ -122,12 +125,24 @@ pub fn do_mmap(
         );
     }
 
+    if !flags.contains(MMapFlags::MAP_ANONYMOUS) {
+        let file = current!().file(fd)?;
+        if let Ok(uring) = file.as_io_uring() {
+            // The rings of io_uring have been allocated in the user space
+            return uring.mmap(size, flags, offset);
+        }
+    }
+
+    if flags.contains(MMapFlags::MAP_FIXED) {
+        // The old mappings in the range are replaced
+        detach_io_urings(addr, size);
+    }
     current!().vm().mmap(addr, size, perms, flags, fd, offset)
 }
 
 pub fn do_munmap(addr: usize, size: usize) -> Result<()> {
     debug!("munmap: addr: {:#x}, size: {:#x}", addr, size);
-    let current = current!();
+    detach_io_urings(addr, size);
     current!().vm().munmap(addr, size)
 }
 
@@ -141,14 +156,32 @@ pub fn do_mremap(
         "mremap: old_addr: {:#x}, old_size: {:#x}, new_size: {:#x}, flags: {:?}",
         old_addr, old_size, new_size, flags
     );
+    // The rings of io_uring cannot be moved, and the old mappings at the new
+    // address are replaced
+    detach_io_urings(old_addr, old_size);
+    if let Some(new_addr) = flags.new_addr() {
+        detach_io_urings(new_addr, new_size);
+    }
     current!().vm().mremap(old_addr, old_size, new_size, flags)
 }
 
+/// Detach the io_urings whose rings are in the range, which is going to be
+/// unmapped or replaced.
+fn detach_io_urings(addr: usize, size: usize) {
+    if let Ok(range) = VMRange::new_with_size(addr, align_up(size, PAGE_SIZE)) {
+        detach_io_urings_in_range(current!().process().pid(), &range);
+    }
+}
+
 pub fn do_mprotect(addr: usize, size: usize, perms: VMPerms) -> Result<()> {
     debug!(
         "mprotect: addr: {:#x}, size: {:#x}, perms: {:?}",
         addr, size, perms
     );
+    // The LibOS keeps accessing the rings of io_uring, which must not fault
+    if let Ok(range) = VMRange::new_with_size(addr, align_up(size, PAGE_SIZE)) {
+        check_io_urings_mprotect(current!().process().pid(), &range, perms)?;
+    }
     current!().vm().mprotect(addr, size, perms)
 }
 
@@ -14,6 +14,7 @@ use crate::process::elf_file::{ElfFile, ProgramHeaderExt};
 use crate::util::sync::rw_lock::RwLockWriteGuard;
 
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // Used for heap and stack start address randomization.
 const RANGE_FOR_RANDOMIZATION: usize = 256 * 4096; // 1M
@@ -200,6 +201,7 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
             heap_range,
             stack_range,
             brk,
+            max_resident_size: AtomicUsize::new(0),
             mem_chunks,
         })
     }
@@ -287,6 +289,8 @@ pub struct ProcessVM {
     heap_range: VMRange,
     stack_range: VMRange,
     brk: RwLock<usize>,
+    // The maximum resident set size observed so far
+    max_resident_size: AtomicUsize,
     // Memory safety notes: the mem_chunks field must be the last one.
     //
     // Rust drops fields in the same order as they are declared. So by making
@@ -303,6 +307,7 @@ impl Default for ProcessVM {
             heap_range: Default::default(),
             stack_range: Default::default(),
             brk: Default::default(),
+            max_resident_size: Default::default(),
             mem_chunks: Arc::new(RwLock::new(HashSet::new())),
         }
     }
@@ -346,6 +351,70 @@ impl ProcessVM {
         &self.heap_range
     }
 
+    /// Get the size of the memory mapped by the process.
+    ///
+    /// The enclave memory is never swapped out, so this is also the resident
+    /// set size of the process.
+    pub fn resident_size(&self, pid: pid_t) -> usize {
+        let mem_chunks = self.mem_chunks.read().unwrap();
+        mem_chunks
+            .iter()
+            .map(|chunk| match chunk.internal() {
+                ChunkType::SingleVMA(vma) => vma.lock().unwrap().size(),
+                ChunkType::MultiVMA(internal_manager) => internal_manager
+                    .lock()
+                    .unwrap()
+                    .chunk_manager()
+                    .vmas()
+                    .iter()
+                    .filter(|vma_obj| vma_obj.vma().belong_to(pid))
+                    .map(|vma_obj| vma_obj.vma().size())
+                    .sum(),
+            })
+            .sum()
+    }
+
+    /// Access the memory of the process in `range` with `perms` added, e.g.,
+    /// on behalf of a tracer. The memory mapping is locked during `f`, so it
+    /// cannot be unmapped or protected concurrently.
+    pub fn access_force<F, R>(&self, pid: pid_t, range: &VMRange, perms: VMPerms, f: F) -> Result<R>
+    where
+        F: FnOnce() -> R,
+    {
+        let mem_chunks = self.mem_chunks.read().unwrap();
+        for chunk in mem_chunks.iter() {
+            if !chunk.range().is_superset_of(range) {
+                continue;
+            }
+            return match chunk.internal() {
+                ChunkType::SingleVMA(vma) => {
+                    let mut vma = vma.lock().unwrap();
+                    if !vma.is_superset_of(range) {
+                        return_errno!(ENOMEM, "the range is not mapped");
+                    }
+                    vma.access_force(range, perms, f)
+                }
+                ChunkType::MultiVMA(internal_manager) => internal_manager
+                    .lock()
+                    .unwrap()
+                    .chunk_manager_mut()
+                    .access_force(pid, range, perms, f),
+            };
+        }
+        return_errno!(ENOMEM, "the range is not mapped");
+    }
+
+    /// Get the maximum resident set size of the process.
+    ///
+    /// The resident set size is sampled when this method is called, i.e.,
+    /// when the resource usage is queried or a thread exits.
+    pub fn max_resident_size(&self, pid: pid_t) -> usize {
+        let size = self.resident_size(pid);
+        self.max_resident_size
+            .fetch_max(size, Ordering::Relaxed)
+            .max(size)
+    }
+
     pub fn add_mem_chunk(&self, chunk: ChunkRef) {
         let mut mem_chunks = self.mem_chunks.write().unwrap();
         mem_chunks.insert(chunk);
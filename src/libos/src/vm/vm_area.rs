@@ -309,6 +309,33 @@ impl VMArea {
         }
     }
 
+    /// Access the memory in `range` with `perms`, which are added to the
+    /// permissions of the VMA during `f`, like ptrace does on Linux, e.g., to
+    /// set breakpoints in the read-only code.
+    ///
+    /// The whole VMA is committed beforehand so that `f` cannot trigger a page
+    /// fault, which would need the lock of the VMA held by the caller.
+    pub fn access_force<F, R>(&mut self, range: &VMRange, perms: VMPerms, f: F) -> Result<R>
+    where
+        F: FnOnce() -> R,
+    {
+        debug_assert!(self.is_superset_of(range));
+        if !self.is_fully_committed() {
+            self.commit_current_vma_whole()?;
+        }
+
+        let curr_perms = self.perms();
+        let access_perms = curr_perms | perms;
+        if access_perms != curr_perms {
+            self.modify_permission_force(Some(range), curr_perms, access_perms);
+        }
+        let ret = f();
+        if access_perms != curr_perms {
+            self.modify_permission_force(Some(range), access_perms, curr_perms);
+        }
+        Ok(ret)
+    }
+
     pub fn handle_page_fault(
         &mut self,
         rip: usize,
@@ -349,6 +376,11 @@ impl VMArea {
             return_errno!(EINVAL, "reserved memory shouldn't trigger PF");
         }
 
+        // The memory is initialized from the backing file
+        if self.backed_file().is_some() {
+            current!().usage().major_page_fault();
+        }
+
         if kernel_triggers || self.pf_count >= PF_NUM_THRESHOLD {
             info!("commit whole vma");
             return self.commit_current_vma_whole();
@@ -801,7 +833,7 @@ impl VMArea {
         Ok(total_commit_size)
     }
 
-    // Only used to handle PF triggered by the kernel
+    // Only used to handle PF triggered by the kernel or accessed by a tracer
     fn commit_current_vma_whole(&mut self) -> Result<()> {
         debug_assert!(!self.is_fully_committed());
 
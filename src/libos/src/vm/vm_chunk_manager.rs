@@ -446,6 +446,33 @@ impl ChunkManager {
         Ok(())
     }
 
+    /// Access the memory of the process in `range`, which must be in a single
+    /// VMA, with `perms` added. See `VMArea::access_force`.
+    pub fn access_force<F, R>(
+        &mut self,
+        pid: pid_t,
+        range: &VMRange,
+        perms: VMPerms,
+        f: F,
+    ) -> Result<R>
+    where
+        F: FnOnce() -> R,
+    {
+        let mut vma_cursor = self.vmas.upper_bound_mut(Bound::Included(&range.start()));
+        if vma_cursor.is_null() {
+            return_errno!(ENOMEM, "no mmap regions that contains the range");
+        }
+        let vma = vma_cursor.get().unwrap().vma();
+        if !vma.belong_to(pid) || !vma.is_superset_of(range) {
+            return_errno!(ENOMEM, "no mmap regions that contains the range");
+        }
+
+        let mut vma = vma.clone();
+        let ret = vma.access_force(range, perms, f)?;
+        vma_cursor.replace_with(VMAObj::new_vma_obj(vma));
+        Ok(ret)
+    }
+
     pub fn usage_percentage(&self) -> f32 {
         let total_size = self.range.size();
         let mut used_size = 0;
@@ -920,6 +920,11 @@ impl InternalVMManager {
                 if !file_ref.access_mode().unwrap().writable() && new_perms.can_write() {
                     return_errno!(EACCES, "file is not writable");
                 }
+                if let Ok(inode_file) = file_ref.as_inode_file() {
+                    if new_perms.can_write() {
+                        inode_file.check_writable_mount()?;
+                    }
+                }
             }
 
             let current_pid = current!().process().pid();
@@ -1252,6 +1257,12 @@ fn mmap_file_check_permissions(mmap_options: &VMMapOptions) -> Result<()> {
                 if !file_ref.access_mode().unwrap().writable() && perms.can_write() {
                     return_errno!(EACCES, "mmap file is not writable");
                 }
+                // The file may be opened before its mount is remounted read-only
+                if let Ok(inode_file) = file_ref.as_inode_file() {
+                    if perms.can_write() {
+                        inode_file.check_writable_mount()?;
+                    }
+                }
             }
 
             return Ok(());
@@ -1,6 +1,7 @@
 #include "ocalls.h"
 #include <errno.h>
 #include <net/if.h>
+#include <stdio.h>
 #include <unistd.h>
 #include <sys/ioctl.h>
 #include <sys/vfs.h>
@@ -45,4 +46,14 @@ int occlum_ocall_ioctl(int fd, int request, void *arg, size_t len) {
 
 int occlum_ocall_statfs(const char *path, struct statfs *buf) {
     return statfs(path, buf);
-}
\ No newline at end of file
+}
+// Implemented by the untrusted library of the SGX protected FS
+extern int32_t u_sgxprotectedfs_fclose(void *f);
+
+int occlum_ocall_sgxfs_fileno(void *f) {
+    return fileno((FILE *)f);
+}
+
+int32_t occlum_ocall_sgxfs_fclose(void *f) {
+    return u_sgxprotectedfs_fclose(f);
+}
@@ -19,6 +19,11 @@ int occlum_ocall_sched_setaffinity(int host_tid, size_t cpusize,
     return syscall(__NR_sched_setaffinity, host_tid, cpusize, buf);
 }
 
+int occlum_ocall_sched_setscheduler(int host_tid, int policy, int priority) {
+    struct sched_param param = { .sched_priority = priority };
+    return syscall(__NR_sched_setscheduler, host_tid, policy, &param);
+}
+
 /* In the Linux implementation, sched_yield() always succeeds */
 void occlum_ocall_sched_yield(void) {
     sched_yield();
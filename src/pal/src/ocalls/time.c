@@ -1,4 +1,8 @@
+#include <errno.h>
 #include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
 #include <sys/time.h>
 #include <sys/timerfd.h>
 #include <sys/prctl.h>
@@ -20,6 +24,34 @@ int occlum_ocall_thread_getcpuclock(struct timespec *tp) {
     return clock_gettime(thread_clock_id, tp);
 }
 
+int occlum_ocall_get_thread_cputime(int host_tid, uint64_t *utime, uint64_t *stime) {
+    char path[64];
+    char buf[1024];
+    snprintf(path, sizeof(path), "/proc/self/task/%d/stat", host_tid);
+    FILE *fp = fopen(path, "r");
+    if (fp == NULL) {
+        return -1;
+    }
+    size_t len = fread(buf, 1, sizeof(buf) - 1, fp);
+    fclose(fp);
+    buf[len] = '\0';
+
+    // The name of the thread may contain spaces, so the fields after it are
+    // located from the last ')'. The utime and stime are the 14th and 15th.
+    char *fields = strrchr(buf, ')');
+    unsigned long utime_ticks, stime_ticks;
+    if (fields == NULL || sscanf(fields + 1,
+                                 " %*c %*d %*d %*d %*d %*d %*u %*u %*u %*u %*u %lu %lu",
+                                 &utime_ticks, &stime_ticks) != 2) {
+        errno = EINVAL;
+        return -1;
+    }
+    long ticks_per_sec = sysconf(_SC_CLK_TCK);
+    *utime = (uint64_t)utime_ticks * 1000000000 / ticks_per_sec;
+    *stime = (uint64_t)stime_ticks * 1000000000 / ticks_per_sec;
+    return 0;
+}
+
 void occlum_ocall_rdtsc(uint32_t *low, uint32_t *high) {
     uint64_t rax, rdx;
     asm volatile("rdtsc" : "=a"(rax), "=d"(rdx));
@@ -0,0 +1,417 @@
+#include <sys/eventfd.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <linux/aio_abi.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdint.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define AIO_FILE_PATH   "/root/test_aio.txt"
+#define AIO_BUF_LEN     4096
+#define AIO_NR_REQS     16
+
+static int io_setup(unsigned nr_events, aio_context_t *ctx) {
+    return syscall(SYS_io_setup, nr_events, ctx);
+}
+
+static int io_destroy(aio_context_t ctx) {
+    return syscall(SYS_io_destroy, ctx);
+}
+
+static int io_submit(aio_context_t ctx, long nr, struct iocb **iocbs) {
+    return syscall(SYS_io_submit, ctx, nr, iocbs);
+}
+
+static int io_getevents(aio_context_t ctx, long min_nr, long nr,
+                        struct io_event *events, struct timespec *timeout) {
+    return syscall(SYS_io_getevents, ctx, min_nr, nr, events, timeout);
+}
+
+static int io_cancel(aio_context_t ctx, struct iocb *iocb, struct io_event *result) {
+    return syscall(SYS_io_cancel, ctx, iocb, result);
+}
+
+static void prep_rw(struct iocb *iocb, int opcode, int fd, void *buf, size_t len,
+                    off_t offset, uint64_t data) {
+    memset(iocb, 0, sizeof(*iocb));
+    iocb->aio_lio_opcode = opcode;
+    iocb->aio_fildes = fd;
+    iocb->aio_buf = (uint64_t)buf;
+    iocb->aio_nbytes = len;
+    iocb->aio_offset = offset;
+    iocb->aio_data = data;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_setup_and_destroy() {
+    aio_context_t ctx = 0;
+
+    if (io_setup(0, &ctx) == 0 || errno != EINVAL) {
+        THROW_ERROR("io_setup with zero events should fail");
+    }
+    if (io_setup(8, &ctx) < 0 || ctx == 0) {
+        THROW_ERROR("failed to setup an AIO context");
+    }
+    // The context must be initialized to zero
+    aio_context_t ctx2 = ctx;
+    if (io_setup(8, &ctx2) == 0 || errno != EINVAL) {
+        THROW_ERROR("io_setup with a non-zero context should fail");
+    }
+    if (io_destroy(ctx) < 0) {
+        THROW_ERROR("failed to destroy the AIO context");
+    }
+    if (io_destroy(ctx) == 0 || errno != EINVAL) {
+        THROW_ERROR("destroying a destroyed context should fail");
+    }
+    return 0;
+}
+
+static int test_pwrite_pread_fsync() {
+    static char write_buf[AIO_BUF_LEN];
+    static char read_buf[AIO_BUF_LEN];
+    aio_context_t ctx = 0;
+    struct iocb iocbs[3];
+    struct iocb *iocb_ptrs[3] = { &iocbs[0], &iocbs[1], &iocbs[2] };
+    struct io_event events[3];
+    int ret = -1;
+
+    memset(write_buf, 'a', sizeof(write_buf));
+    int fd = open(AIO_FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (io_setup(8, &ctx) < 0) {
+        close(fd);
+        THROW_ERROR("failed to setup an AIO context");
+    }
+
+    // The requests may be executed in any order, so the read is submitted
+    // after the write and the sync complete
+    prep_rw(&iocbs[0], IOCB_CMD_PWRITE, fd, write_buf, AIO_BUF_LEN, 100, 1);
+    prep_rw(&iocbs[1], IOCB_CMD_FSYNC, fd, NULL, 0, 0, 2);
+    prep_rw(&iocbs[2], IOCB_CMD_PREAD, fd, read_buf, AIO_BUF_LEN, 100, 3);
+    if (io_submit(ctx, 2, iocb_ptrs) != 2) {
+        printf("failed to submit the requests\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 2, 2, events, NULL) != 2) {
+        printf("failed to get the completion events\n");
+        goto out;
+    }
+    if (io_submit(ctx, 1, &iocb_ptrs[2]) != 1) {
+        printf("failed to submit the request\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 1, 1, &events[2], NULL) != 1) {
+        printf("failed to get the completion event\n");
+        goto out;
+    }
+    for (int i = 0; i < 3; i++) {
+        struct iocb *iocb = (struct iocb *)events[i].obj;
+        if (events[i].data != iocb->aio_data) {
+            printf("the data of the event is wrong\n");
+            goto out;
+        }
+        long expected_res = iocb->aio_lio_opcode == IOCB_CMD_FSYNC ? 0 : AIO_BUF_LEN;
+        if (events[i].res != expected_res) {
+            printf("the result of the event is wrong: %lld\n", (long long)events[i].res);
+            goto out;
+        }
+    }
+    if (memcmp(write_buf, read_buf, AIO_BUF_LEN) != 0) {
+        printf("the content read is wrong\n");
+        goto out;
+    }
+
+    // I/O errors are reported by the events
+    prep_rw(&iocbs[0], IOCB_CMD_PREAD, fd, read_buf, AIO_BUF_LEN, 0, 4);
+    int wronly_fd = open(AIO_FILE_PATH, O_WRONLY);
+    iocbs[0].aio_fildes = wronly_fd;
+    if (io_submit(ctx, 1, iocb_ptrs) != 1) {
+        close(wronly_fd);
+        printf("failed to submit the request\n");
+        goto out;
+    }
+    close(wronly_fd);
+    if (io_getevents(ctx, 1, 1, events, NULL) != 1 || events[0].res != -EBADF) {
+        printf("the error should be reported by the event\n");
+        goto out;
+    }
+
+    // Invalid requests are rejected at submission
+    prep_rw(&iocbs[0], IOCB_CMD_PREAD, -1, read_buf, AIO_BUF_LEN, 0, 5);
+    if (io_submit(ctx, 1, iocb_ptrs) != -1 || errno != EBADF) {
+        printf("the request with an invalid fd should be rejected\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    io_destroy(ctx);
+    close(fd);
+    unlink(AIO_FILE_PATH);
+    return ret;
+}
+
+static int test_poll_and_resfd() {
+    aio_context_t ctx = 0;
+    struct iocb iocb;
+    struct iocb *iocb_ptr = &iocb;
+    struct io_event event;
+    struct timespec zero_timeout = { 0, 0 };
+    int pipe_fds[2];
+    uint64_t efd_val = 0;
+    int ret = -1;
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int efd = eventfd(0, EFD_NONBLOCK);
+    if (efd < 0) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to create an eventfd");
+    }
+    if (io_setup(8, &ctx) < 0) {
+        close_files(3, pipe_fds[0], pipe_fds[1], efd);
+        THROW_ERROR("failed to setup an AIO context");
+    }
+
+    prep_rw(&iocb, IOCB_CMD_POLL, pipe_fds[0], NULL, 0, 0, 6);
+    iocb.aio_buf = POLLIN;
+    iocb.aio_flags = IOCB_FLAG_RESFD;
+    iocb.aio_resfd = efd;
+    if (io_submit(ctx, 1, &iocb_ptr) != 1) {
+        printf("failed to submit the poll request\n");
+        goto out;
+    }
+    // The pipe is not readable yet
+    if (io_getevents(ctx, 1, 1, &event, &zero_timeout) != 0) {
+        printf("the poll request should not complete\n");
+        goto out;
+    }
+    if (write(pipe_fds[1], "x", 1) != 1) {
+        printf("failed to write the pipe\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 1, 1, &event, NULL) != 1) {
+        printf("failed to get the poll event\n");
+        goto out;
+    }
+    if (event.data != 6 || !(event.res & POLLIN)) {
+        printf("the poll event is wrong\n");
+        goto out;
+    }
+    if (read(efd, &efd_val, sizeof(efd_val)) != sizeof(efd_val) || efd_val != 1) {
+        printf("the eventfd should be signaled\n");
+        goto out;
+    }
+
+    // Cancel a pending poll request
+    prep_rw(&iocb, IOCB_CMD_POLL, pipe_fds[1], NULL, 0, 0, 7);
+    iocb.aio_buf = POLLPRI;
+    if (io_submit(ctx, 1, &iocb_ptr) != 1) {
+        printf("failed to submit the poll request\n");
+        goto out;
+    }
+    if (io_cancel(ctx, &iocb, &event) != -1 || errno != EINPROGRESS) {
+        printf("failed to cancel the poll request\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 1, 1, &event, NULL) != 1 || event.data != 7 || event.res != 0) {
+        printf("the cancelled request should complete with no event\n");
+        goto out;
+    }
+    if (io_cancel(ctx, &iocb, &event) != -1 || errno != EINVAL) {
+        printf("a completed request cannot be cancelled\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    io_destroy(ctx);
+    close_files(3, pipe_fds[0], pipe_fds[1], efd);
+    return ret;
+}
+
+static int test_concurrent_preads() {
+    static char write_buf[AIO_NR_REQS * AIO_BUF_LEN];
+    static char read_bufs[AIO_NR_REQS][AIO_BUF_LEN];
+    aio_context_t ctx = 0;
+    struct iocb iocbs[AIO_NR_REQS];
+    struct iocb *iocb_ptrs[AIO_NR_REQS];
+    struct io_event events[AIO_NR_REQS];
+    int ret = -1;
+
+    for (int i = 0; i < sizeof(write_buf); i++) {
+        write_buf[i] = (char)(i / AIO_BUF_LEN + i);
+    }
+    int fd = open(AIO_FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (write(fd, write_buf, sizeof(write_buf)) != sizeof(write_buf)) {
+        close(fd);
+        THROW_ERROR("failed to write the file");
+    }
+    if (io_setup(AIO_NR_REQS, &ctx) < 0) {
+        close(fd);
+        THROW_ERROR("failed to setup an AIO context");
+    }
+
+    // More requests than the workers, which are executed concurrently
+    memset(read_bufs, 0, sizeof(read_bufs));
+    for (int i = 0; i < AIO_NR_REQS; i++) {
+        prep_rw(&iocbs[i], IOCB_CMD_PREAD, fd, read_bufs[i], AIO_BUF_LEN, i * AIO_BUF_LEN, i);
+        iocb_ptrs[i] = &iocbs[i];
+    }
+    if (io_submit(ctx, AIO_NR_REQS, iocb_ptrs) != AIO_NR_REQS) {
+        printf("failed to submit the requests\n");
+        goto out;
+    }
+    if (io_getevents(ctx, AIO_NR_REQS, AIO_NR_REQS, events, NULL) != AIO_NR_REQS) {
+        printf("failed to get the completion events\n");
+        goto out;
+    }
+    for (int i = 0; i < AIO_NR_REQS; i++) {
+        if (events[i].res != AIO_BUF_LEN) {
+            printf("the result of the event is wrong: %lld\n", (long long)events[i].res);
+            goto out;
+        }
+    }
+    for (int i = 0; i < AIO_NR_REQS; i++) {
+        if (memcmp(read_bufs[i], write_buf + i * AIO_BUF_LEN, AIO_BUF_LEN) != 0) {
+            printf("the content read by request %d is wrong\n", i);
+            goto out;
+        }
+    }
+    ret = 0;
+out:
+    io_destroy(ctx);
+    close(fd);
+    unlink(AIO_FILE_PATH);
+    return ret;
+}
+
+static int test_poll_host_files() {
+    aio_context_t ctx = 0;
+    struct iocb iocbs[2];
+    struct iocb *iocb_ptrs[2] = { &iocbs[0], &iocbs[1] };
+    struct io_event events[2];
+    struct timespec zero_timeout = { 0, 0 };
+    struct timespec timeout = { 1, 0 };
+    struct sockaddr_in addr = { 0 };
+    socklen_t addr_len = sizeof(addr);
+    uint64_t efd_val = 1;
+    int ret = -1;
+
+    // UDP sockets are host sockets unless io_uring is enabled
+    int sock_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock_fd < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(sock_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            getsockname(sock_fd, (struct sockaddr *)&addr, &addr_len) < 0) {
+        close(sock_fd);
+        THROW_ERROR("failed to bind the socket");
+    }
+    int efd = eventfd(0, EFD_NONBLOCK);
+    if (efd < 0) {
+        close(sock_fd);
+        THROW_ERROR("failed to create an eventfd");
+    }
+    if (io_setup(8, &ctx) < 0) {
+        close_files(2, sock_fd, efd);
+        THROW_ERROR("failed to setup an AIO context");
+    }
+
+    prep_rw(&iocbs[0], IOCB_CMD_POLL, sock_fd, NULL, 0, 0, 8);
+    iocbs[0].aio_buf = POLLIN;
+    prep_rw(&iocbs[1], IOCB_CMD_POLL, efd, NULL, 0, 0, 9);
+    iocbs[1].aio_buf = POLLIN;
+    if (io_submit(ctx, 2, iocb_ptrs) != 2) {
+        printf("failed to submit the poll requests\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 1, 2, events, &zero_timeout) != 0) {
+        printf("the poll requests should not complete\n");
+        goto out;
+    }
+
+    // No thread polls the host files, but the requests must complete
+    if (sendto(sock_fd, "x", 1, 0, (struct sockaddr *)&addr, sizeof(addr)) != 1) {
+        printf("failed to send to the socket\n");
+        goto out;
+    }
+    if (write(efd, &efd_val, sizeof(efd_val)) != sizeof(efd_val)) {
+        printf("failed to write the eventfd\n");
+        goto out;
+    }
+    int nr_events = 0;
+    while (nr_events < 2) {
+        int nr = io_getevents(ctx, 1, 2 - nr_events, &events[nr_events], &timeout);
+        if (nr <= 0) {
+            printf("failed to get the poll events of the host files\n");
+            goto out;
+        }
+        nr_events += nr;
+    }
+    for (int i = 0; i < 2; i++) {
+        if ((events[i].data != 8 && events[i].data != 9) || !(events[i].res & POLLIN)) {
+            printf("the poll event is wrong\n");
+            goto out;
+        }
+    }
+    ret = 0;
+out:
+    io_destroy(ctx);
+    close_files(2, sock_fd, efd);
+    return ret;
+}
+
+static int test_getevents_timeout() {
+    aio_context_t ctx = 0;
+    struct io_event event;
+    struct timespec timeout = { 0, 10 * 1000 * 1000 };
+
+    if (io_setup(1, &ctx) < 0) {
+        THROW_ERROR("failed to setup an AIO context");
+    }
+    int ret = io_getevents(ctx, 1, 1, &event, &timeout);
+    io_destroy(ctx);
+    if (ret != 0) {
+        THROW_ERROR("io_getevents should timeout with no event");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_setup_and_destroy),
+    TEST_CASE(test_pwrite_pread_fsync),
+    TEST_CASE(test_poll_and_resfd),
+    TEST_CASE(test_concurrent_preads),
+    TEST_CASE(test_poll_host_files),
+    TEST_CASE(test_getevents_timeout),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -0,0 +1,214 @@
+#define _GNU_SOURCE
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+
+#define CHROOT_DIR      "/chroot_dir"
+#define PIVOT_ROOT_DIR  "/pivot_root_dir"
+#define TEST_UID        1000
+
+static int create_dir(const char *dir) {
+    if (mkdir(dir, 00775) < 0 && errno != EEXIST) {
+        THROW_ERROR("failed to mkdir: %s", dir);
+    }
+    return 0;
+}
+
+static int create_file(const char *file_path, const char *content) {
+    int fd = open(file_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file: %s", file_path);
+    }
+    if (write(fd, content, strlen(content)) != strlen(content)) {
+        close(fd);
+        THROW_ERROR("failed to write file: %s", file_path);
+    }
+    close(fd);
+    return 0;
+}
+
+// Run the function in a vforked child, since the root directory can not be
+// changed back.
+static int run_in_vforked_child(int (*fn)(void)) {
+    int status = 0;
+    pid_t child_pid = vfork();
+    if (child_pid == 0) {
+        _exit(fn() < 0 ? 1 : 0);
+    }
+    if (child_pid < 0) {
+        THROW_ERROR("failed to vfork");
+    }
+    if (waitpid(child_pid, &status, 0) < 0) {
+        THROW_ERROR("failed to wait the child");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for chroot
+// ============================================================================
+
+static int __test_chroot(void) {
+    struct stat stat_buf;
+    char cwd[PATH_MAX] = { 0 };
+
+    if (chroot(CHROOT_DIR) < 0 || chdir("/") < 0) {
+        THROW_ERROR("failed to chroot");
+    }
+    if (getcwd(cwd, sizeof(cwd)) == NULL || strcmp(cwd, "/") != 0) {
+        THROW_ERROR("the cwd is not the new root");
+    }
+    if (fs_check_file_content("/file", "chroot") < 0) {
+        THROW_ERROR("failed to read file in the new root");
+    }
+    if (fs_check_file_content("/../../file", "chroot") < 0) {
+        THROW_ERROR("\"..\" escapes the new root");
+    }
+    if (fs_check_file_content("dir/../../file", "chroot") < 0) {
+        THROW_ERROR("relative \"..\" escapes the new root");
+    }
+    if (fs_check_file_content("/dir/link", "chroot") < 0) {
+        THROW_ERROR("absolute symlink escapes the new root");
+    }
+    if (!(stat(CHROOT_DIR, &stat_buf) < 0 && errno == ENOENT)) {
+        THROW_ERROR("the old root is still visible");
+    }
+    return 0;
+}
+
+static int test_chroot() {
+    if (create_dir(CHROOT_DIR) < 0 || create_dir(CHROOT_DIR "/dir") < 0) {
+        THROW_ERROR("failed to create dirs");
+    }
+    if (create_file(CHROOT_DIR "/file", "chroot") < 0) {
+        THROW_ERROR("failed to create file");
+    }
+    unlink(CHROOT_DIR "/dir/link");
+    if (symlink("/file", CHROOT_DIR "/dir/link") < 0) {
+        THROW_ERROR("failed to create symlink");
+    }
+
+    if (run_in_vforked_child(__test_chroot) < 0) {
+        THROW_ERROR("failed to test chroot in child");
+    }
+
+    // The parent is not affected
+    if (fs_check_file_content(CHROOT_DIR "/file", "chroot") < 0) {
+        THROW_ERROR("the root of parent is changed");
+    }
+    return 0;
+}
+
+static int test_chroot_on_file() {
+    int ret = chroot(CHROOT_DIR "/file");
+    if (!(ret < 0 && errno == ENOTDIR)) {
+        THROW_ERROR("chroot on a file should return ENOTDIR");
+    }
+    return 0;
+}
+
+static int test_chroot_without_root() {
+    if (seteuid(TEST_UID) < 0) {
+        THROW_ERROR("failed to seteuid");
+    }
+    int chroot_ret = chroot(CHROOT_DIR);
+    int chroot_errno = errno;
+    int pivot_root_ret = syscall(SYS_pivot_root, PIVOT_ROOT_DIR, PIVOT_ROOT_DIR "/old");
+    int pivot_root_errno = errno;
+    if (seteuid(0) < 0) {
+        THROW_ERROR("failed to restore euid");
+    }
+    if (!(chroot_ret < 0 && chroot_errno == EPERM)) {
+        THROW_ERROR("chroot by non-root should return EPERM");
+    }
+    if (!(pivot_root_ret < 0 && pivot_root_errno == EPERM)) {
+        THROW_ERROR("pivot_root by non-root should return EPERM");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for pivot_root
+// ============================================================================
+
+static int __test_pivot_root(void) {
+    struct stat stat_buf;
+
+    if (syscall(SYS_pivot_root, PIVOT_ROOT_DIR, PIVOT_ROOT_DIR "/old") < 0) {
+        THROW_ERROR("failed to pivot_root");
+    }
+    if (chdir("/") < 0) {
+        THROW_ERROR("failed to chdir");
+    }
+    if (fs_check_file_content("/file", "pivot_root") < 0) {
+        THROW_ERROR("failed to read file in the new root");
+    }
+    if (fs_check_file_content("/old" CHROOT_DIR "/file", "chroot") < 0) {
+        THROW_ERROR("failed to read file in the old root");
+    }
+    if (umount("/old") < 0) {
+        THROW_ERROR("failed to umount the old root");
+    }
+    if (!(stat("/old" CHROOT_DIR, &stat_buf) < 0 && errno == ENOENT)) {
+        THROW_ERROR("the old root is still visible after umount");
+    }
+    return 0;
+}
+
+static int test_pivot_root() {
+    if (create_dir(PIVOT_ROOT_DIR) < 0) {
+        THROW_ERROR("failed to create dir");
+    }
+    if (mount("ramfs", PIVOT_ROOT_DIR, "ramfs", 0, NULL) < 0) {
+        THROW_ERROR("failed to mount ramfs");
+    }
+    if (create_dir(PIVOT_ROOT_DIR "/old") < 0 ||
+            create_file(PIVOT_ROOT_DIR "/file", "pivot_root") < 0) {
+        THROW_ERROR("failed to populate the new root");
+    }
+
+    if (run_in_vforked_child(__test_pivot_root) < 0) {
+        THROW_ERROR("failed to test pivot_root in child");
+    }
+
+    if (umount(PIVOT_ROOT_DIR) < 0) {
+        THROW_ERROR("failed to umount ramfs");
+    }
+    return 0;
+}
+
+static int test_pivot_root_not_mountpoint() {
+    int ret = syscall(SYS_pivot_root, CHROOT_DIR, CHROOT_DIR "/dir");
+    if (!(ret < 0 && errno == EINVAL)) {
+        THROW_ERROR("pivot_root to a non-mountpoint should return EINVAL");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_chroot),
+    TEST_CASE(test_chroot_on_file),
+    TEST_CASE(test_chroot_without_root),
+    TEST_CASE(test_pivot_root),
+    TEST_CASE(test_pivot_root_not_mountpoint),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,288 @@
+#define _GNU_SOURCE
+#include <sys/fsuid.h>
+#include <sys/stat.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <grp.h>
+#include <signal.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+
+#define TEST_DIR        "/cred_test_dir"
+#define ROOT_FILE       TEST_DIR "/root_file"
+#define USER_FILE       TEST_DIR "/user_file"
+#define TEST_UID        1000
+#define TEST_GID        1000
+
+static int check_resuid(uid_t ruid, uid_t euid, uid_t suid) {
+    uid_t r, e, s;
+    if (getresuid(&r, &e, &s) < 0) {
+        THROW_ERROR("failed to getresuid");
+    }
+    if (r != ruid || e != euid || s != suid) {
+        THROW_ERROR("unexpected uids: %d, %d, %d", r, e, s);
+    }
+    return 0;
+}
+
+static int restore_root() {
+    if (seteuid(0) < 0 || setegid(0) < 0) {
+        THROW_ERROR("failed to restore root");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for credentials
+// ============================================================================
+
+static int test_default_ids() {
+    gid_t rgid, egid, sgid;
+
+    if (getuid() != 0 || geteuid() != 0 || getgid() != 0 || getegid() != 0) {
+        THROW_ERROR("the default ids should be root");
+    }
+    if (check_resuid(0, 0, 0) < 0) {
+        THROW_ERROR("the default uids should be root");
+    }
+    if (getresgid(&rgid, &egid, &sgid) < 0 || rgid != 0 || egid != 0 || sgid != 0) {
+        THROW_ERROR("the default gids should be root");
+    }
+    return 0;
+}
+
+static int test_setgroups() {
+    gid_t groups[] = { 0, 100, 200 };
+    gid_t out_groups[8] = { 0 };
+
+    if (setgroups(3, groups) < 0) {
+        THROW_ERROR("failed to setgroups");
+    }
+    int ngroups = getgroups(0, NULL);
+    if (ngroups != 3) {
+        THROW_ERROR("unexpected number of groups: %d", ngroups);
+    }
+    if (!(getgroups(1, out_groups) < 0 && errno == EINVAL)) {
+        THROW_ERROR("getgroups with a small buffer should return EINVAL");
+    }
+    if (getgroups(8, out_groups) != 3 || out_groups[1] != 100 || out_groups[2] != 200) {
+        THROW_ERROR("unexpected groups");
+    }
+    return 0;
+}
+
+static int test_seteuid_and_restore() {
+    if (seteuid(TEST_UID) < 0) {
+        THROW_ERROR("failed to seteuid");
+    }
+    if (check_resuid(0, TEST_UID, 0) < 0) {
+        THROW_ERROR("unexpected uids after seteuid");
+    }
+    // Unprivileged process can only switch among the real, effective and saved IDs
+    if (!(setresuid(2000, -1, -1) < 0 && errno == EPERM)) {
+        THROW_ERROR("setresuid to an arbitrary uid should return EPERM");
+    }
+    if (!(setgroups(0, NULL) < 0 && errno == EPERM)) {
+        THROW_ERROR("setgroups by unprivileged process should return EPERM");
+    }
+    if (restore_root() < 0) {
+        THROW_ERROR("failed to switch back to root");
+    }
+    return check_resuid(0, 0, 0);
+}
+
+static int test_setfsuid() {
+    if (setfsuid(TEST_UID) != 0) {
+        THROW_ERROR("setfsuid should return the previous fsuid");
+    }
+    if (setfsuid(0) != TEST_UID) {
+        THROW_ERROR("failed to set fsuid");
+    }
+    return 0;
+}
+
+static int test_file_permissions() {
+    struct stat stat_buf;
+    int fd;
+
+    if (mkdir(TEST_DIR, 00777) < 0 && errno != EEXIST) {
+        THROW_ERROR("failed to mkdir");
+    }
+    if (chmod(TEST_DIR, 00777) < 0) {
+        THROW_ERROR("failed to chmod");
+    }
+    fd = open(ROOT_FILE, O_RDWR | O_CREAT | O_TRUNC, 00600);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file as root");
+    }
+    close(fd);
+    unlink(USER_FILE);
+
+    if (setegid(TEST_GID) < 0 || seteuid(TEST_UID) < 0) {
+        THROW_ERROR("failed to drop privileges");
+    }
+    fd = open(ROOT_FILE, O_RDONLY);
+    if (!(fd < 0 && errno == EACCES)) {
+        THROW_ERROR("open the file of root should return EACCES");
+    }
+    if (!(access(ROOT_FILE, R_OK) < 0 && errno == EACCES)) {
+        THROW_ERROR("access the file of root should return EACCES");
+    }
+    if (!(chmod(ROOT_FILE, 00666) < 0 && errno == EPERM)) {
+        THROW_ERROR("chmod the file of root should return EPERM");
+    }
+    fd = open(USER_FILE, O_RDWR | O_CREAT, 00600);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file as user");
+    }
+    if (fstat(fd, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat file");
+    }
+    close(fd);
+    if (stat_buf.st_uid != TEST_UID || stat_buf.st_gid != TEST_GID) {
+        THROW_ERROR("the owner of the new file is not the user");
+    }
+    if (!(chown(USER_FILE, 0, -1) < 0 && errno == EPERM)) {
+        THROW_ERROR("chown by unprivileged process should return EPERM");
+    }
+
+    if (restore_root() < 0) {
+        THROW_ERROR("failed to switch back to root");
+    }
+    // Root can access any file
+    fd = open(USER_FILE, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("root failed to open the file of user");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_dir_permissions() {
+    const char *locked_dir = TEST_DIR "/locked_dir";
+    const char *locked_file = TEST_DIR "/locked_dir/file";
+    const char *locked_subdir = TEST_DIR "/locked_dir/subdir";
+    const char *private_dir = TEST_DIR "/private_dir";
+    const char *private_file = TEST_DIR "/private_dir/file";
+    const char *ro_file = TEST_DIR "/ro_file";
+    struct stat stat_buf;
+    int fd;
+
+    // The dirs of root are not writable or searchable by others
+    if ((mkdir(locked_dir, 00755) < 0 && errno != EEXIST) ||
+            (mkdir(locked_subdir, 00755) < 0 && errno != EEXIST) ||
+            (mkdir(private_dir, 00700) < 0 && errno != EEXIST)) {
+        THROW_ERROR("failed to mkdir");
+    }
+    if (chmod(locked_dir, 00755) < 0 || chmod(private_dir, 00700) < 0) {
+        THROW_ERROR("failed to chmod");
+    }
+    fd = open(locked_file, O_RDWR | O_CREAT, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file as root");
+    }
+    close(fd);
+    fd = open(private_file, O_RDWR | O_CREAT, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file as root");
+    }
+    close(fd);
+    unlink(ro_file);
+
+    if (setegid(TEST_GID) < 0 || seteuid(TEST_UID) < 0) {
+        THROW_ERROR("failed to drop privileges");
+    }
+    // The creator can write the new file regardless of its mode
+    fd = open(ro_file, O_RDWR | O_CREAT | O_EXCL, 00444);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file with a read-only mode");
+    }
+    if (write(fd, "a", 1) != 1) {
+        THROW_ERROR("failed to write the created file");
+    }
+    close(fd);
+    fd = open(ro_file, O_RDWR);
+    if (!(fd < 0 && errno == EACCES)) {
+        THROW_ERROR("open the read-only file to write should return EACCES");
+    }
+    if (unlink(ro_file) < 0) {
+        THROW_ERROR("failed to unlink the file in a writable dir");
+    }
+
+    if (!(unlink(locked_file) < 0 && errno == EACCES)) {
+        THROW_ERROR("unlink in a non-writable dir should return EACCES");
+    }
+    if (!(rmdir(locked_subdir) < 0 && errno == EACCES)) {
+        THROW_ERROR("rmdir in a non-writable dir should return EACCES");
+    }
+    if (!(rename(locked_file, TEST_DIR "/renamed_file") < 0 && errno == EACCES)) {
+        THROW_ERROR("rename from a non-writable dir should return EACCES");
+    }
+    if (!(link(USER_FILE, TEST_DIR "/locked_dir/link") < 0 && errno == EACCES)) {
+        THROW_ERROR("link in a non-writable dir should return EACCES");
+    }
+    if (!(stat(private_file, &stat_buf) < 0 && errno == EACCES)) {
+        THROW_ERROR("lookup through a non-searchable dir should return EACCES");
+    }
+
+    if (restore_root() < 0) {
+        THROW_ERROR("failed to switch back to root");
+    }
+    if (stat(private_file, &stat_buf) < 0) {
+        THROW_ERROR("root failed to lookup through any dir");
+    }
+    return 0;
+}
+
+static volatile int sender_uid = -1;
+
+static void record_sender_uid(int signum, siginfo_t *info, void *context) {
+    sender_uid = info->si_uid;
+}
+
+static int test_kill_sender_uid() {
+    struct sigaction action = { 0 };
+    action.sa_sigaction = record_sender_uid;
+    action.sa_flags = SA_SIGINFO;
+    if (sigaction(SIGUSR1, &action, NULL) < 0) {
+        THROW_ERROR("failed to set the signal handler");
+    }
+
+    // Keep root as the saved user ID to restore the privileges later
+    if (setresuid(TEST_UID, TEST_UID, 0) < 0) {
+        THROW_ERROR("failed to setresuid");
+    }
+    int ret = kill(getpid(), SIGUSR1);
+    if (setresuid(0, 0, 0) < 0) {
+        THROW_ERROR("failed to restore root");
+    }
+    if (ret < 0) {
+        THROW_ERROR("failed to kill");
+    }
+    if (sender_uid != TEST_UID) {
+        THROW_ERROR("the uid of the sender should be the real user ID");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_default_ids),
+    TEST_CASE(test_setgroups),
+    TEST_CASE(test_seteuid_and_restore),
+    TEST_CASE(test_setfsuid),
+    TEST_CASE(test_file_permissions),
+    TEST_CASE(test_dir_permissions),
+    TEST_CASE(test_kill_sender_uid),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
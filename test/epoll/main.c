@@ -163,6 +163,107 @@ int test_epoll_ctl_eventfd() {
     return 0;
 }
 
+int test_epoll_exclusive_flags() {
+    int event_fd = eventfd(0, EFD_NONBLOCK);
+    int epfd = epoll_create1(0);
+    int epfd2 = epoll_create1(0);
+    if (event_fd < 0 || epfd < 0 || epfd2 < 0) {
+        THROW_ERROR("failed to create files");
+    }
+
+    struct epoll_event event;
+    event.data.fd = event_fd;
+    event.events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLONESHOT;
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, event_fd, &event) != -1 || errno != EINVAL) {
+        THROW_ERROR("EPOLLEXCLUSIVE with EPOLLONESHOT should be rejected");
+    }
+    event.events = EPOLLIN | EPOLLPRI | EPOLLEXCLUSIVE;
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, event_fd, &event) != -1 || errno != EINVAL) {
+        THROW_ERROR("EPOLLEXCLUSIVE with EPOLLPRI should be rejected");
+    }
+    event.events = EPOLLIN | EPOLLEXCLUSIVE;
+    if (epoll_ctl(epfd2, EPOLL_CTL_ADD, epfd, &event) != -1 || errno != EINVAL) {
+        THROW_ERROR("EPOLLEXCLUSIVE on an epoll file should be rejected");
+    }
+
+    event.events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLWAKEUP;
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, event_fd, &event) < 0) {
+        THROW_ERROR("failed to add an exclusive entry");
+    }
+    event.events = EPOLLIN;
+    if (epoll_ctl(epfd, EPOLL_CTL_MOD, event_fd, &event) != -1 || errno != EINVAL) {
+        THROW_ERROR("an exclusive entry should not be modified");
+    }
+    if (epoll_ctl(epfd, EPOLL_CTL_DEL, event_fd, &event) < 0) {
+        THROW_ERROR("failed to delete an exclusive entry");
+    }
+
+    close(epfd2);
+    close(epfd);
+    close(event_fd);
+    return 0;
+}
+
+#define NR_EXCLUSIVE_WAITERS 4
+
+static void *thread_exclusive_waiter(void *arg) {
+    struct thread_arg *waiter_arg = arg;
+    struct epoll_event event;
+    int nfds = epoll_wait(waiter_arg->fd, &event, 1, 3000);
+    waiter_arg->data = nfds > 0 ? 1 : 0;
+    return NULL;
+}
+
+// This test intends to test that only one of the epoll files that share an
+// EPOLLEXCLUSIVE interest is waken up by an event
+int test_epoll_exclusive_wakeup() {
+    struct thread_arg waiters[NR_EXCLUSIVE_WAITERS];
+    uint64_t data = TEST_DATA;
+
+    int event_fd = eventfd(0, EFD_NONBLOCK);
+    if (event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+
+    for (int i = 0; i < NR_EXCLUSIVE_WAITERS; i++) {
+        waiters[i].fd = epoll_create1(0);
+        waiters[i].data = 0;
+        if (waiters[i].fd < 0) {
+            THROW_ERROR("epoll_create failed");
+        }
+
+        struct epoll_event event;
+        event.data.fd = event_fd;
+        event.events = EPOLLIN | EPOLLET | EPOLLEXCLUSIVE;
+        if (epoll_ctl(waiters[i].fd, EPOLL_CTL_ADD, event_fd, &event) < 0) {
+            THROW_ERROR("epoll_ctl add failed");
+        }
+        if (pthread_create(&waiters[i].tid, NULL, &thread_exclusive_waiter, &waiters[i]) != 0) {
+            THROW_ERROR("failed to create the thread");
+        }
+    }
+
+    // Wait for all the threads to start epoll_wait
+    sleep(1);
+
+    if (write(event_fd, &data, sizeof(data)) < 0) {
+        THROW_ERROR("failed to write an eventfd");
+    }
+
+    int nr_woken = 0;
+    for (int i = 0; i < NR_EXCLUSIVE_WAITERS; i++) {
+        pthread_join(waiters[i].tid, NULL);
+        nr_woken += waiters[i].data;
+        close(waiters[i].fd);
+    }
+    close(event_fd);
+
+    if (nr_woken != 1) {
+        THROW_ERROR("%d waiters are waken up, instead of one", nr_woken);
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -170,6 +271,8 @@ int test_epoll_ctl_eventfd() {
 static test_case_t test_cases[] = {
     TEST_CASE(test_epoll_ctl_eventfd),
     TEST_CASE(test_epoll_ctl_uds),
+    TEST_CASE(test_epoll_exclusive_flags),
+    TEST_CASE(test_epoll_exclusive_wakeup),
 };
 
 int main() {
@@ -93,6 +93,60 @@ static int __test_pwrite_pread(const char *file_path) {
     return 0;
 }
 
+static int __test_large_pwrite_pread(const char *file_path) {
+    // Span many nodes of the protected file that backs the SEFS file
+    size_t len = 300 * KB + 123;
+    off_t offset = BLK_SIZE + 7;
+    int fd, ret = -1;
+    char *write_buf = malloc(len);
+    char *read_buf = malloc(len);
+    if (write_buf == NULL || read_buf == NULL) {
+        THROW_ERROR("failed to malloc buffers");
+    }
+    for (size_t i = 0; i < len; i++) {
+        write_buf[i] = (char)(i * 31 + 7);
+    }
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        goto out;
+    }
+    if (pwrite(fd, write_buf, len, offset) != len) {
+        printf("failed to pwrite the file\n");
+        goto close_out;
+    }
+    // Write the nodes back to the host file
+    if (fsync(fd) < 0) {
+        printf("failed to fsync the file\n");
+        goto close_out;
+    }
+    close(fd);
+
+    fd = open(file_path, O_RDONLY);
+    if (fd < 0) {
+        goto out;
+    }
+    memset(read_buf, 0, len);
+    if (pread(fd, read_buf, len, offset) != len) {
+        printf("failed to pread the file\n");
+        goto close_out;
+    }
+    if (memcmp(write_buf, read_buf, len) != 0) {
+        printf("failed to check the content read\n");
+        goto close_out;
+    }
+    ret = 0;
+close_out:
+    close(fd);
+out:
+    free(write_buf);
+    free(read_buf);
+    if (ret < 0) {
+        THROW_ERROR("failed to test large pwrite and pread");
+    }
+    return 0;
+}
+
 static int __test_writev_readv(const char *file_path) {
     const char *iov_msg[2] = {"hello_", "world!"};
     char read_buf[128] = { 0 };
@@ -493,6 +547,10 @@ static int test_pwrite_pread() {
     return test_file_framework(__test_pwrite_pread);
 }
 
+static int test_large_pwrite_pread() {
+    return test_file_framework(__test_large_pwrite_pread);
+}
+
 static int test_writev_readv() {
     return test_file_framework(__test_writev_readv);
 }
@@ -536,6 +594,7 @@ static int test_fallocate_collapse_range() {
 static test_case_t test_cases[] = {
     TEST_CASE(test_write_read),
     TEST_CASE(test_pwrite_pread),
+    TEST_CASE(test_large_pwrite_pread),
     TEST_CASE(test_writev_readv),
     TEST_CASE(test_lseek),
     TEST_CASE(test_posix_fallocate),
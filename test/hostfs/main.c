@@ -3,6 +3,7 @@
 #include <fcntl.h>
 #include <dirent.h>
 #include <stdbool.h>
+#include <stdlib.h>
 #include "test_fs.h"
 
 // ============================================================================
@@ -97,6 +98,62 @@ static int __test_write_fsync_read(const char *file_path) {
     return 0;
 }
 
+static int __test_large_pwrite_pread(const char *file_path) {
+    // Not a multiple of the page size, and larger than the I/O chunk
+    size_t len = 300 * 1024 + 123;
+    off_t offset = 4096 + 7;
+    int fd, ret = -1;
+    char *write_buf = malloc(len);
+    char *read_buf = malloc(len);
+    if (write_buf == NULL || read_buf == NULL) {
+        THROW_ERROR("failed to malloc buffers");
+    }
+    for (size_t i = 0; i < len; i++) {
+        write_buf[i] = (char)(i * 31 + 7);
+    }
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        goto out;
+    }
+    if (pwrite(fd, write_buf, len, offset) != len) {
+        printf("failed to pwrite the file\n");
+        goto close_out;
+    }
+    if (fsync(fd) < 0) {
+        printf("failed to fsync the file\n");
+        goto close_out;
+    }
+    memset(read_buf, 0, len);
+    if (pread(fd, read_buf, len, offset) != len) {
+        printf("failed to pread the file\n");
+        goto close_out;
+    }
+    if (memcmp(write_buf, read_buf, len) != 0) {
+        printf("failed to check the content read\n");
+        goto close_out;
+    }
+    // Read across the end of file
+    if (pread(fd, read_buf, len, offset + len / 2) != len - len / 2) {
+        printf("failed to pread across the end of file\n");
+        goto close_out;
+    }
+    if (memcmp(write_buf + len / 2, read_buf, len - len / 2) != 0) {
+        printf("failed to check the content read across the end of file\n");
+        goto close_out;
+    }
+    ret = 0;
+close_out:
+    close(fd);
+out:
+    free(write_buf);
+    free(read_buf);
+    if (ret < 0) {
+        THROW_ERROR("failed to test large pwrite and pread");
+    }
+    return 0;
+}
+
 static int __test_rename(const char *file_path) {
     char *rename_path = "/host/hostfs_rename.txt";
     struct stat stat_buf;
@@ -198,6 +255,10 @@ static int test_write_fsync_read() {
     return test_hostfs_framework(__test_write_fsync_read);
 }
 
+static int test_large_pwrite_pread() {
+    return test_hostfs_framework(__test_large_pwrite_pread);
+}
+
 static int test_rename() {
     return test_hostfs_framework(__test_rename);
 }
@@ -249,6 +310,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_write_read),
     TEST_CASE(test_write_fdatasync_read),
     TEST_CASE(test_write_fsync_read),
+    TEST_CASE(test_large_pwrite_pread),
     TEST_CASE(test_rename),
     TEST_CASE(test_readdir),
     TEST_CASE(test_truncate),
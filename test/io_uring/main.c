@@ -0,0 +1,692 @@
+#include <sys/mman.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/un.h>
+#include <linux/io_uring.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define URING_FILE_PATH     "/root/test_io_uring.txt"
+#define URING_BUF_LEN       4096
+#define URING_ENTRIES       8
+#define URING_INET_PORT     8839
+#define URING_UNIX_PATH     "/tmp/test_io_uring.sock"
+
+struct ring {
+    int fd;
+    void *ring_ptr;
+    size_t ring_size;
+    struct io_uring_sqe *sqes;
+    size_t sqes_size;
+    unsigned *sq_head;
+    unsigned *sq_tail;
+    unsigned *sq_mask;
+    unsigned *sq_array;
+    unsigned *cq_head;
+    unsigned *cq_tail;
+    unsigned *cq_mask;
+    struct io_uring_cqe *cqes;
+};
+
+static int io_uring_setup(unsigned entries, struct io_uring_params *params) {
+    return syscall(SYS_io_uring_setup, entries, params);
+}
+
+static int io_uring_enter(int fd, unsigned to_submit, unsigned min_complete,
+                          unsigned flags) {
+    return syscall(SYS_io_uring_enter, fd, to_submit, min_complete, flags, NULL, 0);
+}
+
+static int io_uring_register(int fd, unsigned opcode, void *arg, unsigned nr_args) {
+    return syscall(SYS_io_uring_register, fd, opcode, arg, nr_args);
+}
+
+static int ring_init(struct ring *ring, unsigned entries) {
+    struct io_uring_params params;
+
+    memset(&params, 0, sizeof(params));
+    ring->fd = io_uring_setup(entries, &params);
+    if (ring->fd < 0) {
+        THROW_ERROR("failed to setup an io_uring");
+    }
+    if (!(params.features & IORING_FEAT_SINGLE_MMAP)) {
+        close(ring->fd);
+        THROW_ERROR("the SQ ring and CQ ring should be mapped once");
+    }
+
+    size_t sq_size = params.sq_off.array + params.sq_entries * sizeof(unsigned);
+    size_t cq_size = params.cq_off.cqes + params.cq_entries * sizeof(struct io_uring_cqe);
+    ring->ring_size = sq_size > cq_size ? sq_size : cq_size;
+    ring->ring_ptr = mmap(NULL, ring->ring_size, PROT_READ | PROT_WRITE,
+                          MAP_SHARED | MAP_POPULATE, ring->fd, IORING_OFF_SQ_RING);
+    if (ring->ring_ptr == MAP_FAILED) {
+        close(ring->fd);
+        THROW_ERROR("failed to map the rings");
+    }
+    ring->sqes_size = params.sq_entries * sizeof(struct io_uring_sqe);
+    ring->sqes = mmap(NULL, ring->sqes_size, PROT_READ | PROT_WRITE,
+                      MAP_SHARED | MAP_POPULATE, ring->fd, IORING_OFF_SQES);
+    if (ring->sqes == MAP_FAILED) {
+        munmap(ring->ring_ptr, ring->ring_size);
+        close(ring->fd);
+        THROW_ERROR("failed to map the SQEs");
+    }
+
+    char *ptr = ring->ring_ptr;
+    ring->sq_head = (unsigned *)(ptr + params.sq_off.head);
+    ring->sq_tail = (unsigned *)(ptr + params.sq_off.tail);
+    ring->sq_mask = (unsigned *)(ptr + params.sq_off.ring_mask);
+    ring->sq_array = (unsigned *)(ptr + params.sq_off.array);
+    ring->cq_head = (unsigned *)(ptr + params.cq_off.head);
+    ring->cq_tail = (unsigned *)(ptr + params.cq_off.tail);
+    ring->cq_mask = (unsigned *)(ptr + params.cq_off.ring_mask);
+    ring->cqes = (struct io_uring_cqe *)(ptr + params.cq_off.cqes);
+    return 0;
+}
+
+static void ring_exit(struct ring *ring) {
+    munmap(ring->sqes, ring->sqes_size);
+    munmap(ring->ring_ptr, ring->ring_size);
+    close(ring->fd);
+}
+
+// Get an SQE, which is submitted by the next io_uring_enter
+static struct io_uring_sqe *ring_get_sqe(struct ring *ring, int opcode, int fd,
+        uint64_t user_data) {
+    unsigned tail = *ring->sq_tail;
+    unsigned idx = tail & *ring->sq_mask;
+    struct io_uring_sqe *sqe = &ring->sqes[idx];
+
+    memset(sqe, 0, sizeof(*sqe));
+    sqe->opcode = opcode;
+    sqe->fd = fd;
+    sqe->user_data = user_data;
+    ring->sq_array[idx] = idx;
+    __atomic_store_n(ring->sq_tail, tail + 1, __ATOMIC_RELEASE);
+    return sqe;
+}
+
+// Pop a CQE, returning -1 if the CQ ring is empty
+static int ring_pop_cqe(struct ring *ring, struct io_uring_cqe *cqe) {
+    unsigned head = *ring->cq_head;
+    if (head == __atomic_load_n(ring->cq_tail, __ATOMIC_ACQUIRE)) {
+        return -1;
+    }
+    *cqe = ring->cqes[head & *ring->cq_mask];
+    __atomic_store_n(ring->cq_head, head + 1, __ATOMIC_RELEASE);
+    return 0;
+}
+
+// Pop the CQE of the request, which must be completed
+static int ring_expect_cqe(struct ring *ring, uint64_t user_data, int res) {
+    struct io_uring_cqe cqe;
+    if (ring_pop_cqe(ring, &cqe) < 0) {
+        THROW_ERROR("no CQE for request %lu", (unsigned long)user_data);
+    }
+    if (cqe.user_data != user_data || cqe.res != res) {
+        THROW_ERROR("unexpected CQE: user_data = %lu, res = %d",
+                    (unsigned long)cqe.user_data, cqe.res);
+    }
+    return 0;
+}
+
+// Pop the CQEs of an accept request and a connect request in any order,
+// returning the fd accepted
+static int ring_expect_accept_connect(struct ring *ring, uint64_t accept_data,
+                                      uint64_t connect_data) {
+    struct io_uring_cqe cqe;
+    int accepted_fd = -1;
+    int connect_res = -1;
+
+    for (int i = 0; i < 2; i++) {
+        if (ring_pop_cqe(ring, &cqe) < 0) {
+            THROW_ERROR("no CQE for the accept or connect request");
+        }
+        if (cqe.user_data == accept_data) {
+            accepted_fd = cqe.res;
+        } else if (cqe.user_data == connect_data) {
+            connect_res = cqe.res;
+        } else {
+            THROW_ERROR("unexpected CQE: user_data = %lu", (unsigned long)cqe.user_data);
+        }
+    }
+    if (connect_res != 0) {
+        if (accepted_fd >= 0) {
+            close(accepted_fd);
+        }
+        THROW_ERROR("failed to connect: res = %d", connect_res);
+    }
+    if (accepted_fd < 0) {
+        THROW_ERROR("failed to accept: res = %d", accepted_fd);
+    }
+    return accepted_fd;
+}
+
+// Accept and connect through the ring, and then receive on the accepted
+// socket. The listener and the client socket are blocking, so the requests
+// must never block the submitter.
+static int test_accept_connect_recv(int listen_fd, int client_fd,
+                                    const struct sockaddr *addr, socklen_t addr_len) {
+    char buf[16] = { 0 };
+    struct io_uring_cqe cqe;
+    struct io_uring_sqe *sqe;
+    struct ring ring;
+    int accepted_fd = -1;
+    int ret = -1;
+
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        THROW_ERROR("failed to init the ring");
+    }
+
+    // No connection is incoming, so the accept request should be pending
+    sqe = ring_get_sqe(&ring, IORING_OP_ACCEPT, listen_fd, 1);
+    if (io_uring_enter(ring.fd, 1, 0, 0) != 1) {
+        printf("failed to submit the accept request\n");
+        goto out;
+    }
+    if (ring_pop_cqe(&ring, &cqe) == 0) {
+        printf("the accept request should be pending\n");
+        goto out;
+    }
+
+    sqe = ring_get_sqe(&ring, IORING_OP_CONNECT, client_fd, 2);
+    sqe->addr = (uint64_t)addr;
+    sqe->off = addr_len;
+    if (io_uring_enter(ring.fd, 1, 2, IORING_ENTER_GETEVENTS) != 1) {
+        printf("failed to submit the connect request\n");
+        goto out;
+    }
+    accepted_fd = ring_expect_accept_connect(&ring, 1, 2);
+    if (accepted_fd < 0) {
+        goto out;
+    }
+
+    // No data is sent, so the receiving request should be pending
+    sqe = ring_get_sqe(&ring, IORING_OP_RECV, accepted_fd, 3);
+    sqe->addr = (uint64_t)buf;
+    sqe->len = sizeof(buf);
+    if (io_uring_enter(ring.fd, 1, 0, 0) != 1) {
+        printf("failed to submit the receiving request\n");
+        goto out;
+    }
+    if (ring_pop_cqe(&ring, &cqe) == 0) {
+        printf("the receiving request should be pending\n");
+        goto out;
+    }
+
+    // The data is drained by another reader, so the request should be pending
+    // again instead of blocking the thread that reaps the CQEs
+    if (write(client_fd, "hi", 2) != 2 || read(accepted_fd, buf, 2) != 2) {
+        printf("failed to drain the data\n");
+        goto out;
+    }
+    if (io_uring_enter(ring.fd, 0, 0, IORING_ENTER_GETEVENTS) != 0 ||
+            ring_pop_cqe(&ring, &cqe) == 0) {
+        printf("the receiving request should be pending again\n");
+        goto out;
+    }
+
+    memset(buf, 0, sizeof(buf));
+    if (write(client_fd, "hello", 5) != 5) {
+        printf("failed to send the data\n");
+        goto out;
+    }
+    if (io_uring_enter(ring.fd, 0, 1, IORING_ENTER_GETEVENTS) != 0 ||
+            ring_expect_cqe(&ring, 3, 5) < 0) {
+        printf("the receiving request should complete\n");
+        goto out;
+    }
+    if (strcmp(buf, "hello") != 0) {
+        printf("the content received is wrong\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    if (accepted_fd >= 0) {
+        close(accepted_fd);
+    }
+    return ret;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_setup_and_probe() {
+    struct io_uring_params params;
+    struct ring ring;
+    int ret = -1;
+
+    memset(&params, 0, sizeof(params));
+    if (io_uring_setup(0, &params) >= 0 || errno != EINVAL) {
+        THROW_ERROR("io_uring_setup with zero entries should fail");
+    }
+    params.flags = IORING_SETUP_SQPOLL;
+    if (io_uring_setup(URING_ENTRIES, &params) >= 0 || errno != EINVAL) {
+        THROW_ERROR("SQPOLL should not be supported");
+    }
+    memset(&params, 0, sizeof(params));
+    int fd = io_uring_setup(5, &params);
+    if (fd < 0) {
+        THROW_ERROR("failed to setup an io_uring");
+    }
+    close(fd);
+    if (params.sq_entries != 8 || params.cq_entries != 16) {
+        THROW_ERROR("the entries should be rounded up to a power of two");
+    }
+
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        THROW_ERROR("failed to init the ring");
+    }
+    size_t probe_size = sizeof(struct io_uring_probe) + 256 * sizeof(struct io_uring_probe_op);
+    struct io_uring_probe *probe = calloc(1, probe_size);
+    if (probe == NULL) {
+        ring_exit(&ring);
+        THROW_ERROR("failed to allocate the probe");
+    }
+    if (io_uring_register(ring.fd, IORING_REGISTER_PROBE, probe, 256) < 0) {
+        printf("failed to probe the opcodes\n");
+        goto out;
+    }
+    if (probe->last_op < IORING_OP_RECV || probe->ops_len != probe->last_op + 1) {
+        printf("the probe is wrong\n");
+        goto out;
+    }
+    if (!(probe->ops[IORING_OP_READ].flags & IO_URING_OP_SUPPORTED) ||
+            !(probe->ops[IORING_OP_ACCEPT].flags & IO_URING_OP_SUPPORTED) ||
+            (probe->ops[IORING_OP_SYNC_FILE_RANGE].flags & IO_URING_OP_SUPPORTED)) {
+        printf("the supported opcodes are wrong\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    free(probe);
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_protect_rings() {
+    struct ring ring;
+    int ret = -1;
+
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        THROW_ERROR("failed to init the ring");
+    }
+    // The rings must stay accessible to the LibOS
+    if (mprotect(ring.ring_ptr, ring.ring_size, PROT_READ) == 0 || errno != EACCES) {
+        printf("the rings should not be read-only\n");
+        goto out;
+    }
+    if (mprotect(ring.sqes, ring.sqes_size, PROT_NONE) == 0 || errno != EACCES) {
+        printf("the SQEs should not be inaccessible\n");
+        goto out;
+    }
+    // The SQEs are only read by the LibOS
+    if (mprotect(ring.sqes, ring.sqes_size, PROT_READ) < 0) {
+        printf("failed to protect the SQEs read-only\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_file_read_write() {
+    static char write_buf[URING_BUF_LEN];
+    static char read_buf[URING_BUF_LEN];
+    struct io_uring_sqe *sqe;
+    struct ring ring;
+    int ret = -1;
+
+    memset(write_buf, 'a', sizeof(write_buf));
+    int fd = open(URING_FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        close(fd);
+        THROW_ERROR("failed to init the ring");
+    }
+
+    sqe = ring_get_sqe(&ring, IORING_OP_WRITE, fd, 1);
+    sqe->addr = (uint64_t)write_buf;
+    sqe->len = URING_BUF_LEN;
+    sqe->off = 100;
+    sqe = ring_get_sqe(&ring, IORING_OP_FSYNC, fd, 2);
+    sqe->fsync_flags = IORING_FSYNC_DATASYNC;
+    sqe = ring_get_sqe(&ring, IORING_OP_READ, fd, 3);
+    sqe->addr = (uint64_t)read_buf;
+    sqe->len = URING_BUF_LEN;
+    sqe->off = 100;
+    ring_get_sqe(&ring, IORING_OP_NOP, -1, 4);
+    if (io_uring_enter(ring.fd, 4, 4, IORING_ENTER_GETEVENTS) != 4) {
+        printf("failed to submit the requests\n");
+        goto out;
+    }
+    if (ring_expect_cqe(&ring, 1, URING_BUF_LEN) < 0 ||
+            ring_expect_cqe(&ring, 2, 0) < 0 ||
+            ring_expect_cqe(&ring, 3, URING_BUF_LEN) < 0 ||
+            ring_expect_cqe(&ring, 4, 0) < 0) {
+        goto out;
+    }
+    if (memcmp(write_buf, read_buf, URING_BUF_LEN) != 0) {
+        printf("the content read is wrong\n");
+        goto out;
+    }
+
+    // Errors are reported by the CQEs
+    sqe = ring_get_sqe(&ring, IORING_OP_READ, -1, 5);
+    sqe->addr = (uint64_t)read_buf;
+    sqe->len = URING_BUF_LEN;
+    if (io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS) != 1 ||
+            ring_expect_cqe(&ring, 5, -EBADF) < 0) {
+        printf("the error should be reported by the CQE\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    close(fd);
+    unlink(URING_FILE_PATH);
+    return ret;
+}
+
+static int test_pipe_read_pending() {
+    char buf[16] = { 0 };
+    struct io_uring_cqe cqe;
+    struct io_uring_sqe *sqe;
+    struct ring ring;
+    int pipe_fds[2];
+    int ret = -1;
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to init the ring");
+    }
+
+    sqe = ring_get_sqe(&ring, IORING_OP_READ, pipe_fds[0], 1);
+    sqe->addr = (uint64_t)buf;
+    sqe->len = sizeof(buf);
+    if (io_uring_enter(ring.fd, 1, 0, 0) != 1) {
+        printf("failed to submit the read request\n");
+        goto out;
+    }
+    // The pipe is empty, so the request should not block the submission
+    if (ring_pop_cqe(&ring, &cqe) == 0) {
+        printf("the read request should be pending\n");
+        goto out;
+    }
+    if (write(pipe_fds[1], "hello", 5) != 5) {
+        printf("failed to write the pipe\n");
+        goto out;
+    }
+    if (io_uring_enter(ring.fd, 0, 1, IORING_ENTER_GETEVENTS) != 0 ||
+            ring_expect_cqe(&ring, 1, 5) < 0) {
+        printf("the read request should complete\n");
+        goto out;
+    }
+    if (strcmp(buf, "hello") != 0) {
+        printf("the content read is wrong\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    close_files(2, pipe_fds[0], pipe_fds[1]);
+    return ret;
+}
+
+static int test_poll_and_cancel() {
+    struct io_uring_sqe *sqe;
+    struct ring ring;
+    int pipe_fds[2];
+    int ret = -1;
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to init the ring");
+    }
+
+    // The write end is always writable
+    sqe = ring_get_sqe(&ring, IORING_OP_POLL_ADD, pipe_fds[1], 1);
+    sqe->poll_events = POLLOUT;
+    if (io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS) != 1 ||
+            ring_expect_cqe(&ring, 1, POLLOUT) < 0) {
+        printf("the poll request should complete at once\n");
+        goto out;
+    }
+
+    // Remove a pending poll request
+    sqe = ring_get_sqe(&ring, IORING_OP_POLL_ADD, pipe_fds[0], 2);
+    sqe->poll_events = POLLIN;
+    sqe = ring_get_sqe(&ring, IORING_OP_POLL_REMOVE, -1, 3);
+    sqe->addr = 2;
+    if (io_uring_enter(ring.fd, 2, 2, IORING_ENTER_GETEVENTS) != 2 ||
+            ring_expect_cqe(&ring, 2, -ECANCELED) < 0 ||
+            ring_expect_cqe(&ring, 3, 0) < 0) {
+        printf("failed to remove the poll request\n");
+        goto out;
+    }
+    sqe = ring_get_sqe(&ring, IORING_OP_POLL_REMOVE, -1, 4);
+    sqe->addr = 2;
+    if (io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS) != 1 ||
+            ring_expect_cqe(&ring, 4, -ENOENT) < 0) {
+        printf("a completed request cannot be removed\n");
+        goto out;
+    }
+
+    // Cancel a pending read request
+    static char buf[16];
+    sqe = ring_get_sqe(&ring, IORING_OP_READ, pipe_fds[0], 5);
+    sqe->addr = (uint64_t)buf;
+    sqe->len = sizeof(buf);
+    sqe = ring_get_sqe(&ring, IORING_OP_ASYNC_CANCEL, -1, 6);
+    sqe->addr = 5;
+    if (io_uring_enter(ring.fd, 2, 2, IORING_ENTER_GETEVENTS) != 2 ||
+            ring_expect_cqe(&ring, 5, -ECANCELED) < 0 ||
+            ring_expect_cqe(&ring, 6, 0) < 0) {
+        printf("failed to cancel the read request\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    close_files(2, pipe_fds[0], pipe_fds[1]);
+    return ret;
+}
+
+static int test_timeout() {
+    struct __kernel_timespec ts = { 0, 10 * 1000 * 1000 };
+    struct io_uring_sqe *sqe;
+    struct ring ring;
+    int ret = -1;
+
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        THROW_ERROR("failed to init the ring");
+    }
+
+    sqe = ring_get_sqe(&ring, IORING_OP_TIMEOUT, -1, 1);
+    sqe->addr = (uint64_t)&ts;
+    sqe->len = 1;
+    if (io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS) != 1 ||
+            ring_expect_cqe(&ring, 1, -ETIME) < 0) {
+        printf("the timeout request should expire\n");
+        goto out;
+    }
+
+    // The timeout request completes after another request completes
+    ts.tv_sec = 60;
+    sqe = ring_get_sqe(&ring, IORING_OP_TIMEOUT, -1, 2);
+    sqe->addr = (uint64_t)&ts;
+    sqe->len = 1;
+    sqe->off = 1;
+    ring_get_sqe(&ring, IORING_OP_NOP, -1, 3);
+    if (io_uring_enter(ring.fd, 2, 2, IORING_ENTER_GETEVENTS) != 2 ||
+            ring_expect_cqe(&ring, 3, 0) < 0 ||
+            ring_expect_cqe(&ring, 2, 0) < 0) {
+        printf("the timeout request should complete by the count\n");
+        goto out;
+    }
+
+    // Remove a timeout request
+    sqe = ring_get_sqe(&ring, IORING_OP_TIMEOUT, -1, 4);
+    sqe->addr = (uint64_t)&ts;
+    sqe->len = 1;
+    sqe = ring_get_sqe(&ring, IORING_OP_TIMEOUT_REMOVE, -1, 5);
+    sqe->addr = 4;
+    if (io_uring_enter(ring.fd, 2, 2, IORING_ENTER_GETEVENTS) != 2 ||
+            ring_expect_cqe(&ring, 4, -ECANCELED) < 0 ||
+            ring_expect_cqe(&ring, 5, 0) < 0) {
+        printf("failed to remove the timeout request\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_socket_send_recv() {
+    char buf[16] = { 0 };
+    struct io_uring_sqe *sqe;
+    struct ring ring;
+    int socks[2];
+    int ret = -1;
+
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a socket pair");
+    }
+    if (ring_init(&ring, URING_ENTRIES) < 0) {
+        close_files(2, socks[0], socks[1]);
+        THROW_ERROR("failed to init the ring");
+    }
+
+    // The receiving request waits for the sending one
+    sqe = ring_get_sqe(&ring, IORING_OP_RECV, socks[1], 1);
+    sqe->addr = (uint64_t)buf;
+    sqe->len = sizeof(buf);
+    sqe = ring_get_sqe(&ring, IORING_OP_SEND, socks[0], 2);
+    sqe->addr = (uint64_t)"hello";
+    sqe->len = 5;
+    if (io_uring_enter(ring.fd, 2, 2, IORING_ENTER_GETEVENTS) != 2 ||
+            ring_expect_cqe(&ring, 2, 5) < 0 ||
+            ring_expect_cqe(&ring, 1, 5) < 0) {
+        printf("failed to send and receive\n");
+        goto out;
+    }
+    if (strcmp(buf, "hello") != 0) {
+        printf("the content received is wrong\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    close_files(2, socks[0], socks[1]);
+    return ret;
+}
+
+static int test_inet_accept_connect() {
+    struct sockaddr_in addr;
+    int listen_fd, client_fd;
+    int ret;
+
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(URING_INET_PORT);
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+    listen_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to create the listener");
+    }
+    int reuse = 1;
+    setsockopt(listen_fd, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse));
+    if (bind(listen_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            listen(listen_fd, 8) < 0) {
+        close(listen_fd);
+        THROW_ERROR("failed to listen");
+    }
+    client_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (client_fd < 0) {
+        close(listen_fd);
+        THROW_ERROR("failed to create the client socket");
+    }
+
+    ret = test_accept_connect_recv(listen_fd, client_fd, (struct sockaddr *)&addr,
+                                   sizeof(addr));
+    close_files(2, listen_fd, client_fd);
+    return ret;
+}
+
+static int test_unix_accept_connect() {
+    struct sockaddr_un addr;
+    int listen_fd, client_fd;
+    int ret;
+
+    memset(&addr, 0, sizeof(addr));
+    addr.sun_family = AF_UNIX;
+    strcpy(addr.sun_path, URING_UNIX_PATH);
+    socklen_t addr_len = strlen(addr.sun_path) + sizeof(addr.sun_family) + 1;
+    unlink(URING_UNIX_PATH);
+
+    listen_fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to create the listener");
+    }
+    if (bind(listen_fd, (struct sockaddr *)&addr, addr_len) < 0 ||
+            listen(listen_fd, 8) < 0) {
+        close(listen_fd);
+        THROW_ERROR("failed to listen");
+    }
+    client_fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (client_fd < 0) {
+        close(listen_fd);
+        THROW_ERROR("failed to create the client socket");
+    }
+
+    ret = test_accept_connect_recv(listen_fd, client_fd, (struct sockaddr *)&addr, addr_len);
+    close_files(2, listen_fd, client_fd);
+    unlink(URING_UNIX_PATH);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_setup_and_probe),
+    TEST_CASE(test_protect_rings),
+    TEST_CASE(test_file_read_write),
+    TEST_CASE(test_pipe_read_pending),
+    TEST_CASE(test_poll_and_cancel),
+    TEST_CASE(test_timeout),
+    TEST_CASE(test_socket_send_recv),
+    TEST_CASE(test_inet_accept_connect),
+    TEST_CASE(test_unix_accept_connect),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -1,9 +1,12 @@
 #include <sys/stat.h>
 #include <sys/mount.h>
+#include <sys/mman.h>
 #include <errno.h>
 #include <fcntl.h>
 #include "test_fs.h"
 
+#define PAGE_SIZE 4096
+
 // ============================================================================
 // Helper function
 // ============================================================================
@@ -110,6 +113,20 @@ static int __test_mount_hostfs(const char *mnt_dir) {
     return 0;
 }
 
+static int __test_mount_protected_hostfs(const char *mnt_dir) {
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create protected hostfs mnt dir");
+    }
+
+    if (mount("hostfs", mnt_dir, "hostfs", 0,
+              "dir=./mnt_test/mnt_protected_hostfs,protected,"
+              "key=c7-32-b3-ed-44-df-ec-7b-25-2d-9a-32-38-8d-58-61") < 0) {
+        THROW_ERROR("failed to mount protected hostfs");
+    }
+
+    return 0;
+}
+
 static int __test_mount_ramfs(const char *mnt_dir) {
     if (create_dir(mnt_dir) < 0) {
         THROW_ERROR("failed to create ramfs mnt dir");
@@ -122,6 +139,18 @@ static int __test_mount_ramfs(const char *mnt_dir) {
     return 0;
 }
 
+static int __test_mount_procfs(const char *mnt_dir) {
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create procfs mnt dir");
+    }
+
+    if (mount("proc", mnt_dir, "procfs", 0, NULL) < 0) {
+        THROW_ERROR("failed to mount procfs");
+    }
+
+    return 0;
+}
+
 typedef int(*test_mount_func_t)(const char *);
 
 static int test_mount_framework(test_mount_func_t fn, const char *dir, bool mount) {
@@ -160,11 +189,37 @@ static int test_mount_hostfs() {
     return test_mount_framework(__test_mount_hostfs, mnt_dir, true);
 }
 
+static int test_mount_protected_hostfs() {
+    const char *mnt_dir = "/mnt_protected_hostfs";
+    return test_mount_framework(__test_mount_protected_hostfs, mnt_dir, true);
+}
+
 static int test_mount_ramfs() {
     const char *mnt_dir = "/mnt_ramfs";
     return test_mount_framework(__test_mount_ramfs, mnt_dir, true);
 }
 
+static int test_mount_procfs() {
+    const char *mnt_dir = "/mnt_procfs";
+    if (__test_mount_procfs(mnt_dir) < 0) {
+        return -1;
+    }
+
+    struct stat stat_buf;
+    if (stat("/mnt_procfs/self/exe", &stat_buf) < 0) {
+        THROW_ERROR("failed to stat file in mounted procfs");
+    }
+    return 0;
+}
+
+static int test_mount_on_mountpoint() {
+    int ret = mount("proc", "/mnt_procfs", "procfs", 0, NULL);
+    if (!(ret < 0 && errno == EBUSY)) {
+        THROW_ERROR("failed to check mounting on an existing mountpoint");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test cases for umount
 // ============================================================================
@@ -208,11 +263,451 @@ static int test_umount_hostfs() {
     return test_mount_framework(__test_umount_fs, target, false);
 }
 
+static int test_umount_protected_hostfs() {
+    const char *target = "/mnt_protected_hostfs";
+    return test_mount_framework(__test_umount_fs, target, false);
+}
+
+static int test_umount_procfs() {
+    if (umount("/mnt_procfs") < 0) {
+        THROW_ERROR("failed to umount procfs");
+    }
+    return check_file_no_exists("/mnt_procfs/self");
+}
+
 static int test_umount_ramfs() {
     const char *target = "/mnt_ramfs";
     return test_mount_framework(__test_umount_fs, target, false);
 }
 
+// ============================================================================
+// Test cases for DevFS and Ext2
+// ============================================================================
+
+#define EXT2_HOST_DIR "./mnt_test/mnt_ext2"
+#define EXT2_DISK_NAME "test_disk"
+#define EXT2_MNT_DIR "/mnt_ext2"
+
+static int test_mount_devfs() {
+    const char *mnt_dir = "/mnt_devfs";
+    char buf[16] = { 1 };
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create devfs mnt dir");
+    }
+    if (mount("dev", mnt_dir, "devfs", 0, NULL) < 0) {
+        THROW_ERROR("failed to mount devfs");
+    }
+
+    int fd = open("/mnt_devfs/zero", O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open zero device in mounted devfs");
+    }
+    if (read(fd, buf, sizeof(buf)) != sizeof(buf) || check_bytes_in_buf(buf, sizeof(buf), 0) < 0) {
+        THROW_ERROR("failed to read zero device in mounted devfs");
+    }
+    close(fd);
+    fd = open("/mnt_devfs/null", O_WRONLY);
+    if (fd < 0 || write(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        THROW_ERROR("failed to write null device in mounted devfs");
+    }
+    close(fd);
+
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount devfs");
+    }
+    return check_file_no_exists("/mnt_devfs/null");
+}
+
+static int mount_ext2(const char *source, const char *mnt_dir) {
+    return mount(source, mnt_dir, "ext2", 0,
+                 "dir=" EXT2_HOST_DIR ",disk_size=5GB,"
+                 "key=c7-32-b3-ed-44-df-ec-7b-25-2d-9a-32-38-8d-58-61");
+}
+
+static int test_mount_ext2_with_builtin_dev_name() {
+    if (create_dir(EXT2_MNT_DIR) < 0) {
+        THROW_ERROR("failed to create ext2 mnt dir");
+    }
+    int ret = mount_ext2("/dev/null", EXT2_MNT_DIR);
+    if (!(ret < 0 && errno == EINVAL)) {
+        THROW_ERROR("mounting ext2 on a disk named as a built-in device should fail");
+    }
+    return 0;
+}
+
+static int test_mount_ext2() {
+    const char *another_mnt_dir = "/mnt_ext2_another";
+    const char *file_path = EXT2_MNT_DIR "/test_write_read.txt";
+    struct stat stat_buf;
+
+    // The Ext2 on a new disk is formatted by mke2fs, which is only installed
+    // into the image if an Ext2 is configured in Occlum.json
+    if (access("/sbin/mke2fs", X_OK) < 0) {
+        printf("Warning: Skip test_mount_ext2 since mke2fs is not found\n");
+        return 0;
+    }
+    if (create_dir(EXT2_MNT_DIR) < 0 || create_dir(another_mnt_dir) < 0) {
+        THROW_ERROR("failed to create ext2 mnt dirs");
+    }
+    if (mount_ext2("/dev/" EXT2_DISK_NAME, EXT2_MNT_DIR) < 0) {
+        THROW_ERROR("failed to mount ext2");
+    }
+    if (stat("/dev/" EXT2_DISK_NAME, &stat_buf) < 0) {
+        THROW_ERROR("the disk is not registered into devfs");
+    }
+    if (write_read_file(file_path) < 0) {
+        THROW_ERROR("failed to RW files on ext2");
+    }
+    int ret = mount_ext2("/dev/" EXT2_DISK_NAME, another_mnt_dir);
+    if (!(ret < 0 && errno == EBUSY)) {
+        THROW_ERROR("mounting ext2 on a mounted disk should fail");
+    }
+    if (umount(EXT2_MNT_DIR) < 0) {
+        THROW_ERROR("failed to umount ext2");
+    }
+
+    // The files are kept after mounting the disk again
+    if (mount_ext2("/dev/" EXT2_DISK_NAME, another_mnt_dir) < 0) {
+        THROW_ERROR("failed to mount ext2 again");
+    }
+    if (fs_check_file_content("/mnt_ext2_another/test_write_read.txt", "Hello World\n") < 0) {
+        THROW_ERROR("failed to check file content after mounting again");
+    }
+    if (umount(another_mnt_dir) < 0) {
+        THROW_ERROR("failed to umount ext2");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for bind mount, remount and move mount
+// ============================================================================
+
+static int test_bind_mount() {
+    const char *src_dir = "/mnt_bind_src";
+    const char *target = "/mnt_bind";
+    char file_path[PATH_MAX] = { 0 };
+
+    if (create_dir(src_dir) < 0 || create_dir(target) < 0) {
+        THROW_ERROR("failed to create dirs");
+    }
+    if (mount(src_dir, target, NULL, MS_BIND, NULL) < 0) {
+        THROW_ERROR("failed to bind mount");
+    }
+
+    snprintf(file_path, sizeof(file_path), "%s/test_write_read.txt", target);
+    if (write_read_file(file_path) < 0) {
+        THROW_ERROR("failed to RW files on bind mount");
+    }
+    snprintf(file_path, sizeof(file_path), "%s/test_write_read.txt", src_dir);
+    if (fs_check_file_content(file_path, "Hello World\n") < 0) {
+        THROW_ERROR("failed to check file content in source dir");
+    }
+    return 0;
+}
+
+static int test_remount_read_only() {
+    const char *target = "/mnt_bind";
+    const char *file_path = "/mnt_bind/test_write_read.txt";
+    const char *new_file_path = "/mnt_bind/test_new_file.txt";
+
+    // The mount cannot become read-only while a file is opened to write
+    int opened_fd = open(file_path, O_RDWR);
+    if (opened_fd < 0) {
+        THROW_ERROR("failed to open file to write");
+    }
+    if (!(mount(NULL, target, NULL, MS_REMOUNT | MS_BIND | MS_RDONLY, NULL) < 0 &&
+            errno == EBUSY)) {
+        close(opened_fd);
+        THROW_ERROR("remount read-only with files opened to write should return EBUSY");
+    }
+    close(opened_fd);
+
+    if (mount(NULL, target, NULL, MS_REMOUNT | MS_BIND | MS_RDONLY, NULL) < 0) {
+        THROW_ERROR("failed to remount read-only");
+    }
+    opened_fd = open(file_path, O_RDONLY);
+    if (opened_fd < 0) {
+        THROW_ERROR("failed to open file to read on read-only mount");
+    }
+    void *addr = mmap(NULL, PAGE_SIZE, PROT_READ, MAP_SHARED, opened_fd, 0);
+    if (addr == MAP_FAILED) {
+        THROW_ERROR("failed to mmap read-only on read-only mount");
+    }
+    munmap(addr, PAGE_SIZE);
+    close(opened_fd);
+
+    int fd = open(file_path, O_WRONLY);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("open to write on read-only mount should return EROFS");
+    }
+    fd = open(new_file_path, O_RDWR | O_CREAT, 00666);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("create on read-only mount should return EROFS");
+    }
+    if (!(mkdir("/mnt_bind/dir", 00775) < 0 && errno == EROFS)) {
+        THROW_ERROR("mkdir on read-only mount should return EROFS");
+    }
+    if (!(unlink(file_path) < 0 && errno == EROFS)) {
+        THROW_ERROR("unlink on read-only mount should return EROFS");
+    }
+    if (fs_check_file_content(file_path, "Hello World\n") < 0) {
+        THROW_ERROR("failed to read file on read-only mount");
+    }
+    // The source dir is not affected
+    if (write_read_file("/mnt_bind_src/test_write_read.txt") < 0) {
+        THROW_ERROR("failed to RW files in source dir");
+    }
+
+    if (mount(NULL, target, NULL, MS_REMOUNT | MS_BIND, NULL) < 0) {
+        THROW_ERROR("failed to remount read-write");
+    }
+    if (write_read_file(file_path) < 0) {
+        THROW_ERROR("failed to RW files after remount");
+    }
+    if (!(mount(NULL, "/mnt_bind_src", NULL, MS_REMOUNT, NULL) < 0 && errno == EINVAL)) {
+        THROW_ERROR("remount on non-mountpoint should return EINVAL");
+    }
+    return 0;
+}
+
+static int test_move_mount() {
+    const char *target = "/mnt_move";
+    const char *file_path = "/mnt_move/test_write_read.txt";
+
+    if (create_dir(target) < 0) {
+        THROW_ERROR("failed to create dir");
+    }
+    if (mount("/mnt_bind", target, NULL, MS_MOVE, NULL) < 0) {
+        THROW_ERROR("failed to move mount");
+    }
+    if (fs_check_file_content(file_path, "Hello World\n") < 0) {
+        THROW_ERROR("failed to check file content after moving");
+    }
+    if (check_file_no_exists("/mnt_bind/test_write_read.txt") < 0) {
+        THROW_ERROR("the mount is still on the old place");
+    }
+    if (umount(target) < 0) {
+        THROW_ERROR("failed to umount the moved mount");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for tampering and rollback of protected HostFS
+// ============================================================================
+
+#define PROTECTED_HOSTFS_KEY "c7-32-b3-ed-44-df-ec-7b-25-2d-9a-32-38-8d-58-61"
+#define TAMPER_HOST_DIR "./mnt_test/mnt_protected_hostfs_tamper"
+#define TAMPER_RAW_DIR "/mnt_raw_hostfs"
+#define TAMPER_MNT_DIR "/mnt_tamper_hostfs"
+#define MAX_RAW_FILE_LEN 8192
+#define MAC_STR_LEN 47
+
+static ssize_t read_whole_file(const char *path, char *buf, size_t len) {
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        return -1;
+    }
+    ssize_t ret = pread(fd, buf, len, 0);
+    close(fd);
+    return ret;
+}
+
+static int write_whole_file(const char *path, const char *buf, size_t len) {
+    int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        return -1;
+    }
+    ssize_t ret = write(fd, buf, len);
+    close(fd);
+    return ret == len ? 0 : -1;
+}
+
+static int mount_tamper_hostfs(const char *mac) {
+    char options[256] = { 0 };
+    snprintf(options, sizeof(options), "dir=%s,protected,key=%s%s%s", TAMPER_HOST_DIR,
+             PROTECTED_HOSTFS_KEY, mac != NULL ? ",mac=" : "", mac != NULL ? mac : "");
+    return mount("hostfs", TAMPER_MNT_DIR, "hostfs", 0, options);
+}
+
+static int read_root_mac(char *mac) {
+    ssize_t len = read_whole_file(TAMPER_MNT_DIR "/.occlum_hostfs_mac", mac, MAC_STR_LEN + 1);
+    if (len != MAC_STR_LEN + 1 || mac[MAC_STR_LEN] != '\n') {
+        THROW_ERROR("failed to read the root MAC");
+    }
+    mac[MAC_STR_LEN] = '\0';
+    return 0;
+}
+
+static int check_read_eio(const char *path) {
+    char buf[64] = { 0 };
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        return errno == EIO ? 0 : -1;
+    }
+    ssize_t ret = read(fd, buf, sizeof(buf));
+    int err = errno;
+    close(fd);
+    if (!(ret < 0 && err == EIO)) {
+        THROW_ERROR("reading a tampered file should return EIO");
+    }
+    return 0;
+}
+
+static int test_protected_hostfs_tamper_and_rollback() {
+    const char *raw_data = TAMPER_RAW_DIR "/data.txt";
+    const char *raw_meta = TAMPER_RAW_DIR "/.occlum_hostfs_meta";
+    const char *data = TAMPER_MNT_DIR "/data.txt";
+    static char old_data[MAX_RAW_FILE_LEN];
+    static char old_meta[MAX_RAW_FILE_LEN];
+    static char tampered[MAX_RAW_FILE_LEN];
+    char old_mac[MAC_STR_LEN + 1] = { 0 };
+    char new_mac[MAC_STR_LEN + 1] = { 0 };
+
+    if (create_dir(TAMPER_RAW_DIR) < 0 || create_dir(TAMPER_MNT_DIR) < 0) {
+        THROW_ERROR("failed to create dirs");
+    }
+    // Access the host directory without protection to play the role of the host
+    if (mount("hostfs", TAMPER_RAW_DIR, "hostfs", 0, "dir=" TAMPER_HOST_DIR) < 0) {
+        THROW_ERROR("failed to mount raw hostfs");
+    }
+    unlink(raw_data);
+    unlink(raw_meta);
+
+    if (mount_tamper_hostfs(NULL) < 0) {
+        THROW_ERROR("failed to mount protected hostfs");
+    }
+    if (write_whole_file(data, "version 1", 9) < 0) {
+        THROW_ERROR("failed to write file");
+    }
+    if (read_root_mac(old_mac) < 0) {
+        return -1;
+    }
+    ssize_t data_len = read_whole_file(raw_data, old_data, sizeof(old_data));
+    ssize_t meta_len = read_whole_file(raw_meta, old_meta, sizeof(old_meta));
+    if (data_len <= 40 || meta_len <= 0) {
+        THROW_ERROR("failed to read the raw files");
+    }
+
+    // Tamper with the ciphertext of the data block
+    memcpy(tampered, old_data, data_len);
+    tampered[40] ^= 0xff;
+    if (write_whole_file(raw_data, tampered, data_len) < 0) {
+        THROW_ERROR("failed to tamper with the raw file");
+    }
+    if (check_read_eio(data) < 0) {
+        return -1;
+    }
+    if (write_whole_file(raw_data, old_data, data_len) < 0) {
+        THROW_ERROR("failed to restore the raw file");
+    }
+    if (fs_check_file_content(data, "version 1") < 0) {
+        THROW_ERROR("failed to read the restored file");
+    }
+
+    // Roll back the data block while mounted
+    if (write_whole_file(data, "version 2", 9) < 0) {
+        THROW_ERROR("failed to write file");
+    }
+    if (read_root_mac(new_mac) < 0) {
+        return -1;
+    }
+    if (strcmp(old_mac, new_mac) == 0) {
+        THROW_ERROR("the root MAC is not updated");
+    }
+    if (write_whole_file(raw_data, old_data, data_len) < 0) {
+        THROW_ERROR("failed to roll back the raw file");
+    }
+    if (check_read_eio(data) < 0) {
+        return -1;
+    }
+    if (umount(TAMPER_MNT_DIR) < 0) {
+        THROW_ERROR("failed to umount protected hostfs");
+    }
+
+    // Roll back the whole host directory across mounts
+    if (write_whole_file(raw_meta, old_meta, meta_len) < 0) {
+        THROW_ERROR("failed to roll back the raw metadata");
+    }
+    if (mount_tamper_hostfs(new_mac) == 0) {
+        THROW_ERROR("mounting a rolled back directory with the latest MAC should fail");
+    }
+    if (mount_tamper_hostfs(old_mac) < 0) {
+        THROW_ERROR("failed to mount with the old MAC");
+    }
+    if (fs_check_file_content(data, "version 1") < 0) {
+        THROW_ERROR("failed to read the file of the old version");
+    }
+    if (umount(TAMPER_MNT_DIR) < 0) {
+        THROW_ERROR("failed to umount protected hostfs");
+    }
+
+    // The metadata of the root directory is removed by the host
+    if (unlink(raw_meta) < 0) {
+        THROW_ERROR("failed to remove the raw metadata");
+    }
+    if (mount_tamper_hostfs(NULL) == 0) {
+        THROW_ERROR("mounting a directory without the root metadata should fail");
+    }
+
+    unlink(raw_data);
+    if (umount(TAMPER_RAW_DIR) < 0) {
+        THROW_ERROR("failed to umount raw hostfs");
+    }
+    return 0;
+}
+
+static int test_protected_hostfs_rename_and_unlink() {
+    const char *file = TAMPER_MNT_DIR "/file.txt";
+    const char *renamed = TAMPER_MNT_DIR "/renamed.txt";
+
+    if (mount_tamper_hostfs(NULL) < 0) {
+        THROW_ERROR("failed to mount protected hostfs");
+    }
+    int fd = open(file, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file");
+    }
+    if (rename(file, renamed) < 0) {
+        THROW_ERROR("failed to rename file");
+    }
+    // A new file at the old path must not share the metadata with the renamed one
+    if (write_whole_file(file, "new", 3) < 0) {
+        THROW_ERROR("failed to create a file at the old path");
+    }
+    if (write(fd, "renamed", 7) != 7) {
+        THROW_ERROR("failed to write the renamed file");
+    }
+    if (fs_check_file_content(file, "new") < 0 ||
+            fs_check_file_content(renamed, "renamed") < 0) {
+        THROW_ERROR("failed to check the file contents after rename");
+    }
+
+    // The unlinked file can still be accessed by the opened fd
+    if (unlink(renamed) < 0) {
+        THROW_ERROR("failed to unlink file");
+    }
+    char buf[16] = { 0 };
+    if (pwrite(fd, "unlinked", 8, 0) != 8 || pread(fd, buf, sizeof(buf), 0) != 8 ||
+            strcmp(buf, "unlinked") != 0) {
+        THROW_ERROR("failed to access the unlinked file");
+    }
+    close(fd);
+    if (fs_check_file_content(file, "new") < 0) {
+        THROW_ERROR("the file at the old path is changed");
+    }
+
+    if (unlink(file) < 0) {
+        THROW_ERROR("failed to unlink file");
+    }
+    if (umount(TAMPER_MNT_DIR) < 0) {
+        THROW_ERROR("failed to umount protected hostfs");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -225,8 +720,21 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_umount_unionfs),
     TEST_CASE(test_mount_hostfs),
     TEST_CASE(test_umount_hostfs),
+    TEST_CASE(test_mount_protected_hostfs),
+    TEST_CASE(test_umount_protected_hostfs),
+    TEST_CASE(test_protected_hostfs_tamper_and_rollback),
+    TEST_CASE(test_protected_hostfs_rename_and_unlink),
     TEST_CASE(test_mount_ramfs),
     TEST_CASE(test_umount_ramfs),
+    TEST_CASE(test_mount_procfs),
+    TEST_CASE(test_mount_on_mountpoint),
+    TEST_CASE(test_umount_procfs),
+    TEST_CASE(test_mount_devfs),
+    TEST_CASE(test_mount_ext2_with_builtin_dev_name),
+    TEST_CASE(test_mount_ext2),
+    TEST_CASE(test_bind_mount),
+    TEST_CASE(test_remount_read_only),
+    TEST_CASE(test_move_mount),
 };
 
 int main(int argc, const char *argv[]) {
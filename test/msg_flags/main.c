@@ -0,0 +1,230 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <sys/types.h>
+
+#include "test.h"
+
+#define TEST_MSG    "Hello, MSG flags!"
+#define TEST_PORT   8819
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int connect_tcp_pair(int *server_fd, int *client_fd) {
+    int reuse = 1;
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = htons(TEST_PORT);
+
+    int listen_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to create the listen socket");
+    }
+    if (setsockopt(listen_fd, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse)) < 0) {
+        THROW_ERROR("failed to set SO_REUSEADDR");
+    }
+    if (bind(listen_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to bind");
+    }
+    if (listen(listen_fd, 1) < 0) {
+        THROW_ERROR("failed to listen");
+    }
+
+    *client_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (*client_fd < 0) {
+        THROW_ERROR("failed to create the client socket");
+    }
+    if (connect(*client_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    *server_fd = accept(listen_fd, NULL, NULL);
+    if (*server_fd < 0) {
+        THROW_ERROR("failed to accept");
+    }
+    close(listen_fd);
+    return 0;
+}
+
+static int connect_udp_pair(int *server_fd, int *client_fd) {
+    struct sockaddr_in addr;
+    socklen_t addr_len = sizeof(addr);
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = 0;
+
+    *server_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    *client_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    if (*server_fd < 0 || *client_fd < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+    if (bind(*server_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to bind");
+    }
+    if (getsockname(*server_fd, (struct sockaddr *)&addr, &addr_len) < 0) {
+        THROW_ERROR("failed to get the bound address");
+    }
+    if (connect(*client_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_tcp_msg_peek(void) {
+    int server_fd, client_fd;
+    char buf[64] = {0};
+    int len = strlen(TEST_MSG);
+
+    if (connect_tcp_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect the TCP sockets");
+    }
+    if (send(client_fd, TEST_MSG, len, 0) != len) {
+        THROW_ERROR("failed to send");
+    }
+
+    // The peeked data should still be received later
+    for (int i = 0; i < 2; i++) {
+        memset(buf, 0, sizeof(buf));
+        if (recv(server_fd, buf, 5, MSG_PEEK) != 5 || strncmp(buf, TEST_MSG, 5) != 0) {
+            THROW_ERROR("failed to peek");
+        }
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(server_fd, buf, len, MSG_WAITALL) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("the received data mismatch");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_tcp_msg_trunc(void) {
+    int server_fd, client_fd;
+    char buf[64] = {0};
+    int len = strlen(TEST_MSG);
+
+    if (connect_tcp_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect the TCP sockets");
+    }
+    if (send(client_fd, TEST_MSG, len, 0) != len) {
+        THROW_ERROR("failed to send");
+    }
+
+    // Like Linux, the data are discarded instead of being copied
+    if (recv(server_fd, buf, 7, MSG_TRUNC | MSG_WAITALL) != 7) {
+        THROW_ERROR("failed to discard the data");
+    }
+    if (recv(server_fd, buf, len - 7, MSG_WAITALL) != len - 7 ||
+            strncmp(buf, TEST_MSG + 7, len - 7) != 0) {
+        THROW_ERROR("the remaining data mismatch");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_tcp_msg_oob(void) {
+    int server_fd, client_fd;
+    char buf[64] = {0};
+    char oob = 0;
+    int len = strlen(TEST_MSG);
+
+    if (connect_tcp_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect the TCP sockets");
+    }
+    if (send(client_fd, TEST_MSG, len, 0) != len) {
+        THROW_ERROR("failed to send");
+    }
+    // The last byte of the data sent with MSG_OOB is the urgent byte
+    if (send(client_fd, "!", 1, MSG_OOB) != 1) {
+        THROW_ERROR("failed to send the urgent data");
+    }
+
+    if (recv(server_fd, buf, len, MSG_WAITALL) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("the normal data mismatch");
+    }
+
+    // The urgent data may arrive later than the normal data
+    int ret;
+    for (int i = 0; i < 100; i++) {
+        ret = recv(server_fd, &oob, 1, MSG_OOB);
+        if (ret >= 0 || (errno != EINVAL && errno != EAGAIN)) {
+            break;
+        }
+        usleep(10 * 1000);
+    }
+    if (ret != 1 || oob != '!') {
+        THROW_ERROR("failed to receive the urgent data");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_udp_msg_flags(void) {
+    int server_fd, client_fd;
+    char buf[64] = {0};
+    int len = strlen(TEST_MSG);
+
+    if (connect_udp_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect the UDP sockets");
+    }
+    if (send(client_fd, TEST_MSG, len, MSG_OOB) >= 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("MSG_OOB should not be supported by UDP");
+    }
+    if (send(client_fd, TEST_MSG, len, 0) != len) {
+        THROW_ERROR("failed to send");
+    }
+
+    // MSG_TRUNC returns the real length of the datagram
+    if (recv(server_fd, buf, 5, MSG_PEEK | MSG_TRUNC) != len) {
+        THROW_ERROR("failed to peek the real length of the datagram");
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(server_fd, buf, sizeof(buf), MSG_PEEK) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("failed to peek the datagram");
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(server_fd, buf, sizeof(buf), 0) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("the received datagram mismatch");
+    }
+    if (recv(server_fd, buf, sizeof(buf), MSG_OOB) >= 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("MSG_OOB should not be supported by UDP");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_tcp_msg_peek),
+    TEST_CASE(test_tcp_msg_trunc),
+    TEST_CASE(test_tcp_msg_oob),
+    TEST_CASE(test_udp_msg_flags),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
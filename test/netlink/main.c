@@ -0,0 +1,354 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <ifaddrs.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <sys/socket.h>
+#include <sys/time.h>
+#include <sys/types.h>
+
+#include "test.h"
+
+#define RECV_BUF_SIZE   8192
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+struct link_request {
+    struct nlmsghdr header;
+    struct ifinfomsg info;
+};
+
+static int send_link_request(int fd, uint16_t flags, int index, uint32_t seq) {
+    struct link_request request;
+    memset(&request, 0, sizeof(request));
+    request.header.nlmsg_len = NLMSG_LENGTH(sizeof(struct ifinfomsg));
+    request.header.nlmsg_type = RTM_GETLINK;
+    request.header.nlmsg_flags = NLM_F_REQUEST | flags;
+    request.header.nlmsg_seq = seq;
+    request.info.ifi_family = AF_UNSPEC;
+    request.info.ifi_index = index;
+
+    struct sockaddr_nl kernel;
+    memset(&kernel, 0, sizeof(kernel));
+    kernel.nl_family = AF_NETLINK;
+    if (sendto(fd, &request, request.header.nlmsg_len, 0,
+               (struct sockaddr *)&kernel, sizeof(kernel)) != request.header.nlmsg_len) {
+        THROW_ERROR("failed to send the request");
+    }
+    return 0;
+}
+
+static int open_route_socket(void) {
+    int fd = socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the netlink socket");
+    }
+    return fd;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_getifaddrs(void) {
+    struct ifaddrs *ifaddrs;
+    if (getifaddrs(&ifaddrs) < 0) {
+        THROW_ERROR("getifaddrs failed");
+    }
+
+    int found_lo = 0;
+    for (struct ifaddrs *ifa = ifaddrs; ifa != NULL; ifa = ifa->ifa_next) {
+        if (ifa->ifa_addr == NULL || ifa->ifa_addr->sa_family != AF_INET) {
+            continue;
+        }
+        struct sockaddr_in *addr = (struct sockaddr_in *)ifa->ifa_addr;
+        if (strcmp(ifa->ifa_name, "lo") == 0 &&
+                addr->sin_addr.s_addr == htonl(INADDR_LOOPBACK)) {
+            found_lo = 1;
+        }
+    }
+    freeifaddrs(ifaddrs);
+
+    if (!found_lo) {
+        THROW_ERROR("lo with 127.0.0.1 is not found");
+    }
+    return 0;
+}
+
+static int test_if_nameindex(void) {
+    struct if_nameindex *ifs = if_nameindex();
+    if (ifs == NULL) {
+        THROW_ERROR("if_nameindex failed");
+    }
+
+    int found_lo = 0;
+    for (struct if_nameindex *i = ifs; i->if_index != 0; i++) {
+        if (strcmp(i->if_name, "lo") == 0) {
+            found_lo = 1;
+        }
+    }
+    if_freenameindex(ifs);
+
+    if (!found_lo) {
+        THROW_ERROR("lo is not found");
+    }
+    return 0;
+}
+
+static int test_bind_and_getsockname(void) {
+    int fd = open_route_socket();
+
+    struct sockaddr_nl addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.nl_family = AF_NETLINK;
+    if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to bind");
+    }
+
+    socklen_t addr_len = sizeof(addr);
+    memset(&addr, 0, sizeof(addr));
+    if (getsockname(fd, (struct sockaddr *)&addr, &addr_len) < 0) {
+        THROW_ERROR("failed to getsockname");
+    }
+    if (addr_len != sizeof(addr) || addr.nl_family != AF_NETLINK || addr.nl_pid == 0) {
+        THROW_ERROR("the port ID is not assigned");
+    }
+
+    int type = 0;
+    socklen_t opt_len = sizeof(type);
+    if (getsockopt(fd, SOL_SOCKET, SO_TYPE, &type, &opt_len) < 0 || type != SOCK_RAW) {
+        THROW_ERROR("failed to get the socket type");
+    }
+
+    close(fd);
+    return 0;
+}
+
+static int test_dump_links(void) {
+    int fd = open_route_socket();
+    if (send_link_request(fd, NLM_F_DUMP, 0, 1) < 0) {
+        THROW_ERROR("failed to send the dump request");
+    }
+
+    char buf[RECV_BUF_SIZE];
+    int done = 0, found_lo = 0;
+    while (!done) {
+        struct sockaddr_nl from;
+        socklen_t from_len = sizeof(from);
+        ssize_t len = recvfrom(fd, buf, sizeof(buf), 0, (struct sockaddr *)&from, &from_len);
+        if (len <= 0) {
+            THROW_ERROR("failed to receive the replies");
+        }
+        if (from.nl_family != AF_NETLINK || from.nl_pid != 0) {
+            THROW_ERROR("the replies are not from the kernel");
+        }
+
+        for (struct nlmsghdr *nh = (struct nlmsghdr *)buf; NLMSG_OK(nh, len);
+                nh = NLMSG_NEXT(nh, len)) {
+            if (nh->nlmsg_seq != 1) {
+                THROW_ERROR("unexpected sequence number");
+            }
+            if (nh->nlmsg_type == NLMSG_DONE) {
+                done = 1;
+                break;
+            }
+            if (nh->nlmsg_type != RTM_NEWLINK || !(nh->nlmsg_flags & NLM_F_MULTI)) {
+                THROW_ERROR("unexpected message type");
+            }
+
+            struct ifinfomsg *info = NLMSG_DATA(nh);
+            int attr_len = IFLA_PAYLOAD(nh);
+            for (struct rtattr *rta = IFLA_RTA(info); RTA_OK(rta, attr_len);
+                    rta = RTA_NEXT(rta, attr_len)) {
+                if (rta->rta_type == IFLA_IFNAME && strcmp(RTA_DATA(rta), "lo") == 0) {
+                    found_lo = 1;
+                }
+            }
+        }
+    }
+
+    if (!found_lo) {
+        THROW_ERROR("lo is not found");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_get_link_errors(void) {
+    int fd = open_route_socket();
+    // Hopefully no interface has such an index
+    if (send_link_request(fd, NLM_F_ACK, 0x7fffffff, 2) < 0) {
+        THROW_ERROR("failed to send the request");
+    }
+
+    char buf[RECV_BUF_SIZE];
+    ssize_t len = recv(fd, buf, sizeof(buf), 0);
+    struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+    if (len <= 0 || !NLMSG_OK(nh, len) || nh->nlmsg_type != NLMSG_ERROR) {
+        THROW_ERROR("failed to receive the error");
+    }
+    struct nlmsgerr *err = NLMSG_DATA(nh);
+    if (err->error != -ENODEV || err->msg.nlmsg_seq != 2) {
+        THROW_ERROR("unexpected error");
+    }
+
+    // Nothing is left to receive
+    if (recv(fd, buf, sizeof(buf), MSG_DONTWAIT) != -1 || errno != EAGAIN) {
+        THROW_ERROR("unexpected data are received");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_peek_and_trunc(void) {
+    int fd = open_route_socket();
+    if (send_link_request(fd, NLM_F_DUMP, 0, 3) < 0) {
+        THROW_ERROR("failed to send the dump request");
+    }
+
+    char buf[RECV_BUF_SIZE];
+    ssize_t full_len = recv(fd, buf, sizeof(buf), MSG_PEEK | MSG_TRUNC);
+    if (full_len <= (ssize_t)sizeof(struct nlmsghdr)) {
+        THROW_ERROR("failed to peek");
+    }
+
+    struct msghdr msg;
+    struct iovec iov = { .iov_base = buf, .iov_len = sizeof(struct nlmsghdr) };
+    memset(&msg, 0, sizeof(msg));
+    msg.msg_iov = &iov;
+    msg.msg_iovlen = 1;
+    ssize_t len = recvmsg(fd, &msg, 0);
+    if (len != sizeof(struct nlmsghdr) || !(msg.msg_flags & MSG_TRUNC)) {
+        THROW_ERROR("the datagram is not truncated");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_modify_not_permitted(void) {
+    int fd = open_route_socket();
+
+    struct link_request request;
+    memset(&request, 0, sizeof(request));
+    request.header.nlmsg_len = NLMSG_LENGTH(sizeof(struct ifinfomsg));
+    request.header.nlmsg_type = RTM_NEWLINK;
+    request.header.nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK;
+    request.header.nlmsg_seq = 4;
+    if (send(fd, &request, request.header.nlmsg_len, 0) != request.header.nlmsg_len) {
+        THROW_ERROR("failed to send the request");
+    }
+
+    char buf[RECV_BUF_SIZE];
+    ssize_t len = recv(fd, buf, sizeof(buf), 0);
+    struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+    if (len <= 0 || !NLMSG_OK(nh, len) || nh->nlmsg_type != NLMSG_ERROR) {
+        THROW_ERROR("failed to receive the error");
+    }
+    struct nlmsgerr *err = NLMSG_DATA(nh);
+    if (err->error != -EPERM) {
+        THROW_ERROR("the host network should not be modified");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_recv_timeout(void) {
+    int fd = open_route_socket();
+    struct timeval timeout = { .tv_sec = 0, .tv_usec = 100 * 1000 };
+    if (setsockopt(fd, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout)) < 0) {
+        THROW_ERROR("failed to set SO_RCVTIMEO");
+    }
+    struct timeval val;
+    socklen_t val_len = sizeof(val);
+    if (getsockopt(fd, SOL_SOCKET, SO_RCVTIMEO, &val, &val_len) < 0 ||
+            val.tv_sec != timeout.tv_sec || val.tv_usec != timeout.tv_usec) {
+        THROW_ERROR("failed to get SO_RCVTIMEO");
+    }
+
+    char buf[RECV_BUF_SIZE];
+    if (recv(fd, buf, sizeof(buf), 0) != -1 || errno != EAGAIN) {
+        THROW_ERROR("recv should time out");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_recv_buf_overrun(void) {
+    int fd = open_route_socket();
+    int buf_size = 1024;
+    int lo_index = if_nametoindex("lo");
+    if (lo_index == 0) {
+        THROW_ERROR("lo is not found");
+    }
+    if (setsockopt(fd, SOL_SOCKET, SO_RCVBUF, &buf_size, sizeof(buf_size)) < 0) {
+        THROW_ERROR("failed to set SO_RCVBUF");
+    }
+
+    // The replies that do not fit in the receive buffer are dropped
+    for (int i = 0; i < 200; i++) {
+        if (send_link_request(fd, 0, lo_index, 5) < 0) {
+            THROW_ERROR("failed to send the request");
+        }
+    }
+    char buf[RECV_BUF_SIZE];
+    if (recv(fd, buf, sizeof(buf), 0) != -1 || errno != ENOBUFS) {
+        THROW_ERROR("the overrun should be reported");
+    }
+    int nreplies = 0;
+    while (recv(fd, buf, sizeof(buf), MSG_DONTWAIT) > 0) {
+        nreplies++;
+    }
+    if (nreplies == 0 || nreplies >= 200) {
+        THROW_ERROR("some but not all of the replies should be received");
+    }
+
+    // The replies of a dump are never dropped
+    if (send_link_request(fd, NLM_F_DUMP, 0, 6) < 0) {
+        THROW_ERROR("failed to send the dump request");
+    }
+    int done = 0;
+    while (!done) {
+        ssize_t len = recv(fd, buf, sizeof(buf), 0);
+        if (len <= 0) {
+            THROW_ERROR("failed to receive the replies of the dump");
+        }
+        for (struct nlmsghdr *nh = (struct nlmsghdr *)buf; NLMSG_OK(nh, len);
+                nh = NLMSG_NEXT(nh, len)) {
+            if (nh->nlmsg_type == NLMSG_DONE) {
+                done = 1;
+                break;
+            }
+        }
+    }
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getifaddrs),
+    TEST_CASE(test_if_nameindex),
+    TEST_CASE(test_bind_and_getsockname),
+    TEST_CASE(test_dump_links),
+    TEST_CASE(test_get_link_errors),
+    TEST_CASE(test_peek_and_trunc),
+    TEST_CASE(test_modify_not_permitted),
+    TEST_CASE(test_recv_timeout),
+    TEST_CASE(test_recv_buf_overrun),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,225 @@
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SYS_PIDFD_SEND_SIGNAL   424
+#define SYS_PIDFD_OPEN          434
+
+#ifndef P_PIDFD
+#define P_PIDFD                 3
+#endif
+
+#define PIDFD_NONBLOCK          O_NONBLOCK
+#define CHILD_EXIT_CODE         7
+
+static int pidfd_open(pid_t pid, unsigned int flags) {
+    return syscall(SYS_PIDFD_OPEN, pid, flags);
+}
+
+static int pidfd_send_signal(int pidfd, int sig, siginfo_t *info, unsigned int flags) {
+    return syscall(SYS_PIDFD_SEND_SIGNAL, pidfd, sig, info, flags);
+}
+
+// Spawn this program again with `mode` as the argument
+static pid_t spawn_child(char *mode) {
+    pid_t child_pid;
+    char *child_argv[] = {"pidfd", mode, NULL};
+
+    if (posix_spawn(&child_pid, "/bin/pidfd", NULL, NULL, child_argv, NULL) != 0) {
+        return -1;
+    }
+    return child_pid;
+}
+
+// Wait until the pidfd becomes readable, i.e., the child exits
+static int wait_pidfd_readable(int pidfd) {
+    struct pollfd pfd = { .fd = pidfd, .events = POLLIN };
+    int ret = poll(&pfd, 1, 5000);
+    if (ret != 1 || !(pfd.revents & POLLIN)) {
+        return -1;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_pidfd_open(void) {
+    int pidfd = pidfd_open(getpid(), 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd for the current process");
+    }
+    int fd_flags = fcntl(pidfd, F_GETFD);
+    if (fd_flags < 0 || !(fd_flags & FD_CLOEXEC)) {
+        close(pidfd);
+        THROW_ERROR("the pidfd should be close-on-exec");
+    }
+    // The current process is alive
+    struct pollfd pfd = { .fd = pidfd, .events = POLLIN };
+    if (poll(&pfd, 1, 0) != 0) {
+        close(pidfd);
+        THROW_ERROR("the pidfd of a running process should not be readable");
+    }
+    // Signal 0 only checks the existence of the process
+    if (pidfd_send_signal(pidfd, 0, NULL, 0) < 0) {
+        close(pidfd);
+        THROW_ERROR("failed to send signal 0 via the pidfd");
+    }
+    close(pidfd);
+    return 0;
+}
+
+static int test_pidfd_open_invalid_args(void) {
+    if (pidfd_open(-1, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("pidfd_open should fail with an invalid pid");
+    }
+    if (pidfd_open(getpid(), O_CLOEXEC) >= 0 || errno != EINVAL) {
+        THROW_ERROR("pidfd_open should fail with unknown flags");
+    }
+    if (pidfd_open(65535, 0) >= 0 || errno != ESRCH) {
+        THROW_ERROR("pidfd_open should fail with a non-existent pid");
+    }
+    if (pidfd_send_signal(STDOUT_FILENO, SIGKILL, NULL, 0) >= 0 || errno != EBADF) {
+        THROW_ERROR("pidfd_send_signal should fail with a non-pidfd file");
+    }
+    return 0;
+}
+
+static int test_poll_and_waitid(void) {
+    siginfo_t info = {0};
+
+    pid_t child_pid = spawn_child("exit");
+    if (child_pid < 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    int pidfd = pidfd_open(child_pid, 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd for the child");
+    }
+    if (wait_pidfd_readable(pidfd) < 0) {
+        close(pidfd);
+        THROW_ERROR("the pidfd should be readable after the child exits");
+    }
+    if (waitid(P_PIDFD, pidfd, &info, WEXITED) < 0) {
+        close(pidfd);
+        THROW_ERROR("failed to waitid with the pidfd");
+    }
+    if (info.si_pid != child_pid || info.si_code != CLD_EXITED ||
+            info.si_status != CHILD_EXIT_CODE) {
+        close(pidfd);
+        THROW_ERROR("the siginfo from waitid is not correct");
+    }
+    // The child has been reaped
+    if (pidfd_send_signal(pidfd, 0, NULL, 0) >= 0 || errno != ESRCH) {
+        close(pidfd);
+        THROW_ERROR("pidfd_send_signal should fail after the child is reaped");
+    }
+    if (waitid(P_PIDFD, pidfd, &info, WEXITED) >= 0 || errno != ECHILD) {
+        close(pidfd);
+        THROW_ERROR("waitid should fail with ECHILD after the child is reaped");
+    }
+    close(pidfd);
+    return 0;
+}
+
+static int test_pidfd_send_signal(void) {
+    siginfo_t info = {0};
+
+    pid_t child_pid = spawn_child("sleep");
+    if (child_pid < 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    int pidfd = pidfd_open(child_pid, 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd for the child");
+    }
+    if (pidfd_send_signal(pidfd, SIGKILL, NULL, 1) >= 0 || errno != EINVAL) {
+        close(pidfd);
+        THROW_ERROR("pidfd_send_signal should fail with non-zero flags");
+    }
+    if (pidfd_send_signal(pidfd, SIGKILL, NULL, 0) < 0) {
+        close(pidfd);
+        THROW_ERROR("failed to send SIGKILL via the pidfd");
+    }
+    if (waitid(P_PIDFD, pidfd, &info, WEXITED) < 0) {
+        close(pidfd);
+        THROW_ERROR("failed to waitid with the pidfd");
+    }
+    if (info.si_pid != child_pid || info.si_code != CLD_KILLED || info.si_status != SIGKILL) {
+        close(pidfd);
+        THROW_ERROR("the child should be killed by SIGKILL");
+    }
+    close(pidfd);
+    return 0;
+}
+
+static int test_nonblocking_pidfd(void) {
+    siginfo_t info = {0};
+
+    pid_t child_pid = spawn_child("sleep");
+    if (child_pid < 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    int pidfd = pidfd_open(child_pid, PIDFD_NONBLOCK);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a nonblocking pidfd for the child");
+    }
+    if (waitid(P_PIDFD, pidfd, &info, WEXITED) >= 0 || errno != EAGAIN) {
+        close(pidfd);
+        THROW_ERROR("waitid should fail with EAGAIN when the child is running");
+    }
+    if (pidfd_send_signal(pidfd, SIGKILL, NULL, 0) < 0) {
+        close(pidfd);
+        THROW_ERROR("failed to send SIGKILL via the pidfd");
+    }
+    if (wait_pidfd_readable(pidfd) < 0) {
+        close(pidfd);
+        THROW_ERROR("the pidfd should be readable after the child exits");
+    }
+    if (waitid(P_PIDFD, pidfd, &info, WEXITED) < 0 || info.si_pid != child_pid) {
+        close(pidfd);
+        THROW_ERROR("failed to waitid with the nonblocking pidfd");
+    }
+    close(pidfd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_pidfd_open),
+    TEST_CASE(test_pidfd_open_invalid_args),
+    TEST_CASE(test_poll_and_waitid),
+    TEST_CASE(test_pidfd_send_signal),
+    TEST_CASE(test_nonblocking_pidfd),
+};
+
+int main(int argc, char **argv) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "sleep") == 0) {
+            sleep(100);
+            return EXIT_SUCCESS;
+        } else {
+            return CHILD_EXIT_CODE;
+        }
+    }
+
+    int ret;
+    ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -1,3 +1,4 @@
+#define _GNU_SOURCE
 #include <string.h>
 #include <stdio.h>
 #include <errno.h>
@@ -122,6 +123,46 @@ static int test_spawn_child_and_unlock() {
     return 0;
 }
 
+static int test_ofd_locks() {
+    // Two open file descriptions of the same file
+    int fd1 = open(g_file_path, O_RDWR);
+    int fd2 = open(g_file_path, O_RDWR);
+    if (fd1 < 0 || fd2 < 0) {
+        THROW_ERROR("failed to open file");
+    }
+
+    struct flock fl = { F_WRLCK, SEEK_SET, g_file_len / 2, g_file_len / 4, 0 };
+    if (fcntl(fd1, F_OFD_SETLK, &fl) < 0) {
+        THROW_ERROR("failed to call ofd setlk");
+    }
+
+    // OFD locks conflict with each other even in the same process
+    int dup_fd1 = dup(fd1);
+    if (dup_fd1 < 0) {
+        THROW_ERROR("failed to dup file");
+    }
+    close(fd1);
+    struct flock fl2 = { F_RDLCK, SEEK_SET, g_file_len / 2, 1, 0 };
+    int res = fcntl(fd2, F_OFD_SETLK, &fl2);
+    if (!(res < 0 && errno == EAGAIN)) {
+        THROW_ERROR("failed to check the result of ofd setlk with conflict lock");
+    }
+    if (fcntl(fd2, F_OFD_GETLK, &fl2) < 0) {
+        THROW_ERROR("failed to call ofd getlk");
+    }
+    if (fl2.l_type != F_WRLCK || fl2.l_pid != -1) {
+        THROW_ERROR("failed to get correct ofd lock");
+    }
+
+    // The lock is released when the last fd of the description is closed
+    close(dup_fd1);
+    if (fcntl(fd2, F_OFD_SETLK, &fl) < 0) {
+        THROW_ERROR("failed to acquire the lock after closing the owner");
+    }
+    close(fd2);
+    return 0;
+}
+
 // ============================================================================
 // Child Test cases
 // ============================================================================
@@ -171,6 +212,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_getlk),
     TEST_CASE(test_setlk),
     TEST_CASE(test_spawn_child_and_unlock),
+    TEST_CASE(test_ofd_locks),
 };
 
 static test_case_t child_test_cases[] = {
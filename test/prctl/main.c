@@ -5,7 +5,10 @@
 #include <pthread.h>
 #include <string.h>
 #include <sys/prctl.h>
+#include <sys/wait.h>
 #include <errno.h>
+#include <signal.h>
+#include <spawn.h>
 #include "test.h"
 
 // ============================================================================
@@ -139,6 +142,128 @@ static int test_prctl_get_timerslack(void) {
     return 0;
 }
 
+static int test_prctl_dumpable(void) {
+    if (prctl(PR_GET_DUMPABLE, 0, 0, 0, 0) != 1) {
+        THROW_ERROR("the process should be dumpable by default");
+    }
+    if (prctl(PR_SET_DUMPABLE, 2, 0, 0, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("setting an invalid dumpable value should fail");
+    }
+    if (prctl(PR_SET_DUMPABLE, 0, 0, 0, 0) < 0 ||
+            prctl(PR_GET_DUMPABLE, 0, 0, 0, 0) != 0) {
+        THROW_ERROR("failed to make the process not dumpable");
+    }
+    if (prctl(PR_SET_DUMPABLE, 1, 0, 0, 0) < 0 ||
+            prctl(PR_GET_DUMPABLE, 0, 0, 0, 0) != 1) {
+        THROW_ERROR("failed to make the process dumpable");
+    }
+    return 0;
+}
+
+static int spawn_self(char *mode, pid_t *pid) {
+    char *child_argv[] = {"prctl", mode, NULL};
+    return posix_spawn(pid, "/bin/prctl", NULL, NULL, child_argv, NULL);
+}
+
+// The child process exits without waiting for the grandchild, which is
+// killed by its parent death signal.
+static int child_exit_before_grandchild(void) {
+    pid_t grandchild_pid;
+    if (spawn_self("grandchild", &grandchild_pid) != 0) {
+        return EXIT_FAILURE;
+    }
+    sleep(1);
+    return EXIT_SUCCESS;
+}
+
+static int grandchild_with_pdeathsig(void) {
+    if (prctl(PR_SET_PDEATHSIG, SIGKILL, 0, 0, 0) < 0) {
+        return EXIT_FAILURE;
+    }
+    sleep(5);
+    return EXIT_SUCCESS;
+}
+
+static int test_prctl_pdeathsig(void) {
+    int signum = -1;
+    if (prctl(PR_GET_PDEATHSIG, &signum, 0, 0, 0) < 0 || signum != 0) {
+        THROW_ERROR("the parent death signal should not be set by default");
+    }
+    if (prctl(PR_SET_PDEATHSIG, 100, 0, 0, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("setting an invalid parent death signal should fail");
+    }
+    if (prctl(PR_SET_PDEATHSIG, SIGUSR1, 0, 0, 0) < 0 ||
+            prctl(PR_GET_PDEATHSIG, &signum, 0, 0, 0) < 0 || signum != SIGUSR1) {
+        THROW_ERROR("failed to set the parent death signal");
+    }
+    if (prctl(PR_SET_PDEATHSIG, 0, 0, 0, 0) < 0) {
+        THROW_ERROR("failed to clear the parent death signal");
+    }
+    return 0;
+}
+
+static int test_prctl_child_subreaper(void) {
+    int is_subreaper = -1, status = 0;
+    pid_t child_pid, pid;
+
+    if (prctl(PR_GET_CHILD_SUBREAPER, &is_subreaper, 0, 0, 0) < 0 || is_subreaper != 0) {
+        THROW_ERROR("the process should not be a subreaper by default");
+    }
+    if (prctl(PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) < 0 ||
+            prctl(PR_GET_CHILD_SUBREAPER, &is_subreaper, 0, 0, 0) < 0 || is_subreaper != 1) {
+        THROW_ERROR("failed to become a subreaper");
+    }
+
+    if (spawn_self("child", &child_pid) != 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child failed");
+    }
+
+    // The orphaned grandchild is adopted by the subreaper and killed by
+    // its parent death signal
+    pid = waitpid(-1, &status, 0);
+    if (pid <= 0 || pid == child_pid) {
+        THROW_ERROR("the grandchild should be adopted by the subreaper");
+    }
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL) {
+        THROW_ERROR("the grandchild should be killed by the parent death signal");
+    }
+
+    if (prctl(PR_SET_CHILD_SUBREAPER, 0, 0, 0, 0) < 0) {
+        THROW_ERROR("failed to unset the subreaper");
+    }
+    return 0;
+}
+
+static int test_prctl_no_new_privs(void) {
+    int status = 0;
+    pid_t child_pid;
+
+    if (prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0) != 0) {
+        THROW_ERROR("no_new_privs should not be set by default");
+    }
+    if (prctl(PR_SET_NO_NEW_PRIVS, 0, 0, 0, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("no_new_privs can't be unset");
+    }
+    if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0 ||
+            prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0) != 1) {
+        THROW_ERROR("failed to set no_new_privs");
+    }
+
+    // no_new_privs is inherited by the children
+    if (spawn_self("no_new_privs", &child_pid) != 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("no_new_privs should be inherited by the child");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -147,8 +272,21 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_prctl_set_get_normal_name),
     TEST_CASE(test_prctl_get_default_thread_name),
     TEST_CASE(test_prctl_get_timerslack),
+    TEST_CASE(test_prctl_dumpable),
+    TEST_CASE(test_prctl_pdeathsig),
+    TEST_CASE(test_prctl_child_subreaper),
+    TEST_CASE(test_prctl_no_new_privs),
 };
 
-int main() {
+int main(int argc, char **argv) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "child") == 0) {
+            return child_exit_before_grandchild();
+        } else if (strcmp(argv[1], "grandchild") == 0) {
+            return grandchild_with_pdeathsig();
+        } else {
+            return prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0) == 1 ? EXIT_SUCCESS : EXIT_FAILURE;
+        }
+    }
     return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
 }
@@ -1,10 +1,13 @@
 #define _GNU_SOURCE
+#include <sys/prctl.h>
+#include <sys/stat.h>
 #include <sys/types.h>
 #include <sys/vfs.h>
 #include <fcntl.h>
 #include <limits.h>
 #include <stdlib.h>
 #include <errno.h>
+#include <unistd.h>
 #include "test_fs.h"
 
 // ============================================================================
@@ -282,6 +285,58 @@ static int test_readdir_self_fd() {
     return 0;
 }
 
+static int check_owner_of_proc_self(uid_t expected_uid) {
+    const char *proc_self_files[] = {"/proc/self/", "/proc/self/maps", "/proc/self/fd"};
+    struct stat stat_buf;
+
+    for (int i = 0; i < sizeof(proc_self_files) / sizeof(proc_self_files[0]); i++) {
+        if (stat(proc_self_files[i], &stat_buf) < 0) {
+            THROW_ERROR("failed to stat %s", proc_self_files[i]);
+        }
+        if (stat_buf.st_uid != expected_uid) {
+            THROW_ERROR("the owner of %s is not correct", proc_self_files[i]);
+        }
+    }
+    return 0;
+}
+
+static int test_owner_of_proc_self() {
+    int ret = 0;
+
+    if (seteuid(1000) < 0) {
+        THROW_ERROR("failed to set the effective uid");
+    }
+    // Changing the effective uid may reset the dumpable flag
+    if (prctl(PR_SET_DUMPABLE, 1) < 0) {
+        ret = -1;
+        goto out;
+    }
+    if (check_owner_of_proc_self(1000) < 0) {
+        ret = -1;
+        goto out;
+    }
+    // The files of a non-dumpable process are owned by root
+    if (prctl(PR_SET_DUMPABLE, 0) < 0 || check_owner_of_proc_self(0) < 0) {
+        ret = -1;
+        goto out;
+    }
+    // The process itself can still access its sensitive files
+    if (test_read_from_procfs("/proc/self/maps") < 0) {
+        ret = -1;
+        goto out;
+    }
+
+out:
+    prctl(PR_SET_DUMPABLE, 1);
+    if (seteuid(0) < 0) {
+        THROW_ERROR("failed to restore the effective uid");
+    }
+    if (ret < 0) {
+        THROW_ERROR("failed to test the owner of /proc/self");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -302,6 +357,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_readdir_self),
     TEST_CASE(test_readdir_self_fd),
     TEST_CASE(test_read_from_proc_self_maps),
+    TEST_CASE(test_owner_of_proc_self),
 };
 
 int main(int argc, const char *argv[]) {
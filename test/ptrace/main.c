@@ -0,0 +1,265 @@
+#define _GNU_SOURCE
+#include <sys/prctl.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define MAGIC           0x1234567890abcdefUL
+#define NEW_MAGIC       0xfedcba0987654321UL
+
+static volatile unsigned long magic = MAGIC;
+
+// The child calls getpid with the address of `magic` as the first argument
+// until the tracer changes the value of `magic`.
+static int child_main(void) {
+    while (magic == MAGIC) {
+        syscall(SYS_getpid, &magic);
+    }
+    return 0;
+}
+
+// The non-dumpable child stops itself until it is killed
+static int nondumpable_child_main(void) {
+    if (prctl(PR_SET_DUMPABLE, 0, 0, 0, 0) < 0) {
+        THROW_ERROR("failed to set the child non-dumpable");
+    }
+    raise(SIGSTOP);
+    return child_main();
+}
+
+static int spawn_child_with_arg(pid_t *child_pid, char *arg) {
+    char *child_argv[] = {"ptrace", arg, NULL};
+    if (posix_spawn(child_pid, "/bin/ptrace", NULL, NULL, child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    return 0;
+}
+
+static int spawn_child(pid_t *child_pid) {
+    return spawn_child_with_arg(child_pid, "child");
+}
+
+static int wait_for_stop(pid_t child_pid, int expected_sig) {
+    int status;
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFSTOPPED(status) || WSTOPSIG(status) != expected_sig) {
+        THROW_ERROR("the child is not stopped as expected");
+    }
+    return 0;
+}
+
+// Attach to the child and wait until it stops at the entry of getpid
+static int attach_and_stop_at_getpid(pid_t child_pid, struct user_regs_struct *regs) {
+    if (ptrace(PTRACE_ATTACH, child_pid, NULL, NULL) < 0) {
+        THROW_ERROR("failed to attach to the child");
+    }
+    if (wait_for_stop(child_pid, SIGSTOP) < 0) {
+        THROW_ERROR("the child should be stopped by SIGSTOP");
+    }
+    if (ptrace(PTRACE_SETOPTIONS, child_pid, NULL, PTRACE_O_TRACESYSGOOD) < 0) {
+        THROW_ERROR("failed to set the options");
+    }
+
+    // The child may stop at other syscalls when it is just attached
+    do {
+        if (ptrace(PTRACE_SYSCALL, child_pid, NULL, NULL) < 0) {
+            THROW_ERROR("failed to resume the child");
+        }
+        if (wait_for_stop(child_pid, SIGTRAP | 0x80) < 0) {
+            THROW_ERROR("the child should stop at a syscall");
+        }
+        if (ptrace(PTRACE_GETREGS, child_pid, NULL, regs) < 0) {
+            THROW_ERROR("failed to get the registers");
+        }
+    } while (regs->orig_rax != SYS_getpid || (long)regs->rax != -ENOSYS);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_ptrace_invalid_tracee(void) {
+    if (ptrace(PTRACE_ATTACH, getpid(), NULL, NULL) >= 0 || errno != EPERM) {
+        THROW_ERROR("a process should not trace itself");
+    }
+    if (ptrace(PTRACE_CONT, getppid(), NULL, NULL) >= 0 || errno != ESRCH) {
+        THROW_ERROR("a process that is not traced should not be resumed");
+    }
+    return 0;
+}
+
+static int test_ptrace_syscall_and_memory(void) {
+    pid_t child_pid;
+    int status;
+    struct user_regs_struct regs;
+
+    if (spawn_child(&child_pid) < 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (attach_and_stop_at_getpid(child_pid, &regs) < 0) {
+        THROW_ERROR("failed to stop the child at getpid");
+    }
+
+    // Read and write the memory of the child
+    unsigned long *magic_ptr = (unsigned long *)regs.rdi;
+    errno = 0;
+    unsigned long word = ptrace(PTRACE_PEEKDATA, child_pid, magic_ptr, NULL);
+    if (errno != 0 || word != MAGIC) {
+        THROW_ERROR("failed to peek the memory of the child");
+    }
+    if (ptrace(PTRACE_POKEDATA, child_pid, magic_ptr, NEW_MAGIC) < 0) {
+        THROW_ERROR("failed to poke the memory of the child");
+    }
+    if (ptrace(PTRACE_PEEKDATA, child_pid, magic_ptr, NULL) != NEW_MAGIC) {
+        THROW_ERROR("the memory of the child is not changed");
+    }
+
+    // The return value is available at the exit of the syscall
+    if (ptrace(PTRACE_SYSCALL, child_pid, NULL, NULL) < 0) {
+        THROW_ERROR("failed to resume the child");
+    }
+    if (wait_for_stop(child_pid, SIGTRAP | 0x80) < 0) {
+        THROW_ERROR("the child should stop at the exit of getpid");
+    }
+    if (ptrace(PTRACE_GETREGS, child_pid, NULL, &regs) < 0) {
+        THROW_ERROR("failed to get the registers");
+    }
+    if (regs.orig_rax != SYS_getpid || (pid_t)regs.rax != child_pid) {
+        THROW_ERROR("the return value of getpid is wrong");
+    }
+
+    if (ptrace(PTRACE_DETACH, child_pid, NULL, NULL) < 0) {
+        THROW_ERROR("failed to detach from the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child should exit normally");
+    }
+    return 0;
+}
+
+static int test_ptrace_poke_text(void) {
+    pid_t child_pid;
+    int status;
+    struct user_regs_struct regs;
+
+    if (spawn_child(&child_pid) < 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (attach_and_stop_at_getpid(child_pid, &regs) < 0) {
+        THROW_ERROR("failed to stop the child at getpid");
+    }
+
+    // Write the read-only code like setting a breakpoint, and then restore it
+    void *code_ptr = (void *)regs.rip;
+    errno = 0;
+    unsigned long code = ptrace(PTRACE_PEEKTEXT, child_pid, code_ptr, NULL);
+    if (errno != 0) {
+        THROW_ERROR("failed to peek the code of the child");
+    }
+    unsigned long breakpoint = (code & ~0xffUL) | 0xcc;
+    if (ptrace(PTRACE_POKETEXT, child_pid, code_ptr, breakpoint) < 0) {
+        THROW_ERROR("failed to poke the read-only code of the child");
+    }
+    if (ptrace(PTRACE_PEEKTEXT, child_pid, code_ptr, NULL) != breakpoint) {
+        THROW_ERROR("the code of the child is not changed");
+    }
+    if (ptrace(PTRACE_POKETEXT, child_pid, code_ptr, code) < 0) {
+        THROW_ERROR("failed to restore the code of the child");
+    }
+
+    // Let the child exit
+    if (ptrace(PTRACE_POKEDATA, child_pid, (void *)regs.rdi, NEW_MAGIC) < 0) {
+        THROW_ERROR("failed to poke the memory of the child");
+    }
+    if (ptrace(PTRACE_DETACH, child_pid, NULL, NULL) < 0) {
+        THROW_ERROR("failed to detach from the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child should exit normally");
+    }
+    return 0;
+}
+
+static int test_ptrace_kill_stopped_tracee(void) {
+    pid_t child_pid;
+    int status;
+    struct user_regs_struct regs;
+
+    if (spawn_child(&child_pid) < 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (attach_and_stop_at_getpid(child_pid, &regs) < 0) {
+        THROW_ERROR("failed to stop the child at getpid");
+    }
+    if (kill(child_pid, SIGKILL) < 0) {
+        THROW_ERROR("failed to kill the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFSIGNALED(status) ||
+            WTERMSIG(status) != SIGKILL) {
+        THROW_ERROR("the child should be killed");
+    }
+    return 0;
+}
+
+static int test_ptrace_nondumpable_tracee(void) {
+    pid_t child_pid;
+    int status;
+
+    if (spawn_child_with_arg(&child_pid, "nondumpable") < 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (waitpid(child_pid, &status, WUNTRACED) != child_pid || !WIFSTOPPED(status)) {
+        THROW_ERROR("the child should stop itself");
+    }
+    if (ptrace(PTRACE_ATTACH, child_pid, NULL, NULL) >= 0 || errno != EPERM) {
+        THROW_ERROR("a non-dumpable process should not be traced");
+    }
+    if (kill(child_pid, SIGKILL) < 0) {
+        THROW_ERROR("failed to kill the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFSIGNALED(status)) {
+        THROW_ERROR("the child should be killed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_ptrace_invalid_tracee),
+    TEST_CASE(test_ptrace_syscall_and_memory),
+    TEST_CASE(test_ptrace_poke_text),
+    TEST_CASE(test_ptrace_kill_stopped_tracee),
+    TEST_CASE(test_ptrace_nondumpable_tracee),
+};
+
+int main(int argc, char **argv) {
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        return child_main();
+    }
+    if (argc > 1 && strcmp(argv[1], "nondumpable") == 0) {
+        return nondumpable_child_main();
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,276 @@
+#define _GNU_SOURCE
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/times.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SLEEP_US        (100 * 1000)
+
+static volatile unsigned long sink;
+
+static void busy_loop(void) {
+    for (unsigned long i = 0; i < 100000000UL; i++) {
+        sink += i;
+    }
+}
+
+static long timeval_to_us(struct timeval *tv) {
+    return tv->tv_sec * 1000000L + tv->tv_usec;
+}
+
+static int child_main(void) {
+    busy_loop();
+    usleep(SLEEP_US);
+    return 0;
+}
+
+static void *thread_func(void *arg) {
+    struct rusage *usage = (struct rusage *)arg;
+    usleep(SLEEP_US);
+    if (getrusage(RUSAGE_THREAD, usage) < 0) {
+        return (void *) -1;
+    }
+    return NULL;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_getrusage_invalid_who(void) {
+    struct rusage usage;
+    if (getrusage(42, &usage) >= 0 || errno != EINVAL) {
+        THROW_ERROR("getrusage should fail with EINVAL for an invalid who");
+    }
+    return 0;
+}
+
+static int test_getrusage_self(void) {
+    struct rusage before, after;
+
+    if (getrusage(RUSAGE_SELF, &before) < 0) {
+        THROW_ERROR("failed to get the usage of self");
+    }
+    busy_loop();
+    usleep(SLEEP_US);
+    if (getrusage(RUSAGE_SELF, &after) < 0) {
+        THROW_ERROR("failed to get the usage of self");
+    }
+
+    if (timeval_to_us(&after.ru_utime) < timeval_to_us(&before.ru_utime) ||
+            timeval_to_us(&after.ru_stime) < timeval_to_us(&before.ru_stime)) {
+        THROW_ERROR("the CPU time should never decrease");
+    }
+    // Without vDSO, the CPU time is read from the host, which may count the
+    // busy loop as either user time or system time
+    if (timeval_to_us(&after.ru_utime) + timeval_to_us(&after.ru_stime) <=
+            timeval_to_us(&before.ru_utime) + timeval_to_us(&before.ru_stime)) {
+        THROW_ERROR("the busy loop should be counted as CPU time");
+    }
+    if (after.ru_nvcsw <= before.ru_nvcsw) {
+        THROW_ERROR("sleeping should be counted as a voluntary context switch");
+    }
+    if (after.ru_maxrss <= 0) {
+        THROW_ERROR("the max RSS should be positive");
+    }
+    return 0;
+}
+
+static int test_getrusage_thread(void) {
+    pthread_t thread;
+    struct rusage thread_usage, self_usage;
+
+    if (pthread_create(&thread, NULL, thread_func, &thread_usage) != 0) {
+        THROW_ERROR("failed to create the thread");
+    }
+    void *ret;
+    if (pthread_join(thread, &ret) != 0 || ret != NULL) {
+        THROW_ERROR("failed to get the usage of the thread");
+    }
+    if (thread_usage.ru_nvcsw < 1) {
+        THROW_ERROR("the thread should have slept");
+    }
+
+    // The usage of the exited thread is included in the process
+    if (getrusage(RUSAGE_SELF, &self_usage) < 0) {
+        THROW_ERROR("failed to get the usage of self");
+    }
+    if (self_usage.ru_nvcsw < thread_usage.ru_nvcsw) {
+        THROW_ERROR("the usage of the exited thread is missing");
+    }
+    return 0;
+}
+
+static int test_getrusage_children(void) {
+    struct rusage before, after;
+    pid_t child_pid;
+    int status;
+    char *child_argv[] = {"rusage", "child", NULL};
+
+    if (getrusage(RUSAGE_CHILDREN, &before) < 0) {
+        THROW_ERROR("failed to get the usage of children");
+    }
+    if (posix_spawn(&child_pid, "/bin/rusage", NULL, NULL, child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+
+    // The usage of the child is not counted until it is reaped
+    if (waitid(P_PID, child_pid, NULL, WEXITED | WNOWAIT) < 0) {
+        THROW_ERROR("failed to wait for the child without reaping it");
+    }
+    if (getrusage(RUSAGE_CHILDREN, &after) < 0) {
+        THROW_ERROR("failed to get the usage of children");
+    }
+    if (after.ru_nvcsw != before.ru_nvcsw) {
+        THROW_ERROR("the usage of an unreaped child should not be counted");
+    }
+
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("failed to reap the child");
+    }
+    if (getrusage(RUSAGE_CHILDREN, &after) < 0) {
+        THROW_ERROR("failed to get the usage of children");
+    }
+    if (after.ru_nvcsw <= before.ru_nvcsw) {
+        THROW_ERROR("the usage of the reaped child should be counted");
+    }
+    if (after.ru_maxrss <= 0) {
+        THROW_ERROR("the max RSS of children should be positive");
+    }
+    return 0;
+}
+
+static int test_getrusage_majflt(void) {
+    const char *file_path = "/root/test_rusage_majflt.txt";
+    struct rusage before, after;
+    long page_size = sysconf(_SC_PAGESIZE);
+    char buf[64] = "Hello World";
+
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the file");
+    }
+    if (write(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        close(fd);
+        THROW_ERROR("failed to write the file");
+    }
+    char *addr = mmap(NULL, page_size, PROT_READ, MAP_PRIVATE, fd, 0);
+    close(fd);
+    if (addr == MAP_FAILED) {
+        THROW_ERROR("failed to mmap the file");
+    }
+
+    if (getrusage(RUSAGE_THREAD, &before) < 0) {
+        munmap(addr, page_size);
+        THROW_ERROR("failed to get the usage of the thread");
+    }
+    // Reading the file-backed page is a major page fault if it is not committed
+    sink += addr[0];
+    if (getrusage(RUSAGE_THREAD, &after) < 0) {
+        munmap(addr, page_size);
+        THROW_ERROR("failed to get the usage of the thread");
+    }
+    munmap(addr, page_size);
+    unlink(file_path);
+
+    if (after.ru_majflt < before.ru_majflt || after.ru_minflt < before.ru_minflt) {
+        THROW_ERROR("the page faults should never decrease");
+    }
+    if (after.ru_majflt == before.ru_majflt && after.ru_minflt == before.ru_minflt) {
+        printf("Warning: the check of the page faults is skipped as the page is committed\n");
+    } else if (after.ru_majflt == before.ru_majflt) {
+        THROW_ERROR("the page fault reading the file should be major");
+    }
+    return 0;
+}
+
+static int test_times(void) {
+    struct tms buf;
+    long ticks_per_sec = sysconf(_SC_CLK_TCK);
+
+    clock_t start = times(NULL);
+    if (start == (clock_t) -1) {
+        THROW_ERROR("times with NULL buffer failed");
+    }
+    usleep(SLEEP_US);
+    clock_t end = times(&buf);
+    if (end == (clock_t) -1) {
+        THROW_ERROR("times failed");
+    }
+    if (end - start < SLEEP_US * ticks_per_sec / 1000000 / 2) {
+        THROW_ERROR("the elapsed ticks are too few");
+    }
+    if (buf.tms_utime < 0 || buf.tms_stime < 0 || buf.tms_cutime < 0 ||
+            buf.tms_cstime < 0) {
+        THROW_ERROR("the process times should not be negative");
+    }
+    return 0;
+}
+
+static int test_proc_stat(void) {
+    char buf[1024] = {0};
+    FILE *fp = fopen("/proc/self/stat", "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to open /proc/self/stat");
+    }
+    if (fgets(buf, sizeof(buf), fp) == NULL) {
+        fclose(fp);
+        THROW_ERROR("failed to read /proc/self/stat");
+    }
+    fclose(fp);
+
+    // Skip "pid (comm) "
+    char *p = strrchr(buf, ')');
+    if (p == NULL) {
+        THROW_ERROR("invalid format of /proc/self/stat");
+    }
+    long utime, stime, cutime, cstime, rss;
+    // Fields 3 to 24, in which utime, stime, cutime, cstime and rss are parsed
+    if (sscanf(p + 2, "%*c %*d %*d %*d %*d %*d %*u %*u %*u %*u %*u %ld %ld %ld %ld "
+               "%*d %*d %*d %*d %*u %*u %ld", &utime, &stime, &cutime, &cstime, &rss) != 5) {
+        THROW_ERROR("failed to parse /proc/self/stat");
+    }
+    if (utime < 0 || stime < 0 || cutime < 0 || cstime < 0) {
+        THROW_ERROR("the CPU times should not be negative");
+    }
+    if (rss <= 0) {
+        THROW_ERROR("the RSS should be positive");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getrusage_invalid_who),
+    TEST_CASE(test_getrusage_self),
+    TEST_CASE(test_getrusage_thread),
+    TEST_CASE(test_getrusage_children),
+    TEST_CASE(test_getrusage_majflt),
+    TEST_CASE(test_times),
+    TEST_CASE(test_proc_stat),
+};
+
+int main(int argc, char **argv) {
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        return child_main();
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
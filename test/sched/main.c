@@ -4,6 +4,7 @@
 #include <string.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <stdint.h>
 #include <sched.h>
 #include <errno.h>
 #include <spawn.h>
@@ -356,6 +357,272 @@ static int test_set_get_priority_user() {
     return 0;
 }
 
+// ============================================================================
+// Test cases for scheduling policies
+// ============================================================================
+
+#ifndef SCHED_RESET_ON_FORK
+#define SCHED_RESET_ON_FORK 0x40000000
+#endif
+
+#define SCHED_ATTR_SIZE_VER0 48
+
+struct sched_attr {
+    uint32_t size;
+    uint32_t sched_policy;
+    uint64_t sched_flags;
+    int32_t sched_nice;
+    uint32_t sched_priority;
+    uint64_t sched_runtime;
+    uint64_t sched_deadline;
+    uint64_t sched_period;
+};
+
+static int set_policy(int policy, int priority) {
+    struct sched_param param = { .sched_priority = priority };
+    return sched_setscheduler(0, policy, &param);
+}
+
+static int check_policy(pid_t pid, int expected_policy, int expected_priority) {
+    struct sched_param param;
+    if (sched_getscheduler(pid) != expected_policy) {
+        THROW_ERROR("the policy is wrong");
+    }
+    if (sched_getparam(pid, &param) < 0 || param.sched_priority != expected_priority) {
+        THROW_ERROR("the priority is wrong");
+    }
+    return 0;
+}
+
+static int test_sched_get_priority_range() {
+    if (sched_get_priority_max(SCHED_FIFO) != 99 || sched_get_priority_min(SCHED_FIFO) != 1 ||
+            sched_get_priority_max(SCHED_RR) != 99 || sched_get_priority_min(SCHED_RR) != 1) {
+        THROW_ERROR("wrong priority range of realtime policies");
+    }
+    if (sched_get_priority_max(SCHED_OTHER) != 0 || sched_get_priority_min(SCHED_OTHER) != 0) {
+        THROW_ERROR("wrong priority range of SCHED_OTHER");
+    }
+    if (sched_get_priority_max(-1) >= 0 || errno != EINVAL) {
+        THROW_ERROR("invalid policy should be rejected");
+    }
+    return 0;
+}
+
+static int test_sched_setscheduler() {
+    if (check_policy(0, SCHED_OTHER, 0) < 0) {
+        THROW_ERROR("the default policy should be SCHED_OTHER");
+    }
+    if (set_policy(SCHED_FIFO, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("priority 0 is invalid for SCHED_FIFO");
+    }
+    if (set_policy(SCHED_OTHER, 5) >= 0 || errno != EINVAL) {
+        THROW_ERROR("priority must be 0 for SCHED_OTHER");
+    }
+
+    if (set_policy(SCHED_FIFO, 10) < 0) {
+        THROW_ERROR("failed to set SCHED_FIFO");
+    }
+    if (check_policy(0, SCHED_FIFO, 10) < 0 || check_policy(getpid(), SCHED_FIFO, 10) < 0) {
+        THROW_ERROR("failed to check SCHED_FIFO");
+    }
+
+    // Change the priority only
+    struct sched_param param = { .sched_priority = 20 };
+    if (sched_setparam(0, &param) < 0 || check_policy(0, SCHED_FIFO, 20) < 0) {
+        THROW_ERROR("failed to set the priority");
+    }
+
+    struct timespec interval;
+    if (set_policy(SCHED_RR, 20) < 0 || sched_rr_get_interval(0, &interval) < 0) {
+        THROW_ERROR("failed to get the interval of SCHED_RR");
+    }
+    if (interval.tv_sec == 0 && interval.tv_nsec == 0) {
+        THROW_ERROR("the interval of SCHED_RR should not be zero");
+    }
+
+    if (set_policy(SCHED_OTHER, 0) < 0 || check_policy(0, SCHED_OTHER, 0) < 0) {
+        THROW_ERROR("failed to restore SCHED_OTHER");
+    }
+    return 0;
+}
+
+static int test_sched_policy_inheritance() {
+    int status, child_pid;
+
+    if (set_policy(SCHED_RR, 5) < 0) {
+        THROW_ERROR("failed to set SCHED_RR");
+    }
+    if (posix_spawn(&child_pid, "/bin/getpid", NULL, NULL, NULL, NULL) != 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (check_policy(child_pid, SCHED_RR, 5) < 0) {
+        THROW_ERROR("the policy should be inherited by the child");
+    }
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child");
+    }
+
+    // With SCHED_RESET_ON_FORK, the child uses the default policy
+    if (set_policy(SCHED_RR | SCHED_RESET_ON_FORK, 5) < 0) {
+        THROW_ERROR("failed to set SCHED_RR with SCHED_RESET_ON_FORK");
+    }
+    if (sched_getscheduler(0) != (SCHED_RR | SCHED_RESET_ON_FORK)) {
+        THROW_ERROR("SCHED_RESET_ON_FORK should be reported");
+    }
+    if (posix_spawn(&child_pid, "/bin/getpid", NULL, NULL, NULL, NULL) != 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (check_policy(child_pid, SCHED_OTHER, 0) < 0) {
+        THROW_ERROR("the policy of the child should be reset");
+    }
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child");
+    }
+
+    if (set_policy(SCHED_OTHER, 0) < 0) {
+        THROW_ERROR("failed to restore SCHED_OTHER");
+    }
+    return 0;
+}
+
+static int test_sched_setattr_getattr() {
+    struct sched_attr attr = {
+        .size = sizeof(struct sched_attr),
+        .sched_policy = SCHED_FIFO,
+        .sched_priority = 30,
+    };
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        THROW_ERROR("failed to set the attributes");
+    }
+
+    memset(&attr, 0, sizeof(attr));
+    if (syscall(__NR_sched_getattr, 0, &attr, sizeof(attr), 0) < 0) {
+        THROW_ERROR("failed to get the attributes");
+    }
+    if (attr.size != SCHED_ATTR_SIZE_VER0 || attr.sched_policy != SCHED_FIFO ||
+            attr.sched_priority != 30) {
+        THROW_ERROR("the attributes are wrong");
+    }
+
+    // Set back to SCHED_OTHER with a nice value
+    memset(&attr, 0, sizeof(attr));
+    attr.size = sizeof(struct sched_attr);
+    attr.sched_policy = SCHED_OTHER;
+    attr.sched_nice = 5;
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        THROW_ERROR("failed to set the attributes");
+    }
+    if (syscall(__NR_sched_getattr, 0, &attr, sizeof(attr), 0) < 0 ||
+            attr.sched_policy != SCHED_OTHER || attr.sched_nice != 5) {
+        THROW_ERROR("the nice value is wrong");
+    }
+
+    // Too small buffer
+    if (syscall(__NR_sched_getattr, 0, &attr, SCHED_ATTR_SIZE_VER0 - 1, 0) >= 0 ||
+            errno != EINVAL) {
+        THROW_ERROR("a small buffer should be rejected");
+    }
+    return 0;
+}
+
+#define SCHED_FLAG_KEEP_PARAMS 0x10
+
+static int test_sched_setattr_keep_params() {
+    struct sched_attr attr = {
+        .size = sizeof(struct sched_attr),
+        .sched_policy = SCHED_FIFO,
+        .sched_priority = 30,
+    };
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        THROW_ERROR("failed to set SCHED_FIFO");
+    }
+
+    // The realtime priority is not kept for SCHED_OTHER
+    attr.sched_policy = SCHED_OTHER;
+    attr.sched_priority = 0;
+    attr.sched_flags = SCHED_FLAG_KEEP_PARAMS;
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        THROW_ERROR("failed to set SCHED_OTHER with SCHED_FLAG_KEEP_PARAMS");
+    }
+    if (check_policy(0, SCHED_OTHER, 0) < 0) {
+        THROW_ERROR("the priority should be reset for SCHED_OTHER");
+    }
+    return 0;
+}
+
+static int test_sched_permission() {
+    struct sched_attr attr = {
+        .size = sizeof(struct sched_attr),
+        .sched_policy = SCHED_OTHER,
+        .sched_nice = 10,
+    };
+    int ret = 0;
+
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        THROW_ERROR("failed to set the nice value");
+    }
+    if (seteuid(1000) < 0) {
+        THROW_ERROR("failed to set the effective uid");
+    }
+    // Only root can use the realtime policies or lower the nice value
+    if (set_policy(SCHED_FIFO, 10) >= 0 || errno != EPERM) {
+        ret = -1;
+        goto out;
+    }
+    attr.sched_nice = 5;
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) >= 0 || errno != EPERM) {
+        ret = -1;
+        goto out;
+    }
+    // But a higher nice value is allowed
+    attr.sched_nice = 15;
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        ret = -1;
+        goto out;
+    }
+
+out:
+    if (seteuid(0) < 0) {
+        THROW_ERROR("failed to restore the effective uid");
+    }
+    if (ret < 0) {
+        THROW_ERROR("the permission of the scheduling is not checked");
+    }
+    // Restore the nice value
+    attr.sched_nice = 0;
+    if (syscall(__NR_sched_setattr, 0, &attr, 0) < 0) {
+        THROW_ERROR("failed to restore the nice value");
+    }
+    return 0;
+}
+
+static int test_proc_sched() {
+    char line[256];
+    int policy = -1;
+
+    if (set_policy(SCHED_FIFO, 50) < 0) {
+        THROW_ERROR("failed to set SCHED_FIFO");
+    }
+    FILE *fp = fopen("/proc/self/sched", "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to open /proc/self/sched");
+    }
+    while (fgets(line, sizeof(line), fp) != NULL) {
+        if (strncmp(line, "policy ", strlen("policy ")) == 0) {
+            sscanf(strchr(line, ':') + 1, "%d", &policy);
+        }
+    }
+    fclose(fp);
+
+    if (set_policy(SCHED_OTHER, 0) < 0) {
+        THROW_ERROR("failed to restore SCHED_OTHER");
+    }
+    if (policy != SCHED_FIFO) {
+        THROW_ERROR("the policy in /proc/self/sched is wrong");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -377,6 +644,13 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_set_get_priority_process),
     TEST_CASE(test_set_get_priority_pgrp),
     TEST_CASE(test_set_get_priority_user),
+    TEST_CASE(test_sched_get_priority_range),
+    TEST_CASE(test_sched_setscheduler),
+    TEST_CASE(test_sched_policy_inheritance),
+    TEST_CASE(test_sched_setattr_getattr),
+    TEST_CASE(test_sched_setattr_keep_params),
+    TEST_CASE(test_sched_permission),
+    TEST_CASE(test_proc_sched),
 };
 
 int main() {
@@ -0,0 +1,172 @@
+#include <sys/ioctl.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <termios.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+// Spawn this program again with `mode` as the argument and return its exit status.
+// If `new_pgrp` is set, the child is the leader of a new process group.
+static int run_child(char *mode, int new_pgrp) {
+    int ret, child_pid, status;
+    posix_spawnattr_t attr;
+    char *child_argv[] = {"session", mode, NULL};
+
+    ret = posix_spawnattr_init(&attr);
+    if (ret != 0) {
+        THROW_ERROR("init spawnattr error");
+    }
+    if (new_pgrp) {
+        if (posix_spawnattr_setflags(&attr, POSIX_SPAWN_SETPGROUP) != 0 ||
+                posix_spawnattr_setpgroup(&attr, 0) != 0) {
+            THROW_ERROR("set process group attribute error");
+        }
+    }
+    ret = posix_spawn(&child_pid, "/bin/session", NULL, &attr, child_argv, NULL);
+    if (ret != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    posix_spawnattr_destroy(&attr);
+
+    ret = wait4(child_pid, &status, 0, NULL);
+    if (ret < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("child process failed with status = %d", status);
+    }
+    return 0;
+}
+
+// Create a new session and check it in the child process
+static int child_new_session(void) {
+    pid_t pid = getpid();
+    pid_t old_pgid = getpgid(0);
+    pid_t old_sid = getsid(0);
+
+    if (old_sid != getsid(getppid())) {
+        printf("the child should be in the session of its parent\n");
+        return -1;
+    }
+    if (setsid() != pid) {
+        printf("setsid failed: errno = %d\n", errno);
+        return -1;
+    }
+    if (getsid(0) != pid || getpgid(0) != pid || getsid(getppid()) != old_sid) {
+        printf("the new session or process group is not correct\n");
+        return -1;
+    }
+    // Already a session leader
+    if (setsid() >= 0 || errno != EPERM) {
+        printf("setsid should fail for a session leader\n");
+        return -1;
+    }
+    // A session leader can't change its process group
+    if (setpgid(0, old_pgid) >= 0 || errno != EPERM) {
+        printf("setpgid should fail for a session leader\n");
+        return -1;
+    }
+    // The new session has no controlling terminal
+    if (tcgetpgrp(STDIN_FILENO) >= 0 || errno != ENOTTY) {
+        printf("tcgetpgrp should fail without a controlling terminal\n");
+        return -1;
+    }
+    // The terminal is the controlling terminal of the initial session
+    if (ioctl(STDIN_FILENO, TIOCSCTTY, 0) >= 0 || errno != EPERM) {
+        printf("TIOCSCTTY should fail for a terminal of another session\n");
+        return -1;
+    }
+    return 0;
+}
+
+// A process group leader can't create a new session
+static int child_pgrp_leader(void) {
+    if (getpgid(0) != getpid()) {
+        printf("the child should be a process group leader\n");
+        return -1;
+    }
+    if (setsid() >= 0 || errno != EPERM) {
+        printf("setsid should fail for a process group leader\n");
+        return -1;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for session
+// ============================================================================
+
+int test_getsid() {
+    pid_t sid = getsid(0);
+    if (sid <= 0 || getsid(getpid()) != sid) {
+        THROW_ERROR("getsid error");
+    }
+    if (getsid(-1) >= 0 || errno != ESRCH) {
+        THROW_ERROR("getsid should fail with a negative pid");
+    }
+    if (getsid(65535) >= 0 || errno != ESRCH) {
+        THROW_ERROR("getsid should fail with a non-existent pid");
+    }
+    return 0;
+}
+
+int test_setsid() {
+    return run_child("setsid", 0);
+}
+
+int test_setsid_by_pgrp_leader() {
+    return run_child("leader", 1);
+}
+
+int test_foreground_pgrp() {
+    pid_t pgid = getpgid(0);
+
+    if (tcgetpgrp(STDIN_FILENO) != pgid) {
+        THROW_ERROR("the foreground process group should be the initial one");
+    }
+    if (tcgetsid(STDIN_FILENO) != getsid(0)) {
+        THROW_ERROR("the session of the terminal is not correct");
+    }
+    if (tcsetpgrp(STDIN_FILENO, pgid) < 0) {
+        THROW_ERROR("failed to set the foreground process group");
+    }
+    if (tcsetpgrp(STDIN_FILENO, 65535) >= 0 || errno != ESRCH) {
+        THROW_ERROR("tcsetpgrp should fail with a non-existent process group");
+    }
+    if (tcgetpgrp(STDIN_FILENO) != pgid) {
+        THROW_ERROR("the foreground process group should not change");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getsid),
+    TEST_CASE(test_setsid),
+    TEST_CASE(test_setsid_by_pgrp_leader),
+    TEST_CASE(test_foreground_pgrp),
+};
+
+int main(int argc, char **argv) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "setsid") == 0) {
+            return child_new_session() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        } else {
+            return child_pgrp_leader() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+    }
+
+    int ret;
+    ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -3,6 +3,8 @@
 #include <sys/stat.h>
 #include <sys/mman.h>
 #include <sys/wait.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
 #include <unistd.h>
 #include <ucontext.h>
 #include <stdio.h>
@@ -591,6 +593,113 @@ int test_sigtimedwait() {
     return 0;
 }
 
+// ============================================================================
+// Test sigqueue
+// ============================================================================
+
+#define NUM_QUEUED_SIGNALS  3
+
+int test_sigqueue() {
+    int ret;
+    siginfo_t info;
+    sigset_t new_mask, old_mask;
+    struct rlimit old_limit, new_limit;
+    int signum = SIGRTMIN;
+
+    // Block the real-time signal so that all the signals are queued
+    sigemptyset(&new_mask);
+    sigaddset(&new_mask, signum);
+    if ((ret = sigprocmask(SIG_BLOCK, &new_mask, &old_mask)) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+
+    for (int i = 0; i < NUM_QUEUED_SIGNALS; i++) {
+        union sigval value = { .sival_int = i };
+        if (sigqueue(getpid(), signum, value) < 0) {
+            THROW_ERROR("sigqueue failed unexpectedly");
+        }
+    }
+
+    // The queued signals are received in order with their values
+    for (int i = 0; i < NUM_QUEUED_SIGNALS; i++) {
+        if ((ret = sigtimedwait(&new_mask, &info, NULL)) < 0) {
+            THROW_ERROR("sigtimedwait failed unexpectedly");
+        }
+        if (info.si_signo != signum || info.si_code != SI_QUEUE ||
+                info.si_pid != getpid() || info.si_value.sival_int != i) {
+            THROW_ERROR("the queued signal is not correct");
+        }
+    }
+
+    // The number of queued real-time signals is limited by RLIMIT_SIGPENDING
+    if (getrlimit(RLIMIT_SIGPENDING, &old_limit) < 0) {
+        THROW_ERROR("getrlimit failed unexpectedly");
+    }
+    new_limit.rlim_cur = 1;
+    new_limit.rlim_max = old_limit.rlim_max;
+    if (setrlimit(RLIMIT_SIGPENDING, &new_limit) < 0) {
+        THROW_ERROR("setrlimit failed unexpectedly");
+    }
+    union sigval ptr_value = { .sival_ptr = &new_limit };
+    if (sigqueue(getpid(), signum, ptr_value) < 0) {
+        THROW_ERROR("sigqueue failed unexpectedly");
+    }
+    ret = sigqueue(getpid(), signum, ptr_value);
+    if (ret == 0 || errno != EAGAIN) {
+        THROW_ERROR("sigqueue should fail with EAGAIN when exceeding the limit");
+    }
+    if (setrlimit(RLIMIT_SIGPENDING, &old_limit) < 0) {
+        THROW_ERROR("setrlimit failed unexpectedly");
+    }
+    if ((ret = sigtimedwait(&new_mask, &info, NULL)) < 0 ||
+            info.si_value.sival_ptr != &new_limit) {
+        THROW_ERROR("the pointer value of the queued signal is not correct");
+    }
+
+    // Restore the signal mask
+    if ((ret = sigprocmask(SIG_SETMASK, &old_mask, NULL)) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    return 0;
+}
+
+int test_sigqueue_code() {
+    siginfo_t info, queued_info;
+    sigset_t new_mask, old_mask;
+    int signum = SIGRTMIN;
+
+    memset(&queued_info, 0, sizeof(queued_info));
+    queued_info.si_signo = signum;
+    queued_info.si_code = SI_USER;
+    queued_info.si_value.sival_int = 1;
+
+    // The signals with a non-negative code cannot be sent to other processes
+    if (syscall(__NR_rt_sigqueueinfo, getpid() + 1000, signum, &queued_info) == 0 ||
+            errno != EPERM) {
+        THROW_ERROR("sigqueue with SI_USER to another process should fail with EPERM");
+    }
+
+    // But the code is kept for the signals sent to the process itself
+    sigemptyset(&new_mask);
+    sigaddset(&new_mask, signum);
+    if (sigprocmask(SIG_BLOCK, &new_mask, &old_mask) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    if (syscall(__NR_rt_sigqueueinfo, getpid(), signum, &queued_info) < 0) {
+        THROW_ERROR("sigqueue with SI_USER to itself failed unexpectedly");
+    }
+    if (sigtimedwait(&new_mask, &info, NULL) < 0) {
+        THROW_ERROR("sigtimedwait failed unexpectedly");
+    }
+    if (info.si_code != SI_USER || info.si_value.sival_int != 1) {
+        THROW_ERROR("the code of the queued signal should not be changed");
+    }
+    if (sigprocmask(SIG_SETMASK, &old_mask, NULL) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -605,6 +714,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_sigaltstack),
     TEST_CASE(test_sigchld),
     TEST_CASE(test_sigtimedwait),
+    TEST_CASE(test_sigqueue),
+    TEST_CASE(test_sigqueue_code),
 };
 
 int main(int argc, const char *argv[]) {
@@ -0,0 +1,250 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <linux/filter.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <sys/types.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int connect_udp_pair(int *server_fd, int *client_fd, in_port_t *port) {
+    struct sockaddr_in addr;
+    socklen_t addr_len = sizeof(addr);
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = 0;
+
+    *server_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    *client_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    if (*server_fd < 0 || *client_fd < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+    if (bind(*server_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to bind");
+    }
+    if (getsockname(*server_fd, (struct sockaddr *)&addr, &addr_len) < 0) {
+        THROW_ERROR("failed to get the bound address");
+    }
+    if (connect(*client_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    *port = ntohs(addr.sin_port);
+    return 0;
+}
+
+// Returns whether io_uring is enabled, in which case the UDP sockets are
+// uring sockets rather than host sockets
+static int is_io_uring_enabled(void) {
+    char line[256];
+    FILE *fp = fopen("/proc/io_uring", "r");
+    if (fp == NULL) {
+        return 0;
+    }
+    // io_uring is disabled if there is no header
+    char *header = fgets(line, sizeof(line), fp);
+    fclose(fp);
+    return header != NULL;
+}
+
+static int attach_filter(int fd, struct sock_filter *insns, unsigned short len) {
+    struct sock_fprog fprog = {
+        .len = len,
+        .filter = insns,
+    };
+    return setsockopt(fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog, sizeof(fprog));
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_invalid_filters(void) {
+    int fd = socket(AF_INET, SOCK_DGRAM, 0);
+    int dummy = 0;
+    if (fd < 0) {
+        THROW_ERROR("failed to create the socket");
+    }
+
+    // No return at the end
+    struct sock_filter no_ret[] = {
+        BPF_STMT(BPF_LD | BPF_W | BPF_LEN, 0),
+    };
+    if (attach_filter(fd, no_ret, ARRAY_SIZE(no_ret)) == 0 || errno != EINVAL) {
+        THROW_ERROR("the filter without a return should be rejected");
+    }
+
+    // Jump out of the program
+    struct sock_filter bad_jump[] = {
+        BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, 0, 1, 0),
+        BPF_STMT(BPF_RET | BPF_K, 0),
+    };
+    if (attach_filter(fd, bad_jump, ARRAY_SIZE(bad_jump)) == 0 || errno != EINVAL) {
+        THROW_ERROR("the filter with a bad jump should be rejected");
+    }
+
+    // Divide by a constant zero
+    struct sock_filter div_zero[] = {
+        BPF_STMT(BPF_ALU | BPF_DIV | BPF_K, 0),
+        BPF_STMT(BPF_RET | BPF_A, 0),
+    };
+    if (attach_filter(fd, div_zero, ARRAY_SIZE(div_zero)) == 0 || errno != EINVAL) {
+        THROW_ERROR("the filter dividing by zero should be rejected");
+    }
+
+    // Load an uninitialized memory word
+    struct sock_filter bad_mem[] = {
+        BPF_STMT(BPF_LD | BPF_MEM, 0),
+        BPF_STMT(BPF_RET | BPF_A, 0),
+    };
+    if (attach_filter(fd, bad_mem, ARRAY_SIZE(bad_mem)) == 0 || errno != EINVAL) {
+        THROW_ERROR("the filter loading uninitialized memory should be rejected");
+    }
+
+    if (setsockopt(fd, SOL_SOCKET, SO_DETACH_FILTER, &dummy, sizeof(dummy)) == 0 ||
+            errno != ENOENT) {
+        THROW_ERROR("detaching without a filter should fail");
+    }
+
+    close(fd);
+    return 0;
+}
+
+static int test_drop_all(void) {
+    int server_fd, client_fd, dummy = 0;
+    in_port_t port;
+    char buf[64] = {0};
+    struct sock_filter drop_all[] = {
+        BPF_STMT(BPF_RET | BPF_K, 0),
+    };
+
+    if (connect_udp_pair(&server_fd, &client_fd, &port) < 0) {
+        THROW_ERROR("failed to connect the UDP sockets");
+    }
+    if (attach_filter(server_fd, drop_all, ARRAY_SIZE(drop_all)) < 0) {
+        THROW_ERROR("failed to attach the filter");
+    }
+    if (send(client_fd, "dropped", 7, 0) != 7) {
+        THROW_ERROR("failed to send");
+    }
+    usleep(100 * 1000);
+    if (recv(server_fd, buf, sizeof(buf), MSG_DONTWAIT) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("the datagram should be dropped by the filter");
+    }
+
+    if (setsockopt(server_fd, SOL_SOCKET, SO_DETACH_FILTER, &dummy, sizeof(dummy)) < 0) {
+        THROW_ERROR("failed to detach the filter");
+    }
+    if (send(client_fd, "passed", 6, 0) != 6) {
+        THROW_ERROR("failed to send");
+    }
+    if (recv(server_fd, buf, sizeof(buf), 0) != 6 || strncmp(buf, "passed", 6) != 0) {
+        THROW_ERROR("the datagram should pass after detaching the filter");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_filter_and_trim(void) {
+    int server_fd, client_fd;
+    in_port_t port;
+    char buf[64] = {0};
+
+    if (connect_udp_pair(&server_fd, &client_fd, &port) < 0) {
+        THROW_ERROR("failed to connect the UDP sockets");
+    }
+
+    // Accept the datagrams to the port starting with 'A', keeping the UDP
+    // header and 4 bytes of the payload
+    struct sock_filter insns[] = {
+        BPF_STMT(BPF_LD | BPF_H | BPF_ABS, 2),
+        BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, port, 0, 3),
+        BPF_STMT(BPF_LD | BPF_B | BPF_ABS, 8),
+        BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, 'A', 0, 1),
+        BPF_STMT(BPF_RET | BPF_K, 8 + 4),
+        BPF_STMT(BPF_RET | BPF_K, 0),
+    };
+    if (attach_filter(server_fd, insns, ARRAY_SIZE(insns)) < 0) {
+        THROW_ERROR("failed to attach the filter");
+    }
+
+    struct sock_filter got_insns[ARRAY_SIZE(insns)];
+    socklen_t got_len = ARRAY_SIZE(got_insns);
+    if (getsockopt(server_fd, SOL_SOCKET, SO_GET_FILTER, got_insns, &got_len) < 0) {
+        THROW_ERROR("failed to get the filter");
+    }
+    if (got_len != ARRAY_SIZE(insns) || memcmp(got_insns, insns, sizeof(insns)) != 0) {
+        THROW_ERROR("the got filter mismatch");
+    }
+
+    if (send(client_fd, "Bdropped", 8, 0) != 8) {
+        THROW_ERROR("failed to send");
+    }
+    if (send(client_fd, "Atrimmed", 8, 0) != 8) {
+        THROW_ERROR("failed to send");
+    }
+    if (recv(server_fd, buf, sizeof(buf), 0) != 4 || strncmp(buf, "Atri", 4) != 0) {
+        THROW_ERROR("the datagram should be filtered and trimmed");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+// The filters of host sockets are not supported, since they cannot be
+// enforced in the enclave
+static int test_host_socket_filters(void) {
+    int fd = socket(AF_INET, SOCK_DGRAM, 0);
+    int dummy = 0;
+    if (fd < 0) {
+        THROW_ERROR("failed to create the socket");
+    }
+
+    struct sock_filter accept_all[] = {
+        BPF_STMT(BPF_RET | BPF_K, 0xffffffff),
+    };
+    if (attach_filter(fd, accept_all, ARRAY_SIZE(accept_all)) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("the filters of host sockets should not be supported");
+    }
+    if (setsockopt(fd, SOL_SOCKET, SO_DETACH_FILTER, &dummy, sizeof(dummy)) == 0 ||
+            errno != EOPNOTSUPP) {
+        THROW_ERROR("the filters of host sockets should not be detached");
+    }
+
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_invalid_filters),
+    TEST_CASE(test_drop_all),
+    TEST_CASE(test_filter_and_trim),
+};
+
+static test_case_t host_test_cases[] = {
+    TEST_CASE(test_host_socket_filters),
+};
+
+int main(int argc, const char *argv[]) {
+    if (!is_io_uring_enabled()) {
+        printf("Warning: the filters are only tested on host sockets as io_uring is disabled\n");
+        return test_suite_run(host_test_cases, ARRAY_SIZE(host_test_cases));
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
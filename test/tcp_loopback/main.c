@@ -0,0 +1,523 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <netinet/tcp.h>
+#include <sys/epoll.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <sys/types.h>
+
+#include "test.h"
+
+#define TEST_MSG        "Hello from the other side of the loopback"
+#define TEST_PORT       8828
+#define BULK_SIZE       (4 * 1024 * 1024)
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int listen_fd = -1;
+
+static int create_listener(void) {
+    int reuse = 1;
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_ANY);
+    addr.sin_port = htons(TEST_PORT);
+
+    listen_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to create the listen socket");
+    }
+    if (setsockopt(listen_fd, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse)) < 0) {
+        THROW_ERROR("failed to set SO_REUSEADDR");
+    }
+    if (bind(listen_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to bind");
+    }
+    if (listen(listen_fd, 4) < 0) {
+        THROW_ERROR("failed to listen");
+    }
+    return 0;
+}
+
+static int connect_pair(int *server_fd, int *client_fd) {
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = htons(TEST_PORT);
+
+    *client_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (*client_fd < 0) {
+        THROW_ERROR("failed to create the client socket");
+    }
+    if (connect(*client_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    *server_fd = accept(listen_fd, NULL, NULL);
+    if (*server_fd < 0) {
+        THROW_ERROR("failed to accept");
+    }
+    return 0;
+}
+
+static int listen_at(const char *ip, int port) {
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = inet_addr(ip);
+    addr.sin_port = htons(port);
+
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the listen socket");
+    }
+    if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(fd, 4) < 0) {
+        close(fd);
+        THROW_ERROR("failed to listen");
+    }
+    return fd;
+}
+
+static int connect_to(const char *ip, int port) {
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = inet_addr(ip);
+    addr.sin_port = htons(port);
+
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        return -1;
+    }
+    if (connect(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        int saved_errno = errno;
+        close(fd);
+        errno = saved_errno;
+        return -1;
+    }
+    return fd;
+}
+
+static int check_events(int fd, uint32_t mask, uint32_t expected) {
+    int ep_fd = epoll_create1(0);
+    struct epoll_event event = { .events = mask, .data.fd = fd };
+    if (ep_fd < 0 || epoll_ctl(ep_fd, EPOLL_CTL_ADD, fd, &event) < 0) {
+        THROW_ERROR("failed to set up epoll");
+    }
+    int ret = epoll_wait(ep_fd, &event, 1, 1000);
+    close(ep_fd);
+    if (expected == 0) {
+        if (ret != 0) {
+            THROW_ERROR("unexpected events");
+        }
+        return 0;
+    }
+    if (ret != 1 || (event.events & expected) != expected) {
+        THROW_ERROR("the expected events are not polled");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_send_recv(void) {
+    int server_fd, client_fd;
+    char buf[64] = {0};
+    int len = strlen(TEST_MSG);
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    if (send(client_fd, TEST_MSG, len, 0) != len) {
+        THROW_ERROR("failed to send from the client");
+    }
+    if (recv(server_fd, buf, sizeof(buf), MSG_PEEK) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("failed to peek on the server");
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(server_fd, buf, len, MSG_WAITALL) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("failed to receive on the server");
+    }
+
+    if (write(server_fd, TEST_MSG, len) != len) {
+        THROW_ERROR("failed to send from the server");
+    }
+    memset(buf, 0, sizeof(buf));
+    if (read(client_fd, buf, sizeof(buf)) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("failed to receive on the client");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_addresses(void) {
+    int server_fd, client_fd;
+    struct sockaddr_in client_addr, client_peer, server_addr, server_peer;
+    socklen_t addr_len = sizeof(struct sockaddr_in);
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    if (getsockname(client_fd, (struct sockaddr *)&client_addr, &addr_len) < 0 ||
+            getpeername(client_fd, (struct sockaddr *)&client_peer, &addr_len) < 0 ||
+            getsockname(server_fd, (struct sockaddr *)&server_addr, &addr_len) < 0 ||
+            getpeername(server_fd, (struct sockaddr *)&server_peer, &addr_len) < 0) {
+        THROW_ERROR("failed to get the addresses");
+    }
+    if (ntohs(client_peer.sin_port) != TEST_PORT || ntohs(server_addr.sin_port) != TEST_PORT) {
+        THROW_ERROR("the port of the server is wrong");
+    }
+    if (client_addr.sin_port == 0 || client_addr.sin_port != server_peer.sin_port ||
+            client_addr.sin_addr.s_addr != server_peer.sin_addr.s_addr) {
+        THROW_ERROR("the address of the client is wrong");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_readiness(void) {
+    int server_fd, client_fd, nbytes = 0;
+    char buf[64];
+    int len = strlen(TEST_MSG);
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    if (check_events(server_fd, EPOLLOUT, EPOLLOUT) < 0) {
+        THROW_ERROR("the server should be writable");
+    }
+    if (check_events(server_fd, EPOLLIN, 0) < 0) {
+        THROW_ERROR("the server should not be readable");
+    }
+    if (fcntl(server_fd, F_SETFL, O_NONBLOCK) < 0) {
+        THROW_ERROR("failed to set O_NONBLOCK");
+    }
+    if (read(server_fd, buf, sizeof(buf)) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("read should return EAGAIN");
+    }
+
+    if (write(client_fd, TEST_MSG, len) != len) {
+        THROW_ERROR("failed to send from the client");
+    }
+    if (check_events(server_fd, EPOLLIN, EPOLLIN) < 0) {
+        THROW_ERROR("the server should be readable");
+    }
+    if (ioctl(server_fd, FIONREAD, &nbytes) < 0 || nbytes != len) {
+        THROW_ERROR("FIONREAD returns a wrong length");
+    }
+    if (read(server_fd, buf, sizeof(buf)) != len) {
+        THROW_ERROR("failed to receive on the server");
+    }
+
+    // The remote shutdown of the write side results in EOF
+    if (shutdown(client_fd, SHUT_WR) < 0) {
+        THROW_ERROR("failed to shutdown the client");
+    }
+    if (check_events(server_fd, EPOLLIN | EPOLLRDHUP, EPOLLIN | EPOLLRDHUP) < 0) {
+        THROW_ERROR("the server should be hung up");
+    }
+    if (read(server_fd, buf, sizeof(buf)) != 0) {
+        THROW_ERROR("read should return EOF");
+    }
+
+    // Writing to a closed connection fails
+    close(client_fd);
+    if (send(server_fd, TEST_MSG, len, MSG_NOSIGNAL) >= 0 || errno != EPIPE) {
+        THROW_ERROR("send should fail with EPIPE");
+    }
+
+    close(server_fd);
+    return 0;
+}
+
+static void *bulk_sender(void *arg) {
+    int fd = *(int *)arg;
+    char *buf = malloc(BULK_SIZE);
+    for (int i = 0; i < BULK_SIZE; i++) {
+        buf[i] = (char)i;
+    }
+    size_t sent = 0;
+    while (sent < BULK_SIZE) {
+        ssize_t ret = write(fd, buf + sent, BULK_SIZE - sent);
+        if (ret <= 0) {
+            break;
+        }
+        sent += ret;
+    }
+    free(buf);
+    close(fd);
+    return (void *)sent;
+}
+
+static int test_bulk_transfer(void) {
+    int server_fd, client_fd;
+    pthread_t thread;
+    char buf[4096];
+    size_t received = 0;
+    void *sent;
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    if (pthread_create(&thread, NULL, bulk_sender, &client_fd) != 0) {
+        THROW_ERROR("failed to create the sender thread");
+    }
+    while (1) {
+        ssize_t ret = read(server_fd, buf, sizeof(buf));
+        if (ret < 0) {
+            THROW_ERROR("failed to receive");
+        }
+        if (ret == 0) {
+            break;
+        }
+        for (int i = 0; i < ret; i++) {
+            if (buf[i] != (char)(received + i)) {
+                THROW_ERROR("the received data mismatch");
+            }
+        }
+        received += ret;
+    }
+    pthread_join(thread, &sent);
+    if ((size_t)sent != BULK_SIZE || received != BULK_SIZE) {
+        THROW_ERROR("not all the data are transferred");
+    }
+
+    close(server_fd);
+    return 0;
+}
+
+static int test_peek(void) {
+    int server_fd, client_fd, nbytes = 0;
+    char buf[64] = {0};
+    int len = strlen(TEST_MSG);
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    if (send(client_fd, TEST_MSG, len, 0) != len) {
+        THROW_ERROR("failed to send from the client");
+    }
+    // The peeked data remain in the receive buffer
+    for (int i = 0; i < 2; i++) {
+        memset(buf, 0, sizeof(buf));
+        if (recv(server_fd, buf, sizeof(buf), MSG_PEEK) != len || strcmp(buf, TEST_MSG) != 0) {
+            THROW_ERROR("failed to peek on the server");
+        }
+        if (ioctl(server_fd, FIONREAD, &nbytes) < 0 || nbytes != len) {
+            THROW_ERROR("the peeked data should not be consumed");
+        }
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(server_fd, buf, sizeof(buf), 0) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("failed to receive the peeked data");
+    }
+    if (ioctl(server_fd, FIONREAD, &nbytes) < 0 || nbytes != 0) {
+        THROW_ERROR("the received data should be consumed");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+static int test_listener_ip(void) {
+    int port = TEST_PORT + 1;
+    int fd = listen_at("127.0.0.2", port);
+    if (fd < 0) {
+        THROW_ERROR("failed to listen on 127.0.0.2");
+    }
+
+    // The listener only accepts the connections to its own IP address
+    int client_fd = connect_to("127.0.0.1", port);
+    if (client_fd >= 0 || errno != ECONNREFUSED) {
+        THROW_ERROR("connecting to another IP address should be refused");
+    }
+    client_fd = connect_to("127.0.0.2", port);
+    if (client_fd < 0) {
+        THROW_ERROR("failed to connect to the listener");
+    }
+    int server_fd = accept(fd, NULL, NULL);
+    if (server_fd < 0) {
+        THROW_ERROR("failed to accept");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    close(fd);
+    return 0;
+}
+
+static int test_unaccepted_conn_reset(void) {
+    int port = TEST_PORT + 2;
+    char buf[64];
+    int fd = listen_at("127.0.0.1", port);
+    if (fd < 0) {
+        THROW_ERROR("failed to listen");
+    }
+    int client_fd = connect_to("127.0.0.1", port);
+    if (client_fd < 0) {
+        THROW_ERROR("failed to connect to the listener");
+    }
+
+    // Closing the listener resets the connections that are not accepted
+    close(fd);
+    if (recv(client_fd, buf, sizeof(buf), 0) >= 0 || errno != ECONNRESET) {
+        THROW_ERROR("the unaccepted connection should be reset");
+    }
+
+    close(client_fd);
+    return 0;
+}
+
+static int test_sockopts(void) {
+    int server_fd, client_fd;
+    int val;
+    socklen_t len = sizeof(val);
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+
+    val = 1;
+    if (setsockopt(client_fd, IPPROTO_TCP, TCP_NODELAY, &val, sizeof(val)) < 0 ||
+            getsockopt(client_fd, IPPROTO_TCP, TCP_NODELAY, &val, &len) < 0 || val != 1) {
+        THROW_ERROR("failed to set TCP_NODELAY");
+    }
+    val = 30;
+    if (setsockopt(client_fd, IPPROTO_TCP, TCP_KEEPIDLE, &val, sizeof(val)) < 0 ||
+            getsockopt(client_fd, IPPROTO_TCP, TCP_KEEPIDLE, &val, &len) < 0 || val != 30) {
+        THROW_ERROR("failed to set TCP_KEEPIDLE");
+    }
+    val = 0x10;
+    if (setsockopt(client_fd, IPPROTO_IP, IP_TOS, &val, sizeof(val)) < 0 ||
+            getsockopt(client_fd, IPPROTO_IP, IP_TOS, &val, &len) < 0 || val != 0x10) {
+        THROW_ERROR("failed to set IP_TOS");
+    }
+    // The integer is truncated to the length of the buffer
+    char byte = 0;
+    len = sizeof(byte);
+    if (getsockopt(client_fd, IPPROTO_IP, IP_TOS, &byte, &len) < 0 || len != 1 ||
+            byte != 0x10) {
+        THROW_ERROR("failed to get IP_TOS into a byte");
+    }
+    len = sizeof(val);
+    if (setsockopt(client_fd, IPPROTO_TCP, TCP_KEEPCNT, &byte, sizeof(byte)) == 0 ||
+            errno != EINVAL) {
+        THROW_ERROR("the optlen should be checked");
+    }
+    if (getsockopt(client_fd, IPPROTO_TCP, 9999, &val, &len) == 0 || errno != ENOPROTOOPT) {
+        THROW_ERROR("unknown options should not be supported");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+// Only the options in the table of the LibOS are forwarded to the host when
+// the TCP sockets are uring sockets
+static int test_uring_sockopts(void) {
+    int server_fd, client_fd;
+    int val;
+    socklen_t len = sizeof(val);
+
+    // io_uring is disabled if there is no header
+    char line[256];
+    FILE *fp = fopen("/proc/io_uring", "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to open /proc/io_uring");
+    }
+    char *header = fgets(line, sizeof(line), fp);
+    fclose(fp);
+    if (header == NULL) {
+        printf("Warning: test_uring_sockopts is skipped as io_uring is disabled\n");
+        return 0;
+    }
+
+    if (connect_pair(&server_fd, &client_fd) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+
+    // TCP_THIN_LINEAR_TIMEOUTS is supported by Linux, but not in the table
+    val = 1;
+    if (setsockopt(client_fd, IPPROTO_TCP, 16, &val, sizeof(val)) == 0 || errno != ENOPROTOOPT) {
+        THROW_ERROR("the options not in the table should not be set");
+    }
+    if (getsockopt(client_fd, IPPROTO_TCP, 16, &val, &len) == 0 || errno != ENOPROTOOPT) {
+        THROW_ERROR("the options not in the table should not be got");
+    }
+    // IP_ADD_MEMBERSHIP is write-only and SO_COOKIE is read-only
+    if (getsockopt(client_fd, IPPROTO_IP, IP_ADD_MEMBERSHIP, &val, &len) == 0 ||
+            errno != ENOPROTOOPT) {
+        THROW_ERROR("the write-only options should not be got");
+    }
+    uint64_t cookie = 0;
+    if (setsockopt(client_fd, SOL_SOCKET, SO_COOKIE, &cookie, sizeof(cookie)) == 0 ||
+            errno != ENOPROTOOPT) {
+        THROW_ERROR("the read-only options should not be set");
+    }
+
+    // The values of the structs and the strings are returned as they are
+    struct linger linger = { .l_onoff = 1, .l_linger = 3 };
+    len = sizeof(linger);
+    if (setsockopt(client_fd, SOL_SOCKET, SO_LINGER, &linger, sizeof(linger)) < 0) {
+        THROW_ERROR("failed to set SO_LINGER");
+    }
+    memset(&linger, 0, sizeof(linger));
+    if (getsockopt(client_fd, SOL_SOCKET, SO_LINGER, &linger, &len) < 0 ||
+            len != sizeof(linger) || linger.l_onoff != 1 || linger.l_linger != 3) {
+        THROW_ERROR("failed to get SO_LINGER");
+    }
+    char name[16] = { 0 };
+    len = sizeof(name);
+    if (getsockopt(client_fd, IPPROTO_TCP, TCP_CONGESTION, name, &len) < 0 || len == 0 ||
+            len > sizeof(name) || name[0] == '\0') {
+        THROW_ERROR("failed to get TCP_CONGESTION");
+    }
+
+    close(server_fd);
+    close(client_fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_send_recv),
+    TEST_CASE(test_peek),
+    TEST_CASE(test_addresses),
+    TEST_CASE(test_readiness),
+    TEST_CASE(test_bulk_transfer),
+    TEST_CASE(test_listener_ip),
+    TEST_CASE(test_unaccepted_conn_reset),
+    TEST_CASE(test_sockopts),
+    TEST_CASE(test_uring_sockopts),
+};
+
+int main(int argc, const char *argv[]) {
+    if (create_listener() < 0) {
+        THROW_ERROR("failed to create the listener");
+    }
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    close(listen_fd);
+    return ret;
+}
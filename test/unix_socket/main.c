@@ -1,7 +1,10 @@
+#define _GNU_SOURCE
 #include <sys/syscall.h>
+#include <sys/time.h>
 #include <sys/wait.h>
 #include <sys/socket.h>
 #include <sys/un.h>
+#include <netinet/in.h>
 #include <sys/ioctl.h>
 #include <poll.h>
 #include <unistd.h>
@@ -11,6 +14,7 @@
 #include <string.h>
 #include <sys/epoll.h>
 #include <pthread.h>
+#include <errno.h>
 
 #include "test.h"
 
@@ -558,6 +562,175 @@ int test_sendmsg_recvmsg() {
     return ret;
 }
 
+int test_sockopts() {
+    int socks[2];
+    int val;
+    socklen_t len;
+
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("socket pair create failed");
+    }
+
+    struct ucred cred;
+    len = sizeof(cred);
+    if (getsockopt(socks[0], SOL_SOCKET, SO_PEERCRED, &cred, &len) < 0 ||
+            len != sizeof(cred)) {
+        THROW_ERROR("failed to get SO_PEERCRED");
+    }
+    if (cred.pid != getpid() || cred.uid != geteuid() || cred.gid != getegid()) {
+        THROW_ERROR("the peer credentials are wrong");
+    }
+
+    val = 1;
+    len = sizeof(val);
+    if (setsockopt(socks[0], SOL_SOCKET, SO_PASSCRED, &val, sizeof(val)) < 0 ||
+            getsockopt(socks[0], SOL_SOCKET, SO_PASSCRED, &val, &len) < 0 || val != 1) {
+        THROW_ERROR("failed to set SO_PASSCRED");
+    }
+
+    // The credentials of the peer are received even if the peer sends none
+    char data = 'a';
+    if (write(socks[1], &data, sizeof(data)) != sizeof(data)) {
+        THROW_ERROR("failed to write");
+    }
+    char control[CMSG_SPACE(sizeof(struct ucred))];
+    struct iovec iov = { .iov_base = &data, .iov_len = sizeof(data) };
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = control,
+        .msg_controllen = sizeof(control),
+    };
+    if (recvmsg(socks[0], &msg, 0) != sizeof(data) || (msg.msg_flags & MSG_CTRUNC)) {
+        THROW_ERROR("failed to recvmsg");
+    }
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    if (cmsg == NULL || cmsg->cmsg_level != SOL_SOCKET || cmsg->cmsg_type != SCM_CREDENTIALS ||
+            cmsg->cmsg_len != CMSG_LEN(sizeof(struct ucred))) {
+        THROW_ERROR("the credentials are not received");
+    }
+    memcpy(&cred, CMSG_DATA(cmsg), sizeof(cred));
+    if (cred.pid != getpid() || cred.uid != geteuid() || cred.gid != getegid()) {
+        THROW_ERROR("the received credentials are wrong");
+    }
+
+    // The size of the buffer is doubled, like Linux
+    val = 8192;
+    if (setsockopt(socks[0], SOL_SOCKET, SO_RCVBUF, &val, sizeof(val)) < 0 ||
+            getsockopt(socks[0], SOL_SOCKET, SO_RCVBUF, &val, &len) < 0 || val != 16384) {
+        THROW_ERROR("failed to set SO_RCVBUF");
+    }
+
+    // The buffer of the connected socket is resized
+    val = 4096;
+    if (setsockopt(socks[1], SOL_SOCKET, SO_SNDBUF, &val, sizeof(val)) < 0) {
+        THROW_ERROR("failed to set SO_SNDBUF");
+    }
+    static char big_buf[64 * 1024];
+    ssize_t sent = send(socks[1], big_buf, sizeof(big_buf), MSG_DONTWAIT);
+    if (sent <= 0 || sent >= sizeof(big_buf)) {
+        THROW_ERROR("the send buffer is not resized");
+    }
+    size_t received = 0;
+    while (received < sent) {
+        ssize_t ret = recv(socks[0], big_buf, sizeof(big_buf), 0);
+        if (ret <= 0) {
+            THROW_ERROR("failed to receive the data in the resized buffer");
+        }
+        received += ret;
+    }
+
+    struct linger linger = { .l_onoff = 1, .l_linger = 5 };
+    len = sizeof(linger);
+    if (setsockopt(socks[0], SOL_SOCKET, SO_LINGER, &linger, sizeof(linger)) < 0) {
+        THROW_ERROR("failed to set SO_LINGER");
+    }
+    memset(&linger, 0, sizeof(linger));
+    if (getsockopt(socks[0], SOL_SOCKET, SO_LINGER, &linger, &len) < 0 ||
+            linger.l_onoff != 1 || linger.l_linger != 5) {
+        THROW_ERROR("failed to get SO_LINGER");
+    }
+
+    struct timeval timeout = { .tv_sec = 0, .tv_usec = 100 * 1000 };
+    if (setsockopt(socks[0], SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout)) < 0) {
+        THROW_ERROR("failed to set SO_RCVTIMEO");
+    }
+    char buf[16];
+    if (recv(socks[0], buf, sizeof(buf), 0) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("the receiving should time out");
+    }
+
+    len = sizeof(val);
+    if (getsockopt(socks[0], SOL_SOCKET, SO_TYPE, &val, &len) < 0 || val != SOCK_STREAM) {
+        THROW_ERROR("failed to get SO_TYPE");
+    }
+    if (getsockopt(socks[0], IPPROTO_TCP, 1, &val, &len) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("the options of other levels should not be supported");
+    }
+
+    close(socks[0]);
+    close(socks[1]);
+    return 0;
+}
+
+static int send_credentials(int fd, pid_t pid, uid_t uid, gid_t gid) {
+    char data = 'c';
+    char control[CMSG_SPACE(sizeof(struct ucred))];
+    struct iovec iov = { .iov_base = &data, .iov_len = sizeof(data) };
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = control,
+        .msg_controllen = sizeof(control),
+    };
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_CREDENTIALS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(struct ucred));
+    struct ucred cred = { .pid = pid, .uid = uid, .gid = gid };
+    memcpy(CMSG_DATA(cmsg), &cred, sizeof(cred));
+    return sendmsg(fd, &msg, 0);
+}
+
+int test_send_credentials() {
+    int socks[2];
+    char data;
+
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("socket pair create failed");
+    }
+
+    // Only root can send the credentials of others
+    if (send_credentials(socks[0], 1, 0, 0) != 1 || read(socks[1], &data, 1) != 1) {
+        THROW_ERROR("root should send any credentials");
+    }
+    if (seteuid(1000) < 0) {
+        THROW_ERROR("failed to drop the effective uid");
+    }
+    int ret = send_credentials(socks[0], 1, getuid(), getgid());
+    int pid_errno = errno;
+    int uid_ret = send_credentials(socks[0], getpid(), 2000, getgid());
+    int uid_errno = errno;
+    // The saved uid is still root
+    int own_ret = send_credentials(socks[0], getpid(), 0, getgid());
+    if (seteuid(0) < 0) {
+        THROW_ERROR("failed to restore the effective uid");
+    }
+    if (ret >= 0 || pid_errno != EPERM) {
+        THROW_ERROR("the pid of others should not be sent");
+    }
+    if (uid_ret >= 0 || uid_errno != EPERM) {
+        THROW_ERROR("the uid of others should not be sent");
+    }
+    if (own_ret != 1 || read(socks[1], &data, 1) != 1) {
+        THROW_ERROR("the own credentials should be sent");
+    }
+
+    close(socks[0]);
+    close(socks[1]);
+    return 0;
+}
+
 static test_case_t test_cases[] = {
     TEST_CASE(test_unix_socket_inter_process),
     TEST_CASE(test_socketpair_inter_process),
@@ -568,6 +741,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_unix_socket_rename),
     TEST_CASE(test_epoll_wait),
     TEST_CASE(test_sendmsg_recvmsg),
+    TEST_CASE(test_sockopts),
+    TEST_CASE(test_send_credentials),
 };
 
 int main(int argc, const char *argv[]) {
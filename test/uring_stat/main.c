@@ -0,0 +1,199 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <sys/types.h>
+
+#include "test.h"
+
+#define TEST_MSG        "Hello from io_uring"
+#define TEST_PORT       8838
+#define MAX_URINGS      16
+// Long enough for the idle CQE pollers to be parked
+#define IDLE_US         (500 * 1000)
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+struct uring_stat {
+    int id;
+    int is_active;
+    int is_parked;
+    unsigned int nr_sockets;
+    unsigned long task_load;
+    unsigned long nr_submitted;
+    unsigned long nr_completed;
+    unsigned long nr_parks;
+};
+
+// Returns the number of io_uring instances, or -1 on error
+static int read_uring_stats(struct uring_stat *stats) {
+    char line[256];
+    FILE *fp = fopen("/proc/io_uring", "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to open /proc/io_uring");
+    }
+
+    // io_uring is disabled if there is no header
+    if (fgets(line, sizeof(line), fp) == NULL) {
+        fclose(fp);
+        return 0;
+    }
+    if (strncmp(line, "id", 2) != 0) {
+        fclose(fp);
+        THROW_ERROR("invalid header of /proc/io_uring");
+    }
+
+    int num = 0;
+    while (num < MAX_URINGS && fgets(line, sizeof(line), fp) != NULL) {
+        struct uring_stat *stat = &stats[num];
+        if (sscanf(line, "%d %d %d %u %lu %lu %lu %lu", &stat->id, &stat->is_active,
+                   &stat->is_parked, &stat->nr_sockets, &stat->task_load,
+                   &stat->nr_submitted, &stat->nr_completed, &stat->nr_parks) != 8) {
+            fclose(fp);
+            THROW_ERROR("invalid line of /proc/io_uring");
+        }
+        num++;
+    }
+    fclose(fp);
+    return num;
+}
+
+static void sum_uring_stats(struct uring_stat *stats, int num, struct uring_stat *sum) {
+    memset(sum, 0, sizeof(*sum));
+    for (int i = 0; i < num; i++) {
+        sum->is_active += stats[i].is_active;
+        sum->is_parked += stats[i].is_parked;
+        sum->nr_sockets += stats[i].nr_sockets;
+        sum->nr_completed += stats[i].nr_completed;
+        sum->nr_parks += stats[i].nr_parks;
+    }
+}
+
+static int create_udp_pair(int *sender_fd, int *receiver_fd, struct sockaddr_in *addr) {
+    memset(addr, 0, sizeof(*addr));
+    addr->sin_family = AF_INET;
+    addr->sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr->sin_port = htons(TEST_PORT);
+
+    *receiver_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    *sender_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    if (*receiver_fd < 0 || *sender_fd < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+    if (bind(*receiver_fd, (struct sockaddr *)addr, sizeof(*addr)) < 0) {
+        THROW_ERROR("failed to bind");
+    }
+    return 0;
+}
+
+static int round_trip(int sender_fd, int receiver_fd, struct sockaddr_in *addr) {
+    char buf[64] = {0};
+    int len = strlen(TEST_MSG);
+    if (sendto(sender_fd, TEST_MSG, len, 0, (struct sockaddr *)addr, sizeof(*addr)) != len) {
+        THROW_ERROR("failed to send");
+    }
+    if (recv(receiver_fd, buf, sizeof(buf), 0) != len || strcmp(buf, TEST_MSG) != 0) {
+        THROW_ERROR("failed to receive");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_uring_stat(void) {
+    struct uring_stat stats[MAX_URINGS], before, after;
+    int sender_fd, receiver_fd;
+    struct sockaddr_in addr;
+
+    int num = read_uring_stats(stats);
+    if (num < 0) {
+        THROW_ERROR("failed to read the statistics");
+    }
+    if (num == 0) {
+        printf("Warning: test_uring_stat is skipped as io_uring is disabled\n");
+        return 0;
+    }
+    sum_uring_stats(stats, num, &before);
+    if (before.is_active < 1) {
+        THROW_ERROR("at least one io_uring instance should be active");
+    }
+
+    if (create_udp_pair(&sender_fd, &receiver_fd, &addr) < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+    if (round_trip(sender_fd, receiver_fd, &addr) < 0) {
+        THROW_ERROR("failed to transfer data");
+    }
+    num = read_uring_stats(stats);
+    sum_uring_stats(stats, num, &after);
+    if (after.nr_sockets < before.nr_sockets + 2) {
+        THROW_ERROR("the sockets are not counted");
+    }
+    if (after.nr_completed <= before.nr_completed) {
+        THROW_ERROR("the completions are not counted");
+    }
+
+    close(sender_fd);
+    close(receiver_fd);
+    return 0;
+}
+
+static int test_parked_poller_wakes_up(void) {
+    struct uring_stat stats[MAX_URINGS], idle, busy;
+    int sender_fd, receiver_fd;
+    struct sockaddr_in addr;
+
+    if (read_uring_stats(stats) == 0) {
+        printf("Warning: test_parked_poller_wakes_up is skipped as io_uring is disabled\n");
+        return 0;
+    }
+    if (create_udp_pair(&sender_fd, &receiver_fd, &addr) < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+
+    // The CQE pollers are parked once idle
+    usleep(IDLE_US);
+    int num = read_uring_stats(stats);
+    sum_uring_stats(stats, num, &idle);
+    if (idle.nr_parks == 0) {
+        THROW_ERROR("the idle CQE pollers should be parked");
+    }
+
+    // The parked pollers are woken up by new completions
+    for (int i = 0; i < 10; i++) {
+        if (round_trip(sender_fd, receiver_fd, &addr) < 0) {
+            THROW_ERROR("failed to transfer data after the pollers are parked");
+        }
+    }
+    num = read_uring_stats(stats);
+    sum_uring_stats(stats, num, &busy);
+    if (busy.nr_completed <= idle.nr_completed) {
+        THROW_ERROR("the completions are not polled after the pollers are parked");
+    }
+
+    close(sender_fd);
+    close(receiver_fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_uring_stat),
+    TEST_CASE(test_parked_poller_wakes_up),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
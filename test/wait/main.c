@@ -4,6 +4,8 @@
 #include <spawn.h>
 #include <stdbool.h>
 #include <stdlib.h>
+#include <string.h>
+#include <signal.h>
 #include "test.h"
 
 static int test_wait_no_children() {
@@ -42,6 +44,91 @@ static int test_wait_nohang() {
     return 0;
 }
 
+static int test_waitid_exited() {
+    siginfo_t info;
+    int child_pid = 0;
+    if (posix_spawn(&child_pid, "/bin/getpid", NULL, NULL, NULL, NULL) < 0) {
+        THROW_ERROR("posix_spawn child error");
+    }
+
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PID, child_pid, &info, WEXITED) < 0) {
+        THROW_ERROR("waitid child error");
+    }
+    if (info.si_signo != SIGCHLD || info.si_code != CLD_EXITED || info.si_pid != child_pid ||
+            info.si_status != 0 || info.si_uid != getuid()) {
+        THROW_ERROR("waitid siginfo error");
+    }
+
+    // The child has been reaped
+    if (waitid(P_PID, child_pid, &info, WEXITED) != -1 || errno != ECHILD) {
+        THROW_ERROR("the child should be reaped by waitid");
+    }
+    return 0;
+}
+
+static int test_waitid_nowait() {
+    siginfo_t info;
+    int status = 0;
+    int child_pid = 0;
+    if (posix_spawn(&child_pid, "/bin/getpid", NULL, NULL, NULL, NULL) < 0) {
+        THROW_ERROR("posix_spawn child error");
+    }
+
+    // Peek the exited child twice without reaping it
+    for (int i = 0; i < 2; i++) {
+        memset(&info, 0, sizeof(info));
+        if (waitid(P_ALL, 0, &info, WEXITED | WNOWAIT) < 0) {
+            THROW_ERROR("waitid with WNOWAIT error");
+        }
+        if (info.si_pid != child_pid || info.si_code != CLD_EXITED) {
+            THROW_ERROR("waitid with WNOWAIT siginfo error");
+        }
+    }
+
+    // The child can still be reaped by waitpid
+    if (waitpid(child_pid, &status, 0) != child_pid || !WIFEXITED(status)) {
+        THROW_ERROR("waitpid after WNOWAIT error");
+    }
+    return 0;
+}
+
+static int test_waitid_nohang() {
+    siginfo_t info;
+    int child_pid = 0;
+    // /bin/sleep lasts more than 1 sec
+    if (posix_spawn(&child_pid, "/bin/sleep", NULL, NULL, NULL, NULL) < 0) {
+        THROW_ERROR("posix_spawn child error");
+    }
+
+    memset(&info, 0xff, sizeof(info));
+    if (waitid(P_PGID, 0, &info, WEXITED | WNOHANG) < 0) {
+        THROW_ERROR("waitid with WNOHANG error");
+    }
+    if (info.si_pid != 0 || info.si_signo != 0) {
+        THROW_ERROR("siginfo should be zeroed if no child has changed");
+    }
+
+    if (waitid(P_PID, child_pid, &info, WEXITED) < 0 || info.si_pid != child_pid) {
+        THROW_ERROR("waitid child error");
+    }
+    return 0;
+}
+
+static int test_waitid_invalid_args() {
+    siginfo_t info;
+    if (waitid(P_ALL, 0, &info, WNOHANG) != -1 || errno != EINVAL) {
+        THROW_ERROR("waitid should fail without any state changes to wait for");
+    }
+    if (waitid(P_PID, 0, &info, WEXITED) != -1 || errno != EINVAL) {
+        THROW_ERROR("waitid should fail with an invalid pid");
+    }
+    if (waitid(P_ALL, 0, &info, WEXITED) != -1 || errno != ECHILD) {
+        THROW_ERROR("waitid should fail without any children");
+    }
+    return 0;
+}
+
 // NOTE: WUNTRACED is same as WSTOPPED
 // TODO: Support WUNTRACED and WCONTINUED and enable this test case
 static int test_wait_untraced_and_continued() {
@@ -94,8 +181,11 @@ static int test_wait_untraced_and_continued() {
 static test_case_t test_cases[] = {
     TEST_CASE(test_wait_no_children),
     TEST_CASE(test_wait_nohang),
-    // TODO: Enable this test case
-    // TEST_CASE(test_wait_untraced_and_continued),
+    TEST_CASE(test_waitid_exited),
+    TEST_CASE(test_waitid_nowait),
+    TEST_CASE(test_waitid_nohang),
+    TEST_CASE(test_waitid_invalid_args),
+    TEST_CASE(test_wait_untraced_and_continued),
 };
 
 int main(int argc, const char *argv[]) {